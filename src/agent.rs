@@ -0,0 +1,320 @@
+//! Embeddable agent: a conversation with the model and the tools on a workspace, driven
+//! through `core::llm::chat` the way prompt mode does it.
+//!
+//! The builder picks the tools, the answer to confirmations (destructive Bash commands,
+//! writes outside the workspace), and an event callback; each `send` runs one turn and
+//! keeps its messages for the next.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::core::config::Config;
+use crate::core::confirm::PendingAction;
+use crate::core::llm::{
+    self, ChatError, ChatOptions, ChatRequest, ChatResult, MessageUsage, ProgressEvent, ToolEvent,
+};
+use crate::core::tools::{self, Tool};
+use crate::core::workspace::Workspace;
+use crate::core::{generation, index, models, network, permissions, redact};
+
+/// Mode of new agents: every tool is offered.
+pub const DEFAULT_MODE: &str = "Build";
+
+/// What the agent is doing during a turn, for `AgentBuilder::on_event`.
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// Model call, tool call, retry, … (`Display` gives a log line).
+    Progress(ProgressEvent),
+    /// A chunk of the reply text, as streamed.
+    Text(String),
+    /// Tool calls queued, started, finished.
+    Tool(ToolEvent),
+}
+
+type EventHandler = dyn Fn(&AgentEvent) + Send + Sync;
+type ConfirmHandler = dyn Fn(&PendingAction) -> bool + Send + Sync;
+
+/// Outcome of a turn.
+#[derive(Debug, Clone)]
+pub struct AgentReply {
+    /// Final reply text.
+    pub content: String,
+    /// One line per tool call (e.g. "→ Bash: ls").
+    pub tool_log: Vec<String>,
+    /// Tokens, latency, and cost of the turn.
+    pub usage: MessageUsage,
+    /// The reply was cut off by the output token limit.
+    pub truncated: bool,
+    /// Notes of the post-processing hooks (`config.post_hooks`, see `core::hooks`).
+    pub annotations: Vec<String>,
+    /// The reply parsed as JSON, for turns sent with `Agent::send_structured` whose reply
+    /// matches the schema.
+    pub structured: Option<Value>,
+}
+
+/// Builder of an `Agent` (see `Agent::builder`).
+pub struct AgentBuilder {
+    config: Config,
+    workspace: Workspace,
+    model: Option<String>,
+    mode: String,
+    tools: Option<Vec<Box<dyn Tool>>>,
+    confirm: Option<Arc<ConfirmHandler>>,
+    on_event: Vec<Arc<EventHandler>>,
+}
+
+impl AgentBuilder {
+    /// Model ID to use instead of `config.model_id`.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// "Ask" (read-only tools), "Plan" (read-only tools plus TaskPlan), or "Build" (all
+    /// tools, the default).
+    pub fn with_mode(mut self, mode: impl Into<String>) -> Self {
+        self.mode = mode.into();
+        self
+    }
+
+    /// Tools offered to the model instead of the built-in ones (`core::tools::all`). Tools
+    /// disabled by `config.disabled_tools` and by the mode are still left out.
+    pub fn with_tools(mut self, tools: Vec<Box<dyn Tool>>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Answer to pending actions: true runs the destructive command or the write outside
+    /// the workspace, false tells the model the user declined. Without a handler, every
+    /// such action is declined.
+    pub fn with_confirm_handler(
+        mut self,
+        handler: impl Fn(&PendingAction) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirm = Some(Arc::new(handler));
+        self
+    }
+
+    /// Called with each event of a turn (progress, streamed text, tool calls). Handlers
+    /// run on the task awaiting `Agent::send`, so they should return quickly.
+    pub fn on_event(mut self, handler: impl Fn(&AgentEvent) + Send + Sync + 'static) -> Self {
+        self.on_event.push(Arc::new(handler));
+        self
+    }
+
+    pub fn build(self) -> Agent {
+        let model = self.model.unwrap_or_else(|| self.config.model_id.clone());
+        let tools_defs = match &self.tools {
+            Some(tools) => tools.iter().map(|t| t.definition()).collect(),
+            None => tools::definitions().to_vec(),
+        };
+        Agent {
+            context_length: models::resolve_context_length(&model),
+            model,
+            config: self.config,
+            workspace: self.workspace,
+            mode: self.mode,
+            tools: self.tools,
+            tools_defs,
+            confirm: self.confirm,
+            on_event: self.on_event,
+            messages: None,
+        }
+    }
+}
+
+/// An agent conversation on a workspace.
+///
+/// ```no_run
+/// use my_open_claude::Agent;
+/// use my_open_claude::core::{config, workspace};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = config::load()?;
+/// let workspace = workspace::detect();
+/// my_open_claude::configure(&config, &workspace);
+/// let mut agent = Agent::builder(config, workspace)
+///     .with_confirm_handler(|action| {
+///         eprintln!("declining {}", action);
+///         false
+///     })
+///     .on_event(|event| eprintln!("{:?}", event))
+///     .build();
+/// let reply = agent.send("What does this project do?").await?;
+/// println!("{}", reply.content);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Agent {
+    config: Config,
+    workspace: Workspace,
+    model: String,
+    context_length: u64,
+    mode: String,
+    tools: Option<Vec<Box<dyn Tool>>>,
+    tools_defs: Vec<Value>,
+    confirm: Option<Arc<ConfirmHandler>>,
+    on_event: Vec<Arc<EventHandler>>,
+    /// API messages of the conversation so far (None before the first turn).
+    messages: Option<Vec<Value>>,
+}
+
+impl Agent {
+    /// A builder with the built-in tools, the "Build" mode, and `config.model_id`.
+    pub fn builder(config: Config, workspace: Workspace) -> AgentBuilder {
+        AgentBuilder {
+            config,
+            workspace,
+            model: None,
+            mode: DEFAULT_MODE.to_string(),
+            tools: None,
+            confirm: None,
+            on_event: Vec::new(),
+        }
+    }
+
+    /// Run one turn: send `prompt` after the previous turns, run the tools the model
+    /// calls, and return its final reply.
+    pub async fn send(&mut self, prompt: &str) -> Result<AgentReply, ChatError> {
+        self.send_with_cancel(prompt, None).await
+    }
+
+    /// `send`, aborted when `cancel` fires (`ChatError::Cancelled`).
+    pub async fn send_with_cancel(
+        &mut self,
+        prompt: &str,
+        cancel: Option<CancellationToken>,
+    ) -> Result<AgentReply, ChatError> {
+        self.run_turn(prompt, None, cancel).await
+    }
+
+    /// `send` with a reply constrained to the JSON `schema` (structured output): the reply
+    /// is asked for again while it does not match, and `AgentReply::structured` holds the
+    /// parsed value. It is None when the reply still did not match after the retries; an
+    /// annotation says why.
+    pub async fn send_structured(
+        &mut self,
+        prompt: &str,
+        schema: Value,
+    ) -> Result<AgentReply, ChatError> {
+        self.run_turn(prompt, Some(schema), None).await
+    }
+
+    async fn run_turn(
+        &mut self,
+        prompt: &str,
+        schema: Option<Value>,
+        cancel: Option<CancellationToken>,
+    ) -> Result<AgentReply, ChatError> {
+        let confirm = self.confirm.clone();
+        let mut generation = generation::effective(&self.config.generation, &self.model, None);
+        generation.response_schema = schema;
+        let result = llm::chat(ChatRequest {
+            config: &self.config,
+            model: &self.model,
+            prompt,
+            images: &[],
+            mode: &self.mode,
+            context_length: self.context_length,
+            confirm_destructive: Some(Box::new(move |action| {
+                confirm.as_ref().is_some_and(|confirm| confirm(action))
+            })),
+            previous_messages: self.messages.clone(),
+            options: self.chat_options(cancel),
+            workspace: &self.workspace,
+            tools_list: self.tools(),
+            tools_defs: &self.tools_defs,
+            undo_stack: None,
+            pinned_files: &[],
+            pinned_messages: &[],
+            generation: &generation,
+        })
+        .await?;
+        match result {
+            ChatResult::Complete {
+                content,
+                tool_log,
+                messages,
+                message_usage,
+                truncated,
+                annotations,
+                structured,
+                ..
+            } => {
+                self.messages = Some(messages);
+                Ok(AgentReply {
+                    content,
+                    tool_log,
+                    usage: message_usage,
+                    truncated,
+                    annotations,
+                    structured,
+                })
+            }
+            // The confirmation callback answers every pending action in the loop.
+            ChatResult::NeedsConfirmation { action, .. } => Err(ChatError::Other(
+                format!("unexpected confirmation request for {}", action).into(),
+            )),
+        }
+    }
+
+    /// API messages of the conversation (system prompt included); empty before the
+    /// first turn.
+    pub fn messages(&self) -> &[Value] {
+        self.messages.as_deref().unwrap_or_default()
+    }
+
+    /// Start a new conversation: the next turn only sees its own prompt.
+    pub fn reset(&mut self) {
+        self.messages = None;
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    fn tools(&self) -> &[Box<dyn Tool>] {
+        match &self.tools {
+            Some(tools) => tools,
+            None => tools::all(),
+        }
+    }
+
+    /// Callbacks forwarding the turn's events to the `on_event` handlers.
+    fn chat_options(&self, cancel: Option<CancellationToken>) -> ChatOptions {
+        let emit = {
+            let handlers = self.on_event.clone();
+            move |event: AgentEvent| handlers.iter().for_each(|handler| handler(&event))
+        };
+        let emit = Arc::new(emit);
+        let (progress, text, tool) = (Arc::clone(&emit), Arc::clone(&emit), emit);
+        ChatOptions {
+            on_progress: Some(Box::new(move |e| progress(AgentEvent::Progress(e.clone())))),
+            on_content_chunk: Some(Box::new(move |c| text(AgentEvent::Text(c.to_string())))),
+            on_tool_event: Some(Box::new(move |e| tool(AgentEvent::Tool(e.clone())))),
+            cancel_token: cancel,
+            // Embedded conversations are not saved in the history.
+            conversation_id: None,
+            modes: None,
+        }
+    }
+}
+
+/// Apply `config` to the process-wide settings the tools and the agent loop read: the
+/// provider, the HTTP proxy and TLS options, the embedding index, the file permissions
+/// (denied paths, writes confined to the workspace root), and the masking of secrets in
+/// tool results. Call it once before
+/// the first turn, and again when switching configs; until then, every path is allowed.
+pub fn configure(config: &Config, workspace: &Workspace) {
+    models::configure(config);
+    network::configure(config);
+    index::configure(config);
+    permissions::configure(config, &workspace.root);
+    redact::configure(config);
+}
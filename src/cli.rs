@@ -1,9 +1,11 @@
 //! CLI definitions: argument parsing, subcommands, and help text.
 
-use clap::{ArgAction, Parser, Subcommand};
-use clap_complete::Shell;
+use std::io::Write;
 
-pub use clap_complete::generate;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use clap_complete::{Shell, generate};
+
+use crate::core::history::Reaction;
 
 const LONG_ABOUT: &str = "\
 Interactive TUI by default. Use -p for single-prompt mode. Supports agent tools
@@ -15,6 +17,10 @@ EXAMPLES:
   my-open-claude                    Launch interactive TUI
   my-open-claude -p \"explain X\"     Single prompt, stream response to stdout
   my-open-claude -p - -m anthropic/claude-3.5-haiku  Prompt from stdin, specific model
+  my-open-claude -p \"fix tests\" --stream  Tool logs and response on stdout, line by line
+  my-open-claude -m openai/gpt-4o   Launch TUI with a specific model (not saved)
+  my-open-claude --remote devbox:~/src/app  Work on a project on an SSH host
+  my-open-claude --batch tasks.yaml  Run a file of prompts one after another, then report
   my-open-claude install            Install to ~/.cargo/bin
   my-open-claude update --check     Check for updates without downloading
   my-open-claude config show        Show config paths and status
@@ -23,7 +29,22 @@ EXAMPLES:
   my-open-claude models --query claude  Filter models by name or id
   my-open-claude history list       List conversations
   my-open-claude history list -l 10  List last 10 conversations
+  my-open-claude history reactions --only flag > flagged.jsonl  Export flagged replies
+  my-open-claude history prune --dry-run  Report the conversations the retention limits would remove
+  my-open-claude history archive <ID>  Keep a conversation when the history is pruned
+  my-open-claude replay <ID>        Replay a saved conversation in the TUI (no API calls)
+  my-open-claude replay <ID> --step  Advance the replay one message per key press
+  my-open-claude index              Build or update the semantic search index
+  my-open-claude agents generate    Create or update AGENTS.md without the TUI (like /init)
+  my-open-claude tools list         List the tools offered to the model, with their arguments
+  my-open-claude tools run Grep --args '{\"pattern\": \"TODO\"}'  Run one tool directly and print its result
+  my-open-claude watch -p \"run tests and summarize failures\" --glob 'src/**/*.rs'  Re-run a prompt on change
+  my-open-claude audit show --since 1d  Commands, file changes, and confirmations of the last day
+  my-open-claude stats              Usage over the last 30 days (tokens, cost, top models)
+  my-open-claude stats --days 7     Usage over the last week
+  my-open-claude report             Open a GitHub issue from the latest crash report
   my-open-claude completions bash   Generate bash completions
+  source <(my-open-claude completions bash)  Enable them in the current bash session
 ";
 
 /// Command-line arguments for the application.
@@ -48,14 +69,26 @@ pub struct Args {
     )]
     pub prompt: Option<String>,
 
-    /// Override model for single prompt mode
-    #[arg(short = 'm', long, help = "Model ID (e.g. anthropic/claude-haiku-4.5)")]
+    /// Override the model for this invocation (prompt mode and TUI); not saved as last model
+    #[arg(
+        short = 'm',
+        long,
+        help = "Model ID for this invocation (e.g. anthropic/claude-haiku-4.5)"
+    )]
     pub model: Option<String>,
 
     /// Use read-only tools (Ask mode) for single prompt
     #[arg(long, help = "Restrict to read-only tools in prompt mode")]
     pub ask: bool,
 
+    /// Plan mode for single prompt: read-only tools plus TaskPlan, no edits or commands
+    #[arg(
+        long,
+        conflicts_with = "ask",
+        help = "Plan without modifying files: read-only tools plus TaskPlan in prompt mode"
+    )]
+    pub plan: bool,
+
     /// Disable streaming in prompt mode (wait for full response before printing)
     #[arg(
         long,
@@ -63,11 +96,81 @@ pub struct Args {
     )]
     pub no_stream: bool,
 
+    /// Headless streaming: tool logs and content printed line by line to stdout as they arrive
+    #[arg(
+        long,
+        requires = "prompt",
+        conflicts_with = "no_stream",
+        help = "In prompt mode, print tool logs and response lines to stdout as they arrive"
+    )]
+    pub stream: bool,
+
+    /// Structured output: JSON schema the response must match
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "prompt",
+        conflicts_with = "stream",
+        help = "In prompt mode, constrain the response to the JSON schema in FILE and print it as one line of validated JSON"
+    )]
+    pub json_schema: Option<String>,
+
+    /// Run the prompts of a YAML or JSON file one after another, then print a report
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "prompt",
+        help = "Run each task of a YAML/JSON file headlessly, saving each as a conversation, then print a report"
+    )]
+    pub batch: Option<String>,
+
+    /// Write the batch report as JSON
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "batch",
+        help = "With --batch, also write the report (status, cost, changed files per task) as JSON"
+    )]
+    pub batch_report: Option<String>,
+
+    /// Work on an SSH host: tools and workspace detection run there
+    #[arg(
+        long,
+        value_name = "HOST[:PATH]",
+        help = "Run tools on an SSH host, in PATH (default: the login directory), e.g. devbox:~/src/app"
+    )]
+    pub remote: Option<String>,
+
+    /// Simulate tools with side effects: Write, Edit, ReplaceAll, and Bash report what they
+    /// would do instead of doing it
+    #[arg(
+        long,
+        help = "Simulate Write/Edit/ReplaceAll/Bash: report the diffs and commands without applying them"
+    )]
+    pub dry_run: bool,
+
+    /// Named profile from the profiles directory (falls back to MY_OPEN_CLAUDE_PROFILE)
+    #[arg(
+        long,
+        value_name = "NAME",
+        global = true,
+        help = "Use the profile ~/.config/my-open-claude/profiles/NAME.json (provider, key, model, theme, tools)"
+    )]
+    pub profile: Option<String>,
+
+    /// Send even when a session, daily, or weekly spend cap is reached
+    #[arg(
+        long,
+        global = true,
+        help = "Send even when MY_OPEN_CLAUDE_SESSION/DAILY/WEEKLY_SPEND_LIMIT is reached (prompt mode, agents generate, batch, watch)"
+    )]
+    pub ignore_spend_limit: bool,
+
     /// Increase log verbosity (use multiple times for debug)
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count, global = true)]
     pub verbose: u8,
 
-    /// Reduce log output (errors only)
+    /// Reduce output: errors-only logs; in prompt mode, response text without tool progress
     #[arg(short = 'q', long = "quiet", global = true)]
     pub quiet: bool,
 }
@@ -79,8 +182,14 @@ pub enum Commands {
     /// Update to the latest release from GitHub
     Update {
         /// Only check if an update is available, don't download
-        #[arg(long)]
+        #[arg(long, conflicts_with = "rollback")]
         check: bool,
+        /// Install this release (e.g. 0.4.2 or v0.4.2) instead of the latest; older ones too
+        #[arg(long, value_name = "VERSION", conflicts_with = "rollback")]
+        version: Option<String>,
+        /// Restore the binary replaced by the last update (kept as <binary>.old)
+        #[arg(long)]
+        rollback: bool,
     },
     /// Show config paths, model, and API key status
     Config {
@@ -93,17 +202,90 @@ pub enum Commands {
         #[arg(long)]
         query: Option<String>,
     },
+    /// Build or update the SemanticSearch index of the current directory
+    Index {
+        /// Discard the existing index and embed every file again
+        #[arg(long)]
+        rebuild: bool,
+    },
     /// Manage conversation history
     History {
         #[command(subcommand)]
         subcommand: HistorySubcommand,
     },
+    /// Replay a saved conversation in the TUI, without calling the API
+    Replay {
+        /// Conversation ID (see `history list`)
+        id: String,
+        /// Advance one message per key press instead of with realistic timing
+        #[arg(long)]
+        step: bool,
+        /// Playback speed multiplier (2 plays twice as fast)
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+    },
+    /// Manage AGENTS.md, the instructions file for coding agents
+    Agents {
+        #[command(subcommand)]
+        subcommand: AgentsSubcommand,
+    },
+    /// Run a prompt headlessly, then again each time files matching --glob change, in one
+    /// rolling conversation
+    Watch {
+        /// Prompt to run on each change
+        #[arg(short = 'p', long)]
+        prompt: String,
+        /// Files to watch, relative to the workspace root (repeatable), e.g. 'src/**/*.rs'
+        #[arg(long = "glob", value_name = "PATTERN", required = true)]
+        globs: Vec<String>,
+        /// Quiet time after the last change before a run starts, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = crate::core::watch::DEFAULT_DEBOUNCE.as_millis() as u64)]
+        debounce: u64,
+        /// Restrict to read-only tools (Ask mode)
+        #[arg(long)]
+        ask: bool,
+        /// Read-only tools plus TaskPlan (Plan mode)
+        #[arg(long, conflicts_with = "ask")]
+        plan: bool,
+    },
+    /// List the tools or run one directly, with the limits and permissions of the agent loop
+    Tools {
+        #[command(subcommand)]
+        subcommand: ToolsSubcommand,
+    },
+    /// Review what the agent did on this machine: commands, file changes, confirmations
+    Audit {
+        #[command(subcommand)]
+        subcommand: AuditSubcommand,
+    },
+    /// Show usage over time across sessions: tokens, cost, top models, commands, and tools
+    Stats {
+        /// Number of days to cover (at most 90)
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..=90))]
+        days: u64,
+    },
+    /// Open a prefilled GitHub issue from the latest crash report
+    Report {
+        /// Print the report path and issue URL without opening the browser
+        #[arg(long)]
+        no_open: bool,
+    },
     /// Generate shell completion script
     Completions {
         /// Shell to generate completions for (bash, zsh, fish, powershell, elvish)
         #[arg(value_parser = clap::value_parser!(Shell))]
         shell: Shell,
     },
+    /// Serve a scripted mock of the chat API for smoke tests (`testing` feature)
+    #[cfg(feature = "testing")]
+    #[command(hide = true)]
+    MockServer {
+        /// JSON array of responses (see `core::testing::load_script`)
+        script: std::path::PathBuf,
+        /// Port on 127.0.0.1 (default: a free port)
+        #[arg(long, default_value_t = 0)]
+        port: u16,
+    },
 }
 
 #[derive(Subcommand)]
@@ -119,6 +301,50 @@ pub enum ConfigSubcommand {
     },
 }
 
+#[derive(Subcommand)]
+pub enum AgentsSubcommand {
+    /// Create or update AGENTS.md headlessly (the /init flow), streaming progress to stdout
+    Generate,
+}
+
+#[derive(Subcommand)]
+pub enum ToolsSubcommand {
+    /// List the tools with their description and arguments
+    List {
+        /// Print the tool definitions sent to the model, as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a tool once and print its result, as the model would receive it
+    Run {
+        /// Tool name (see `tools list`), e.g. Read or Grep
+        name: String,
+        /// Arguments of the call, as a JSON object
+        #[arg(long, value_name = "JSON", default_value = "{}")]
+        args: String,
+        /// Restrict to read-only tools (Ask mode)
+        #[arg(long)]
+        ask: bool,
+        /// Read-only tools plus TaskPlan (Plan mode)
+        #[arg(long, conflicts_with = "ask")]
+        plan: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditSubcommand {
+    /// List the audited actions, oldest first, with the conversation and turn behind them
+    Show {
+        /// Only actions since then: a duration (30m, 12h, 7d, 2w), a date (2024-05-01), or
+        /// an RFC 3339 time
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+        /// Print the log entries as JSON Lines
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum HistorySubcommand {
     /// List conversations
@@ -127,6 +353,114 @@ pub enum HistorySubcommand {
         #[arg(short, long)]
         limit: Option<usize>,
     },
+    /// Export the assistant replies marked with a reaction (Alt+A) as JSON Lines, with the
+    /// prompt they answer
+    Reactions {
+        /// Only replies with this reaction: good, bad, or flag
+        #[arg(long, value_parser = parse_reaction)]
+        only: Option<Reaction>,
+    },
+    /// Remove the conversations past the retention limits (count, age, per workspace)
+    Prune {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Keep a conversation when the history is pruned
+    Archive {
+        /// Conversation ID (see `history list`)
+        id: String,
+    },
+    /// Let a conversation be pruned again
+    Unarchive {
+        /// Conversation ID (see `history list`)
+        id: String,
+    },
+}
+
+/// Bash: complete `replay` with the saved conversation IDs, everything else as generated.
+const BASH_CONVERSATION_IDS: &str = r#"
+_{name}_with_conversation_ids() {
+    local i word cmd="" cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        word="${COMP_WORDS[i]}"
+        if [[ "${word}" != -* ]]; then
+            cmd="${word}"
+            break
+        fi
+    done
+    if [[ "${cmd}" == "replay" && "${cur}" != -* && "${prev}" != "--speed" ]]; then
+        COMPREPLY=( $(compgen -W "$({name} history list 2>/dev/null | cut -f1)" -- "${cur}") )
+        return 0
+    fi
+    _{name} "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _{name}_with_conversation_ids -o nosort -o bashdefault -o default {name}
+else
+    complete -F _{name}_with_conversation_ids -o bashdefault -o default {name}
+fi
+"#;
+
+/// Zsh: conversation IDs with their titles, used as the action of the `replay` ID argument.
+const ZSH_CONVERSATION_IDS: &str = r#"(( $+functions[_{name}__conversation_ids] )) ||
+_{name}__conversation_ids() {
+    local -a ids
+    local id title rest
+    {name} history list 2>/dev/null | while IFS=$'\t' read -r id title rest; do
+        ids+=("${id//:/\\:}:${title}")
+    done
+    _describe -t conversations 'conversation ID' ids
+}
+
+"#;
+
+/// Fish: conversation IDs, described by their titles.
+const FISH_CONVERSATION_IDS: &str = r#"complete -c {name} -n "__fish_{fn_name}_using_subcommand replay" -f -a "({name} history list 2>/dev/null | cut -f1,2)"
+"#;
+
+/// Write the completion script for `shell`. Bash, zsh, and fish scripts also complete the
+/// conversation IDs of `replay`, read from `history list` when completing.
+pub fn write_completions(shell: Shell, out: &mut impl Write) -> std::io::Result<()> {
+    let name = crate::core::app::NAME;
+    let mut generated = Vec::new();
+    generate(shell, &mut Args::command(), name, &mut generated);
+    let mut script = String::from_utf8_lossy(&generated).into_owned();
+    match shell {
+        Shell::Bash => script.push_str(&BASH_CONVERSATION_IDS.replace("{name}", name)),
+        Shell::Zsh => {
+            let replay_id = "':id -- Conversation ID (see `history list`):_default'";
+            let action = format!(
+                "':id -- Conversation ID (see `history list`):_{}__conversation_ids'",
+                name
+            );
+            script = script.replacen(replay_id, &action, 1);
+            if let Some(pos) = script.find("if [ \"$funcstack[1]\"") {
+                script.insert_str(pos, &ZSH_CONVERSATION_IDS.replace("{name}", name));
+            }
+        }
+        Shell::Fish => script.push_str(
+            &FISH_CONVERSATION_IDS
+                .replace("{fn_name}", &name.replace('-', "_"))
+                .replace("{name}", name),
+        ),
+        _ => {}
+    }
+    out.write_all(script.as_bytes())
+}
+
+/// Replay speed: a positive number.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("'{}' is not a positive number", s)),
+    }
+}
+
+/// Reaction filter of `history reactions`.
+fn parse_reaction(s: &str) -> Result<Reaction, String> {
+    s.parse()
 }
 
 impl Args {
@@ -0,0 +1,484 @@
+//! Audit log of what the agent did on this machine: Bash commands, file changes (with the
+//! SHA-256 of the file before and after), other tool calls with side effects, and the
+//! answers to confirmations, each with the conversation and turn that triggered it.
+//!
+//! Entries are appended to `audit.jsonl` (data directory), one JSON object per line, and
+//! never rewritten. `my-open-claude audit show` reviews them. Commands and arguments are
+//! masked like tool results (see `core::redact`). Recording never blocks a tool call:
+//! failures are logged.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::core::confirm::PendingAction;
+use crate::core::{paths, redact, remote, tools};
+
+/// Hex digits of a hash shown by `audit show` (the log keeps the full hash).
+const SHORT_HASH_LEN: usize = 12;
+
+/// Serializes appends from concurrent turns (e.g. TUI tabs).
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// Turns started by this process, for turn IDs.
+static TURN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Errors reading `--since`.
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error(
+        "invalid --since '{0}': expected a duration (30m, 12h, 7d, 2w), a date (2024-05-01), or an RFC 3339 time"
+    )]
+    InvalidSince(String),
+}
+
+/// What triggered the tool calls of a turn: the conversation (when it has been saved) and
+/// the turn, so a review can tell which prompt led to which change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnOrigin {
+    pub conversation: Option<String>,
+    pub turn: String,
+}
+
+impl TurnOrigin {
+    /// Origin of a new turn of `conversation`. Turn IDs are the start time, the process ID,
+    /// and a counter, e.g. "20240501T101500-4242-3".
+    pub fn new(conversation: Option<String>) -> Self {
+        let n = TURN_COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+        TurnOrigin {
+            conversation,
+            turn: format!(
+                "{}-{}-{}",
+                Local::now().format("%Y%m%dT%H%M%S"),
+                std::process::id(),
+                n
+            ),
+        }
+    }
+}
+
+/// An audited action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A Bash command ran in `cwd`.
+    Command {
+        command: String,
+        cwd: String,
+        success: bool,
+    },
+    /// A file tool ran on `path`. Hashes are SHA-256 in hex; None when the file did not
+    /// exist (before) or no longer exists (after).
+    FileChange {
+        tool: String,
+        path: String,
+        #[serde(default)]
+        before: Option<String>,
+        #[serde(default)]
+        after: Option<String>,
+        success: bool,
+    },
+    /// Another tool with side effects ran (e.g. ReplaceAll, or a file tool on a remote host).
+    Tool {
+        tool: String,
+        args: String,
+        success: bool,
+    },
+    /// The user approved or declined a destructive command or a write outside the workspace.
+    Confirmation {
+        action: String,
+        detail: String,
+        approved: bool,
+    },
+}
+
+fn short_hash(hash: &Option<String>) -> &str {
+    match hash {
+        Some(hash) => &hash[..hash.len().min(SHORT_HASH_LEN)],
+        None => "none",
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = |success: &bool| if *success { "" } else { " (failed)" };
+        match self {
+            AuditEvent::Command {
+                command,
+                cwd,
+                success,
+            } => write!(f, "Bash in {}: {}{}", cwd, command, failed(success)),
+            AuditEvent::FileChange {
+                tool,
+                path,
+                before,
+                after,
+                success,
+            } => write!(
+                f,
+                "{} {} ({} -> {}){}",
+                tool,
+                path,
+                short_hash(before),
+                short_hash(after),
+                failed(success)
+            ),
+            AuditEvent::Tool {
+                tool,
+                args,
+                success,
+            } => write!(f, "{}: {}{}", tool, args, failed(success)),
+            AuditEvent::Confirmation {
+                action,
+                detail,
+                approved,
+            } => write!(
+                f,
+                "{} {}: {}",
+                if *approved { "Approved" } else { "Declined" },
+                action.to_lowercase(),
+                detail
+            ),
+        }
+    }
+}
+
+/// A line of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Seconds since the epoch.
+    pub time: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation: Option<String>,
+    pub turn: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// A tool call with side effects being audited: started before the tool runs (to hash the
+/// file it changes), recorded once it returns.
+#[derive(Debug)]
+pub(crate) struct ToolCallAudit {
+    origin: TurnOrigin,
+    event: AuditEvent,
+    /// File hashed again after the call.
+    file: Option<PathBuf>,
+}
+
+impl ToolCallAudit {
+    /// Start auditing a call of `tool`; None for read-only tools.
+    pub(crate) fn start(
+        origin: &TurnOrigin,
+        tool: &dyn tools::Tool,
+        args: &Value,
+        working_dir: &Path,
+    ) -> Option<Self> {
+        if tool.is_read_only() {
+            return None;
+        }
+        let masked = |text: &str| redact::redact_tool_output(text).text;
+        let remote = remote::is_active() && tool.runs_remotely();
+        let target = tool
+            .target_path(args)
+            .filter(|path| !path.is_empty() && !remote)
+            .map(|path| tools::resolve_path(working_dir, &path))
+            .filter(|path| !path.is_dir());
+        let (event, file) = if let Some(command) = args.get("command").and_then(|v| v.as_str()) {
+            let event = AuditEvent::Command {
+                command: masked(command),
+                cwd: working_dir.display().to_string(),
+                success: false,
+            };
+            (event, None)
+        } else if let Some(path) = target {
+            let event = AuditEvent::FileChange {
+                tool: tool.name().to_string(),
+                path: path.display().to_string(),
+                before: sha256_file(&path),
+                after: None,
+                success: false,
+            };
+            (event, Some(path))
+        } else {
+            let event = AuditEvent::Tool {
+                tool: tool.name().to_string(),
+                args: masked(&tool.args_preview(args)),
+                success: false,
+            };
+            (event, None)
+        };
+        Some(ToolCallAudit {
+            origin: origin.clone(),
+            event,
+            file,
+        })
+    }
+
+    /// Record the call, which returned with `ok`.
+    pub(crate) fn finish(self, ok: bool) {
+        let (origin, event) = self.into_event(ok);
+        record(&origin, event);
+    }
+
+    /// The event to record for the call, which returned with `ok`.
+    fn into_event(mut self, ok: bool) -> (TurnOrigin, AuditEvent) {
+        match &mut self.event {
+            AuditEvent::Command { success, .. } | AuditEvent::Tool { success, .. } => {
+                *success = ok;
+            }
+            AuditEvent::FileChange { after, success, .. } => {
+                *after = self.file.as_deref().and_then(sha256_file);
+                *success = ok;
+            }
+            AuditEvent::Confirmation { .. } => {}
+        }
+        (self.origin, self.event)
+    }
+}
+
+/// Record the user's answer to the confirmation of `action`.
+pub(crate) fn record_confirmation(origin: &TurnOrigin, action: &PendingAction, approved: bool) {
+    let (_, detail) = action.detail();
+    record(
+        origin,
+        AuditEvent::Confirmation {
+            action: action.title().to_string(),
+            detail: redact::redact_tool_output(detail).text,
+            approved,
+        },
+    );
+}
+
+/// SHA-256 of the file at `path`, in lowercase hex; None when it cannot be read.
+fn sha256_file(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).ok()?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Append `event` to the audit log. Failures are logged.
+fn record(origin: &TurnOrigin, event: AuditEvent) {
+    let Some(path) = paths::audit_file() else {
+        return;
+    };
+    let entry = AuditEntry {
+        time: now_secs(),
+        conversation: origin.conversation.clone(),
+        turn: origin.turn.clone(),
+        event,
+    };
+    let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = append(&path, &entry) {
+        log::warn!("Failed to write the audit log: {}", e);
+    }
+}
+
+fn append(path: &Path, entry: &AuditEntry) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    line.push('\n');
+    // One write per line: appends from other processes do not interleave within it.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Entries of the audit log recorded at or after `since` (seconds since the epoch), oldest
+/// first. Empty when nothing was recorded yet.
+pub fn read(since: Option<u64>) -> io::Result<Vec<AuditEntry>> {
+    match paths::audit_file() {
+        Some(path) => read_from(&path, since),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn read_from(path: &Path, since: Option<u64>) -> io::Result<Vec<AuditEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        // A line cut short by a crash is skipped, not an error.
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) else {
+            continue;
+        };
+        if since.is_none_or(|since| entry.time >= since) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Start of the `--since` window, in seconds since the epoch: a duration back from `now`
+/// (`30m`, `12h`, `7d`, `2w`), a local date (`2024-05-01`, from midnight), or an RFC 3339
+/// time.
+pub fn parse_since(value: &str, now: DateTime<Local>) -> Result<u64, AuditError> {
+    let invalid = || AuditError::InvalidSince(value.to_string());
+    let value = value.trim();
+    let since = if let Some(unit) = value.chars().last().filter(char::is_ascii_alphabetic)
+        && let Ok(n) = value[..value.len() - 1].parse::<i64>()
+    {
+        let span = match unit {
+            'm' => chrono::Duration::try_minutes(n),
+            'h' => chrono::Duration::try_hours(n),
+            'd' => chrono::Duration::try_days(n),
+            'w' => chrono::Duration::try_weeks(n),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+        now - span
+    } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?;
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .ok_or_else(invalid)?
+    } else {
+        DateTime::parse_from_rfc3339(value)
+            .map_err(|_| invalid())?
+            .with_timezone(&Local)
+    };
+    Ok(since.timestamp().max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_calls_are_recorded_with_file_hashes_and_read_back_since() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "old").unwrap();
+        let origin = TurnOrigin::new(Some("conv-1".to_string()));
+
+        let call = ToolCallAudit::start(
+            &origin,
+            &tools::WriteTool,
+            &serde_json::json!({ "file_path": "notes.txt", "content": "new" }),
+            dir.path(),
+        )
+        .unwrap();
+        fs::write(&file, "new").unwrap();
+        let (recorded_origin, event) = call.into_event(true);
+        assert_eq!(recorded_origin, origin);
+        let AuditEvent::FileChange {
+            before,
+            after,
+            success,
+            ..
+        } = &event
+        else {
+            panic!("expected a file change");
+        };
+        assert_eq!(before.as_deref(), Some(sha256_hex("old").as_str()));
+        assert_eq!(after.as_deref(), Some(sha256_hex("new").as_str()));
+        assert!(success);
+        assert!(
+            ToolCallAudit::start(
+                &origin,
+                &tools::ReadTool,
+                &serde_json::json!({ "file_path": "notes.txt" }),
+                dir.path()
+            )
+            .is_none()
+        );
+
+        let log = dir.path().join("audit.jsonl");
+        let entry = |time: u64, event: AuditEvent| AuditEntry {
+            time,
+            conversation: origin.conversation.clone(),
+            turn: origin.turn.clone(),
+            event,
+        };
+        let old = entry(
+            100,
+            AuditEvent::Command {
+                command: "cargo test".to_string(),
+                cwd: "/work".to_string(),
+                success: true,
+            },
+        );
+        let new = entry(
+            200,
+            AuditEvent::Confirmation {
+                action: "Destructive command".to_string(),
+                detail: "rm -rf build".to_string(),
+                approved: false,
+            },
+        );
+        append(&log, &old).unwrap();
+        append(&log, &new).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .unwrap()
+            .write_all(b"{\"time\":30")
+            .unwrap();
+        assert_eq!(read_from(&log, None).unwrap(), vec![old, new.clone()]);
+        assert_eq!(read_from(&log, Some(150)).unwrap(), vec![new.clone()]);
+        assert_eq!(
+            new.event.to_string(),
+            "Declined destructive command: rm -rf build"
+        );
+    }
+
+    fn sha256_hex(text: &str) -> String {
+        format!("{:x}", Sha256::digest(text.as_bytes()))
+    }
+
+    #[test]
+    fn parse_since_accepts_durations_dates_and_times() {
+        let now = Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let at = |s: &str| parse_since(s, now).unwrap();
+        assert_eq!(
+            at("2h"),
+            (now - chrono::Duration::hours(2)).timestamp() as u64
+        );
+        assert_eq!(
+            at("7d"),
+            (now - chrono::Duration::days(7)).timestamp() as u64
+        );
+        assert_eq!(
+            at("2024-05-01"),
+            Local
+                .with_ymd_and_hms(2024, 5, 1, 0, 0, 0)
+                .unwrap()
+                .timestamp() as u64
+        );
+        assert_eq!(at("2024-05-01T00:00:00Z"), 1_714_521_600);
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("5y", now).is_err());
+    }
+}
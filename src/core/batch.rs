@@ -0,0 +1,415 @@
+//! Batch mode (`--batch FILE`): a YAML or JSON file of prompts run one after another in
+//! headless mode. Each task is saved as its own conversation; the run ends with a report
+//! of every task's status, cost, and changed files.
+//!
+//! The file is a list of tasks, or a mapping with `tasks` and the defaults `mode` and
+//! `model`. A task is a prompt string, or a mapping with `prompt` and optionally `mode`,
+//! `model`, and `title`:
+//!
+//! ```yaml
+//! mode: Build
+//! tasks:
+//!   - Fix the clippy warnings in src/core
+//!   - prompt: Summarize the open TODOs
+//!     mode: Ask
+//!     model: anthropic/claude-haiku-4.5
+//! ```
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::core::models;
+
+/// Mode of tasks that set none, in files that set no default.
+const DEFAULT_MODE: &str = "Build";
+
+/// Interaction modes a task can run in.
+const MODES: [&str; 3] = ["Ask", "Build", "Plan"];
+
+/// Keys of a task mapping.
+const TASK_KEYS: [&str; 4] = ["prompt", "mode", "model", "title"];
+
+/// Errors from loading a batch file.
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("cannot read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("{path} is not valid {format}: {message}")]
+    Parse {
+        path: String,
+        format: &'static str,
+        message: String,
+    },
+
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+}
+
+/// A prompt to run, with its mode and optional model and conversation title.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub prompt: String,
+    /// "Ask", "Build", or "Plan".
+    pub mode: String,
+    /// Model for this task; None for the configured model.
+    pub model: Option<String>,
+    /// Title of the saved conversation; None to title it after the prompt.
+    pub title: Option<String>,
+}
+
+/// Read the tasks of a batch file: JSON for a `.json` file, else YAML.
+pub fn load(path: &Path) -> Result<Vec<Task>, BatchError> {
+    let display = path.display().to_string();
+    let text = std::fs::read_to_string(path).map_err(|source| BatchError::Read {
+        path: display.clone(),
+        source,
+    })?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let value: Value = if is_json {
+        serde_json::from_str(&text).map_err(|e| BatchError::Parse {
+            path: display.clone(),
+            format: "JSON",
+            message: e.to_string(),
+        })?
+    } else {
+        serde_yaml::from_str(&text).map_err(|e| BatchError::Parse {
+            path: display.clone(),
+            format: "YAML",
+            message: e.to_string(),
+        })?
+    };
+    let tasks = parse(&value).map_err(|message| BatchError::Invalid {
+        path: display,
+        message,
+    })?;
+    for task in &tasks {
+        if let Some(ref model) = task.model {
+            models::validate_model_id(model).map_err(|e| BatchError::Invalid {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })?;
+        }
+    }
+    Ok(tasks)
+}
+
+/// Tasks of a parsed file; the error names the faulty task (from 1) or key.
+fn parse(value: &Value) -> Result<Vec<Task>, String> {
+    let (items, defaults) = match value {
+        Value::Array(items) => (items, &Map::new()),
+        Value::Object(doc) => {
+            if let Some(key) = doc
+                .keys()
+                .find(|k| !["tasks", "mode", "model"].contains(&k.as_str()))
+            {
+                return Err(format!(
+                    "unknown key '{}' (expected tasks, mode, model)",
+                    key
+                ));
+            }
+            match doc.get("tasks") {
+                Some(Value::Array(items)) => (items, doc),
+                _ => return Err("'tasks' must be a list of tasks".to_string()),
+            }
+        }
+        _ => return Err("expected a list of tasks, or a mapping with 'tasks'".to_string()),
+    };
+    let default_mode = match defaults.get("mode") {
+        Some(mode) => parse_mode(mode).map_err(|e| format!("mode: {}", e))?,
+        None => DEFAULT_MODE.to_string(),
+    };
+    let default_model = optional_string(defaults, "model")?;
+    if items.is_empty() {
+        return Err("no tasks".to_string());
+    }
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            parse_task(item, &default_mode, default_model.as_deref())
+                .map_err(|e| format!("task {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+fn parse_task(
+    item: &Value,
+    default_mode: &str,
+    default_model: Option<&str>,
+) -> Result<Task, String> {
+    let task = match item {
+        Value::String(prompt) => Task {
+            prompt: prompt.clone(),
+            mode: default_mode.to_string(),
+            model: None,
+            title: None,
+        },
+        Value::Object(fields) => {
+            if let Some(key) = fields.keys().find(|k| !TASK_KEYS.contains(&k.as_str())) {
+                return Err(format!(
+                    "unknown key '{}' (expected {})",
+                    key,
+                    TASK_KEYS.join(", ")
+                ));
+            }
+            Task {
+                prompt: optional_string(fields, "prompt")?.unwrap_or_default(),
+                mode: match fields.get("mode") {
+                    Some(mode) => parse_mode(mode)?,
+                    None => default_mode.to_string(),
+                },
+                model: optional_string(fields, "model")?,
+                title: optional_string(fields, "title")?,
+            }
+        }
+        _ => return Err("expected a prompt or a mapping with 'prompt'".to_string()),
+    };
+    if task.prompt.trim().is_empty() {
+        return Err("empty prompt".to_string());
+    }
+    Ok(Task {
+        prompt: task.prompt.trim().to_string(),
+        model: task.model.or_else(|| default_model.map(str::to_string)),
+        ..task
+    })
+}
+
+/// "ask", "BUILD", ... as the mode name used by the agent loop.
+fn parse_mode(value: &Value) -> Result<String, String> {
+    value
+        .as_str()
+        .and_then(|s| MODES.iter().find(|m| m.eq_ignore_ascii_case(s.trim())))
+        .map(|m| m.to_string())
+        .ok_or_else(|| format!("mode must be one of {}", MODES.join(", ")))
+}
+
+/// Non-empty string value of `key`, if set.
+fn optional_string(fields: &Map<String, Value>, key: &str) -> Result<Option<String>, String> {
+    match fields.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) if s.trim().is_empty() => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.trim().to_string())),
+        Some(_) => Err(format!("'{}' must be a string", key)),
+    }
+}
+
+/// Outcome of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Done,
+    Failed,
+    /// Not run: a spend cap was reached before it.
+    Skipped,
+}
+
+/// What a task did.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskReport {
+    pub title: String,
+    pub mode: String,
+    pub model: String,
+    pub status: TaskStatus,
+    /// Saved conversation (see `history list`, `replay`).
+    pub conversation_id: Option<String>,
+    /// Cost in credits (USD), as reported by the API.
+    pub cost: f64,
+    pub tokens: u64,
+    pub tool_calls: usize,
+    pub duration_secs: f64,
+    /// Files written or edited, relative to the workspace root when inside it.
+    pub changed_files: Vec<String>,
+    /// Why the task failed or was skipped.
+    pub error: Option<String>,
+}
+
+/// Report of a whole batch run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchReport {
+    pub tasks: Vec<TaskReport>,
+    /// Total cost in credits (USD).
+    pub cost: f64,
+    pub duration_secs: f64,
+}
+
+impl BatchReport {
+    pub fn new(tasks: Vec<TaskReport>, duration_secs: f64) -> Self {
+        let cost = tasks.iter().map(|t| t.cost).sum();
+        BatchReport {
+            tasks,
+            cost,
+            duration_secs,
+        }
+    }
+
+    /// Number of tasks with `status`.
+    pub fn count(&self, status: TaskStatus) -> usize {
+        self.tasks.iter().filter(|t| t.status == status).count()
+    }
+
+    /// True when every task is done.
+    pub fn succeeded(&self) -> bool {
+        self.count(TaskStatus::Done) == self.tasks.len()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    /// A line with the totals, then one block per task:
+    /// "✓ 1. Fix the lints · Build · model · 12.3s · $0.0040", its conversation, and its
+    /// changed files or error.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut totals = vec![format!("{} done", self.count(TaskStatus::Done))];
+        for (status, label) in [
+            (TaskStatus::Failed, "failed"),
+            (TaskStatus::Skipped, "skipped"),
+        ] {
+            let n = self.count(status);
+            if n > 0 {
+                totals.push(format!("{} {}", n, label));
+            }
+        }
+        writeln!(
+            f,
+            "Batch: {} task{}, {} · ${:.4} · {:.1}s",
+            self.tasks.len(),
+            if self.tasks.len() == 1 { "" } else { "s" },
+            totals.join(", "),
+            self.cost,
+            self.duration_secs
+        )?;
+        for (i, task) in self.tasks.iter().enumerate() {
+            let marker = match task.status {
+                TaskStatus::Done => "✓",
+                TaskStatus::Failed => "✗",
+                TaskStatus::Skipped => "-",
+            };
+            write!(
+                f,
+                "{} {}. {} · {} · {}",
+                marker,
+                i + 1,
+                task.title,
+                task.mode,
+                task.model
+            )?;
+            if task.status == TaskStatus::Skipped {
+                writeln!(f, " · skipped")?;
+            } else {
+                writeln!(f, " · {:.1}s · ${:.4}", task.duration_secs, task.cost)?;
+            }
+            if let Some(ref id) = task.conversation_id {
+                writeln!(f, "    conversation: {}", id)?;
+            }
+            if !task.changed_files.is_empty() {
+                writeln!(f, "    changed: {}", task.changed_files.join(", "))?;
+            }
+            if let Some(ref error) = task.error {
+                writeln!(f, "    error: {}", error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn parse_reads_prompts_defaults_and_overrides() {
+        let tasks = parse(&yaml(
+            "mode: plan\nmodel: a/default\ntasks:\n  - Fix the lints\n  - prompt: \" Summarize TODOs \"\n    mode: ASK\n    model: b/other\n    title: TODOs\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            tasks,
+            vec![
+                Task {
+                    prompt: "Fix the lints".to_string(),
+                    mode: "Plan".to_string(),
+                    model: Some("a/default".to_string()),
+                    title: None,
+                },
+                Task {
+                    prompt: "Summarize TODOs".to_string(),
+                    mode: "Ask".to_string(),
+                    model: Some("b/other".to_string()),
+                    title: Some("TODOs".to_string()),
+                },
+            ]
+        );
+
+        let tasks = parse(&serde_json::json!(["one", {"prompt": "two"}])).unwrap();
+        assert_eq!(tasks[1].mode, "Build");
+        assert_eq!(tasks[1].model, None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_tasks() {
+        let err = |s: &str| parse(&yaml(s)).unwrap_err();
+        assert_eq!(err("[]"), "no tasks");
+        assert_eq!(
+            err("- ok\n- promt: typo\n"),
+            "task 2: unknown key 'promt' (expected prompt, mode, model, title)"
+        );
+        assert_eq!(
+            err("- prompt: x\n  mode: fast\n"),
+            "task 1: mode must be one of Ask, Build, Plan"
+        );
+        assert_eq!(err("- \"  \"\n"), "task 1: empty prompt");
+        assert!(err("tasks: x\n").contains("'tasks' must be a list"));
+    }
+
+    #[test]
+    fn report_counts_statuses_and_lists_changes() {
+        let task = |status, cost| TaskReport {
+            title: "Fix".to_string(),
+            mode: "Build".to_string(),
+            model: "m".to_string(),
+            status,
+            conversation_id: None,
+            cost,
+            tokens: 0,
+            tool_calls: 0,
+            duration_secs: 1.0,
+            changed_files: Vec::new(),
+            error: None,
+        };
+        let mut done = task(TaskStatus::Done, 0.5);
+        done.changed_files = vec!["src/a.rs".to_string(), "src/b.rs".to_string()];
+        let report = BatchReport::new(
+            vec![
+                done,
+                task(TaskStatus::Failed, 0.25),
+                task(TaskStatus::Skipped, 0.0),
+            ],
+            3.0,
+        );
+        assert_eq!(report.cost, 0.75);
+        assert!(!report.succeeded());
+        let text = report.to_string();
+        assert!(text.starts_with("Batch: 3 tasks, 1 done, 1 failed, 1 skipped · $0.7500 · 3.0s\n"));
+        assert!(
+            text.contains(
+                "✓ 1. Fix · Build · m · 1.0s · $0.5000\n    changed: src/a.rs, src/b.rs\n"
+            )
+        );
+        assert!(text.contains("- 3. Fix · Build · m · skipped\n"));
+    }
+}
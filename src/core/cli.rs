@@ -1,4 +1,6 @@
-//! CLI-only commands: config info, models list, history list.
+//! CLI-only commands: config info, models list, history list, reactions export, prune and
+//! archive, semantic index, crash report, usage statistics, tool listing and direct tool runs,
+//! audit log review.
 //!
 //! These run without opening the TUI and produce plain text output.
 
@@ -6,11 +8,22 @@ use std::env;
 use std::io::{self, Read};
 
 use crate::core::api_key;
-use crate::core::config::{self, ConfigError};
+use crate::core::audit;
+use crate::core::config::{self, ConfigError, Provider};
+use crate::core::confirm;
+use crate::core::crash;
+use crate::core::generation;
 use crate::core::history;
+use crate::core::index;
+use crate::core::llm;
 use crate::core::models;
+use crate::core::network::NetworkOptions;
 use crate::core::paths;
-use crate::core::persistence;
+use crate::core::profiles;
+use crate::core::spend;
+use crate::core::stats;
+use crate::core::tools;
+use crate::core::workspace;
 
 /// Run the `config` command: display paths, model, and API key status.
 pub fn run_config() {
@@ -24,19 +37,240 @@ pub fn run_config() {
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "—".to_string());
 
-    let (model, model_source, api_key_status) = match config::load() {
-        Ok(c) => (c.model_id, model_source(), "set ✓"),
-        Err(ConfigError::MissingApiKey) => {
-            let (id, src) = fallback_model();
-            (id, src, "not set")
+    let (
+        provider,
+        model,
+        model_source,
+        api_key_status,
+        sections,
+        caps,
+        embedding,
+        titles,
+        routing,
+        generation,
+        network,
+        redaction,
+    ) = match config::load() {
+        Ok(mut c) => {
+            if let Ok(cwd) = env::current_dir() {
+                config::apply_workspace_model(&mut c, &cwd);
+            }
+            let sections: Vec<&str> = c.prompt_layout.order.iter().map(|id| id.key()).collect();
+            let provider = match c.provider {
+                Provider::OpenRouter => c.provider.to_string(),
+                Provider::Ollama => format!("{} ({})", c.provider, c.ollama_host()),
+                Provider::Anthropic => format!("{} ({})", c.provider, c.base_url()),
+            };
+            let api_key_status = match c.provider {
+                Provider::OpenRouter | Provider::Anthropic => "set ✓",
+                Provider::Ollama => "not needed",
+            };
+            let generation = generation::effective(&c.generation, &c.model_id, None).summary();
+            (
+                provider,
+                c.model_id,
+                c.model_source,
+                api_key_status,
+                sections.join(", "),
+                c.spend_limits.to_string(),
+                c.embedding_model.unwrap_or_else(|| "off".to_string()),
+                c.title_model.unwrap_or_else(|| "off".to_string()),
+                routing_summary(&c.openrouter),
+                generation,
+                network_summary(&c.network),
+                redaction_summary(c.redact_tool_output, &c.redact_patterns),
+            )
+        }
+        Err(ConfigError::MissingApiKey(_) | ConfigError::MissingProfileApiKey { .. }) => {
+            let provider = Provider::from_env().unwrap_or(Provider::OpenRouter);
+            let (id, src) = config::resolve_model(provider);
+            let none = || "—".to_string();
+            let provider = provider.to_string();
+            (
+                provider,
+                id,
+                src,
+                "not set",
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+            )
+        }
+        Err(e @ ConfigError::InvalidOpenRouterOption(_)) => {
+            let (id, src) = config::resolve_model(Provider::OpenRouter);
+            let none = || "—".to_string();
+            let provider = Provider::OpenRouter.to_string();
+            let routing = format!("error: {}", e);
+            (
+                provider,
+                id,
+                src,
+                "—",
+                none(),
+                none(),
+                none(),
+                none(),
+                routing,
+                none(),
+                none(),
+                none(),
+            )
+        }
+        Err(e @ ConfigError::UnknownPromptSection(_)) => {
+            let provider = Provider::from_env().unwrap_or(Provider::OpenRouter);
+            let (id, src) = config::resolve_model(provider);
+            let none = || "—".to_string();
+            (
+                provider.to_string(),
+                id,
+                src,
+                "—",
+                format!("error: {}", e),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+            )
+        }
+        Err(e @ ConfigError::UnknownProvider(_)) => {
+            let (id, src) = config::resolve_model(Provider::OpenRouter);
+            let none = || "—".to_string();
+            let provider = format!("error: {}", e);
+            (
+                provider,
+                id,
+                src,
+                "—",
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+            )
+        }
+        Err(e @ ConfigError::InvalidRedactPattern { .. }) => {
+            let provider = Provider::from_env().unwrap_or(Provider::OpenRouter);
+            let (id, src) = config::resolve_model(provider);
+            let none = || "—".to_string();
+            (
+                provider.to_string(),
+                id,
+                src,
+                "—",
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                format!("error: {}", e),
+            )
+        }
+        Err(ConfigError::InvalidNetworkOption(e)) => {
+            let provider = Provider::from_env().unwrap_or(Provider::OpenRouter);
+            let (id, src) = config::resolve_model(provider);
+            let none = || "—".to_string();
+            (
+                provider.to_string(),
+                id,
+                src,
+                "—",
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                none(),
+                format!("error: {}", e),
+                none(),
+            )
         }
     };
 
     println!("Config:        {}", config_dir);
     println!("Cache:        {}", cache_dir);
     println!("Conversations: {}", data_dir);
+    println!("Profile:      {}", profile_summary());
+    println!("Provider:     {}", provider);
     println!("Model:        {} ({})", model, model_source);
     println!("API key:      {}", api_key_status);
+    println!("Prompt:       {}", sections);
+    println!("Spend:        {} (caps: {})", spend::totals(), caps);
+    println!("Embeddings:   {}", embedding);
+    println!("Titles:       {}", titles);
+    println!("Routing:      {}", routing);
+    println!("Generation:   {}", generation);
+    println!("Network:      {}", network);
+    println!("Redaction:    {}", redaction);
+}
+
+/// Active profile and the available ones for `config show`.
+fn profile_summary() -> String {
+    let names: Vec<String> = profiles::list().into_iter().map(|p| p.name).collect();
+    let active = profiles::active().map_or_else(|| "none".to_string(), |p| p.name);
+    if names.is_empty() {
+        active
+    } else {
+        format!("{} (available: {})", active, names.join(", "))
+    }
+}
+
+/// Masking of secrets in tool results, for `config show`.
+fn redaction_summary(enabled: bool, patterns: &[String]) -> String {
+    match (enabled, patterns.len()) {
+        (false, _) => "off (tool results are sent as is)".to_string(),
+        (true, 0) => "tool results".to_string(),
+        (true, n) => format!("tool results, {} custom pattern(s)", n),
+    }
+}
+
+/// One-line summary of the proxy and TLS options for `config show`.
+fn network_summary(options: &NetworkOptions) -> String {
+    let mut parts = vec![match options.effective_proxy() {
+        Some(proxy) if options.proxy.is_some() => format!("proxy {}", proxy),
+        Some(proxy) => format!("proxy {} (from the environment)", proxy),
+        None => "direct".to_string(),
+    }];
+    if let Some(path) = &options.ca_bundle {
+        parts.push(format!("CA bundle {}", path.display()));
+    }
+    if options.insecure_tls {
+        parts.push("certificates not verified".to_string());
+    }
+    parts.join("; ")
+}
+
+/// One-line summary of the OpenRouter routing options for `config show`.
+fn routing_summary(options: &config::OpenRouterOptions) -> String {
+    let mut parts = Vec::new();
+    if !options.provider.is_empty() {
+        parts.push(format!(
+            "provider {}",
+            serde_json::Value::Object(options.provider.clone())
+        ));
+    }
+    match options.transforms.as_deref() {
+        Some([]) => parts.push("no transforms".to_string()),
+        Some(transforms) => parts.push(format!("transforms {}", transforms.join(", "))),
+        None => {}
+    }
+    if parts.is_empty() {
+        "default".to_string()
+    } else {
+        parts.join("; ")
+    }
 }
 
 /// Run the `config set-api-key` command: store API key in config directory.
@@ -72,43 +306,6 @@ pub fn run_config_set_api_key(api_key: Option<String>) {
     }
 }
 
-fn model_source() -> &'static str {
-    if persistence::load_last_model().is_some() {
-        "from last_model"
-    } else if env::var("OPENROUTER_MODEL")
-        .ok()
-        .filter(|s| !s.is_empty())
-        .is_some()
-    {
-        "from OPENROUTER_MODEL"
-    } else {
-        "default"
-    }
-}
-
-fn fallback_model() -> (String, &'static str) {
-    if let Some(id) = persistence::load_last_model() {
-        return (id, "from last_model");
-    }
-    if let Ok(id) = env::var("OPENROUTER_MODEL")
-        && !id.is_empty()
-    {
-        return (id, "from OPENROUTER_MODEL");
-    }
-    ("anthropic/claude-haiku-4.5".to_string(), "default")
-}
-
-/// Format context length as human-readable (e.g. "128k", "1M").
-fn format_context(n: u64) -> String {
-    if n >= 1_000_000 {
-        format!("{:.1}M", n as f64 / 1_000_000.0)
-    } else if n >= 1_000 {
-        format!("{}k", n / 1_000)
-    } else {
-        n.to_string()
-    }
-}
-
 /// Run the `models` command: list available models (tool-capable) from cache or API.
 pub async fn run_models(config: &crate::core::config::Config, query: Option<&str>) {
     let models = match models::fetch_models_with_tools(config).await {
@@ -146,13 +343,38 @@ pub async fn run_models(config: &crate::core::config::Config, query: Option<&str
     println!("{}  {}  ------", "-".repeat(id_w), "-".repeat(name_w));
 
     for m in &filtered {
-        let ctx = format_context(m.context_length);
+        let ctx = models::format_context_length(m.context_length);
         println!("{:<id_w$}  {:<name_w$}  {:>6}", m.id, m.name, ctx);
     }
 
     println!("\n{} model(s) listed", filtered.len());
 }
 
+/// Run the `index` command: build or update the semantic index of the workspace root.
+pub fn run_index(rebuild: bool) {
+    if !index::is_configured() {
+        eprintln!("Error: {}", index::IndexError::NotConfigured);
+        std::process::exit(1);
+    }
+    let root = workspace::detect().root;
+    println!("Indexing {}...", root.display());
+    match index::update(&root, rebuild) {
+        Ok(stats) => {
+            println!(
+                "{} file(s), {} chunk(s) indexed ({} embedded, {} removed)",
+                stats.files, stats.chunks, stats.embedded, stats.removed
+            );
+            if let Some(path) = index::index_path(&root) {
+                println!("Index: {}", path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Run the `history list` command: list conversations with optional limit.
 pub fn run_history_list(limit: Option<usize>) {
     let convs = match history::list_conversations() {
@@ -171,9 +393,306 @@ pub fn run_history_list(limit: Option<usize>) {
     }
 }
 
+/// Run the `history reactions` command: the replies marked with a reaction (or only with
+/// `only`), one JSON object per line.
+pub fn run_history_reactions(only: Option<history::Reaction>) {
+    let reacted = match history::export_reactions(only) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if reacted.is_empty() {
+        eprintln!("No replies with a reaction yet: press Alt+A on a reply in the TUI.");
+        return;
+    }
+    for message in reacted {
+        match serde_json::to_string(&message) {
+            Ok(line) => println!("{}", line),
+            Err(e) => log::warn!("Skipping reply of {}: {}", message.conversation_id, e),
+        }
+    }
+}
+
+/// Run the `history prune` command: remove the conversations past the retention limits (or
+/// with `dry_run`, only list them), one per line with the reason.
+pub fn run_history_prune(dry_run: bool) {
+    let config = match config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let removed = match history::prune_history(&config, dry_run) {
+        Ok(removed) => removed,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    for candidate in &removed {
+        let meta = &candidate.meta;
+        println!(
+            "{}\t{}\t{}\t{}",
+            meta.id,
+            meta.title,
+            format_timestamp(meta.updated_at),
+            candidate.reason
+        );
+    }
+    let verb = if dry_run {
+        "would be removed"
+    } else {
+        "removed"
+    };
+    println!("{} conversation(s) {}", removed.len(), verb);
+}
+
+/// Run the `audit show` command: the audit log since `since` (see `audit::parse_since`),
+/// one tab-separated line per entry, or the entries as JSON Lines.
+pub fn run_audit_show(since: Option<&str>, json: bool) {
+    let since = match since.map(|s| audit::parse_since(s, chrono::Local::now())) {
+        Some(Ok(since)) => Some(since),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    let entries = match audit::read(since) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if entries.is_empty() {
+        eprintln!("No commands, file changes, or confirmations recorded.");
+        return;
+    }
+    for entry in &entries {
+        if json {
+            match serde_json::to_string(entry) {
+                Ok(line) => println!("{}", line),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        } else {
+            println!(
+                "{}\t{}\t{}\t{}",
+                format_timestamp(entry.time),
+                entry.conversation.as_deref().unwrap_or("-"),
+                entry.turn,
+                entry.event
+            );
+        }
+    }
+}
+
+/// Run the `history archive` / `history unarchive` command.
+pub fn run_history_archive(id: &str, archived: bool) {
+    if let Err(e) = history::set_archived(id, archived) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if archived {
+        println!("Archived {}: it is kept when the history is pruned.", id);
+    } else {
+        println!("Unarchived {}.", id);
+    }
+}
+
+/// Run the `report` command: open a prefilled GitHub issue from the latest crash report.
+pub fn run_report(no_open: bool) {
+    let Some(path) = crash::latest_bundle() else {
+        println!("No crash report found.");
+        return;
+    };
+    let bundle = match std::fs::read_to_string(&path) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let url = crash::issue_url(&bundle, &path);
+    println!("Crash report: {}", path.display());
+    println!(
+        "Review it before submitting; secrets were redacted but the log may hold paths or prompts."
+    );
+    if no_open || opener::open_browser(&url).is_err() {
+        println!("Open this URL to file the issue:\n{}", url);
+    }
+}
+
+/// Run the `stats` command: usage over the last `days` days, across sessions.
+pub fn run_stats(days: usize) {
+    let report = stats::report(days);
+    let (Some(first), Some(last)) = (report.days.first(), report.days.last()) else {
+        return;
+    };
+    if report.sessions == 0 {
+        println!("No usage recorded in the last {} days.", report.days.len());
+        return;
+    }
+    println!(
+        "Usage from {} to {} ({} days, {} session{})",
+        first.start,
+        last.start,
+        report.days.len(),
+        report.sessions,
+        if report.sessions == 1 { "" } else { "s" }
+    );
+    println!("Requests:      {}", report.requests);
+    println!(
+        "Tokens:        {} prompt, {} completion",
+        report.prompt_tokens, report.completion_tokens
+    );
+    println!("Cost:          ${:.2}", report.cost);
+    println!();
+    let daily: Vec<u64> = report.days.iter().map(|d| d.tokens).collect();
+    let weekly: Vec<u64> = report.weeks.iter().map(|w| w.tokens).collect();
+    println!("Daily tokens   |{}|", stats::sparkline(&daily));
+    println!(
+        "Weekly tokens  |{}| (last {} weeks)",
+        stats::sparkline(&weekly),
+        weekly.len()
+    );
+
+    println!("\nTop models");
+    for (model, usage) in &report.top_models {
+        println!(
+            "  {:<40} {:>6} requests {:>12} tokens  ${:.2}",
+            model, usage.requests, usage.tokens, usage.cost
+        );
+    }
+    if !report.top_commands.is_empty() {
+        println!("\nTop slash commands");
+        for (name, count) in &report.top_commands {
+            println!("  /{:<39} {:>6}", name, count);
+        }
+    }
+    if !report.top_tools.is_empty() {
+        println!("\nTop tools");
+        for (name, count) in &report.top_tools {
+            println!("  {:<40} {:>6}", name, count);
+        }
+    }
+}
+
+/// Run the `tools list` command: each tool with its description and arguments (required ones
+/// marked with *), or the definitions sent to the model as JSON.
+pub fn run_tools_list(config: &config::Config, json: bool) {
+    if json {
+        let definitions = serde_json::Value::Array(tools::definitions().to_vec());
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&definitions).unwrap_or_default()
+        );
+        return;
+    }
+    for definition in tools::definitions() {
+        let function = &definition["function"];
+        let name = function["name"].as_str().unwrap_or_default();
+        let note = match tools::all().iter().find(|t| t.name() == name) {
+            Some(tool) if !tool.available() => " (not available)",
+            _ if llm::is_tool_disabled(name, &config.disabled_tools) => " (disabled)",
+            _ => "",
+        };
+        let description = function["description"].as_str().unwrap_or_default();
+        println!("{}{}", name, note);
+        println!("  {}", description.lines().next().unwrap_or_default());
+        let arguments = tool_arguments(&function["parameters"]);
+        if !arguments.is_empty() {
+            println!("  args: {}", arguments.join(", "));
+        }
+    }
+}
+
+/// Argument names of a tool's parameters schema, required ones followed by `*`.
+fn tool_arguments(parameters: &serde_json::Value) -> Vec<String> {
+    let required: Vec<&str> = parameters["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(|n| n.as_str()).collect())
+        .unwrap_or_default();
+    parameters["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .keys()
+                .map(|name| {
+                    if required.contains(&name.as_str()) {
+                        format!("{}*", name)
+                    } else {
+                        name.clone()
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Run the `tools run` command: call tool `name` once with the JSON object `args`, with the
+/// checks of the agent loop (see `llm::execute_standalone`), and print its result. Exits with
+/// status 1 when the call fails.
+pub fn run_tool(
+    config: &config::Config,
+    working_dir: &std::path::Path,
+    name: &str,
+    args: &str,
+    mode: &str,
+) {
+    let args = match serde_json::from_str::<serde_json::Value>(args) {
+        Ok(args) if args.is_object() => args,
+        Ok(_) => {
+            eprintln!("Error: --args must be a JSON object");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: invalid --args JSON: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = llm::execute_standalone(
+        name,
+        &args,
+        mode,
+        &config.disabled_tools,
+        working_dir,
+        &config.tool_timeouts,
+        config.dry_run,
+        &confirm::default_confirm(),
+    );
+    if result.success {
+        println!("{}", result.content);
+    } else {
+        eprintln!("{}", result.content);
+        std::process::exit(1);
+    }
+}
+
 fn format_timestamp(secs: u64) -> String {
     use chrono::{TimeZone, Utc};
     let dt = Utc.timestamp_opt(secs as i64, 0).single();
     dt.map(|d| d.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_else(|| secs.to_string())
 }
+
+/// Run the hidden `mock-server` command: serve `script` until interrupted.
+#[cfg(feature = "testing")]
+pub fn run_mock_server(script: &std::path::Path, port: u16) -> Result<(), String> {
+    use crate::core::testing::{self, MockServer};
+
+    let script = testing::load_script(script)?;
+    let count = script.len();
+    let server = MockServer::bind(port, script).map_err(|e| e.to_string())?;
+    println!("Mock server: {} ({} responses)", server.base_url(), count);
+    println!(
+        "Try: OPENROUTER_API_KEY=test OPENROUTER_BASE_URL={} my-open-claude",
+        server.base_url()
+    );
+    loop {
+        std::thread::park();
+    }
+}
@@ -0,0 +1,268 @@
+//! User rules for Bash commands, checked before the Bash tool's built-in detection of
+//! destructive commands (rm, mv...).
+//!
+//! Allow rules let a command run without confirmation (e.g. `rm -rf target/`); deny rules
+//! always ask, even for commands that are not destructive. Saved rules live in
+//! `bash_rules.json` in the config directory; rules added during the session (confirm popup
+//! "always allow", `/permissions`) last until exit unless saved.
+//!
+//! A rule matches the normalized command (trimmed, lowercase, single spaces) exactly, or as a
+//! prefix when it ends with `*` (`git push *`). Allow rules never apply to commands chaining
+//! several commands (`;`, `&&`, `|`, `$(...)`...); deny rules also match each part of them.
+
+use std::fs;
+use std::io;
+use std::sync::{LazyLock, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::paths;
+
+const RULES_FILE: &str = "bash_rules.json";
+
+/// Characters that chain or substitute commands: an allowed prefix could hide anything after them.
+const CONTROL_CHARS: &[char] = &[';', '&', '|', '`', '\n', '\r'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    /// Run without confirmation.
+    Allow,
+    /// Always ask for confirmation.
+    Deny,
+}
+
+impl RuleKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RuleKind::Allow => "allow",
+            RuleKind::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRule {
+    pub kind: RuleKind,
+    /// Normalized pattern; a trailing `*` matches any rest of the command.
+    pub pattern: String,
+    /// Stored in `bash_rules.json` (false: this session only).
+    pub saved: bool,
+}
+
+impl CommandRule {
+    /// True when the rule matches `command`, already normalized.
+    fn matches(&self, command: &str) -> bool {
+        match self.pattern.strip_suffix('*') {
+            Some(prefix) => command.starts_with(prefix),
+            None => command == self.pattern,
+        }
+    }
+}
+
+/// Contents of `bash_rules.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+static RULES: LazyLock<Mutex<Vec<CommandRule>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+fn rules() -> std::sync::MutexGuard<'static, Vec<CommandRule>> {
+    RULES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Trim, lowercase and collapse whitespace, so rules ignore spacing and case.
+pub fn normalize(command: &str) -> String {
+    command
+        .trim()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// True when `command` runs a single command, so allow rules may apply to it.
+pub fn can_allow(command: &str) -> bool {
+    !command.contains(CONTROL_CHARS) && !command.contains("$(")
+}
+
+/// Rule deciding for `command` among `rules`: any matching deny rule wins over allow rules.
+pub fn decide(rules: &[CommandRule], command: &str) -> Option<RuleKind> {
+    let parts: Vec<String> = command
+        .split(CONTROL_CHARS)
+        .map(normalize)
+        .filter(|part| !part.is_empty())
+        .collect();
+    let denied = rules
+        .iter()
+        .filter(|rule| rule.kind == RuleKind::Deny)
+        .any(|rule| parts.iter().any(|part| rule.matches(part)));
+    if denied {
+        return Some(RuleKind::Deny);
+    }
+    let normalized = normalize(command);
+    let allowed = can_allow(command)
+        && rules
+            .iter()
+            .filter(|rule| rule.kind == RuleKind::Allow)
+            .any(|rule| rule.matches(&normalized));
+    allowed.then_some(RuleKind::Allow)
+}
+
+/// Rule deciding for `command` among the current rules, if any.
+pub fn check(command: &str) -> Option<RuleKind> {
+    decide(&rules(), command)
+}
+
+/// Load the saved rules (called once at startup). A missing or invalid file yields none.
+pub fn load() {
+    let file: RulesFile = paths::config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(RULES_FILE)).ok())
+        .and_then(|content| match serde_json::from_str(&content) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                log::warn!("Ignoring invalid {}: {}", RULES_FILE, e);
+                None
+            }
+        })
+        .unwrap_or_default();
+    let saved = |kind, patterns: Vec<String>| {
+        patterns
+            .into_iter()
+            .map(|p| normalize(&p))
+            .filter(|p| !p.is_empty())
+            .map(move |pattern| CommandRule {
+                kind,
+                pattern,
+                saved: true,
+            })
+    };
+    let mut rules = rules();
+    rules.clear();
+    rules.extend(saved(RuleKind::Allow, file.allow));
+    rules.extend(saved(RuleKind::Deny, file.deny));
+}
+
+/// Current rules: the saved ones in file order, then those added during the session.
+pub fn list() -> Vec<CommandRule> {
+    rules().clone()
+}
+
+/// Add a session rule. Returns false when the pattern is empty or already has a rule of `kind`.
+pub fn add(kind: RuleKind, pattern: &str) -> bool {
+    let pattern = normalize(pattern);
+    let mut rules = rules();
+    if pattern.is_empty()
+        || rules
+            .iter()
+            .any(|rule| rule.kind == kind && rule.pattern == pattern)
+    {
+        return false;
+    }
+    rules.push(CommandRule {
+        kind,
+        pattern,
+        saved: false,
+    });
+    true
+}
+
+/// Remove the rule at `index` of `list()`, also from `bash_rules.json` when it was saved.
+pub fn remove(index: usize) -> io::Result<()> {
+    let mut rules = rules();
+    if index >= rules.len() {
+        return Ok(());
+    }
+    let removed = rules.remove(index);
+    if removed.saved {
+        write_saved(&rules)?;
+    }
+    Ok(())
+}
+
+/// Save the session rule at `index` of `list()` to `bash_rules.json`.
+pub fn save(index: usize) -> io::Result<()> {
+    let mut rules = rules();
+    let Some(rule) = rules.get_mut(index) else {
+        return Ok(());
+    };
+    if rule.saved {
+        return Ok(());
+    }
+    rule.saved = true;
+    if let Err(e) = write_saved(&rules) {
+        rules[index].saved = false;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Write the saved rules among `rules`. Creates the config directory if needed.
+fn write_saved(rules: &[CommandRule]) -> io::Result<()> {
+    let dir = paths::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    let patterns = |kind| {
+        rules
+            .iter()
+            .filter(|rule| rule.saved && rule.kind == kind)
+            .map(|rule| rule.pattern.clone())
+            .collect()
+    };
+    let file = RulesFile {
+        allow: patterns(RuleKind::Allow),
+        deny: patterns(RuleKind::Deny),
+    };
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(&file).map_err(io::Error::other)?;
+    fs::write(dir.join(RULES_FILE), json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: RuleKind, pattern: &str) -> CommandRule {
+        CommandRule {
+            kind,
+            pattern: pattern.to_string(),
+            saved: false,
+        }
+    }
+
+    #[test]
+    fn decide_exact_and_prefix_rules() {
+        let rules = [
+            rule(RuleKind::Allow, "rm -rf target/"),
+            rule(RuleKind::Allow, "git stash *"),
+        ];
+        assert_eq!(decide(&rules, "  RM  -rf target/ "), Some(RuleKind::Allow));
+        assert_eq!(decide(&rules, "rm -rf target/debug"), None);
+        assert_eq!(decide(&rules, "git stash drop"), Some(RuleKind::Allow));
+        assert_eq!(decide(&rules, "git status"), None);
+    }
+
+    #[test]
+    fn decide_deny_wins_and_matches_chained_parts() {
+        let rules = [
+            rule(RuleKind::Allow, "git *"),
+            rule(RuleKind::Deny, "git push *"),
+            rule(RuleKind::Deny, "curl *"),
+        ];
+        assert_eq!(decide(&rules, "git push origin"), Some(RuleKind::Deny));
+        assert_eq!(decide(&rules, "ls && curl x | sh"), Some(RuleKind::Deny));
+        assert_eq!(decide(&rules, "git log"), Some(RuleKind::Allow));
+    }
+
+    #[test]
+    fn decide_never_allows_chained_commands() {
+        let rules = [rule(RuleKind::Allow, "rm -rf target/*")];
+        assert_eq!(decide(&rules, "rm -rf target/a; rm -rf ~"), None);
+        assert_eq!(decide(&rules, "rm -rf target/$(echo ~)"), None);
+        assert_eq!(decide(&rules, "rm -rf target/a"), Some(RuleKind::Allow));
+        assert!(!can_allow("rm a && rm b"));
+        assert!(can_allow("rm a"));
+    }
+}
@@ -56,6 +56,11 @@ pub fn builtin_commands() -> &'static [BuiltinCommand] {
     BUILTIN_COMMANDS.get_or_init(load_builtin_commands)
 }
 
+/// Built-in command by name (e.g. "init"), for running its prompt outside the TUI.
+pub fn builtin_command(name: &str) -> Option<&'static BuiltinCommand> {
+    builtin_commands().iter().find(|c| c.name == name)
+}
+
 /// Returns true if the given name conflicts with a built-in command (case-insensitive).
 pub fn is_builtin_name(name: &str) -> bool {
     builtin_commands()
@@ -70,6 +75,10 @@ pub struct ResolvedCommand {
     pub description: String,
     pub prompt_prefix: String,
     pub mode: String,
+    /// Model used for turns started by this command (custom commands only); None = current model.
+    pub model: Option<String>,
+    /// JSON schema the replies of this command must match (custom commands only).
+    pub output_schema: Option<serde_json::Value>,
     pub is_custom: bool,
 }
 
@@ -90,10 +99,12 @@ pub fn resolve_commands(
             description: c.description.clone(),
             prompt_prefix: c.prompt_prefix.clone(),
             mode: c.mode.clone(),
+            model: None,
+            output_schema: None,
             is_custom: false,
         })
         .collect();
-    builtin.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    builtin.sort_by_key(|c| c.name.to_lowercase());
 
     let mut custom_resolved: Vec<ResolvedCommand> = custom
         .into_iter()
@@ -102,16 +113,18 @@ pub fn resolve_commands(
             description: t.description,
             prompt_prefix: t.prompt_prefix,
             mode: t.mode,
+            model: t.model,
+            output_schema: t.output_schema,
             is_custom: true,
         })
         .collect();
-    custom_resolved.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    custom_resolved.sort_by_key(|c| c.name.to_lowercase());
 
     builtin.append(&mut custom_resolved);
     Ok(builtin)
 }
 
-/// Filter resolved commands by query (case-insensitive match on name or description).
+/// Filter resolved commands by query (fuzzy match on name or description), best first.
 pub fn filter_commands_resolved<'a>(
     commands: &'a [ResolvedCommand],
     query: &str,
@@ -121,8 +134,23 @@ pub fn filter_commands_resolved<'a>(
     })
 }
 
+/// Filter resolved commands for the text typed after "/". Once an argument follows the
+/// command name (e.g. "pin src/main.rs"), only the command with that exact name matches.
+pub fn filter_commands_for_input<'a>(
+    commands: &'a [ResolvedCommand],
+    query: &str,
+) -> Vec<&'a ResolvedCommand> {
+    match query.split_once(char::is_whitespace) {
+        Some((name, _)) => commands
+            .iter()
+            .filter(|c| c.name.eq_ignore_ascii_case(name))
+            .collect(),
+        None => filter_commands_resolved(commands, query),
+    }
+}
+
 /// Filter built-in commands by the query (everything after "/" in user input).
-/// Returns commands whose name or description match (case-insensitive).
+/// Returns commands whose name or description fuzzy-match, best first.
 #[allow(dead_code)]
 pub fn filter_commands(query: &str) -> Vec<&'static BuiltinCommand> {
     filter_by_query(builtin_commands(), query, |c| {
@@ -134,7 +162,10 @@ pub fn filter_commands(query: &str) -> Vec<&'static BuiltinCommand> {
 mod tests {
     use crate::core::templates::CustomTemplate;
 
-    use super::{builtin_commands, filter_commands, filter_commands_resolved, resolve_commands};
+    use super::{
+        builtin_commands, filter_commands, filter_commands_for_input, filter_commands_resolved,
+        resolve_commands,
+    };
 
     #[test]
     fn filter_empty_returns_all() {
@@ -180,6 +211,8 @@ mod tests {
             description: "Audit".to_string(),
             prompt_prefix: "Check".to_string(),
             mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
         }];
         let resolved = resolve_commands(custom).unwrap();
         assert!(resolved.len() > builtin_commands().len());
@@ -195,6 +228,16 @@ mod tests {
         assert!(out.iter().any(|c| c.name == "test"));
     }
 
+    #[test]
+    fn filter_commands_for_input_matches_exact_name_once_argument_typed() {
+        let commands = resolve_commands(vec![]).unwrap();
+        let out = filter_commands_for_input(&commands, "pin src/main.rs");
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].name, "pin");
+        assert!(filter_commands_for_input(&commands, "pi src/main.rs").is_empty());
+        assert!(filter_commands_for_input(&commands, "pi").len() > 1);
+    }
+
     #[test]
     fn resolve_commands_empty_custom_returns_builtins_only() {
         let resolved = resolve_commands(vec![]).unwrap();
@@ -210,12 +253,16 @@ mod tests {
                 description: "A".to_string(),
                 prompt_prefix: "x".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             CustomTemplate {
                 name: "omega".to_string(),
                 description: "Z".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Build".to_string(),
+                model: None,
+                output_schema: None,
             },
         ];
         let resolved = resolve_commands(custom).unwrap();
@@ -232,12 +279,16 @@ mod tests {
                 description: "Z".to_string(),
                 prompt_prefix: "x".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             CustomTemplate {
                 name: "alpha".to_string(),
                 description: "A".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Build".to_string(),
+                model: None,
+                output_schema: None,
             },
         ];
         let resolved = resolve_commands(custom).unwrap();
@@ -252,6 +303,8 @@ mod tests {
             description: "Hidden".to_string(),
             prompt_prefix: "x".to_string(),
             mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
         }];
         let commands = resolve_commands(custom).unwrap();
         let out = filter_commands_resolved(&commands, "");
@@ -265,6 +318,8 @@ mod tests {
             description: "Hidden".to_string(),
             prompt_prefix: "x".to_string(),
             mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
         }];
         let commands = resolve_commands(custom).unwrap();
         let out = filter_commands_resolved(&commands, "secret");
@@ -279,6 +334,8 @@ mod tests {
             description: "Hidden audit".to_string(),
             prompt_prefix: "x".to_string(),
             mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
         }];
         let commands = resolve_commands(custom).unwrap();
         let out = filter_commands_resolved(&commands, "audit");
@@ -1,39 +1,269 @@
 use std::env;
+use std::path::Path;
+use std::time::Duration;
 
+use async_openai::Client;
 use async_openai::config::OpenAIConfig;
+use serde_json::{Map, Value};
 
+use crate::core::generation::{self, GenerationParams, ParamError};
+use crate::core::history::Retention;
+use crate::core::llm::{AnthropicClient, ChatClient, PromptLayout, SectionId};
+use crate::core::network::{NetworkError, NetworkOptions};
 use crate::core::persistence;
+use crate::core::profiles::{self, Profile};
+use crate::core::spend::SpendLimits;
+use crate::core::tools::ToolTimeouts;
+use crate::core::{app, desktop, paths};
 
 /// Represents the configuration for the AI chat application.
 ///
 /// # Fields
 /// * `openai_config`: Configuration for OpenAI/OpenRouter API interactions
 /// * `model_id`: ID of the selected AI model
+/// * `model_source`: Where `model_id` came from
 /// * `base_url`: Base URL for the AI service API
 /// * `api_key`: Authentication API key for the service
 /// * `max_conversations`: Maximum number of conversations to retain
+/// * `history_retention`: Age and per-workspace limits of the history (see `core::history::Retention`)
 /// * `show_timestamps`: Whether to show timestamps next to messages in the TUI
+/// * `show_usage`: Whether to show token usage and latency under assistant messages in the TUI
+/// * `streaming`: Whether to stream responses (false = single non-streaming request per turn)
+/// * `prompt_cache`: Whether to mark the system prompt for prompt caching (see `core::llm::cache`)
+/// * `auto_continue`: Requests sent to continue a reply cut off by the output token limit
+/// * `text_tools`: Describe tools in the prompt for models without tool calling (see `core::llm::ToolProtocol`)
+/// * `disabled_tools`: Tool names disabled globally (never offered to the model)
+/// * `webhook_urls`: URLs notified of conversation events (see `core::notify`)
+/// * `post_hooks`: Scripts run on each reply before it is displayed and saved (see `core::hooks`)
+/// * `desktop_notify_after`: Turn length after which an unfocused TUI sends a desktop notification (see `core::desktop`)
+/// * `prompt_layout`: System prompt sections to send, in order (see `core::llm::SectionId`)
+/// * `spend_limits`: Optional session, daily, and weekly spend caps (see `core::spend`)
+/// * `embedding_model`: Model embedding the workspace for SemanticSearch (see `core::index`)
+/// * `title_model`: Model generating conversation titles, or None when disabled
+/// * `provider`: Backend serving the models (OpenRouter or a local Ollama server)
+/// * `denied_paths`: Path patterns file tools may never read or write (see `core::permissions`)
+/// * `redact_tool_output`: Whether secrets are masked in tool results sent to the model (see `core::redact`)
+/// * `redact_patterns`: Extra regular expressions masked in tool results
+/// * `tool_timeouts`: Per-tool time limits from the workspace settings (see `core::tools::ToolTimeouts`)
+/// * `gist_token`: GitHub token used by `/share` to upload gists
+/// * `remote`: SSH host (`HOST[:PATH]`) the workspace lives on (see `core::remote`)
+/// * `openrouter`: Provider routing, transforms, and attribution headers sent to OpenRouter
+/// * `generation`: Default temperature, max_tokens, top_p, and stop sequences (see `core::generation`)
+/// * `profile`: Name of the profile the settings come from, if any (see `core::profiles`)
+/// * `theme`: Code block highlighting theme set by the profile (None: the default theme)
+/// * `network`: Proxy and TLS options of the HTTP clients (see `core::network`)
+/// * `dry_run`: Whether conversations start in dry-run mode (`--dry-run`; see `core::dry_run`)
 #[derive(Debug, Clone)]
 pub struct Config {
     pub openai_config: OpenAIConfig,
     pub model_id: String,
+    pub model_source: ModelSource,
     pub base_url: String,
     pub api_key: String,
     pub max_conversations: u32,
+    pub history_retention: Retention,
     pub show_timestamps: bool,
+    pub show_usage: bool,
+    pub streaming: bool,
+    pub prompt_cache: bool,
+    pub auto_continue: u32,
+    pub text_tools: bool,
+    pub disabled_tools: Vec<String>,
+    pub webhook_urls: Vec<String>,
+    pub post_hooks: Vec<String>,
+    pub desktop_notify_after: Option<Duration>,
+    pub prompt_layout: PromptLayout,
+    pub spend_limits: SpendLimits,
+    pub embedding_model: Option<String>,
+    pub title_model: Option<String>,
+    pub provider: Provider,
+    pub denied_paths: Vec<String>,
+    pub redact_tool_output: bool,
+    pub redact_patterns: Vec<String>,
+    pub tool_timeouts: ToolTimeouts,
+    /// GitHub token for `/share` gists (`MY_OPEN_CLAUDE_GIST_TOKEN`).
+    pub gist_token: Option<String>,
+    /// Remote workspace, `HOST[:PATH]` (`MY_OPEN_CLAUDE_REMOTE`; `--remote` overrides it).
+    pub remote: Option<String>,
+    pub openrouter: OpenRouterOptions,
+    pub generation: GenerationParams,
+    pub profile: Option<String>,
+    pub theme: Option<String>,
+    pub network: NetworkOptions,
+    pub dry_run: bool,
+}
+
+/// OpenRouter request options: routing preferences and transforms added to every chat
+/// request body, and the app attribution headers. Empty with Ollama.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenRouterOptions {
+    /// `provider` preferences (`order`, `allow_fallbacks`, `data_collection`, ...).
+    pub provider: Map<String, Value>,
+    /// `transforms` (e.g. `middle-out`); an empty list turns off OpenRouter's defaults.
+    pub transforms: Option<Vec<String>>,
+    /// `HTTP-Referer` attribution header.
+    pub referer: Option<String>,
+    /// `X-Title` attribution header.
+    pub title: Option<String>,
+}
+
+impl OpenRouterOptions {
+    /// Add the routing preferences and transforms to a chat completion request body.
+    pub fn apply(&self, body: &mut Value) {
+        if !self.provider.is_empty() {
+            body["provider"] = Value::Object(self.provider.clone());
+        }
+        if let Some(ref transforms) = self.transforms {
+            body["transforms"] = serde_json::json!(transforms);
+        }
+    }
+
+    /// Attribution headers, as (name, value) pairs.
+    fn headers(&self) -> Vec<(&'static str, &str)> {
+        [("HTTP-Referer", &self.referer), ("X-Title", &self.title)]
+            .into_iter()
+            .filter_map(|(name, value)| value.as_deref().map(|v| (name, v)))
+            .collect()
+    }
+}
+
+/// Backend serving the models. OpenRouter and Ollama are reached through an
+/// OpenAI-compatible chat API; Ollama runs locally, needs no API key, and lists the installed
+/// models. Anthropic is reached through its own Messages API (see `ChatClient`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenRouter,
+    Ollama,
+    Anthropic,
+}
+
+impl Provider {
+    /// Provider from `MY_OPEN_CLAUDE_PROVIDER` ("openrouter", "ollama", or "anthropic");
+    /// OpenRouter when unset.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match env::var("MY_OPEN_CLAUDE_PROVIDER") {
+            Ok(s) => Self::parse(&s),
+            Err(_) => Ok(Provider::OpenRouter),
+        }
+    }
+
+    /// Provider named "openrouter", "ollama", or "anthropic" (any case); OpenRouter when blank.
+    fn parse(name: &str) -> Result<Self, ConfigError> {
+        match name.trim() {
+            s if s.eq_ignore_ascii_case("ollama") => Ok(Provider::Ollama),
+            s if s.eq_ignore_ascii_case("anthropic") => Ok(Provider::Anthropic),
+            s if s.is_empty() || s.eq_ignore_ascii_case("openrouter") => Ok(Provider::OpenRouter),
+            _ => Err(ConfigError::UnknownProvider(name.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provider::OpenRouter => write!(f, "OpenRouter"),
+            Provider::Ollama => write!(f, "Ollama"),
+            Provider::Anthropic => write!(f, "Anthropic"),
+        }
+    }
+}
+
+/// Where the effective model ID came from, in increasing precedence for startup resolution:
+/// default < `OPENROUTER_MODEL` < last used model < profile < workspace pin < `--model`.
+/// `Command` applies to a single turn started by a slash command with its own model;
+/// `Conversation` is the model restored with a conversation loaded from history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelSource {
+    Default,
+    Env,
+    LastModel,
+    Profile(String),
+    Workspace,
+    Flag,
+    Command(String),
+    Conversation,
+}
+
+impl ModelSource {
+    /// Short label for the TUI header.
+    pub fn label(&self) -> String {
+        match self {
+            ModelSource::Default => "default".to_string(),
+            ModelSource::Env => "env".to_string(),
+            ModelSource::LastModel => "last used".to_string(),
+            ModelSource::Profile(name) => format!("profile {}", name),
+            ModelSource::Workspace => "workspace".to_string(),
+            ModelSource::Flag => "--model".to_string(),
+            ModelSource::Command(name) => format!("/{}", name),
+            ModelSource::Conversation => "conversation".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ModelSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelSource::Default => write!(f, "default"),
+            ModelSource::Env => write!(f, "from OPENROUTER_MODEL"),
+            ModelSource::LastModel => write!(f, "from last_model"),
+            ModelSource::Profile(name) => write!(f, "from profile '{}'", name),
+            ModelSource::Workspace => write!(f, "pinned in .my-open-claude/settings.json"),
+            ModelSource::Flag => write!(f, "from --model"),
+            ModelSource::Command(name) => write!(f, "from /{}", name),
+            ModelSource::Conversation => write!(f, "from the loaded conversation"),
+        }
+    }
 }
 
 /// Errors that can occur during configuration loading.
 #[derive(Debug)]
 pub enum ConfigError {
-    /// Indicates that the required API key is missing from environment variables
-    MissingApiKey,
+    /// The provider's API key variable (`OPENROUTER_API_KEY`, `ANTHROPIC_API_KEY`) is not set
+    MissingApiKey(&'static str),
+    /// The environment variable the profile takes its API key from is not set
+    MissingProfileApiKey { profile: String, var: String },
+    /// `MY_OPEN_CLAUDE_PROMPT_SECTIONS` names a section that does not exist
+    UnknownPromptSection(String),
+    /// `MY_OPEN_CLAUDE_PROVIDER` is not "openrouter", "ollama", or "anthropic"
+    UnknownProvider(String),
+    /// `MY_OPEN_CLAUDE_PROVIDER_ROUTING` is not a JSON object, or a header value is invalid
+    InvalidOpenRouterOption(String),
+    /// The proxy URL or the CA bundle cannot be used
+    InvalidNetworkOption(NetworkError),
+    /// A pattern of `MY_OPEN_CLAUDE_REDACT_PATTERNS` or the profile is not a valid regex
+    InvalidRedactPattern { pattern: String, message: String },
 }
 
 impl std::fmt::Display for ConfigError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ConfigError::MissingApiKey => write!(f, "OPENROUTER_API_KEY is not set"),
+            ConfigError::MissingApiKey(var) => write!(f, "{} is not set", var),
+            ConfigError::MissingProfileApiKey { profile, var } => write!(
+                f,
+                "{} is not set (the API key of profile '{}')",
+                var, profile
+            ),
+            ConfigError::UnknownPromptSection(key) => {
+                let keys: Vec<&str> = SectionId::ALL.iter().map(|id| id.key()).collect();
+                write!(
+                    f,
+                    "Unknown prompt section '{}' in MY_OPEN_CLAUDE_PROMPT_SECTIONS (expected: {})",
+                    key,
+                    keys.join(", ")
+                )
+            }
+            ConfigError::UnknownProvider(name) => write!(
+                f,
+                "Unknown provider '{}' in MY_OPEN_CLAUDE_PROVIDER or the profile (expected: openrouter, ollama, anthropic)",
+                name
+            ),
+            ConfigError::InvalidOpenRouterOption(msg) => write!(f, "{}", msg),
+            ConfigError::InvalidNetworkOption(e) => write!(f, "{}", e),
+            ConfigError::InvalidRedactPattern { pattern, message } => write!(
+                f,
+                "Invalid redaction pattern '{}' in MY_OPEN_CLAUDE_REDACT_PATTERNS or the profile: {}",
+                pattern, message
+            ),
         }
     }
 }
@@ -46,15 +276,71 @@ impl Config {
         &self.api_key
     }
 
+    /// Credentials to scrub from text that leaves the machine (crash bundles, `/share`).
+    pub fn secrets(&self) -> Vec<String> {
+        let mut secrets = Vec::new();
+        // Ollama's placeholder key is not a secret (and scrubbing it would mangle the word).
+        if self.provider != Provider::Ollama && !self.api_key.trim().is_empty() {
+            secrets.push(self.api_key.trim().to_string());
+        }
+        secrets.extend(self.gist_token.clone());
+        secrets
+    }
+
     /// Returns the base URL for the AI service.
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    /// Chat API client of the provider, through the configured proxy and with the configured
+    /// TLS options.
+    pub fn chat_client(&self) -> ChatClient {
+        let http = self.network.client().unwrap_or_else(|e| {
+            log::warn!("Ignoring the network options: {}", e);
+            reqwest::Client::new()
+        });
+        match self.provider {
+            Provider::Anthropic => ChatClient::Anthropic(AnthropicClient::new(
+                http,
+                &self.base_url,
+                &self.api_key,
+                self.prompt_cache,
+            )),
+            Provider::OpenRouter | Provider::Ollama => ChatClient::OpenAi(Box::new(
+                Client::with_config(self.openai_config.clone()).with_http_client(http),
+            )),
+        }
+    }
+
+    /// Root URL of the Ollama server (the chat API lives under `/v1`).
+    pub fn ollama_host(&self) -> &str {
+        self.base_url.trim_end_matches("/v1")
+    }
 }
 
 /// Default AI model to use if no model is specified
 const DEFAULT_MODEL: &str = "anthropic/claude-haiku-4.5";
 
+/// Default model with Ollama: small, tool-capable, and commonly installed.
+const DEFAULT_OLLAMA_MODEL: &str = "llama3.1";
+
+/// Default model with the Anthropic API (also its title model): the default model above,
+/// under its Anthropic ID.
+const DEFAULT_ANTHROPIC_MODEL: &str = "claude-haiku-4-5";
+
+/// Default Anthropic API root (the Messages API lives under `/v1`).
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Paths denied to file tools unless `MY_OPEN_CLAUDE_DENIED_PATHS` says otherwise: keys,
+/// cloud credentials, and dotenv files.
+const DEFAULT_DENIED_PATHS: &[&str] = &["~/.ssh", "~/.gnupg", "~/.aws", ".env"];
+
+/// Default address of a local Ollama server.
+const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+
+/// Default model for conversation titles: cheap and fast, titles are a few words.
+const DEFAULT_TITLE_MODEL: &str = "openai/gpt-4o-mini";
+
 /// Load configuration from environment variables and persistent storage.
 ///
 /// # Configuration Resolution Order
@@ -62,28 +348,106 @@ const DEFAULT_MODEL: &str = "anthropic/claude-haiku-4.5";
 /// 2. OPENROUTER_MODEL environment variable
 /// 3. Default model
 ///
+/// A workspace pin (`apply_workspace_model`) and `--model` are applied on top by the caller.
+///
+/// The active profile (see `core::profiles`) replaces the variables for the fields it sets:
+/// provider, base URL, API key variable, model, disabled tools, and denied paths.
+///
 /// # Environment Variables
+/// * `MY_OPEN_CLAUDE_PROVIDER`: "openrouter" (default), "ollama", or "anthropic" (optional)
 /// * `OPENROUTER_BASE_URL`: Custom base URL for AI service (optional)
-/// * `OPENROUTER_API_KEY`: Required API key
+/// * `OPENROUTER_API_KEY`: Required API key (not used with Ollama)
 /// * `OPENROUTER_MODEL`: Preferred model (optional)
+/// * `OLLAMA_HOST`: Ollama server address, default "http://localhost:11434" (optional)
+/// * `ANTHROPIC_API_KEY`: API key with the Anthropic provider (required there)
+/// * `ANTHROPIC_BASE_URL`: Anthropic API root, default "https://api.anthropic.com" (optional)
 /// * `MY_OPEN_CLAUDE_MAX_CONVERSATIONS`: Maximum conversations to retain (optional)
+/// * `MY_OPEN_CLAUDE_MAX_CONVERSATION_AGE_DAYS`: Days after their last update that conversations are pruned (optional)
+/// * `MY_OPEN_CLAUDE_MAX_CONVERSATIONS_PER_WORKSPACE`: Maximum conversations retained per workspace (optional)
 /// * `MY_OPEN_CLAUDE_SHOW_TIMESTAMPS`: Set to 1 or true to show timestamps next to messages (optional)
+/// * `MY_OPEN_CLAUDE_STREAMING`: Set to 0 or false to disable streaming (optional)
+/// * `MY_OPEN_CLAUDE_PROMPT_CACHE`: Set to 0 or false to stop marking the system prompt for prompt caching (optional)
+/// * `MY_OPEN_CLAUDE_AUTO_CONTINUE`: Times a reply cut off by the token limit is continued automatically, default 0 (optional)
+/// * `MY_OPEN_CLAUDE_TEXT_TOOLS`: Set to 0 or false to chat without tools with models lacking tool calling (optional)
+/// * `MY_OPEN_CLAUDE_DISABLED_TOOLS`: Comma-separated tool names to disable, e.g. "Bash,Write" (optional)
+/// * `MY_OPEN_CLAUDE_WEBHOOK_URLS`: Comma-separated URLs receiving JSON event payloads (optional)
+/// * `MY_OPEN_CLAUDE_DESKTOP_NOTIFY`: Seconds after which a turn ending in the unfocused TUI sends a desktop notification, or "on" for 30 (optional)
+/// * `MY_OPEN_CLAUDE_PROMPT_SECTIONS`: Comma-separated system prompt sections, in order (optional)
+/// * `MY_OPEN_CLAUDE_CUSTOM_INSTRUCTIONS`: Text of the custom system prompt section (optional)
+/// * `MY_OPEN_CLAUDE_SESSION_SPEND_LIMIT`: Spend cap of one session in credits (USD) (optional)
+/// * `MY_OPEN_CLAUDE_DAILY_SPEND_LIMIT`: Daily spend cap in credits (USD), e.g. "5" (optional)
+/// * `MY_OPEN_CLAUDE_WEEKLY_SPEND_LIMIT`: Weekly spend cap in credits (USD), from Monday (optional)
+/// * `MY_OPEN_CLAUDE_EMBEDDING_MODEL`: Embedding model enabling SemanticSearch, e.g. "openai/text-embedding-3-small" (optional)
+/// * `MY_OPEN_CLAUDE_TITLE_MODEL`: Model generating conversation titles; 0 or false to disable (optional)
+/// * `MY_OPEN_CLAUDE_DENIED_PATHS`: Comma-separated path patterns denied to file tools (optional)
+/// * `MY_OPEN_CLAUDE_REDACT`: Set to 0 or false to send tool results without masking secrets (optional)
+/// * `MY_OPEN_CLAUDE_REDACT_PATTERNS`: Space-separated regexes also masked in tool results (optional)
+/// * `MY_OPEN_CLAUDE_GIST_TOKEN`: GitHub token for `/share` gists (optional)
+/// * `MY_OPEN_CLAUDE_REMOTE`: Remote workspace over SSH, `HOST[:PATH]` (optional)
+/// * `MY_OPEN_CLAUDE_PROVIDER_ROUTING`: OpenRouter `provider` preferences as a JSON object (optional)
+/// * `MY_OPEN_CLAUDE_PROVIDER_ORDER`: Comma-separated providers to try first, e.g. "anthropic,openai" (optional)
+/// * `MY_OPEN_CLAUDE_ALLOW_FALLBACKS`: Set to 0 or false to use only the listed providers (optional)
+/// * `MY_OPEN_CLAUDE_TRANSFORMS`: Comma-separated OpenRouter transforms, e.g. "middle-out" (optional)
+/// * `MY_OPEN_CLAUDE_APP_URL` / `MY_OPEN_CLAUDE_APP_TITLE`: Attribution headers; empty to omit (optional)
+/// * `MY_OPEN_CLAUDE_TEMPERATURE`: Default sampling temperature, 0 to 2 (optional)
+/// * `MY_OPEN_CLAUDE_MAX_TOKENS`: Default maximum tokens per reply (optional)
+/// * `MY_OPEN_CLAUDE_TOP_P`: Default nucleus sampling, 0 to 1 (optional)
+/// * `MY_OPEN_CLAUDE_STOP`: Default comma-separated stop sequences, `\n` for a newline (optional)
+/// * `MY_OPEN_CLAUDE_PROXY`: Proxy URL for every request, over `HTTPS_PROXY` and friends (optional)
+/// * `MY_OPEN_CLAUDE_CA_BUNDLE`: PEM file of extra trusted root certificates (optional)
+/// * `MY_OPEN_CLAUDE_INSECURE_TLS`: Set to 1 or true to skip certificate verification (optional)
 ///
 /// # Returns
 /// A `Result` containing the loaded `Config` or a `ConfigError`
 pub fn load() -> Result<Config, ConfigError> {
-    // Determine base URL, defaulting to OpenRouter's API
-    let base_url = env::var("OPENROUTER_BASE_URL")
-        .unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string());
+    let profile = profiles::active().unwrap_or_default();
+    let provider = match &profile.provider {
+        Some(name) => Provider::parse(name)?,
+        None => Provider::from_env()?,
+    };
 
-    // Require API key
-    let api_key = env::var("OPENROUTER_API_KEY").map_err(|_| ConfigError::MissingApiKey)?;
+    // Determine base URL and API key: OpenRouter's API and a required key, or the local
+    // Ollama server's OpenAI-compatible API (which ignores the key)
+    let (base_url, api_key) = match provider {
+        Provider::OpenRouter => (
+            profile
+                .base_url
+                .clone()
+                .or_else(|| env::var("OPENROUTER_BASE_URL").ok())
+                .unwrap_or_else(|| "https://openrouter.ai/api/v1".to_string()),
+            profile_api_key(&profile, "OPENROUTER_API_KEY")?,
+        ),
+        Provider::Ollama => (
+            format!(
+                "{}/v1",
+                ollama_host(
+                    profile
+                        .base_url
+                        .clone()
+                        .or_else(|| env::var("OLLAMA_HOST").ok())
+                        .as_deref()
+                )
+            ),
+            "ollama".to_string(),
+        ),
+        Provider::Anthropic => (
+            profile
+                .base_url
+                .clone()
+                .or_else(|| env::var("ANTHROPIC_BASE_URL").ok())
+                .unwrap_or_else(|| DEFAULT_ANTHROPIC_BASE_URL.to_string()),
+            profile_api_key(&profile, "ANTHROPIC_API_KEY")?,
+        ),
+    };
 
     // Resolve model selection
-    let model_id = persistence::load_last_model()
-        .or_else(|| env::var("OPENROUTER_MODEL").ok())
-        .filter(|s| !s.is_empty())
-        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let (model_id, model_source) = match &profile.model {
+        Some(model) if !model.trim().is_empty() => (
+            model.trim().to_string(),
+            ModelSource::Profile(profile.name.clone()),
+        ),
+        _ => resolve_model(provider),
+    };
 
     // Configure max conversations, with a sensible default
     const DEFAULT_MAX_CONVERSATIONS: u32 = 50;
@@ -91,22 +455,458 @@ pub fn load() -> Result<Config, ConfigError> {
         .ok()
         .and_then(|s| s.parse::<u32>().ok())
         .unwrap_or(DEFAULT_MAX_CONVERSATIONS);
+    let retention_limit = |name: &str| {
+        env::var(name)
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .unwrap_or(0)
+    };
+    let history_retention = Retention {
+        max_age_days: retention_limit("MY_OPEN_CLAUDE_MAX_CONVERSATION_AGE_DAYS"),
+        max_per_workspace: retention_limit("MY_OPEN_CLAUDE_MAX_CONVERSATIONS_PER_WORKSPACE"),
+    };
 
     let show_timestamps = env::var("MY_OPEN_CLAUDE_SHOW_TIMESTAMPS")
         .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
         .unwrap_or(true);
 
+    let show_usage = env::var("MY_OPEN_CLAUDE_SHOW_USAGE")
+        .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+
+    let streaming = env::var("MY_OPEN_CLAUDE_STREAMING")
+        .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+
+    let prompt_cache = env::var("MY_OPEN_CLAUDE_PROMPT_CACHE")
+        .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+
+    let auto_continue = env::var("MY_OPEN_CLAUDE_AUTO_CONTINUE")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+
+    let text_tools = env::var("MY_OPEN_CLAUDE_TEXT_TOOLS")
+        .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+
+    let disabled_tools = match &profile.disabled_tools {
+        Some(tools) => tools.clone(),
+        None => env::var("MY_OPEN_CLAUDE_DISABLED_TOOLS")
+            .map(|s| parse_list(&s))
+            .unwrap_or_default(),
+    };
+
+    let webhook_urls = env::var("MY_OPEN_CLAUDE_WEBHOOK_URLS")
+        .map(|s| parse_list(&s))
+        .unwrap_or_default();
+
+    let post_hooks = match &profile.post_hooks {
+        Some(hooks) => hooks.clone(),
+        None => env::var("MY_OPEN_CLAUDE_POST_HOOKS")
+            .map(|s| parse_list(&s))
+            .unwrap_or_default(),
+    };
+
+    let desktop_notify_after = env::var("MY_OPEN_CLAUDE_DESKTOP_NOTIFY")
+        .ok()
+        .and_then(|s| desktop::parse_threshold(&s));
+
+    let order = match env::var("MY_OPEN_CLAUDE_PROMPT_SECTIONS") {
+        Ok(list) => PromptLayout::parse_order(&list).map_err(ConfigError::UnknownPromptSection)?,
+        Err(_) => SectionId::ALL.to_vec(),
+    };
+    let prompt_layout = PromptLayout {
+        order,
+        memory_file: paths::config_dir().map(|d| d.join("memory.md")),
+        custom: env::var("MY_OPEN_CLAUDE_CUSTOM_INSTRUCTIONS").ok(),
+    };
+
+    let spend_limits = SpendLimits {
+        session: spend_limit("MY_OPEN_CLAUDE_SESSION_SPEND_LIMIT"),
+        daily: spend_limit("MY_OPEN_CLAUDE_DAILY_SPEND_LIMIT"),
+        weekly: spend_limit("MY_OPEN_CLAUDE_WEEKLY_SPEND_LIMIT"),
+    };
+
+    let embedding_model = env::var("MY_OPEN_CLAUDE_EMBEDDING_MODEL")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let title_model = match env::var("MY_OPEN_CLAUDE_TITLE_MODEL") {
+        Ok(s) if s.trim() == "0" || s.trim().eq_ignore_ascii_case("false") => None,
+        Ok(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        // The default title model is an OpenRouter model; with Ollama, titles are opt-in.
+        _ if provider == Provider::Ollama => None,
+        _ if provider == Provider::Anthropic => Some(DEFAULT_ANTHROPIC_MODEL.to_string()),
+        _ => Some(DEFAULT_TITLE_MODEL.to_string()),
+    };
+
+    let denied_paths = match (
+        &profile.denied_paths,
+        env::var("MY_OPEN_CLAUDE_DENIED_PATHS"),
+    ) {
+        (Some(paths), _) => paths.clone(),
+        (None, Ok(s)) => parse_list(&s),
+        (None, Err(_)) => DEFAULT_DENIED_PATHS.iter().map(|p| p.to_string()).collect(),
+    };
+
+    let redact_tool_output = env::var("MY_OPEN_CLAUDE_REDACT")
+        .map(|s| s.trim() != "0" && !s.trim().eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+    let redact_patterns = match &profile.redact_patterns {
+        Some(patterns) => patterns.clone(),
+        None => env::var("MY_OPEN_CLAUDE_REDACT_PATTERNS")
+            .map(|s| s.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+    };
+    // A pattern that silently matches nothing would leak what it was meant to mask.
+    if let Some((pattern, e)) = redact_patterns
+        .iter()
+        .find_map(|p| regex::Regex::new(p).err().map(|e| (p, e)))
+    {
+        return Err(ConfigError::InvalidRedactPattern {
+            pattern: pattern.clone(),
+            message: e.to_string(),
+        });
+    }
+
+    let gist_token = env::var("MY_OPEN_CLAUDE_GIST_TOKEN")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let remote = env::var("MY_OPEN_CLAUDE_REMOTE")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let openrouter = match provider {
+        Provider::OpenRouter => openrouter_options()?,
+        Provider::Ollama | Provider::Anthropic => OpenRouterOptions::default(),
+    };
+
+    let generation = generation_defaults();
+
+    let network = NetworkOptions::from_env();
+    // Fail here, with the variable to fix, rather than on the first request.
+    network
+        .client()
+        .map_err(ConfigError::InvalidNetworkOption)?;
+    if network.insecure_tls {
+        log::warn!("MY_OPEN_CLAUDE_INSECURE_TLS is set: server certificates are not verified");
+    }
+
     // Create OpenAI/OpenRouter configuration
-    let openai_config = OpenAIConfig::new()
+    let mut openai_config = OpenAIConfig::new()
         .with_api_base(&base_url)
         .with_api_key(&api_key);
+    for (name, value) in openrouter.headers() {
+        openai_config = openai_config.with_header(name, value).map_err(|e| {
+            ConfigError::InvalidOpenRouterOption(format!("Invalid {} header: {}", name, e))
+        })?;
+    }
 
     Ok(Config {
         openai_config,
         model_id,
+        model_source,
         base_url,
         api_key,
         max_conversations,
+        history_retention,
         show_timestamps,
+        show_usage,
+        streaming,
+        prompt_cache,
+        auto_continue,
+        text_tools,
+        disabled_tools,
+        webhook_urls,
+        post_hooks,
+        desktop_notify_after,
+        prompt_layout,
+        spend_limits,
+        embedding_model,
+        title_model,
+        provider,
+        denied_paths,
+        redact_tool_output,
+        redact_patterns,
+        tool_timeouts: ToolTimeouts::default(),
+        gist_token,
+        remote,
+        openrouter,
+        generation,
+        profile: (!profile.name.is_empty()).then(|| profile.name.clone()),
+        theme: profile.theme.clone(),
+        network,
+        dry_run: false,
+    })
+}
+
+/// API key: from the variable the profile names, or `default_var`.
+fn profile_api_key(profile: &Profile, default_var: &'static str) -> Result<String, ConfigError> {
+    match &profile.api_key_env {
+        Some(var) => env::var(var)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| ConfigError::MissingProfileApiKey {
+                profile: profile.name.clone(),
+                var: var.clone(),
+            }),
+        None => env::var(default_var).map_err(|_| ConfigError::MissingApiKey(default_var)),
+    }
+}
+
+/// Model from the last used model (of this provider), `OPENROUTER_MODEL` (OpenRouter only),
+/// or the default (in that order).
+pub fn resolve_model(provider: Provider) -> (String, ModelSource) {
+    if let Some(id) = persistence::load_last_model(provider) {
+        return (id, ModelSource::LastModel);
+    }
+    match provider {
+        Provider::OpenRouter => {
+            if let Ok(id) = env::var("OPENROUTER_MODEL")
+                && !id.is_empty()
+            {
+                return (id, ModelSource::Env);
+            }
+            (DEFAULT_MODEL.to_string(), ModelSource::Default)
+        }
+        Provider::Ollama => (DEFAULT_OLLAMA_MODEL.to_string(), ModelSource::Default),
+        Provider::Anthropic => (DEFAULT_ANTHROPIC_MODEL.to_string(), ModelSource::Default),
+    }
+}
+
+/// Ollama server URL from `OLLAMA_HOST`, which may omit the scheme ("127.0.0.1:11434", as
+/// accepted by the Ollama CLI).
+fn ollama_host(value: Option<&str>) -> String {
+    let host = match value.map(str::trim) {
+        Some(h) if !h.is_empty() => h.trim_end_matches('/'),
+        _ => return DEFAULT_OLLAMA_HOST.to_string(),
+    };
+    if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("http://{}", host)
+    }
+}
+
+/// Apply the model pinned in `<root>/.my-open-claude/settings.json`, if any.
+/// Pins that are not in the models cache are ignored with a warning.
+pub fn apply_workspace_model(config: &mut Config, root: &Path) {
+    let Some(model) = persistence::load_workspace_model(root) else {
+        return;
+    };
+    if let Err(e) = crate::core::models::validate_model_id(&model) {
+        log::warn!("Ignoring workspace model pin: {}", e);
+        return;
+    }
+    config.model_id = model;
+    config.model_source = ModelSource::Workspace;
+}
+
+/// Apply the tool timeouts set in `<root>/.my-open-claude/settings.json`, if any.
+/// Entries for tools that have no time limit are ignored with a warning.
+pub fn apply_workspace_tool_timeouts(config: &mut Config, root: &Path) {
+    let Some(settings) = persistence::load_workspace_settings(root) else {
+        return;
+    };
+    let timeouts = ToolTimeouts::new(settings.tool_timeouts);
+    for name in timeouts.unsupported() {
+        log::warn!(
+            "Ignoring tool_timeouts entry '{}': the tool has no time limit",
+            name
+        );
+    }
+    config.tool_timeouts = timeouts;
+}
+
+/// Positive amount from a spend limit variable; invalid values are ignored with a warning.
+fn spend_limit(var: &str) -> Option<f64> {
+    let value = env::var(var).ok()?;
+    match parse_amount(&value) {
+        Some(amount) => Some(amount),
+        None => {
+            log::warn!("Ignoring {}={:?}: expected a positive amount", var, value);
+            None
+        }
+    }
+}
+
+/// Default generation parameters from the environment (see `load`). Invalid values are
+/// ignored with a warning.
+fn generation_defaults() -> GenerationParams {
+    fn var<T>(name: &str, parse: fn(&str) -> Result<Option<T>, ParamError>) -> Option<T> {
+        let value = env::var(name).ok()?;
+        parse(&value).unwrap_or_else(|e| {
+            log::warn!("Ignoring {}: {}", name, e);
+            None
+        })
+    }
+    GenerationParams {
+        temperature: var("MY_OPEN_CLAUDE_TEMPERATURE", generation::parse_temperature),
+        max_tokens: var("MY_OPEN_CLAUDE_MAX_TOKENS", generation::parse_max_tokens),
+        top_p: var("MY_OPEN_CLAUDE_TOP_P", generation::parse_top_p),
+        stop: var("MY_OPEN_CLAUDE_STOP", generation::parse_stop),
+        response_schema: None,
+    }
+}
+
+/// OpenRouter options from the environment (see `load`). Attribution defaults to the app's
+/// name and repository.
+fn openrouter_options() -> Result<OpenRouterOptions, ConfigError> {
+    let var = |name: &str| env::var(name).ok();
+    let provider = provider_routing(
+        var("MY_OPEN_CLAUDE_PROVIDER_ROUTING").as_deref(),
+        var("MY_OPEN_CLAUDE_PROVIDER_ORDER").as_deref(),
+        var("MY_OPEN_CLAUDE_ALLOW_FALLBACKS").as_deref(),
+    )?;
+    let attribution = |name: &str, default: String| match var(name) {
+        Some(v) if v.trim().is_empty() => None,
+        Some(v) => Some(v.trim().to_string()),
+        None => Some(default),
+    };
+    Ok(OpenRouterOptions {
+        provider,
+        transforms: var("MY_OPEN_CLAUDE_TRANSFORMS").map(|s| parse_list(&s)),
+        referer: attribution(
+            "MY_OPEN_CLAUDE_APP_URL",
+            format!("https://github.com/{}/{}", app::VENDOR, app::NAME),
+        ),
+        title: attribution("MY_OPEN_CLAUDE_APP_TITLE", app::NAME.to_string()),
     })
 }
+
+/// OpenRouter `provider` preferences: the `routing` JSON object, with `order` and
+/// `allow_fallbacks` overridden by their own variables when set.
+fn provider_routing(
+    routing: Option<&str>,
+    order: Option<&str>,
+    allow_fallbacks: Option<&str>,
+) -> Result<Map<String, Value>, ConfigError> {
+    let mut provider = match routing.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(json) => match serde_json::from_str::<Value>(json) {
+            Ok(Value::Object(map)) => map,
+            _ => {
+                return Err(ConfigError::InvalidOpenRouterOption(format!(
+                    "MY_OPEN_CLAUDE_PROVIDER_ROUTING must be a JSON object, e.g. {{\"order\": [\"anthropic\"]}} (got {})",
+                    json
+                )));
+            }
+        },
+        None => Map::new(),
+    };
+    if let Some(order) = order.map(parse_list).filter(|o| !o.is_empty()) {
+        provider.insert("order".to_string(), serde_json::json!(order));
+    }
+    if let Some(allow) = allow_fallbacks.map(str::trim).filter(|s| !s.is_empty()) {
+        let allow = allow != "0" && !allow.eq_ignore_ascii_case("false");
+        provider.insert("allow_fallbacks".to_string(), Value::Bool(allow));
+    }
+    Ok(provider)
+}
+
+/// Parse a positive amount, with or without a leading `$`.
+fn parse_amount(value: &str) -> Option<f64> {
+    let value = value.trim();
+    value
+        .strip_prefix('$')
+        .unwrap_or(value)
+        .parse::<f64>()
+        .ok()
+        .filter(|amount| amount.is_finite() && *amount > 0.0)
+}
+
+/// Parse a comma-separated list (tool names, URLs), ignoring blanks.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ModelSource, OpenRouterOptions, ollama_host, parse_amount, parse_list, provider_routing,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn parse_list_trims_and_skips_blanks() {
+        assert_eq!(parse_list(" Bash, Write ,,"), vec!["Bash", "Write"]);
+        assert!(parse_list("").is_empty());
+    }
+
+    #[test]
+    fn parse_amount_accepts_positive_amounts() {
+        assert_eq!(parse_amount(" 5 "), Some(5.0));
+        assert_eq!(parse_amount("$2.50"), Some(2.5));
+        assert_eq!(parse_amount("0"), None);
+        assert_eq!(parse_amount("-1"), None);
+        assert_eq!(parse_amount("five"), None);
+    }
+
+    #[test]
+    fn ollama_host_adds_scheme_and_default() {
+        assert_eq!(ollama_host(None), "http://localhost:11434");
+        assert_eq!(ollama_host(Some(" ")), "http://localhost:11434");
+        assert_eq!(
+            ollama_host(Some("127.0.0.1:11434")),
+            "http://127.0.0.1:11434"
+        );
+        assert_eq!(
+            ollama_host(Some("https://gpu-box:11434/")),
+            "https://gpu-box:11434"
+        );
+    }
+
+    #[test]
+    fn provider_routing_merges_json_and_overrides() {
+        let provider = provider_routing(
+            Some(r#"{"order": ["openai"], "data_collection": "deny"}"#),
+            Some("anthropic, google-vertex"),
+            Some("false"),
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::Value::Object(provider),
+            json!({
+                "order": ["anthropic", "google-vertex"],
+                "data_collection": "deny",
+                "allow_fallbacks": false,
+            })
+        );
+        assert!(provider_routing(None, None, None).unwrap().is_empty());
+        assert!(provider_routing(Some("[1]"), None, None).is_err());
+        assert!(provider_routing(Some("{oops"), None, None).is_err());
+    }
+
+    #[test]
+    fn openrouter_options_apply_to_request_body() {
+        let mut body = json!({"model": "m"});
+        OpenRouterOptions::default().apply(&mut body);
+        assert_eq!(body, json!({"model": "m"}));
+
+        let options = OpenRouterOptions {
+            provider: provider_routing(None, Some("anthropic"), None).unwrap(),
+            transforms: Some(vec!["middle-out".to_string()]),
+            ..Default::default()
+        };
+        options.apply(&mut body);
+        assert_eq!(body["provider"], json!({"order": ["anthropic"]}));
+        assert_eq!(body["transforms"], json!(["middle-out"]));
+    }
+
+    #[test]
+    fn model_source_labels() {
+        assert_eq!(ModelSource::Workspace.label(), "workspace");
+        assert_eq!(ModelSource::Command("fast".to_string()).label(), "/fast");
+        assert_eq!(ModelSource::LastModel.to_string(), "from last_model");
+    }
+}
@@ -1,21 +1,111 @@
-//! Confirmation of destructive actions (e.g. Bash commands like rm, rmdir).
+//! Confirmation of risky tool calls: destructive Bash commands (e.g. rm, rmdir) and file
+//! writes outside the workspace (see `core::permissions`).
 //! Used by CLI (prompt mode). The TUI uses an in-app popup instead.
 
-/// Callback type for confirming destructive Bash commands.
-/// Receives the command, returns true to run, false to cancel.
+use serde_json::Value;
+
+use crate::core::command_rules::{self, RuleKind};
+
+/// Tool call waiting for the user's approval.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingAction {
+    /// Destructive Bash command.
+    Command(String),
+    /// File tool call writing outside the workspace root; approval covers this call only.
+    OutsideWorkspace {
+        tool: String,
+        path: String,
+        args: Value,
+    },
+}
+
+impl PendingAction {
+    /// Popup title.
+    pub fn title(&self) -> &'static str {
+        match self {
+            PendingAction::Command(_) => "Destructive command",
+            PendingAction::OutsideWorkspace { .. } => "Write outside the workspace",
+        }
+    }
+
+    /// Label and value shown for approval, e.g. ("Command", "rm -rf build").
+    pub fn detail(&self) -> (&str, &str) {
+        match self {
+            PendingAction::Command(command) => ("Command", command),
+            PendingAction::OutsideWorkspace { tool, path, .. } => (tool, path),
+        }
+    }
+
+    /// Name of the tool that runs once approved.
+    pub fn tool_name(&self) -> &str {
+        match self {
+            PendingAction::Command(_) => "Bash",
+            PendingAction::OutsideWorkspace { tool, .. } => tool,
+        }
+    }
+
+    /// Command that "always allow" would add an allow rule for (see `core::command_rules`).
+    /// None for file writes and for chained commands, which allow rules never match.
+    pub fn allowable_command(&self) -> Option<&str> {
+        match self {
+            PendingAction::Command(command) if command_rules::can_allow(command) => Some(command),
+            _ => None,
+        }
+    }
+
+    /// Approve this command and every later identical one for the rest of the session.
+    pub fn allow_always(&self) {
+        if let Some(command) = self.allowable_command() {
+            command_rules::add(RuleKind::Allow, command);
+        }
+    }
+
+    /// Tool result sent to the model when the user declines.
+    pub fn cancelled_message(&self) -> String {
+        match self {
+            PendingAction::Command(_) => {
+                "Command cancelled (destructive command not confirmed).".to_string()
+            }
+            PendingAction::OutsideWorkspace { tool, path, .. } => format!(
+                "{} cancelled: {} is outside the workspace and the user did not approve the change.",
+                tool, path
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for PendingAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PendingAction::Command(command) => write!(f, "{}", command),
+            PendingAction::OutsideWorkspace { tool, path, .. } => write!(f, "{} {}", tool, path),
+        }
+    }
+}
+
+/// Callback type for confirming pending actions.
+/// Receives the action, returns true to run, false to cancel.
 /// Sync required so futures holding &ConfirmDestructive across await points are Send.
-pub type ConfirmDestructive = Box<dyn Fn(&str) -> bool + Send + Sync>;
+pub type ConfirmDestructive = Box<dyn Fn(&PendingAction) -> bool + Send + Sync>;
 
-/// Default implementation: prompt on stderr, read y/N from stdin.
-/// For CLI (prompt mode) where the terminal is already in cooked mode.
+/// Default implementation: prompt on stderr, read y/N from stdin ("a" approves the command
+/// for the rest of the run). For CLI (prompt mode) where the terminal is already in cooked mode.
 pub fn default_confirm() -> ConfirmDestructive {
-    Box::new(|cmd: &str| {
-        eprintln!("⚠ Destructive command: {}", cmd);
-        eprint!("Confirm? [y/N] ");
+    Box::new(|action: &PendingAction| {
+        eprintln!("⚠ {}: {}", action.title(), action);
+        if action.allowable_command().is_some() {
+            eprint!("Confirm? [y/N/a=always] ");
+        } else {
+            eprint!("Confirm? [y/N] ");
+        }
         let _ = std::io::Write::flush(&mut std::io::stderr());
         let mut s = String::new();
         let _ = std::io::stdin().read_line(&mut s);
         let t = s.trim();
+        if t.eq_ignore_ascii_case("a") || t.eq_ignore_ascii_case("always") {
+            action.allow_always();
+            return action.allowable_command().is_some();
+        }
         t.eq_ignore_ascii_case("y") || t.eq_ignore_ascii_case("yes")
     })
 }
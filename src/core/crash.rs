@@ -0,0 +1,333 @@
+//! Crash reports: on panic or fatal error, a diagnostics bundle is written to
+//! `<cache>/crashes/` and its path printed. `my-open-claude report` opens a prefilled
+//! GitHub issue from the latest bundle.
+//!
+//! A bundle holds the version, OS, terminal, a redacted config summary, the latest trace
+//! events, and the last log lines. Secrets (the API key, bearer tokens, `sk-` keys, URL
+//! credentials) and the home directory are redacted from every section.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::core::config::Config;
+use crate::core::redact::redact;
+use crate::core::{app, paths, trace};
+
+/// Log lines included in a bundle.
+const LOG_TAIL_LINES: usize = 200;
+/// Trace events included in a bundle.
+const TRACE_EVENTS: usize = 30;
+/// Bundles kept in the crashes directory; older ones are removed when a new one is written.
+const MAX_BUNDLES: usize = 10;
+/// Longest issue body put in the URL (browsers and GitHub reject very long URLs).
+const ISSUE_BODY_MAX_CHARS: usize = 6_000;
+
+/// Redacted config summary and secrets, set once the config is loaded.
+struct Context {
+    config_summary: Option<String>,
+    secrets: Vec<String>,
+}
+
+static CONTEXT: Mutex<Context> = Mutex::new(Context {
+    config_summary: None,
+    secrets: Vec::new(),
+});
+
+/// Bundle written by this process, if any (the TUI prints it after restoring the terminal).
+static LAST_BUNDLE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Remember the config for crash bundles: a redacted summary, plus the secrets to scrub.
+pub fn set_config(config: &Config) {
+    let mut ctx = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    ctx.config_summary = Some(config_summary(config));
+    ctx.secrets = config.secrets();
+}
+
+fn config_summary(config: &Config) -> String {
+    let disabled = if config.disabled_tools.is_empty() {
+        "none".to_string()
+    } else {
+        config.disabled_tools.join(", ")
+    };
+    format!(
+        "- Provider: {}\n\
+         - Model: {} ({})\n\
+         - Base URL: {}\n\
+         - API key: {}\n\
+         - Streaming: {}\n\
+         - Timestamps: {}\n\
+         - Usage annotations: {}\n\
+         - Max conversations: {}\n\
+         - Disabled tools: {}\n\
+         - Webhooks: {} configured\n\
+         - Post hooks: {} configured\n\
+         - Prompt sections: {}\n\
+         - Spend caps: {}\n\
+         - Embedding model: {}\n\
+         - Title model: {}\n\
+         - Denied paths: {}\n",
+        config.provider,
+        config.model_id,
+        config.model_source,
+        config.base_url,
+        if config.api_key.trim().is_empty() {
+            "not set"
+        } else {
+            "set"
+        },
+        config.streaming,
+        config.show_timestamps,
+        config.show_usage,
+        config.max_conversations,
+        disabled,
+        config.webhook_urls.len(),
+        config.post_hooks.len(),
+        config
+            .prompt_layout
+            .order
+            .iter()
+            .map(|id| id.key())
+            .collect::<Vec<_>>()
+            .join(", "),
+        config.spend_limits,
+        config.embedding_model.as_deref().unwrap_or("none"),
+        config.title_model.as_deref().unwrap_or("off"),
+        if config.denied_paths.is_empty() {
+            "none".to_string()
+        } else {
+            config.denied_paths.join(", ")
+        },
+    )
+}
+
+/// Install a panic hook that writes a bundle after the default panic output.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        let location = info
+            .location()
+            .map(|l| format!(" at {}:{}", l.file(), l.line()))
+            .unwrap_or_default();
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        let details = format!(
+            "thread '{}' panicked{}: {}\n\n```\n{}\n```",
+            thread,
+            location,
+            message,
+            std::backtrace::Backtrace::force_capture()
+        );
+        if let Some(path) = write_bundle("panic", &details) {
+            eprintln!("{}", notice(&path));
+        }
+    }));
+}
+
+/// Write a bundle for an error that ends the program, and print its path.
+pub fn report_fatal(error: &str) {
+    if let Some(path) = write_bundle("fatal error", error) {
+        eprintln!("{}", notice(&path));
+    }
+}
+
+/// One-line message pointing at a bundle.
+pub fn notice(path: &Path) -> String {
+    format!(
+        "Crash report written to {} — run `{} report` to open a GitHub issue with it.",
+        path.display(),
+        app::NAME
+    )
+}
+
+/// Bundle written by this process, if any.
+pub fn last_bundle() -> Option<PathBuf> {
+    LAST_BUNDLE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+fn crashes_dir() -> Option<PathBuf> {
+    paths::cache_dir().map(|d| d.join("crashes"))
+}
+
+/// Most recent bundle in the crashes directory.
+pub fn latest_bundle() -> Option<PathBuf> {
+    bundle_files(&crashes_dir()?).pop()
+}
+
+/// Bundle files in `dir`, oldest first (names start with a sortable timestamp).
+fn bundle_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "md"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Write a bundle for `kind` ("panic", "fatal error") with `details`. Returns its path.
+pub fn write_bundle(kind: &str, details: &str) -> Option<PathBuf> {
+    let dir = crashes_dir()?;
+    fs::create_dir_all(&dir).ok()?;
+    for old in bundle_files(&dir).into_iter().rev().skip(MAX_BUNDLES - 1) {
+        let _ = fs::remove_file(old);
+    }
+    let path = dir.join(format!(
+        "crash-{}-{}.md",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    let (config_summary, secrets) = {
+        let ctx = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+        (ctx.config_summary.clone(), ctx.secrets.clone())
+    };
+    let text = bundle_text(kind, details, config_summary.as_deref(), &log_tail());
+    fs::write(&path, redact(&text, &secrets)).ok()?;
+    *LAST_BUNDLE.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.clone());
+    Some(path)
+}
+
+fn terminal_description() -> String {
+    let vars: Vec<String> = ["TERM", "TERM_PROGRAM", "TERM_PROGRAM_VERSION", "COLORTERM"]
+        .iter()
+        .filter_map(|k| std::env::var(k).ok().map(|v| format!("{}={}", k, v)))
+        .collect();
+    if vars.is_empty() {
+        "unknown".to_string()
+    } else {
+        vars.join(", ")
+    }
+}
+
+/// Last `LOG_TAIL_LINES` lines of the log file, if there is one.
+fn log_tail() -> Option<String> {
+    let content = fs::read_to_string(paths::log_file()?).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(LOG_TAIL_LINES);
+    Some(lines[start..].join("\n"))
+}
+
+fn bundle_text(kind: &str, details: &str, config: Option<&str>, log: &Option<String>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {} crash report\n", app::NAME);
+    let _ = writeln!(out, "- Version: {}", app::VERSION);
+    let _ = writeln!(
+        out,
+        "- Time: {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S %z")
+    );
+    let _ = writeln!(
+        out,
+        "- OS: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(out, "- Terminal: {}\n", terminal_description());
+    let _ = writeln!(out, "## Error ({})\n\n{}\n", kind, details.trim_end());
+    let _ = writeln!(out, "## Config\n\n{}", config.unwrap_or("(not loaded)\n"));
+    let _ = writeln!(out, "## Recent trace events\n\n```");
+    for record in trace::recent(TRACE_EVENTS) {
+        let _ = writeln!(
+            out,
+            "+{}ms {} {}",
+            record.elapsed_ms,
+            record.event.name(),
+            record.event.summary()
+        );
+    }
+    let _ = writeln!(out, "```\n\n## Log (last {} lines)\n\n```", LOG_TAIL_LINES);
+    let _ = writeln!(out, "{}", log.as_deref().unwrap_or("(no log file)"));
+    let _ = writeln!(out, "```");
+    out
+}
+
+/// Issue title from a bundle: the first line under the `## Error` heading.
+fn issue_title(bundle: &str) -> String {
+    let summary = bundle
+        .lines()
+        .skip_while(|l| !l.starts_with("## Error"))
+        .skip(1)
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("crash");
+    let summary: String = summary.chars().take(100).collect();
+    format!("Crash: {}", summary)
+}
+
+/// Prefilled "new issue" URL for a bundle; long bundles are cut and point at the file.
+pub fn issue_url(bundle: &str, path: &Path) -> String {
+    let mut body: String = bundle.chars().take(ISSUE_BODY_MAX_CHARS).collect();
+    if body.len() < bundle.len() {
+        let _ = write!(
+            body,
+            "\n…\n\n(truncated — full report in `{}`, please attach it)",
+            path.display()
+        );
+    }
+    format!(
+        "https://github.com/{}/{}/issues/new?title={}&body={}",
+        app::VENDOR,
+        app::NAME,
+        percent_encode(&issue_title(bundle)),
+        percent_encode(&body)
+    )
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 3);
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{:02X}", b);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_url_uses_error_line_as_title() {
+        let bundle = bundle_text("panic", "thread 'main' panicked: boom", None, &None);
+        assert!(bundle.contains("## Log (last 200 lines)"));
+        let url = issue_url(&bundle, Path::new("/tmp/crash.md"));
+        assert!(url.starts_with(&format!(
+            "https://github.com/{}/{}/issues/new?title=Crash%3A%20thread%20%27main%27%20panicked%3A%20boom&body=",
+            app::VENDOR,
+            app::NAME
+        )));
+        assert!(!url.contains("truncated"));
+    }
+
+    #[test]
+    fn issue_url_truncates_long_bundles() {
+        let bundle = format!("## Error (panic)\n\nboom\n{}", "x".repeat(10_000));
+        let url = issue_url(&bundle, Path::new("/tmp/crash.md"));
+        assert!(url.contains("truncated"));
+        assert!(url.len() < 8_000);
+    }
+
+    #[test]
+    fn percent_encode_keeps_unreserved() {
+        assert_eq!(percent_encode("a-b_c.d~e f/é"), "a-b_c.d~e%20f%2F%C3%A9");
+    }
+}
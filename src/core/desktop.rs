@@ -0,0 +1,212 @@
+//! Desktop notifications for long turns that end while the terminal is unfocused, sent
+//! through the platform's notifier: `notify-send` (Linux and BSD), `osascript` (macOS), or
+//! a PowerShell balloon tip (Windows). Sending never blocks and never fails the turn.
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Threshold used when notifications are turned on without a number of seconds.
+pub const DEFAULT_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Title of every notification.
+const TITLE: &str = "my-open-claude";
+
+/// Longest reply or command excerpt in a notification body, in characters.
+const EXCERPT_CHARS: usize = 120;
+
+/// Parse `MY_OPEN_CLAUDE_DESKTOP_NOTIFY`: a number of seconds, or "on"/"true" for the
+/// default threshold. "0", "off", "false", and unparsable values turn notifications off.
+pub fn parse_threshold(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("on") || value.eq_ignore_ascii_case("true") {
+        return Some(DEFAULT_THRESHOLD);
+    }
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// How the turn ended, for the notification body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnOutcome {
+    /// The reply is complete; holds its text.
+    Finished(String),
+    /// The turn waits for the user to confirm an action; holds its description.
+    NeedsConfirmation(String),
+    /// The turn failed; holds the error message.
+    Failed(String),
+}
+
+/// Body of the notification for a turn that ran `elapsed`.
+pub fn message(outcome: &TurnOutcome, elapsed: Duration) -> String {
+    let took = format_elapsed(elapsed);
+    match outcome {
+        TurnOutcome::Finished(reply) => match excerpt(reply) {
+            Some(first) => format!("Finished after {}: {}", took, first),
+            None => format!("Finished after {}", took),
+        },
+        TurnOutcome::NeedsConfirmation(action) => match excerpt(action) {
+            Some(action) => format!("Needs confirmation after {}: {}", took, action),
+            None => format!("Needs confirmation after {}", took),
+        },
+        TurnOutcome::Failed(error) => match excerpt(error) {
+            Some(error) => format!("Failed after {}: {}", took, error),
+            None => format!("Failed after {}", took),
+        },
+    }
+}
+
+/// Send a notification with `body`; the notifier runs in the background and errors are
+/// only logged.
+pub fn send(body: &str) {
+    let Some(mut command) = command(TITLE, body) else {
+        log::debug!("desktop notifications are not supported on this platform");
+        return;
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    match command.spawn() {
+        // Reap the notifier once it exits, without waiting for it here.
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => log::debug!("desktop notification not sent: {}", e),
+    }
+}
+
+/// "45s", "3m05s".
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// First non-blank line of `text`, shortened to `EXCERPT_CHARS`.
+fn excerpt(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.chars().count() <= EXCERPT_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(EXCERPT_CHARS - 1).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(applescript(title, body));
+    Some(command)
+}
+
+#[cfg(windows)]
+fn command(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command"])
+        .arg(powershell_script(title, body));
+    Some(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn command(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", TITLE, title, body]);
+    Some(command)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn command(_title: &str, _body: &str) -> Option<Command> {
+    None
+}
+
+/// `display notification` script, with the strings quoted for AppleScript.
+#[cfg_attr(not(any(test, target_os = "macos")), allow(dead_code))]
+fn applescript(title: &str, body: &str) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    format!(
+        "display notification {} with title {}",
+        quote(body),
+        quote(title)
+    )
+}
+
+/// Script showing a balloon tip from a temporary tray icon, with the strings quoted for
+/// PowerShell.
+#[cfg_attr(not(any(test, windows)), allow(dead_code))]
+fn powershell_script(title: &str, body: &str) -> String {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         $n = New-Object System.Windows.Forms.NotifyIcon; \
+         $n.Icon = [System.Drawing.SystemIcons]::Information; \
+         $n.Visible = $true; \
+         $n.ShowBalloonTip(10000, {}, {}, 'Info'); \
+         Start-Sleep -Seconds 10; \
+         $n.Dispose()",
+        quote(title),
+        quote(body)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_threshold_accepts_seconds_and_switches() {
+        assert_eq!(parse_threshold("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_threshold(" on "), Some(DEFAULT_THRESHOLD));
+        assert_eq!(parse_threshold("TRUE"), Some(DEFAULT_THRESHOLD));
+        assert_eq!(parse_threshold("0"), None);
+        assert_eq!(parse_threshold("off"), None);
+        assert_eq!(parse_threshold("soon"), None);
+    }
+
+    #[test]
+    fn message_names_the_outcome_and_duration() {
+        let reply = TurnOutcome::Finished("\nAll tests pass now.\nDetails follow.".into());
+        assert_eq!(
+            message(&reply, Duration::from_secs(185)),
+            "Finished after 3m05s: All tests pass now."
+        );
+        let confirm = TurnOutcome::NeedsConfirmation("Bash: rm -rf build".into());
+        assert_eq!(
+            message(&confirm, Duration::from_secs(42)),
+            "Needs confirmation after 42s: Bash: rm -rf build"
+        );
+        assert_eq!(
+            message(
+                &TurnOutcome::Finished(String::new()),
+                Duration::from_secs(30)
+            ),
+            "Finished after 30s"
+        );
+    }
+
+    #[test]
+    fn message_shortens_long_lines() {
+        let long = "x".repeat(300);
+        let body = message(&TurnOutcome::Failed(long), Duration::from_secs(31));
+        assert!(body.ends_with('…'));
+        assert_eq!(
+            body.chars().count(),
+            "Failed after 31s: ".len() + EXCERPT_CHARS
+        );
+    }
+
+    #[test]
+    fn scripts_quote_their_strings() {
+        assert_eq!(
+            applescript("t", r#"say "hi" \ bye"#),
+            r#"display notification "say \"hi\" \\ bye" with title "t""#
+        );
+        assert!(powershell_script("t", "it's done").contains("'it''s done'"));
+    }
+}
@@ -0,0 +1,498 @@
+//! Unified diffs: `git diff` output parsed into files and hunks, and the change between two
+//! texts (what a Write or Edit would do) in the same form, for the TUI diff viewer.
+
+use std::path::Path;
+use std::process::Command;
+
+use thiserror::Error;
+
+/// Context lines kept around changes when diffing two texts (as `git diff`).
+const CONTEXT_LINES: usize = 3;
+/// Above this many line pairs, the changed middle of two texts is shown as removed then
+/// added instead of computing a minimal diff.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Errors reading the changes of a Git repository.
+#[derive(Debug, Error)]
+pub enum DiffError {
+    #[error("not a Git repository")]
+    NotARepository,
+
+    #[error("Git command failed: {0}")]
+    CommandFailed(String),
+}
+
+/// Side of a diff line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// One line of a hunk, with its number in the old and new file (None on the side it is
+/// missing from).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// `@@ -1,3 +1,4 @@` header, with the enclosing function when git reports one.
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+/// How a file changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Modified,
+    Added,
+    Deleted,
+    Renamed { from: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    pub change: FileChange,
+    /// Binary file: no hunks to show.
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    fn new(path: String) -> Self {
+        FileDiff {
+            path,
+            change: FileChange::Modified,
+            binary: false,
+            hunks: Vec::new(),
+        }
+    }
+
+    /// Number of added and removed lines.
+    pub fn stats(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .flat_map(|h| &h.lines)
+            .fold((0, 0), |(added, removed), line| match line.kind {
+                LineKind::Added => (added + 1, removed),
+                LineKind::Removed => (added, removed + 1),
+                LineKind::Context => (added, removed),
+            })
+    }
+}
+
+/// Parse unified diff text (`git diff`, `git show`, or plain `diff -u`) into files.
+/// Lines outside file sections (commit headers) are ignored.
+pub fn parse(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Lines still expected in the current hunk (old side, new side).
+    let mut remaining = (0usize, 0usize);
+    let mut next = (0usize, 0usize);
+    // A `diff --git` header was seen and its `---`/`+++` lines are still to come.
+    let mut in_header = false;
+
+    for line in diff.lines() {
+        if remaining.0 > 0 || remaining.1 > 0 {
+            let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) else {
+                remaining = (0, 0);
+                continue;
+            };
+            let (kind, text) = match line.chars().next() {
+                Some('+') => (LineKind::Added, &line[1..]),
+                Some('-') => (LineKind::Removed, &line[1..]),
+                Some(' ') => (LineKind::Context, &line[1..]),
+                Some('\\') => continue, // "\ No newline at end of file"
+                // Some tools strip the space of empty context lines.
+                None => (LineKind::Context, ""),
+                Some(_) => {
+                    remaining = (0, 0);
+                    continue;
+                }
+            };
+            let old_line = (kind != LineKind::Added).then(|| {
+                remaining.0 = remaining.0.saturating_sub(1);
+                next.0 += 1;
+                next.0 - 1
+            });
+            let new_line = (kind != LineKind::Removed).then(|| {
+                remaining.1 = remaining.1.saturating_sub(1);
+                next.1 += 1;
+                next.1 - 1
+            });
+            hunk.lines.push(DiffLine {
+                kind,
+                old_line,
+                new_line,
+                text: text.to_string(),
+            });
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let path = rest
+                .split_once(" b/")
+                .map(|(_, b)| b)
+                .unwrap_or(rest)
+                .to_string();
+            files.push(FileDiff::new(path));
+            in_header = true;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            if !in_header {
+                files.push(FileDiff::new(strip_side(path, "a/")));
+            }
+            if path.trim() == "/dev/null"
+                && let Some(file) = files.last_mut()
+            {
+                file.change = FileChange::Added;
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            in_header = false;
+            if let Some(file) = files.last_mut() {
+                if path.trim() == "/dev/null" {
+                    file.change = FileChange::Deleted;
+                } else {
+                    file.path = strip_side(path, "b/");
+                }
+            }
+        } else if let Some(header) = line.strip_prefix("@@ ") {
+            in_header = false;
+            let Some(file) = files.last_mut() else {
+                continue;
+            };
+            let Some((old, new)) = parse_ranges(header) else {
+                continue;
+            };
+            remaining = (old.1, new.1);
+            next = (old.0, new.0);
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(file) = files.last_mut() {
+            if line.starts_with("new file mode") {
+                file.change = FileChange::Added;
+            } else if line.starts_with("deleted file mode") {
+                file.change = FileChange::Deleted;
+            } else if let Some(from) = line.strip_prefix("rename from ") {
+                file.change = FileChange::Renamed {
+                    from: from.to_string(),
+                };
+            } else if let Some(to) = line.strip_prefix("rename to ") {
+                file.path = to.to_string();
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                file.binary = true;
+            }
+        }
+    }
+    files
+}
+
+/// Changes reviewed by `/review` in the repository containing `root`: `git diff <scope>` when
+/// the scope names a commit or branch, otherwise the uncommitted changes (staged and not).
+pub fn git_changes(root: &Path, scope: Option<&str>) -> Result<Vec<FileDiff>, DiffError> {
+    git(root, &["rev-parse", "--show-toplevel"]).map_err(|_| DiffError::NotARepository)?;
+    let revision = scope
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && !s.starts_with('-'))
+        .filter(|s| {
+            git(
+                root,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("{}^{{commit}}", s),
+                ],
+            )
+            .is_ok()
+        });
+    let diff = match revision {
+        Some(rev) => git(root, &["diff", "--no-color", "--no-ext-diff", rev])?,
+        None if git(root, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok() => {
+            git(root, &["diff", "--no-color", "--no-ext-diff", "HEAD"])?
+        }
+        // No commit yet: everything staged, then what changed since.
+        None => {
+            git(root, &["diff", "--no-color", "--no-ext-diff", "--staged"])?
+                + &git(root, &["diff", "--no-color", "--no-ext-diff"])?
+        }
+    };
+    Ok(parse(&diff))
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String, DiffError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| DiffError::CommandFailed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(DiffError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Path of a `---`/`+++` line without its `a/`/`b/` prefix or trailing timestamp.
+fn strip_side(path: &str, prefix: &str) -> String {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+/// `(start, count)` of both sides from `-12,5 +12,7 @@ ...`; a missing count is 1.
+fn parse_ranges(header: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = header.split_whitespace();
+    let old = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let new = parse_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// The change from `old` to `new` content of `path`, as hunks with context lines.
+pub fn between(path: &str, old: &str, new: &str) -> FileDiff {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+    let mut file = FileDiff::new(path.to_string());
+    if old.is_empty() {
+        file.change = FileChange::Added;
+    }
+    file.hunks = group_hunks(&lines);
+    file
+}
+
+/// Every line of both texts, in order, marked context, removed, or added.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut o, mut n) = (0, 0);
+    let mut push = |kind: LineKind, text: &str, o: &mut usize, n: &mut usize| {
+        let old_line = (kind != LineKind::Added).then(|| {
+            *o += 1;
+            *o
+        });
+        let new_line = (kind != LineKind::Removed).then(|| {
+            *n += 1;
+            *n
+        });
+        lines.push(DiffLine {
+            kind,
+            old_line,
+            new_line,
+            text: text.to_string(),
+        });
+    };
+    for text in &old[..prefix] {
+        push(LineKind::Context, text, &mut o, &mut n);
+    }
+    for (kind, text) in middle_ops(old_mid, new_mid) {
+        push(kind, text, &mut o, &mut n);
+    }
+    for text in &old[old.len() - suffix..] {
+        push(LineKind::Context, text, &mut o, &mut n);
+    }
+    lines
+}
+
+/// Minimal edit script between the differing middles (longest common subsequence).
+fn middle_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(LineKind, &'a str)> {
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|l| (LineKind::Removed, *l))
+            .chain(new.iter().map(|l| (LineKind::Added, *l)))
+            .collect();
+    }
+    // lcs[i][j]: LCS length of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((LineKind::Context, old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len()
+            && (i == old.len() || lcs[i * width + j + 1] >= lcs[(i + 1) * width + j])
+        {
+            ops.push((LineKind::Added, new[j]));
+            j += 1;
+        } else {
+            ops.push((LineKind::Removed, old[i]));
+            i += 1;
+        }
+    }
+    // Removed lines first within each change, as in git output.
+    for run in ops.chunk_by_mut(|a, b| (a.0 == LineKind::Context) == (b.0 == LineKind::Context)) {
+        run.sort_by_key(|(kind, _)| *kind != LineKind::Removed);
+    }
+    ops
+}
+
+/// Changed lines with `CONTEXT_LINES` around them; changes closer than twice that share a hunk.
+fn group_hunks(lines: &[DiffLine]) -> Vec<Hunk> {
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| l.kind != LineKind::Context)
+        .map(|(i, _)| i)
+        .collect();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let hunk_lines = &lines[start..end];
+            let before = &lines[..start];
+            let side = |pick: fn(&DiffLine) -> Option<usize>| {
+                let count = hunk_lines.iter().filter_map(pick).count();
+                let first = hunk_lines.iter().find_map(pick);
+                // An empty side starts at the line before it, as in git.
+                let start =
+                    first.unwrap_or_else(|| before.iter().rev().find_map(pick).unwrap_or(0));
+                (start, count)
+            };
+            let (old_start, old_count) = side(|l| l.old_line);
+            let (new_start, new_count) = side(|l| l.new_line);
+            Hunk {
+                header: format!(
+                    "@@ -{},{} +{},{} @@",
+                    old_start, old_count, new_start, new_count
+                ),
+                lines: hunk_lines.to_vec(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIT_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@ mod a;
+ fn one() {}
+--- not a header
++fn two() {}
+
+ fn three() {}
+diff --git a/assets/logo.png b/assets/logo.png
+new file mode 100644
+index 0000000..1111111
+Binary files /dev/null and b/assets/logo.png differ
+diff --git a/old.txt b/new.txt
+similarity index 90%
+rename from old.txt
+rename to new.txt
+@@ -3 +3 @@
+-x
++y
+";
+
+    #[test]
+    fn parses_git_diff_files_hunks_and_line_numbers() {
+        let files = parse(GIT_DIFF);
+        assert_eq!(files.len(), 3);
+
+        let lib = &files[0];
+        assert_eq!(lib.path, "src/lib.rs");
+        assert_eq!(lib.change, FileChange::Modified);
+        assert_eq!(lib.hunks.len(), 1);
+        let lines = &lib.hunks[0].lines;
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[1].kind, LineKind::Removed);
+        assert_eq!(lines[1].text, "-- not a header");
+        assert_eq!((lines[1].old_line, lines[1].new_line), (Some(2), None));
+        assert_eq!((lines[2].old_line, lines[2].new_line), (None, Some(2)));
+        assert_eq!(lines[3].text, "");
+        assert_eq!((lines[4].old_line, lines[4].new_line), (Some(4), Some(4)));
+        assert_eq!(lib.stats(), (1, 1));
+
+        assert_eq!(files[1].path, "assets/logo.png");
+        assert_eq!(files[1].change, FileChange::Added);
+        assert!(files[1].binary);
+
+        assert_eq!(files[2].path, "new.txt");
+        assert_eq!(
+            files[2].change,
+            FileChange::Renamed {
+                from: "old.txt".to_string()
+            }
+        );
+        assert_eq!(files[2].hunks[0].lines[0].old_line, Some(3));
+    }
+
+    #[test]
+    fn diff_between_texts_keeps_context_and_splits_distant_changes() {
+        let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line 18\nline 18b\n");
+        let file = between("notes.txt", &old, &new);
+        assert_eq!(file.change, FileChange::Modified);
+        assert_eq!(file.hunks.len(), 2);
+        assert_eq!(file.hunks[0].header, "@@ -1,5 +1,5 @@");
+        let first: Vec<_> = file.hunks[0]
+            .lines
+            .iter()
+            .map(|l| (l.kind, l.text.as_str()))
+            .collect();
+        assert_eq!(first[1], (LineKind::Removed, "line 2"));
+        assert_eq!(first[2], (LineKind::Added, "line two"));
+        assert_eq!(file.hunks[1].header, "@@ -16,5 +16,6 @@");
+        assert_eq!(file.stats(), (2, 1));
+    }
+
+    #[test]
+    fn diff_of_a_new_file_adds_every_line() {
+        let file = between("new.rs", "", "a\nb\n");
+        assert_eq!(file.change, FileChange::Added);
+        assert_eq!(file.hunks.len(), 1);
+        assert_eq!(file.hunks[0].header, "@@ -0,0 +1,2 @@");
+        assert!(between("same.rs", "a\n", "a\n").hunks.is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Dry-run mode (`--dry-run`, `/dryrun`): tools with side effects (Write, Edit, ReplaceAll,
+//! Bash) report what they would do — the diff of a file change, the command — instead of
+//! doing it, so an agent's plan can be audited on a machine it must not change. The model
+//! gets these simulated results marked as such; read-only tools run normally. The mode is set
+//! per conversation (see `llm::ToolModes`).
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::core::diff::{self, LineKind};
+use crate::core::tools::{Tool, ToolError};
+
+/// Max diff lines in the description of a simulated file change.
+const MAX_DIFF_LINES: usize = 200;
+
+/// First line of a simulated result.
+const SIMULATED_HEADER: &str = "[DRY RUN] Simulated: nothing was changed or executed.";
+
+/// Last paragraph of a simulated result, telling the model how to go on.
+const SIMULATED_NOTE: &str = "Dry-run mode is on: continue the task as if this step had succeeded (without relying on output you did not get), and tell the user which steps were simulated.";
+
+/// Result given to the model instead of running `tool`: its simulation (see
+/// `Tool::simulate`), marked as such. Arguments the call would fail on are errors, as they
+/// would be when run.
+pub fn simulated_result(
+    tool: &dyn Tool,
+    args: &Value,
+    working_dir: &Path,
+) -> Result<String, ToolError> {
+    let summary = tool.simulate(args, working_dir)?;
+    Ok(format!(
+        "{}\n{}\n\n{}",
+        SIMULATED_HEADER, summary, SIMULATED_NOTE
+    ))
+}
+
+/// The change from `old` to `new` content of `path`, described as a unified diff.
+pub fn change_summary(path: &str, old: &str, new: &str) -> String {
+    let diff = diff::between(path, old, new);
+    if diff.hunks.is_empty() {
+        return format!("Would leave {} unchanged.", path);
+    }
+    let (added, removed) = diff.stats();
+    let verb = if old.is_empty() { "create" } else { "change" };
+    let mut text = format!(
+        "Would {} {} (+{} −{}):\n```diff\n",
+        verb, path, added, removed
+    );
+    let lines: Vec<String> = diff
+        .hunks
+        .iter()
+        .flat_map(|hunk| {
+            std::iter::once(hunk.header.clone()).chain(hunk.lines.iter().map(|line| {
+                let sign = match line.kind {
+                    LineKind::Context => ' ',
+                    LineKind::Added => '+',
+                    LineKind::Removed => '-',
+                };
+                format!("{}{}", sign, line.text)
+            }))
+        })
+        .collect();
+    for line in lines.iter().take(MAX_DIFF_LINES) {
+        text.push_str(line);
+        text.push('\n');
+    }
+    if lines.len() > MAX_DIFF_LINES {
+        text.push_str(&format!(
+            "… {} more diff lines\n",
+            lines.len() - MAX_DIFF_LINES
+        ));
+    }
+    text.push_str("```");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::{BashTool, EditTool, WriteTool};
+    use serde_json::json;
+
+    #[test]
+    fn simulated_results_describe_the_call_and_change_nothing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "one\ntwo\n").unwrap();
+
+        let edit = simulated_result(
+            &EditTool,
+            &json!({"file_path": "notes.txt", "old_string": "two", "new_string": "three"}),
+            dir.path(),
+        )
+        .unwrap();
+        assert!(edit.starts_with("[DRY RUN]"), "{}", edit);
+        assert!(edit.contains("Would change notes.txt (+1 −1)"), "{}", edit);
+        assert!(edit.contains("\n-two\n+three\n"), "{}", edit);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+
+        let missing = simulated_result(
+            &EditTool,
+            &json!({"file_path": "notes.txt", "old_string": "four", "new_string": "x"}),
+            dir.path(),
+        );
+        assert!(missing.is_err());
+
+        let write = simulated_result(
+            &WriteTool,
+            &json!({"file_path": "new.txt", "content": "hello\n"}),
+            dir.path(),
+        )
+        .unwrap();
+        assert!(write.contains("Would create new.txt (+1 −0)"), "{}", write);
+        assert!(!dir.path().join("new.txt").exists());
+
+        let bash =
+            simulated_result(&BashTool, &json!({"command": "rm notes.txt"}), dir.path()).unwrap();
+        assert!(bash.contains("Would run: rm notes.txt"), "{}", bash);
+        assert!(file.exists());
+    }
+}
@@ -0,0 +1,152 @@
+//! Workspace tree for the file browser popup (Alt+F): directories and files under the
+//! root, without the junk directories the tools skip and what the root `.gitignore`
+//! ignores, plus the file reads behind its preview and "attach" action.
+
+use std::fs;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+use crate::core::tools::ignore::{self, GitIgnore};
+
+/// Most entries listed in the tree.
+pub const MAX_TREE_ENTRIES: usize = 5_000;
+/// Bytes of a file read for the preview.
+pub const MAX_PREVIEW_BYTES: usize = 32 * 1024;
+/// Largest file attached to the prompt whole.
+pub const MAX_ATTACH_BYTES: usize = 100 * 1024;
+
+/// A directory or file of the tree, in display order (depth first, directories first).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// Path relative to the workspace root, `/`-separated.
+    pub path: String,
+    /// 1 for entries directly under the root.
+    pub depth: usize,
+    pub is_dir: bool,
+}
+
+impl TreeEntry {
+    /// Last component of the path.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Entries under `root`, capped at `MAX_TREE_ENTRIES`. Each directory lists its
+/// subdirectories first, then its files, by name.
+pub fn workspace_tree(root: &Path) -> Vec<TreeEntry> {
+    let gitignore = GitIgnore::load(root);
+    WalkDir::new(root)
+        .min_depth(1)
+        .sort_by(|a, b| {
+            b.file_type()
+                .is_dir()
+                .cmp(&a.file_type().is_dir())
+                .then_with(|| a.file_name().cmp(b.file_name()))
+        })
+        .into_iter()
+        .filter_entry(|e| {
+            !ignore::is_ignored(e)
+                && e.path()
+                    .strip_prefix(root)
+                    .is_ok_and(|rel| !gitignore.is_ignored(rel, e.file_type().is_dir()))
+        })
+        .flatten()
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(root).ok()?;
+            Some(TreeEntry {
+                path: rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                depth: e.depth(),
+                is_dir: e.file_type().is_dir(),
+            })
+        })
+        .take(MAX_TREE_ENTRIES)
+        .collect()
+}
+
+/// Start of a file for the preview, or a note when it is binary or unreadable.
+pub fn preview(path: &Path) -> String {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return format!("(cannot read the file: {})", e),
+    };
+    let head = &bytes[..bytes.len().min(MAX_PREVIEW_BYTES)];
+    if head.contains(&0) {
+        return format!("(binary file, {} bytes)", bytes.len());
+    }
+    if bytes.is_empty() {
+        return "(empty file)".to_string();
+    }
+    String::from_utf8_lossy(head).into_owned()
+}
+
+/// Whole content of a text file to attach to the prompt.
+pub fn read_for_attachment(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    if bytes.len() > MAX_ATTACH_BYTES {
+        return Err(format!(
+            "{} is too large to attach ({} KB, max {} KB); insert its path instead",
+            path.display(),
+            bytes.len() / 1024,
+            MAX_ATTACH_BYTES / 1024
+        ));
+    }
+    if bytes.contains(&0) {
+        return Err(format!("{} is a binary file", path.display()));
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_lists_directories_first_and_skips_ignored_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::write(root.join(".gitignore"), "logs/\n*.tmp\n").unwrap();
+        fs::write(root.join("README.md"), "readme").unwrap();
+        fs::write(root.join("notes.tmp"), "scratch").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/bin/tool.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("logs/app.log"), "log").unwrap();
+
+        let tree: Vec<(String, usize, bool)> = workspace_tree(root)
+            .into_iter()
+            .map(|e| (e.path, e.depth, e.is_dir))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                ("src".to_string(), 1, true),
+                ("src/bin".to_string(), 2, true),
+                ("src/bin/tool.rs".to_string(), 3, false),
+                ("src/main.rs".to_string(), 2, false),
+                (".gitignore".to_string(), 1, false),
+                ("README.md".to_string(), 1, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn preview_and_attachment_refuse_binary_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let text = dir.path().join("a.txt");
+        let binary = dir.path().join("a.bin");
+        fs::write(&text, "hello\n").unwrap();
+        fs::write(&binary, [0u8, 1, 2]).unwrap();
+        assert_eq!(preview(&text), "hello\n");
+        assert_eq!(preview(&binary), "(binary file, 3 bytes)");
+        assert_eq!(read_for_attachment(&text).unwrap(), "hello\n");
+        assert!(read_for_attachment(&binary).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! Fuzzy matching for filter-as-you-type lists (models, slash commands, history).
+//!
+//! A query matches when its characters appear in order in the candidate, ignoring case
+//! (`gpt4` matches `openai/gpt-4o`). Among all the ways to place the query, the best one is
+//! scored in the style of fzf: every matched character scores, characters at the start of a
+//! word or after a lowercase-to-uppercase change score more, consecutive matches are
+//! rewarded, and gaps between matches are penalized. The matched positions are returned so
+//! lists can highlight them.
+
+/// Score of each matched character.
+const SCORE_MATCH: i32 = 16;
+/// Penalty for the first skipped character of a gap between two matches.
+const PENALTY_GAP_START: i32 = 3;
+/// Penalty for each further skipped character of a gap.
+const PENALTY_GAP_EXTENSION: i32 = 1;
+/// Bonus for a match at the start of the candidate or after a separator (`/`, `-`, space...).
+const BONUS_BOUNDARY: i32 = 8;
+/// Bonus for an uppercase match following a lowercase character (camelCase).
+const BONUS_CAMEL: i32 = 7;
+/// Bonus for a match directly following the previous one.
+const BONUS_CONSECUTIVE: i32 = 4;
+/// The bonus of the first query character counts this many times, so queries anchored on a
+/// word start rank first.
+const FIRST_CHAR_BONUS_MULTIPLIER: i32 = 2;
+
+/// A successful match: its score (higher is better) and the matched character indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Indices (in chars, not bytes) of the candidate characters matched by the query.
+    pub positions: Vec<usize>,
+}
+
+/// Lowercase a character without changing the character count, so indices stay aligned.
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Bonus for matching the candidate character at `index`.
+fn position_bonus(chars: &[char], index: usize) -> i32 {
+    let Some(prev) = index.checked_sub(1).map(|i| chars[i]) else {
+        return BONUS_BOUNDARY;
+    };
+    let current = chars[index];
+    if !prev.is_alphanumeric() && current.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if prev.is_lowercase() && current.is_uppercase()
+        || !prev.is_numeric() && current.is_numeric()
+    {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Match `query` against `candidate`. Returns `None` when the query is not a subsequence of
+/// the candidate (case-insensitive); an empty query matches everything with score 0.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = chars.iter().copied().map(fold).collect();
+
+    // Cheap rejection before scoring: the query must be a subsequence.
+    let mut remaining = query.iter().peekable();
+    for c in &folded {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let n = chars.len();
+    let bonuses: Vec<i32> = (0..n).map(|j| position_bonus(&chars, j)).collect();
+    // scores[i][j]: best score with query[..=i] matched and query[i] on candidate[j].
+    // from[i][j]: candidate index of query[i - 1] in that best placement.
+    let mut scores = vec![vec![None::<i32>; n]; query.len()];
+    let mut from = vec![vec![0usize; n]; query.len()];
+    for (j, c) in folded.iter().enumerate() {
+        if *c == query[0] {
+            scores[0][j] = Some(SCORE_MATCH + bonuses[j] * FIRST_CHAR_BONUS_MULTIPLIER);
+        }
+    }
+    for i in 1..query.len() {
+        // Best placement of query[i - 1] at least two characters back, gap penalty included.
+        let mut best_gap: Option<(i32, usize)> = None;
+        for j in i..n {
+            best_gap = best_gap.map(|(s, k)| (s - PENALTY_GAP_EXTENSION, k));
+            if j >= 2
+                && let Some(prev) = scores[i - 1][j - 2]
+            {
+                let started = (prev - PENALTY_GAP_START, j - 2);
+                best_gap = Some(match best_gap {
+                    Some(extended) if extended.0 >= started.0 => extended,
+                    _ => started,
+                });
+            }
+            if folded[j] != query[i] {
+                continue;
+            }
+            let consecutive = scores[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1));
+            let best = match (consecutive, best_gap) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + SCORE_MATCH + bonuses[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = query.len() - 1;
+    let (score, end) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (s, j)))
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+    let mut positions = vec![end; query.len()];
+    for i in (1..query.len()).rev() {
+        positions[i - 1] = from[i][positions[i]];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequence_ignoring_case() {
+        let m = fuzzy_match("gpt4", "openai/GPT-4o").unwrap();
+        assert_eq!(m.positions, vec![7, 8, 9, 11]);
+        assert!(fuzzy_match("4gpt", "openai/gpt-4o").is_none());
+        assert!(fuzzy_match("xyz", "openai/gpt-4o").is_none());
+        assert_eq!(fuzzy_match("", "anything").unwrap().score, 0);
+    }
+
+    #[test]
+    fn prefers_word_starts_and_consecutive_runs() {
+        // "rev" is placed on the word "review", not scattered over "refactor ... v".
+        let m = fuzzy_match("rev", "refactor then review").unwrap();
+        assert_eq!(m.positions, vec![14, 15, 16]);
+
+        let prefix = fuzzy_match("com", "commit").unwrap().score;
+        let inner = fuzzy_match("com", "recommit").unwrap().score;
+        let scattered = fuzzy_match("com", "account mode").unwrap().score;
+        assert!(prefix > inner, "{} <= {}", prefix, inner);
+        assert!(inner > scattered, "{} <= {}", inner, scattered);
+    }
+
+    #[test]
+    fn camel_case_and_separators_score_as_boundaries() {
+        let camel = fuzzy_match("fb", "fooBar").unwrap().score;
+        let plain = fuzzy_match("fb", "foobar").unwrap().score;
+        assert!(camel > plain);
+        let m = fuzzy_match("cs", "claude-sonnet").unwrap();
+        assert_eq!(m.positions, vec![0, 7]);
+    }
+
+    #[test]
+    fn positions_are_char_indices() {
+        let m = fuzzy_match("ét", "café tests").unwrap();
+        assert_eq!(m.positions, vec![3, 5]);
+    }
+}
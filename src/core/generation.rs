@@ -0,0 +1,288 @@
+//! Generation parameters sent with chat requests: temperature, max_tokens, top_p, stop
+//! sequences, and the JSON schema of structured output.
+//!
+//! Global defaults come from the environment (`Config::generation`). Each model can have its
+//! own values, saved in `model_params.json`; in the TUI, `/params` sets values for the
+//! session, which replace the model's saved values until the app exits. Fields left unset
+//! are not sent, so the provider's defaults apply. The output schema is set per request (a
+//! custom command's `output_schema`, `--json-schema` in prompt mode) and never saved.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::core::llm::structured;
+use crate::core::persistence;
+
+/// Most stop sequences accepted by OpenAI-compatible APIs.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Sampling parameters of a chat request. `None` leaves the provider's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    /// JSON schema the reply must match (see `core::llm::structured`).
+    #[serde(skip)]
+    pub response_schema: Option<Value>,
+}
+
+/// Invalid generation parameter value.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ParamError {
+    #[error("temperature must be a number from 0 to 2, got '{0}'")]
+    Temperature(String),
+    #[error("max_tokens must be a positive whole number, got '{0}'")]
+    MaxTokens(String),
+    #[error("top_p must be a number from 0 to 1, got '{0}'")]
+    TopP(String),
+    #[error("at most {MAX_STOP_SEQUENCES} stop sequences are allowed, got {0}")]
+    TooManyStops(usize),
+}
+
+impl GenerationParams {
+    pub fn is_empty(&self) -> bool {
+        *self == GenerationParams::default()
+    }
+
+    /// These parameters with the fields set in `over` replaced.
+    pub fn merged(&self, over: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            temperature: over.temperature.or(self.temperature),
+            max_tokens: over.max_tokens.or(self.max_tokens),
+            top_p: over.top_p.or(self.top_p),
+            stop: over.stop.clone().or_else(|| self.stop.clone()),
+            response_schema: over
+                .response_schema
+                .clone()
+                .or_else(|| self.response_schema.clone()),
+        }
+    }
+
+    /// Add the set parameters to a chat completion request body.
+    pub fn apply(&self, body: &mut Value) {
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if let Some(top_p) = self.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(ref stop) = self.stop {
+            body["stop"] = json!(stop);
+        }
+        if let Some(ref schema) = self.response_schema {
+            body["response_format"] = structured::response_format(schema);
+        }
+    }
+
+    /// One-line description, e.g. "temperature 0.2, max_tokens 2048, stop ###".
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(t) = self.temperature {
+            parts.push(format!("temperature {}", t));
+        }
+        if let Some(m) = self.max_tokens {
+            parts.push(format!("max_tokens {}", m));
+        }
+        if let Some(p) = self.top_p {
+            parts.push(format!("top_p {}", p));
+        }
+        if let Some(ref stop) = self.stop {
+            parts.push(format!("stop {}", format_stop(stop)));
+        }
+        if self.response_schema.is_some() {
+            parts.push("JSON output schema".to_string());
+        }
+        if parts.is_empty() {
+            "provider defaults".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Parameters applying to `model_id`: `defaults`, then the model's values, which are the
+/// `session` values when given and the saved ones otherwise.
+pub fn effective(
+    defaults: &GenerationParams,
+    model_id: &str,
+    session: Option<&GenerationParams>,
+) -> GenerationParams {
+    let model = match session {
+        Some(params) => params.clone(),
+        None => persistence::load_model_params()
+            .remove(model_id)
+            .unwrap_or_default(),
+    };
+    defaults.merged(&model)
+}
+
+/// Number from `value` within `min..=max`; empty means unset.
+fn parse_bounded(value: &str, min: f64, max: f64) -> Result<Option<f64>, ()> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse::<f64>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(Some(n)),
+        _ => Err(()),
+    }
+}
+
+/// Temperature from 0 to 2; empty means unset.
+pub fn parse_temperature(value: &str) -> Result<Option<f64>, ParamError> {
+    parse_bounded(value, 0.0, 2.0).map_err(|_| ParamError::Temperature(value.trim().to_string()))
+}
+
+/// Nucleus sampling mass from 0 to 1; empty means unset.
+pub fn parse_top_p(value: &str) -> Result<Option<f64>, ParamError> {
+    parse_bounded(value, 0.0, 1.0).map_err(|_| ParamError::TopP(value.trim().to_string()))
+}
+
+/// Positive token count; empty means unset.
+pub fn parse_max_tokens(value: &str) -> Result<Option<u32>, ParamError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(Some(n)),
+        _ => Err(ParamError::MaxTokens(value.to_string())),
+    }
+}
+
+/// Comma-separated stop sequences, with `\n`, `\t`, `\,` and `\\` escapes; empty means unset.
+pub fn parse_stop(value: &str) -> Result<Option<Vec<String>>, ParamError> {
+    let mut stops = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    let mut push = |current: &mut String| {
+        let stop = std::mem::take(current);
+        let stop = stop.trim_matches(' ');
+        if !stop.is_empty() {
+            stops.push(stop.to_string());
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => current.push('\n'),
+                Some('t') => current.push('\t'),
+                Some(e @ (',' | '\\')) => current.push(e),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ',' => push(&mut current),
+            _ => current.push(c),
+        }
+    }
+    push(&mut current);
+    match stops.len() {
+        0 => Ok(None),
+        n if n > MAX_STOP_SEQUENCES => Err(ParamError::TooManyStops(n)),
+        _ => Ok(Some(stops)),
+    }
+}
+
+/// Stop sequences in the format read by `parse_stop`.
+pub fn format_stop(stops: &[String]) -> String {
+    stops
+        .iter()
+        .map(|s| {
+            s.replace('\\', "\\\\")
+                .replace(',', "\\,")
+                .replace('\n', "\\n")
+                .replace('\t', "\\t")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_overrides_set_fields_only() {
+        let defaults = GenerationParams {
+            temperature: Some(0.7),
+            max_tokens: Some(4096),
+            ..Default::default()
+        };
+        let model = GenerationParams {
+            temperature: Some(0.2),
+            stop: Some(vec!["###".to_string()]),
+            ..Default::default()
+        };
+        let merged = defaults.merged(&model);
+        assert_eq!(merged.temperature, Some(0.2));
+        assert_eq!(merged.max_tokens, Some(4096));
+        assert_eq!(merged.top_p, None);
+        assert_eq!(merged.stop, Some(vec!["###".to_string()]));
+    }
+
+    #[test]
+    fn apply_adds_only_set_parameters() {
+        let mut body = json!({"model": "m"});
+        GenerationParams::default().apply(&mut body);
+        assert_eq!(body, json!({"model": "m"}));
+
+        let params = GenerationParams {
+            temperature: Some(0.5),
+            max_tokens: Some(100),
+            top_p: Some(0.9),
+            stop: Some(vec!["\n\n".to_string()]),
+            response_schema: Some(json!({"type": "object"})),
+        };
+        params.apply(&mut body);
+        assert_eq!(body["temperature"], json!(0.5));
+        assert_eq!(body["max_tokens"], json!(100));
+        assert_eq!(body["top_p"], json!(0.9));
+        assert_eq!(body["stop"], json!(["\n\n"]));
+        assert_eq!(body["response_format"]["type"], json!("json_schema"));
+    }
+
+    #[test]
+    fn parse_validates_ranges() {
+        assert_eq!(parse_temperature(" 0.3 "), Ok(Some(0.3)));
+        assert_eq!(parse_temperature(""), Ok(None));
+        assert!(parse_temperature("2.5").is_err());
+        assert!(parse_temperature("hot").is_err());
+        assert_eq!(parse_top_p("1"), Ok(Some(1.0)));
+        assert!(parse_top_p("-0.1").is_err());
+        assert_eq!(parse_max_tokens("2048"), Ok(Some(2048)));
+        assert!(parse_max_tokens("0").is_err());
+        assert!(parse_max_tokens("1.5").is_err());
+    }
+
+    #[test]
+    fn stop_sequences_round_trip_with_escapes() {
+        let stops = parse_stop("###, \\n\\nUser:, a\\,b").unwrap().unwrap();
+        assert_eq!(stops, vec!["###", "\n\nUser:", "a,b"]);
+        assert_eq!(parse_stop(&format_stop(&stops)).unwrap().unwrap(), stops);
+        assert_eq!(parse_stop(" , "), Ok(None));
+        assert_eq!(parse_stop("a,b,c,d,e"), Err(ParamError::TooManyStops(5)));
+    }
+
+    #[test]
+    fn summary_lists_set_parameters() {
+        assert_eq!(GenerationParams::default().summary(), "provider defaults");
+        let params = GenerationParams {
+            temperature: Some(0.2),
+            stop: Some(vec!["###".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(params.summary(), "temperature 0.2, stop ###");
+    }
+}
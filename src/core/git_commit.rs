@@ -0,0 +1,229 @@
+//! Commit workflow for `/commit`: staged changes summary, drafted message cleanup,
+//! and running `git commit` through the Bash tool.
+//!
+//! Works from linked worktrees: the message file is written to the worktree's own
+//! Git directory (`git rev-parse --absolute-git-dir`), not to `<root>/.git`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use regex::Regex;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::core::tools::{BashTool, Tool};
+
+/// File name of the commit message written to the Git directory.
+const MESSAGE_FILE: &str = "MY_OPEN_CLAUDE_COMMIT_MSG";
+
+/// Errors from the commit workflow.
+#[derive(Debug, Error)]
+pub enum CommitError {
+    #[error("not a Git repository")]
+    NotARepository,
+
+    #[error("nothing staged; stage changes with `git add` first")]
+    NothingStaged,
+
+    #[error("commit message is empty")]
+    EmptyMessage,
+
+    #[error("Git command failed: {0}")]
+    CommandFailed(String),
+
+    #[error("git commit failed: {0}")]
+    CommitFailed(String),
+
+    #[error("cannot write commit message: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What is about to be committed.
+#[derive(Debug, Clone)]
+pub struct StagedChanges {
+    /// Current branch (None when detached).
+    pub branch: Option<String>,
+    /// Working tree root (differs from the main checkout in a linked worktree).
+    pub worktree: PathBuf,
+    /// Git directory of this worktree.
+    pub git_dir: PathBuf,
+    /// Output of `git diff --staged --stat`.
+    pub stat: String,
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String, CommitError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| CommitError::CommandFailed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(CommitError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Gather the staged changes of the worktree containing `root`.
+pub fn staged_changes(root: &Path) -> Result<StagedChanges, CommitError> {
+    let worktree =
+        git(root, &["rev-parse", "--show-toplevel"]).map_err(|_| CommitError::NotARepository)?;
+    let worktree = PathBuf::from(worktree);
+    let git_dir = PathBuf::from(git(root, &["rev-parse", "--absolute-git-dir"])?);
+    let branch = git(root, &["branch", "--show-current"])
+        .ok()
+        .filter(|b| !b.is_empty());
+    let stat = git(root, &["diff", "--staged", "--stat"])?;
+    if stat.is_empty() {
+        return Err(CommitError::NothingStaged);
+    }
+    Ok(StagedChanges {
+        branch,
+        worktree,
+        git_dir,
+        stat,
+    })
+}
+
+/// Extract the commit message from a model response: the first fenced code block when
+/// present, otherwise the whole response.
+pub fn extract_commit_message(response: &str) -> String {
+    let mut in_block = false;
+    let mut block = Vec::new();
+    for line in response.lines() {
+        if line.trim_start().starts_with("```") {
+            if in_block {
+                return block.join("\n").trim().to_string();
+            }
+            in_block = true;
+        } else if in_block {
+            block.push(line);
+        }
+    }
+    response.trim().to_string()
+}
+
+/// Quote a path for `sh -c`.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Path the commit message is written to before committing.
+pub fn message_file(changes: &StagedChanges) -> PathBuf {
+    changes.git_dir.join(MESSAGE_FILE)
+}
+
+/// The command run through the Bash tool: commit from the worktree root with the message file.
+pub fn shell_command(changes: &StagedChanges) -> String {
+    format!(
+        "cd {} && git commit -F {}",
+        shell_quote(&changes.worktree.display().to_string()),
+        shell_quote(&message_file(changes).display().to_string())
+    )
+}
+
+/// True when the Bash tool asks for confirmation before running `command`.
+pub fn needs_confirmation(command: &str) -> bool {
+    BashTool.may_need_confirmation(&json!({ "command": command }))
+}
+
+/// Commit hash from `git commit` output (`[main 1a2b3c4] subject`, `[main (root-commit) …]`).
+pub fn parse_commit_hash(output: &str) -> Option<String> {
+    let re = Regex::new(r"(?m)^\[[^\]]*?\b([0-9a-f]{7,40})\]").ok()?;
+    re.captures(output).map(|c| c[1].to_string())
+}
+
+/// Write `message` to the worktree's Git directory and run `git commit` through the
+/// Bash tool from the worktree root. Returns the new commit hash.
+pub fn commit(changes: &StagedChanges, message: &str) -> Result<String, CommitError> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(CommitError::EmptyMessage);
+    }
+    let path = message_file(changes);
+    fs::write(&path, format!("{}\n", message))?;
+
+    let result = BashTool.execute(
+        &json!({ "command": shell_command(changes) }),
+        &changes.worktree,
+    );
+    let _ = fs::remove_file(&path);
+
+    let output = result.map_err(|e| CommitError::CommitFailed(e.to_string()))?;
+    parse_commit_hash(&output).ok_or_else(|| CommitError::CommitFailed(output.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_commit_message_prefers_code_block() {
+        let response =
+            "Here is a message:\n\n```\nfeat(tui): add commit flow\n\nDetails.\n```\nDone.";
+        assert_eq!(
+            extract_commit_message(response),
+            "feat(tui): add commit flow\n\nDetails."
+        );
+        assert_eq!(extract_commit_message("  fix: typo \n"), "fix: typo");
+    }
+
+    #[test]
+    fn parse_commit_hash_handles_root_commit() {
+        assert_eq!(
+            parse_commit_hash("[main 1a2b3c4] feat: x\n 1 file changed"),
+            Some("1a2b3c4".to_string())
+        );
+        assert_eq!(
+            parse_commit_hash("[main (root-commit) abcdef0] init"),
+            Some("abcdef0".to_string())
+        );
+        assert_eq!(parse_commit_hash("nothing to commit"), None);
+    }
+
+    #[test]
+    fn shell_command_quotes_paths() {
+        let changes = StagedChanges {
+            branch: None,
+            worktree: PathBuf::from("/tmp/it's"),
+            git_dir: PathBuf::from("/tmp/it's/.git"),
+            stat: String::new(),
+        };
+        assert_eq!(
+            shell_command(&changes),
+            r"cd '/tmp/it'\''s' && git commit -F '/tmp/it'\''s/.git/MY_OPEN_CLAUDE_COMMIT_MSG'"
+        );
+        assert!(!needs_confirmation(&shell_command(&changes)));
+    }
+
+    #[test]
+    fn commit_in_temp_repo_returns_hash() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let root = dir.path();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.email", "dev@example.com"],
+            &["config", "user.name", "Dev"],
+        ] {
+            git(root, args).expect("git setup");
+        }
+        assert!(matches!(
+            staged_changes(root),
+            Err(CommitError::NothingStaged)
+        ));
+
+        fs::write(root.join("a.txt"), "hello\n").expect("write");
+        git(root, &["add", "a.txt"]).expect("git add");
+        let changes = staged_changes(root).expect("staged");
+        assert!(changes.stat.contains("a.txt"));
+
+        let hash = commit(&changes, "feat: add a.txt").expect("commit");
+        assert_eq!(
+            git(root, &["rev-parse", "--short", "HEAD"]).expect("head")[..7],
+            hash[..7]
+        );
+        assert!(!message_file(&changes).exists());
+    }
+}
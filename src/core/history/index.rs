@@ -6,8 +6,10 @@ use std::io;
 use serde::{Deserialize, Serialize};
 
 use crate::core::config::Config;
+use crate::core::fuzzy;
 
-use super::storage;
+use super::retention::{self, PruneCandidate};
+use super::storage::{self, IndexFile};
 
 /// Metadata for a conversation in the index.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,10 +18,18 @@ pub struct ConversationMeta {
     pub title: String,
     pub created_at: u64,
     pub updated_at: u64,
+    /// Workspace root the conversation was last saved from, for the per-workspace cap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Archived conversations are kept when the history is pruned.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
 }
 
-/// Filter conversations by title, id, or message content (case-insensitive).
-/// When `content_by_id` is provided, also matches if any message content contains the query.
+/// Filter conversations by title (fuzzy), id, or message content (case-insensitive
+/// substring). Title matches come first, best first; id and content matches follow in
+/// input order. When `content_by_id` is provided, also matches if any message content
+/// contains the query.
 pub fn filter_conversations_with_content<'a>(
     convs: &'a [ConversationMeta],
     query: &str,
@@ -29,16 +39,24 @@ pub fn filter_conversations_with_content<'a>(
         return convs.iter().collect();
     }
     let q = query.to_lowercase();
-    convs
-        .iter()
-        .filter(|c| {
-            c.title.to_lowercase().contains(&q)
-                || c.id.to_lowercase().contains(&q)
-                || content_by_id
-                    .get(&c.id)
-                    .map(|s| s.to_lowercase().contains(&q))
-                    .unwrap_or(false)
-        })
+    let mut by_title: Vec<(i32, &ConversationMeta)> = Vec::new();
+    let mut by_content: Vec<&ConversationMeta> = Vec::new();
+    for c in convs {
+        if let Some(m) = fuzzy::fuzzy_match(query, &c.title) {
+            by_title.push((m.score, c));
+        } else if c.id.to_lowercase().contains(&q)
+            || content_by_id
+                .get(&c.id)
+                .is_some_and(|s| s.to_lowercase().contains(&q))
+        {
+            by_content.push(c);
+        }
+    }
+    by_title.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    by_title
+        .into_iter()
+        .map(|(_, c)| c)
+        .chain(by_content)
         .collect()
 }
 
@@ -47,57 +65,49 @@ pub fn list_conversations() -> io::Result<Vec<ConversationMeta>> {
     let mut index = storage::load_index()?;
     index
         .conversations
-        .sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        .sort_by_key(|c| std::cmp::Reverse(c.updated_at));
     Ok(index.conversations)
 }
 
 /// Add or update a conversation in the index. Removes any existing entry with the same id.
-pub(super) fn add_or_update(meta: ConversationMeta) -> io::Result<()> {
-    let mut index = storage::load_index()?;
+pub(super) fn add_or_update(index: &mut IndexFile, meta: ConversationMeta) {
     index.conversations.retain(|c| c.id != meta.id);
     index.conversations.push(meta);
-    storage::save_index(&index)
 }
 
 /// Update the title of a conversation by ID.
-pub(super) fn update_title(id: &str, new_title: &str) -> io::Result<()> {
-    let mut index = storage::load_index()?;
+pub(super) fn update_title(index: &mut IndexFile, id: &str, new_title: &str) {
     if let Some(meta) = index.conversations.iter_mut().find(|c| c.id == id) {
         meta.title = new_title.to_string();
-        storage::save_index(&index)?;
     }
-    Ok(())
 }
 
 /// Remove a conversation from the index by ID.
-pub(super) fn remove(id: &str) -> io::Result<()> {
-    let mut index = storage::load_index()?;
+pub(super) fn remove(index: &mut IndexFile, id: &str) {
     index.conversations.retain(|c| c.id != id);
-    storage::save_index(&index)
 }
 
-/// Remove old conversations when exceeding max_conversations.
-/// Deletes conversation files and updates the index.
-pub(super) fn prune(config: &Config) -> io::Result<()> {
-    let max = config.max_conversations as usize;
-    if max == 0 {
-        return Ok(());
-    }
-
-    let mut index = storage::load_index()?;
-    index
-        .conversations
-        .sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-
-    if index.conversations.len() <= max {
-        return Ok(());
+/// Set whether a conversation is archived. Returns false when it is not in the index.
+pub(super) fn set_archived(index: &mut IndexFile, id: &str, archived: bool) -> bool {
+    match index.conversations.iter_mut().find(|c| c.id == id) {
+        Some(meta) => {
+            meta.archived = archived;
+            true
+        }
+        None => false,
     }
+}
 
-    let to_remove: Vec<_> = index.conversations.drain(max..).collect();
-    for meta in &to_remove {
-        if let Err(e) = storage::remove_conv_file(&meta.id) {
-            log::warn!("Failed to remove conversation file {}: {}", meta.id, e);
+/// Remove the conversations past the retention limits of `config` at time `now` (see
+/// `retention::plan`), with their conversation files. Returns what was removed.
+pub(super) fn prune(index: &mut IndexFile, config: &Config, now: u64) -> Vec<PruneCandidate> {
+    let removed = retention::plan(&index.conversations, config, now);
+    for candidate in &removed {
+        let id = &candidate.meta.id;
+        if let Err(e) = storage::remove_conv_file(id) {
+            log::warn!("Failed to remove conversation file {}: {}", id, e);
         }
+        remove(index, id);
     }
-    storage::save_index(&index)
+    removed
 }
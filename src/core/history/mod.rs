@@ -1,21 +1,80 @@
 //! Persistence of conversation history in ~/.local/share/my-open-claude/conversations/.
 
 mod index;
+mod origin;
+mod reactions;
+mod retention;
 mod storage;
+mod ui_state;
 
 pub use index::{ConversationMeta, filter_conversations_with_content, list_conversations};
+pub use origin::ConversationOrigin;
+pub use reactions::{Reaction, export_reactions};
+pub use retention::{PruneCandidate, PruneReason, Retention};
+pub use ui_state::{UiState, load_ui_state, save_ui_state};
 
+use std::collections::HashSet;
 use std::io;
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
 use crate::core::config::Config;
+use crate::core::generation::GenerationParams;
+use crate::core::llm;
 use crate::core::message;
+use crate::core::text;
 
-/// Extract messages suitable for persistence: user, assistant, and tool_log with content.
-/// tool_log entries preserve verbose tool execution output for display when re-opening.
+/// Number of most recent conversations compared when looking for a near-duplicate.
+const DUPLICATE_SCAN_LIMIT: usize = 50;
+/// Minimum word overlap (Jaccard index) for two first messages to be near-duplicates.
+const DUPLICATE_SIMILARITY: f64 = 0.8;
+/// Role of the persisted marker left by /clear-context: messages before it are kept for
+/// display but not sent to the model.
+pub const CONTEXT_RESET_ROLE: &str = "context_reset";
+/// Role of the persisted marker left by /compact: its content summarizes the messages
+/// before it, except the last `kept_turns` user turns, which are still sent to the model.
+pub const CONTEXT_SUMMARY_ROLE: &str = "context_summary";
+/// Suffix marking the title of a branched conversation.
+const BRANCH_TITLE_SUFFIX: &str = " (branch)";
+/// Suffix marking the title of a conversation saved as a copy, because another instance
+/// changed the original since it was loaded.
+const COPY_TITLE_SUFFIX: &str = " (copy)";
+
+/// Model, mode, and `/params` values a conversation was held with, saved next to its
+/// messages and restored when it is loaded. Unset fields keep the current values. The
+/// origin (workspace, branch, environment) is only shown, and compared with the current
+/// workspace on resume.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(default, skip_serializing_if = "GenerationParams::is_empty")]
+    pub params: GenerationParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<ConversationOrigin>,
+}
+
+impl ConversationSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == ConversationSettings::default()
+    }
+}
+
+/// Extract messages suitable for persistence: user and assistant (with their pin flag, and
+/// the reaction of assistant replies), tool_log with content, typed tool_result views, and
+/// context_reset and context_summary markers. tool_log and tool_result entries preserve tool
+/// execution output for display when re-opening.
 fn sanitize_messages_for_save(messages: &[Value]) -> Vec<Value> {
+    let with_pin = |mut saved: Value, msg: &Value| {
+        if msg.get("pinned").and_then(|p| p.as_bool()) == Some(true) {
+            saved["pinned"] = serde_json::json!(true);
+        }
+        saved
+    };
     messages
         .iter()
         .filter_map(|msg| {
@@ -23,71 +82,182 @@ fn sanitize_messages_for_save(messages: &[Value]) -> Vec<Value> {
             match role {
                 "user" => {
                     let content = msg.get("content")?;
-                    Some(serde_json::json!({"role": "user", "content": content}))
+                    Some(with_pin(
+                        serde_json::json!({"role": "user", "content": content}),
+                        msg,
+                    ))
                 }
                 "assistant" => {
                     let content = msg
                         .get("content")
                         .cloned()
                         .unwrap_or(serde_json::Value::Null);
-                    Some(serde_json::json!({"role": "assistant", "content": content}))
+                    let mut saved = with_pin(
+                        serde_json::json!({"role": "assistant", "content": content}),
+                        msg,
+                    );
+                    if let Some(reaction) = Reaction::of_message(msg) {
+                        saved["reaction"] = serde_json::json!(reaction);
+                    }
+                    Some(saved)
                 }
                 "tool_log" => {
                     let content = msg.get("content").and_then(|c| c.as_str()).unwrap_or("");
                     Some(serde_json::json!({"role": "tool_log", "content": content}))
                 }
+                "tool_result" => Some(serde_json::json!({
+                    "role": "tool_result",
+                    "tool": msg.get("tool").cloned().unwrap_or_default(),
+                    "view": msg.get("view")?,
+                })),
+                CONTEXT_RESET_ROLE => Some(serde_json::json!({"role": CONTEXT_RESET_ROLE})),
+                CONTEXT_SUMMARY_ROLE => Some(serde_json::json!({
+                    "role": CONTEXT_SUMMARY_ROLE,
+                    "content": msg.get("content").and_then(|c| c.as_str()).unwrap_or(""),
+                    "kept_turns": msg.get("kept_turns").and_then(|k| k.as_u64()).unwrap_or(0),
+                })),
                 _ => None,
             }
         })
         .collect()
 }
 
-/// Generate title from first user message. Truncates to max_len chars with ellipsis.
+/// Generate title from first user message. Truncates to max_len display columns with ellipsis.
 pub fn first_message_preview(messages: &[Value], max_len: usize) -> String {
     for msg in messages {
         if msg.get("role").and_then(|r| r.as_str()) == Some("user")
             && let Some(content) = message::extract_content(msg)
         {
             let s = content.trim().replace('\n', " ");
-            let char_count = s.chars().count();
-            if char_count <= max_len {
-                return s;
-            }
-            let truncated: String = s.chars().take(max_len.saturating_sub(1)).collect();
-            return format!("{}…", truncated);
+            return text::truncate_end(&s, max_len);
         }
     }
     "(No title)".to_string()
 }
 
+/// Content of the first user message, if any.
+fn first_user_message(messages: &[Value]) -> Option<String> {
+    messages
+        .iter()
+        .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+        .and_then(message::extract_content)
+}
+
+/// First user prompt and the first non-empty assistant answer after it, if both exist.
+pub fn first_exchange(messages: &[Value]) -> Option<(String, String)> {
+    let role = |m: &Value| m.get("role").and_then(|r| r.as_str()).map(str::to_string);
+    let start = messages
+        .iter()
+        .position(|m| role(m).as_deref() == Some("user"))?;
+    let prompt = message::extract_content(&messages[start])?;
+    let answer = messages[start + 1..]
+        .iter()
+        .filter(|m| role(m).as_deref() == Some("assistant"))
+        .filter_map(message::extract_content)
+        .find(|c| !c.trim().is_empty())?;
+    Some((prompt, answer))
+}
+
+/// Lowercased words of `s`, ignoring punctuation.
+fn words(s: &str) -> HashSet<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// True when two first messages are the same prompt modulo case, punctuation, and small
+/// edits (word overlap of at least `DUPLICATE_SIMILARITY`).
+pub fn is_near_duplicate(a: &str, b: &str) -> bool {
+    let (a_words, b_words) = (words(a), words(b));
+    if a_words.is_empty() || b_words.is_empty() {
+        return !a.trim().is_empty() && a.trim() == b.trim();
+    }
+    let common = a_words.intersection(&b_words).count();
+    let total = a_words.union(&b_words).count();
+    common as f64 / total as f64 >= DUPLICATE_SIMILARITY
+}
+
+/// Find a recent saved conversation whose first user message nearly matches the one in
+/// `messages` (see `is_near_duplicate`). Returns the most recently updated match.
+pub fn find_similar_conversation(messages: &[Value]) -> Option<ConversationMeta> {
+    let first = first_user_message(messages)?;
+    list_conversations()
+        .ok()?
+        .into_iter()
+        .take(DUPLICATE_SCAN_LIMIT)
+        .find(|meta| {
+            storage::read_conv_messages(&meta.id)
+                .and_then(|existing| first_user_message(&existing))
+                .is_some_and(|existing| is_near_duplicate(&first, &existing))
+        })
+}
+
 /// Load a conversation by ID. Returns all persisted messages (user, assistant, tool_log).
 pub fn load_conversation(id: &str) -> Option<Vec<Value>> {
-    storage::read_conv_messages(id)
+    storage::open_conv_messages(id)
 }
 
-/// Filter persisted messages to API format (user and assistant only).
-/// Used for chat_resume; the API does not accept tool_log.
+/// Settings saved with a conversation. Empty for conversations saved without them, or
+/// when the conversation cannot be loaded.
+pub fn load_conversation_settings(id: &str) -> ConversationSettings {
+    storage::read_conv_settings(id).unwrap_or_default()
+}
+
+/// Filter persisted messages to API format (user and assistant only), starting after the
+/// last context reset. After a compaction, the conversation starts with its summary and the
+/// turns it kept. Used for chat_resume; the API does not accept tool_log.
 pub fn api_messages_from_persisted(persisted: &[Value]) -> Vec<Value> {
-    persisted
+    let has_role = |m: &Value, role: &str| m.get("role").and_then(|r| r.as_str()) == Some(role);
+    let reset = persisted
         .iter()
-        .filter_map(|msg| {
-            let role = msg.get("role")?.as_str()?;
-            match role {
-                "user" => {
-                    let content = msg.get("content")?;
-                    Some(serde_json::json!({"role": "user", "content": content}))
-                }
-                "assistant" => {
-                    let content = msg
-                        .get("content")
-                        .cloned()
-                        .unwrap_or(serde_json::Value::Null);
-                    Some(serde_json::json!({"role": "assistant", "content": content}))
-                }
-                _ => None,
+        .rposition(|m| has_role(m, CONTEXT_RESET_ROLE))
+        .map_or(0, |i| i + 1);
+    let summary = persisted[reset..]
+        .iter()
+        .rposition(|m| has_role(m, CONTEXT_SUMMARY_ROLE))
+        .map(|i| reset + i);
+    let mut api = Vec::new();
+    let mut start = reset;
+    if let Some(marker) = summary {
+        let summary = &persisted[marker];
+        let kept_turns = summary
+            .get("kept_turns")
+            .and_then(|k| k.as_u64())
+            .unwrap_or(0) as usize;
+        // The kept turns start at the kept_turns-th user message before the marker.
+        let users: Vec<usize> = (reset..marker)
+            .filter(|&i| has_role(&persisted[i], "user"))
+            .collect();
+        start = match users.len().checked_sub(kept_turns) {
+            Some(n) if n < users.len() => users[n],
+            Some(_) => marker,
+            None => reset,
+        };
+        let content = summary
+            .get("content")
+            .and_then(|c| c.as_str())
+            .unwrap_or("");
+        api.push(llm::summary_message(content));
+    }
+    api.extend(persisted[start..].iter().filter_map(|msg| {
+        let role = msg.get("role")?.as_str()?;
+        match role {
+            "user" => {
+                let content = msg.get("content")?;
+                Some(serde_json::json!({"role": "user", "content": content}))
             }
-        })
-        .collect()
+            "assistant" => {
+                let content = msg
+                    .get("content")
+                    .cloned()
+                    .unwrap_or(serde_json::Value::Null);
+                Some(serde_json::json!({"role": "assistant", "content": content}))
+            }
+            _ => None,
+        }
+    }));
+    api
 }
 
 /// Load concatenated text content from a conversation for full-text search.
@@ -101,14 +271,22 @@ pub fn load_conversation_searchable_content(id: &str) -> Option<String> {
     Some(parts.join("\n"))
 }
 
-/// Save a conversation. Creates or updates. Returns the conversation ID.
+/// Save a conversation with its settings. Creates or updates. Returns the conversation ID.
+/// `title` names new conversations; an existing one keeps its stored title, so renamed and
+/// generated titles survive later saves.
+///
+/// When another instance saved conversation `id` since this one loaded or saved it, the
+/// messages are saved as a new conversation titled "<title> (copy)" instead of replacing
+/// the other version: the returned ID then differs from `id`. Fails with
+/// `ErrorKind::WouldBlock` when another instance holds the history lock for too long (see
+/// `is_lock_timeout`).
 pub fn save_conversation(
     id: Option<&str>,
     title: &str,
     messages: &[Value],
+    settings: &ConversationSettings,
     config: &Config,
 ) -> io::Result<String> {
-    storage::ensure_data_dir()?;
     let sanitized = sanitize_messages_for_save(messages);
     if sanitized.is_empty() {
         if let Some(existing_id) = id {
@@ -120,41 +298,72 @@ pub fn save_conversation(
         ));
     }
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or_else(|e| {
-            log::warn!("System time before UNIX epoch: {}", e);
-            0
-        });
-
-    let conv_id = id
-        .map(String::from)
-        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let now = now_secs();
+    let workspace = settings
+        .origin
+        .as_ref()
+        .map(|o| o.workspace_root.display().to_string());
 
-    storage::write_conv_file(&conv_id, &sanitized)?;
+    storage::update_index(|index| {
+        let existing = id.and_then(|existing_id| {
+            index
+                .conversations
+                .iter()
+                .find(|c| c.id == existing_id)
+                .cloned()
+        });
+        let (mut title, mut created_at, mut archived) = match existing {
+            Some(meta) => (meta.title, meta.created_at, meta.archived),
+            None => (title.to_string(), now, false),
+        };
+        let mut conv_id = id.map(String::from);
+        if let Some(existing_id) = id
+            && storage::changed_elsewhere(existing_id)
+        {
+            log::warn!(
+                "Conversation {} was saved by another instance, saving a copy",
+                existing_id
+            );
+            conv_id = None;
+            title = format!("{}{}", title, COPY_TITLE_SUFFIX);
+            created_at = now;
+            archived = false;
+        }
+        let conv_id = conv_id.unwrap_or_else(|| Uuid::new_v4().to_string());
 
-    let created_at = id
-        .and_then(|existing_id| {
-            storage::load_index().ok().and_then(|idx| {
-                idx.conversations
-                    .iter()
-                    .find(|c| c.id == existing_id)
-                    .map(|c| c.created_at)
-            })
-        })
-        .unwrap_or(now);
+        storage::write_conv_file(&conv_id, &sanitized, settings)?;
+        index::add_or_update(
+            index,
+            index::ConversationMeta {
+                id: conv_id.clone(),
+                title,
+                created_at,
+                updated_at: now,
+                workspace: workspace.clone(),
+                archived,
+            },
+        );
+        index::prune(index, config, now);
+        Ok(conv_id)
+    })
+}
 
-    let meta = index::ConversationMeta {
-        id: conv_id.clone(),
-        title: title.to_string(),
-        created_at,
-        updated_at: now,
+/// Save the first `count` persisted messages as a new conversation titled after `title`,
+/// so another direction can be explored without changing the original. Returns the new ID.
+pub fn branch_conversation(
+    messages: &[Value],
+    count: usize,
+    title: &str,
+    settings: &ConversationSettings,
+    config: &Config,
+) -> io::Result<String> {
+    let kept = &messages[..count.min(messages.len())];
+    let title = if title.ends_with(BRANCH_TITLE_SUFFIX) {
+        title.to_string()
+    } else {
+        format!("{}{}", title, BRANCH_TITLE_SUFFIX)
     };
-
-    index::add_or_update(meta)?;
-    index::prune(config)?;
-    Ok(conv_id)
+    save_conversation(None, &title, kept, settings, config)
 }
 
 /// Rename a conversation by ID. Updates only the title in the index.
@@ -166,13 +375,68 @@ pub fn rename_conversation(id: &str, new_title: &str) -> io::Result<()> {
             "Title cannot be empty",
         ));
     }
-    index::update_title(id, new_title)
+    storage::update_index(|index| {
+        index::update_title(index, id, new_title);
+        Ok(())
+    })
 }
 
-/// Delete a conversation by ID. Removes the file and index entry.
+/// Delete a conversation by ID. Removes the file, its view state, and index entry.
 pub fn delete_conversation(id: &str) -> io::Result<()> {
-    storage::remove_conv_file(id)?;
-    index::remove(id)
+    storage::update_index(|index| {
+        storage::remove_conv_file(id)?;
+        index::remove(index, id);
+        Ok(())
+    })
+}
+
+/// Archive a conversation (kept when the history is pruned), or unarchive it. Fails with
+/// `ErrorKind::NotFound` for an unknown ID.
+pub fn set_archived(id: &str, archived: bool) -> io::Result<()> {
+    storage::update_index(|index| {
+        if index::set_archived(index, id, archived) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("conversation '{}' not found", id),
+            ))
+        }
+    })
+}
+
+/// Remove the conversations past the retention limits of `config` (overall cap, age,
+/// per-workspace cap; see `Retention`), or with `dry_run` only list them. Returns the
+/// conversations removed (or that would be), most recently updated first.
+pub fn prune_history(config: &Config, dry_run: bool) -> io::Result<Vec<PruneCandidate>> {
+    if dry_run {
+        let index = storage::load_index()?;
+        return Ok(retention::plan(&index.conversations, config, now_secs()));
+    }
+    storage::update_index(|index| Ok(index::prune(index, config, now_secs())))
+}
+
+/// Seconds since the epoch (0 when the clock is before it).
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_else(|e| {
+            log::warn!("System time before UNIX epoch: {}", e);
+            0
+        })
+}
+
+/// True when `e` is a save that gave up waiting for another instance to release the
+/// history lock.
+pub fn is_lock_timeout(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::WouldBlock
+}
+
+/// True (once) when a history change since the last call had to wait for another instance
+/// holding the lock.
+pub fn take_lock_contention() -> bool {
+    storage::take_lock_contention()
 }
 
 #[cfg(test)]
@@ -0,0 +1,101 @@
+//! Where a conversation was held: workspace root, Git branch, and the environment variables
+//! that select toolchains and targets, captured each time it is saved.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::workspace::{self, Workspace};
+
+/// Environment variables recorded with a conversation: they change what the same commands
+/// do (Python or Node environment, Rust toolchain, cloud account, cluster), and hold no
+/// secrets.
+const CAPTURED_ENV: &[&str] = &[
+    "VIRTUAL_ENV",
+    "CONDA_DEFAULT_ENV",
+    "NODE_ENV",
+    "RUSTUP_TOOLCHAIN",
+    "AWS_PROFILE",
+    "KUBECONFIG",
+];
+
+/// Workspace, branch, and environment a conversation was last saved from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversationOrigin {
+    pub workspace_root: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Set variables among `CAPTURED_ENV`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl ConversationOrigin {
+    /// The origin of a conversation held now in `workspace` (runs `git branch`).
+    pub fn capture(workspace: &Workspace) -> Self {
+        Self::from_parts(
+            &workspace.root,
+            workspace::current_branch(&workspace.root),
+            |name| env::var(name).ok(),
+        )
+    }
+
+    /// `capture` with the branch and environment given (for tests).
+    pub fn from_parts(
+        root: &Path,
+        git_branch: Option<String>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let env = CAPTURED_ENV
+            .iter()
+            .filter_map(|name| {
+                var(name)
+                    .filter(|v| !v.is_empty())
+                    .map(|v| (name.to_string(), v))
+            })
+            .collect();
+        Self {
+            workspace_root: root.to_path_buf(),
+            git_branch,
+            env,
+        }
+    }
+
+    /// Labeled values describing the origin, for the history selector detail pane.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut lines = vec![("Workspace", self.workspace_root.display().to_string())];
+        if let Some(branch) = &self.git_branch {
+            lines.push(("Branch", branch.clone()));
+        }
+        if !self.env.is_empty() {
+            let vars: Vec<String> = self
+                .env
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            lines.push(("Env", vars.join("  ")));
+        }
+        lines
+    }
+
+    /// Warning for resuming the conversation in the workspace at `root`, when it was held
+    /// in another one: paths and commands in it may not apply.
+    pub fn mismatch_warning(&self, root: &Path) -> Option<String> {
+        if self.workspace_root.as_os_str().is_empty() || self.workspace_root == root {
+            return None;
+        }
+        let branch = self
+            .git_branch
+            .as_ref()
+            .map(|b| format!(" (branch {})", b))
+            .unwrap_or_default();
+        Some(format!(
+            "This conversation ran in {}{}, not in the current workspace ({}): the files and commands it mentions may differ here.",
+            self.workspace_root.display(),
+            branch,
+            root.display()
+        ))
+    }
+}
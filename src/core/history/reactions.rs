@@ -0,0 +1,154 @@
+//! Reactions to assistant replies (good, bad, flag), saved with the conversation as a
+//! `reaction` field of the message, and exported across conversations (`history
+//! reactions`) as JSON Lines, to build an evaluation set of model behavior.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::message;
+
+use super::{index, storage};
+
+/// Reaction to an assistant reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Good,
+    Bad,
+    /// Worth a second look, neither good nor bad yet.
+    Flag,
+}
+
+impl Reaction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Reaction::Good => "good",
+            Reaction::Bad => "bad",
+            Reaction::Flag => "flag",
+        }
+    }
+
+    /// Mark shown next to the message.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Reaction::Good => "👍",
+            Reaction::Bad => "👎",
+            Reaction::Flag => "🚩",
+        }
+    }
+
+    /// Next reaction of the cycle none → good → bad → flag → none.
+    pub fn cycle(current: Option<Reaction>) -> Option<Reaction> {
+        match current {
+            None => Some(Reaction::Good),
+            Some(Reaction::Good) => Some(Reaction::Bad),
+            Some(Reaction::Bad) => Some(Reaction::Flag),
+            Some(Reaction::Flag) => None,
+        }
+    }
+
+    /// Reaction of a persisted message, if any.
+    pub fn of_message(msg: &Value) -> Option<Reaction> {
+        msg.get("reaction")
+            .and_then(|r| serde_json::from_value(r.clone()).ok())
+    }
+}
+
+impl fmt::Display for Reaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for Reaction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "good" => Ok(Reaction::Good),
+            "bad" => Ok(Reaction::Bad),
+            "flag" => Ok(Reaction::Flag),
+            other => Err(format!(
+                "unknown reaction '{}' (expected good, bad, or flag)",
+                other
+            )),
+        }
+    }
+}
+
+/// An assistant reply with a reaction, as exported.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReactedMessage {
+    pub conversation_id: String,
+    pub conversation_title: String,
+    /// Model the conversation was saved with, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub reaction: Reaction,
+    /// The user message the reply answers (None when the reply starts the conversation).
+    pub prompt: Option<String>,
+    pub response: String,
+}
+
+/// Reacted assistant replies of persisted `messages`, with the user prompt each answers:
+/// (reaction, prompt, response). `only` keeps one kind of reaction.
+fn reacted_replies(
+    messages: &[Value],
+    only: Option<Reaction>,
+) -> Vec<(Reaction, Option<String>, String)> {
+    let mut prompt = None;
+    let mut replies = Vec::new();
+    for msg in messages {
+        match msg.get("role").and_then(|r| r.as_str()) {
+            Some("user") => prompt = message::extract_content(msg),
+            Some("assistant") => {
+                let Some(reaction) = Reaction::of_message(msg) else {
+                    continue;
+                };
+                if only.is_some_and(|o| o != reaction) {
+                    continue;
+                }
+                replies.push((
+                    reaction,
+                    prompt.clone(),
+                    message::extract_content(msg).unwrap_or_default(),
+                ));
+            }
+            _ => {}
+        }
+    }
+    replies
+}
+
+/// Reacted assistant replies of every saved conversation, most recent conversation first.
+/// `only` keeps one kind of reaction. Conversations that cannot be read are skipped.
+pub fn export_reactions(only: Option<Reaction>) -> io::Result<Vec<ReactedMessage>> {
+    let mut exported = Vec::new();
+    for meta in index::list_conversations()? {
+        let Some(messages) = storage::read_conv_messages(&meta.id) else {
+            continue;
+        };
+        let replies = reacted_replies(&messages, only);
+        if replies.is_empty() {
+            continue;
+        }
+        let model = storage::read_conv_settings(&meta.id).and_then(|s| s.model_id);
+        exported.extend(
+            replies
+                .into_iter()
+                .map(|(reaction, prompt, response)| ReactedMessage {
+                    conversation_id: meta.id.clone(),
+                    conversation_title: meta.title.clone(),
+                    model: model.clone(),
+                    reaction,
+                    prompt,
+                    response,
+                }),
+        );
+    }
+    Ok(exported)
+}
@@ -0,0 +1,110 @@
+//! Retention of the conversation history: which conversations a prune removes. Besides the
+//! overall cap (`MY_OPEN_CLAUDE_MAX_CONVERSATIONS`), conversations can expire after a number
+//! of days and be capped per workspace. Archived conversations are never removed, and do not
+//! count toward the caps.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::core::config::Config;
+
+use super::index::ConversationMeta;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Retention limits besides the overall cap. 0 disables a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Days after its last update that a conversation is removed.
+    pub max_age_days: u32,
+    /// Conversations kept per workspace (the most recently updated ones).
+    pub max_per_workspace: u32,
+}
+
+/// Why a conversation is removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneReason {
+    /// Not updated for more than this many days.
+    Age { days: u32 },
+    /// Past the cap of conversations held in this workspace.
+    WorkspaceCap { workspace: String, max: u32 },
+    /// Past the overall cap.
+    Count { max: u32 },
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneReason::Age { days } => write!(f, "older than {} days", days),
+            PruneReason::WorkspaceCap { workspace, max } => {
+                write!(f, "more than {} conversations in {}", max, workspace)
+            }
+            PruneReason::Count { max } => write!(f, "more than {} conversations", max),
+        }
+    }
+}
+
+/// A conversation removed by a prune, and why.
+#[derive(Debug, Clone)]
+pub struct PruneCandidate {
+    pub meta: ConversationMeta,
+    pub reason: PruneReason,
+}
+
+/// Conversations to remove under the limits of `config` at time `now` (seconds since the
+/// epoch), most recently updated first. Conversations are kept newest first until a limit
+/// is reached; those without a recorded workspace are only subject to the age limit and
+/// the overall cap.
+pub(super) fn plan(
+    conversations: &[ConversationMeta],
+    config: &Config,
+    now: u64,
+) -> Vec<PruneCandidate> {
+    let retention = config.history_retention;
+    let mut sorted: Vec<&ConversationMeta> = conversations.iter().collect();
+    sorted.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+    let mut kept = 0u32;
+    let mut kept_per_workspace: HashMap<&str, u32> = HashMap::new();
+    let mut removed = Vec::new();
+    for meta in sorted.into_iter().filter(|c| !c.archived) {
+        let workspace_kept = meta
+            .workspace
+            .as_deref()
+            .map_or(0, |w| kept_per_workspace.get(w).copied().unwrap_or(0));
+        let reason = if retention.max_age_days > 0
+            && now.saturating_sub(meta.updated_at)
+                > u64::from(retention.max_age_days) * SECONDS_PER_DAY
+        {
+            Some(PruneReason::Age {
+                days: retention.max_age_days,
+            })
+        } else if retention.max_per_workspace > 0
+            && let Some(workspace) = &meta.workspace
+            && workspace_kept >= retention.max_per_workspace
+        {
+            Some(PruneReason::WorkspaceCap {
+                workspace: workspace.clone(),
+                max: retention.max_per_workspace,
+            })
+        } else if config.max_conversations > 0 && kept >= config.max_conversations {
+            Some(PruneReason::Count {
+                max: config.max_conversations,
+            })
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => removed.push(PruneCandidate {
+                meta: meta.clone(),
+                reason,
+            }),
+            None => {
+                kept += 1;
+                if let Some(workspace) = &meta.workspace {
+                    *kept_per_workspace.entry(workspace).or_default() += 1;
+                }
+            }
+        }
+    }
+    removed
+}
@@ -1,23 +1,54 @@
 //! Index and conversation file persistence (index.json, conv_*.json).
+//!
+//! Several instances (one per terminal) share these files. Every change of the index runs
+//! under an advisory lock and applies to the index as it is on disk at that moment, so the
+//! entries other instances added meanwhile are kept. The lock is taken on a sidecar file
+//! (index.json.lock) rather than on index.json itself, which each save replaces.
 
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::core::paths;
 
+use super::ConversationSettings;
 use super::index::ConversationMeta;
+use super::ui_state::UiState;
 
-fn index_path() -> Option<std::path::PathBuf> {
+/// How long a change waits for another instance to release the index lock.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pause between two attempts to take the index lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+/// Max display columns of the titles given to conversations when rebuilding the index.
+const REBUILT_TITLE_MAX_LEN: usize = 60;
+
+/// Set when a change had to wait for the lock of another instance.
+static LOCK_CONTENDED: AtomicBool = AtomicBool::new(false);
+
+/// Hash of each conversation file as this process last loaded or wrote it, to notice when
+/// another instance changed it meanwhile.
+static SEEN: LazyLock<Mutex<HashMap<String, u64>>> = LazyLock::new(Default::default);
+
+fn index_path() -> Option<PathBuf> {
     paths::data_dir().map(|d| d.join("index.json"))
 }
 
-fn conv_path(id: &str) -> Option<std::path::PathBuf> {
+fn conv_path(id: &str) -> Option<PathBuf> {
     paths::data_dir().map(|d| d.join(format!("conv_{}.json", id)))
 }
 
+fn ui_state_path(id: &str) -> Option<PathBuf> {
+    paths::data_dir().map(|d| d.join(format!("ui_{}.json", id)))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) struct IndexFile {
     pub(super) conversations: Vec<ConversationMeta>,
@@ -26,9 +57,11 @@ pub(super) struct IndexFile {
 #[derive(Debug, Serialize, Deserialize)]
 struct ConvFile {
     messages: Vec<Value>,
+    #[serde(default, skip_serializing_if = "ConversationSettings::is_empty")]
+    settings: ConversationSettings,
 }
 
-pub(super) fn ensure_data_dir() -> io::Result<std::path::PathBuf> {
+pub(super) fn ensure_data_dir() -> io::Result<PathBuf> {
     let dir = paths::data_dir()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory"))?;
     fs::create_dir_all(&dir)?;
@@ -59,42 +92,203 @@ pub(super) fn load_index() -> io::Result<IndexFile> {
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
 }
 
-pub(super) fn save_index(index: &IndexFile) -> io::Result<()> {
-    ensure_data_dir()?;
+/// Write `data` to a temporary file next to `path` and rename it over `path`, so readers
+/// never see a partial file. The temporary name includes the process ID: two instances
+/// saving at once do not write into the same one.
+fn write_replacing(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, data)?;
+    fs::rename(tmp, path)
+}
+
+fn save_index(index: &IndexFile) -> io::Result<()> {
     let path =
         index_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No index path"))?;
     let json = serde_json::to_string_pretty(index)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    let tmp = path.with_extension("tmp");
-    fs::write(&tmp, json)?;
-    fs::rename(tmp, path)?;
-    Ok(())
+    write_replacing(&path, json.as_bytes())
 }
 
-pub(super) fn read_conv_messages(id: &str) -> Option<Vec<Value>> {
+/// Take the advisory lock on the index, waiting up to `LOCK_TIMEOUT` for another instance
+/// to release it. The lock lasts as long as the returned file is open.
+fn lock_index(dir: &Path) -> io::Result<fs::File> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("index.json.lock"))?;
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(fs::TryLockError::WouldBlock) => {
+                LOCK_CONTENDED.store(true, Ordering::Relaxed);
+                if Instant::now() >= deadline {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WouldBlock,
+                        "Conversation history is locked by another instance",
+                    ));
+                }
+                std::thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(e),
+        }
+    }
+}
+
+/// True (once) when a change since the last call had to wait for another instance.
+pub(super) fn take_lock_contention() -> bool {
+    LOCK_CONTENDED.swap(false, Ordering::Relaxed)
+}
+
+/// Apply `f` to the index as it is on disk and save the result, under the index lock:
+/// concurrent instances apply their changes one after the other instead of overwriting
+/// each other's. An unreadable index is rebuilt from the conversation files first.
+pub(super) fn update_index<R>(f: impl FnOnce(&mut IndexFile) -> io::Result<R>) -> io::Result<R> {
+    let dir = ensure_data_dir()?;
+    let _lock = lock_index(&dir)?;
+    let mut index = match load_index() {
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            log::warn!("Conversation index is unreadable ({}), rebuilding it", e);
+            rebuild_index(&dir)?
+        }
+        other => other?,
+    };
+    let result = f(&mut index)?;
+    save_index(&index)?;
+    Ok(result)
+}
+
+/// Index of the conversation files in `dir`, after keeping the unreadable index as
+/// index.json.corrupt. Titles come from the first user message (renamed titles and archive
+/// marks are lost), dates from the file modification times.
+fn rebuild_index(dir: &Path) -> io::Result<IndexFile> {
+    if let Some(path) = index_path() {
+        fs::copy(&path, path.with_extension("json.corrupt"))?;
+    }
+    let mut conversations = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name();
+        let Some(id) = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("conv_"))
+            .and_then(|n| n.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        let Some(file) = read_conv_file(id) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        conversations.push(ConversationMeta {
+            id: id.to_string(),
+            title: super::first_message_preview(&file.messages, REBUILT_TITLE_MAX_LEN),
+            created_at: modified,
+            updated_at: modified,
+            workspace: file
+                .settings
+                .origin
+                .map(|o| o.workspace_root.display().to_string()),
+            archived: false,
+        });
+    }
+    Ok(IndexFile { conversations })
+}
+
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn mark_seen(id: &str, data: &[u8]) {
+    SEEN.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id.to_string(), content_hash(data));
+}
+
+/// True when the file of conversation `id` differs from the version this process last
+/// loaded or wrote, i.e. another instance saved the conversation meanwhile.
+pub(super) fn changed_elsewhere(id: &str) -> bool {
+    let Some(seen) = SEEN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(id)
+        .copied()
+    else {
+        return false;
+    };
+    conv_path(id)
+        .and_then(|p| fs::read(p).ok())
+        .is_some_and(|data| content_hash(&data) != seen)
+}
+
+fn read_conv_file(id: &str) -> Option<ConvFile> {
     let path = conv_path(id)?;
     let data = fs::read_to_string(path).ok()?;
-    let file: ConvFile = serde_json::from_str(&data).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub(super) fn read_conv_messages(id: &str) -> Option<Vec<Value>> {
+    read_conv_file(id).map(|file| file.messages)
+}
+
+/// Messages of conversation `id`, loaded to be continued: later saves check that no other
+/// instance changed the file since (see `changed_elsewhere`).
+pub(super) fn open_conv_messages(id: &str) -> Option<Vec<Value>> {
+    let data = fs::read(conv_path(id)?).ok()?;
+    let file: ConvFile = serde_json::from_slice(&data).ok()?;
+    mark_seen(id, &data);
     Some(file.messages)
 }
 
-pub(super) fn write_conv_file(id: &str, messages: &[Value]) -> io::Result<()> {
+pub(super) fn read_conv_settings(id: &str) -> Option<ConversationSettings> {
+    read_conv_file(id).map(|file| file.settings)
+}
+
+pub(super) fn write_conv_file(
+    id: &str,
+    messages: &[Value],
+    settings: &ConversationSettings,
+) -> io::Result<()> {
     let path =
         conv_path(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No conv path"))?;
     let file = ConvFile {
         messages: messages.to_vec(),
+        settings: settings.clone(),
     };
     let json = serde_json::to_string_pretty(&file)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    let tmp = path.with_extension("tmp");
-    fs::write(&tmp, json)?;
-    fs::rename(tmp, path)?;
+    write_replacing(&path, json.as_bytes())?;
+    mark_seen(id, json.as_bytes());
     Ok(())
 }
 
+pub(super) fn read_ui_state(id: &str) -> Option<UiState> {
+    let data = fs::read_to_string(ui_state_path(id)?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub(super) fn write_ui_state(id: &str, state: &UiState) -> io::Result<()> {
+    let dir = ensure_data_dir()?;
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    write_replacing(&dir.join(format!("ui_{}.json", id)), json.as_bytes())
+}
+
 /// Remove a conversation file by ID. Returns Ok(()) if removed, path unavailable, or file already gone.
 /// Treats NotFound as success: goal is "file should not exist", and it doesn't.
 pub(super) fn remove_conv_file(id: &str) -> io::Result<()> {
+    SEEN.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    // The view state is disposable: a leftover file is harmless.
+    if let Some(p) = ui_state_path(id) {
+        let _ = fs::remove_file(p);
+    }
     let Some(p) = conv_path(id) else {
         return Ok(());
     };
@@ -9,8 +9,12 @@ use std::collections::HashMap;
 
 use crate::core::history::index::ConversationMeta;
 use crate::core::history::{
-    api_messages_from_persisted, filter_conversations_with_content, first_message_preview,
-    load_conversation, save_conversation,
+    ConversationOrigin, ConversationSettings, Reaction, UiState, api_messages_from_persisted,
+    branch_conversation, delete_conversation, export_reactions, filter_conversations_with_content,
+    find_similar_conversation, first_exchange, first_message_preview, is_near_duplicate,
+    list_conversations, load_conversation, load_conversation_settings, load_ui_state,
+    prune_history, rename_conversation, save_conversation, save_ui_state, set_archived,
+    take_lock_contention,
 };
 use async_openai::config::OpenAIConfig;
 
@@ -63,12 +67,16 @@ fn filter_conversations_empty_query_returns_all() {
             title: "Chat 1".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
         ConversationMeta {
             id: "2".to_string(),
             title: "Chat 2".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
     ];
     let cache = HashMap::new();
@@ -84,12 +92,16 @@ fn filter_conversations_match_by_title() {
             title: "Hello world".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
         ConversationMeta {
             id: "2".to_string(),
             title: "Other chat".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
     ];
     let cache = HashMap::new();
@@ -105,6 +117,8 @@ fn filter_conversations_match_by_id() {
         title: "Chat".to_string(),
         created_at: 0,
         updated_at: 0,
+        workspace: None,
+        archived: false,
     }];
     let cache = HashMap::new();
     let out = filter_conversations_with_content(&convs, "abc", &cache);
@@ -120,12 +134,16 @@ fn filter_conversations_match_by_content() {
             title: "Chat A".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
         ConversationMeta {
             id: "2".to_string(),
             title: "Chat B".to_string(),
             created_at: 0,
             updated_at: 0,
+            workspace: None,
+            archived: false,
         },
     ];
     let mut cache = HashMap::new();
@@ -138,14 +156,67 @@ fn filter_conversations_match_by_content() {
     assert_eq!(out[0].id, "2");
 }
 
+#[test]
+fn filter_conversations_ranks_fuzzy_title_matches_first() {
+    let meta = |id: &str, title: &str| ConversationMeta {
+        id: id.to_string(),
+        title: title.to_string(),
+        created_at: 0,
+        updated_at: 0,
+        workspace: None,
+        archived: false,
+    };
+    let convs = vec![
+        meta("1", "Notes"),
+        meta("2", "Refactor the parser"),
+        meta("3", "Fix the README"),
+    ];
+    let mut cache = HashMap::new();
+    cache.insert("1".to_string(), "fix the parser tests".to_string());
+    let out = filter_conversations_with_content(&convs, "fix", &cache);
+    let ids: Vec<&str> = out.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, vec!["3", "1"]);
+    let out = filter_conversations_with_content(&convs, "rfp", &cache);
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].id, "2");
+}
+
 fn test_config() -> Config {
     Config {
         openai_config: OpenAIConfig::new(),
         model_id: "test".to_string(),
+        model_source: crate::core::config::ModelSource::Default,
         base_url: "https://test".to_string(),
         api_key: "test".to_string(),
         max_conversations: 10,
+        history_retention: Default::default(),
         show_timestamps: false,
+        show_usage: false,
+        streaming: true,
+        prompt_cache: true,
+        auto_continue: 0,
+        text_tools: true,
+        disabled_tools: Vec::new(),
+        webhook_urls: Vec::new(),
+        post_hooks: Vec::new(),
+        desktop_notify_after: None,
+        prompt_layout: Default::default(),
+        spend_limits: Default::default(),
+        embedding_model: None,
+        title_model: None,
+        provider: crate::core::config::Provider::OpenRouter,
+        denied_paths: Vec::new(),
+        redact_tool_output: true,
+        redact_patterns: Vec::new(),
+        tool_timeouts: Default::default(),
+        gist_token: None,
+        remote: None,
+        openrouter: Default::default(),
+        generation: Default::default(),
+        profile: None,
+        theme: None,
+        network: Default::default(),
+        dry_run: false,
     }
 }
 
@@ -171,7 +242,13 @@ fn save_conversation_empty_messages_new_returns_err() {
 
     let config = test_config();
     let messages: Vec<Value> = vec![];
-    let result = save_conversation(None, "title", &messages, &config);
+    let result = save_conversation(
+        None,
+        "title",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    );
 
     assert!(result.is_err());
     let err = result.unwrap_err();
@@ -195,7 +272,14 @@ fn save_then_load_roundtrip() {
         serde_json::json!({"role": "assistant", "content": "Hi"}),
     ];
 
-    let id = save_conversation(None, "Test Chat", &messages, &config).expect("save should succeed");
+    let id = save_conversation(
+        None,
+        "Test Chat",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save should succeed");
     assert!(!id.is_empty());
 
     let loaded = load_conversation(&id).expect("load should return Some");
@@ -204,6 +288,39 @@ fn save_then_load_roundtrip() {
     assert_eq!(loaded[1]["role"], "assistant");
 }
 
+#[test]
+fn ui_state_is_restored_and_deleted_with_the_conversation() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let messages = vec![serde_json::json!({"role": "user", "content": "Hello"})];
+    let id = save_conversation(
+        None,
+        "UI state",
+        &messages,
+        &ConversationSettings::default(),
+        &test_config(),
+    )
+    .expect("save should succeed");
+    assert_eq!(load_ui_state(&id), None);
+
+    let state = UiState {
+        scroll_line: Some(42),
+        expanded_tool_results: vec![3, 7],
+        split_pane: true,
+        truncate_code: true,
+    };
+    save_ui_state(&id, &state).expect("save ui state");
+    assert_eq!(load_ui_state(&id), Some(state));
+
+    delete_conversation(&id).expect("delete");
+    assert_eq!(load_ui_state(&id), None);
+}
+
 #[test]
 fn save_then_load_preserves_tool_log() {
     let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
@@ -221,8 +338,14 @@ fn save_then_load_preserves_tool_log() {
         serde_json::json!({"role": "assistant", "content": "Here is the code..."}),
     ];
 
-    let id =
-        save_conversation(None, "Test with tool_log", &messages, &config).expect("save succeed");
+    let id = save_conversation(
+        None,
+        "Test with tool_log",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save succeed");
     let loaded = load_conversation(&id).expect("load Some");
     assert_eq!(loaded.len(), 3);
     assert_eq!(loaded[0]["role"], "user");
@@ -236,6 +359,133 @@ fn save_then_load_preserves_tool_log() {
     assert_eq!(api_only[1]["role"], "assistant");
 }
 
+#[test]
+fn save_then_load_preserves_tool_result_views() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let data_dir = tmp.path().join("conversations");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", &data_dir);
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let messages = vec![
+        serde_json::json!({"role": "user", "content": "List the sources"}),
+        serde_json::json!({"role": "tool_log", "content": "→ ListDir ."}),
+        serde_json::json!({
+            "role": "tool_result",
+            "tool": "ListDir",
+            "view": {"kind": "tree", "root": ".", "entries": ["src/"], "omitted": 0},
+        }),
+        serde_json::json!({"role": "assistant", "content": "There is a src/ directory."}),
+    ];
+
+    let id = save_conversation(
+        None,
+        "Test with tool_result",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save succeed");
+    let loaded = load_conversation(&id).expect("load Some");
+    assert_eq!(loaded.len(), 4);
+    assert_eq!(loaded[2]["role"], "tool_result");
+    assert_eq!(loaded[2]["tool"], "ListDir");
+    assert_eq!(loaded[2]["view"]["entries"][0], "src/");
+
+    // The view is for the history only: the model never sees it.
+    let api_only = api_messages_from_persisted(&loaded);
+    assert_eq!(api_only.len(), 2);
+    assert_eq!(api_only[0]["role"], "user");
+    assert_eq!(api_only[1]["role"], "assistant");
+}
+
+#[test]
+fn save_then_load_restores_settings() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let data_dir = tmp.path().join("conversations");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", &data_dir);
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let messages = vec![serde_json::json!({"role": "user", "content": "Plan the migration"})];
+    let settings = ConversationSettings {
+        model_id: Some("anthropic/claude-haiku-4.5".to_string()),
+        mode: Some("Plan".to_string()),
+        params: crate::core::generation::GenerationParams {
+            temperature: Some(0.2),
+            ..Default::default()
+        },
+        origin: Some(ConversationOrigin::from_parts(
+            std::path::Path::new("/work/project"),
+            Some("main".to_string()),
+            |name| (name == "VIRTUAL_ENV").then(|| "/work/project/.venv".to_string()),
+        )),
+    };
+    let id = save_conversation(None, "Migration", &messages, &settings, &config).expect("save");
+    assert_eq!(load_conversation_settings(&id), settings);
+
+    // Conversations saved without settings (or before they existed) load with none.
+    let plain = save_conversation(
+        None,
+        "Plain",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    let file = std::fs::read_to_string(data_dir.join(format!("conv_{}.json", plain))).unwrap();
+    assert!(!file.contains("settings"));
+    assert!(load_conversation_settings(&plain).is_empty());
+    assert!(load_conversation_settings("missing").is_empty());
+}
+
+#[test]
+fn save_then_load_keeps_pinned_flag_out_of_api_messages() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let messages = vec![
+        serde_json::json!({"role": "user", "content": "Use PostgreSQL", "pinned": true}),
+        serde_json::json!({"role": "assistant", "content": "Noted", "pinned": false}),
+    ];
+    let id = save_conversation(
+        None,
+        "Pins",
+        &messages,
+        &ConversationSettings::default(),
+        &test_config(),
+    )
+    .expect("save succeed");
+    let loaded = load_conversation(&id).expect("load Some");
+    assert_eq!(loaded[0]["pinned"], true);
+    assert!(loaded[1].get("pinned").is_none());
+    let api_only = api_messages_from_persisted(&loaded);
+    assert!(api_only[0].get("pinned").is_none());
+}
+
+#[test]
+fn api_messages_from_persisted_starts_after_context_reset() {
+    let persisted = vec![
+        serde_json::json!({"role": "user", "content": "old question"}),
+        serde_json::json!({"role": "assistant", "content": "old answer"}),
+        serde_json::json!({"role": "context_reset"}),
+        serde_json::json!({"role": "user", "content": "fresh start"}),
+    ];
+    let api = api_messages_from_persisted(&persisted);
+    assert_eq!(api.len(), 1);
+    assert_eq!(api[0]["content"], "fresh start");
+}
+
 #[test]
 fn load_conversation_nonexistent_returns_none() {
     let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
@@ -262,7 +512,14 @@ fn load_conversation_invalid_json_returns_none() {
 
     let config = test_config();
     let messages = vec![serde_json::json!({"role": "user", "content": "Hi"})];
-    let id = save_conversation(None, "Title", &messages, &config).expect("save ok");
+    let id = save_conversation(
+        None,
+        "Title",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save ok");
 
     let conv_path = data_dir.join(format!("conv_{}.json", id));
     std::fs::write(&conv_path, "not valid json {{{").expect("write");
@@ -270,3 +527,519 @@ fn load_conversation_invalid_json_returns_none() {
     let loaded = load_conversation(&id);
     assert!(loaded.is_none());
 }
+
+#[test]
+fn is_near_duplicate_ignores_case_punctuation_and_small_edits() {
+    assert!(is_near_duplicate(
+        "Explain the borrow checker in Rust.",
+        "explain the borrow checker in rust"
+    ));
+    assert!(is_near_duplicate(
+        "Add a --json flag to the history list command and update the docs",
+        "Add a --json flag to the history list command and update docs"
+    ));
+    assert!(!is_near_duplicate(
+        "Explain the borrow checker",
+        "Write tests for the parser"
+    ));
+    assert!(!is_near_duplicate("", ""));
+}
+
+#[test]
+fn find_similar_conversation_matches_saved_first_message() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let saved = vec![
+        serde_json::json!({"role": "user", "content": "Refactor the config loader"}),
+        serde_json::json!({"role": "assistant", "content": "Done"}),
+    ];
+    let id = save_conversation(
+        None,
+        "Refactor",
+        &saved,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+
+    let new = vec![serde_json::json!({"role": "user", "content": "refactor the config loader!"})];
+    assert_eq!(find_similar_conversation(&new).map(|m| m.id), Some(id));
+
+    let other = vec![serde_json::json!({"role": "user", "content": "Fix the flaky test"})];
+    assert!(find_similar_conversation(&other).is_none());
+}
+
+#[test]
+fn branch_conversation_saves_prefix_as_new_conversation() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let messages = vec![
+        serde_json::json!({"role": "user", "content": "Pick a database"}),
+        serde_json::json!({"role": "assistant", "content": "Postgres"}),
+        serde_json::json!({"role": "user", "content": "Write the schema"}),
+        serde_json::json!({"role": "assistant", "content": "CREATE TABLE ..."}),
+    ];
+    let original = save_conversation(
+        None,
+        "Pick a database",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+
+    let branch = branch_conversation(
+        &messages,
+        2,
+        "Pick a database",
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("branch");
+    assert_ne!(branch, original);
+    assert_eq!(load_conversation(&branch).expect("branch").len(), 2);
+    assert_eq!(load_conversation(&original).expect("original").len(), 4);
+
+    let again = branch_conversation(
+        &messages,
+        1,
+        "Pick a database (branch)",
+        &ConversationSettings::default(),
+        &config,
+    )
+    .unwrap();
+    let titles: Vec<(String, String)> = list_conversations()
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.id, m.title))
+        .collect();
+    assert!(titles.contains(&(branch, "Pick a database (branch)".to_string())));
+    assert!(titles.contains(&(again, "Pick a database (branch)".to_string())));
+}
+
+#[test]
+fn save_keeps_renamed_title_of_existing_conversation() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let mut messages = vec![
+        serde_json::json!({"role": "user", "content": "why is the build slow"}),
+        serde_json::json!({"role": "assistant", "content": ""}),
+        serde_json::json!({"role": "assistant", "content": "Incremental builds are off."}),
+    ];
+    assert_eq!(
+        first_exchange(&messages),
+        Some((
+            "why is the build slow".to_string(),
+            "Incremental builds are off.".to_string()
+        ))
+    );
+    let id = save_conversation(
+        None,
+        "why is the build slow",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    rename_conversation(&id, "Speed up slow builds").expect("rename");
+
+    messages.push(serde_json::json!({"role": "user", "content": "turn them on"}));
+    save_conversation(
+        Some(&id),
+        "why is the build slow",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    let meta = list_conversations()
+        .unwrap()
+        .into_iter()
+        .find(|m| m.id == id)
+        .unwrap();
+    assert_eq!(meta.title, "Speed up slow builds");
+    assert_eq!(first_exchange(&messages[..1]), None);
+}
+
+#[test]
+fn api_messages_from_persisted_starts_with_summary_and_kept_turns() {
+    let persisted = vec![
+        serde_json::json!({"role": "user", "content": "first"}),
+        serde_json::json!({"role": "assistant", "content": "one"}),
+        serde_json::json!({"role": "tool_log", "content": "→ Read: a.rs"}),
+        serde_json::json!({"role": "user", "content": "second"}),
+        serde_json::json!({"role": "assistant", "content": "two"}),
+        serde_json::json!({"role": "context_summary", "content": "- did one", "kept_turns": 1}),
+        serde_json::json!({"role": "user", "content": "third"}),
+    ];
+    let api = api_messages_from_persisted(&persisted);
+    assert_eq!(api.len(), 4);
+    assert!(crate::core::llm::is_summary(&api[0]));
+    assert!(api[0]["content"].as_str().unwrap().ends_with("- did one"));
+    assert_eq!(api[1]["content"], "second");
+    assert_eq!(api[3]["content"], "third");
+}
+
+#[test]
+fn concurrent_saves_keep_every_conversation() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    // Each save reloads the index under the lock, so none drops the entries of the others.
+    let config = test_config();
+    std::thread::scope(|s| {
+        for i in 0..8 {
+            let config = &config;
+            s.spawn(move || {
+                let messages =
+                    vec![serde_json::json!({"role": "user", "content": format!("task {}", i)})];
+                save_conversation(
+                    None,
+                    &format!("task {}", i),
+                    &messages,
+                    &ConversationSettings::default(),
+                    config,
+                )
+                .expect("save");
+            });
+        }
+    });
+    assert_eq!(list_conversations().unwrap().len(), 8);
+}
+
+#[test]
+fn save_waits_for_the_lock_of_another_instance() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let data_dir = tmp.path().join("conversations");
+    std::fs::create_dir_all(&data_dir).expect("create dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", &data_dir);
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let held = std::fs::File::create(data_dir.join("index.json.lock")).unwrap();
+    held.lock().unwrap();
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        drop(held);
+    });
+    take_lock_contention();
+    let messages = vec![serde_json::json!({"role": "user", "content": "hello"})];
+    save_conversation(
+        None,
+        "hello",
+        &messages,
+        &ConversationSettings::default(),
+        &test_config(),
+    )
+    .expect("save after the lock is released");
+    release.join().unwrap();
+    assert!(take_lock_contention());
+    assert!(!take_lock_contention());
+    assert_eq!(list_conversations().unwrap().len(), 1);
+}
+
+#[test]
+fn save_of_conversation_changed_elsewhere_goes_to_a_copy() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let data_dir = tmp.path().join("conversations");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", &data_dir);
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let mut messages = vec![serde_json::json!({"role": "user", "content": "plan the release"})];
+    let id = save_conversation(
+        None,
+        "plan the release",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    let loaded = load_conversation(&id).unwrap();
+
+    // Another instance continues the same conversation.
+    let path = data_dir.join(format!("conv_{}.json", id));
+    let theirs = r#"{"messages": [{"role": "user", "content": "plan the release"}, {"role": "assistant", "content": "theirs"}]}"#;
+    std::fs::write(&path, theirs).unwrap();
+
+    messages = loaded;
+    messages.push(serde_json::json!({"role": "assistant", "content": "ours"}));
+    let saved = save_conversation(
+        Some(&id),
+        "plan the release",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    assert_ne!(saved, id);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), theirs);
+    assert_eq!(load_conversation(&saved).unwrap()[1]["content"], "ours");
+    let titles: Vec<String> = list_conversations()
+        .unwrap()
+        .into_iter()
+        .map(|m| m.title)
+        .collect();
+    assert!(titles.contains(&"plan the release (copy)".to_string()));
+
+    // The copy is now the version this instance works on: later saves update it.
+    messages.push(serde_json::json!({"role": "user", "content": "and the changelog"}));
+    let again = save_conversation(
+        Some(&saved),
+        "plan the release",
+        &messages,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    assert_eq!(again, saved);
+}
+
+#[test]
+fn corrupt_index_is_rebuilt_from_conversation_files() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let data_dir = tmp.path().join("conversations");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", &data_dir);
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let first = vec![serde_json::json!({"role": "user", "content": "first question"})];
+    let first_id = save_conversation(
+        None,
+        "first question",
+        &first,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    std::fs::write(data_dir.join("index.json"), "{\"conversations\": [").unwrap();
+    assert!(list_conversations().is_err());
+
+    let second = vec![serde_json::json!({"role": "user", "content": "second question"})];
+    save_conversation(
+        None,
+        "second question",
+        &second,
+        &ConversationSettings::default(),
+        &config,
+    )
+    .expect("save");
+    let list = list_conversations().unwrap();
+    assert_eq!(list.len(), 2);
+    let rebuilt = list.iter().find(|m| m.id == first_id).unwrap();
+    assert_eq!(rebuilt.title, "first question");
+    assert!(data_dir.join("index.json.corrupt").exists());
+}
+
+#[test]
+fn reactions_are_saved_with_replies_and_exported_with_their_prompt() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let messages = vec![
+        serde_json::json!({"role": "user", "content": "Sort this list", "reaction": "good"}),
+        serde_json::json!({"role": "assistant", "content": "Use sort()", "reaction": "bad"}),
+        serde_json::json!({"role": "user", "content": "In place?"}),
+        serde_json::json!({"role": "assistant", "content": "Use sort_unstable()", "reaction": "flag"}),
+        serde_json::json!({"role": "assistant", "content": "Or sort_by_key()"}),
+    ];
+    let settings = ConversationSettings {
+        model_id: Some("openai/gpt-4o".to_string()),
+        ..Default::default()
+    };
+    let id = save_conversation(None, "Sorting", &messages, &settings, &test_config())
+        .expect("save succeed");
+    let loaded = load_conversation(&id).expect("load Some");
+    // Only assistant replies keep a reaction.
+    assert!(loaded[0].get("reaction").is_none());
+    assert_eq!(Reaction::of_message(&loaded[1]), Some(Reaction::Bad));
+    assert!(
+        api_messages_from_persisted(&loaded)[1]
+            .get("reaction")
+            .is_none()
+    );
+
+    let all = export_reactions(None).expect("export");
+    let reactions: Vec<Reaction> = all.iter().map(|m| m.reaction).collect();
+    assert_eq!(reactions, vec![Reaction::Bad, Reaction::Flag]);
+
+    let flagged = export_reactions(Some(Reaction::Flag)).expect("export");
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].conversation_id, id);
+    assert_eq!(flagged[0].conversation_title, "Sorting");
+    assert_eq!(flagged[0].model.as_deref(), Some("openai/gpt-4o"));
+    assert_eq!(flagged[0].prompt.as_deref(), Some("In place?"));
+    assert_eq!(flagged[0].response, "Use sort_unstable()");
+
+    assert_eq!(Reaction::cycle(Some(Reaction::Flag)), None);
+    assert_eq!("Flag".parse::<Reaction>(), Ok(Reaction::Flag));
+    assert!("meh".parse::<Reaction>().is_err());
+}
+
+#[test]
+fn origin_records_workspace_branch_and_known_env() {
+    let root = std::path::Path::new("/work/project");
+    let origin =
+        ConversationOrigin::from_parts(root, Some("feature/x".to_string()), |name| match name {
+            "NODE_ENV" => Some("test".to_string()),
+            "AWS_PROFILE" => Some(String::new()),
+            "OPENROUTER_API_KEY" => Some("sk-secret".to_string()),
+            _ => None,
+        });
+    assert_eq!(
+        origin.details(),
+        vec![
+            ("Workspace", "/work/project".to_string()),
+            ("Branch", "feature/x".to_string()),
+            ("Env", "NODE_ENV=test".to_string()),
+        ]
+    );
+
+    assert_eq!(origin.mismatch_warning(root), None);
+    let warning = origin
+        .mismatch_warning(std::path::Path::new("/elsewhere"))
+        .expect("warning");
+    assert!(warning.contains("/work/project (branch feature/x)"));
+    assert!(warning.contains("/elsewhere"));
+    // Conversations saved before origins were recorded have none to compare.
+    assert_eq!(
+        ConversationOrigin::default().mismatch_warning(std::path::Path::new("/elsewhere")),
+        None
+    );
+}
+
+#[test]
+fn retention_plan_applies_age_workspace_and_count_limits() {
+    const DAY: u64 = 24 * 60 * 60;
+    let now = 100 * DAY;
+    let meta =
+        |id: &str, days_ago: u64, workspace: Option<&str>, archived: bool| ConversationMeta {
+            id: id.to_string(),
+            title: id.to_string(),
+            created_at: 0,
+            updated_at: now - days_ago * DAY,
+            workspace: workspace.map(str::to_string),
+            archived,
+        };
+    let convs = vec![
+        meta("a1", 1, Some("/a"), false),
+        meta("a2", 2, Some("/a"), false),
+        meta("a3", 3, Some("/a"), false),
+        meta("b1", 4, Some("/b"), false),
+        meta("old", 40, None, false),
+        meta("kept", 60, Some("/a"), true),
+        meta("none", 5, None, false),
+    ];
+    let mut config = test_config();
+    config.max_conversations = 3;
+    config.history_retention = crate::core::history::Retention {
+        max_age_days: 30,
+        max_per_workspace: 2,
+    };
+    let removed: Vec<(String, String)> = super::retention::plan(&convs, &config, now)
+        .into_iter()
+        .map(|c| (c.meta.id, c.reason.to_string()))
+        .collect();
+    assert_eq!(
+        removed,
+        vec![
+            (
+                "a3".to_string(),
+                "more than 2 conversations in /a".to_string()
+            ),
+            ("none".to_string(), "more than 3 conversations".to_string()),
+            ("old".to_string(), "older than 30 days".to_string()),
+        ]
+    );
+
+    config.history_retention = Default::default();
+    config.max_conversations = 0;
+    assert!(super::retention::plan(&convs, &config, now).is_empty());
+}
+
+#[test]
+fn archived_conversations_are_kept_by_prune() {
+    let _lock = PERSISTENCE_TEST_LOCK.lock().unwrap();
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    unsafe {
+        std::env::set_var("TEST_DATA_DIR", tmp.path().join("conversations"));
+    }
+    let _guard = EnvGuard("TEST_DATA_DIR");
+
+    let config = test_config();
+    let save = |prompt: &str| {
+        let messages = vec![serde_json::json!({"role": "user", "content": prompt})];
+        save_conversation(
+            None,
+            prompt,
+            &messages,
+            &ConversationSettings::default(),
+            &config,
+        )
+        .expect("save")
+    };
+    let archived = save("Keep me");
+    save("Prune me");
+    set_archived(&archived, true).expect("archive");
+    assert_eq!(
+        set_archived("missing", true).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    save("Newest");
+    // Saving with the default cap (10) removed nothing.
+    assert_eq!(list_conversations().unwrap().len(), 3);
+
+    // One unarchived conversation is kept: one of "Prune me" and "Newest" goes.
+    let mut strict = test_config();
+    strict.max_conversations = 1;
+    let dry = prune_history(&strict, true).expect("dry run");
+    assert_eq!(dry.len(), 1);
+    assert_ne!(dry[0].meta.id, archived);
+    assert_eq!(list_conversations().unwrap().len(), 3);
+
+    let removed = prune_history(&strict, false).expect("prune");
+    assert_eq!(removed.len(), 1);
+    let left: Vec<String> = list_conversations()
+        .unwrap()
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    assert_eq!(left.len(), 2);
+    assert!(left.contains(&archived));
+    assert!(load_conversation(&removed[0].meta.id).is_none());
+}
@@ -0,0 +1,38 @@
+//! View state of a conversation in the TUI (scroll position, expanded tool results, split
+//! pane, code block wrapping), saved in `ui_<id>.json` next to the conversation when leaving
+//! it and restored when it is reopened from the history selector. Losing the file only
+//! resets the view.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use super::storage;
+
+/// How a conversation was displayed when it was left.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiState {
+    /// First history line shown; None when following the latest message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scroll_line: Option<usize>,
+    /// Indices (among the displayed messages) of the expanded tool results.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expanded_tool_results: Vec<usize>,
+    /// The side pane was shown (Alt+S).
+    #[serde(default)]
+    pub split_pane: bool,
+    /// Code blocks were truncated with horizontal scrolling instead of soft-wrapped (Alt+W).
+    #[serde(default)]
+    pub truncate_code: bool,
+}
+
+/// View state saved for conversation `id`; None when none was saved or the file cannot be
+/// read.
+pub fn load_ui_state(id: &str) -> Option<UiState> {
+    storage::read_ui_state(id)
+}
+
+/// Save the view state of conversation `id`, replacing the previous one.
+pub fn save_ui_state(id: &str, state: &UiState) -> io::Result<()> {
+    storage::write_ui_state(id, state)
+}
@@ -0,0 +1,377 @@
+//! Post-processing hooks: user scripts run after each assistant turn, in the order
+//! configured (`MY_OPEN_CLAUDE_POST_HOOKS` or the profile's `post_hooks`).
+//!
+//! A hook is an executable (absolute path, `~/` path, path relative to the working
+//! directory, or name in PATH), run on this machine even with a remote workspace. It reads
+//! a JSON object on stdin: the assistant `message`, the user `prompt` it answers, and the
+//! `model`, `mode`, and `working_dir` of the turn. It may print a JSON object on stdout,
+//! all fields optional:
+//!
+//! - `content`: replaces the reply where it is displayed and saved (the model still sees
+//!   its own words in the next turns)
+//! - `annotations`: notes (a string or a list of strings) shown after the reply
+//! - `block_save`: true, or the reason as a string: the conversation is not saved after
+//!   this turn
+//!
+//! Any other output is shown as an annotation. Each hook sees the reply as changed by the
+//! previous ones. A hook that fails, exits with an error, or runs longer than
+//! `HOOK_TIMEOUT` leaves the reply unchanged, with an annotation saying so.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+/// Time a hook may run before it is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often a running hook is checked for exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// Max characters of a failing hook's stderr quoted in its annotation.
+const STDERR_EXCERPT_CHARS: usize = 200;
+
+/// The turn handed to the hooks.
+#[derive(Debug, Clone, Copy)]
+pub struct TurnContext<'a> {
+    pub prompt: &'a str,
+    pub model: &'a str,
+    pub mode: &'a str,
+    pub working_dir: &'a Path,
+}
+
+/// The reply after the hooks ran.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PostProcessed {
+    /// Reply content, as changed by the hooks.
+    pub content: String,
+    /// Notes from the hooks, each prefixed by the hook's file name.
+    pub annotations: Vec<String>,
+    /// Why the conversation should not be saved after this turn, if a hook said so.
+    pub save_blocked: Option<String>,
+}
+
+/// What one hook asked for.
+#[derive(Debug, Default, PartialEq)]
+struct HookOutput {
+    content: Option<String>,
+    annotations: Vec<String>,
+    block_save: Option<String>,
+}
+
+/// JSON written to a hook's stdin.
+fn payload(content: &str, turn: TurnContext<'_>) -> Value {
+    json!({
+        "event": "turn_completed",
+        "message": {"role": "assistant", "content": content},
+        "prompt": turn.prompt,
+        "model": turn.model,
+        "mode": turn.mode,
+        "working_dir": turn.working_dir,
+    })
+}
+
+/// Read a hook's stdout: a JSON object with the fields above, or text shown as is.
+fn parse_output(stdout: &str) -> HookOutput {
+    let text = stdout.trim();
+    if text.is_empty() {
+        return HookOutput::default();
+    }
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(text) else {
+        return HookOutput {
+            annotations: vec![text.to_string()],
+            ..Default::default()
+        };
+    };
+    let annotations = match fields.get("annotations") {
+        Some(Value::String(note)) => vec![note.clone()],
+        Some(Value::Array(notes)) => notes
+            .iter()
+            .filter_map(|n| n.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let block_save = match fields.get("block_save") {
+        Some(Value::String(reason)) if !reason.trim().is_empty() => Some(reason.clone()),
+        Some(Value::String(_)) | Some(Value::Bool(true)) => Some(String::new()),
+        _ => None,
+    };
+    HookOutput {
+        content: fields
+            .get("content")
+            .and_then(|c| c.as_str())
+            .map(str::to_string),
+        annotations,
+        block_save,
+    }
+}
+
+/// File name of a hook, used to label its annotations.
+fn hook_name(hook: &str) -> String {
+    Path::new(hook)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| hook.to_string())
+}
+
+/// Program to run for `hook`: `~/` is the home directory, other relative paths are
+/// resolved against `working_dir`, and a bare name is looked up in PATH.
+fn program(hook: &str, working_dir: &Path) -> PathBuf {
+    if let Some(rest) = hook.strip_prefix("~/")
+        && let Some(dirs) = directories::BaseDirs::new()
+    {
+        return dirs.home_dir().join(rest);
+    }
+    let path = Path::new(hook);
+    if path.is_relative() && path.components().count() > 1 {
+        working_dir.join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Read a pipe to the end on a background thread.
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Run `hook` with `input` on stdin. Returns its stdout, or why it failed.
+fn run_hook(
+    hook: &str,
+    input: &[u8],
+    working_dir: &Path,
+    timeout: Duration,
+) -> Result<String, String> {
+    let mut child = Command::new(program(hook, working_dir))
+        .current_dir(working_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not be started ({})", e))?;
+    let stdout = spawn_reader(child.stdout.take());
+    let stderr = spawn_reader(child.stderr.take());
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that does not read its input closes the pipe early.
+        let input = input.to_vec();
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                kill(&mut child);
+                return Err(format!("timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                kill(&mut child);
+                return Err(e.to_string());
+            }
+        }
+    };
+    let stdout = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).into_owned();
+    if status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+    let excerpt: String = stderr.trim().chars().take(STDERR_EXCERPT_CHARS).collect();
+    Err(match (status.code(), excerpt.is_empty()) {
+        (Some(code), true) => format!("exited with status {}", code),
+        (Some(code), false) => format!("exited with status {}: {}", code, excerpt),
+        (None, _) => "was killed by a signal".to_string(),
+    })
+}
+
+/// Run `hooks` one after the other on the reply `content` (blocking).
+fn run_all(
+    hooks: &[String],
+    content: String,
+    turn: TurnContext<'_>,
+    timeout: Duration,
+) -> PostProcessed {
+    let mut result = PostProcessed {
+        content,
+        ..Default::default()
+    };
+    for hook in hooks {
+        let name = hook_name(hook);
+        let input = payload(&result.content, turn).to_string();
+        let output = match run_hook(hook, input.as_bytes(), turn.working_dir, timeout) {
+            Ok(stdout) => parse_output(&stdout),
+            Err(e) => {
+                log::warn!("Post-processing hook {} failed: {}", hook, e);
+                result
+                    .annotations
+                    .push(format!("{}: hook {}; reply left unchanged", name, e));
+                continue;
+            }
+        };
+        if let Some(content) = output.content {
+            result.content = content;
+        }
+        result.annotations.extend(
+            output
+                .annotations
+                .into_iter()
+                .map(|note| format!("{}: {}", name, note)),
+        );
+        if let Some(reason) = output.block_save {
+            let reason = if reason.is_empty() {
+                format!("blocked by {}", name)
+            } else {
+                format!("{} ({})", reason, name)
+            };
+            result.save_blocked = Some(reason);
+        }
+    }
+    result
+}
+
+/// Run the post-processing `hooks` on the reply `content` of a turn. With no hooks, the
+/// reply is returned unchanged.
+pub async fn post_process(hooks: &[String], content: &str, turn: TurnContext<'_>) -> PostProcessed {
+    if hooks.is_empty() {
+        return PostProcessed {
+            content: content.to_string(),
+            ..Default::default()
+        };
+    }
+    let hooks = hooks.to_vec();
+    let content = content.to_string();
+    let (prompt, model, mode) = (
+        turn.prompt.to_string(),
+        turn.model.to_string(),
+        turn.mode.to_string(),
+    );
+    let working_dir: PathBuf = turn.working_dir.to_path_buf();
+    let fallback = content.clone();
+    tokio::task::spawn_blocking(move || {
+        let turn = TurnContext {
+            prompt: &prompt,
+            model: &model,
+            mode: &mode,
+            working_dir: &working_dir,
+        };
+        run_all(&hooks, content, turn, HOOK_TIMEOUT)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        log::warn!("Post-processing hooks panicked: {}", e);
+        PostProcessed {
+            content: fallback,
+            ..Default::default()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_reads_fields_or_plain_text() {
+        assert_eq!(parse_output("  \n"), HookOutput::default());
+        assert_eq!(
+            parse_output("Looks good\n"),
+            HookOutput {
+                annotations: vec!["Looks good".to_string()],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_output(r#"{"content": "new", "annotations": ["a", 1, "b"], "block_save": true}"#),
+            HookOutput {
+                content: Some("new".to_string()),
+                annotations: vec!["a".to_string(), "b".to_string()],
+                block_save: Some(String::new()),
+            }
+        );
+        assert_eq!(
+            parse_output(r#"{"annotations": "one", "block_save": "contains a secret"}"#),
+            HookOutput {
+                annotations: vec!["one".to_string()],
+                block_save: Some("contains a secret".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_output(r#"{"block_save": false}"#),
+            HookOutput::default()
+        );
+    }
+
+    #[test]
+    fn program_resolves_relative_paths_against_the_working_dir() {
+        let dir = Path::new("/work/project");
+        assert_eq!(
+            program("hooks/lint.sh", dir),
+            PathBuf::from("/work/project/hooks/lint.sh")
+        );
+        assert_eq!(program("/opt/lint", dir), PathBuf::from("/opt/lint"));
+        assert_eq!(program("notify-send", dir), PathBuf::from("notify-send"));
+    }
+
+    #[cfg(unix)]
+    fn script(dir: &Path, name: &str, body: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_chain_changes_and_report_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let turn = TurnContext {
+            prompt: "Say hi",
+            model: "test/model",
+            mode: "Build",
+            working_dir: dir.path(),
+        };
+        let hooks = vec![
+            // Reads the message from stdin and rewrites it.
+            script(
+                dir.path(),
+                "upper.sh",
+                r#"content=$(sed -n 's/.*"content":"\([^"]*\)".*/\1/p')
+printf '{"content": "%s!", "annotations": ["shouted"]}' "$(echo "$content" | tr a-z A-Z)""#,
+            ),
+            script(dir.path(), "fail.sh", "echo broken >&2; exit 3"),
+            script(dir.path(), "slow.sh", "sleep 5"),
+            script(
+                dir.path(),
+                "guard.sh",
+                r#"echo '{"block_save": "private"}'"#,
+            ),
+        ];
+        let result = run_all(&hooks, "hi there".to_string(), turn, Duration::from_secs(1));
+        assert_eq!(result.content, "HI THERE!");
+        assert_eq!(
+            result.annotations,
+            [
+                "upper.sh: shouted",
+                "fail.sh: hook exited with status 3: broken; reply left unchanged",
+                "slow.sh: hook timed out after 1s; reply left unchanged",
+            ]
+        );
+        assert_eq!(result.save_blocked.as_deref(), Some("private (guard.sh)"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Embeddings API client: OpenAI-compatible `POST {base_url}/embeddings`.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::network;
+
+use super::IndexError;
+
+/// Per-request timeout (a batch of chunks can take a while on slow endpoints).
+const EMBED_TIMEOUT: Duration = Duration::from_secs(60);
+/// Max characters of an error body kept in the error message.
+const ERROR_BODY_CHARS: usize = 300;
+
+/// Endpoint and model used to embed chunks and queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingSettings {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embed `inputs` in one request, one vector per input in order.
+///
+/// Runs on its own thread and runtime: callers are sync tools that may already be running
+/// inside the async runtime, where blocking on a future would panic.
+pub fn embed(settings: &EmbeddingSettings, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexError> {
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let settings = settings.clone();
+    let inputs = inputs.to_vec();
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        rt.block_on(request(&settings, &inputs))
+    })
+    .join()
+    .map_err(|_| IndexError::Http("embedding thread panicked".to_string()))?
+}
+
+async fn request(
+    settings: &EmbeddingSettings,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, IndexError> {
+    let http = |e: reqwest::Error| IndexError::Http(e.to_string());
+    let client = network::client_builder()
+        .timeout(EMBED_TIMEOUT)
+        .build()
+        .map_err(http)?;
+    let url = format!("{}/embeddings", settings.base_url.trim_end_matches('/'));
+    let response = client
+        .post(url)
+        .bearer_auth(&settings.api_key)
+        .json(&json!({ "model": settings.model, "input": inputs }))
+        .send()
+        .await
+        .map_err(http)?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let body: String = body.trim().chars().take(ERROR_BODY_CHARS).collect();
+        return Err(IndexError::Http(format!("{}: {}", status, body)));
+    }
+    let parsed: EmbeddingResponse = response
+        .json()
+        .await
+        .map_err(|e| IndexError::InvalidResponse(e.to_string()))?;
+    vectors_in_order(parsed, inputs.len())
+}
+
+/// Vectors sorted by input index; fails unless there is exactly one per input.
+fn vectors_in_order(
+    mut response: EmbeddingResponse,
+    expected: usize,
+) -> Result<Vec<Vec<f32>>, IndexError> {
+    if response.data.len() != expected {
+        return Err(IndexError::InvalidResponse(format!(
+            "expected {} embeddings, got {}",
+            expected,
+            response.data.len()
+        )));
+    }
+    response.data.sort_by_key(|d| d.index);
+    Ok(response.data.into_iter().map(|d| d.embedding).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_follow_input_order() {
+        let response: EmbeddingResponse = serde_json::from_value(json!({
+            "data": [
+                {"index": 1, "embedding": [0.0, 1.0]},
+                {"index": 0, "embedding": [1.0, 0.0]}
+            ]
+        }))
+        .unwrap();
+        let vectors = vectors_in_order(response, 2).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let short: EmbeddingResponse =
+            serde_json::from_value(json!({"data": [{"embedding": [1.0]}]})).unwrap();
+        assert!(vectors_in_order(short, 2).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Semantic index of the workspace for the SemanticSearch tool.
+//!
+//! Text files are split into line chunks, each embedded with the model from
+//! `MY_OPEN_CLAUDE_EMBEDDING_MODEL`, and stored in the cache directory
+//! (`index/<root hash>.json`). Updates are incremental: only files whose size or
+//! modification time changed are embedded again, and removed files are dropped.
+//! Changing the embedding model rebuilds the whole index.
+
+mod embeddings;
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+pub use embeddings::EmbeddingSettings;
+
+use crate::core::config::Config;
+use crate::core::paths;
+use crate::core::permissions;
+use crate::core::tools::ignore;
+
+/// Bumped when the stored format or the chunking changes (older indexes are rebuilt).
+const INDEX_VERSION: u32 = 1;
+/// Lines per chunk.
+const CHUNK_LINES: usize = 40;
+/// Max characters of a chunk sent for embedding (minified files have very long lines).
+const MAX_CHUNK_CHARS: usize = 6000;
+/// Files larger than this are not indexed (generated code, data dumps).
+const MAX_FILE_BYTES: u64 = 200 * 1024;
+/// Chunks per embeddings request.
+const EMBED_BATCH: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexError {
+    #[error("Semantic search is not configured (set MY_OPEN_CLAUDE_EMBEDDING_MODEL)")]
+    NotConfigured,
+    #[error("No cache directory available")]
+    NoCacheDir,
+    #[error("Embedding request failed: {0}")]
+    Http(String),
+    #[error("Invalid embedding response: {0}")]
+    InvalidResponse(String),
+    #[error("Index I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Index file contents.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredIndex {
+    version: u32,
+    model: String,
+    /// Keyed by path relative to the root, with `/` separators.
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// Modification time (seconds since the Unix epoch) when embedded.
+    modified: u64,
+    size: u64,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    /// 1-based, inclusive.
+    start_line: usize,
+    end_line: usize,
+    /// Little-endian f32 values, base64-encoded.
+    vector: String,
+}
+
+/// Result of an index update.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Files in the index after the update.
+    pub files: usize,
+    /// Chunks in the index after the update.
+    pub chunks: usize,
+    /// Files embedded by this update (new or changed).
+    pub embedded: usize,
+    /// Files dropped because they no longer exist.
+    pub removed: usize,
+}
+
+/// A chunk matching a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// Path relative to the root.
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Cosine similarity with the query.
+    pub score: f32,
+}
+
+static SETTINGS: RwLock<Option<EmbeddingSettings>> = RwLock::new(None);
+/// Serializes updates: parallel SemanticSearch calls would otherwise embed the same files.
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Enable semantic search when an embedding model is configured, disable it otherwise.
+/// Called at startup and when switching profiles.
+pub fn configure(config: &Config) {
+    let settings = config
+        .embedding_model
+        .as_ref()
+        .map(|model| EmbeddingSettings {
+            base_url: config.base_url.clone(),
+            api_key: config.api_key.clone(),
+            model: model.clone(),
+        });
+    *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = settings;
+}
+
+/// True when an embedding model is configured.
+pub fn is_configured() -> bool {
+    SETTINGS.read().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+fn settings() -> Result<EmbeddingSettings, IndexError> {
+    SETTINGS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or(IndexError::NotConfigured)
+}
+
+/// Index file for a workspace root: `<cache>/index/<hash of the canonical root>.json`.
+pub fn index_path(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let hash = fnv1a(root.to_string_lossy().as_bytes());
+    paths::cache_dir().map(|d| d.join("index").join(format!("{:016x}.json", hash)))
+}
+
+/// Stable across runs and Rust versions, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Bring the index of `root` up to date. `rebuild` discards the existing index first.
+pub fn update(root: &Path, rebuild: bool) -> Result<IndexStats, IndexError> {
+    let settings = settings()?;
+    let path = index_path(root).ok_or(IndexError::NoCacheDir)?;
+    if rebuild {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    update_with(&path, root, &settings.model, |inputs| {
+        embeddings::embed(&settings, inputs)
+    })
+}
+
+/// Update the index of `root`, then return the `limit` chunks closest to `query`.
+pub fn search(root: &Path, query: &str, limit: usize) -> Result<Vec<SearchHit>, IndexError> {
+    let settings = settings()?;
+    let path = index_path(root).ok_or(IndexError::NoCacheDir)?;
+    search_with(&path, root, &settings.model, query, limit, |inputs| {
+        embeddings::embed(&settings, inputs)
+    })
+}
+
+/// A file being embedded: its entry without vectors, and the text of each chunk.
+struct PendingFile {
+    path: String,
+    modified: u64,
+    size: u64,
+    chunks: Vec<(usize, usize, String)>,
+}
+
+fn update_with<E>(
+    index_file: &Path,
+    root: &Path,
+    model: &str,
+    mut embed: E,
+) -> Result<IndexStats, IndexError>
+where
+    E: FnMut(&[String]) -> Result<Vec<Vec<f32>>, IndexError>,
+{
+    let _guard = UPDATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = load_index(index_file)
+        .filter(|i| i.version == INDEX_VERSION && i.model == model)
+        .unwrap_or_else(|| StoredIndex {
+            version: INDEX_VERSION,
+            model: model.to_string(),
+            files: BTreeMap::new(),
+        });
+
+    let mut seen = HashSet::new();
+    let mut batch: Vec<PendingFile> = Vec::new();
+    let mut batch_chunks = 0;
+    let mut stats = IndexStats::default();
+    let mut changed = false;
+    for (rel, abs, modified, size) in workspace_files(root) {
+        seen.insert(rel.clone());
+        if index
+            .files
+            .get(&rel)
+            .is_some_and(|e| e.modified == modified && e.size == size)
+        {
+            continue;
+        }
+        let Some(content) = read_text(&abs) else {
+            // Binary or unreadable now: drop a stale entry, if any.
+            changed |= index.files.remove(&rel).is_some();
+            continue;
+        };
+        let chunks = chunk_lines(&content);
+        batch_chunks += chunks.len();
+        batch.push(PendingFile {
+            path: rel,
+            modified,
+            size,
+            chunks,
+        });
+        if batch_chunks >= EMBED_BATCH {
+            let result = embed_files(&mut index, std::mem::take(&mut batch), &mut embed);
+            batch_chunks = 0;
+            changed = true;
+            stats.embedded += finish_batch(index_file, &index, result)?;
+        }
+    }
+    if !batch.is_empty() {
+        let result = embed_files(&mut index, batch, &mut embed);
+        changed = true;
+        stats.embedded += finish_batch(index_file, &index, result)?;
+    }
+
+    let before = index.files.len();
+    index.files.retain(|path, _| seen.contains(path));
+    stats.removed = before - index.files.len();
+    if changed || stats.removed > 0 || !index_file.exists() {
+        save_index(index_file, &index)?;
+    }
+    stats.files = index.files.len();
+    stats.chunks = index.files.values().map(|e| e.chunks.len()).sum();
+    Ok(stats)
+}
+
+/// On failure, keep the files embedded so far so the next update resumes from there.
+fn finish_batch(
+    index_file: &Path,
+    index: &StoredIndex,
+    result: Result<usize, IndexError>,
+) -> Result<usize, IndexError> {
+    if result.is_err() {
+        save_index(index_file, index)?;
+    }
+    result
+}
+
+/// Embed the chunks of `files` and add them to the index. Returns the number of files added.
+fn embed_files<E>(
+    index: &mut StoredIndex,
+    files: Vec<PendingFile>,
+    embed: &mut E,
+) -> Result<usize, IndexError>
+where
+    E: FnMut(&[String]) -> Result<Vec<Vec<f32>>, IndexError>,
+{
+    let inputs: Vec<String> = files
+        .iter()
+        .flat_map(|f| {
+            f.chunks
+                .iter()
+                .map(move |(start, end, text)| format!("{}:{}-{}\n{}", f.path, start, end, text))
+        })
+        .collect();
+    let mut vectors = Vec::with_capacity(inputs.len());
+    for batch in inputs.chunks(EMBED_BATCH) {
+        let embedded = embed(batch)?;
+        if embedded.len() != batch.len() {
+            return Err(IndexError::InvalidResponse(format!(
+                "expected {} embeddings, got {}",
+                batch.len(),
+                embedded.len()
+            )));
+        }
+        vectors.extend(embedded);
+    }
+    let mut vectors = vectors.into_iter();
+    let added = files.len();
+    for file in files {
+        let chunks = file
+            .chunks
+            .iter()
+            .zip(vectors.by_ref())
+            .map(|((start, end, _), vector)| Chunk {
+                start_line: *start,
+                end_line: *end,
+                vector: encode_vector(&vector),
+            })
+            .collect();
+        index.files.insert(
+            file.path,
+            FileEntry {
+                modified: file.modified,
+                size: file.size,
+                chunks,
+            },
+        );
+    }
+    Ok(added)
+}
+
+fn search_with<E>(
+    index_file: &Path,
+    root: &Path,
+    model: &str,
+    query: &str,
+    limit: usize,
+    mut embed: E,
+) -> Result<Vec<SearchHit>, IndexError>
+where
+    E: FnMut(&[String]) -> Result<Vec<Vec<f32>>, IndexError>,
+{
+    update_with(index_file, root, model, &mut embed)?;
+    let index = load_index(index_file).unwrap_or_default();
+    let query_vector = embed(&[query.to_string()])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| IndexError::InvalidResponse("no embedding for the query".to_string()))?;
+    let mut hits: Vec<SearchHit> = index
+        .files
+        .iter()
+        .flat_map(|(path, entry)| {
+            entry.chunks.iter().filter_map(|chunk| {
+                let vector = decode_vector(&chunk.vector)?;
+                Some(SearchHit {
+                    path: path.clone(),
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    score: cosine(&query_vector, &vector),
+                })
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Files under `root` that may be indexed: (relative path, absolute path, mtime, size).
+/// Denied paths are never embedded.
+fn workspace_files(root: &Path) -> Vec<(String, PathBuf, u64, u64)> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| !ignore::is_ignored(e) && !permissions::is_denied(root, e.path()))
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            if meta.len() > MAX_FILE_BYTES {
+                return None;
+            }
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            let rel = e.path().strip_prefix(root).ok()?;
+            let rel = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((rel, e.path().to_path_buf(), modified, meta.len()))
+        })
+        .collect()
+}
+
+/// File contents when they look like text (valid UTF-8 without NUL bytes).
+fn read_text(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    (!content.contains('\0')).then_some(content)
+}
+
+/// Split into chunks of `CHUNK_LINES` lines: (start line, end line, text). Blank chunks are
+/// skipped.
+fn chunk_lines(content: &str) -> Vec<(usize, usize, String)> {
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .chunks(CHUNK_LINES)
+        .enumerate()
+        .filter(|(_, chunk)| chunk.iter().any(|l| !l.trim().is_empty()))
+        .map(|(i, chunk)| {
+            let start = i * CHUNK_LINES + 1;
+            let text: String = chunk.join("\n").chars().take(MAX_CHUNK_CHARS).collect();
+            (start, start + chunk.len() - 1, text)
+        })
+        .collect()
+}
+
+fn encode_vector(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+    BASE64.encode(bytes)
+}
+
+fn decode_vector(encoded: &str) -> Option<Vec<f32>> {
+    let bytes = BASE64.decode(encoded).ok()?;
+    let values = bytes.chunks_exact(4);
+    if !values.remainder().is_empty() {
+        return None;
+    }
+    Some(
+        values
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    )
+}
+
+/// Cosine similarity; 0 for empty, zero, or mismatched vectors.
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn load_index(path: &Path) -> Option<StoredIndex> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Write through a temporary file so an interrupted write never leaves a truncated index.
+fn save_index(path: &Path, index: &StoredIndex) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string(index).map_err(io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
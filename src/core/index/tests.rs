@@ -0,0 +1,128 @@
+use std::cell::Cell;
+use std::fs;
+
+use super::*;
+
+const DIM: usize = 64;
+
+/// Bag-of-words embedder: one dimension per hashed lowercase word.
+fn fake_embed(inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexError> {
+    Ok(inputs
+        .iter()
+        .map(|text| {
+            let mut vector = vec![0.0; DIM];
+            for word in text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| w.len() > 2)
+            {
+                vector[fnv1a(word.to_lowercase().as_bytes()) as usize % DIM] += 1.0;
+            }
+            vector
+        })
+        .collect())
+}
+
+fn workspace() -> tempfile::TempDir {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("auth.rs"),
+        "fn login(user: &str, password: &str) {\n    verify password hash for user\n}\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/render.rs"),
+        "fn draw_frame() {\n    paint widgets on the terminal canvas\n}\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("target")).unwrap();
+    fs::write(dir.path().join("target/ignored.rs"), "fn login() {}\n").unwrap();
+    dir
+}
+
+#[test]
+fn update_embeds_only_new_and_changed_files() {
+    let root = workspace();
+    let cache = tempfile::TempDir::new().unwrap();
+    let index_file = cache.path().join("index.json");
+    let calls = Cell::new(0);
+    let embed = |inputs: &[String]| {
+        calls.set(calls.get() + inputs.len());
+        fake_embed(inputs)
+    };
+
+    let stats = update_with(&index_file, root.path(), "m", embed).unwrap();
+    assert_eq!((stats.files, stats.embedded, stats.removed), (2, 2, 0));
+    assert_eq!(calls.get(), 2, "one chunk per file; target/ is ignored");
+
+    let stats = update_with(&index_file, root.path(), "m", embed).unwrap();
+    assert_eq!(stats.embedded, 0);
+    assert_eq!(calls.get(), 2, "unchanged files are not embedded again");
+
+    fs::write(root.path().join("auth.rs"), "fn logout() {}\n").unwrap();
+    fs::remove_file(root.path().join("src/render.rs")).unwrap();
+    let stats = update_with(&index_file, root.path(), "m", embed).unwrap();
+    assert_eq!((stats.files, stats.embedded, stats.removed), (1, 1, 1));
+    assert_eq!(calls.get(), 3);
+
+    let stats = update_with(&index_file, root.path(), "other-model", embed).unwrap();
+    assert_eq!(stats.embedded, 1, "a new model rebuilds the index");
+}
+
+#[test]
+fn search_ranks_related_chunks_first() {
+    let root = workspace();
+    let cache = tempfile::TempDir::new().unwrap();
+    let index_file = cache.path().join("index.json");
+
+    let hits = search_with(
+        &index_file,
+        root.path(),
+        "m",
+        "where is the password verified for a user",
+        1,
+        fake_embed,
+    )
+    .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].path, "auth.rs");
+    assert_eq!((hits[0].start_line, hits[0].end_line), (1, 3));
+
+    let hits = search_with(
+        &index_file,
+        root.path(),
+        "m",
+        "terminal widgets",
+        5,
+        fake_embed,
+    )
+    .unwrap();
+    assert_eq!(hits[0].path, "src/render.rs");
+    assert_eq!(hits.len(), 2);
+}
+
+#[test]
+fn failed_embedding_is_retried_on_next_update() {
+    let root = workspace();
+    let cache = tempfile::TempDir::new().unwrap();
+    let index_file = cache.path().join("index.json");
+    let failing = |_: &[String]| -> Result<Vec<Vec<f32>>, IndexError> {
+        Err(IndexError::Http("503".to_string()))
+    };
+    assert!(update_with(&index_file, root.path(), "m", failing).is_err());
+    let stats = update_with(&index_file, root.path(), "m", fake_embed).unwrap();
+    assert_eq!(stats.embedded, 2);
+}
+
+#[test]
+fn chunks_and_vectors_round_trip() {
+    let content: String = (1..=90).map(|i| format!("line {}\n", i)).collect();
+    let chunks = chunk_lines(&content);
+    let ranges: Vec<(usize, usize)> = chunks.iter().map(|(s, e, _)| (*s, *e)).collect();
+    assert_eq!(ranges, vec![(1, 40), (41, 80), (81, 90)]);
+    assert!(chunk_lines("\n\n  \n").is_empty());
+
+    let vector = vec![0.5, -1.25, 3.0];
+    assert_eq!(decode_vector(&encode_vector(&vector)), Some(vector));
+    assert_eq!(cosine(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+}
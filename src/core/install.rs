@@ -1,12 +1,60 @@
-//! Install the binary to ~/.cargo/bin from the current project.
+//! Install the binary to Cargo's bin directory from the current project.
 //!
-//! Runs `cargo install --path .` when invoked from a directory containing Cargo.toml.
+//! Runs `cargo install --path .` when invoked from a directory containing Cargo.toml, then
+//! checks that the bin directory is on PATH and prints how to add it for the platform.
 
 use std::env;
 use std::env::consts::EXE_SUFFIX;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 
 use crate::core::app;
 
+/// Cargo's bin directory: `$CARGO_HOME/bin`, else `.cargo/bin` in the home directory
+/// (the user profile on Windows).
+fn cargo_bin_dir() -> Option<PathBuf> {
+    let cargo_home = env::var_os("CARGO_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| directories::BaseDirs::new().map(|d| d.home_dir().join(".cargo")))?;
+    Some(cargo_home.join("bin"))
+}
+
+/// Same directory, ignoring trailing separators (and case on Windows).
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let normalize = |p: &Path| {
+        let s = p.to_string_lossy();
+        let s = s.trim_end_matches(['/', '\\']);
+        if cfg!(windows) {
+            s.replace('/', "\\").to_lowercase()
+        } else {
+            s.to_string()
+        }
+    };
+    normalize(a) == normalize(b)
+}
+
+/// Whether `dir` is one of the entries of `path_var` (`;`-separated on Windows, `:` elsewhere).
+fn is_on_path(dir: &Path, path_var: &OsStr) -> bool {
+    env::split_paths(path_var).any(|entry| same_dir(&entry, dir))
+}
+
+/// How to add `dir` to PATH on this platform.
+fn path_hint(dir: &Path) -> String {
+    let dir = dir.display();
+    if cfg!(windows) {
+        format!(
+            "{dir} is not in your PATH. Add it for your user from PowerShell, then open a new terminal:\n  \
+             [Environment]::SetEnvironmentVariable(\"Path\", [Environment]::GetEnvironmentVariable(\"Path\", \"User\") + \";{dir}\", \"User\")"
+        )
+    } else {
+        format!(
+            "{dir} is not in your PATH. Add this line to your shell profile (~/.bashrc, ~/.zshrc), then open a new terminal:\n  \
+             export PATH=\"{dir}:$PATH\""
+        )
+    }
+}
+
 /// Install the binary to the user's cargo bin directory.
 ///
 /// Requires Cargo.toml in the current directory. Spawns `cargo install --path .`.
@@ -31,13 +79,49 @@ pub fn run_install() -> Result<(), Box<dyn std::error::Error>> {
     if !status.success() {
         std::process::exit(status.code().unwrap_or(1));
     }
-    let cargo_home = env::var("CARGO_HOME").unwrap_or_else(|_| {
-        let home = env::var("HOME")
-            .or_else(|_| env::var("USERPROFILE"))
-            .unwrap_or_default();
-        format!("{}/.cargo", home)
-    });
-    let install_path = format!("{}/bin/{}{}", cargo_home, app::NAME, EXE_SUFFIX);
-    println!("Installed to {}", install_path);
+    let Some(bin_dir) = cargo_bin_dir() else {
+        println!(
+            "Installed {}{} to Cargo's bin directory",
+            app::NAME,
+            EXE_SUFFIX
+        );
+        return Ok(());
+    };
+    let install_path = bin_dir.join(format!("{}{}", app::NAME, EXE_SUFFIX));
+    println!("Installed to {}", install_path.display());
+    let path_var = env::var_os("PATH").unwrap_or_default();
+    if !is_on_path(&bin_dir, &path_var) {
+        println!("{}", path_hint(&bin_dir));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_on_path_finds_entry_with_platform_separator() {
+        let bin = PathBuf::from("/home/u/.cargo/bin");
+        let path_var = env::join_paths([PathBuf::from("/usr/bin"), bin.join("")]).unwrap();
+        assert!(is_on_path(&bin, &path_var));
+        assert!(!is_on_path(Path::new("/opt/bin"), &path_var));
+        assert!(!is_on_path(&bin, OsStr::new("")));
+    }
+
+    #[test]
+    fn same_dir_ignores_trailing_separator() {
+        assert!(same_dir(Path::new("/a/b/"), Path::new("/a/b")));
+        assert!(!same_dir(Path::new("/a/b"), Path::new("/a/bc")));
+        assert_eq!(
+            same_dir(Path::new("/A/b"), Path::new("/a/b")),
+            cfg!(windows)
+        );
+    }
+
+    #[test]
+    fn path_hint_names_directory() {
+        let hint = path_hint(Path::new("/home/u/.cargo/bin"));
+        assert!(hint.contains("/home/u/.cargo/bin is not in your PATH"));
+    }
+}
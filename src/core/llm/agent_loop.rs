@@ -1,86 +1,197 @@
-//! Agent loop: streaming, tool execution, repeat until done.
+//! Agent loop: API call (streaming or not), tool execution, repeat until done.
 
-use async_openai::Client;
-use async_openai::config::OpenAIConfig;
 use futures::StreamExt;
 use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::core::audit::TurnOrigin;
+use crate::core::config::OpenRouterOptions;
 use crate::core::confirm::ConfirmDestructive;
+use crate::core::generation::GenerationParams;
+use crate::core::notify;
+use crate::core::pins::PinnedMessage;
+use crate::core::spend::{self, SpendLimits};
 use crate::core::tools;
+use crate::core::trace::{self, TraceEvent};
+use crate::core::{hooks, message, models, stats};
 
+use super::cache;
+use super::client::ChatClient;
 use super::context;
-use super::stream::{MAX_CONTENT_BYTES, TokenUsage, merge_tool_call_delta, parse_usage};
-use super::tool_execution;
+use super::prompt;
+use super::stream::{
+    MAX_CONTENT_BYTES, MessageUsage, TokenUsage, ToolCallProgress, collect_message_tool_calls,
+    merge_tool_call_delta, parse_usage,
+};
+use super::structured;
+use super::subtask;
+use super::text_tools::{self, ToolProtocol};
+use super::tool_execution::{self, ToolModes};
 use super::undo;
-use super::{ChatError, ChatResult, map_api_error};
+use super::{ChatError, ChatResult, ProgressEvent, ToolEvent};
 
 /// Maximum number of retries for transient API errors.
 const MAX_RETRIES: u32 = 3;
 /// Base delay in milliseconds for exponential backoff (1s, 2s, 4s).
 const BASE_DELAY_MS: u64 = 1000;
+/// Longest provider-requested wait honoured before retrying; longer waits fail the request.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// `finish_reason` of a response cut off by the output token limit.
+const FINISH_LENGTH: &str = "length";
 
 fn make_complete(
-    content: &str,
+    post: hooks::PostProcessed,
     tool_log: &[String],
     messages: &[Value],
     usage: TokenUsage,
+    message_usage: MessageUsage,
+    truncated: bool,
+    structured: Option<Value>,
 ) -> ChatResult {
     ChatResult::Complete {
-        content: content.to_string(),
+        content: post.content,
         tool_log: tool_log.to_vec(),
         messages: messages.to_vec(),
         usage,
+        message_usage,
+        truncated,
+        annotations: post.annotations,
+        save_blocked: post.save_blocked,
+        structured,
+        suggestions: None,
     }
 }
 
+/// Text of the last user message: the prompt the reply answers.
+fn last_prompt(messages: &[Value]) -> String {
+    messages
+        .iter()
+        .rev()
+        .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+        .and_then(message::extract_content)
+        .unwrap_or_default()
+}
+
 /// Callbacks and options for the agent loop (confirmation, progress, streaming, cancellation).
 pub(super) struct AgentLoopCallbacks<'a> {
     pub confirm_destructive: &'a Option<ConfirmDestructive>,
-    pub on_progress: Option<&'a (dyn Fn(&str) + Send + Sync)>,
+    pub on_progress: Option<&'a (dyn Fn(&ProgressEvent) + Send + Sync)>,
     pub on_content_chunk: Option<&'a (dyn Fn(&str) + Send + Sync)>,
+    pub on_tool_event: Option<&'a (dyn Fn(&ToolEvent) + Send + Sync)>,
     pub cancel_token: Option<&'a CancellationToken>,
 }
 
 /// Core parameters for the agent loop (API, model, tools, messages).
 pub(super) struct AgentLoopParams<'a> {
-    pub client: &'a Client<OpenAIConfig>,
+    pub client: &'a ChatClient,
     pub model: &'a str,
     pub context_length: u64,
     pub tools_defs: &'a [Value],
+    /// How the tools are offered: switches to the fallback protocol when the provider
+    /// rejects tool definitions.
+    pub tool_protocol: ToolProtocol,
+    /// Fallback for models without tool calling: text protocol when set, else no tools.
+    pub text_tools: bool,
     pub tools_list: &'a [Box<dyn tools::Tool>],
+    /// Tools disabled by configuration: not advertised to the model and rejected if called.
+    pub disabled_tools: &'a [String],
     pub messages: &'a mut Arc<Vec<Value>>,
     pub tool_log: &'a mut Arc<Vec<String>>,
     pub mode: &'a str,
     pub undo_stack: Option<undo::SharedUndoStack>,
+    /// Usage of the reply so far (non-empty when resuming after a confirmation).
+    pub message_usage: MessageUsage,
+    /// Pinned files, re-read and added to the system prompt before each API call.
+    pub pinned_files: &'a [PathBuf],
+    /// Pinned messages, quoted in the system prompt before each API call.
+    pub pinned_messages: &'a [PinnedMessage],
+    /// Directory that tool paths and Bash commands are resolved against.
+    pub working_dir: &'a Path,
+    /// Time limits of the tools that can be stopped.
+    pub tool_timeouts: &'a tools::ToolTimeouts,
+    /// Dry-run and suggest modes of the conversation, applied to each tool call.
+    pub modes: ToolModes,
+    /// Stream responses; when false (or after a stream failure), use non-streaming requests.
+    pub streaming: bool,
+    /// Mark the system prompt for prompt caching (see `cache`).
+    pub prompt_cache: bool,
+    /// Provider routing and transforms added to each request (OpenRouter).
+    pub routing: &'a OpenRouterOptions,
+    /// Temperature, max_tokens, top_p, and stop sequences added to each request.
+    pub generation: &'a GenerationParams,
+    /// Webhook URLs notified of turn completion, pending confirmation, and budget overflow.
+    pub webhook_urls: &'a [String],
+    /// Scripts run on the final reply of the turn (see `core::hooks`).
+    pub post_hooks: &'a [String],
+    /// Follow-up requests sent when a reply is cut off by the output token limit.
+    pub auto_continue: u32,
+    /// Limits of a subtask's loop (see `subtask::run`); None for the main conversation.
+    pub budget: Option<subtask::Budget>,
+    /// Spend caps checked after each model call: once one is reached (and not overridden),
+    /// the tool calls of the reply are not run and the turn ends.
+    pub spend_limits: SpendLimits,
+    /// Conversation and turn recorded with the tool calls in the audit log.
+    pub audit: &'a TurnOrigin,
 }
 
-/// Result of a single streaming API call: content, tool calls, and token usage.
+/// Result of a single API call (streaming or not): content, tool calls, and token usage.
 struct StreamResult {
     content: String,
     tool_calls: Vec<Value>,
     usage: TokenUsage,
+    /// Why the model stopped (`stop`, `length`, `tool_calls`, ...), when reported.
+    finish_reason: Option<String>,
+    /// Duration of the successful attempt (set by `api_call_with_retry`).
+    latency: Duration,
+}
+
+/// Chat completion request body. Tools are omitted when none are offered (all disabled, or
+/// a local model without tool support), since some servers reject an empty `tools` list.
+fn request_body(
+    model: &str,
+    messages: &[Value],
+    tools_defs: &[Value],
+    routing: &OpenRouterOptions,
+    generation: &GenerationParams,
+    stream: bool,
+) -> Value {
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "stream": stream,
+        // OpenRouter usage accounting: adds `usage.cost` (tracked by core::spend).
+        "usage": { "include": true },
+    });
+    if !tools_defs.is_empty() {
+        body["tool_choice"] = json!("auto");
+        body["tools"] = json!(tools_defs);
+    }
+    routing.apply(&mut body);
+    generation.apply(&mut body);
+    body
 }
 
 /// Make a single streaming API call and collect the full response.
+#[allow(clippy::too_many_arguments)]
 async fn stream_api_call(
-    client: &Client<OpenAIConfig>,
+    client: &ChatClient,
     model: &str,
     messages: &[Value],
     tools_defs: &[Value],
+    routing: &OpenRouterOptions,
+    generation: &GenerationParams,
     cancel_token: Option<&CancellationToken>,
+    on_progress: Option<&(dyn Fn(&ProgressEvent) + Send + Sync)>,
     on_content_chunk: Option<&(dyn Fn(&str) + Send + Sync)>,
 ) -> Result<StreamResult, ChatError> {
-    let chat_api = client.chat();
-    let stream_future = chat_api.create_stream_byot::<_, Value>(json!({
-        "model": model,
-        "messages": messages,
-        "tool_choice": "auto",
-        "tools": tools_defs,
-        "stream": true,
-    }));
+    let stream_future = client.stream(request_body(
+        model, messages, tools_defs, routing, generation, true,
+    ));
 
     let stream_result = if let Some(token) = cancel_token {
         tokio::select! {
@@ -94,11 +205,13 @@ async fn stream_api_call(
         stream_future.await
     };
 
-    let mut stream = stream_result.map_err(map_api_error)?;
+    let mut stream = stream_result?;
 
     let mut full_content = String::new();
     let mut accumulated_tool_calls: Vec<Value> = Vec::new();
+    let mut tool_progress = ToolCallProgress::default();
     let mut last_usage = TokenUsage::default();
+    let mut finish_reason = None;
 
     // Read stream chunks, racing against cancellation.
     loop {
@@ -115,14 +228,14 @@ async fn stream_api_call(
         };
 
         let Some(chunk_result) = chunk_opt else { break };
-        let chunk = chunk_result.map_err(map_api_error)?;
+        let chunk = chunk_result?;
 
         if let Some(err) = chunk.get("error") {
             let msg = err
                 .get("message")
                 .and_then(|v| v.as_str())
                 .unwrap_or("Unknown error");
-            return Err(ChatError::ApiMessage(msg.to_string()));
+            return Err(ChatError::from_api_message(msg));
         }
 
         // Capture token usage from the final chunk (OpenRouter includes it).
@@ -135,6 +248,9 @@ async fn stream_api_call(
         let Some(choice) = choices.first() else {
             continue;
         };
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            finish_reason = Some(reason.to_string());
+        }
         let delta = &choice["delta"];
 
         if let Some(content) = delta["content"].as_str() {
@@ -151,6 +267,13 @@ async fn stream_api_call(
         if let Some(tc_arr) = delta["tool_calls"].as_array() {
             for tc in tc_arr {
                 merge_tool_call_delta(&mut accumulated_tool_calls, tc);
+                if let Some(progress) = on_progress
+                    && let Some(index) = tc["index"].as_u64()
+                    && let Some(event) =
+                        tool_progress.update(&accumulated_tool_calls, index as usize)
+                {
+                    progress(&event);
+                }
             }
         }
     }
@@ -159,40 +282,158 @@ async fn stream_api_call(
         content: full_content,
         tool_calls: accumulated_tool_calls,
         usage: last_usage,
+        finish_reason,
+        latency: Duration::ZERO,
     })
 }
 
-/// Make a streaming API call with retry on transient errors (rate limit, timeout, overload).
-async fn stream_api_call_with_retry(
-    client: &Client<OpenAIConfig>,
+/// Make a single non-streaming API call. Content is passed to `on_content_chunk` in one piece.
+#[allow(clippy::too_many_arguments)]
+async fn complete_api_call(
+    client: &ChatClient,
     model: &str,
     messages: &[Value],
     tools_defs: &[Value],
+    routing: &OpenRouterOptions,
+    generation: &GenerationParams,
     cancel_token: Option<&CancellationToken>,
-    on_progress: Option<&(dyn Fn(&str) + Send + Sync)>,
+    on_content_chunk: Option<&(dyn Fn(&str) + Send + Sync)>,
+) -> Result<StreamResult, ChatError> {
+    let response_future = client.complete(request_body(
+        model, messages, tools_defs, routing, generation, false,
+    ));
+
+    let response_result = if let Some(token) = cancel_token {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => {
+                return Err(ChatError::Cancelled);
+            }
+            result = response_future => result,
+        }
+    } else {
+        response_future.await
+    };
+
+    let response = response_result?;
+
+    if let Some(err) = response.get("error") {
+        let msg = err
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error");
+        return Err(ChatError::from_api_message(msg));
+    }
+
+    let choice = &response["choices"][0];
+    let message = &choice["message"];
+    let mut content = message["content"].as_str().unwrap_or("").to_string();
+    content.truncate(content.floor_char_boundary(MAX_CONTENT_BYTES));
+    if !content.is_empty()
+        && let Some(cb) = on_content_chunk
+    {
+        cb(&content);
+    }
+
+    Ok(StreamResult {
+        content,
+        tool_calls: collect_message_tool_calls(message),
+        usage: parse_usage(&response).unwrap_or_default(),
+        finish_reason: choice["finish_reason"].as_str().map(str::to_string),
+        latency: Duration::ZERO,
+    })
+}
+
+/// Make an API call with retry on transient errors (rate limit, timeout, overload).
+#[allow(clippy::too_many_arguments)]
+async fn api_call_with_retry(
+    client: &ChatClient,
+    model: &str,
+    messages: &[Value],
+    tools_defs: &[Value],
+    routing: &OpenRouterOptions,
+    generation: &GenerationParams,
+    streaming: bool,
+    cancel_token: Option<&CancellationToken>,
+    on_progress: Option<&(dyn Fn(&ProgressEvent) + Send + Sync)>,
     on_content_chunk: Option<&(dyn Fn(&str) + Send + Sync)>,
 ) -> Result<StreamResult, ChatError> {
     for attempt in 0..=MAX_RETRIES {
-        match stream_api_call(
-            client,
-            model,
-            messages,
-            tools_defs,
-            cancel_token,
-            on_content_chunk,
-        )
-        .await
-        {
-            Ok(result) => return Ok(result),
-            Err(e) if e.is_retryable() && attempt < MAX_RETRIES => {
-                let delay_ms = BASE_DELAY_MS * 2u64.pow(attempt);
+        trace::record(TraceEvent::ApiRequest {
+            model: model.to_string(),
+            messages: messages.len(),
+            request_bytes: serde_json::to_vec(messages).map(|v| v.len()).unwrap_or(0),
+            streaming,
+        });
+        let started = Instant::now();
+        let call_result = if streaming {
+            stream_api_call(
+                client,
+                model,
+                messages,
+                tools_defs,
+                routing,
+                generation,
+                cancel_token,
+                on_progress,
+                on_content_chunk,
+            )
+            .await
+        } else {
+            complete_api_call(
+                client,
+                model,
+                messages,
+                tools_defs,
+                routing,
+                generation,
+                cancel_token,
+                on_content_chunk,
+            )
+            .await
+        };
+        let latency = started.elapsed();
+        let duration_ms = trace::millis(latency);
+        match &call_result {
+            Ok(result) => trace::record(TraceEvent::ApiResponse {
+                model: model.to_string(),
+                duration_ms,
+                content_bytes: result.content.len(),
+                tool_calls: result.tool_calls.len(),
+                prompt_tokens: result.usage.prompt_tokens,
+                completion_tokens: result.usage.completion_tokens,
+                cost: result.usage.cost,
+            }),
+            Err(ChatError::Cancelled) => {}
+            Err(e) => trace::record(TraceEvent::ApiError {
+                model: model.to_string(),
+                duration_ms,
+                error: e.to_string(),
+            }),
+        }
+        match call_result {
+            Ok(result) => return Ok(StreamResult { latency, ..result }),
+            Err(e)
+                if e.is_retryable()
+                    && attempt < MAX_RETRIES
+                    && e.retry_after().is_none_or(|wait| wait <= MAX_RETRY_AFTER) =>
+            {
+                let delay_ms = e
+                    .retry_after()
+                    .map_or(BASE_DELAY_MS * 2u64.pow(attempt), |wait| {
+                        (wait.as_millis() as u64).max(BASE_DELAY_MS)
+                    });
+                trace::record(TraceEvent::Retry {
+                    attempt: attempt + 1,
+                    delay_ms,
+                    error: e.to_string(),
+                });
                 if let Some(progress) = on_progress {
-                    progress(&format!(
-                        "Retrying in {}s... (attempt {}/{})",
-                        delay_ms / 1000,
-                        attempt + 1,
-                        MAX_RETRIES
-                    ));
+                    progress(&ProgressEvent::Retrying {
+                        delay: Duration::from_millis(delay_ms),
+                        attempt: attempt + 1,
+                        max_attempts: MAX_RETRIES,
+                    });
                 }
                 // Sleep while respecting cancellation.
                 let sleep = tokio::time::sleep(Duration::from_millis(delay_ms));
@@ -219,6 +460,26 @@ pub(super) async fn run_agent_loop(
 ) -> Result<ChatResult, ChatError> {
     let cancel_token = callbacks.cancel_token;
     let mut init_file_written = false;
+    let mut streaming = params.streaming;
+    let mut message_usage = params.message_usage.clone();
+    // Text of the reply before its automatic continuations, and how many were sent.
+    let mut continued_content = String::new();
+    let mut continuations = 0;
+    let mut protocol = params.tool_protocol;
+    // Model calls of the turn, counted against the budget.
+    let mut model_calls = 0;
+    // Replies rejected for not matching the output schema.
+    let mut structured_retries = 0;
+    let enabled_defs = tool_execution::enabled_tool_definitions(
+        params.tools_defs,
+        params.tools_list,
+        params.mode,
+        params.disabled_tools,
+    );
+    let event_ctx = notify::EventContext {
+        model: params.model,
+        mode: params.mode,
+    };
 
     loop {
         // Check cancellation before starting a new API call.
@@ -227,24 +488,142 @@ pub(super) async fn run_agent_loop(
         }
 
         // Truncate context if it exceeds the model's window.
-        context::truncate_if_needed(Arc::make_mut(params.messages), params.context_length);
+        let removed_messages =
+            context::truncate_if_needed(Arc::make_mut(params.messages), params.context_length);
+        if removed_messages > 0 {
+            trace::record(TraceEvent::ContextTruncated {
+                removed_messages,
+                context_length: params.context_length,
+            });
+            let event = notify::Event::BudgetExceeded {
+                removed_messages,
+                context_length: params.context_length,
+            };
+            notify::send(params.webhook_urls, &event, event_ctx).await;
+        }
 
         if let Some(ref progress) = callbacks.on_progress {
-            progress("Calling API...");
+            progress(&ProgressEvent::CallingModel);
         }
 
-        let result = stream_api_call_with_retry(
+        // Track whether any content reached the caller: falling back after partial output
+        // would show the response twice.
+        let streamed_any = AtomicBool::new(false);
+        let on_chunk = |chunk: &str| {
+            streamed_any.store(true, Ordering::Relaxed);
+            if let Some(cb) = callbacks.on_content_chunk {
+                cb(chunk);
+            }
+        };
+
+        // Last call of a spent budget: no tools, so the model answers with what it has.
+        let last_call = params
+            .budget
+            .is_some_and(|budget| budget.is_spent(model_calls, &message_usage));
+        let mut request_messages =
+            prompt::with_pins(params.messages, params.pinned_files, params.pinned_messages);
+        if protocol == ToolProtocol::Text {
+            request_messages =
+                text_tools::request_messages(&request_messages, &enabled_defs).into();
+        }
+        if params.prompt_cache {
+            cache::mark_system_prompt(params.model, &mut request_messages);
+        }
+        if last_call {
+            request_messages
+                .to_mut()
+                .push(subtask::final_call_message());
+        }
+        let offered_defs: &[Value] = match protocol {
+            ToolProtocol::Native if !last_call => &enabled_defs,
+            _ => &[],
+        };
+        let mut result = match api_call_with_retry(
             params.client,
             params.model,
-            params.messages.as_ref(),
-            params.tools_defs,
+            &request_messages,
+            offered_defs,
+            params.routing,
+            params.generation,
+            streaming,
             cancel_token,
             callbacks.on_progress,
-            callbacks.on_content_chunk,
+            Some(&on_chunk),
         )
-        .await?;
+        .await
+        {
+            // The model cannot call tools: continue the turn with the fallback protocol.
+            Err(ChatError::ToolsUnsupported(msg)) if !offered_defs.is_empty() => {
+                log::info!("{} rejected tools: {}", params.model, msg);
+                protocol = ToolProtocol::fallback(params.text_tools);
+                models::mark_tools_unsupported(params.model);
+                if let Some(progress) = callbacks.on_progress {
+                    progress(&ProgressEvent::ToolsUnsupported {
+                        model: params.model.to_string(),
+                        text_tools: params.text_tools,
+                    });
+                }
+                continue;
+            }
+            Err(e)
+                if streaming
+                    && e.allows_non_streaming_fallback()
+                    && !streamed_any.load(Ordering::Relaxed) =>
+            {
+                log::warn!("Streaming failed, falling back to non-streaming: {}", e);
+                trace::record(TraceEvent::StreamFallback {
+                    error: e.to_string(),
+                });
+                if let Some(ref progress) = callbacks.on_progress {
+                    progress(&ProgressEvent::StreamingFallback);
+                }
+                // Stay non-streaming for the rest of this turn.
+                streaming = false;
+                api_call_with_retry(
+                    params.client,
+                    params.model,
+                    &request_messages,
+                    offered_defs,
+                    params.routing,
+                    params.generation,
+                    streaming,
+                    cancel_token,
+                    callbacks.on_progress,
+                    callbacks.on_content_chunk,
+                )
+                .await?
+            }
+            other => other?,
+        };
+        model_calls += 1;
+        if protocol == ToolProtocol::Text && result.tool_calls.is_empty() {
+            let (text, calls) = text_tools::parse_tool_calls(&result.content);
+            if !calls.is_empty() {
+                result.content = text;
+                result.tool_calls = calls;
+            }
+        }
+        // Tool calls past the budget are not run: the reply ends the loop.
+        if last_call {
+            result.tool_calls.clear();
+        }
 
         let last_usage = result.usage;
+        message_usage.add_call(params.model, &last_usage, result.latency);
+        if let Err(e) = spend::record(last_usage.cost) {
+            log::warn!("Failed to record spend: {}", e);
+        }
+        cache::record(params.model, &last_usage);
+        stats::record_request(params.model, &last_usage);
+        // A runaway loop stops at a spend cap: the tool calls of the reply are not run.
+        let capped = if result.tool_calls.is_empty() {
+            None
+        } else {
+            spend::blocking_limit(&params.spend_limits)
+        };
+        if capped.is_some() {
+            result.tool_calls.clear();
+        }
 
         let assistant_message = if !result.tool_calls.is_empty() {
             json!({
@@ -263,25 +642,95 @@ pub(super) async fn run_agent_loop(
             })
         };
 
+        // Extract tool_calls before summarizing: tools run with the full arguments.
+        let tool_calls = assistant_message
+            .get("tool_calls")
+            .and_then(|v| v.as_array())
+            .cloned();
+
         Arc::make_mut(params.messages).push(assistant_message);
 
         // Summarize Write/Edit tool arguments to reduce context bloat on subsequent turns.
         context::summarize_write_args_in_last(Arc::make_mut(params.messages).as_mut_slice());
 
-        // Extract tool_calls from the (potentially summarized) message.
-        let tool_calls_vec = match params
-            .messages
-            .last()
-            .and_then(|m| m.get("tool_calls"))
-            .and_then(|v| v.as_array())
-        {
-            Some(tc) if !tc.is_empty() => tc.to_vec(),
+        let truncated = result.finish_reason.as_deref() == Some(FINISH_LENGTH);
+        let tool_calls_vec = match tool_calls {
+            Some(tc) if !tc.is_empty() => {
+                continued_content.clear();
+                tc
+            }
+            _ if truncated && capped.is_none() && continuations < params.auto_continue => {
+                continuations += 1;
+                continued_content.push_str(&result.content);
+                Arc::make_mut(params.messages).push(prompt::continue_message());
+                if let Some(progress) = callbacks.on_progress {
+                    progress(&ProgressEvent::Continuing {
+                        attempt: continuations,
+                        max_attempts: params.auto_continue,
+                    });
+                }
+                continue;
+            }
             _ => {
+                let content = std::mem::take(&mut continued_content) + &result.content;
+                // A reply that does not match the output schema is asked for again.
+                let structured = match params.generation.response_schema {
+                    Some(ref schema) if capped.is_none() => {
+                        match structured::parse(&content, schema) {
+                            Ok(value) => Some(Ok(value)),
+                            Err(error) if structured_retries < structured::MAX_RETRIES => {
+                                structured_retries += 1;
+                                Arc::make_mut(params.messages)
+                                    .push(structured::retry_message(&error));
+                                if let Some(progress) = callbacks.on_progress {
+                                    progress(&ProgressEvent::InvalidStructuredOutput {
+                                        error,
+                                        attempt: structured_retries,
+                                        max_attempts: structured::MAX_RETRIES,
+                                    });
+                                }
+                                continue;
+                            }
+                            Err(error) => Some(Err(error)),
+                        }
+                    }
+                    _ => None,
+                };
+                let prompt = last_prompt(params.messages);
+                let turn = hooks::TurnContext {
+                    prompt: &prompt,
+                    model: params.model,
+                    mode: params.mode,
+                    working_dir: params.working_dir,
+                };
+                let mut post = hooks::post_process(params.post_hooks, &content, turn).await;
+                if let Some(hit) = capped {
+                    post.annotations.push(format!(
+                        "{}: the turn stopped before running the tool calls of the reply. \
+                         Send a prompt to continue past the cap.",
+                        hit
+                    ));
+                }
+                if let Some(Err(ref error)) = structured {
+                    post.annotations.push(format!(
+                        "Structured output rejected after {} retries: {}",
+                        structured::MAX_RETRIES,
+                        error
+                    ));
+                }
+                let event = notify::Event::TurnCompleted {
+                    content: post.content.clone(),
+                    usage: last_usage.clone(),
+                };
+                notify::send(params.webhook_urls, &event, event_ctx).await;
                 return Ok(make_complete(
-                    &result.content,
+                    post,
                     params.tool_log.as_ref(),
                     params.messages.as_ref(),
                     last_usage,
+                    message_usage,
+                    truncated,
+                    structured.and_then(Result::ok),
                 ));
             }
         };
@@ -292,12 +741,27 @@ pub(super) async fn run_agent_loop(
             return Err(ChatError::Cancelled);
         }
 
+        if let Some(cb) = callbacks.on_tool_event {
+            cb(&ToolEvent::Queued(
+                tool_calls
+                    .iter()
+                    .map(|tc| {
+                        tool_execution::tool_call_info(tc, params.tools_list, params.working_dir)
+                    })
+                    .collect(),
+            ));
+        }
+
         // Create an undo batch for this iteration (captures file state before modifications).
         let mut undo_batch = undo::UndoBatch::default();
 
         // Check if all tool calls in this batch are read-only (safe to parallelize).
+        // Task calls run their own loop in this task, one at a time.
         let all_read_only = tool_calls.iter().all(|tc| {
             let name = tc["function"]["name"].as_str().unwrap_or_default();
+            if subtask::is_subtask_call(tc, params.tools_list) {
+                return false;
+            }
             params
                 .tools_list
                 .iter()
@@ -308,33 +772,101 @@ pub(super) async fn run_agent_loop(
         if all_read_only && tool_calls.len() > 1 {
             // Execute read-only tools in parallel using blocking tasks.
             // No undo needed for read-only tools.
+            let working_dir = params.working_dir.to_path_buf();
+            let tool_timeouts = params.tool_timeouts.clone();
             let mode = params.mode.to_string();
+            let disabled_tools = params.disabled_tools.to_vec();
             let tools_list = params.tools_list;
             let tool_calls_owned: Vec<Value> = tool_calls.to_vec();
 
-            let handles: Vec<_> = tool_calls_owned
+            let total = tool_calls_owned.len();
+            for (i, tc) in tool_calls_owned.iter().enumerate() {
+                if let Some(progress) = callbacks.on_progress {
+                    progress(&tool_execution::progress_event(
+                        tc,
+                        params.tools_list,
+                        params.working_dir,
+                        params.modes,
+                        i + 1,
+                        total,
+                    ));
+                }
+                if let Some(cb) = callbacks.on_tool_event {
+                    cb(&ToolEvent::Started {
+                        id: tc["id"].as_str().unwrap_or_default().to_string(),
+                    });
+                }
+            }
+
+            let mut pending: futures::stream::FuturesUnordered<_> = tool_calls_owned
                 .into_iter()
-                .map(|tc| {
+                .enumerate()
+                .map(|(i, tc)| {
                     let mode = mode.clone();
+                    let disabled_tools = disabled_tools.clone();
+                    let working_dir = working_dir.clone();
+                    let tool_timeouts = tool_timeouts.clone();
                     let tools_ref: *const [Box<dyn tools::Tool>] = tools_list;
                     // SAFETY: tools_list is borrowed from params which outlives this scope.
-                    // We join all tasks before the end of this block via join_all.await below.
+                    // All tasks are drained below before any result is inspected or returned.
                     let tools_static: &'static [Box<dyn tools::Tool>] = unsafe { &*tools_ref };
-                    tokio::task::spawn_blocking(move || {
-                        tool_execution::execute_read_only_tool_call(&tc, tools_static, &mode)
-                    })
+                    let id = tc["id"].as_str().unwrap_or_default().to_string();
+                    let name = tc["function"]["name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string();
+                    let handle = tokio::task::spawn_blocking(move || {
+                        let started = Instant::now();
+                        let result = tool_execution::execute_read_only_tool_call(
+                            &tc,
+                            tools_static,
+                            &mode,
+                            &disabled_tools,
+                            &working_dir,
+                            &tool_timeouts,
+                        );
+                        (result, started.elapsed())
+                    });
+                    async move { (i, id, name, handle.await) }
                 })
                 .collect();
 
-            let results = futures::future::join_all(handles).await;
+            // Drain in completion order (for Finished events), then restore request order.
+            let mut results = Vec::with_capacity(pending.len());
+            while let Some((i, id, name, join_result)) = pending.next().await {
+                let (success, duration, output_bytes, view) = match &join_result {
+                    Ok((Ok(r), d)) => (
+                        !tool_execution::is_error_result(&r.content),
+                        *d,
+                        r.content.len(),
+                        r.view.clone(),
+                    ),
+                    Ok((Err(_), d)) => (false, *d, 0, None),
+                    Err(_) => (false, Duration::ZERO, 0, None),
+                };
+                stats::record_tool(&name);
+                trace::record(TraceEvent::Tool {
+                    name,
+                    duration_ms: trace::millis(duration),
+                    success,
+                    output_bytes,
+                });
+                if let Some(cb) = callbacks.on_tool_event {
+                    cb(&ToolEvent::Finished {
+                        id,
+                        success,
+                        duration,
+                        view,
+                    });
+                }
+                results.push((i, join_result));
+            }
+            results.sort_by_key(|(i, _)| *i);
 
-            for join_result in results {
-                let tool_result = join_result.map_err(|e| ChatError::Other(e.into()))??;
+            for (_, join_result) in results {
+                let tool_result = join_result.map_err(|e| ChatError::Other(e.into()))?.0?;
 
-                Arc::make_mut(params.tool_log).push(tool_result.log_line.clone());
-                if let Some(ref progress) = callbacks.on_progress {
-                    progress(&tool_result.log_line);
-                }
+                Arc::make_mut(params.tool_log).push(tool_result.log_line);
                 Arc::make_mut(params.messages).push(json!({
                     "role": "tool",
                     "tool_call_id": tool_result.tool_call_id,
@@ -343,27 +875,61 @@ pub(super) async fn run_agent_loop(
             }
         } else {
             // Sequential execution for write tools, single tool calls, or mixed batches.
-            for tool_call in tool_calls {
+            let total = tool_calls.len();
+            for (i, tool_call) in tool_calls.iter().enumerate() {
                 if cancel_token.is_some_and(|t| t.is_cancelled()) {
                     return Err(ChatError::Cancelled);
                 }
 
+                if subtask::is_subtask_call(tool_call, params.tools_list) {
+                    let outcome =
+                        subtask::run(tool_call, &params, &callbacks, protocol, (i + 1, total))
+                            .await?;
+                    message_usage.add_usage(&outcome.usage);
+                    Arc::make_mut(params.tool_log).push(outcome.log_line);
+                    Arc::make_mut(params.messages).push(outcome.message);
+                    continue;
+                }
+
                 let mut tool_ctx = tool_execution::ToolCallContext {
                     confirm_destructive: callbacks.confirm_destructive,
                     tools_defs: params.tools_defs,
+                    disabled_tools: params.disabled_tools,
                     messages: params.messages,
                     tool_log: params.tool_log,
                     on_progress: callbacks.on_progress,
+                    on_tool_event: callbacks.on_tool_event,
                     init_file_written: Some(&mut init_file_written),
                     undo_batch: Some(&mut undo_batch),
                     undo_stack: params.undo_stack.clone(),
+                    cancel_token,
+                    working_dir: params.working_dir,
+                    tool_timeouts: params.tool_timeouts,
+                    modes: params.modes,
+                    position: (i + 1, total),
+                    audit: params.audit,
                 };
-                if let Some(needs_confirmation) = tool_execution::execute_tool_call(
+                if let Some(mut needs_confirmation) = tool_execution::execute_tool_call(
                     tool_call,
                     params.tools_list,
                     params.mode,
                     &mut tool_ctx,
                 )? {
+                    if let ChatResult::NeedsConfirmation {
+                        ref action,
+                        ref mut state,
+                    } = needs_confirmation
+                    {
+                        state.message_usage = message_usage.clone();
+                        state.pinned_files = params.pinned_files.to_vec();
+                        state.pinned_messages = params.pinned_messages.to_vec();
+                        state.generation = params.generation.clone();
+                        state.tool_protocol = protocol;
+                        let event = notify::Event::ConfirmationPending {
+                            command: action.to_string(),
+                        };
+                        notify::send(params.webhook_urls, &event, event_ctx).await;
+                    }
                     return Ok(needs_confirmation);
                 }
             }
@@ -0,0 +1,708 @@
+//! Anthropic Messages API backend (`MY_OPEN_CLAUDE_PROVIDER=anthropic`).
+//!
+//! The rest of the crate speaks chat completion JSON; this module translates request bodies
+//! to the Messages API (system blocks, `tool_use` / `tool_result` content blocks, tool input
+//! schemas) and translates responses and streaming events back into chat completion
+//! messages and chunks. Usage, prompt cache reads and writes included, comes back in the
+//! `usage` shape `parse_usage` reads.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use super::client::ChunkStream;
+use super::error::{ChatError, map_api_error, retry_wait};
+
+/// Version of the Messages API the translation targets.
+pub const API_VERSION: &str = "2023-06-01";
+
+/// Reply token limit when none is configured: the Messages API requires one.
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+/// Highest temperature the Messages API accepts (chat completions go up to 2).
+const MAX_TEMPERATURE: f64 = 1.0;
+
+/// Client of the Messages API at `base_url` (e.g. "https://api.anthropic.com").
+/// `prompt_cache` places cache breakpoints (`MY_OPEN_CLAUDE_PROMPT_CACHE`).
+#[derive(Debug, Clone)]
+pub struct AnthropicClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    prompt_cache: bool,
+}
+
+impl AnthropicClient {
+    pub fn new(http: reqwest::Client, base_url: &str, api_key: &str, prompt_cache: bool) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            prompt_cache,
+        }
+    }
+
+    /// Send a chat completion `body`, translated; errors carry the API's error type.
+    async fn send(&self, body: &Value) -> Result<reqwest::Response, ChatError> {
+        let response = self
+            .http
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&request(body, self.prompt_cache))
+            .send()
+            .await
+            .map_err(map_api_error)?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status().as_u16();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .and_then(retry_wait);
+        let text = response.text().await.unwrap_or_default();
+        Err(status_error(status, &text, retry_after))
+    }
+
+    /// Non-streaming request; returns a chat completion response.
+    pub async fn complete(&self, body: &Value) -> Result<Value, ChatError> {
+        let response = self.send(body).await?;
+        let message: Value = response.json().await.map_err(map_api_error)?;
+        Ok(response_to_chat(&message))
+    }
+
+    /// Streaming request; yields chat completion chunks.
+    pub async fn stream(&self, body: &Value) -> Result<ChunkStream, ChatError> {
+        let response = self.send(body).await?;
+        let state = StreamState {
+            response,
+            events: EventBuffer::default(),
+            translator: StreamTranslator::default(),
+            pending: VecDeque::new(),
+            done: false,
+        };
+        Ok(Box::pin(futures::stream::unfold(state, next_chunk)))
+    }
+}
+
+/// Progress of a streaming response: the HTTP body, undecoded events, and translated
+/// chunks not yet yielded.
+struct StreamState {
+    response: reqwest::Response,
+    events: EventBuffer,
+    translator: StreamTranslator,
+    pending: VecDeque<Result<Value, ChatError>>,
+    done: bool,
+}
+
+async fn next_chunk(mut state: StreamState) -> Option<(Result<Value, ChatError>, StreamState)> {
+    loop {
+        if let Some(item) = state.pending.pop_front() {
+            return Some((item, state));
+        }
+        if state.done {
+            return None;
+        }
+        match state.response.chunk().await {
+            Ok(Some(bytes)) => {
+                for event in state.events.push(&bytes) {
+                    state.pending.extend(state.translator.event(&event));
+                }
+            }
+            Ok(None) => state.done = true,
+            Err(e) => {
+                state.pending.push_back(Err(map_api_error(e)));
+                state.done = true;
+            }
+        }
+    }
+}
+
+/// Messages API request for a chat completion `body` (as built by the agent loop, titles,
+/// and compaction). OpenRouter-only fields (`usage`, `provider`, `transforms`) and
+/// `response_format` have no equivalent and are dropped.
+///
+/// With `prompt_cache`, cache breakpoints go on the system prompt, the tool definitions, and
+/// the last message, so each turn reads the previous turn's prefix from the prompt cache.
+pub(super) fn request(body: &Value, prompt_cache: bool) -> Value {
+    let model = body["model"].as_str().unwrap_or_default();
+    let model = model.strip_prefix("anthropic/").unwrap_or(model);
+
+    let mut system = Vec::new();
+    let mut messages: Vec<Value> = Vec::new();
+    for message in body["messages"].as_array().into_iter().flatten() {
+        let (role, blocks) = match message["role"].as_str().unwrap_or("user") {
+            "system" | "developer" => {
+                system.extend(content_blocks(&message["content"]));
+                continue;
+            }
+            "assistant" => ("assistant", assistant_blocks(message)),
+            "tool" => ("user", vec![tool_result(message)]),
+            _ => ("user", content_blocks(&message["content"])),
+        };
+        if blocks.is_empty() {
+            continue;
+        }
+        // Tool results follow each other, and an empty assistant turn may have been
+        // skipped: the Messages API wants one message per turn.
+        match messages.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(content) = last["content"].as_array_mut() {
+                    content.extend(blocks);
+                }
+            }
+            _ => messages.push(json!({ "role": role, "content": blocks })),
+        }
+    }
+    if prompt_cache {
+        mark_cached(system.last_mut());
+        mark_cached(
+            messages
+                .last_mut()
+                .and_then(|m| m["content"].as_array_mut())
+                .and_then(|content| content.last_mut()),
+        );
+    }
+
+    let max_tokens = body["max_tokens"]
+        .as_u64()
+        .or_else(|| body["max_completion_tokens"].as_u64())
+        .unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut out = json!({
+        "model": model,
+        "max_tokens": max_tokens,
+        "messages": messages,
+    });
+    if !system.is_empty() {
+        out["system"] = json!(system);
+    }
+    if let Some(tools) = body["tools"].as_array()
+        && !tools.is_empty()
+    {
+        let mut definitions: Vec<Value> = tools.iter().map(tool_definition).collect();
+        if prompt_cache {
+            mark_cached(definitions.last_mut());
+        }
+        out["tools"] = json!(definitions);
+        out["tool_choice"] = json!({ "type": "auto" });
+    }
+    if body["stream"].as_bool() == Some(true) {
+        out["stream"] = json!(true);
+    }
+    if let Some(temperature) = body["temperature"].as_f64() {
+        out["temperature"] = json!(temperature.min(MAX_TEMPERATURE));
+    }
+    if let Some(top_p) = body["top_p"].as_f64() {
+        out["top_p"] = json!(top_p);
+    }
+    match &body["stop"] {
+        Value::String(stop) => out["stop_sequences"] = json!([stop]),
+        Value::Array(stops) if !stops.is_empty() => out["stop_sequences"] = json!(stops),
+        _ => {}
+    }
+    out
+}
+
+fn mark_cached(block: Option<&mut Value>) {
+    if let Some(block) = block {
+        block["cache_control"] = json!({ "type": "ephemeral" });
+    }
+}
+
+/// Content blocks of a message `content`: a string, or text and image parts.
+fn content_blocks(content: &Value) -> Vec<Value> {
+    match content {
+        Value::String(text) if !text.is_empty() => vec![json!({ "type": "text", "text": text })],
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| match part["type"].as_str()? {
+                "text" => part["text"]
+                    .as_str()
+                    .filter(|text| !text.is_empty())
+                    .map(|text| json!({ "type": "text", "text": text })),
+                "image_url" => part["image_url"]["url"].as_str().map(image_block),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Image block for an `image_url` part: inline for data URLs, else fetched by the API.
+fn image_block(url: &str) -> Value {
+    let inline = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"));
+    match inline {
+        Some((media_type, data)) => json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": media_type, "data": data },
+        }),
+        None => json!({ "type": "image", "source": { "type": "url", "url": url } }),
+    }
+}
+
+/// Text and `tool_use` blocks of an assistant message.
+fn assistant_blocks(message: &Value) -> Vec<Value> {
+    let mut blocks = content_blocks(&message["content"]);
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let function = &call["function"];
+        // Arguments cut off by the token limit are not an object; the tool call failed
+        // and its result says so.
+        let input = function["arguments"]
+            .as_str()
+            .and_then(|args| serde_json::from_str::<Value>(args).ok())
+            .filter(Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        blocks.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": function["name"],
+            "input": input,
+        }));
+    }
+    blocks
+}
+
+/// `tool_result` block of a `tool` message.
+fn tool_result(message: &Value) -> Value {
+    let content = match &message["content"] {
+        Value::Array(_) => json!(content_blocks(&message["content"])),
+        Value::String(text) => json!(text),
+        _ => json!(""),
+    };
+    json!({
+        "type": "tool_result",
+        "tool_use_id": message["tool_call_id"],
+        "content": content,
+    })
+}
+
+/// Messages API tool from a chat completion function definition.
+fn tool_definition(tool: &Value) -> Value {
+    let function = &tool["function"];
+    let mut definition = json!({
+        "name": function["name"],
+        "input_schema": match &function["parameters"] {
+            Value::Object(_) => function["parameters"].clone(),
+            _ => json!({ "type": "object", "properties": {} }),
+        },
+    });
+    if let Some(description) = function["description"].as_str() {
+        definition["description"] = json!(description);
+    }
+    definition
+}
+
+/// Chat completion response for a Messages API response.
+fn response_to_chat(message: &Value) -> Value {
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+    for block in message["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => content.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block["id"],
+                "type": "function",
+                "function": { "name": block["name"], "arguments": block["input"].to_string() },
+            })),
+            _ => {}
+        }
+    }
+    let mut reply = json!({ "role": "assistant", "content": content });
+    if !tool_calls.is_empty() {
+        reply["tool_calls"] = json!(tool_calls);
+    }
+    json!({
+        "id": message["id"],
+        "model": message["model"],
+        "choices": [{
+            "index": 0,
+            "message": reply,
+            "finish_reason": finish_reason(message["stop_reason"].as_str()),
+        }],
+        "usage": usage_to_chat(&message["usage"]),
+    })
+}
+
+/// Chat completion `finish_reason` for a Messages API `stop_reason`.
+fn finish_reason(stop_reason: Option<&str>) -> Option<&'static str> {
+    Some(match stop_reason? {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" => "content_filter",
+        _ => "stop",
+    })
+}
+
+/// Chat completion usage for Messages API usage. Input tokens exclude cached ones there;
+/// prompt tokens include them here, with the cache reads and writes in
+/// `prompt_tokens_details`.
+fn usage_to_chat(usage: &Value) -> Value {
+    let count = |key: &str| usage[key].as_u64().unwrap_or(0);
+    let cache_read = count("cache_read_input_tokens");
+    let cache_write = count("cache_creation_input_tokens");
+    let prompt = count("input_tokens") + cache_read + cache_write;
+    let completion = count("output_tokens");
+    json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+        "prompt_tokens_details": {
+            "cached_tokens": cache_read,
+            "cache_write_tokens": cache_write,
+        },
+    })
+}
+
+/// Error for a failed request: the error `type` of the body, else the HTTP status, decides.
+fn status_error(status: u16, body: &str, retry_after: Option<Duration>) -> ChatError {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+    let error = parsed.as_ref().map(|v| &v["error"]);
+    let message = error
+        .and_then(|e| e["message"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP {}: {}", status, body.trim()));
+    let kind = match (error.and_then(|e| e["type"].as_str()), status) {
+        (Some(kind), _) => kind,
+        (None, 401) => "authentication_error",
+        (None, 403) => "permission_error",
+        (None, 429) => "rate_limit_error",
+        (None, 500..) => "api_error",
+        (None, _) => "",
+    };
+    api_error(kind, &message, retry_after)
+}
+
+/// Error for a Messages API error `kind` ("rate_limit_error", "overloaded_error", ...).
+fn api_error(kind: &str, message: &str, retry_after: Option<Duration>) -> ChatError {
+    match kind {
+        "authentication_error" | "permission_error" => ChatError::ApiAuth(format!(
+            "API error: {}. Check ANTHROPIC_API_KEY in .env (see env.example).",
+            message
+        )),
+        "rate_limit_error" => ChatError::RateLimited {
+            message: message.to_string(),
+            retry_after,
+        },
+        "overloaded_error" | "api_error" => ChatError::ProviderDown(message.to_string()),
+        _ => ChatError::from_api_message(message),
+    }
+}
+
+/// Server-sent events split from the response body as it arrives.
+#[derive(Default)]
+struct EventBuffer {
+    buf: Vec<u8>,
+}
+
+impl EventBuffer {
+    /// Append `bytes` and return the JSON data of every event completed by them.
+    fn push(&mut self, bytes: &[u8]) -> Vec<Value> {
+        self.buf.extend(bytes.iter().filter(|&&b| b != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buf.windows(2).position(|w| w == b"\n\n") {
+            let raw: Vec<u8> = self.buf.drain(..end + 2).collect();
+            let raw = String::from_utf8_lossy(&raw);
+            let data: Vec<&str> = raw
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect();
+            if data.is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&data.join("\n")) {
+                Ok(event) => events.push(event),
+                Err(e) => log::warn!("Skipping an unreadable Anthropic stream event: {}", e),
+            }
+        }
+        events
+    }
+}
+
+/// Translation of streaming events into chat completion chunks.
+#[derive(Default)]
+struct StreamTranslator {
+    /// Usage so far: input tokens from `message_start`, output tokens from `message_delta`.
+    usage: serde_json::Map<String, Value>,
+    /// Tool call index of each `tool_use` content block, by block index.
+    tool_calls: HashMap<u64, usize>,
+}
+
+impl StreamTranslator {
+    fn event(&mut self, event: &Value) -> Option<Result<Value, ChatError>> {
+        match event["type"].as_str()? {
+            "message_start" => {
+                self.add_usage(&event["message"]["usage"]);
+                None
+            }
+            "content_block_start" => {
+                let block = &event["content_block"];
+                match block["type"].as_str()? {
+                    "tool_use" => {
+                        let index = self.tool_calls.len();
+                        self.tool_calls.insert(event["index"].as_u64()?, index);
+                        Some(Ok(chunk(json!({ "tool_calls": [{
+                            "index": index,
+                            "id": block["id"],
+                            "type": "function",
+                            "function": { "name": block["name"], "arguments": "" },
+                        }] }))))
+                    }
+                    "text" => block["text"]
+                        .as_str()
+                        .filter(|text| !text.is_empty())
+                        .map(|text| Ok(chunk(json!({ "content": text })))),
+                    _ => None,
+                }
+            }
+            "content_block_delta" => {
+                let delta = &event["delta"];
+                match delta["type"].as_str()? {
+                    "text_delta" => Some(Ok(chunk(json!({ "content": delta["text"] })))),
+                    "input_json_delta" => {
+                        let index = self.tool_calls.get(&event["index"].as_u64()?)?;
+                        Some(Ok(chunk(json!({ "tool_calls": [{
+                            "index": index,
+                            "function": { "arguments": delta["partial_json"] },
+                        }] }))))
+                    }
+                    _ => None,
+                }
+            }
+            "message_delta" => {
+                self.add_usage(&event["usage"]);
+                Some(Ok(json!({
+                    "choices": [{
+                        "index": 0,
+                        "delta": {},
+                        "finish_reason": finish_reason(event["delta"]["stop_reason"].as_str()),
+                    }],
+                    "usage": usage_to_chat(&Value::Object(self.usage.clone())),
+                })))
+            }
+            "error" => {
+                let error = &event["error"];
+                Some(Err(api_error(
+                    error["type"].as_str().unwrap_or_default(),
+                    error["message"].as_str().unwrap_or("Unknown error"),
+                    None,
+                )))
+            }
+            _ => None,
+        }
+    }
+
+    fn add_usage(&mut self, usage: &Value) {
+        if let Some(usage) = usage.as_object() {
+            for (key, value) in usage {
+                if !value.is_null() {
+                    self.usage.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+fn chunk(delta: Value) -> Value {
+    json!({ "choices": [{ "index": 0, "delta": delta, "finish_reason": null }] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::llm::stream::{merge_tool_call_delta, parse_usage};
+
+    #[test]
+    fn request_translates_messages_tools_and_generation() {
+        let body = json!({
+            "model": "anthropic/claude-haiku-4-5",
+            "stream": true,
+            "usage": { "include": true },
+            "temperature": 1.5,
+            "stop": "END",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": [
+                    { "type": "text", "text": "What is in a.rs?" },
+                    { "type": "image_url", "image_url": { "url": "data:image/png;base64,iVBO" } },
+                ] },
+                { "role": "assistant", "content": null, "tool_calls": [
+                    { "id": "t1", "type": "function", "function": { "name": "Read", "arguments": "{\"file_path\":\"a.rs\"}" } },
+                    { "id": "t2", "type": "function", "function": { "name": "Read", "arguments": "{\"file_path\":\"b.rs\"}" } },
+                ] },
+                { "role": "tool", "tool_call_id": "t1", "content": "fn a() {}" },
+                { "role": "tool", "tool_call_id": "t2", "content": "fn b() {}" },
+            ],
+            "tool_choice": "auto",
+            "tools": [{ "type": "function", "function": {
+                "name": "Read",
+                "description": "Read a file",
+                "parameters": { "type": "object", "properties": { "file_path": { "type": "string" } } },
+            } }],
+        });
+        let out = request(&body, true);
+        assert_eq!(out["model"], "claude-haiku-4-5");
+        assert_eq!(out["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(out["temperature"], 1.0);
+        assert_eq!(out["stop_sequences"], json!(["END"]));
+        assert_eq!(out["stream"], true);
+        assert!(out.get("usage").is_none());
+        assert_eq!(out["system"][0]["text"], "Be brief.");
+        assert_eq!(out["system"][0]["cache_control"]["type"], "ephemeral");
+
+        let messages = out["messages"].as_array().expect("messages");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0]["content"][1]["source"]["media_type"],
+            "image/png"
+        );
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["content"][0]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][1]["input"]["file_path"], "b.rs");
+        // Both results in one user turn, the last block marked for caching.
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "t1");
+        assert_eq!(messages[2]["content"][1]["content"], "fn b() {}");
+        assert_eq!(
+            messages[2]["content"][1]["cache_control"]["type"],
+            "ephemeral"
+        );
+
+        assert_eq!(out["tools"][0]["name"], "Read");
+        assert_eq!(out["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(out["tool_choice"], json!({ "type": "auto" }));
+    }
+
+    #[test]
+    fn request_without_prompt_cache_has_no_breakpoints() {
+        let body = json!({
+            "model": "claude-haiku-4-5",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": "hi" },
+            ],
+            "tools": [{ "type": "function", "function": { "name": "Read", "parameters": {} } }],
+        });
+        let out = request(&body, false);
+        assert!(!out.to_string().contains("cache_control"));
+    }
+
+    #[test]
+    fn response_becomes_a_chat_completion_with_cached_usage() {
+        let message = json!({
+            "id": "msg_1",
+            "content": [
+                { "type": "text", "text": "Reading it." },
+                { "type": "tool_use", "id": "t1", "name": "Read", "input": { "file_path": "a.rs" } },
+            ],
+            "stop_reason": "tool_use",
+            "usage": {
+                "input_tokens": 10,
+                "cache_creation_input_tokens": 200,
+                "cache_read_input_tokens": 1000,
+                "output_tokens": 25,
+            },
+        });
+        let response = response_to_chat(&message);
+        let choice = &response["choices"][0];
+        assert_eq!(choice["finish_reason"], "tool_calls");
+        assert_eq!(choice["message"]["content"], "Reading it.");
+        let args = choice["message"]["tool_calls"][0]["function"]["arguments"]
+            .as_str()
+            .expect("arguments");
+        assert_eq!(
+            serde_json::from_str::<Value>(args).expect("json"),
+            json!({ "file_path": "a.rs" })
+        );
+
+        let usage = parse_usage(&response).expect("usage");
+        assert_eq!(usage.prompt_tokens, 1210);
+        assert_eq!(usage.completion_tokens, 25);
+        assert_eq!(usage.cache_read_tokens, 1000);
+        assert_eq!(usage.cache_write_tokens, 200);
+    }
+
+    #[test]
+    fn stream_events_become_chunks() {
+        let body = concat!(
+            "event: message_start\r\n",
+            "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,\"cache_read_input_tokens\":300,\"output_tokens\":1}}}\r\n\r\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me look.\"}}\n\n",
+            "event: ping\n",
+            "data: {\"type\":\"ping\"}\n\n",
+            "event: content_block_start\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Read\",\"input\":{}}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"file_path\\\":\"}}\n\n",
+            "event: content_block_delta\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"\\\"a.rs\\\"}\"}}\n\n",
+            "event: message_delta\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":40}}\n\n",
+            "event: message_stop\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        // Split mid-event, as the network does.
+        let mut buffer = EventBuffer::default();
+        let (first, rest) = body.as_bytes().split_at(150);
+        let mut events = buffer.push(first);
+        events.extend(buffer.push(rest));
+        assert_eq!(events.len(), 9);
+
+        let mut translator = StreamTranslator::default();
+        let chunks: Vec<Value> = events
+            .iter()
+            .filter_map(|e| translator.event(e))
+            .collect::<Result<_, _>>()
+            .expect("no error");
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for chunk in &chunks {
+            let delta = &chunk["choices"][0]["delta"];
+            content.push_str(delta["content"].as_str().unwrap_or_default());
+            for tc in delta["tool_calls"].as_array().into_iter().flatten() {
+                merge_tool_call_delta(&mut tool_calls, tc);
+            }
+        }
+        assert_eq!(content, "Let me look.");
+        assert_eq!(tool_calls[0]["id"], "t1");
+        assert_eq!(
+            tool_calls[0]["function"]["arguments"],
+            "{\"file_path\":\"a.rs\"}"
+        );
+
+        let last = chunks.last().expect("final chunk");
+        assert_eq!(last["choices"][0]["finish_reason"], "tool_calls");
+        let usage = parse_usage(last).expect("usage");
+        assert_eq!(usage.prompt_tokens, 312);
+        assert_eq!(usage.completion_tokens, 40);
+        assert_eq!(usage.cache_read_tokens, 300);
+    }
+
+    #[test]
+    fn errors_map_by_type_then_status() {
+        let overloaded =
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(matches!(
+            status_error(529, overloaded, None),
+            ChatError::ProviderDown(_)
+        ));
+        assert!(matches!(status_error(401, "", None), ChatError::ApiAuth(_)));
+        let err = status_error(429, "", Some(Duration::from_secs(3)));
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+        let too_long = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        assert!(matches!(
+            status_error(400, too_long, None),
+            ChatError::ContextTooLong { .. }
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+//! Prompt caching: the system message (system prompt, AGENTS.md, git context) stays the same
+//! across the turns of a conversation, so it is marked with `cache_control` for the models
+//! that only cache from an explicit breakpoint. The cache reads and writes reported in usage
+//! are priced against the model's prompt price to count what caching saved (`/cost`).
+
+use std::borrow::Cow;
+
+use serde_json::{Value, json};
+
+use crate::core::models::{self, ModelInfo};
+use crate::core::spend::{self, CacheTotals};
+
+use super::stream::TokenUsage;
+
+/// Models cached only from a `cache_control` breakpoint; others (OpenAI, DeepSeek, ...) cache
+/// a repeated prefix on their own.
+fn needs_breakpoint(model: &str) -> bool {
+    model.starts_with("anthropic/") || model.starts_with("google/gemini")
+}
+
+/// Turn the system message into a text block marked `cache_control` when `model` needs a
+/// breakpoint. The messages are copied only then.
+pub(super) fn mark_system_prompt(model: &str, messages: &mut Cow<'_, [Value]>) {
+    let is_text_system = |m: &Value| m["role"] == "system" && m["content"].is_string();
+    if !needs_breakpoint(model) || !messages.first().is_some_and(is_text_system) {
+        return;
+    }
+    let system = &mut messages.to_mut()[0];
+    let text = system["content"].take();
+    system["content"] = json!([{
+        "type": "text",
+        "text": text,
+        "cache_control": { "type": "ephemeral" },
+    }]);
+}
+
+/// Credits saved by the cache on one call: reads are billed below the prompt price, writes
+/// above it. 0 when the prices are unknown.
+fn savings(model: &ModelInfo, read_tokens: u64, write_tokens: u64) -> f64 {
+    let Some(prompt) = model.prompt_price else {
+        return 0.0;
+    };
+    let read = model
+        .cache_read_price
+        .map_or(0.0, |price| (prompt - price) * read_tokens as f64);
+    let write = model
+        .cache_write_price
+        .map_or(0.0, |price| (price - prompt) * write_tokens as f64);
+    read - write
+}
+
+/// Add the prompt cache use of an API call with `model` to the session's totals.
+pub(super) fn record(model: &str, usage: &TokenUsage) {
+    let (read_tokens, write_tokens) = (usage.cache_read_tokens, usage.cache_write_tokens);
+    let saved = if read_tokens > 0 || write_tokens > 0 {
+        models::resolve_model_info(model).map_or(0.0, |m| savings(&m, read_tokens, write_tokens))
+    } else {
+        0.0
+    };
+    spend::record_cache(CacheTotals {
+        prompt_tokens: usage.prompt_tokens,
+        read_tokens,
+        write_tokens,
+        saved,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Value> {
+        vec![
+            json!({"role": "system", "content": "Be brief."}),
+            json!({"role": "user", "content": "hi"}),
+        ]
+    }
+
+    #[test]
+    fn system_prompt_is_marked_for_models_needing_a_breakpoint() {
+        let original = messages();
+        let mut request = Cow::Borrowed(original.as_slice());
+        mark_system_prompt("anthropic/claude-haiku-4.5", &mut request);
+        assert_eq!(request[0]["content"][0]["text"], "Be brief.");
+        assert_eq!(
+            request[0]["content"][0]["cache_control"],
+            json!({"type": "ephemeral"})
+        );
+        assert_eq!(request[1], original[1]);
+
+        // Other models cache on their own: the messages are not even copied.
+        let mut request = Cow::Borrowed(original.as_slice());
+        mark_system_prompt("openai/gpt-4o", &mut request);
+        assert!(matches!(request, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn savings_count_reads_below_and_writes_above_the_prompt_price() {
+        let model = ModelInfo {
+            id: "anthropic/claude-sonnet-4".to_string(),
+            name: "Claude Sonnet 4".to_string(),
+            context_length: 200_000,
+            prompt_price: Some(0.000003),
+            completion_price: Some(0.000015),
+            cache_read_price: Some(0.0000003),
+            cache_write_price: Some(0.00000375),
+        };
+        let saved = savings(&model, 100_000, 10_000);
+        assert!((saved - (0.27 - 0.0075)).abs() < 1e-9);
+        let unpriced = ModelInfo {
+            prompt_price: None,
+            ..model
+        };
+        assert_eq!(savings(&unpriced, 100_000, 0), 0.0);
+    }
+}
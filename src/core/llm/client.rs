@@ -0,0 +1,51 @@
+//! Chat API client: an OpenAI-compatible endpoint (OpenRouter, Ollama) or the Anthropic
+//! Messages API. Both take chat completion request bodies and return chat completion
+//! responses and chunks, so callers do not depend on the provider.
+
+use std::pin::Pin;
+
+use async_openai::Client;
+use async_openai::config::OpenAIConfig;
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+use super::anthropic::AnthropicClient;
+use super::error::{ChatError, map_api_error};
+
+/// Chat completion chunks of a streaming response.
+pub type ChunkStream = Pin<Box<dyn Stream<Item = Result<Value, ChatError>> + Send>>;
+
+/// Client for the configured provider (see `Config::chat_client`).
+pub enum ChatClient {
+    OpenAi(Box<Client<OpenAIConfig>>),
+    Anthropic(AnthropicClient),
+}
+
+impl ChatClient {
+    /// Non-streaming request; `body` must have `"stream": false`.
+    pub async fn complete(&self, body: Value) -> Result<Value, ChatError> {
+        match self {
+            ChatClient::OpenAi(client) => client
+                .chat()
+                .create_byot::<_, Value>(body)
+                .await
+                .map_err(map_api_error),
+            ChatClient::Anthropic(client) => client.complete(&body).await,
+        }
+    }
+
+    /// Streaming request; `body` must have `"stream": true`.
+    pub async fn stream(&self, body: Value) -> Result<ChunkStream, ChatError> {
+        match self {
+            ChatClient::OpenAi(client) => {
+                let stream = client
+                    .chat()
+                    .create_stream_byot::<_, Value>(body)
+                    .await
+                    .map_err(map_api_error)?;
+                Ok(Box::pin(stream.map(|chunk| chunk.map_err(map_api_error))))
+            }
+            ChatClient::Anthropic(client) => client.stream(&body).await,
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Conversation compaction (`/compact`): the model writes a dense summary of the earlier
+//! conversation, which replaces it in the context; the last turns are kept as they are.
+
+use serde_json::{Value, json};
+
+use crate::core::config::Config;
+use crate::core::{message, spend, stats};
+
+use super::cache;
+use super::context::estimate_tokens;
+use super::error::ChatError;
+use super::stream::parse_usage;
+
+/// User turns kept verbatim after the summary.
+pub const KEEP_TURNS: usize = 2;
+/// Longest summary requested, in tokens.
+const SUMMARY_MAX_TOKENS: u32 = 2048;
+/// Characters of each tool call's arguments and result sent for summarization.
+const TOOL_EXCERPT_CHARS: usize = 2000;
+/// First line of the summary message. It tells the summary apart from the system prompt.
+const SUMMARY_HEADING: &str =
+    "Summary of the conversation so far (the earlier messages were compacted):";
+
+const SUMMARY_INSTRUCTIONS: &str = "You compact a conversation between a user and a coding \
+assistant. Write a dense summary that lets the assistant continue the work without the \
+original messages: the user's goals and requests, decisions made, files read or changed (with \
+paths), commands run and their outcomes, errors and how they were fixed, and what remains to \
+do. Keep exact names, paths, identifiers, and numbers. Use short bullet points, no preamble.";
+
+/// Result of a compaction.
+#[derive(Debug)]
+pub struct Compaction {
+    /// New API messages: the system prompt, the summary, and the kept turns.
+    pub messages: Vec<Value>,
+    pub summary: String,
+    /// Number of API messages replaced by the summary.
+    pub summarized: usize,
+    /// Estimated tokens of the messages before and after compaction.
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+/// True for the summary message left by a compaction.
+pub fn is_summary(msg: &Value) -> bool {
+    msg["role"] == "system"
+        && msg["content"]
+            .as_str()
+            .is_some_and(|c| c.starts_with(SUMMARY_HEADING))
+}
+
+/// API message holding a conversation summary.
+pub fn summary_message(summary: &str) -> Value {
+    json!({
+        "role": "system",
+        "content": format!("{}\n\n{}", SUMMARY_HEADING, summary.trim()),
+    })
+}
+
+/// True when `messages` starts with the system prompt (not a summary).
+fn has_system_prompt(messages: &[Value]) -> bool {
+    messages
+        .first()
+        .is_some_and(|m| m["role"] == "system" && !is_summary(m))
+}
+
+/// Index of the first message kept as is: the start of the last `keep_turns` user turns.
+/// None when there is nothing before it to summarize.
+pub fn split_point(messages: &[Value], keep_turns: usize) -> Option<usize> {
+    let turns: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m["role"] == "user")
+        .map(|(i, _)| i)
+        .collect();
+    let kept = turns.len().checked_sub(keep_turns).filter(|&n| n > 0)?;
+    Some(turns.get(kept).copied().unwrap_or(messages.len()))
+}
+
+/// First `max` characters of `s`, with "…" when cut.
+fn excerpt(s: &str, max: usize) -> String {
+    let mut out: String = s.chars().take(max).collect();
+    if out.len() < s.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// Plain-text transcript of `messages` for the summarization request. A summary left by an
+/// earlier compaction is included; tool arguments and results are cut.
+fn transcript(messages: &[Value]) -> String {
+    let mut parts = Vec::new();
+    for msg in messages {
+        let content = message::extract_content(msg).unwrap_or_default();
+        match msg["role"].as_str().unwrap_or("") {
+            "system" if is_summary(msg) => parts.push(content),
+            "user" => parts.push(format!("User: {}", content.trim())),
+            "assistant" => {
+                if !content.trim().is_empty() {
+                    parts.push(format!("Assistant: {}", content.trim()));
+                }
+                for call in msg["tool_calls"].as_array().into_iter().flatten() {
+                    let function = &call["function"];
+                    parts.push(format!(
+                        "Assistant called {}: {}",
+                        function["name"].as_str().unwrap_or("a tool"),
+                        excerpt(
+                            function["arguments"].as_str().unwrap_or(""),
+                            TOOL_EXCERPT_CHARS
+                        )
+                    ));
+                }
+            }
+            "tool" => parts.push(format!(
+                "Tool result: {}",
+                excerpt(content.trim(), TOOL_EXCERPT_CHARS)
+            )),
+            _ => {}
+        }
+    }
+    parts.join("\n\n")
+}
+
+/// Messages after compaction: the system prompt (if any), the summary, then
+/// `messages[split..]`.
+fn compacted_messages(messages: &[Value], split: usize, summary: &str) -> Vec<Value> {
+    let mut out = Vec::with_capacity(messages.len() - split + 2);
+    if has_system_prompt(messages) {
+        out.push(messages[0].clone());
+    }
+    out.push(summary_message(summary));
+    out.extend_from_slice(&messages[split..]);
+    out
+}
+
+/// Ask `model` to summarize `messages[..split]` (after the system prompt) and build the
+/// compacted message list (see `split_point`).
+pub async fn compact(
+    config: &Config,
+    model: &str,
+    messages: &[Value],
+    split: usize,
+) -> Result<Compaction, ChatError> {
+    let start = usize::from(has_system_prompt(messages));
+    let older = &messages[start..split];
+    let client = config.chat_client();
+    let mut body = json!({
+        "model": model,
+        "messages": [
+            {"role": "system", "content": SUMMARY_INSTRUCTIONS},
+            {"role": "user", "content": transcript(older)},
+        ],
+        "max_tokens": SUMMARY_MAX_TOKENS,
+        "stream": false,
+        "usage": { "include": true },
+    });
+    config.openrouter.apply(&mut body);
+    let response = client.complete(body).await?;
+    if let Some(err) = response.get("error") {
+        let msg = err
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error");
+        return Err(ChatError::from_api_message(msg));
+    }
+    if let Some(usage) = parse_usage(&response) {
+        if let Err(e) = spend::record(usage.cost) {
+            log::warn!("Failed to record spend: {}", e);
+        }
+        cache::record(model, &usage);
+        stats::record_request(model, &usage);
+    }
+    let summary = response["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if summary.is_empty() {
+        return Err(ChatError::ApiMessage(
+            "the model returned an empty summary".to_string(),
+        ));
+    }
+    let compacted = compacted_messages(messages, split, &summary);
+    Ok(Compaction {
+        tokens_before: estimate_tokens(messages),
+        tokens_after: estimate_tokens(&compacted),
+        summarized: older.len(),
+        messages: compacted,
+        summary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<Value> {
+        vec![
+            json!({"role": "system", "content": "You are helpful."}),
+            json!({"role": "user", "content": "Read main.rs"}),
+            json!({"role": "assistant", "content": null, "tool_calls": [
+                {"id": "1", "type": "function",
+                 "function": {"name": "Read", "arguments": "{\"file_path\":\"main.rs\"}"}}
+            ]}),
+            json!({"role": "tool", "tool_call_id": "1", "content": "fn main() {}"}),
+            json!({"role": "assistant", "content": "It is empty."}),
+            json!({"role": "user", "content": "Add a print"}),
+            json!({"role": "assistant", "content": "Done."}),
+            json!({"role": "user", "content": "Thanks"}),
+            json!({"role": "assistant", "content": "You're welcome."}),
+        ]
+    }
+
+    #[test]
+    fn split_point_keeps_last_turns() {
+        let messages = conversation();
+        assert_eq!(split_point(&messages, 2), Some(5));
+        assert_eq!(split_point(&messages, 1), Some(7));
+        assert_eq!(split_point(&messages, 3), None);
+        assert_eq!(split_point(&messages, 0), Some(9));
+    }
+
+    #[test]
+    fn compacted_messages_replace_older_turns_with_summary() {
+        let messages = conversation();
+        let compacted = compacted_messages(&messages, 5, "- main.rs is empty");
+        assert_eq!(compacted.len(), 6);
+        assert_eq!(compacted[0]["content"], "You are helpful.");
+        assert!(is_summary(&compacted[1]));
+        assert!(!is_summary(&compacted[0]));
+        assert_eq!(compacted[2]["content"], "Add a print");
+
+        // Compacting again keeps the system prompt in front and replaces the old summary.
+        assert_eq!(split_point(&compacted, 1), Some(4));
+        let again = compacted_messages(&compacted, 4, "- print added");
+        assert_eq!(again.len(), 4);
+        assert!(
+            again[1]["content"]
+                .as_str()
+                .unwrap()
+                .ends_with("- print added")
+        );
+    }
+
+    #[test]
+    fn transcript_includes_tool_calls_and_previous_summary() {
+        let mut messages = vec![summary_message("- earlier work")];
+        messages.extend_from_slice(&conversation()[1..5]);
+        let text = transcript(&messages);
+        assert!(text.starts_with(SUMMARY_HEADING));
+        assert!(text.contains("- earlier work\n\nUser: Read main.rs"));
+        assert!(text.contains("Assistant called Read: {\"file_path\":\"main.rs\"}"));
+        assert!(text.contains("Tool result: fn main() {}"));
+        assert!(text.ends_with("Assistant: It is empty."));
+    }
+}
@@ -9,10 +9,29 @@ const CONTEXT_BUDGET_RATIO: f64 = 0.85;
 const WRITE_TOOL: &str = "Write";
 const EDIT_TOOL: &str = "Edit";
 
+/// Flat estimate per attached image; providers bill images by resolution, not encoded size.
+const IMAGE_TOKEN_ESTIMATE: usize = 1_500;
+
 /// Estimate the number of tokens in a single message.
-/// Uses JSON byte length / 4 as a rough chars-to-tokens ratio.
+/// Uses JSON byte length / 4 as a rough chars-to-tokens ratio; image parts count as
+/// `IMAGE_TOKEN_ESTIMATE` each instead of their base64 length.
 fn estimate_message_tokens(msg: &Value) -> usize {
-    serde_json::to_vec(msg).map_or(0, |v| v.len()) / 4
+    let is_image = |part: &Value| part["type"] == "image_url";
+    match msg["content"].as_array() {
+        Some(parts) if parts.iter().any(is_image) => {
+            let mut stripped = msg.clone();
+            let mut images = 0;
+            if let Some(parts) = stripped["content"].as_array_mut() {
+                parts.retain(|p| {
+                    let image = is_image(p);
+                    images += usize::from(image);
+                    !image
+                });
+            }
+            serde_json::to_vec(&stripped).map_or(0, |v| v.len()) / 4 + images * IMAGE_TOKEN_ESTIMATE
+        }
+        _ => serde_json::to_vec(msg).map_or(0, |v| v.len()) / 4,
+    }
 }
 
 /// Estimate the number of tokens in a set of messages.
@@ -32,9 +51,10 @@ pub fn estimate_tokens(messages: &[Value]) -> usize {
 /// - Remove the oldest messages first (index 0, 1, ...) until under budget
 ///
 /// Runs in O(n): computes per-message sizes once, then subtracts when removing.
-pub fn truncate_if_needed(messages: &mut Vec<Value>, context_length: u64) {
+/// Returns the number of messages removed.
+pub fn truncate_if_needed(messages: &mut Vec<Value>, context_length: u64) -> usize {
     if context_length == 0 {
-        return;
+        return 0;
     }
 
     let budget = (context_length as f64 * CONTEXT_BUDGET_RATIO) as usize;
@@ -44,7 +64,7 @@ pub fn truncate_if_needed(messages: &mut Vec<Value>, context_length: u64) {
     let mut total: usize = sizes.iter().sum();
 
     if total <= budget || messages.len() <= 1 {
-        return;
+        return 0;
     }
 
     // Remove from front, subtracting from total (O(1) per removal).
@@ -58,6 +78,7 @@ pub fn truncate_if_needed(messages: &mut Vec<Value>, context_length: u64) {
     } else {
         0
     };
+    let mut removed_count = 0;
     while messages.len() > 1 && total > budget {
         if remove_from >= messages.len() {
             break;
@@ -65,7 +86,9 @@ pub fn truncate_if_needed(messages: &mut Vec<Value>, context_length: u64) {
         let removed = sizes.remove(remove_from);
         total = total.saturating_sub(removed);
         messages.remove(remove_from);
+        removed_count += 1;
     }
+    removed_count
 }
 
 /// Summarize Write/Edit tool call arguments in an assistant message to reduce context size.
@@ -156,6 +179,21 @@ mod tests {
         assert!(tok > 0);
     }
 
+    #[test]
+    fn estimate_tokens_counts_images_flat() {
+        let image = format!("data:image/png;base64,{}", "A".repeat(400_000));
+        let messages = vec![serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "what is this?"},
+                {"type": "image_url", "image_url": {"url": image}},
+            ]
+        })];
+        let tok = estimate_tokens(&messages);
+        assert!(tok >= IMAGE_TOKEN_ESTIMATE);
+        assert!(tok < IMAGE_TOKEN_ESTIMATE + 100);
+    }
+
     #[test]
     fn truncate_if_needed_under_budget_no_change() {
         let mut messages = vec![serde_json::json!({"role": "user", "content": "Hi"})];
@@ -171,7 +209,7 @@ mod tests {
             serde_json::json!({"role": "assistant", "content": "Reply"}),
             serde_json::json!({"role": "user", "content": "Last prompt"}),
         ];
-        truncate_if_needed(&mut messages, 1);
+        assert_eq!(truncate_if_needed(&mut messages, 1), 2);
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0]["content"], "Last prompt");
     }
@@ -1,12 +1,43 @@
 //! Chat and API error types.
 
+use std::time::Duration;
+
+use crate::core::network;
+
+/// Longest wait taken from a rate-limit message or `retry-after` header; longer stated
+/// waits are clamped to it.
+const MAX_RETRY_HINT: Duration = Duration::from_secs(60 * 60);
+
 /// Errors from the chat/agent pipeline.
 #[derive(Debug)]
 pub enum ChatError {
     ApiAuth(String),
     ApiMessage(String),
-    /// Rate-limited or temporarily overloaded (retryable).
-    RateLimited(String),
+    /// Rate-limited or temporarily overloaded (retryable). `retry_after` is the wait the
+    /// provider asked for, when its message states one.
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The request does not fit the model's context window. Token counts are those of the
+    /// provider message, when it states them.
+    ContextTooLong {
+        needed: Option<u64>,
+        max: Option<u64>,
+    },
+    /// Unknown model ID, or no provider endpoint serving it.
+    ModelNotFound(String),
+    /// The model does not accept tool definitions (no tool calling).
+    ToolsUnsupported(String),
+    /// Provider-side failure (bad gateway, service unavailable); retryable.
+    ProviderDown(String),
+    /// The request timed out; retryable.
+    NetworkTimeout(String),
+    /// The proxy (URL without credentials) refused or dropped the connection; retryable.
+    ProxyUnreachable {
+        proxy: String,
+        message: String,
+    },
     ToolArgs {
         tool: String,
         source: serde_json::Error,
@@ -16,11 +47,30 @@ pub enum ChatError {
     Other(Box<dyn std::error::Error + Send + Sync + 'static>),
 }
 
+/// Next step suggested to the user after a failed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextAction {
+    /// Send the same prompt again.
+    Retry,
+    /// Pick another model.
+    SwitchModel,
+    /// Drop earlier messages from the model context.
+    CompactContext,
+}
+
 impl ChatError {
+    /// Classify an error message returned by the provider.
+    pub fn from_api_message(message: &str) -> Self {
+        classify(message).unwrap_or_else(|| ChatError::ApiMessage(message.to_string()))
+    }
+
     /// Whether this error is transient and the request should be retried.
     pub fn is_retryable(&self) -> bool {
         match self {
-            ChatError::RateLimited(_) => true,
+            ChatError::RateLimited { .. }
+            | ChatError::ProviderDown(_)
+            | ChatError::NetworkTimeout(_)
+            | ChatError::ProxyUnreachable { .. } => true,
             ChatError::ApiMessage(msg) => {
                 let lower = msg.to_lowercase();
                 lower.contains("timeout")
@@ -34,7 +84,111 @@ impl ChatError {
                 let lower = e.to_string().to_lowercase();
                 lower.contains("connection") || lower.contains("timeout")
             }
-            ChatError::ApiAuth(_) | ChatError::Cancelled | ChatError::ToolArgs { .. } => false,
+            ChatError::ApiAuth(_)
+            | ChatError::ContextTooLong { .. }
+            | ChatError::ModelNotFound(_)
+            | ChatError::ToolsUnsupported(_)
+            | ChatError::Cancelled
+            | ChatError::ToolArgs { .. } => false,
+        }
+    }
+
+    /// Wait requested by the provider before retrying, when known.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ChatError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether a failed streaming call may succeed as a non-streaming request.
+    /// Auth failures, rate limits, oversized requests, unknown models, models without tool
+    /// calling, an unreachable proxy, and cancellation would fail the same way.
+    pub fn allows_non_streaming_fallback(&self) -> bool {
+        !matches!(
+            self,
+            ChatError::ApiAuth(_)
+                | ChatError::RateLimited { .. }
+                | ChatError::ProxyUnreachable { .. }
+                | ChatError::ContextTooLong { .. }
+                | ChatError::ModelNotFound(_)
+                | ChatError::ToolsUnsupported(_)
+                | ChatError::Cancelled
+        )
+    }
+
+    /// What the user can do about this error, in one sentence; None when there is nothing
+    /// more to say than the error itself.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ChatError::ApiAuth(_) => Some(
+                "Check the key with `my-open-claude config show`, or store a new one with `my-open-claude config set-api-key`."
+                    .to_string(),
+            ),
+            ChatError::RateLimited {
+                retry_after: Some(wait),
+                ..
+            } => Some(format!(
+                "The provider asks to wait {}s before retrying; another model may not be limited.",
+                wait.as_secs().max(1)
+            )),
+            ChatError::RateLimited { .. } => {
+                Some("Wait a moment before retrying, or switch to another model.".to_string())
+            }
+            ChatError::ContextTooLong {
+                needed: Some(needed),
+                max: Some(max),
+            } => Some(format!(
+                "The conversation needs about {} tokens but the model accepts {}: clear the context or switch to a model with a larger window.",
+                needed, max
+            )),
+            ChatError::ContextTooLong { .. } => Some(
+                "Clear the context or switch to a model with a larger window.".to_string(),
+            ),
+            ChatError::ModelNotFound(_) => Some(
+                "The provider does not serve this model: pick another one.".to_string(),
+            ),
+            ChatError::ToolsUnsupported(_) => Some(
+                "This model cannot call tools: switch to a model that supports them.".to_string(),
+            ),
+            ChatError::ProviderDown(_) => Some(
+                "The provider is having trouble: retry in a moment or switch to another model."
+                    .to_string(),
+            ),
+            ChatError::NetworkTimeout(_) => {
+                Some("Check your network connection, then retry.".to_string())
+            }
+            ChatError::ProxyUnreachable { .. } => Some(
+                "Check that the proxy is running and that MY_OPEN_CLAUDE_PROXY (or HTTPS_PROXY) points to it, then retry."
+                    .to_string(),
+            ),
+            ChatError::ApiMessage(_)
+            | ChatError::ToolArgs { .. }
+            | ChatError::Cancelled
+            | ChatError::Other(_) => None,
+        }
+    }
+
+    /// Actions suggested alongside the hint, most relevant first.
+    pub fn next_actions(&self) -> &'static [NextAction] {
+        match self {
+            ChatError::RateLimited { .. } | ChatError::ProviderDown(_) => {
+                &[NextAction::Retry, NextAction::SwitchModel]
+            }
+            ChatError::ContextTooLong { .. } => {
+                &[NextAction::CompactContext, NextAction::SwitchModel]
+            }
+            ChatError::ModelNotFound(_) | ChatError::ToolsUnsupported(_) => {
+                &[NextAction::SwitchModel]
+            }
+            ChatError::NetworkTimeout(_) | ChatError::ProxyUnreachable { .. } => {
+                &[NextAction::Retry]
+            }
+            ChatError::ApiAuth(_)
+            | ChatError::ApiMessage(_)
+            | ChatError::ToolArgs { .. }
+            | ChatError::Cancelled
+            | ChatError::Other(_) => &[],
         }
     }
 }
@@ -44,7 +198,23 @@ impl std::fmt::Display for ChatError {
         match self {
             ChatError::ApiAuth(msg) => write!(f, "{}", msg),
             ChatError::ApiMessage(msg) => write!(f, "API error: {}", msg),
-            ChatError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            ChatError::RateLimited { message, .. } => write!(f, "Rate limited: {}", message),
+            ChatError::ContextTooLong {
+                needed: Some(needed),
+                max: Some(max),
+            } => write!(
+                f,
+                "Context too long: about {} tokens requested, the model accepts {}",
+                needed, max
+            ),
+            ChatError::ContextTooLong { .. } => write!(f, "Context too long for this model"),
+            ChatError::ModelNotFound(msg) => write!(f, "Model not found: {}", msg),
+            ChatError::ToolsUnsupported(msg) => write!(f, "Tools not supported: {}", msg),
+            ChatError::ProviderDown(msg) => write!(f, "Provider unavailable: {}", msg),
+            ChatError::NetworkTimeout(msg) => write!(f, "Request timed out: {}", msg),
+            ChatError::ProxyUnreachable { proxy, message } => {
+                write!(f, "Cannot reach the proxy {}: {}", proxy, message)
+            }
             ChatError::ToolArgs { tool, source } => {
                 write!(f, "Invalid tool arguments for {}: {}", tool, source)
             }
@@ -62,11 +232,121 @@ impl std::error::Error for ChatError {
             ChatError::Cancelled
             | ChatError::ApiAuth(_)
             | ChatError::ApiMessage(_)
-            | ChatError::RateLimited(_) => None,
+            | ChatError::RateLimited { .. }
+            | ChatError::ContextTooLong { .. }
+            | ChatError::ModelNotFound(_)
+            | ChatError::ToolsUnsupported(_)
+            | ChatError::ProviderDown(_)
+            | ChatError::NetworkTimeout(_)
+            | ChatError::ProxyUnreachable { .. } => None,
         }
     }
 }
 
+/// Map a provider message to a known failure; None for anything else.
+fn classify(message: &str) -> Option<ChatError> {
+    let lower = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+    // Status codes count as whole numbers only: "142900 tokens" is not a 429.
+    let status = |codes: &[&str]| {
+        lower
+            .split(|c: char| !c.is_ascii_digit())
+            .any(|word| codes.contains(&word))
+    };
+    if status(&["429"])
+        || has(&["rate limit", "too many requests"])
+        || (status(&["502", "503"]) && lower.contains("overloaded"))
+    {
+        return Some(ChatError::RateLimited {
+            message: message.to_string(),
+            retry_after: parse_retry_after(&lower),
+        });
+    }
+    if has(&[
+        "context length",
+        "context_length_exceeded",
+        "context window",
+        "prompt is too long",
+    ]) {
+        let (needed, max) = token_counts(message);
+        return Some(ChatError::ContextTooLong { needed, max });
+    }
+    // Checked before unknown models: OpenRouter reports "No endpoints found that support
+    // tool use" for a model that exists but cannot call tools.
+    if has(&[
+        "support tool use",
+        "does not support tools",
+        "tool use is not supported",
+        "tools are not supported",
+    ]) {
+        return Some(ChatError::ToolsUnsupported(message.to_string()));
+    }
+    if has(&[
+        "no endpoints found",
+        "not a valid model",
+        "model not found",
+        "model_not_found",
+    ]) || (lower.contains("model") && lower.contains("not found"))
+    {
+        return Some(ChatError::ModelNotFound(message.to_string()));
+    }
+    if status(&["502", "503", "504"])
+        || has(&["bad gateway", "service unavailable", "gateway timeout"])
+    {
+        return Some(ChatError::ProviderDown(message.to_string()));
+    }
+    if has(&["timed out", "timeout"]) {
+        return Some(ChatError::NetworkTimeout(message.to_string()));
+    }
+    None
+}
+
+/// Wait stated in a rate-limit message ("try again in 20s", "retry after 1.5 seconds",
+/// "try again in 500ms").
+fn parse_retry_after(lower: &str) -> Option<Duration> {
+    let rest = ["try again in ", "retry after ", "retry in "]
+        .iter()
+        .find_map(|marker| lower.find(marker).map(|i| &lower[i + marker.len()..]))?;
+    let number_len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..number_len].parse().ok()?;
+    let unit = rest[number_len..].trim_start();
+    let seconds = if unit.starts_with("ms") || unit.starts_with("millisecond") {
+        value / 1000.0
+    } else if unit.starts_with("min") || unit.starts_with("m ") || unit == "m" {
+        value * 60.0
+    } else {
+        value
+    };
+    retry_wait(seconds)
+}
+
+/// Wait of `seconds` as a retry hint, at most `MAX_RETRY_HINT`. None when it is not a
+/// wait at all (negative or NaN), so a garbled hint is ignored rather than trusted.
+pub(super) fn retry_wait(seconds: f64) -> Option<Duration> {
+    if seconds.is_nan() || seconds < 0.0 {
+        return None;
+    }
+    let wait = Duration::try_from_secs_f64(seconds).unwrap_or(MAX_RETRY_HINT);
+    Some(wait.min(MAX_RETRY_HINT))
+}
+
+/// Requested and maximum token counts of a context-length message: its first two numbers
+/// of at least 1000 (skipping status codes), the larger being the request.
+fn token_counts(message: &str) -> (Option<u64>, Option<u64>) {
+    let numbers: Vec<u64> = message
+        .split(|c: char| !c.is_ascii_digit() && c != ',')
+        .filter_map(|word| word.replace(',', "").parse().ok())
+        .filter(|&n| n >= 1000)
+        .take(2)
+        .collect();
+    match numbers[..] {
+        [a, b] => (Some(a.max(b)), Some(a.min(b))),
+        _ => (None, None),
+    }
+}
+
 /// Map async-openai or API errors into ChatError.
 pub fn map_api_error<E>(e: E) -> ChatError
 where
@@ -78,22 +358,21 @@ where
             "API error (401): No cookie auth credentials found. Check OPENROUTER_API_KEY in .env (see env.example).".to_string(),
         );
     }
-    // Detect rate limiting / overloaded responses.
-    if s.contains("429") || s.contains("rate limit") || s.contains("Rate limit") {
-        return ChatError::RateLimited(s);
-    }
-    if (s.contains("503") || s.contains("502")) && s.contains("overloaded") {
-        return ChatError::RateLimited(s);
-    }
     if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s)
         && let Some(msg) = v
             .get("error")
             .and_then(|e| e.get("message"))
             .and_then(|m| m.as_str())
     {
-        return ChatError::ApiMessage(msg.to_string());
+        return ChatError::from_api_message(msg);
+    }
+    let e = e.into();
+    if let Some(proxy) = network::effective_proxy()
+        && network::is_connect_error(e.as_ref())
+    {
+        return ChatError::ProxyUnreachable { proxy, message: s };
     }
-    ChatError::Other(e.into())
+    classify(&s).unwrap_or(ChatError::Other(e))
 }
 
 #[cfg(test)]
@@ -126,10 +405,26 @@ mod tests {
     fn map_api_error_json_rate_limit_message() {
         let e = std::io::Error::other(r#"{"error":{"message":"Rate limit exceeded"}}"#);
         let err = map_api_error(e);
-        assert!(matches!(&err, ChatError::RateLimited(_)));
+        assert!(matches!(&err, ChatError::RateLimited { .. }));
         assert!(err.is_retryable());
     }
 
+    #[test]
+    fn proxy_unreachable_is_retryable_and_names_the_proxy() {
+        let err = ChatError::ProxyUnreachable {
+            proxy: "http://proxy.corp:3128".to_string(),
+            message: "error sending request".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Cannot reach the proxy http://proxy.corp:3128: error sending request"
+        );
+        assert!(err.is_retryable());
+        assert!(!err.allows_non_streaming_fallback());
+        assert!(err.hint().unwrap().contains("MY_OPEN_CLAUDE_PROXY"));
+        assert_eq!(err.next_actions(), &[NextAction::Retry]);
+    }
+
     #[test]
     fn map_api_error_generic() {
         let e = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "connection refused");
@@ -144,7 +439,7 @@ mod tests {
     fn map_api_error_rate_limited_429() {
         let e = std::io::Error::other("HTTP 429 Too Many Requests - rate limit exceeded");
         let err = map_api_error(e);
-        assert!(matches!(&err, ChatError::RateLimited(_)));
+        assert!(matches!(&err, ChatError::RateLimited { .. }));
         assert!(err.is_retryable());
     }
 
@@ -152,7 +447,7 @@ mod tests {
     fn map_api_error_overloaded_503() {
         let e = std::io::Error::other("503 Service Unavailable - model overloaded");
         let err = map_api_error(e);
-        assert!(matches!(&err, ChatError::RateLimited(_)));
+        assert!(matches!(&err, ChatError::RateLimited { .. }));
         assert!(err.is_retryable());
     }
 
@@ -191,4 +486,126 @@ mod tests {
         let err = ChatError::ApiMessage("Invalid model ID".to_string());
         assert!(!err.is_retryable());
     }
+
+    #[test]
+    fn non_streaming_fallback_allowed_for_api_message() {
+        let err = ChatError::ApiMessage("tool call streaming not supported".to_string());
+        assert!(err.allows_non_streaming_fallback());
+    }
+
+    #[test]
+    fn non_streaming_fallback_denied_for_auth_rate_limit_cancel() {
+        assert!(!ChatError::ApiAuth("bad key".to_string()).allows_non_streaming_fallback());
+        assert!(
+            !ChatError::RateLimited {
+                message: "429".to_string(),
+                retry_after: None
+            }
+            .allows_non_streaming_fallback()
+        );
+        assert!(!ChatError::Cancelled.allows_non_streaming_fallback());
+    }
+
+    #[test]
+    fn map_api_error_context_length_openrouter() {
+        let e = std::io::Error::other(
+            r#"{"error":{"message":"This endpoint's maximum context length is 128000 tokens. However, you requested about 142900 tokens (137900 of text input, 5000 in the output)."}}"#,
+        );
+        let err = map_api_error(e);
+        assert!(matches!(
+            err,
+            ChatError::ContextTooLong {
+                needed: Some(142900),
+                max: Some(128000)
+            }
+        ));
+        assert!(!err.is_retryable());
+        assert!(!err.allows_non_streaming_fallback());
+        assert_eq!(
+            err.next_actions(),
+            &[NextAction::CompactContext, NextAction::SwitchModel]
+        );
+    }
+
+    #[test]
+    fn from_api_message_context_length_anthropic_skips_status_code() {
+        let err =
+            ChatError::from_api_message("400: prompt is too long: 210,000 tokens > 200000 maximum");
+        assert!(matches!(
+            err,
+            ChatError::ContextTooLong {
+                needed: Some(210000),
+                max: Some(200000)
+            }
+        ));
+        assert!(err.hint().unwrap().contains("210000"));
+    }
+
+    #[test]
+    fn from_api_message_model_not_found() {
+        let err = ChatError::from_api_message("No endpoints found for acme/unknown-model.");
+        assert!(matches!(err, ChatError::ModelNotFound(_)));
+        assert_eq!(err.next_actions(), &[NextAction::SwitchModel]);
+        let err = ChatError::from_api_message(r#"model "llama9" not found, try pulling it first"#);
+        assert!(matches!(err, ChatError::ModelNotFound(_)));
+    }
+
+    #[test]
+    fn from_api_message_tools_unsupported() {
+        let err = ChatError::from_api_message(
+            "No endpoints found that support tool use. To learn more about provider routing, visit: https://openrouter.ai/docs/provider-routing",
+        );
+        assert!(matches!(err, ChatError::ToolsUnsupported(_)));
+        assert!(!err.is_retryable());
+        assert!(!err.allows_non_streaming_fallback());
+        let err = ChatError::from_api_message(
+            r#"registry.ollama.ai/library/gemma:2b does not support tools"#,
+        );
+        assert!(matches!(err, ChatError::ToolsUnsupported(_)));
+    }
+
+    #[test]
+    fn from_api_message_provider_down_and_timeout_are_retryable() {
+        let err = ChatError::from_api_message("502 Bad Gateway");
+        assert!(matches!(err, ChatError::ProviderDown(_)));
+        assert!(err.is_retryable());
+        let err = map_api_error(std::io::Error::other("http error: operation timed out"));
+        assert!(matches!(err, ChatError::NetworkTimeout(_)));
+        assert!(err.is_retryable());
+        assert_eq!(err.next_actions(), &[NextAction::Retry]);
+    }
+
+    #[test]
+    fn rate_limit_retry_after() {
+        let err = ChatError::from_api_message("Rate limit reached. Please try again in 20s.");
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(20)));
+        assert!(err.hint().unwrap().contains("20s"));
+        let err = ChatError::from_api_message("429: retry after 1.5 seconds");
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(1500)));
+        let err = ChatError::from_api_message("Too many requests, try again in 500ms");
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(500)));
+        let err = ChatError::from_api_message("Rate limit exceeded");
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn retry_hint_out_of_range_is_clamped_or_ignored() {
+        let huge = format!(
+            "Rate limit reached. Please try again in {}s.",
+            "9".repeat(400)
+        );
+        let err = ChatError::from_api_message(&huge);
+        assert_eq!(err.retry_after(), Some(MAX_RETRY_HINT));
+        assert_eq!(retry_wait(f64::INFINITY), Some(MAX_RETRY_HINT));
+        assert_eq!(retry_wait(-1.0), None);
+        assert_eq!(retry_wait(f64::NAN), None);
+    }
+
+    #[test]
+    fn unclassified_message_has_no_hint() {
+        let err = ChatError::from_api_message("Invalid model specified");
+        assert!(matches!(err, ChatError::ApiMessage(_)));
+        assert!(err.hint().is_none());
+        assert!(err.next_actions().is_empty());
+    }
 }
@@ -1,24 +1,55 @@
 //! Agent loop: chat with tool calling, streaming, and destructive command confirmation.
 
 mod agent_loop;
+mod anthropic;
+mod cache;
+mod client;
+mod compact;
 pub(crate) mod context;
 mod error;
+mod prompt;
+mod sections;
 mod stream;
+pub mod structured;
+mod subtask;
+mod text_tools;
+mod title;
 mod tool_execution;
 pub mod undo;
 
-use async_openai::Client;
 use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::core::audit::{self, ToolCallAudit, TurnOrigin};
 use crate::core::config::Config;
+use crate::core::confirm::PendingAction;
+use crate::core::dry_run;
+use crate::core::generation::GenerationParams;
+use crate::core::models;
+use crate::core::pins::PinnedMessage;
+use crate::core::stats;
+use crate::core::suggest;
 use crate::core::tools;
-use crate::core::tools::Tool;
+use crate::core::trace::{self, TraceEvent};
 use crate::core::workspace::Workspace;
 
-pub use error::{ChatError, map_api_error};
-pub use stream::TokenUsage;
+pub use anthropic::{API_VERSION as ANTHROPIC_API_VERSION, AnthropicClient};
+pub use client::{ChatClient, ChunkStream};
+pub use compact::{Compaction, KEEP_TURNS, compact, is_summary, split_point, summary_message};
+pub use error::{ChatError, NextAction, map_api_error};
+pub use prompt::{
+    CONTINUE_PROMPT, ContextPreview, PromptSection, preview_context, refresh_system_message,
+};
+pub use sections::{PromptLayout, SectionId};
+pub use stream::{MessageUsage, TokenUsage};
+pub use text_tools::ToolProtocol;
+pub use title::generate_title;
+pub use tool_execution::{
+    StandaloneToolResult, ToolModes, enabled_tool_definitions, execute_standalone, is_tool_disabled,
+};
 
 /// Result of a chat turn. Either complete, or needs user confirmation for a destructive command.
 #[derive(Debug)]
@@ -27,12 +58,28 @@ pub enum ChatResult {
         content: String,
         tool_log: Vec<String>,
         messages: Vec<Value>,
+        /// Usage of the last API call (its prompt tokens are the current context size).
         usage: TokenUsage,
+        /// Usage and latency of the whole reply.
+        message_usage: MessageUsage,
+        /// The reply was cut off by the output token limit (`finish_reason` "length"), after
+        /// any automatic continuations.
+        truncated: bool,
+        /// Notes of the post-processing hooks, to show after the reply (see `core::hooks`).
+        annotations: Vec<String>,
+        /// Why a hook asked not to save the conversation after this turn.
+        save_blocked: Option<String>,
+        /// The reply parsed as JSON, when the request had an output schema and the reply
+        /// matches it (see `structured`).
+        structured: Option<Value>,
+        /// Edits staged in suggest mode, for the user to review (see `core::suggest`).
+        suggestions: Option<suggest::ChangeSet>,
     },
-    /// Destructive command pending; caller must show confirmation UI then call `chat_resume`.
+    /// Destructive command or write outside the workspace pending; caller must show
+    /// confirmation UI then call `chat_resume`.
     NeedsConfirmation {
-        command: String,
-        state: ConfirmState,
+        action: PendingAction,
+        state: Box<ConfirmState>,
     },
 }
 
@@ -44,27 +91,221 @@ pub struct ConfirmState {
     pub(crate) tool_call_id: String,
     pub(crate) mode: String,
     pub(crate) tools: Vec<Value>,
-    pub(crate) command: String,
+    pub(crate) action: PendingAction,
     pub(crate) undo_stack: Option<undo::SharedUndoStack>,
+    /// Directory the confirmed tool call runs in.
+    pub(crate) working_dir: PathBuf,
+    /// Usage of the reply so far, continued after the confirmation.
+    pub(crate) message_usage: MessageUsage,
+    /// Pinned files, still sent with each request after the confirmation.
+    pub(crate) pinned_files: Vec<PathBuf>,
+    /// Pinned messages, still sent with each request after the confirmation.
+    pub(crate) pinned_messages: Vec<PinnedMessage>,
+    /// Generation parameters of the turn, kept after the confirmation.
+    pub(crate) generation: GenerationParams,
+    /// How tools are offered to the model, kept after the confirmation.
+    pub(crate) tool_protocol: ToolProtocol,
+    /// Turn the confirmed call belongs to, for the audit log.
+    pub(crate) audit: TurnOrigin,
 }
 
-/// Callback for progress updates during chat (e.g. "Calling API...", "→ Bash: ls").
+impl ConfirmState {
+    /// Directory the pending tool call runs in.
+    pub fn working_dir(&self) -> &Path {
+        &self.working_dir
+    }
+}
+
+/// What a turn is doing, reported through `ChatOptions::on_progress`. `Display` gives the
+/// log line (e.g. "Calling API...", "→ Bash: ls").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// A request was sent to the model; waiting for its reply.
+    CallingModel,
+    /// A tool call started: the `index`-th (from 1) of the `total` calls in the response.
+    Tool {
+        name: String,
+        /// Short argument preview (e.g. path or command).
+        preview: String,
+        index: usize,
+        total: usize,
+        /// The tool runs on the remote host (see `core::remote`).
+        remote: bool,
+        /// The call is simulated (see `core::dry_run`).
+        dry_run: bool,
+    },
+    /// A failed request is sent again after `delay`.
+    Retrying {
+        delay: Duration,
+        attempt: u32,
+        max_attempts: u32,
+    },
+    /// Streaming failed before any content; the request is sent again without streaming.
+    StreamingFallback,
+    /// The model cannot call tools; the turn continues with tools described in the prompt
+    /// (`text_tools`) or without them.
+    ToolsUnsupported { model: String, text_tools: bool },
+    /// The reply was cut off by the output token limit; the model is asked to continue it.
+    Continuing { attempt: u32, max_attempts: u32 },
+    /// The reply does not match the output schema; the model is asked to answer again.
+    InvalidStructuredOutput {
+        error: String,
+        attempt: u32,
+        max_attempts: u32,
+    },
+    /// A tool call is streaming in and has not run yet. Reported again as its arguments
+    /// grow, so a long generation (e.g. a whole file for Write) shows it is alive.
+    PreparingTool {
+        name: String,
+        /// Path or command of the call once it has streamed in, else empty.
+        preview: String,
+        /// Size of the arguments received so far.
+        bytes: usize,
+    },
+}
+
+/// Tool log line of a call, e.g. "→ Bash: ls", "→ Bash (remote): ls", or
+/// "→ Write (dry run): a.rs".
+fn tool_log_line(name: &str, preview: &str, remote: bool, dry_run: bool) -> String {
+    let remote = if remote { " (remote)" } else { "" };
+    let dry_run = if dry_run { " (dry run)" } else { "" };
+    format!("→ {}{}{}: {}", name, remote, dry_run, preview)
+}
+
+/// Size of streamed arguments: "512 B" or "2.1 KB".
+fn streamed_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+impl std::fmt::Display for ProgressEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgressEvent::CallingModel => write!(f, "Calling API..."),
+            ProgressEvent::Tool {
+                name,
+                preview,
+                remote,
+                dry_run,
+                ..
+            } => f.write_str(&tool_log_line(name, preview, *remote, *dry_run)),
+            ProgressEvent::Retrying {
+                delay,
+                attempt,
+                max_attempts,
+            } => write!(
+                f,
+                "Retrying in {}s... (attempt {}/{})",
+                delay.as_secs(),
+                attempt,
+                max_attempts
+            ),
+            ProgressEvent::StreamingFallback => {
+                write!(f, "Streaming failed; retrying without streaming...")
+            }
+            ProgressEvent::ToolsUnsupported { model, text_tools } => write!(
+                f,
+                "{} does not support tool calling; {}",
+                model,
+                if *text_tools {
+                    "tools are described in the prompt instead"
+                } else {
+                    "continuing without tools"
+                }
+            ),
+            ProgressEvent::Continuing {
+                attempt,
+                max_attempts,
+            } => write!(
+                f,
+                "Response truncated; continuing... ({}/{})",
+                attempt, max_attempts
+            ),
+            ProgressEvent::InvalidStructuredOutput {
+                error,
+                attempt,
+                max_attempts,
+            } => write!(
+                f,
+                "Invalid structured output ({}); asking again... ({}/{})",
+                error, attempt, max_attempts
+            ),
+            ProgressEvent::PreparingTool {
+                name,
+                preview,
+                bytes,
+            } => {
+                write!(f, "Preparing {}", name)?;
+                if !preview.is_empty() {
+                    write!(f, ": {}", preview)?;
+                }
+                if *bytes > 0 {
+                    write!(f, " ({} so far)", streamed_size(*bytes))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Callback for progress events during chat (model call, tool call, retry).
 /// Sync required so futures holding &OnProgress across await points are Send.
-pub type OnProgress = Box<dyn Fn(&str) + Send + Sync>;
+pub type OnProgress = Box<dyn Fn(&ProgressEvent) + Send + Sync>;
 
 /// Callback for each streamed content chunk (text only).
 /// Sync required so futures holding &OnContentChunk across await points are Send.
 pub type OnContentChunk = Box<dyn Fn(&str) + Send + Sync>;
 
-/// Optional callbacks for chat: progress, streaming, cancellation.
+/// A tool call requested by the model (for queue displays).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCallInfo {
+    pub id: String,
+    pub name: String,
+    /// Short argument preview (e.g. path or command).
+    pub preview: String,
+    /// File or directory the call reads or writes, resolved against the working directory.
+    pub path: Option<PathBuf>,
+}
+
+/// Lifecycle of the tool calls in one model response.
+#[derive(Debug, Clone)]
+pub enum ToolEvent {
+    /// The model requested these tool calls (in order); none has started yet.
+    Queued(Vec<ToolCallInfo>),
+    /// Execution of a tool call started.
+    Started { id: String },
+    /// A tool call finished. `success` is false when the tool returned an error.
+    Finished {
+        id: String,
+        success: bool,
+        duration: Duration,
+        /// Typed view of the result, for tools that give one (see `tools::ToolView`).
+        view: Option<tools::ToolView>,
+    },
+}
+
+/// Callback for tool call lifecycle events (queued, started, finished).
+pub type OnToolEvent = Box<dyn Fn(&ToolEvent) + Send + Sync>;
+
+/// Optional callbacks for chat: progress, streaming, tool events, cancellation; the
+/// conversation the turn belongs to, and its tool modes.
 #[derive(Default)]
 pub struct ChatOptions {
-    /// Called when progress events occur (e.g. "Calling API...", "→ Bash: ls").
+    /// Called when progress events occur (model call, tool call, retry).
     pub on_progress: Option<OnProgress>,
     /// Called for each streamed content chunk (text only).
     pub on_content_chunk: Option<OnContentChunk>,
+    /// Called when tool calls are queued, start, and finish.
+    pub on_tool_event: Option<OnToolEvent>,
     /// When cancelled, the request is aborted.
     pub cancel_token: Option<CancellationToken>,
+    /// Saved conversation the turn continues, recorded in the audit log (see `core::audit`).
+    pub conversation_id: Option<String>,
+    /// Dry-run and suggest modes of the conversation; None: `ToolModes::from_config`.
+    pub modes: Option<ToolModes>,
 }
 
 /// Parameters for starting a new chat.
@@ -75,7 +316,9 @@ pub struct ChatRequest<'a> {
     pub model: &'a str,
     /// User prompt.
     pub prompt: &'a str,
-    /// Mode: "Ask" (read-only tools) or "Build" (all tools).
+    /// Images attached to the prompt, as data URLs (sent as multimodal content parts).
+    pub images: &'a [String],
+    /// Mode: "Ask" (read-only tools), "Plan" (read-only tools plus TaskPlan), or "Build" (all tools).
     pub mode: &'a str,
     /// Model context window length (tokens).
     pub context_length: u64,
@@ -94,93 +337,109 @@ pub struct ChatRequest<'a> {
     /// Shared undo stack for file modifications. When set, Write/Edit tool calls capture
     /// original file content before modification, enabling `/undo`.
     pub undo_stack: Option<undo::SharedUndoStack>,
+    /// Pinned files whose current content is added to the system prompt of each API call.
+    pub pinned_files: &'a [PathBuf],
+    /// Pinned messages quoted in the system prompt of each API call.
+    pub pinned_messages: &'a [PinnedMessage],
+    /// Temperature, max_tokens, top_p, and stop sequences (see `generation::effective`).
+    pub generation: &'a GenerationParams,
 }
 
 /// Run an agent loop that:
 /// - starts with the user's prompt (and optional previous conversation)
 /// - repeatedly calls the model
-/// - executes any requested tools (except Write/Bash in Ask mode)
+/// - executes any requested tools (except Write/Edit/Bash in Ask and Plan modes, and tools
+///   disabled by configuration)
 /// - feeds tool results back to the model
 /// - stops when the model responds without tool calls
 pub async fn chat(req: ChatRequest<'_>) -> Result<ChatResult, ChatError> {
-    let client = Client::with_config(req.config.openai_config.clone());
-
-    let root = req.workspace.root.display().to_string();
-    let project_type = req
-        .workspace
-        .project_type
-        .map(|pt| pt.to_string())
-        .unwrap_or_else(|| "unknown".to_string());
-
-    let mut content = format!(
-        "Respond in the same language as the user. If they write in French, respond in French; if in English, respond in English; match their language.\n\nWorkspace root: {}\nProject type: {}\nUse the workspace root as the default base path for Read, Write, Grep, ListDir, Glob, and Edit when the user does not specify a path.",
-        root, project_type
-    );
-
-    if let Some(ref agent_md) = req.workspace.agent_md {
-        content.push_str("\n\n--- Project context (AGENTS.md) ---\n");
-        content.push_str(agent_md);
-        content.push_str("\n---");
-    }
-
-    if let Some(ref git) = req.workspace.git_context {
-        content.push_str("\n\n--- Git context ---\n");
-        content.push_str(&git.formatted());
-        content.push_str("\n---");
-    }
-
-    let system_msg = json!({
-        "role": "system",
-        "content": content
-    });
+    let started = turn_started(req.model, req.mode);
+    let client = req.config.chat_client();
+    let origin = TurnOrigin::new(req.options.conversation_id.clone());
+    let modes = req
+        .options
+        .modes
+        .unwrap_or_else(|| ToolModes::from_config(req.config));
 
-    let mut messages: Vec<Value> = req.previous_messages.unwrap_or_default();
-    if messages.is_empty()
-        || messages
-            .first()
-            .and_then(|m| m.get("role").and_then(|r| r.as_str()))
-            != Some("system")
-    {
-        messages.insert(0, system_msg);
-    }
-    messages.push(json!({
-        "role": "user",
-        "content": req.prompt,
-    }));
+    let messages = prompt::initial_messages(
+        req.workspace,
+        &req.config.prompt_layout,
+        req.previous_messages,
+        req.prompt,
+        req.images,
+    );
     let mut messages = Arc::new(messages);
     let mut tool_log = Arc::new(Vec::<String>::new());
     let confirm_destructive = req.confirm_destructive;
 
-    agent_loop::run_agent_loop(
+    // Models without tool calling still answer: tools go through the prompt, or are left out.
+    let tool_protocol = if models::supports_tools(req.config, req.model).await {
+        ToolProtocol::Native
+    } else {
+        let protocol = ToolProtocol::fallback(req.config.text_tools);
+        log::info!("{} does not support tools; using {:?}", req.model, protocol);
+        if let Some(progress) = req.options.on_progress.as_deref() {
+            progress(&ProgressEvent::ToolsUnsupported {
+                model: req.model.to_string(),
+                text_tools: req.config.text_tools,
+            });
+        }
+        protocol
+    };
+
+    let result = agent_loop::run_agent_loop(
         agent_loop::AgentLoopParams {
             client: &client,
             model: req.model,
             context_length: req.context_length,
             tools_defs: req.tools_defs,
+            tool_protocol,
+            text_tools: req.config.text_tools,
             tools_list: req.tools_list,
+            disabled_tools: &req.config.disabled_tools,
             messages: &mut messages,
             tool_log: &mut tool_log,
             mode: req.mode,
             undo_stack: req.undo_stack,
+            message_usage: MessageUsage::default(),
+            pinned_files: req.pinned_files,
+            pinned_messages: req.pinned_messages,
+            working_dir: &req.workspace.working_dir,
+            tool_timeouts: &req.config.tool_timeouts,
+            modes,
+            streaming: req.config.streaming,
+            prompt_cache: req.config.prompt_cache,
+            routing: &req.config.openrouter,
+            generation: req.generation,
+            webhook_urls: &req.config.webhook_urls,
+            post_hooks: &req.config.post_hooks,
+            auto_continue: req.config.auto_continue,
+            budget: None,
+            spend_limits: req.config.spend_limits,
+            audit: &origin,
         },
         agent_loop::AgentLoopCallbacks {
             confirm_destructive: &confirm_destructive,
             on_progress: req.options.on_progress.as_deref(),
             on_content_chunk: req.options.on_content_chunk.as_deref(),
+            on_tool_event: req.options.on_tool_event.as_deref(),
             cancel_token: req.options.cancel_token.as_ref(),
         },
     )
-    .await
+    .await;
+    turn_ended(req.model, req.mode, started, &result);
+    with_suggestions(result, &origin.turn)
 }
 
-/// Resume the chat loop after user confirmed or cancelled a destructive command.
+/// Resume the chat loop after user confirmed or cancelled a pending action (destructive
+/// command or write outside the workspace).
 ///
-/// Call when the user answered y/n to the destructive command confirmation popup.
+/// Call when the user answered y/n to the confirmation popup.
 ///
 /// # Arguments
 ///
 /// * `state` - Internal state from `ChatResult::NeedsConfirmation`, required to continue the loop.
-/// * `confirmed` - `true` if user accepted, `false` if cancelled (sends "Command cancelled" to model).
+/// * `confirmed` - `true` if user accepted, `false` if cancelled (the model is told so).
 pub async fn chat_resume(
     config: &Config,
     model: &str,
@@ -191,17 +450,85 @@ pub async fn chat_resume(
     options: impl Into<ChatOptions>,
 ) -> Result<ChatResult, ChatError> {
     let opts = options.into();
-    let client = Client::with_config(config.openai_config.clone());
+    let turn_started_at = turn_started(model, &state.mode);
+    let client = config.chat_client();
+    let modes = opts.modes.unwrap_or_else(|| ToolModes::from_config(config));
 
-    let bash_tool = tools::BashTool;
+    let tool_name = state.action.tool_name().to_string();
+    // A conversation saved while the confirmation was pending is recorded from now on.
+    let mut origin = state.audit.clone();
+    origin.conversation = origin.conversation.or_else(|| opts.conversation_id.clone());
+    audit::record_confirmation(&origin, &state.action, confirmed);
+    let audit_call = match &state.action {
+        _ if !confirmed || modes.dry_run => None,
+        PendingAction::Command(command) => ToolCallAudit::start(
+            &origin,
+            &tools::BashTool,
+            &json!({ "command": command }),
+            &state.working_dir,
+        ),
+        PendingAction::OutsideWorkspace { tool, args, .. } => tools_list
+            .iter()
+            .find(|t| t.name() == tool)
+            .and_then(|t| ToolCallAudit::start(&origin, t.as_ref(), args, &state.working_dir)),
+    };
+    let started = std::time::Instant::now();
     let result = if confirmed {
-        tool_execution::tool_result_string(
-            bash_tool.execute(&json!({ "command": state.command })),
-            "Bash",
-        )
+        let output = match &state.action {
+            // Dry run turned on while the confirmation was pending.
+            PendingAction::Command(command) if modes.dry_run => dry_run::simulated_result(
+                &tools::BashTool,
+                &json!({ "command": command }),
+                &state.working_dir,
+            ),
+            PendingAction::OutsideWorkspace { tool, args, .. } if modes.dry_run => {
+                match tools_list.iter().find(|t| t.name() == tool) {
+                    Some(t) => dry_run::simulated_result(t.as_ref(), args, &state.working_dir),
+                    None => Err(format!("unknown tool '{}'", tool).into()),
+                }
+            }
+            PendingAction::Command(command) => tool_execution::execute_with_timeout(
+                &tools::BashTool,
+                &json!({ "command": command }),
+                &state.working_dir,
+                opts.cancel_token.as_ref(),
+                config.tool_timeouts.for_tool(&tools::BashTool),
+            )
+            .map(|output| output.text),
+            PendingAction::OutsideWorkspace { tool, args, .. } => run_approved_file_tool(
+                tool,
+                args,
+                tools_list,
+                &state.working_dir,
+                state.undo_stack.as_ref(),
+            ),
+        };
+        let output = tool_execution::tool_result_string(output, &tool_name);
+        tool_execution::redact_tool_result(&tool_name, output)
     } else {
-        "Command cancelled (destructive command not confirmed).".to_string()
+        state.action.cancelled_message()
     };
+    let duration = started.elapsed();
+    let cancelled = opts.cancel_token.as_ref().is_some_and(|t| t.is_cancelled());
+    let success = confirmed && !cancelled && !tool_execution::is_error_result(&result);
+    if let Some(call) = audit_call {
+        call.finish(success);
+    }
+    stats::record_tool(&tool_name);
+    trace::record(TraceEvent::Tool {
+        name: tool_name,
+        duration_ms: trace::millis(duration),
+        success,
+        output_bytes: result.len(),
+    });
+    if let Some(ref cb) = opts.on_tool_event {
+        cb(&ToolEvent::Finished {
+            id: state.tool_call_id.clone(),
+            success,
+            duration,
+            view: None,
+        });
+    }
 
     let mut messages = state.messages;
     Arc::make_mut(&mut messages).push(json!({
@@ -213,24 +540,124 @@ pub async fn chat_resume(
     let mut tool_log = state.tool_log;
     let tools_defs = state.tools;
 
-    agent_loop::run_agent_loop(
+    let result = agent_loop::run_agent_loop(
         agent_loop::AgentLoopParams {
             client: &client,
             model,
             context_length,
             tools_defs: &tools_defs,
+            tool_protocol: state.tool_protocol,
+            text_tools: config.text_tools,
             tools_list,
+            disabled_tools: &config.disabled_tools,
             messages: &mut messages,
             tool_log: &mut tool_log,
             mode: &state.mode,
             undo_stack: state.undo_stack,
+            message_usage: state.message_usage,
+            pinned_files: &state.pinned_files,
+            pinned_messages: &state.pinned_messages,
+            working_dir: &state.working_dir,
+            tool_timeouts: &config.tool_timeouts,
+            modes,
+            streaming: config.streaming,
+            prompt_cache: config.prompt_cache,
+            routing: &config.openrouter,
+            generation: &state.generation,
+            webhook_urls: &config.webhook_urls,
+            post_hooks: &config.post_hooks,
+            auto_continue: config.auto_continue,
+            budget: None,
+            spend_limits: config.spend_limits,
+            audit: &origin,
         },
         agent_loop::AgentLoopCallbacks {
             confirm_destructive: &None,
             on_progress: opts.on_progress.as_deref(),
             on_content_chunk: opts.on_content_chunk.as_deref(),
+            on_tool_event: opts.on_tool_event.as_deref(),
             cancel_token: opts.cancel_token.as_ref(),
         },
     )
-    .await
+    .await;
+    turn_ended(model, &state.mode, turn_started_at, &result);
+    with_suggestions(result, &origin.turn)
+}
+
+/// Attach the edits staged during `turn` to its result once the turn is over. They stay
+/// staged while a confirmation is pending, and are dropped when the turn fails.
+fn with_suggestions(
+    mut result: Result<ChatResult, ChatError>,
+    turn: &str,
+) -> Result<ChatResult, ChatError> {
+    match &mut result {
+        Ok(ChatResult::Complete { suggestions, .. }) => *suggestions = suggest::take(turn),
+        Ok(ChatResult::NeedsConfirmation { .. }) => {}
+        Err(_) => drop(suggest::take(turn)),
+    }
+    result
+}
+
+/// Trace the start of a turn; returns when it started.
+fn turn_started(model: &str, mode: &str) -> Instant {
+    trace::record(TraceEvent::TurnStarted {
+        model: model.to_string(),
+        mode: mode.to_string(),
+    });
+    Instant::now()
+}
+
+/// Trace the end of a turn started at `started`, with its usage so far.
+fn turn_ended(model: &str, mode: &str, started: Instant, result: &Result<ChatResult, ChatError>) {
+    let (outcome, usage, error) = match result {
+        Ok(ChatResult::Complete { message_usage, .. }) => ("complete", Some(message_usage), None),
+        Ok(ChatResult::NeedsConfirmation { state, .. }) => {
+            ("needs_confirmation", Some(&state.message_usage), None)
+        }
+        Err(ChatError::Cancelled) => ("cancelled", None, None),
+        Err(e) => ("error", None, Some(e.to_string())),
+    };
+    let usage = usage.cloned().unwrap_or_default();
+    trace::record(TraceEvent::Turn {
+        model: model.to_string(),
+        mode: mode.to_string(),
+        duration_ms: trace::millis(started.elapsed()),
+        outcome: outcome.to_string(),
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        cost: usage.cost,
+        error,
+    });
 }
+
+/// Run a file tool call the user approved despite its path being outside the workspace.
+/// Write and Edit changes are recorded as their own undo batch.
+fn run_approved_file_tool(
+    name: &str,
+    args: &Value,
+    tools_list: &[Box<dyn tools::Tool>],
+    working_dir: &Path,
+    undo_stack: Option<&undo::SharedUndoStack>,
+) -> Result<String, tools::ToolError> {
+    let tool = tools_list
+        .iter()
+        .find(|t| t.name() == name)
+        .ok_or_else(|| format!("unknown tool '{}'", name))?;
+    let mut batch = undo::UndoBatch::default();
+    if tool_execution::UNDO_CAPTURE_TOOLS.contains(&name)
+        && let Some(file_path) = args.get("file_path").and_then(|v| v.as_str())
+    {
+        batch.capture(&tools::resolve_path(working_dir, file_path).to_string_lossy());
+    }
+    let output = tool.execute(args, working_dir);
+    if let Some(stack) = undo_stack {
+        stack
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_batch(batch);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,414 @@
+//! System prompt assembly and pre-send context preview.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use serde_json::{Value, json};
+
+use crate::core::pins::{self, PinnedMessage};
+use crate::core::workspace::Workspace;
+
+use super::compact;
+use super::context::estimate_tokens;
+use super::sections::{PromptLayout, SectionId};
+
+/// A named part of the system prompt (a registered section, a pinned file, or the pinned
+/// messages).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSection {
+    /// Registered section this comes from; None for pins.
+    pub id: Option<SectionId>,
+    pub title: String,
+    pub content: String,
+}
+
+/// Build the system prompt sections for a workspace: the sections of `layout.order` that
+/// have content, in that order.
+pub fn system_prompt_sections(workspace: &Workspace, layout: &PromptLayout) -> Vec<PromptSection> {
+    layout
+        .order
+        .iter()
+        .filter_map(|&id| {
+            id.content(workspace, layout).map(|content| PromptSection {
+                id: Some(id),
+                title: id.title().to_string(),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Join sections into the system prompt string. The first section is inlined;
+/// the others are wrapped in `--- title ---` delimiters.
+pub fn join_sections(sections: &[PromptSection]) -> String {
+    let mut content = String::new();
+    for (i, section) in sections.iter().enumerate() {
+        if i == 0 {
+            content.push_str(&section.content);
+        } else {
+            content.push_str(&format!(
+                "\n\n--- {} ---\n{}\n---",
+                section.title, section.content
+            ));
+        }
+    }
+    content
+}
+
+/// Build the full system prompt for a workspace.
+pub fn build_system_prompt(workspace: &Workspace, layout: &PromptLayout) -> String {
+    join_sections(&system_prompt_sections(workspace, layout))
+}
+
+/// Prepend the system message (unless the previous conversation already starts with one)
+/// and append the user prompt. This is the message list sent on the first API call of a turn.
+pub(crate) fn initial_messages(
+    workspace: &Workspace,
+    layout: &PromptLayout,
+    previous_messages: Option<Vec<Value>>,
+    prompt: &str,
+    images: &[String],
+) -> Vec<Value> {
+    let mut messages: Vec<Value> = previous_messages.unwrap_or_default();
+    // A resumed compacted conversation starts with its summary, not the system prompt.
+    if messages
+        .first()
+        .is_none_or(|m| m["role"] != "system" || compact::is_summary(m))
+    {
+        messages.insert(
+            0,
+            json!({
+                "role": "system",
+                "content": build_system_prompt(workspace, layout)
+            }),
+        );
+    }
+    messages.push(json!({
+        "role": "user",
+        "content": user_content(prompt, images),
+    }));
+    messages
+}
+
+/// Prompt asking the model to pick up a reply cut off by the output token limit (`/continue`
+/// and automatic continuation).
+pub const CONTINUE_PROMPT: &str = "Your previous reply was cut off by the output token limit. \
+Continue exactly where it stopped, without repeating any of it or adding a preamble.";
+
+/// User message of an automatic continuation.
+pub(super) fn continue_message() -> Value {
+    json!({ "role": "user", "content": CONTINUE_PROMPT })
+}
+
+/// Rebuild the system message of a conversation in progress for `workspace` (e.g. after
+/// AGENTS.md changed). Without a system message, nothing changes: the next turn adds one.
+pub fn refresh_system_message(
+    messages: &mut [Value],
+    workspace: &Workspace,
+    layout: &PromptLayout,
+) {
+    if let Some(system) = messages
+        .first_mut()
+        .filter(|m| m["role"] == "system" && !compact::is_summary(m))
+    {
+        system["content"] = json!(build_system_prompt(workspace, layout));
+    }
+}
+
+/// System prompt sections of the pins: each pinned file, then the pinned messages.
+fn pin_sections(pinned: &[PathBuf], pinned_messages: &[PinnedMessage]) -> Vec<PromptSection> {
+    let mut sections = pins::sections(pinned);
+    sections.extend(pins::messages_section(pinned_messages));
+    sections
+}
+
+/// Messages for one API call: the pinned files' current content and the pinned messages are
+/// appended to the system message as extra sections. The stored conversation is left
+/// untouched.
+pub(crate) fn with_pins<'a>(
+    messages: &'a [Value],
+    pinned: &[PathBuf],
+    pinned_messages: &[PinnedMessage],
+) -> Cow<'a, [Value]> {
+    let Some(system) = messages
+        .first()
+        .filter(|m| m["role"] == "system")
+        .and_then(|m| m["content"].as_str())
+    else {
+        return Cow::Borrowed(messages);
+    };
+    if pinned.is_empty() && pinned_messages.is_empty() {
+        return Cow::Borrowed(messages);
+    }
+    let mut sections = vec![PromptSection {
+        id: None,
+        title: String::new(),
+        content: system.to_string(),
+    }];
+    sections.extend(pin_sections(pinned, pinned_messages));
+    let mut owned = messages.to_vec();
+    owned[0]["content"] = json!(join_sections(&sections));
+    Cow::Owned(owned)
+}
+
+/// User message content: plain text, or text followed by image parts (data URLs).
+fn user_content(prompt: &str, images: &[String]) -> Value {
+    if images.is_empty() {
+        return json!(prompt);
+    }
+    let mut parts = vec![json!({ "type": "text", "text": prompt })];
+    parts.extend(
+        images
+            .iter()
+            .map(|url| json!({ "type": "image_url", "image_url": { "url": url } })),
+    );
+    Value::Array(parts)
+}
+
+/// What the next request would send: prompt sections, message count, and token estimates.
+#[derive(Debug, Clone)]
+pub struct ContextPreview {
+    /// System prompt sections with their estimated token counts.
+    pub sections: Vec<(PromptSection, usize)>,
+    /// Configured sections with nothing to send (e.g. no AGENTS.md).
+    pub empty_sections: Vec<SectionId>,
+    /// Registered sections left out by configuration.
+    pub disabled_sections: Vec<SectionId>,
+    /// Number of messages in the request (system + history + pending prompt).
+    pub message_count: usize,
+    /// Estimated tokens for the system message.
+    pub system_tokens: usize,
+    /// Estimated tokens for conversation history and the pending prompt.
+    pub message_tokens: usize,
+    /// Number of tool definitions sent with the request.
+    pub tool_count: usize,
+    /// Estimated tokens for tool definitions.
+    pub tool_tokens: usize,
+    /// Model context window (tokens); 0 when unknown.
+    pub context_length: u64,
+}
+
+impl ContextPreview {
+    /// Total estimated tokens for the request.
+    pub fn total_tokens(&self) -> usize {
+        self.system_tokens + self.message_tokens + self.tool_tokens
+    }
+}
+
+/// Preview the next request without sending it.
+///
+/// Token counts use the same byte-length heuristic as context truncation.
+#[allow(clippy::too_many_arguments)]
+pub fn preview_context(
+    workspace: &Workspace,
+    layout: &PromptLayout,
+    previous_messages: Option<&[Value]>,
+    prompt: &str,
+    pinned: &[PathBuf],
+    pinned_messages: &[PinnedMessage],
+    tools_defs: &[Value],
+    context_length: u64,
+) -> ContextPreview {
+    let messages = initial_messages(
+        workspace,
+        layout,
+        previous_messages.map(<[Value]>::to_vec),
+        prompt,
+        &[],
+    );
+    let messages = with_pins(&messages, pinned, pinned_messages);
+    let system_tokens = estimate_tokens(&messages[..1]);
+    let message_tokens = estimate_tokens(&messages[1..]);
+    let sections: Vec<(PromptSection, usize)> = system_prompt_sections(workspace, layout)
+        .into_iter()
+        .chain(pin_sections(pinned, pinned_messages))
+        .map(|s| {
+            let tokens = s.content.len() / 4;
+            (s, tokens)
+        })
+        .collect();
+
+    let empty_sections = layout
+        .order
+        .iter()
+        .copied()
+        .filter(|id| !sections.iter().any(|(s, _)| s.id == Some(*id)))
+        .collect();
+
+    ContextPreview {
+        sections,
+        empty_sections,
+        disabled_sections: layout.excluded(),
+        message_count: messages.len(),
+        system_tokens,
+        message_tokens,
+        tool_count: tools_defs.len(),
+        tool_tokens: estimate_tokens(tools_defs),
+        context_length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn workspace(agent_md: Option<&str>) -> Workspace {
+        Workspace {
+            root: PathBuf::from("/tmp/project"),
+            working_dir: PathBuf::from("/tmp/project"),
+            project_type: None,
+            agent_md: agent_md.map(str::to_string),
+            context_files: Vec::new(),
+            git_context: None,
+        }
+    }
+
+    #[test]
+    fn build_system_prompt_wraps_agents_md() {
+        let prompt = build_system_prompt(&workspace(Some("Use tabs.")), &PromptLayout::default());
+        assert!(prompt.contains("Workspace root: /tmp/project"));
+        assert!(prompt.ends_with("\n\n--- Project context (AGENTS.md) ---\nUse tabs.\n---"));
+    }
+
+    #[test]
+    fn system_prompt_sections_follow_configured_order() {
+        let layout = PromptLayout {
+            order: vec![SectionId::AgentsMd, SectionId::Git, SectionId::Identity],
+            ..PromptLayout::default()
+        };
+        let sections = system_prompt_sections(&workspace(Some("Use tabs.")), &layout);
+        let ids: Vec<_> = sections.iter().map(|s| s.id).collect();
+        // No Git context in this workspace: the section is skipped.
+        assert_eq!(
+            ids,
+            vec![Some(SectionId::AgentsMd), Some(SectionId::Identity)]
+        );
+        let prompt = join_sections(&sections);
+        assert!(prompt.starts_with("Use tabs."));
+        assert!(!prompt.contains("Workspace root"));
+    }
+
+    #[test]
+    fn initial_messages_keeps_existing_system_message() {
+        let previous = vec![json!({"role": "system", "content": "old"})];
+        let messages = initial_messages(
+            &workspace(None),
+            &PromptLayout::default(),
+            Some(previous),
+            "hi",
+            &[],
+        );
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["content"], "old");
+        assert_eq!(messages[1]["content"], "hi");
+
+        // A compaction summary is not the system prompt: one is added in front of it.
+        let previous = vec![compact::summary_message("- earlier work")];
+        let messages = initial_messages(
+            &workspace(None),
+            &PromptLayout::default(),
+            Some(previous),
+            "hi",
+            &[],
+        );
+        assert_eq!(messages.len(), 3);
+        assert!(!compact::is_summary(&messages[0]));
+        assert!(compact::is_summary(&messages[1]));
+    }
+
+    #[test]
+    fn refresh_system_message_uses_new_agents_md() {
+        let mut messages = initial_messages(
+            &workspace(Some("Use tabs.")),
+            &PromptLayout::default(),
+            None,
+            "hi",
+            &[],
+        );
+        refresh_system_message(
+            &mut messages,
+            &workspace(Some("Use spaces.")),
+            &PromptLayout::default(),
+        );
+        let system = messages[0]["content"].as_str().unwrap();
+        assert!(system.contains("Use spaces."));
+        assert!(!system.contains("Use tabs."));
+        assert_eq!(messages[1]["content"], "hi");
+    }
+
+    #[test]
+    fn with_pins_extends_system_message_only_for_the_request() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pinned = dir.path().join("notes.md");
+        std::fs::write(&pinned, "remember this").unwrap();
+        let messages =
+            initial_messages(&workspace(None), &PromptLayout::default(), None, "hi", &[]);
+        let pinned_message = PinnedMessage {
+            from_user: true,
+            content: "Target Rust 1.85.".to_string(),
+        };
+        let request = with_pins(
+            &messages,
+            std::slice::from_ref(&pinned),
+            std::slice::from_ref(&pinned_message),
+        );
+        let system = request[0]["content"].as_str().unwrap();
+        assert!(system.starts_with("Respond in the same language"));
+        assert!(system.contains(&format!(
+            "--- Pinned file: {} ---\nremember this\n---",
+            pinned.display()
+        )));
+        assert!(system.ends_with("[User]\nTarget Rust 1.85.\n---"));
+        assert!(
+            !messages[0]["content"]
+                .as_str()
+                .unwrap()
+                .contains("remember")
+        );
+        assert!(matches!(with_pins(&messages, &[], &[]), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn initial_messages_attaches_images_as_parts() {
+        let images = vec!["data:image/png;base64,AAAA".to_string()];
+        let messages = initial_messages(
+            &workspace(None),
+            &PromptLayout::default(),
+            None,
+            "what is this?",
+            &images,
+        );
+        let content = &messages[1]["content"];
+        assert_eq!(content[0]["text"], "what is this?");
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(content[1]["image_url"]["url"], images[0]);
+    }
+
+    #[test]
+    fn preview_context_counts_messages_and_tools() {
+        let previous = vec![
+            json!({"role": "user", "content": "a"}),
+            json!({"role": "assistant", "content": "b"}),
+        ];
+        let tools = vec![json!({"type": "function"})];
+        let preview = preview_context(
+            &workspace(None),
+            &PromptLayout::default(),
+            Some(&previous),
+            "c",
+            &[],
+            &[],
+            &tools,
+            1000,
+        );
+        assert_eq!(preview.message_count, 4);
+        // Identity and workspace; the other registered sections have nothing to send.
+        assert_eq!(preview.sections.len(), 2);
+        assert_eq!(preview.empty_sections.len(), SectionId::ALL.len() - 2);
+        assert!(preview.disabled_sections.is_empty());
+        assert_eq!(preview.tool_count, 1);
+        assert!(preview.total_tokens() > preview.system_tokens);
+    }
+}
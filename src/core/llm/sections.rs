@@ -0,0 +1,286 @@
+//! Registry of system prompt sections: each kind of context has a key, a title, and a
+//! builder. Which sections are sent, and in which order, comes from `PromptLayout`.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::remote;
+use crate::core::workspace::{ContextFile, Workspace};
+
+/// Kind of system prompt section. `ALL` lists them in the default order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SectionId {
+    /// Base behaviour (answer in the user's language).
+    Identity,
+    /// Workspace root, project type, default base path for tools.
+    Workspace,
+    /// AGENTS.md from the workspace root.
+    AgentsMd,
+    /// Files listed in `.my-open-claude/context.yaml`.
+    ContextFiles,
+    /// Git branch and status.
+    Git,
+    /// User-level notes from `memory.md` in the config directory, sent in every workspace.
+    Memory,
+    /// Free-form instructions from `MY_OPEN_CLAUDE_CUSTOM_INSTRUCTIONS`.
+    Custom,
+}
+
+impl SectionId {
+    pub const ALL: [SectionId; 7] = [
+        SectionId::Identity,
+        SectionId::Workspace,
+        SectionId::AgentsMd,
+        SectionId::ContextFiles,
+        SectionId::Git,
+        SectionId::Memory,
+        SectionId::Custom,
+    ];
+
+    /// Key used in `MY_OPEN_CLAUDE_PROMPT_SECTIONS` and shown by `/context`.
+    pub fn key(self) -> &'static str {
+        match self {
+            SectionId::Identity => "identity",
+            SectionId::Workspace => "workspace",
+            SectionId::AgentsMd => "agents",
+            SectionId::ContextFiles => "files",
+            SectionId::Git => "git",
+            SectionId::Memory => "memory",
+            SectionId::Custom => "custom",
+        }
+    }
+
+    /// Title of the section in the system prompt (`--- title ---` delimiter).
+    pub fn title(self) -> &'static str {
+        match self {
+            SectionId::Identity => "Instructions",
+            SectionId::Workspace => "Workspace",
+            SectionId::AgentsMd => "Project context (AGENTS.md)",
+            SectionId::ContextFiles => "Context files",
+            SectionId::Git => "Git context",
+            SectionId::Memory => "Memory",
+            SectionId::Custom => "Custom instructions",
+        }
+    }
+
+    /// Section for a key (case-insensitive); "agents.md" is accepted for `AgentsMd`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim().to_lowercase();
+        if key == "agents.md" {
+            return Some(SectionId::AgentsMd);
+        }
+        SectionId::ALL.into_iter().find(|id| id.key() == key)
+    }
+
+    /// Content of this section, or None when there is nothing to send (no AGENTS.md, no
+    /// context files, not a Git repo, no memory file, no custom instructions).
+    pub fn content(self, workspace: &Workspace, layout: &PromptLayout) -> Option<String> {
+        match self {
+            SectionId::Identity => Some(
+                "Respond in the same language as the user. If they write in French, respond in French; if in English, respond in English; match their language.".to_string(),
+            ),
+            SectionId::Workspace => {
+                let project_type = workspace
+                    .project_type
+                    .map(|pt| pt.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                let mut content = format!(
+                    "Workspace root: {}\nProject type: {}\nUse the workspace root as the default base path for Read, Write, Grep, ListDir, Glob, and Edit when the user does not specify a path.",
+                    workspace.root.display(),
+                    project_type
+                );
+                if workspace.working_dir != workspace.root {
+                    content.push_str(&format!(
+                        "\nWorking directory: {}\nRelative paths in tool calls and Bash commands resolve against the working directory.",
+                        workspace.working_dir.display()
+                    ));
+                }
+                if let Some(remote) = remote::current() {
+                    content.push_str(&format!(
+                        "\nThe workspace is on the remote host {}: Bash, Read, Write, Edit, and Grep run there, and paths are paths on that host. To list or find files, use Bash (e.g. ls, find).",
+                        remote.host()
+                    ));
+                }
+                Some(content)
+            }
+            SectionId::AgentsMd => workspace.agent_md.clone(),
+            SectionId::ContextFiles => context_files(&workspace.context_files),
+            SectionId::Git => workspace.git_context.as_ref().map(|git| git.formatted()),
+            SectionId::Memory => layout
+                .memory_file
+                .as_ref()
+                .and_then(|path| fs::read_to_string(path).ok()),
+            SectionId::Custom => layout.custom.clone(),
+        }
+        .filter(|content| !content.trim().is_empty())
+    }
+}
+
+/// The context files, each under its path, with a note on the ones cut by the size caps.
+fn context_files(files: &[ContextFile]) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    let mut content = String::from(
+        "The project asks for these files to always be in context. Their content is current; \
+         read them again only after editing them.",
+    );
+    for file in files {
+        content.push_str(&format!("\n\n### {}\n```\n{}", file.path, file.content));
+        if !file.content.is_empty() && !file.content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str("```");
+        if file.truncated() {
+            content.push_str(&format!(
+                "\n(truncated: file is {} bytes, first {} shown; use Read for the rest)",
+                file.size,
+                file.content.len()
+            ));
+        }
+    }
+    Some(content)
+}
+
+impl fmt::Display for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+/// Which sections the system prompt contains, in order, and the sources of the
+/// user-provided ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptLayout {
+    /// Included sections, in the order they are sent. Sections not listed are left out.
+    pub order: Vec<SectionId>,
+    /// File read for the memory section.
+    pub memory_file: Option<PathBuf>,
+    /// Content of the custom section.
+    pub custom: Option<String>,
+}
+
+impl Default for PromptLayout {
+    fn default() -> Self {
+        Self {
+            order: SectionId::ALL.to_vec(),
+            memory_file: None,
+            custom: None,
+        }
+    }
+}
+
+impl PromptLayout {
+    /// Parse a comma-separated list of section keys. Duplicates are dropped; an unknown key
+    /// is returned as the error.
+    pub fn parse_order(list: &str) -> Result<Vec<SectionId>, String> {
+        let mut order = Vec::new();
+        for key in list.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+            let id = SectionId::from_key(key).ok_or_else(|| key.to_string())?;
+            if !order.contains(&id) {
+                order.push(id);
+            }
+        }
+        Ok(order)
+    }
+
+    /// Registered sections left out by the configured order.
+    pub fn excluded(&self) -> Vec<SectionId> {
+        SectionId::ALL
+            .into_iter()
+            .filter(|id| !self.order.contains(id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_order_keeps_order_and_drops_duplicates() {
+        assert_eq!(
+            PromptLayout::parse_order("git, Identity,agents.md,git"),
+            Ok(vec![
+                SectionId::Git,
+                SectionId::Identity,
+                SectionId::AgentsMd
+            ])
+        );
+        assert_eq!(
+            PromptLayout::parse_order("identity,soul"),
+            Err("soul".to_string())
+        );
+    }
+
+    #[test]
+    fn excluded_lists_sections_missing_from_order() {
+        let layout = PromptLayout {
+            order: vec![SectionId::Workspace, SectionId::Identity],
+            ..PromptLayout::default()
+        };
+        assert_eq!(
+            layout.excluded(),
+            vec![
+                SectionId::AgentsMd,
+                SectionId::ContextFiles,
+                SectionId::Git,
+                SectionId::Memory,
+                SectionId::Custom
+            ]
+        );
+    }
+
+    #[test]
+    fn memory_and_custom_are_empty_without_sources() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let workspace = Workspace {
+            root: dir.path().to_path_buf(),
+            working_dir: dir.path().to_path_buf(),
+            project_type: None,
+            agent_md: None,
+            context_files: Vec::new(),
+            git_context: None,
+        };
+        let mut layout = PromptLayout {
+            memory_file: Some(dir.path().join("memory.md")),
+            ..PromptLayout::default()
+        };
+        assert_eq!(SectionId::Memory.content(&workspace, &layout), None);
+        assert_eq!(SectionId::Custom.content(&workspace, &layout), None);
+
+        fs::write(dir.path().join("memory.md"), "I prefer small commits.").unwrap();
+        layout.custom = Some("Be terse.".to_string());
+        assert_eq!(
+            SectionId::Memory.content(&workspace, &layout).as_deref(),
+            Some("I prefer small commits.")
+        );
+        assert_eq!(
+            SectionId::Custom.content(&workspace, &layout).as_deref(),
+            Some("Be terse.")
+        );
+    }
+
+    #[test]
+    fn context_files_quote_each_file_and_note_truncation() {
+        let files = vec![
+            ContextFile {
+                path: "db/schema.sql".to_string(),
+                content: "CREATE TABLE t;\n".to_string(),
+                size: 16,
+            },
+            ContextFile {
+                path: "api.yaml".to_string(),
+                content: "openapi".to_string(),
+                size: 2000,
+            },
+        ];
+        let content = context_files(&files).unwrap();
+        assert!(content.contains("### db/schema.sql\n```\nCREATE TABLE t;\n```\n\n### api.yaml"));
+        assert!(content.ends_with(
+            "openapi\n```\n(truncated: file is 2000 bytes, first 7 shown; use Read for the rest)"
+        ));
+        assert_eq!(context_files(&[]), None);
+    }
+}
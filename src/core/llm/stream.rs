@@ -1,7 +1,14 @@
-//! Streaming chat response: tool call delta merging, size limits, token usage.
+//! Chat response parsing: tool call delta merging, size limits, token usage, and progress
+//! of tool calls while their arguments stream in.
+//! Non-streaming responses reuse the same accumulation so limits apply to both paths.
 
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
+use super::ProgressEvent;
+
 /// Max tool calls to accept from a single response (guards against malformed API).
 pub(super) const MAX_TOOL_CALLS: usize = 64;
 /// Max content size (2MB) to prevent unbounded memory growth from malformed streams.
@@ -9,16 +16,25 @@ pub(super) const MAX_CONTENT_BYTES: usize = 2 * 1024 * 1024;
 /// Max size for a single tool call's arguments JSON (64KB).
 pub(super) const MAX_TOOL_CALL_ARGS_BYTES: usize = 64 * 1024;
 
+/// Argument fields previewed while a tool call streams in, in order of preference.
+const PREPARING_PREVIEW_KEYS: [&str; 4] = ["file_path", "path", "command", "pattern"];
+/// Growth of a call's arguments between two reports of it (see `ToolCallProgress`).
+const PREPARING_STEP_BYTES: usize = 1024;
+
 /// Token usage reported by the API in the final streaming chunk.
 #[derive(Debug, Clone, Default)]
 pub struct TokenUsage {
-    /// Prompt tokens (from API; reserved for future detailed display).
-    #[allow(dead_code)]
+    /// Prompt tokens (from API; reported in webhook payloads).
     pub prompt_tokens: u64,
-    /// Completion tokens (from API; reserved for future detailed display).
-    #[allow(dead_code)]
+    /// Completion tokens (from API; reported in webhook payloads).
     pub completion_tokens: u64,
     pub total_tokens: u64,
+    /// Cost of the call in credits (`usage.cost`, OpenRouter); 0 when not reported.
+    pub cost: f64,
+    /// Prompt tokens read from the provider's prompt cache (included in `prompt_tokens`).
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the provider's prompt cache (included in `prompt_tokens`).
+    pub cache_write_tokens: u64,
 }
 
 impl TokenUsage {
@@ -30,13 +46,50 @@ impl TokenUsage {
             prompt_tokens: total,
             completion_tokens: 0,
             total_tokens: total,
+            ..Self::default()
         }
     }
 }
 
+/// Token usage and API latency of one assistant reply, summed over every API call of the
+/// turn (tool rounds included). Shown under the reply in the TUI and saved with it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Time spent waiting for the API (tool execution excluded).
+    pub latency_ms: u64,
+    /// Model that produced the reply.
+    pub model: String,
+    /// Cost in credits, as reported by the API (0 when not reported).
+    #[serde(default)]
+    pub cost: f64,
+}
+
+impl MessageUsage {
+    /// Account for one API call of the turn.
+    pub fn add_call(&mut self, model: &str, usage: &TokenUsage, latency: Duration) {
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        self.cost += usage.cost;
+        self.latency_ms += u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        self.model = model.to_string();
+    }
+
+    /// Account for the calls of another loop run for this reply (a subtask).
+    pub fn add_usage(&mut self, other: &MessageUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+        self.latency_ms += other.latency_ms;
+    }
+}
+
 /// Parse token usage from a streaming chunk's `usage` field (present in the final chunk).
+/// Cache counts come from `prompt_tokens_details` (OpenRouter and the Anthropic backend).
 pub(crate) fn parse_usage(chunk: &Value) -> Option<TokenUsage> {
     let usage = chunk.get("usage")?;
+    let details = &usage["prompt_tokens_details"];
     Some(TokenUsage {
         prompt_tokens: usage
             .get("prompt_tokens")
@@ -50,6 +103,9 @@ pub(crate) fn parse_usage(chunk: &Value) -> Option<TokenUsage> {
             .get("total_tokens")
             .and_then(|v| v.as_u64())
             .unwrap_or(0),
+        cost: usage.get("cost").and_then(|v| v.as_f64()).unwrap_or(0.0),
+        cache_read_tokens: details["cached_tokens"].as_u64().unwrap_or(0),
+        cache_write_tokens: details["cache_write_tokens"].as_u64().unwrap_or(0),
     })
 }
 
@@ -90,23 +146,132 @@ pub(crate) fn merge_tool_call_delta(acc: &mut Vec<Value>, delta_tc: &Value) {
     }
 }
 
+/// Collect the tool calls of a complete (non-streaming) assistant message.
+/// Each call is merged as a delta at its position, applying the same limits as streaming.
+pub(crate) fn collect_message_tool_calls(message: &Value) -> Vec<Value> {
+    let mut acc = Vec::new();
+    if let Some(tc_arr) = message["tool_calls"].as_array() {
+        for (i, tc) in tc_arr.iter().enumerate() {
+            let mut delta = tc.clone();
+            delta["index"] = json!(i);
+            merge_tool_call_delta(&mut acc, &delta);
+        }
+    }
+    acc
+}
+
+/// Value of the string field `key` of the JSON object `args`, which may still be
+/// incomplete, once the closing quote of the value has arrived.
+fn partial_string_field(args: &str, key: &str) -> Option<String> {
+    let needle = format!("\"{}\"", key);
+    let start = args.find(&needle)? + needle.len();
+    let value = args[start..]
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start()
+        .strip_prefix('"')?;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return serde_json::from_str(&format!("\"{}\"", &value[..i])).ok(),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Progress of the tool calls of a streamed response, reported before they run: large
+/// calls (a Write of a whole file) can take a while to stream in.
+#[derive(Debug, Default)]
+pub(crate) struct ToolCallProgress {
+    /// Argument size and preview last reported, by call index.
+    reported: Vec<Option<(usize, String)>>,
+}
+
+impl ToolCallProgress {
+    /// Event for the call at `index` of `acc` once its name is known, then each time its
+    /// preview changes or its arguments grew by `PREPARING_STEP_BYTES`.
+    pub(crate) fn update(&mut self, acc: &[Value], index: usize) -> Option<ProgressEvent> {
+        let function = &acc.get(index)?["function"];
+        let name = function["name"].as_str().filter(|n| !n.is_empty())?;
+        let args = function["arguments"].as_str().unwrap_or("");
+        let preview = PREPARING_PREVIEW_KEYS
+            .iter()
+            .find_map(|key| partial_string_field(args, key))
+            .unwrap_or_default();
+        if self.reported.len() <= index {
+            self.reported.resize(index + 1, None);
+        }
+        let due = match &self.reported[index] {
+            None => true,
+            Some((bytes, reported)) => {
+                *reported != preview || args.len() >= bytes + PREPARING_STEP_BYTES
+            }
+        };
+        if !due {
+            return None;
+        }
+        self.reported[index] = Some((args.len(), preview.clone()));
+        Some(ProgressEvent::PreparingTool {
+            name: name.to_string(),
+            preview,
+            bytes: args.len(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn message_usage_sums_calls() {
+        let mut usage = MessageUsage::default();
+        let call = TokenUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 200,
+            total_tokens: 1200,
+            ..TokenUsage::default()
+        };
+        usage.add_call("a/model", &call, Duration::from_millis(1500));
+        usage.add_call("a/model", &call, Duration::from_millis(500));
+        assert_eq!(usage.prompt_tokens, 2000);
+        assert_eq!(usage.completion_tokens, 400);
+        assert_eq!(usage.latency_ms, 2000);
+        assert_eq!(usage.model, "a/model");
+    }
+
     #[test]
     fn parse_usage_valid_chunk() {
         let chunk = serde_json::json!({
             "usage": {
                 "prompt_tokens": 10,
                 "completion_tokens": 20,
-                "total_tokens": 30
+                "total_tokens": 30,
+                "cost": 0.0125
             }
         });
         let usage = parse_usage(&chunk).unwrap();
         assert_eq!(usage.prompt_tokens, 10);
         assert_eq!(usage.completion_tokens, 20);
         assert_eq!(usage.total_tokens, 30);
+        assert_eq!(usage.cost, 0.0125);
+        assert_eq!(usage.cache_read_tokens, 0);
+    }
+
+    #[test]
+    fn parse_usage_cache_details() {
+        let chunk = serde_json::json!({
+            "usage": {
+                "prompt_tokens": 1200,
+                "prompt_tokens_details": {"cached_tokens": 1000, "cache_write_tokens": 150}
+            }
+        });
+        let usage = parse_usage(&chunk).unwrap();
+        assert_eq!(usage.cache_read_tokens, 1000);
+        assert_eq!(usage.cache_write_tokens, 150);
     }
 
     #[test]
@@ -172,4 +337,78 @@ mod tests {
         merge_tool_call_delta(&mut acc, &delta);
         assert!(acc.is_empty());
     }
+
+    #[test]
+    fn collect_message_tool_calls_assigns_indices() {
+        let message = serde_json::json!({
+            "role": "assistant",
+            "tool_calls": [
+                {"id": "a", "type": "function", "function": {"name": "Read", "arguments": "{}"}},
+                {"id": "b", "type": "function", "function": {"name": "Grep", "arguments": "{}"}}
+            ]
+        });
+        let calls = collect_message_tool_calls(&message);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["id"], "a");
+        assert_eq!(calls[1]["function"]["name"], "Grep");
+    }
+
+    #[test]
+    fn collect_message_tool_calls_none() {
+        let message = serde_json::json!({"role": "assistant", "content": "Hi"});
+        assert!(collect_message_tool_calls(&message).is_empty());
+    }
+
+    #[test]
+    fn partial_string_field_waits_for_the_closing_quote() {
+        let args = r#"{"file_path": "src/a \"b\".rs", "content": "fn ma"#;
+        assert_eq!(
+            partial_string_field(args, "file_path").as_deref(),
+            Some("src/a \"b\".rs")
+        );
+        assert_eq!(partial_string_field(args, "content"), None);
+        assert_eq!(
+            partial_string_field(r#"{"file_path": "src/"#, "file_path"),
+            None
+        );
+        assert_eq!(partial_string_field("{", "path"), None);
+    }
+
+    #[test]
+    fn tool_call_progress_reports_name_preview_and_growth() {
+        let mut progress = ToolCallProgress::default();
+        let mut acc = vec![];
+        let mut push = |acc: &mut Vec<Value>, delta: Value| {
+            merge_tool_call_delta(acc, &delta);
+            progress.update(acc, 0)
+        };
+        let event = push(
+            &mut acc,
+            json!({"index": 0, "id": "c1", "function": {"name": "Write", "arguments": ""}}),
+        );
+        assert_eq!(
+            event,
+            Some(ProgressEvent::PreparingTool {
+                name: "Write".to_string(),
+                preview: String::new(),
+                bytes: 0
+            })
+        );
+        let event = push(
+            &mut acc,
+            json!({"index": 0, "function": {"arguments": "{\"file_path\": \"src/foo.rs\", \"content\": \""}}),
+        )
+        .unwrap();
+        assert_eq!(
+            event.to_string(),
+            "Preparing Write: src/foo.rs (40 B so far)"
+        );
+        let small = json!({"index": 0, "function": {"arguments": "x".repeat(100)}});
+        assert_eq!(push(&mut acc, small), None);
+        let large = json!({"index": 0, "function": {"arguments": "x".repeat(2000)}});
+        assert_eq!(
+            push(&mut acc, large).unwrap().to_string(),
+            "Preparing Write: src/foo.rs (2.1 KB so far)"
+        );
+    }
 }
@@ -0,0 +1,304 @@
+//! Structured output: replies constrained to a JSON schema. The schema is sent as
+//! `response_format` (see `GenerationParams::response_schema`); the final reply of the turn
+//! is parsed and checked against it, and the model is asked again when it does not match.
+//!
+//! The check covers the keywords structured output schemas use: `type`, `enum`, `const`,
+//! `properties`, `required`, `additionalProperties`, `items`, `minItems`, `maxItems`,
+//! `minimum`, `maximum`, `minLength`, `maxLength`, `anyOf`. Other keywords are not checked.
+
+use serde_json::{Map, Value, json};
+
+/// Requests sent again when the reply is not valid JSON for the schema.
+pub const MAX_RETRIES: u32 = 2;
+
+/// Most schema mismatches quoted in an error.
+const MAX_ERRORS: usize = 5;
+
+/// `response_format` of a request constrained to `schema`.
+pub fn response_format(schema: &Value) -> Value {
+    json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": "output",
+            "schema": schema,
+        },
+    })
+}
+
+/// Parse a reply as JSON (a ```json fence around it is accepted) and check it against
+/// `schema`. The error says what is wrong, for the retry message and the final note.
+pub fn parse(content: &str, schema: &Value) -> Result<Value, String> {
+    let value: Value = serde_json::from_str(unfenced(content))
+        .map_err(|e| format!("the reply is not valid JSON: {}", e))?;
+    let mut errors = Vec::new();
+    check(&value, schema, "$", &mut errors);
+    if errors.is_empty() {
+        return Ok(value);
+    }
+    let more = errors.len().saturating_sub(MAX_ERRORS);
+    errors.truncate(MAX_ERRORS);
+    let mut message = format!("the reply does not match the schema: {}", errors.join("; "));
+    if more > 0 {
+        message.push_str(&format!(" (and {} more)", more));
+    }
+    Err(message)
+}
+
+/// User message asking the model to answer again after `error`.
+pub fn retry_message(error: &str) -> Value {
+    json!({
+        "role": "user",
+        "content": format!(
+            "Your previous reply was rejected: {}. Reply again with only a JSON value that \
+             matches the requested schema, without any other text.",
+            error
+        ),
+    })
+}
+
+/// `content` without a Markdown code fence around it.
+fn unfenced(content: &str) -> &str {
+    let trimmed = content.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return trimmed;
+    };
+    // Drop the info string ("json") on the opening line.
+    match body.split_once('\n') {
+        Some((_, body)) => body.trim(),
+        None => trimmed,
+    }
+}
+
+/// Add the mismatches between `value` (at `path`, e.g. "$.items[2]") and `schema`.
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` or an empty schema accepts anything; `false` accepts nothing.
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{}: no value is allowed here", path));
+        }
+        return;
+    };
+    if let Some(expected) = schema.get("type")
+        && !type_matches(value, expected)
+    {
+        errors.push(format!(
+            "{}: expected {}, got {}",
+            path,
+            type_names(expected),
+            type_name(value)
+        ));
+        return;
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{}: {} is not one of the allowed values",
+            path, value
+        ));
+    }
+    if let Some(constant) = schema.get("const")
+        && constant != value
+    {
+        errors.push(format!("{}: expected {}", path, constant));
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array)
+        && !options.iter().any(|option| {
+            let mut option_errors = Vec::new();
+            check(value, option, path, &mut option_errors);
+            option_errors.is_empty()
+        })
+    {
+        errors.push(format!("{}: matches none of the anyOf schemas", path));
+    }
+    match value {
+        Value::Object(object) => check_object(object, schema, path, errors),
+        Value::Array(items) => check_array(items, schema, path, errors),
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && len < min
+            {
+                errors.push(format!("{}: shorter than {} characters", path, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && len > max
+            {
+                errors.push(format!("{}: longer than {} characters", path, max));
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && n < min
+            {
+                errors.push(format!("{}: less than the minimum {}", path, min));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && n > max
+            {
+                errors.push(format!("{}: more than the maximum {}", path, max));
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn check_object(
+    object: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for name in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !object.contains_key(name) {
+            errors.push(format!("{}: missing required property \"{}\"", path, name));
+        }
+    }
+    for (name, value) in object {
+        let child = format!("{}.{}", path, name);
+        match properties.and_then(|p| p.get(name)) {
+            Some(property) => check(value, property, &child, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected property \"{}\"", path, name))
+                }
+                Some(extra) => check(value, extra, &child, errors),
+                None => {}
+            },
+        }
+    }
+}
+
+fn check_array(items: &[Value], schema: &Map<String, Value>, path: &str, errors: &mut Vec<String>) {
+    let len = items.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+        && len < min
+    {
+        errors.push(format!("{}: fewer than {} items", path, min));
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+        && len > max
+    {
+        errors.push(format!("{}: more than {} items", path, max));
+    }
+    if let Some(item_schema) = schema.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            check(item, item_schema, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+/// `value` has the schema `type` (a name, or a list of names).
+fn type_matches(value: &Value, expected: &Value) -> bool {
+    let is = |name: &str| match name {
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        "number" => value.is_number(),
+        other => type_name(value) == other,
+    };
+    match expected {
+        Value::String(name) => is(name),
+        Value::Array(names) => names.iter().filter_map(Value::as_str).any(is),
+        _ => true,
+    }
+}
+
+fn type_names(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or_default().to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string", "maxLength": 20},
+                "labels": {"type": "array", "items": {"enum": ["bug", "feature"]}, "maxItems": 2},
+                "priority": {"type": "integer", "minimum": 1, "maximum": 3},
+                "assignee": {"type": ["string", "null"]},
+            },
+            "required": ["title", "priority"],
+            "additionalProperties": false,
+        })
+    }
+
+    #[test]
+    fn parse_accepts_matching_replies_in_or_out_of_a_fence() {
+        let reply =
+            r#"{"title": "Crash on save", "labels": ["bug"], "priority": 1, "assignee": null}"#;
+        let value = parse(reply, &schema()).unwrap();
+        assert_eq!(value["priority"], json!(1));
+        let fenced = format!("```json\n{}\n```", reply);
+        assert_eq!(parse(&fenced, &schema()).unwrap(), value);
+    }
+
+    #[test]
+    fn parse_lists_schema_mismatches() {
+        let reply = r#"{"title": "A title longer than twenty", "labels": ["bug", "docs"], "priority": 1.5, "extra": true}"#;
+        let error = parse(reply, &schema()).unwrap_err();
+        assert!(
+            error.contains("$.title: longer than 20 characters"),
+            "{}",
+            error
+        );
+        assert!(error.contains("$.labels[1]: \"docs\" is not one of the allowed values"));
+        assert!(error.contains("$.priority: expected integer, got number"));
+        assert!(error.contains("$: unexpected property \"extra\""));
+
+        let error = parse(r#"{"title": "x"}"#, &schema()).unwrap_err();
+        assert!(
+            error.contains("missing required property \"priority\""),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn parse_rejects_text_that_is_not_json() {
+        let error = parse("Here is the issue: {...}", &schema()).unwrap_err();
+        assert!(
+            error.starts_with("the reply is not valid JSON"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn response_format_names_the_schema() {
+        let format = response_format(&json!({"type": "string"}));
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["name"], "output");
+        assert_eq!(format["json_schema"]["schema"], json!({"type": "string"}));
+    }
+}
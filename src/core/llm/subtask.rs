@@ -0,0 +1,298 @@
+//! Task tool calls: a child agent loop with its own messages, read-only tools, and a budget,
+//! whose final reply becomes the tool result. The child never sees the parent conversation
+//! and the parent only gets the summary, so long explorations stay out of the main context.
+
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::core::generation::GenerationParams;
+use crate::core::stats;
+use crate::core::tools::{self, Tool};
+use crate::core::trace::{self, TraceEvent};
+
+use super::agent_loop::{self, AgentLoopCallbacks, AgentLoopParams};
+use super::stream::MessageUsage;
+use super::text_tools::ToolProtocol;
+use super::tool_execution;
+use super::{ChatError, ChatResult, ToolEvent};
+
+/// Tokens (prompt and completion, all calls) a subtask may use before its last call.
+const MAX_TASK_TOKENS: u64 = 200_000;
+
+/// Mode of the child loop: tools with side effects are blocked.
+const CHILD_MODE: &str = "Ask";
+
+/// Limits of a subtask's loop. Once reached, the next request offers no tools, so the
+/// model answers with what it found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Budget {
+    pub max_calls: u32,
+    pub max_tokens: u64,
+}
+
+impl Budget {
+    /// True when the request following `calls` model calls that used `usage` is the last.
+    pub(super) fn is_spent(&self, calls: u32, usage: &MessageUsage) -> bool {
+        calls + 1 >= self.max_calls
+            || usage.prompt_tokens + usage.completion_tokens >= self.max_tokens
+    }
+}
+
+/// Tool result and usage of a Task call, appended by the parent loop.
+pub(super) struct SubtaskOutcome {
+    pub log_line: String,
+    pub message: Value,
+    /// Usage of the child's model calls, counted in the parent reply.
+    pub usage: MessageUsage,
+}
+
+/// True when `tool_call` calls the Task tool and it is one of `tools_list`.
+pub(super) fn is_subtask_call(tool_call: &Value, tools_list: &[Box<dyn Tool>]) -> bool {
+    let name = tool_call["function"]["name"].as_str().unwrap_or_default();
+    name == tools::TaskTool.name() && tools_list.iter().any(|t| t.name() == name)
+}
+
+/// Tools the child may not use: those disabled for the parent, the ones with side effects,
+/// Task itself (no nesting), and those left out of the call's `tools` list.
+fn child_disabled_tools(
+    tools_list: &[Box<dyn Tool>],
+    disabled: &[String],
+    allowed: Option<&[String]>,
+) -> Vec<String> {
+    let mut child = disabled.to_vec();
+    for tool in tools_list {
+        let name = tool.name();
+        let usable = tool.is_read_only()
+            && name != tools::TaskTool.name()
+            && allowed.is_none_or(|names| names.iter().any(|n| n.eq_ignore_ascii_case(name)));
+        if !usable {
+            child.push(name.to_string());
+        }
+    }
+    child
+}
+
+/// Added to the last request of a spent budget, which offers no tools.
+pub(super) fn final_call_message() -> Value {
+    json!({
+        "role": "user",
+        "content": "Your tool budget is spent. Reply now with your summary of what you found.",
+    })
+}
+
+fn system_prompt(working_dir: &Path, max_calls: u32) -> String {
+    format!(
+        "You are a sub-agent of a coding assistant, working on one focused task in the project at {}. \
+Explore with the read-only tools you have, then reply with a concise summary of your findings: \
+the answer first, then the relevant file paths and line numbers. Your reply is all the parent agent \
+sees: do not ask questions, and say what you could not determine. You have at most {} model calls; \
+the last one offers no tools.",
+        working_dir.display(),
+        max_calls
+    )
+}
+
+/// Run a Task call: the child loop runs to its final reply (or its budget), which becomes
+/// the tool result. Failures of the child are reported to the model as an error result;
+/// only cancellation stops the parent turn.
+pub(super) async fn run(
+    tool_call: &Value,
+    params: &AgentLoopParams<'_>,
+    callbacks: &AgentLoopCallbacks<'_>,
+    protocol: ToolProtocol,
+    position: (usize, usize),
+) -> Result<SubtaskOutcome, ChatError> {
+    let id = tool_call["id"].as_str().unwrap_or_default().to_string();
+    let function = &tool_call["function"];
+    let name = function["name"].as_str().unwrap_or_default();
+    let args_str = function["arguments"].as_str().unwrap_or("{}");
+    let args: Value = serde_json::from_str(args_str).map_err(|e| ChatError::ToolArgs {
+        tool: name.to_string(),
+        source: e,
+    })?;
+
+    let (index, total) = position;
+    let event = tool_execution::progress_event(
+        tool_call,
+        params.tools_list,
+        params.working_dir,
+        params.modes,
+        index,
+        total,
+    );
+    let log_line = event.to_string();
+    if let Some(progress) = callbacks.on_progress {
+        progress(&event);
+    }
+    if let Some(cb) = callbacks.on_tool_event {
+        cb(&ToolEvent::Started { id: id.clone() });
+    }
+    let started = Instant::now();
+
+    let mut usage = MessageUsage::default();
+    let blocked = tools::TaskTool;
+    let result =
+        match tool_execution::blocked_tool_message(&blocked, params.mode, params.disabled_tools) {
+            Some(message) => message,
+            None => match serde_json::from_value::<tools::TaskArgs>(args) {
+                Err(e) => format!("Error: invalid Task arguments: {}", e),
+                Ok(task) => match run_child(&task, params, callbacks, protocol).await {
+                    Ok((content, child_usage)) => {
+                        usage = child_usage;
+                        content
+                    }
+                    Err(ChatError::Cancelled) => return Err(ChatError::Cancelled),
+                    Err(e) => format!("Error: the subtask failed: {}", e),
+                },
+            },
+        };
+    let result = tool_execution::truncate_tool_output(result, tools::MAX_OUTPUT_LARGE);
+
+    let duration = started.elapsed();
+    let success = !tool_execution::is_error_result(&result);
+    stats::record_tool(name);
+    trace::record(TraceEvent::Tool {
+        name: name.to_string(),
+        duration_ms: trace::millis(duration),
+        success,
+        output_bytes: result.len(),
+    });
+    if let Some(cb) = callbacks.on_tool_event {
+        cb(&ToolEvent::Finished {
+            id: id.clone(),
+            success,
+            duration,
+            view: None,
+        });
+    }
+    Ok(SubtaskOutcome {
+        log_line,
+        message: json!({
+            "role": "tool",
+            "tool_call_id": id,
+            "content": result,
+        }),
+        usage,
+    })
+}
+
+/// Run the child loop of `task`; returns its final reply and usage.
+async fn run_child(
+    task: &tools::TaskArgs,
+    params: &AgentLoopParams<'_>,
+    callbacks: &AgentLoopCallbacks<'_>,
+    protocol: ToolProtocol,
+) -> Result<(String, MessageUsage), ChatError> {
+    let max_calls = task.turns();
+    let disabled = child_disabled_tools(
+        params.tools_list,
+        params.disabled_tools,
+        task.tools.as_deref(),
+    );
+    let mut messages = Arc::new(vec![
+        json!({ "role": "system", "content": system_prompt(params.working_dir, max_calls) }),
+        json!({ "role": "user", "content": task.prompt }),
+    ]);
+    let mut tool_log = Arc::new(Vec::new());
+    // The output schema is the parent's reply's: the child reports in plain text.
+    let generation = GenerationParams {
+        response_schema: None,
+        ..params.generation.clone()
+    };
+    // Boxed: the child loop is the same future type as the one running this call.
+    let result = Box::pin(agent_loop::run_agent_loop(
+        AgentLoopParams {
+            client: params.client,
+            model: params.model,
+            context_length: params.context_length,
+            tools_defs: params.tools_defs,
+            tool_protocol: protocol,
+            text_tools: params.text_tools,
+            tools_list: params.tools_list,
+            disabled_tools: &disabled,
+            messages: &mut messages,
+            tool_log: &mut tool_log,
+            mode: CHILD_MODE,
+            undo_stack: None,
+            message_usage: MessageUsage::default(),
+            pinned_files: &[],
+            pinned_messages: &[],
+            working_dir: params.working_dir,
+            tool_timeouts: params.tool_timeouts,
+            modes: params.modes,
+            streaming: params.streaming,
+            prompt_cache: params.prompt_cache,
+            routing: params.routing,
+            generation: &generation,
+            // The parent turn reports completion and budget events.
+            webhook_urls: &[],
+            post_hooks: &[],
+            auto_continue: 0,
+            budget: Some(Budget {
+                max_calls,
+                max_tokens: MAX_TASK_TOKENS,
+            }),
+            spend_limits: params.spend_limits,
+            audit: params.audit,
+        },
+        AgentLoopCallbacks {
+            confirm_destructive: &None,
+            // Progress lines show what the child does; its text and tool queue stay hidden.
+            on_progress: callbacks.on_progress,
+            on_content_chunk: None,
+            on_tool_event: None,
+            cancel_token: callbacks.cancel_token,
+        },
+    ))
+    .await?;
+    match result {
+        ChatResult::Complete {
+            content,
+            message_usage,
+            ..
+        } if !content.trim().is_empty() => Ok((content, message_usage)),
+        ChatResult::Complete { message_usage, .. } => Ok((
+            "Error: the subtask ended without a summary.".to_string(),
+            message_usage,
+        )),
+        // Read-only tools never ask for a confirmation.
+        ChatResult::NeedsConfirmation { action, .. } => Err(ChatError::Other(
+            format!("unexpected confirmation request for {}", action).into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_gets_only_the_allowed_read_only_tools() {
+        let disabled = child_disabled_tools(tools::all(), &["Glob".to_string()], None);
+        for name in ["Bash", "Write", "Edit", "ReplaceAll", "Task", "Glob"] {
+            assert!(disabled.iter().any(|d| d == name), "{} usable", name);
+        }
+        assert!(!disabled.iter().any(|d| d == "Read" || d == "Grep"));
+
+        let allowed = ["grep".to_string()];
+        let disabled = child_disabled_tools(tools::all(), &[], Some(&allowed));
+        assert!(disabled.iter().any(|d| d == "Read"));
+        assert!(!disabled.iter().any(|d| d == "Grep"));
+    }
+
+    #[test]
+    fn budget_keeps_the_last_call_for_the_answer() {
+        let budget = Budget {
+            max_calls: 3,
+            max_tokens: 1_000,
+        };
+        let mut usage = MessageUsage::default();
+        assert!(!budget.is_spent(0, &usage));
+        assert!(!budget.is_spent(1, &usage));
+        assert!(budget.is_spent(2, &usage));
+        usage.prompt_tokens = 1_000;
+        assert!(budget.is_spent(0, &usage));
+    }
+}
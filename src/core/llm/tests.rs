@@ -0,0 +1,644 @@
+//! Smoke tests of the agent loop against the mock server (`core::testing`): streamed and
+//! non-streamed replies, tool call merging, confirmations, cancellation, and structured
+//! output, directly and through the `Agent` facade.
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_openai::config::OpenAIConfig;
+use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::config::{Config, ModelSource, Provider};
+use crate::core::confirm::PendingAction;
+use crate::core::generation::GenerationParams;
+use crate::core::llm::{
+    ChatError, ChatOptions, ChatRequest, ChatResult, ProgressEvent, ToolEvent, chat, chat_resume,
+};
+use crate::core::testing::{MockResponse, MockServer};
+use crate::core::tools;
+use crate::core::workspace::Workspace;
+use crate::{Agent, AgentEvent};
+
+fn config(server: &MockServer, streaming: bool) -> Config {
+    let base_url = server.base_url();
+    Config {
+        openai_config: OpenAIConfig::new()
+            .with_api_base(&base_url)
+            .with_api_key("test"),
+        model_id: "mock/model".to_string(),
+        model_source: ModelSource::Default,
+        base_url,
+        api_key: "test".to_string(),
+        max_conversations: 10,
+        history_retention: Default::default(),
+        show_timestamps: false,
+        show_usage: false,
+        streaming,
+        prompt_cache: true,
+        auto_continue: 0,
+        text_tools: true,
+        disabled_tools: Vec::new(),
+        webhook_urls: Vec::new(),
+        post_hooks: Vec::new(),
+        desktop_notify_after: None,
+        prompt_layout: Default::default(),
+        spend_limits: Default::default(),
+        embedding_model: None,
+        title_model: None,
+        provider: Provider::OpenRouter,
+        denied_paths: Vec::new(),
+        redact_tool_output: true,
+        redact_patterns: Vec::new(),
+        tool_timeouts: Default::default(),
+        gist_token: None,
+        remote: None,
+        openrouter: Default::default(),
+        generation: Default::default(),
+        profile: None,
+        theme: None,
+        network: Default::default(),
+        dry_run: false,
+    }
+}
+
+fn workspace(dir: &tempfile::TempDir) -> Workspace {
+    Workspace {
+        root: dir.path().to_path_buf(),
+        working_dir: dir.path().to_path_buf(),
+        project_type: None,
+        agent_md: None,
+        context_files: Vec::new(),
+        git_context: None,
+    }
+}
+
+/// Run a turn with `prompt` in `mode`, without confirmation callback.
+async fn run(
+    config: &Config,
+    workspace: &Workspace,
+    mode: &str,
+    prompt: &str,
+    options: ChatOptions,
+) -> Result<ChatResult, ChatError> {
+    chat(ChatRequest {
+        config,
+        model: &config.model_id,
+        prompt,
+        images: &[],
+        mode,
+        context_length: 100_000,
+        confirm_destructive: None,
+        previous_messages: None,
+        options,
+        workspace,
+        tools_list: tools::all(),
+        tools_defs: tools::definitions(),
+        undo_stack: None,
+        pinned_files: &[],
+        pinned_messages: &[],
+        generation: &GenerationParams::default(),
+    })
+    .await
+}
+
+fn complete(result: Result<ChatResult, ChatError>) -> (String, Vec<String>, Vec<Value>) {
+    match result {
+        Ok(ChatResult::Complete {
+            content,
+            tool_log,
+            messages,
+            ..
+        }) => (content, tool_log, messages),
+        other => panic!("expected a complete turn, got {:?}", other),
+    }
+}
+
+/// Content of the tool result message answering `id`.
+fn tool_result<'a>(messages: &'a [Value], id: &str) -> &'a str {
+    messages
+        .iter()
+        .find(|m| m["role"] == "tool" && m["tool_call_id"] == id)
+        .and_then(|m| m["content"].as_str())
+        .unwrap_or_else(|| panic!("no result for tool call {}", id))
+}
+
+#[tokio::test]
+async fn streamed_reply_reaches_the_caller_in_chunks() {
+    let server = MockServer::start(vec![MockResponse::text(
+        "Hello from the mock model, streamed in pieces.",
+    )])
+    .unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    let chunks = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&chunks);
+    let options = ChatOptions {
+        on_content_chunk: Some(Box::new(move |c| sink.lock().unwrap().push(c.to_string()))),
+        ..Default::default()
+    };
+
+    let (content, tool_log, messages) = complete(
+        run(
+            &config(&server, true),
+            &workspace(&dir),
+            "Ask",
+            "Say hello",
+            options,
+        )
+        .await,
+    );
+    assert_eq!(content, "Hello from the mock model, streamed in pieces.");
+    assert!(tool_log.is_empty());
+    let chunks = chunks.lock().unwrap();
+    assert!(chunks.len() > 1, "chunks: {:?}", chunks);
+    assert_eq!(chunks.concat(), content);
+    assert_eq!(messages.last().unwrap()["role"], "assistant");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["stream"], true);
+    assert_eq!(requests[0]["model"], "mock/model");
+    let last = requests[0]["messages"].as_array().unwrap().last().unwrap();
+    assert_eq!(last["content"], "Say hello");
+}
+
+#[tokio::test]
+async fn streamed_tool_call_deltas_are_merged_and_run_in_order() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("notes.txt"), "first line\nsecond line\n").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[
+            ("call_read", "Read", json!({ "file_path": "notes.txt" })),
+            (
+                "call_grep",
+                "Grep",
+                json!({ "pattern": "second", "path": "." }),
+            ),
+        ]),
+        MockResponse::text("The file has two lines."),
+    ])
+    .unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let options = ChatOptions {
+        on_tool_event: Some(Box::new(move |e| sink.lock().unwrap().push(e.clone()))),
+        ..Default::default()
+    };
+
+    let (content, tool_log, messages) = complete(
+        run(
+            &config(&server, true),
+            &workspace(&dir),
+            "Ask",
+            "What is in notes.txt?",
+            options,
+        )
+        .await,
+    );
+    assert_eq!(content, "The file has two lines.");
+    assert_eq!(tool_log.len(), 2, "tool log: {:?}", tool_log);
+    assert!(tool_log[0].starts_with("→ Read"), "{}", tool_log[0]);
+    assert!(tool_log[1].starts_with("→ Grep"), "{}", tool_log[1]);
+    assert!(tool_result(&messages, "call_read").contains("second line"));
+    assert!(tool_result(&messages, "call_grep").contains("notes.txt"));
+
+    let events = events.lock().unwrap();
+    let ToolEvent::Queued(queued) = &events[0] else {
+        panic!("expected the calls to be queued first, got {:?}", events[0]);
+    };
+    let ids: Vec<&str> = queued.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, vec!["call_read", "call_grep"]);
+    let finished = events
+        .iter()
+        .filter(|e| matches!(e, ToolEvent::Finished { success: true, .. }))
+        .count();
+    assert_eq!(finished, 2);
+
+    // The second request carries the merged calls and their results.
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    let sent = requests[1]["messages"].as_array().unwrap();
+    let assistant = sent.iter().find(|m| m["tool_calls"].is_array()).unwrap();
+    assert_eq!(
+        assistant["tool_calls"][0]["function"]["arguments"],
+        json!({ "file_path": "notes.txt" }).to_string()
+    );
+    assert_eq!(assistant["tool_calls"][1]["function"]["name"], "Grep");
+}
+
+#[tokio::test]
+async fn secrets_in_tool_results_are_masked_before_they_are_sent() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("deploy.conf"),
+        "HOST=db.internal\nDB_PASSWORD=hunter22\n",
+    )
+    .unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[("c1", "Read", json!({ "file_path": "deploy.conf" }))]),
+        MockResponse::text("The database is db.internal."),
+    ])
+    .unwrap();
+
+    let (_, _, messages) = complete(
+        run(
+            &config(&server, true),
+            &workspace(&dir),
+            "Ask",
+            "Where is the database?",
+            ChatOptions::default(),
+        )
+        .await,
+    );
+    let result = tool_result(&messages, "c1");
+    assert!(result.contains("HOST=db.internal"), "{}", result);
+    assert!(result.contains("DB_PASSWORD=[REDACTED]"), "{}", result);
+    let sent = server.requests()[1].to_string();
+    assert!(!sent.contains("hunter22"), "{}", sent);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn post_hooks_rewrite_and_annotate_the_reply() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::TempDir::new().unwrap();
+    // Echoes the prompt it received, and signs the reply.
+    let hook = dir.path().join("sign.sh");
+    fs::write(
+        &hook,
+        r#"#!/bin/sh
+prompt=$(sed -n 's/.*"prompt":"\([^"]*\)".*/\1/p')
+printf '{"content": "Signed reply", "annotations": ["saw %s"], "block_save": "draft"}' "$prompt"
+"#,
+    )
+    .unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    let server = MockServer::start(vec![MockResponse::text("Original reply")]).unwrap();
+    let mut config = config(&server, true);
+    config.post_hooks = vec![hook.to_string_lossy().into_owned()];
+
+    let result = run(
+        &config,
+        &workspace(&dir),
+        "Ask",
+        "Say hello",
+        ChatOptions::default(),
+    )
+    .await;
+    match result {
+        Ok(ChatResult::Complete {
+            content,
+            annotations,
+            save_blocked,
+            messages,
+            ..
+        }) => {
+            assert_eq!(content, "Signed reply");
+            assert_eq!(annotations, ["sign.sh: saw Say hello"]);
+            assert_eq!(save_blocked.as_deref(), Some("draft (sign.sh)"));
+            // The model's own words stay in the API history.
+            assert_eq!(messages.last().unwrap()["content"], "Original reply");
+        }
+        other => panic!("expected a complete turn, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn streamed_tool_call_is_previewed_before_it_runs() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let content = "line\n".repeat(600);
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[(
+            "c1",
+            "Write",
+            json!({ "file_path": "out.txt", "content": content }),
+        )]),
+        MockResponse::text("Written."),
+    ])
+    .unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let options = ChatOptions {
+        on_progress: Some(Box::new(move |e| sink.lock().unwrap().push(e.clone()))),
+        ..Default::default()
+    };
+
+    let (content_reply, _, _) = complete(
+        run(
+            &config(&server, true),
+            &workspace(&dir),
+            "Build",
+            "Write out.txt",
+            options,
+        )
+        .await,
+    );
+    assert_eq!(content_reply, "Written.");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+        content
+    );
+
+    let events = events.lock().unwrap();
+    let ran = events
+        .iter()
+        .position(|e| matches!(e, ProgressEvent::Tool { .. }))
+        .unwrap();
+    let preparing: Vec<&ProgressEvent> = events[..ran]
+        .iter()
+        .filter(|e| matches!(e, ProgressEvent::PreparingTool { .. }))
+        .collect();
+    assert!(preparing.len() >= 2, "events: {:?}", events);
+    let last = preparing.last().unwrap().to_string();
+    assert!(
+        last.starts_with("Preparing Write: out.txt (") && last.ends_with(" KB so far)"),
+        "{}",
+        last
+    );
+}
+
+#[tokio::test]
+async fn non_streamed_tool_calls_run_too() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn main() {}\n").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[("c1", "ListDir", json!({ "path": "." }))]),
+        MockResponse::text("One file."),
+    ])
+    .unwrap();
+
+    let (content, _, messages) = complete(
+        run(
+            &config(&server, false),
+            &workspace(&dir),
+            "Ask",
+            "List the files",
+            ChatOptions::default(),
+        )
+        .await,
+    );
+    assert_eq!(content, "One file.");
+    assert!(tool_result(&messages, "c1").contains("a.rs"));
+    assert_eq!(server.requests()[0]["stream"], false);
+}
+
+#[tokio::test]
+async fn task_call_runs_a_child_loop_and_returns_its_summary() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn main() {}\n").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[(
+            "t1",
+            "Task",
+            json!({ "description": "Find main", "prompt": "Where is main?", "max_turns": 2 }),
+        )]),
+        MockResponse::tool_calls(&[("c1", "Read", json!({ "file_path": "a.rs" }))]),
+        MockResponse::text("main is in a.rs:1."),
+        MockResponse::text("Done."),
+    ])
+    .unwrap();
+
+    let (content, _, messages) = complete(
+        run(
+            &config(&server, true),
+            &workspace(&dir),
+            "Build",
+            "Find main",
+            ChatOptions::default(),
+        )
+        .await,
+    );
+    assert_eq!(content, "Done.");
+    assert_eq!(tool_result(&messages, "t1"), "main is in a.rs:1.");
+    // The child's messages stay out of the parent conversation.
+    assert!(!messages.iter().any(|m| m["tool_call_id"] == "c1"));
+
+    let requests = server.requests();
+    let offered = |request: &Value| -> Vec<String> {
+        request["tools"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t["function"]["name"].as_str().map(String::from))
+            .collect()
+    };
+    let child = offered(&requests[1]);
+    assert!(child.contains(&"Read".to_string()));
+    for name in ["Write", "Bash", "Task"] {
+        assert!(!child.contains(&name.to_string()), "{} offered", name);
+    }
+    assert_eq!(requests[1]["messages"][1]["content"], "Where is main?");
+    // Second and last call of the child's budget: no tools.
+    assert!(offered(&requests[2]).is_empty());
+    assert!(offered(&requests[3]).contains(&"Task".to_string()));
+}
+
+#[tokio::test]
+async fn destructive_command_waits_for_confirmation_then_resumes() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let target = dir.path().join("old.log");
+    fs::write(&target, "x").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[("c1", "Bash", json!({ "command": "rm old.log" }))]),
+        MockResponse::text("Removed."),
+    ])
+    .unwrap();
+    let config = config(&server, true);
+
+    let result = run(
+        &config,
+        &workspace(&dir),
+        "Build",
+        "Delete old.log",
+        ChatOptions::default(),
+    )
+    .await;
+    let Ok(ChatResult::NeedsConfirmation { action, state }) = result else {
+        panic!("expected a confirmation, got {:?}", result);
+    };
+    assert_eq!(action, PendingAction::Command("rm old.log".to_string()));
+    assert!(target.exists(), "nothing runs before the confirmation");
+
+    let (content, _, messages) = complete(
+        chat_resume(
+            &config,
+            &config.model_id,
+            100_000,
+            *state,
+            true,
+            tools::all(),
+            ChatOptions::default(),
+        )
+        .await,
+    );
+    assert_eq!(content, "Removed.");
+    assert!(!target.exists());
+    assert_eq!(messages.iter().filter(|m| m["role"] == "tool").count(), 1);
+}
+
+#[tokio::test]
+async fn declined_command_is_reported_to_the_model() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let target = dir.path().join("keep.txt");
+    fs::write(&target, "x").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[("c1", "Bash", json!({ "command": "rm keep.txt" }))]),
+        MockResponse::text("Understood, I left it."),
+    ])
+    .unwrap();
+    let config = config(&server, true);
+    let workspace = workspace(&dir);
+
+    let result = chat(ChatRequest {
+        config: &config,
+        model: &config.model_id,
+        prompt: "Delete keep.txt",
+        images: &[],
+        mode: "Build",
+        context_length: 100_000,
+        confirm_destructive: Some(Box::new(|_| false)),
+        previous_messages: None,
+        options: ChatOptions::default(),
+        workspace: &workspace,
+        tools_list: tools::all(),
+        tools_defs: tools::definitions(),
+        undo_stack: None,
+        pinned_files: &[],
+        pinned_messages: &[],
+        generation: &GenerationParams::default(),
+    })
+    .await;
+    let (content, _, messages) = complete(result);
+    assert_eq!(content, "Understood, I left it.");
+    assert!(target.exists());
+    assert!(tool_result(&messages, "c1").contains("cancel"));
+}
+
+#[tokio::test]
+async fn cancellation_aborts_a_pending_request() {
+    let server = MockServer::start(vec![
+        MockResponse::text("Too late.").delayed(Duration::from_secs(10)),
+    ])
+    .unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        canceller.cancel();
+    });
+
+    let started = Instant::now();
+    let result = run(
+        &config(&server, true),
+        &workspace(&dir),
+        "Ask",
+        "Take your time",
+        ChatOptions {
+            cancel_token: Some(token),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert!(matches!(result, Err(ChatError::Cancelled)), "{:?}", result);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn api_errors_end_the_turn() {
+    let server = MockServer::start(vec![MockResponse::error(400, "Invalid request")]).unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+
+    let result = run(
+        &config(&server, false),
+        &workspace(&dir),
+        "Ask",
+        "Hi",
+        ChatOptions::default(),
+    )
+    .await;
+    let err = result.expect_err("the turn should fail");
+    assert!(err.to_string().contains("Invalid request"), "{}", err);
+    assert_eq!(server.remaining(), 0);
+}
+
+#[tokio::test]
+async fn agent_keeps_the_conversation_and_asks_its_confirm_handler() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let target = dir.path().join("keep.txt");
+    fs::write(&target, "x").unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::tool_calls(&[("c1", "Bash", json!({ "command": "rm keep.txt" }))]),
+        MockResponse::text("Understood, I left it."),
+        MockResponse::text("You asked me to delete keep.txt."),
+    ])
+    .unwrap();
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (asked_by, events_seen) = (Arc::clone(&asked), Arc::clone(&events));
+    let mut agent = Agent::builder(config(&server, true), workspace(&dir))
+        .with_confirm_handler(move |action| {
+            asked_by.lock().unwrap().push(action.to_string());
+            false
+        })
+        .on_event(move |event| {
+            if let AgentEvent::Tool(ToolEvent::Queued(calls)) = event {
+                events_seen.lock().unwrap().push(calls[0].name.clone());
+            }
+        })
+        .build();
+
+    let reply = agent.send("Delete keep.txt").await.unwrap();
+    assert_eq!(reply.content, "Understood, I left it.");
+    assert!(target.exists());
+    assert_eq!(*asked.lock().unwrap(), ["rm keep.txt"]);
+    assert_eq!(*events.lock().unwrap(), ["Bash"]);
+
+    let reply = agent.send("What did I ask?").await.unwrap();
+    assert_eq!(reply.content, "You asked me to delete keep.txt.");
+    let last_request = server.requests().pop().unwrap();
+    let sent = last_request["messages"].to_string();
+    assert!(sent.contains("Delete keep.txt") && sent.contains("What did I ask?"));
+    assert_eq!(agent.messages().last().unwrap()["role"], "assistant");
+
+    agent.reset();
+    assert!(agent.messages().is_empty());
+}
+
+#[tokio::test]
+async fn structured_reply_is_asked_again_until_it_matches_the_schema() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let server = MockServer::start(vec![
+        MockResponse::text("Sure! The answer is 42."),
+        MockResponse::text(r#"{"answer": "42"}"#),
+        MockResponse::text("```json\n{\"answer\": 42}\n```"),
+    ])
+    .unwrap();
+    let schema = json!({
+        "type": "object",
+        "properties": {"answer": {"type": "integer"}},
+        "required": ["answer"],
+    });
+    let mut agent = Agent::builder(config(&server, true), workspace(&dir)).build();
+
+    let reply = agent
+        .send_structured("What is six times seven?", schema.clone())
+        .await
+        .unwrap();
+    assert_eq!(reply.structured, Some(json!({"answer": 42})));
+    assert!(reply.annotations.is_empty());
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[0]["response_format"]["json_schema"]["schema"],
+        schema
+    );
+    let retry = requests[2]["messages"].as_array().unwrap().last().unwrap();
+    assert!(
+        retry["content"]
+            .as_str()
+            .unwrap()
+            .contains("$.answer: expected integer, got string")
+    );
+}
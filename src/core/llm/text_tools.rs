@@ -0,0 +1,254 @@
+//! Text tool protocol for models without native tool calling.
+//!
+//! Tools are described in the system prompt, and the model asks for a call with a fenced
+//! `tool_call` block holding `{"name": ..., "arguments": {...}}`. Calls parsed from the reply
+//! are stored and run like native ones; each request turns the earlier calls and their
+//! results back into text, so the conversation stays in the native format.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Value, json};
+
+/// Language of the fenced blocks holding tool calls.
+const FENCE_LANG: &str = "tool_call";
+
+/// How tools are offered to the model during a turn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToolProtocol {
+    /// Tool definitions sent with the request; the model answers with tool calls.
+    #[default]
+    Native,
+    /// Tools described in the system prompt; the model writes fenced `tool_call` blocks.
+    Text,
+    /// No tools: the model answers from the conversation alone.
+    Off,
+}
+
+impl ToolProtocol {
+    /// Protocol for a model without tool calling: text calls when `text_tools` is set
+    /// (`MY_OPEN_CLAUDE_TEXT_TOOLS`), else no tools.
+    pub fn fallback(text_tools: bool) -> Self {
+        if text_tools {
+            ToolProtocol::Text
+        } else {
+            ToolProtocol::Off
+        }
+    }
+}
+
+/// System prompt section describing the tools in `tools_defs` and how to call them.
+fn instructions(tools_defs: &[Value]) -> String {
+    let mut text = format!(
+        "## Tools\n\
+         This model has no native tool calling. To use a tool, write a fenced code block with \
+         the language `{lang}` holding one JSON object, for example:\n\n\
+         ```{lang}\n{{\"name\": \"Read\", \"arguments\": {{\"file_path\": \"src/main.rs\"}}}}\n```\n\n\
+         Write one block per call. End your reply right after the blocks: the results come \
+         back in the next message. When no tool is needed, answer without any block.\n\n\
+         Available tools:",
+        lang = FENCE_LANG
+    );
+    for def in tools_defs {
+        let function = &def["function"];
+        text.push_str(&format!(
+            "\n- {}: {}\n  Arguments (JSON schema): {}",
+            function["name"].as_str().unwrap_or_default(),
+            function["description"].as_str().unwrap_or_default(),
+            function["parameters"]
+        ));
+    }
+    text
+}
+
+/// A tool call written as a fenced block.
+fn call_block(name: &str, arguments: &str) -> String {
+    let arguments: Value = serde_json::from_str(arguments).unwrap_or_else(|_| json!({}));
+    format!(
+        "```{}\n{}\n```",
+        FENCE_LANG,
+        json!({ "name": name, "arguments": arguments })
+    )
+}
+
+/// Messages for one API call with the text protocol: the tools are described in the system
+/// message, assistant tool calls become fenced blocks, and tool results become user messages
+/// (consecutive results are merged into one).
+pub(super) fn request_messages(messages: &[Value], tools_defs: &[Value]) -> Vec<Value> {
+    let mut out: Vec<Value> = Vec::with_capacity(messages.len() + 1);
+    let mut call_names: Vec<(String, String)> = Vec::new();
+    for message in messages {
+        match message["role"].as_str() {
+            Some("assistant") if message["tool_calls"].is_array() => {
+                let mut parts: Vec<String> = message["content"]
+                    .as_str()
+                    .filter(|c| !c.trim().is_empty())
+                    .map(|c| vec![c.to_string()])
+                    .unwrap_or_default();
+                for call in message["tool_calls"].as_array().into_iter().flatten() {
+                    let name = call["function"]["name"].as_str().unwrap_or_default();
+                    let arguments = call["function"]["arguments"].as_str().unwrap_or("{}");
+                    call_names.push((
+                        call["id"].as_str().unwrap_or_default().to_string(),
+                        name.to_string(),
+                    ));
+                    parts.push(call_block(name, arguments));
+                }
+                out.push(json!({ "role": "assistant", "content": parts.join("\n\n") }));
+            }
+            Some("tool") => {
+                let id = message["tool_call_id"].as_str().unwrap_or_default();
+                let name = call_names
+                    .iter()
+                    .find(|(call_id, _)| call_id == id)
+                    .map_or("tool", |(_, name)| name.as_str());
+                let result = format!(
+                    "Result of {}:\n{}",
+                    name,
+                    message["content"].as_str().unwrap_or_default()
+                );
+                match out.last_mut() {
+                    Some(last) if last["role"] == "user" && last["tool_results"] == true => {
+                        let merged = format!("{}\n\n{}", last["content"].as_str().unwrap(), result);
+                        last["content"] = json!(merged);
+                    }
+                    _ => {
+                        out.push(json!({ "role": "user", "content": result, "tool_results": true }))
+                    }
+                }
+            }
+            _ => out.push(message.clone()),
+        }
+    }
+    // The marker only groups results above; it is not part of the API format.
+    for message in &mut out {
+        if let Some(obj) = message.as_object_mut() {
+            obj.remove("tool_results");
+        }
+    }
+
+    let section = instructions(tools_defs);
+    match out.first_mut() {
+        Some(system) if system["role"] == "system" && system["content"].is_string() => {
+            let content = format!("{}\n\n{}", system["content"].as_str().unwrap(), section);
+            system["content"] = json!(content);
+        }
+        _ => out.insert(0, json!({ "role": "system", "content": section })),
+    }
+    out
+}
+
+/// Tool calls written as fenced `tool_call` blocks in `content`, in the native format
+/// (arguments as a JSON string), with the reply text before the first call. Blocks that do
+/// not hold a JSON object with a `name` are not calls and stay in the text.
+pub(super) fn parse_tool_calls(content: &str) -> (String, Vec<Value>) {
+    let id_base = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let mut calls = Vec::new();
+    let mut text_end = content.len();
+    let mut offset = 0;
+    let mut lines = content.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+        if line.trim() != format!("```{}", FENCE_LANG) {
+            continue;
+        }
+        let mut body = String::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            offset += inner.len();
+            if inner.trim() == "```" {
+                closed = true;
+                break;
+            }
+            body.push_str(inner);
+        }
+        let parsed: Option<Value> = serde_json::from_str(body.trim()).ok();
+        let Some(name) = parsed
+            .as_ref()
+            .and_then(|call| call["name"].as_str())
+            .filter(|name| !name.is_empty())
+        else {
+            continue;
+        };
+        let arguments = match parsed.as_ref().map(|call| &call["arguments"]) {
+            Some(Value::Object(args)) => Value::Object(args.clone()).to_string(),
+            // Some models send the arguments already encoded as a string.
+            Some(Value::String(args)) => args.clone(),
+            _ => "{}".to_string(),
+        };
+        calls.push(json!({
+            "id": format!("text_{:x}_{}", id_base, calls.len()),
+            "type": "function",
+            "function": { "name": name, "arguments": arguments },
+        }));
+        text_end = text_end.min(start);
+        if !closed {
+            break;
+        }
+    }
+    (content[..text_end].trim_end().to_string(), calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tool_calls_reads_blocks_and_keeps_text_before_them() {
+        let content = "Let me look.\n\n```tool_call\n{\"name\": \"Read\", \"arguments\": {\"file_path\": \"a.rs\"}}\n```\n```tool_call\n{\"name\": \"Grep\", \"arguments\": \"{\\\"pattern\\\": \\\"x\\\"}\"}\n```\nResult: (made up)";
+        let (text, calls) = parse_tool_calls(content);
+        assert_eq!(text, "Let me look.");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["function"]["name"], "Read");
+        let args: Value =
+            serde_json::from_str(calls[0]["function"]["arguments"].as_str().unwrap()).unwrap();
+        assert_eq!(args["file_path"], "a.rs");
+        assert_eq!(calls[1]["function"]["arguments"], "{\"pattern\": \"x\"}");
+        assert_ne!(calls[0]["id"], calls[1]["id"]);
+    }
+
+    #[test]
+    fn parse_tool_calls_ignores_other_and_invalid_blocks() {
+        let content = "```json\n{\"name\": \"Read\"}\n```\n```tool_call\nnot json\n```";
+        let (text, calls) = parse_tool_calls(content);
+        assert!(calls.is_empty());
+        assert_eq!(text, content);
+    }
+
+    #[test]
+    fn request_messages_turn_calls_and_results_into_text() {
+        let defs = vec![json!({"type": "function", "function": {
+            "name": "Read", "description": "Read a file",
+            "parameters": {"type": "object"}
+        }})];
+        let messages = vec![
+            json!({"role": "system", "content": "Be brief."}),
+            json!({"role": "user", "content": "show a.rs"}),
+            json!({"role": "assistant", "content": "", "tool_calls": [
+                {"id": "c1", "type": "function", "function": {"name": "Read", "arguments": "{\"file_path\":\"a.rs\"}"}},
+                {"id": "c2", "type": "function", "function": {"name": "Read", "arguments": "{\"file_path\":\"b.rs\"}"}}
+            ]}),
+            json!({"role": "tool", "tool_call_id": "c1", "content": "fn a() {}"}),
+            json!({"role": "tool", "tool_call_id": "c2", "content": "fn b() {}"}),
+        ];
+        let out = request_messages(&messages, &defs);
+        assert_eq!(out.len(), 4);
+        let system = out[0]["content"].as_str().unwrap();
+        assert!(system.starts_with("Be brief.\n\n## Tools"));
+        assert!(system.contains("- Read: Read a file"));
+        let assistant = out[2]["content"].as_str().unwrap();
+        assert!(assistant.starts_with("```tool_call\n{\"arguments\":{\"file_path\":\"a.rs\"}"));
+        assert!(out[2].get("tool_calls").is_none());
+        assert_eq!(out[3]["role"], "user");
+        assert_eq!(
+            out[3]["content"],
+            "Result of Read:\nfn a() {}\n\nResult of Read:\nfn b() {}"
+        );
+        assert!(out[3].get("tool_results").is_none());
+        // The stored round trip parses back to the same calls.
+        let (_, calls) = parse_tool_calls(assistant);
+        assert_eq!(calls.len(), 2);
+    }
+}
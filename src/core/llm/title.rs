@@ -0,0 +1,114 @@
+//! Conversation titles: a short request to a cheap model summarizing the first exchange.
+
+use serde_json::json;
+
+use crate::core::config::Config;
+use crate::core::{spend, stats};
+
+use super::cache;
+use super::error::ChatError;
+use super::stream::parse_usage;
+
+/// Characters of the prompt and of the answer sent to the title model.
+const EXCERPT_CHARS: usize = 2000;
+/// Longest title kept, in words.
+const MAX_TITLE_WORDS: usize = 8;
+
+const TITLE_INSTRUCTIONS: &str = "Write a concise title of 4 to 8 words for this conversation \
+between a user and a coding assistant. Reply with the title only: no quotes, no trailing \
+punctuation, same language as the user.";
+
+/// Generate a title for a conversation from its first prompt and answer, with
+/// `config.title_model`. Returns None when title generation is disabled or the model gave
+/// nothing usable.
+pub async fn generate_title(
+    config: &Config,
+    prompt: &str,
+    answer: &str,
+) -> Result<Option<String>, ChatError> {
+    let Some(model) = config.title_model.as_deref() else {
+        return Ok(None);
+    };
+    let excerpt = |s: &str| s.trim().chars().take(EXCERPT_CHARS).collect::<String>();
+    let client = config.chat_client();
+    let mut body = json!({
+            "model": model,
+            "messages": [
+                {"role": "system", "content": TITLE_INSTRUCTIONS},
+                {
+                    "role": "user",
+                    "content": format!("User:\n{}\n\nAssistant:\n{}", excerpt(prompt), excerpt(answer)),
+                },
+            ],
+            "max_tokens": 30,
+            "stream": false,
+            "usage": { "include": true },
+    });
+    config.openrouter.apply(&mut body);
+    let response = client.complete(body).await?;
+    if let Some(err) = response.get("error") {
+        let msg = err
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown error");
+        return Err(ChatError::from_api_message(msg));
+    }
+    if let Some(usage) = parse_usage(&response) {
+        if let Err(e) = spend::record(usage.cost) {
+            log::warn!("Failed to record spend: {}", e);
+        }
+        cache::record(model, &usage);
+        stats::record_request(model, &usage);
+    }
+    let raw = response["choices"][0]["message"]["content"]
+        .as_str()
+        .unwrap_or_default();
+    Ok(clean_title(raw))
+}
+
+/// First line of the model's reply without a "Title:" label, quotes, markdown emphasis, or
+/// trailing punctuation, cut to `MAX_TITLE_WORDS` words.
+fn clean_title(raw: &str) -> Option<String> {
+    let decoration = |c: char| {
+        c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '*' | '#' | '“' | '”' | '.' | '!')
+    };
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.trim_matches(decoration);
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line)
+        .trim_matches(decoration);
+    let title = line
+        .split_whitespace()
+        .take(MAX_TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clean_title;
+
+    #[test]
+    fn clean_title_strips_decoration() {
+        assert_eq!(
+            clean_title("\"Fix flaky login test.\"\n").as_deref(),
+            Some("Fix flaky login test")
+        );
+        assert_eq!(
+            clean_title("\n**Title:** Rust borrow checker help").as_deref(),
+            Some("Rust borrow checker help")
+        );
+        assert_eq!(
+            clean_title("Title: Add   retry  to webhook delivery").as_deref(),
+            Some("Add retry to webhook delivery")
+        );
+        assert_eq!(
+            clean_title("one two three four five six seven eight nine ten").as_deref(),
+            Some("one two three four five six seven eight")
+        );
+        assert_eq!(clean_title("  \n \"\" "), None);
+    }
+}
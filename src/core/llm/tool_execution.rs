@@ -1,22 +1,68 @@
 //! Execute a single tool call from the agent loop.
 
 use serde_json::{Value, json};
-
-use crate::core::confirm::ConfirmDestructive;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::audit::{self, ToolCallAudit, TurnOrigin};
+use crate::core::config::Config;
+use crate::core::confirm::{ConfirmDestructive, PendingAction};
+use crate::core::dry_run;
+use crate::core::permissions::{self, PathAccess};
+use crate::core::redact;
+use crate::core::remote;
+use crate::core::stats;
+use crate::core::suggest;
 use crate::core::tools;
+use crate::core::trace::{self, TraceEvent};
 
 use super::ChatError;
 use super::ChatResult;
 use super::ConfirmState;
 use super::undo;
+use super::{ProgressEvent, ToolCallInfo, ToolEvent, tool_log_line};
 
 /// Tool names whose file_path argument should be captured for undo before execution.
-const UNDO_CAPTURE_TOOLS: &[&str] = &["Write", "Edit"];
+pub(super) const UNDO_CAPTURE_TOOLS: &[&str] = &["Write", "Edit"];
 
 const ASK_MODE_DISABLED: &str = "Ask mode: file modification and command execution are disabled. Use Read, Grep, ListDir, and Glob tools to explore, then respond with an explanation.";
 
+const PLAN_MODE_DISABLED: &str = "Plan mode: file modification and command execution are disabled. Explore with Read, Grep, ListDir, and Glob, record the steps with TaskPlan, then present the plan.";
+
 const INIT_FILE_ALREADY_WRITTEN: &str = "Already written this session. Do not call Write again. Provide your brief summary to the user now.";
 
+/// Modes changing what tools with side effects do, set per conversation (each TUI tab has its
+/// own) and passed to every tool call of its turns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolModes {
+    /// Simulate tools with side effects (see `core::dry_run`).
+    pub dry_run: bool,
+    /// Stage Edit calls for the user's review (see `core::suggest`).
+    pub suggest: bool,
+}
+
+impl ToolModes {
+    /// Modes a conversation starts with: dry run with `--dry-run`, edits applied directly.
+    pub fn from_config(config: &Config) -> Self {
+        ToolModes {
+            dry_run: config.dry_run,
+            suggest: false,
+        }
+    }
+
+    /// True when calls to `tool` are simulated (dry-run mode, tools with side effects).
+    pub fn simulates(self, tool: &dyn tools::Tool) -> bool {
+        self.dry_run && !tool.is_read_only()
+    }
+
+    /// True when calls to `tool` are staged instead of run (suggest mode, outside dry runs).
+    pub fn stages(self, tool: &dyn tools::Tool) -> bool {
+        self.suggest && !self.dry_run && tool.stages_suggestions()
+    }
+}
+
 /// Truncate a tool result string to the given max bytes, appending a notice.
 pub(crate) fn truncate_tool_output(output: String, max_bytes: usize) -> String {
     if output.len() <= max_bytes {
@@ -35,11 +81,107 @@ pub(crate) fn truncate_tool_output(output: String, max_bytes: usize) -> String {
     )
 }
 
+/// Mask the secrets in the result of `tool` before it is added to the messages (see
+/// `redact::redact_tool_output`), and log what was masked.
+pub(crate) fn redact_tool_result(tool: &str, result: String) -> String {
+    let redacted = redact::redact_tool_output(&result);
+    if redacted.kinds.is_empty() {
+        return result;
+    }
+    log::info!(
+        "Masked in the {} result: {}",
+        tool,
+        redact::summary(&redacted.kinds)
+    );
+    trace::record(TraceEvent::Redaction {
+        tool: tool.to_string(),
+        kinds: redacted.kinds,
+    });
+    redacted.text
+}
+
 /// Interaction mode: "Ask" = explanations only (no write/bash), "Build" = all tools.
 pub fn is_ask_mode(mode: &str) -> bool {
     mode.eq_ignore_ascii_case("ask")
 }
 
+/// Interaction mode: "Plan" = read-only tools plus TaskPlan (no write/bash).
+pub fn is_plan_mode(mode: &str) -> bool {
+    mode.eq_ignore_ascii_case("plan")
+}
+
+/// True when `name` is listed in the globally disabled tools (case-insensitive).
+pub fn is_tool_disabled(name: &str, disabled_tools: &[String]) -> bool {
+    disabled_tools.iter().any(|d| d.eq_ignore_ascii_case(name))
+}
+
+/// Tool definitions usable in `mode`: tools blocked by the mode (e.g. Write and Bash in Ask)
+/// or disabled by configuration are not sent, so the model does not attempt them.
+pub fn enabled_tool_definitions(
+    tools_defs: &[Value],
+    tools_list: &[Box<dyn tools::Tool>],
+    mode: &str,
+    disabled_tools: &[String],
+) -> Vec<Value> {
+    tools_defs
+        .iter()
+        .filter(|def| {
+            let name = def["function"]["name"].as_str().unwrap_or_default();
+            tools_list
+                .iter()
+                .find(|t| t.name() == name)
+                .is_some_and(|t| blocked_tool_message(t.as_ref(), mode, disabled_tools).is_none())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Message returned to the model instead of running the tool, or None when the tool may run
+/// in this mode and is neither disabled nor unavailable in this configuration.
+pub(super) fn blocked_tool_message(
+    tool: &dyn tools::Tool,
+    mode: &str,
+    disabled_tools: &[String],
+) -> Option<String> {
+    if !tool.available() {
+        Some(format!(
+            "Error: the {} tool is not available in this configuration.",
+            tool.name()
+        ))
+    } else if is_tool_disabled(tool.name(), disabled_tools) {
+        Some(format!(
+            "Error: the {} tool is disabled by configuration.",
+            tool.name()
+        ))
+    } else if is_ask_mode(mode) && tool.disabled_in_ask_mode() {
+        Some(ASK_MODE_DISABLED.to_string())
+    } else if is_plan_mode(mode) && !tool.allowed_in_plan_mode() {
+        Some(PLAN_MODE_DISABLED.to_string())
+    } else {
+        None
+    }
+}
+
+/// Path permission of a tool call: file tools may not touch denied paths, and writes outside
+/// the workspace need approval. Tools without a path are always allowed.
+fn path_access(tool: &dyn tools::Tool, args: &Value, working_dir: &Path) -> PathAccess {
+    match tool.target_path(args) {
+        Some(path) if !path.is_empty() => {
+            permissions::check(working_dir, &path, !tool.is_read_only())
+        }
+        _ => PathAccess::Allowed,
+    }
+}
+
+/// Message returned to the model for a call to a denied path.
+fn denied_path_message(tool: &dyn tools::Tool, args: &Value, pattern: &str) -> String {
+    format!(
+        "Error: access to '{}' is denied (matches '{}' in MY_OPEN_CLAUDE_DENIED_PATHS). Do not retry with this path.",
+        tool.target_path(args).unwrap_or_default(),
+        pattern
+    )
+}
+
 /// Run a tool and format errors. Logs the underlying error before returning user-facing string.
 pub(crate) fn tool_result_string(res: Result<String, tools::ToolError>, tool_name: &str) -> String {
     match res {
@@ -51,10 +193,80 @@ pub(crate) fn tool_result_string(res: Result<String, tools::ToolError>, tool_nam
     }
 }
 
+/// Like `tool_result_string`, keeping the typed view of a successful result.
+fn tool_output(
+    res: Result<tools::ToolOutput, tools::ToolError>,
+    tool_name: &str,
+) -> tools::ToolOutput {
+    match res {
+        Ok(output) => output,
+        Err(e) => tool_result_string(Err(e), tool_name).into(),
+    }
+}
+
+/// True when a tool result string reports an error (see `tool_result_string`).
+pub(crate) fn is_error_result(result: &str) -> bool {
+    result.starts_with("Error:")
+}
+
+/// Id, name, argument preview, and target path of a tool call. Malformed arguments yield an
+/// empty preview and no path.
+pub(super) fn tool_call_info(
+    tool_call: &Value,
+    tools_list: &[Box<dyn tools::Tool>],
+    working_dir: &Path,
+) -> ToolCallInfo {
+    let function = &tool_call["function"];
+    let name = function["name"].as_str().unwrap_or_default();
+    let tool = tools_list.iter().find(|t| t.name() == name);
+    let args = serde_json::from_str::<Value>(function["arguments"].as_str().unwrap_or("{}")).ok();
+    let (preview, path) = match (tool, args) {
+        (Some(tool), Some(args)) => (
+            tool.args_preview(&args),
+            tool.target_path(&args)
+                .filter(|p| !p.is_empty())
+                .map(|p| tools::resolve_path(working_dir, &p)),
+        ),
+        _ => (String::new(), None),
+    };
+    ToolCallInfo {
+        id: tool_call["id"].as_str().unwrap_or_default().to_string(),
+        name: name.to_string(),
+        preview,
+        path,
+    }
+}
+
+/// Progress event for the start of `tool_call`, the `index`-th of `total` calls.
+pub(super) fn progress_event(
+    tool_call: &Value,
+    tools_list: &[Box<dyn tools::Tool>],
+    working_dir: &Path,
+    modes: ToolModes,
+    index: usize,
+    total: usize,
+) -> ProgressEvent {
+    let info = tool_call_info(tool_call, tools_list, working_dir);
+    let tool = tools_list.iter().find(|t| t.name() == info.name);
+    ProgressEvent::Tool {
+        remote: tool.is_some_and(|t| runs_on_remote(t.as_ref())),
+        dry_run: tool.is_some_and(|t| modes.simulates(t.as_ref())),
+        name: info.name,
+        preview: info.preview,
+        index,
+        total,
+    }
+}
+
+/// True when `tool` runs on the remote host of a remote workspace.
+fn runs_on_remote(tool: &dyn tools::Tool) -> bool {
+    remote::is_active() && tool.runs_remotely()
+}
+
 /// Outcome of executing the Bash tool: either output string or needs user confirmation.
 enum BashOutcome {
-    Output(String),
-    NeedsConfirmation(ConfirmState),
+    Output(tools::ToolOutput),
+    NeedsConfirmation(Box<ConfirmState>),
 }
 
 /// Execute Bash tool with destructive-command confirmation logic.
@@ -67,32 +279,58 @@ fn execute_bash_tool(
 ) -> BashOutcome {
     let command = match args.get("command").and_then(|v| v.as_str()) {
         Some(c) => c,
-        None => return BashOutcome::Output("Error: missing command argument".to_string()),
+        None => {
+            return BashOutcome::Output("Error: missing command argument".to_string().into());
+        }
     };
 
     if !tool.may_need_confirmation(args) {
-        return BashOutcome::Output(tool_result_string(tool.execute(args), tool.name()));
+        return BashOutcome::Output(run_tool(tool, args, ctx));
     }
 
+    let action = PendingAction::Command(command.to_string());
     if let Some(cb) = ctx.confirm_destructive {
-        return if cb(command) {
-            BashOutcome::Output(tool_result_string(tool.execute(args), tool.name()))
+        return if ask(cb, &action, ctx.audit) {
+            BashOutcome::Output(run_tool(tool, args, ctx))
         } else {
-            BashOutcome::Output(
-                "Command cancelled (destructive command not confirmed).".to_string(),
-            )
+            BashOutcome::Output(action.cancelled_message().into())
         };
     }
 
-    BashOutcome::NeedsConfirmation(ConfirmState {
+    BashOutcome::NeedsConfirmation(Box::new(confirm_state(action, id, mode, ctx)))
+}
+
+/// State to resume the loop once the user answers the confirmation for `action`.
+fn confirm_state(
+    action: PendingAction,
+    id: &str,
+    mode: &str,
+    ctx: &ToolCallContext<'_>,
+) -> ConfirmState {
+    ConfirmState {
         messages: std::sync::Arc::clone(ctx.messages),
         tool_log: std::sync::Arc::clone(ctx.tool_log),
         tool_call_id: id.to_string(),
         mode: mode.to_string(),
         tools: ctx.tools_defs.to_vec(),
-        command: command.to_string(),
+        action,
         undo_stack: ctx.undo_stack.clone(),
-    })
+        working_dir: ctx.working_dir.to_path_buf(),
+        // Filled in by the agent loop, which tracks the reply's usage.
+        message_usage: Default::default(),
+        pinned_files: Default::default(),
+        pinned_messages: Default::default(),
+        generation: Default::default(),
+        tool_protocol: Default::default(),
+        audit: ctx.audit.clone(),
+    }
+}
+
+/// Ask `confirm` about `action`, recording the answer in the audit log.
+fn ask(confirm: &ConfirmDestructive, action: &PendingAction, origin: &TurnOrigin) -> bool {
+    let approved = confirm(action);
+    audit::record_confirmation(origin, action, approved);
+    approved
 }
 
 /// Result of executing a read-only tool call (pure, no side effects on shared state).
@@ -100,6 +338,7 @@ pub(super) struct ReadOnlyToolResult {
     pub tool_call_id: String,
     pub log_line: String,
     pub content: String,
+    pub view: Option<tools::ToolView>,
 }
 
 /// Execute a single read-only tool call without mutating shared state.
@@ -109,6 +348,9 @@ pub(super) fn execute_read_only_tool_call(
     tool_call: &Value,
     tools_list: &[Box<dyn tools::Tool>],
     mode: &str,
+    disabled_tools: &[String],
+    working_dir: &Path,
+    tool_timeouts: &tools::ToolTimeouts,
 ) -> Result<ReadOnlyToolResult, ChatError> {
     let id = tool_call["id"].as_str().unwrap_or_default().to_string();
     let function = &tool_call["function"];
@@ -122,18 +364,36 @@ pub(super) fn execute_read_only_tool_call(
 
     let tool_opt = tools_list.iter().find(|t| t.name() == name);
     let args_preview = tool_opt.map(|t| t.args_preview(&args)).unwrap_or_default();
-    let log_line = format!("→ {}: {}", name, args_preview);
-
-    let result = match tool_opt {
-        Some(tool) => {
-            if is_ask_mode(mode) && tool.disabled_in_ask_mode() {
-                ASK_MODE_DISABLED.to_string()
-            } else {
-                tool_result_string(tool.execute(&args), name)
-            }
-        }
-        None => format!("Error: unknown tool '{}'", name),
+    let log_line = tool_log_line(
+        name,
+        &args_preview,
+        tool_opt.is_some_and(|t| runs_on_remote(t.as_ref())),
+        false,
+    );
+
+    let output = match tool_opt {
+        Some(tool) => match blocked_tool_message(tool.as_ref(), mode, disabled_tools) {
+            Some(message) => message.into(),
+            None => match path_access(tool.as_ref(), &args, working_dir) {
+                PathAccess::Denied { pattern } => {
+                    denied_path_message(tool.as_ref(), &args, &pattern).into()
+                }
+                _ => tool_output(
+                    execute_with_timeout(
+                        tool.as_ref(),
+                        &args,
+                        working_dir,
+                        None,
+                        tool_timeouts.for_tool(tool.as_ref()),
+                    ),
+                    name,
+                ),
+            },
+        },
+        None => format!("Error: unknown tool '{}'", name).into(),
     };
+    let tools::ToolOutput { text: result, view } = output;
+    let result = redact_tool_result(name, result);
 
     let result = match tool_opt.and_then(|t| t.output_limit()) {
         Some(limit) => truncate_tool_output(result, limit),
@@ -144,6 +404,7 @@ pub(super) fn execute_read_only_tool_call(
         tool_call_id: id,
         log_line,
         content: result,
+        view,
     })
 }
 
@@ -151,15 +412,190 @@ pub(super) fn execute_read_only_tool_call(
 pub(super) struct ToolCallContext<'a> {
     pub confirm_destructive: &'a Option<ConfirmDestructive>,
     pub tools_defs: &'a [Value],
+    /// Tools disabled by configuration; calls to them are rejected.
+    pub disabled_tools: &'a [String],
     pub messages: &'a mut std::sync::Arc<Vec<Value>>,
     pub tool_log: &'a mut std::sync::Arc<Vec<String>>,
-    pub on_progress: Option<&'a (dyn Fn(&str) + Send + Sync)>,
+    pub on_progress: Option<&'a (dyn Fn(&ProgressEvent) + Send + Sync)>,
+    pub on_tool_event: Option<&'a (dyn Fn(&ToolEvent) + Send + Sync)>,
     /// When set, blocks repeated Write to AGENT.md/AGENTS.md to prevent infinite loops.
     pub init_file_written: Option<&'a mut bool>,
     /// When set, captures file state before Write/Edit for undo support.
     pub undo_batch: Option<&'a mut undo::UndoBatch>,
     /// Shared undo stack for file modifications. Passed through to resumed chats.
     pub undo_stack: Option<undo::SharedUndoStack>,
+    /// When cancelled, a running Bash command is killed.
+    pub cancel_token: Option<&'a CancellationToken>,
+    /// Directory that relative tool paths and Bash commands are resolved against.
+    pub working_dir: &'a Path,
+    /// Time limits of the tools that can be stopped.
+    pub tool_timeouts: &'a tools::ToolTimeouts,
+    /// Dry-run and suggest modes of the conversation.
+    pub modes: ToolModes,
+    /// Index (from 1) of this call and number of calls in the model's response.
+    pub position: (usize, usize),
+    /// Conversation and turn recorded with the call in the audit log.
+    pub audit: &'a TurnOrigin,
+}
+
+/// Run a tool within its time limit, letting it stop early when the request is cancelled.
+fn run_tool(tool: &dyn tools::Tool, args: &Value, ctx: &ToolCallContext<'_>) -> tools::ToolOutput {
+    audited(ctx.audit, tool, args, ctx.working_dir, || {
+        let result = execute_with_timeout(
+            tool,
+            args,
+            ctx.working_dir,
+            ctx.cancel_token,
+            ctx.tool_timeouts.for_tool(tool),
+        );
+        tool_output(result, tool.name())
+    })
+}
+
+/// Run a call of `tool` with `run`, recorded in the audit log when the tool has side effects.
+fn audited(
+    origin: &TurnOrigin,
+    tool: &dyn tools::Tool,
+    args: &Value,
+    working_dir: &Path,
+    run: impl FnOnce() -> tools::ToolOutput,
+) -> tools::ToolOutput {
+    let call = ToolCallAudit::start(origin, tool, args, working_dir);
+    let output = run();
+    if let Some(call) = call {
+        call.finish(!is_error_result(&output.text));
+    }
+    output
+}
+
+/// Execute a tool, cancelling it once `timeout` has passed. A timed-out call returns an
+/// error telling the model how long it ran, instead of the tool's partial result.
+pub(super) fn execute_with_timeout(
+    tool: &dyn tools::Tool,
+    args: &Value,
+    working_dir: &Path,
+    cancel_token: Option<&CancellationToken>,
+    timeout: Option<Duration>,
+) -> Result<tools::ToolOutput, tools::ToolError> {
+    let Some(timeout) = timeout else {
+        let token = cancel_token.cloned().unwrap_or_default();
+        return tool.execute_with_view(args, working_dir, &token);
+    };
+    let token = cancel_token.map_or_else(CancellationToken::new, |t| t.child_token());
+    // The watchdog cancels the call at the deadline, or exits as soon as the call returns
+    // (the sender is dropped).
+    let (done, finished) = mpsc::channel::<()>();
+    let watchdog = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            let timed_out = finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout);
+            if timed_out {
+                token.cancel();
+            }
+            timed_out
+        })
+    };
+    let result = tool.execute_with_view(args, working_dir, &token);
+    drop(done);
+    let timed_out = watchdog.join().unwrap_or(false);
+    if timed_out && !cancel_token.is_some_and(|t| t.is_cancelled()) {
+        return Err(timeout_message(tool.name(), timeout).into());
+    }
+    result
+}
+
+/// Error returned to the model for a call stopped by its time limit.
+fn timeout_message(tool: &str, timeout: Duration) -> String {
+    format!(
+        "{} timed out after {}s and was stopped (no result). Narrow the call (a smaller path, a more specific pattern, a command that exits on its own) or run long commands in the background.",
+        tool,
+        timeout.as_secs()
+    )
+}
+
+/// Result of a tool run outside the agent loop (`tools run`).
+#[derive(Debug, Clone)]
+pub struct StandaloneToolResult {
+    /// Result as the model would receive it (masked and truncated).
+    pub content: String,
+    /// The tool ran without error (false when it was blocked, cancelled, or failed).
+    pub success: bool,
+}
+
+/// Run the tool `name` (case-insensitive) with `args` outside the agent loop, with the checks
+/// of the loop: mode and disabled tools, denied paths, approval through `confirm` of writes
+/// outside the workspace and destructive commands, dry run (when `dry_run` is set), time
+/// limits, masking of secrets, and output limits.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_standalone(
+    name: &str,
+    args: &Value,
+    mode: &str,
+    disabled_tools: &[String],
+    working_dir: &Path,
+    tool_timeouts: &tools::ToolTimeouts,
+    dry_run: bool,
+    confirm: &ConfirmDestructive,
+) -> StandaloneToolResult {
+    let Some(tool) = tools::all()
+        .iter()
+        .find(|t| t.name().eq_ignore_ascii_case(name))
+    else {
+        return StandaloneToolResult {
+            content: format!("Error: unknown tool '{}'", name),
+            success: false,
+        };
+    };
+    let tool = tool.as_ref();
+    let origin = TurnOrigin::new(None);
+    // Whether the tool ran (or was simulated): blocked and cancelled calls are failures.
+    let run = || {
+        let output = audited(&origin, tool, args, working_dir, || {
+            let output =
+                execute_with_timeout(tool, args, working_dir, None, tool_timeouts.for_tool(tool));
+            tool_output(output, tool.name())
+        });
+        (output, true)
+    };
+    let approved = |action: PendingAction| {
+        if ask(confirm, &action, &origin) {
+            run()
+        } else {
+            (action.cancelled_message().into(), false)
+        }
+    };
+    let access = path_access(tool, args, working_dir);
+    let (output, ran): (tools::ToolOutput, bool) =
+        if let Some(message) = blocked_tool_message(tool, mode, disabled_tools) {
+            (message.into(), false)
+        } else if let PathAccess::Denied { pattern } = &access {
+            (denied_path_message(tool, args, pattern).into(), false)
+        } else if dry_run && !tool.is_read_only() {
+            let simulated = dry_run::simulated_result(tool, args, working_dir);
+            (tool_result_string(simulated, tool.name()).into(), true)
+        } else if let PathAccess::NeedsApproval { path } = access {
+            approved(PendingAction::OutsideWorkspace {
+                tool: tool.name().to_string(),
+                path: path.display().to_string(),
+                args: args.clone(),
+            })
+        } else if tool.may_need_confirmation(args) {
+            match args.get("command").and_then(|v| v.as_str()) {
+                Some(command) => approved(PendingAction::Command(command.to_string())),
+                None => ("Error: missing command argument".to_string().into(), false),
+            }
+        } else {
+            run()
+        };
+    let content = redact_tool_result(tool.name(), output.text);
+    let content = match tool.output_limit() {
+        Some(limit) => truncate_tool_output(content, limit),
+        None => content,
+    };
+    StandaloneToolResult {
+        success: ran && !is_error_result(&content),
+        content,
+    }
 }
 
 /// Execute a single tool call. Returns `Some(ChatResult::NeedsConfirmation)` if destructive and needs confirmation.
@@ -179,31 +615,72 @@ pub(super) fn execute_tool_call(
         source: e,
     })?;
 
-    let args_preview = tools_list
-        .iter()
-        .find(|t| t.name() == name)
-        .map(|t| t.args_preview(&args))
-        .unwrap_or_default();
-    let log_line = format!("→ {}: {}", name, args_preview);
-    std::sync::Arc::make_mut(ctx.tool_log).push(log_line.clone());
+    let (index, total) = ctx.position;
+    let event = progress_event(
+        tool_call,
+        tools_list,
+        ctx.working_dir,
+        ctx.modes,
+        index,
+        total,
+    );
+    std::sync::Arc::make_mut(ctx.tool_log).push(event.to_string());
     if let Some(ref progress) = ctx.on_progress {
-        progress(&log_line);
+        progress(&event);
+    }
+    if let Some(cb) = ctx.on_tool_event {
+        cb(&ToolEvent::Started { id: id.clone() });
     }
+    let started = Instant::now();
 
     let tool_opt = tools_list.iter().find(|t| t.name() == name);
 
-    // Capture file state before Write/Edit for undo support.
+    // Capture file state before Write/Edit for undo support (nothing changes in dry run, and
+    // staged suggestions are captured when the review applies them).
     if UNDO_CAPTURE_TOOLS.contains(&name)
+        && !ctx.modes.dry_run
+        && !tool_opt.is_some_and(|t| ctx.modes.stages(t.as_ref()))
         && let Some(file_path) = args.get("file_path").and_then(|v| v.as_str())
         && let Some(ref mut batch) = ctx.undo_batch
     {
-        batch.capture(file_path);
+        batch.capture(&tools::resolve_path(ctx.working_dir, file_path).to_string_lossy());
     }
 
-    let result = match tool_opt {
+    let output: tools::ToolOutput = match tool_opt {
         Some(tool) => {
-            if is_ask_mode(mode) && tool.disabled_in_ask_mode() {
-                ASK_MODE_DISABLED.to_string()
+            let access = path_access(tool.as_ref(), &args, ctx.working_dir);
+            if let Some(message) = blocked_tool_message(tool.as_ref(), mode, ctx.disabled_tools) {
+                message.into()
+            } else if let PathAccess::Denied { pattern } = &access {
+                denied_path_message(tool.as_ref(), &args, pattern).into()
+            } else if ctx.modes.simulates(tool.as_ref()) {
+                // Nothing runs: no approval needed.
+                tool_result_string(
+                    dry_run::simulated_result(tool.as_ref(), &args, ctx.working_dir),
+                    name,
+                )
+                .into()
+            } else if ctx.modes.stages(tool.as_ref()) {
+                // Nothing is written until the user reviews it: no approval needed.
+                tool_result_string(
+                    suggest::stage(&ctx.audit.turn, tool.as_ref(), &args, ctx.working_dir),
+                    name,
+                )
+                .into()
+            } else if let PathAccess::NeedsApproval { path } = access {
+                let action = PendingAction::OutsideWorkspace {
+                    tool: name.to_string(),
+                    path: path.display().to_string(),
+                    args: args.clone(),
+                };
+                match ctx.confirm_destructive {
+                    Some(cb) if ask(cb, &action, ctx.audit) => run_tool(tool.as_ref(), &args, ctx),
+                    Some(_) => action.cancelled_message().into(),
+                    None => {
+                        let state = Box::new(confirm_state(action.clone(), &id, mode, ctx));
+                        return Ok(Some(ChatResult::NeedsConfirmation { action, state }));
+                    }
+                }
             } else if tool
                 .is_init_file_target(args.get("file_path").and_then(|v| v.as_str()).unwrap_or(""))
             {
@@ -214,27 +691,37 @@ pub(super) fn execute_tool_call(
                             "tool_call_id": id,
                             "content": INIT_FILE_ALREADY_WRITTEN
                         }));
+                        if let Some(cb) = ctx.on_tool_event {
+                            cb(&ToolEvent::Finished {
+                                id,
+                                success: false,
+                                duration: started.elapsed(),
+                                view: None,
+                            });
+                        }
                         return Ok(None);
                     }
                     **written = true;
                 }
-                tool_result_string(tool.execute(&args), name)
+                run_tool(tool.as_ref(), &args, ctx)
             } else if tool.may_need_confirmation(&args) {
                 match execute_bash_tool(tool.as_ref(), &args, &id, mode, ctx) {
                     BashOutcome::Output(s) => s,
                     BashOutcome::NeedsConfirmation(state) => {
                         return Ok(Some(ChatResult::NeedsConfirmation {
-                            command: state.command.clone(),
+                            action: state.action.clone(),
                             state,
                         }));
                     }
                 }
             } else {
-                tool_result_string(tool.execute(&args), name)
+                run_tool(tool.as_ref(), &args, ctx)
             }
         }
-        None => format!("Error: unknown tool '{}'", name),
+        None => format!("Error: unknown tool '{}'", name).into(),
     };
+    let tools::ToolOutput { text: result, view } = output;
+    let result = redact_tool_result(name, result);
 
     // Truncate large tool outputs to stay within context budget.
     let result = match tool_opt.and_then(|t| t.output_limit()) {
@@ -242,6 +729,25 @@ pub(super) fn execute_tool_call(
         None => result,
     };
 
+    let duration = started.elapsed();
+    let cancelled = ctx.cancel_token.is_some_and(|t| t.is_cancelled());
+    let success = !cancelled && !is_error_result(&result);
+    stats::record_tool(name);
+    trace::record(TraceEvent::Tool {
+        name: name.to_string(),
+        duration_ms: trace::millis(duration),
+        success,
+        output_bytes: result.len(),
+    });
+    if let Some(cb) = ctx.on_tool_event {
+        cb(&ToolEvent::Finished {
+            id: id.clone(),
+            success,
+            duration,
+            view,
+        });
+    }
+
     std::sync::Arc::make_mut(ctx.messages).push(json!({
         "role": "tool",
         "tool_call_id": id,
@@ -253,6 +759,7 @@ pub(super) fn execute_tool_call(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn is_ask_mode_true() {
@@ -267,6 +774,141 @@ mod tests {
         assert!(!is_ask_mode("build"));
     }
 
+    #[test]
+    fn is_plan_mode_matches_case_insensitive() {
+        assert!(is_plan_mode("Plan"));
+        assert!(is_plan_mode("plan"));
+        assert!(!is_plan_mode("Ask"));
+    }
+
+    #[test]
+    fn blocked_tool_message_by_mode() {
+        let bash = tools::BashTool;
+        let read = tools::ReadTool;
+        let plan = tools::TaskPlanTool;
+        assert!(blocked_tool_message(&bash, "Plan", &[]).is_some());
+        assert!(blocked_tool_message(&read, "Plan", &[]).is_none());
+        assert!(blocked_tool_message(&plan, "Plan", &[]).is_none());
+        assert!(blocked_tool_message(&plan, "Ask", &[]).is_some());
+        assert!(blocked_tool_message(&bash, "Build", &[]).is_none());
+    }
+
+    #[test]
+    fn blocked_tool_message_disabled_by_config() {
+        let disabled = vec!["bash".to_string()];
+        let msg = blocked_tool_message(&tools::BashTool, "Build", &disabled).unwrap();
+        assert!(msg.contains("disabled by configuration"));
+        assert!(blocked_tool_message(&tools::ReadTool, "Build", &disabled).is_none());
+    }
+
+    #[test]
+    fn blocked_tool_message_unavailable_tool() {
+        // No embedding model is configured in tests.
+        let msg = blocked_tool_message(&tools::SemanticSearchTool, "Build", &[]).unwrap();
+        assert!(msg.contains("not available"));
+    }
+
+    #[test]
+    fn tool_modes_simulate_side_effects_and_stage_edits_outside_dry_runs() {
+        let off = ToolModes::default();
+        assert!(!off.simulates(&tools::EditTool));
+        assert!(!off.stages(&tools::EditTool));
+
+        let dry_run = ToolModes {
+            dry_run: true,
+            suggest: true,
+        };
+        assert!(dry_run.simulates(&tools::EditTool));
+        assert!(dry_run.simulates(&tools::BashTool));
+        assert!(!dry_run.simulates(&tools::ReadTool));
+        assert!(
+            !dry_run.stages(&tools::EditTool),
+            "dry run takes precedence"
+        );
+
+        let suggest = ToolModes {
+            dry_run: false,
+            suggest: true,
+        };
+        assert!(suggest.stages(&tools::EditTool));
+        assert!(!suggest.stages(&tools::BashTool));
+        assert!(!suggest.simulates(&tools::EditTool));
+    }
+
+    #[test]
+    fn execute_standalone_runs_the_tool_with_the_loop_checks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello").unwrap();
+        let confirm: ConfirmDestructive = Box::new(|_| false);
+        let run = |name: &str, args: Value, mode: &str| {
+            execute_standalone(
+                name,
+                &args,
+                mode,
+                &[],
+                dir.path(),
+                &tools::ToolTimeouts::default(),
+                false,
+                &confirm,
+            )
+        };
+        let read = run("read", json!({"file_path": "notes.txt"}), "Ask");
+        assert!(read.success, "{}", read.content);
+        assert!(read.content.contains("hello"));
+
+        let write = run(
+            "Write",
+            json!({"file_path": "out.txt", "content": "x"}),
+            "Ask",
+        );
+        assert!(!write.success);
+        assert_eq!(write.content, ASK_MODE_DISABLED);
+        assert!(!dir.path().join("out.txt").exists());
+
+        let unknown = run("Nope", json!({}), "Build");
+        assert!(!unknown.success);
+        assert_eq!(unknown.content, "Error: unknown tool 'Nope'");
+    }
+
+    /// Number of tools available in this configuration.
+    fn available_count() -> usize {
+        tools::all().iter().filter(|t| t.available()).count()
+    }
+
+    #[test]
+    fn enabled_tool_definitions_filters_disabled() {
+        let disabled = vec!["Bash".to_string(), "Write".to_string()];
+        let defs = enabled_tool_definitions(tools::definitions(), tools::all(), "Build", &disabled);
+        assert_eq!(defs.len(), available_count() - 2);
+        assert!(defs.iter().all(|d| d["function"]["name"] != "Bash"));
+    }
+
+    #[test]
+    fn enabled_tool_definitions_filters_by_mode() {
+        let names = |mode: &str| -> Vec<String> {
+            enabled_tool_definitions(tools::definitions(), tools::all(), mode, &[])
+                .iter()
+                .map(|d| {
+                    d["function"]["name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        };
+        let build = names("Build");
+        assert_eq!(build.len(), available_count());
+        let ask = names("Ask");
+        assert!(ask.contains(&"Read".to_string()));
+        assert!(
+            !ask.iter()
+                .any(|n| n == "Bash" || n == "Write" || n == "TaskPlan")
+        );
+        let plan = names("Plan");
+        assert!(plan.contains(&"TaskPlan".to_string()));
+        assert!(!plan.iter().any(|n| n == "Bash" || n == "Edit"));
+    }
+
     #[test]
     fn truncate_tool_output_under_limit() {
         let s = "short output";
@@ -287,4 +929,58 @@ mod tests {
         let result = truncate_tool_output(s, 5);
         assert!(result.contains("truncated"));
     }
+
+    #[test]
+    fn is_error_result_detects_prefix() {
+        assert!(is_error_result("Error: file not found"));
+        assert!(!is_error_result("fn main() {}"));
+    }
+
+    #[test]
+    fn tool_call_info_extracts_preview() {
+        let tc = json!({
+            "id": "call_1",
+            "function": {"name": "Read", "arguments": "{\"file_path\": \"src/main.rs\"}"}
+        });
+        let info = tool_call_info(&tc, tools::all(), Path::new("/repo"));
+        assert_eq!(info.id, "call_1");
+        assert_eq!(info.name, "Read");
+        assert!(info.preview.contains("src/main.rs"));
+        assert_eq!(info.path, Some(PathBuf::from("/repo/src/main.rs")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn execute_with_timeout_stops_slow_command() {
+        let args = json!({"command": "sleep 30"});
+        let started = Instant::now();
+        let err = execute_with_timeout(
+            &tools::BashTool,
+            &args,
+            Path::new("."),
+            None,
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(err.to_string().starts_with("Bash timed out after 1s"));
+
+        let out = execute_with_timeout(
+            &tools::BashTool,
+            &json!({"command": "echo done"}),
+            Path::new("."),
+            None,
+            Some(Duration::from_secs(30)),
+        )
+        .unwrap();
+        assert_eq!(out.text.trim(), "done");
+    }
+
+    #[test]
+    fn tool_call_info_malformed_args_empty_preview() {
+        let tc = json!({"id": "x", "function": {"name": "Read", "arguments": "{oops"}});
+        let info = tool_call_info(&tc, tools::all(), Path::new("."));
+        assert_eq!(info.preview, "");
+        assert_eq!(info.path, None);
+    }
 }
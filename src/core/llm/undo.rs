@@ -3,9 +3,11 @@
 //! Before each Write or Edit tool execution, the original file content is captured.
 //! The user can then undo the last batch of changes (one agent loop iteration).
 
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
 use std::path::PathBuf;
+
+use crate::core::remote::fs;
 use std::sync::{Arc, Mutex};
 
 /// Snapshot of files before a batch of tool calls (one agent loop iteration).
@@ -23,7 +25,15 @@ impl UndoBatch {
         if self.snapshots.contains_key(&path_buf) {
             return; // already captured in this batch
         }
-        let original = fs::read_to_string(&path_buf).ok();
+        let original = match fs::read_to_string(&path_buf) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            // Unreadable (or the remote host is unreachable): undo would delete the file.
+            Err(e) => {
+                log::warn!("Undo will not restore {}: {}", path_buf.display(), e);
+                return;
+            }
+        };
         self.snapshots.insert(path_buf, original);
     }
 
@@ -65,12 +75,10 @@ impl UndoStack {
                 }
                 None => {
                     // File did not exist before — remove it.
-                    if path.exists() {
-                        if let Err(e) = fs::remove_file(path) {
-                            errors.push(format!("{}: {}", path.display(), e));
-                        } else {
-                            deleted += 1;
-                        }
+                    match fs::remove_file(path) {
+                        Ok(()) => deleted += 1,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                        Err(e) => errors.push(format!("{}: {}", path.display(), e)),
                     }
                 }
             }
@@ -83,6 +91,22 @@ impl UndoStack {
         })
     }
 
+    /// Files written or edited so far whose content differs from before their first
+    /// change (a blocked or failed write is captured too), sorted.
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut originals: BTreeMap<&PathBuf, &Option<String>> = BTreeMap::new();
+        for batch in &self.batches {
+            for (path, original) in &batch.snapshots {
+                originals.entry(path).or_insert(original);
+            }
+        }
+        originals
+            .into_iter()
+            .filter(|(path, original)| fs::read_to_string(path).ok() != **original)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
     /// Number of undo batches available.
     #[allow(dead_code)]
     pub fn len(&self) -> usize {
@@ -174,6 +198,26 @@ mod tests {
         assert_eq!(fs::read_to_string(&path).unwrap(), "original content");
     }
 
+    #[test]
+    fn changed_paths_skips_files_left_as_they_were() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let edited = dir.path().join("edited.txt");
+        let untouched = dir.path().join("untouched.txt");
+        let never_created = dir.path().join("never_created.txt");
+        fs::write(&edited, "before").expect("write");
+        fs::write(&untouched, "same").expect("write");
+
+        let mut batch = UndoBatch::default();
+        for path in [&edited, &untouched, &never_created] {
+            batch.capture(&path.to_string_lossy());
+        }
+        fs::write(&edited, "after").expect("write");
+        let mut stack = UndoStack::default();
+        stack.push_batch(batch);
+
+        assert_eq!(stack.changed_paths(), vec![edited]);
+    }
+
     #[test]
     fn capture_and_undo_new_file() {
         let dir = tempfile::tempdir().expect("temp dir");
@@ -19,10 +19,102 @@ pub fn extract_content(msg: &Value) -> Option<String> {
     None
 }
 
+/// Index and text of the last user message in an API message list, if any.
+pub fn last_user_message(messages: &[Value]) -> Option<(usize, String)> {
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, m)| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+        .map(|(i, m)| (i, extract_content(m).unwrap_or_default()))
+}
+
+/// A tool call that returned an error: tool name, JSON arguments, and the error text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolFailure {
+    pub tool: String,
+    pub arguments: String,
+    pub error: String,
+}
+
+/// The last tool call of the current turn (after the last user message) whose result is an
+/// error ("Error: ..."), if any.
+pub fn last_tool_failure(messages: &[Value]) -> Option<ToolFailure> {
+    let start = last_user_message(messages).map_or(0, |(i, _)| i);
+    let turn = &messages[start..];
+    let (pos, result) = turn.iter().enumerate().rev().find(|(_, m)| {
+        m.get("role").and_then(|r| r.as_str()) == Some("tool")
+            && extract_content(m).is_some_and(|c| c.starts_with("Error:"))
+    })?;
+    let call_id = result.get("tool_call_id").and_then(|id| id.as_str());
+    let call = turn[..pos]
+        .iter()
+        .rev()
+        .filter_map(|m| m.get("tool_calls").and_then(|c| c.as_array()))
+        .flatten()
+        .find(|c| c.get("id").and_then(|id| id.as_str()) == call_id);
+    let function = |key: &str| {
+        call.and_then(|c| c.get("function"))
+            .and_then(|f| f.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    Some(ToolFailure {
+        tool: function("name"),
+        arguments: function("arguments"),
+        error: extract_content(result).unwrap_or_default(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn last_user_message_skips_assistant_and_tool() {
+        let messages = vec![
+            serde_json::json!({"role": "system", "content": "sys"}),
+            serde_json::json!({"role": "user", "content": "first"}),
+            serde_json::json!({"role": "assistant", "content": "a"}),
+            serde_json::json!({"role": "user", "content": "second"}),
+            serde_json::json!({"role": "assistant", "content": "", "tool_calls": []}),
+            serde_json::json!({"role": "tool", "tool_call_id": "x", "content": "out"}),
+            serde_json::json!({"role": "assistant", "content": "done"}),
+        ];
+        assert_eq!(
+            last_user_message(&messages),
+            Some((3, "second".to_string()))
+        );
+        assert_eq!(last_user_message(&messages[..1]), None);
+    }
+
+    #[test]
+    fn last_tool_failure_finds_the_failed_call_of_the_turn() {
+        let call = |id: &str, name: &str, args: &str| serde_json::json!({"id": id, "type": "function", "function": {"name": name, "arguments": args}});
+        let messages = vec![
+            serde_json::json!({"role": "user", "content": "run the tests"}),
+            serde_json::json!({"role": "assistant", "content": "", "tool_calls": [
+                call("a", "Read", r#"{"path":"Cargo.toml"}"#),
+                call("b", "Bash", r#"{"command":"cargo test"}"#),
+            ]}),
+            serde_json::json!({"role": "tool", "tool_call_id": "a", "content": "[package]"}),
+            serde_json::json!({"role": "tool", "tool_call_id": "b", "content": "Error: exit status 101"}),
+            serde_json::json!({"role": "assistant", "content": "The tests fail."}),
+        ];
+        assert_eq!(
+            last_tool_failure(&messages),
+            Some(ToolFailure {
+                tool: "Bash".to_string(),
+                arguments: r#"{"command":"cargo test"}"#.to_string(),
+                error: "Error: exit status 101".to_string(),
+            })
+        );
+        let mut next_turn = messages;
+        next_turn.push(serde_json::json!({"role": "user", "content": "thanks"}));
+        assert_eq!(last_tool_failure(&next_turn), None);
+    }
+
     #[test]
     fn extract_content_string_direct() {
         let msg = serde_json::json!({"role": "user", "content": "Hello world"});
@@ -1,19 +1,48 @@
 pub mod api_key;
 pub mod app;
+pub mod audit;
+pub mod batch;
 pub mod cli;
+pub mod command_rules;
 pub mod commands;
 pub mod config;
 pub mod confirm;
+pub mod crash;
 pub mod credits;
+pub mod desktop;
+pub mod diff;
+pub mod dry_run;
+pub mod file_tree;
+pub mod fuzzy;
+pub mod generation;
+pub mod git_commit;
 pub mod history;
+pub mod hooks;
+pub mod index;
 pub mod install;
 pub mod llm;
 pub mod message;
 pub mod models;
+pub mod network;
+pub mod notify;
 pub mod paths;
+pub mod permissions;
 pub mod persistence;
+pub mod pins;
+pub mod profiles;
+pub mod redact;
+pub mod remote;
+pub mod share;
+pub mod spend;
+pub mod stats;
+pub mod suggest;
 pub mod templates;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod text;
 pub mod tools;
+pub mod trace;
 pub mod update;
 pub mod util;
+pub mod watch;
 pub mod workspace;
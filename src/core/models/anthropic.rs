@@ -0,0 +1,58 @@
+//! Models of the Anthropic API (`GET /v1/models`).
+
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::core::config::Config;
+use crate::core::llm::ANTHROPIC_API_VERSION;
+
+use super::info::ModelInfo;
+
+/// Context window of Claude models (the list does not report it).
+const CLAUDE_CONTEXT_LENGTH: u64 = 200_000;
+
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    #[serde(default)]
+    data: Vec<Model>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Model {
+    id: String,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+/// Models available to the API key, sorted by name. Every Claude model accepts tools.
+pub async fn list_models(config: &Config) -> Result<Vec<ModelInfo>, Box<dyn Error + Send + Sync>> {
+    let client = config.network.client()?;
+    let response: ModelsResponse = client
+        .get(format!(
+            "{}/v1/models?limit=1000",
+            config.base_url().trim_end_matches('/')
+        ))
+        .header("x-api-key", config.api_key())
+        .header("anthropic-version", ANTHROPIC_API_VERSION)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let mut models: Vec<ModelInfo> = response
+        .data
+        .into_iter()
+        .map(|m| ModelInfo {
+            name: m.display_name.unwrap_or_else(|| m.id.clone()),
+            id: m.id,
+            context_length: CLAUDE_CONTEXT_LENGTH,
+            prompt_price: None,
+            completion_price: None,
+            cache_read_price: None,
+            cache_write_price: None,
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
@@ -1,6 +1,7 @@
-//! 24h cache for OpenRouter models list.
+//! 24h cache for the models list (one file per provider).
 
 use super::info::ModelInfo;
+use crate::core::config::Provider;
 use crate::core::paths;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -16,7 +17,12 @@ struct CachedModels {
 }
 
 fn cache_path() -> Option<std::path::PathBuf> {
-    paths::cache_dir().map(|d| d.join("models.json"))
+    let file = match super::provider() {
+        Provider::OpenRouter => "models.json",
+        Provider::Ollama => "ollama_models.json",
+        Provider::Anthropic => "anthropic_models.json",
+    };
+    paths::cache_dir().map(|d| d.join(file))
 }
 
 /// Load cached models if fresh (< 24h). Returns None if cache miss or expired.
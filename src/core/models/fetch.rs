@@ -1,16 +1,17 @@
-//! Fetch available models from OpenRouter (filtered by tool support).
+//! Fetch available models from OpenRouter (filtered by tool support), a local Ollama server,
+//! or the Anthropic API.
 
 use openrouter_rs::{OpenRouterClient, types::SupportedParameters};
 use std::error::Error;
 use std::io;
 
-use crate::core::config::Config;
+use crate::core::config::{Config, Provider};
 use crate::core::util;
 
 use super::cache;
-use super::info::ModelInfo;
+use super::info::{ModelInfo, parse_price};
 
-/// Filter models by query (case-insensitive match on id or name).
+/// Filter models by query (fuzzy match on id or name), best matches first.
 pub fn filter_models<'a>(models: &'a [ModelInfo], query: &str) -> Vec<&'a ModelInfo> {
     util::filter_by_query(models, query, |m| (m.id.as_str(), m.name.as_str()))
 }
@@ -27,6 +28,39 @@ pub fn resolve_model_display_name(model_id: &str) -> String {
         .unwrap_or_else(|| model_id.to_string())
 }
 
+/// Errors when validating a model ID supplied on the command line.
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("Unknown model '{0}'. Run `models` to list available models.")]
+    Unknown(String),
+}
+
+/// Check that `model_id` is present in the given models list.
+fn validate_model_id_in(models: &[ModelInfo], model_id: &str) -> Result<(), ModelError> {
+    if models.iter().any(|m| m.id == model_id) {
+        Ok(())
+    } else {
+        Err(ModelError::Unknown(model_id.to_string()))
+    }
+}
+
+/// Validate a model ID against the models cache.
+/// Accepts any ID when the cache is missing or expired (cannot validate offline).
+pub fn validate_model_id(model_id: &str) -> Result<(), ModelError> {
+    match cache::load_cached_models() {
+        Some(models) => validate_model_id_in(&models, model_id),
+        None => {
+            log::debug!("No models cache; skipping validation of '{}'", model_id);
+            Ok(())
+        }
+    }
+}
+
+/// Cached info (name, context length, prices) of a model; None when it is not in the cache.
+pub fn resolve_model_info(model_id: &str) -> Option<ModelInfo> {
+    cache::load_cached_models().and_then(|models| models.into_iter().find(|m| m.id == model_id))
+}
+
 /// Resolve model ID to its context length. Falls back to default if not found.
 pub fn resolve_context_length(model_id: &str) -> u64 {
     cache::load_cached_models()
@@ -41,14 +75,33 @@ pub fn resolve_context_length(model_id: &str) -> u64 {
 
 /// Fetch models that support tool calling, suitable for the agent.
 /// Uses 24h cache; sorts alphabetically by name.
+///
+/// With Ollama, lists every installed model (chats with models lacking tool support run
+/// without tools) and always asks the server: the list is local and changes with `ollama pull`.
+/// The cache is still written so context lengths and names resolve offline.
 pub async fn fetch_models_with_tools(
     config: &Config,
 ) -> Result<Vec<ModelInfo>, Box<dyn Error + Send + Sync>> {
+    if config.provider == Provider::Ollama {
+        let models = super::ollama::list_models(config.ollama_host()).await?;
+        if let Err(e) = cache::save_models_to_cache(&models) {
+            log::warn!("Failed to save models cache: {}", e);
+        }
+        return Ok(models);
+    }
     if let Some(mut cached) = cache::load_cached_models() {
         cached.sort_by(|a, b| a.name.cmp(&b.name));
         return Ok(cached);
     }
 
+    if config.provider == Provider::Anthropic {
+        let models = super::anthropic::list_models(config).await?;
+        if let Err(e) = cache::save_models_to_cache(&models) {
+            log::warn!("Failed to save models cache: {}", e);
+        }
+        return Ok(models);
+    }
+
     let client = OpenRouterClient::builder()
         .api_key(config.api_key())
         .build()?;
@@ -73,6 +126,10 @@ pub async fn fetch_models_with_tools(
                 id: m.id,
                 name: m.name,
                 context_length,
+                prompt_price: parse_price(&m.pricing.prompt),
+                completion_price: parse_price(&m.pricing.completion),
+                cache_read_price: m.pricing.input_cache_read.as_deref().and_then(parse_price),
+                cache_write_price: m.pricing.input_cache_write.as_deref().and_then(parse_price),
             }
         })
         .collect();
@@ -84,3 +141,33 @@ pub async fn fetch_models_with_tools(
     }
     Ok(model_infos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str) -> ModelInfo {
+        ModelInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            context_length: super::super::info::DEFAULT_CONTEXT_LENGTH,
+            prompt_price: None,
+            completion_price: None,
+            cache_read_price: None,
+            cache_write_price: None,
+        }
+    }
+
+    #[test]
+    fn validate_model_id_in_known() {
+        let models = vec![model("anthropic/claude-haiku-4.5"), model("openai/gpt-4o")];
+        assert!(validate_model_id_in(&models, "openai/gpt-4o").is_ok());
+    }
+
+    #[test]
+    fn validate_model_id_in_unknown() {
+        let models = vec![model("anthropic/claude-haiku-4.5")];
+        let err = validate_model_id_in(&models, "nope/model").unwrap_err();
+        assert!(err.to_string().contains("nope/model"));
+    }
+}
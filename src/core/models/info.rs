@@ -13,8 +13,90 @@ pub struct ModelInfo {
     /// Maximum context window in tokens. Defaults to 128k when missing (backward compat).
     #[serde(default = "default_context_length")]
     pub context_length: u64,
+    /// USD per prompt token; None when unknown or variable (e.g. routers).
+    #[serde(default)]
+    pub prompt_price: Option<f64>,
+    /// USD per completion token; None when unknown or variable.
+    #[serde(default)]
+    pub completion_price: Option<f64>,
+    /// USD per prompt token read from the provider's prompt cache; None when not cached.
+    #[serde(default)]
+    pub cache_read_price: Option<f64>,
+    /// USD per prompt token written to the provider's prompt cache; None when free or unknown.
+    #[serde(default)]
+    pub cache_write_price: Option<f64>,
+}
+
+impl ModelInfo {
+    /// Prompt and completion prices per million tokens, e.g. "$3/$15", "free", or "—".
+    pub fn price_label(&self) -> String {
+        match (self.prompt_price, self.completion_price) {
+            (Some(p), Some(c)) if p == 0.0 && c == 0.0 => "free".to_string(),
+            (Some(p), Some(c)) => format!(
+                "${}/${}",
+                format_price(p * 1_000_000.0),
+                format_price(c * 1_000_000.0)
+            ),
+            _ => "—".to_string(),
+        }
+    }
+}
+
+/// Up to three decimals, without trailing zeros ("3", "0.15", "0.075").
+fn format_price(amount: f64) -> String {
+    let s = format!("{:.3}", amount);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Context length for display, e.g. "128k" or "1.0M".
+pub fn format_context_length(n: u64) -> String {
+    if n >= 1_000_000 {
+        format!("{:.1}M", n as f64 / 1_000_000.0)
+    } else if n >= 1_000 {
+        format!("{}k", n / 1_000)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Price per token from the API (a decimal string); negative values mean "variable".
+pub(super) fn parse_price(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|p| p.is_finite() && *p >= 0.0)
 }
 
 fn default_context_length() -> u64 {
     DEFAULT_CONTEXT_LENGTH
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_label_per_million_tokens() {
+        let mut model = ModelInfo {
+            id: "a/b".to_string(),
+            name: "B".to_string(),
+            context_length: DEFAULT_CONTEXT_LENGTH,
+            prompt_price: parse_price("0.000003"),
+            completion_price: parse_price("0.000015"),
+            cache_read_price: None,
+            cache_write_price: None,
+        };
+        assert_eq!(model.price_label(), "$3/$15");
+        model.prompt_price = parse_price("0.00000015");
+        model.completion_price = parse_price("0.000000075");
+        assert_eq!(model.price_label(), "$0.15/$0.075");
+        model.prompt_price = parse_price("0");
+        model.completion_price = parse_price("0");
+        assert_eq!(model.price_label(), "free");
+        model.prompt_price = parse_price("-1");
+        assert_eq!(model.price_label(), "—");
+        assert_eq!(format_context_length(200_000), "200k");
+        assert_eq!(format_context_length(1_048_576), "1.0M");
+    }
+}
@@ -1,10 +1,62 @@
 //! Model discovery, filtering, and caching.
 
+use std::sync::Mutex;
+
+use crate::core::config::{Config, Provider};
+
+mod anthropic;
 mod cache;
 mod fetch;
 mod info;
+mod ollama;
 
 pub use fetch::{
     fetch_models_with_tools, filter_models, resolve_context_length, resolve_model_display_name,
+    resolve_model_info, validate_model_id,
 };
-pub use info::ModelInfo;
+pub use info::{ModelInfo, format_context_length};
+
+/// Provider whose models the cache and lookups refer to; set at startup and on profile switch.
+static PROVIDER: Mutex<Option<Provider>> = Mutex::new(None);
+
+/// Use the models of `config.provider` for the cache, validation, and lookups
+/// (called at startup and when switching profiles; OpenRouter when never called).
+pub fn configure(config: &Config) {
+    *PROVIDER.lock().unwrap_or_else(|e| e.into_inner()) = Some(config.provider);
+}
+
+/// Models whose provider rejected tool definitions during this session.
+static NO_TOOLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn provider() -> Provider {
+    PROVIDER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .unwrap_or(Provider::OpenRouter)
+}
+
+/// Remember that `model` rejected tool definitions, so later turns skip the failed request.
+pub fn mark_tools_unsupported(model: &str) {
+    let mut models = NO_TOOLS.lock().unwrap_or_else(|e| e.into_inner());
+    if !models.iter().any(|m| m == model) {
+        models.push(model.to_string());
+    }
+}
+
+/// Whether `model` accepts tools. True with OpenRouter, where only tool-capable models are
+/// listed, and with Anthropic, unless a request was rejected (see `mark_tools_unsupported`);
+/// asked to the server with Ollama, whose local models may not.
+pub async fn supports_tools(config: &Config, model: &str) -> bool {
+    if NO_TOOLS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|m| m == model)
+    {
+        return false;
+    }
+    match config.provider {
+        Provider::OpenRouter | Provider::Anthropic => true,
+        Provider::Ollama => ollama::supports_tools(config.ollama_host(), model).await,
+    }
+}
@@ -0,0 +1,181 @@
+//! Local Ollama models: discovery (`GET /api/tags`) and per-model details (`POST /api/show`).
+//!
+//! Chat requests go through Ollama's OpenAI-compatible endpoint (`<host>/v1`); this module
+//! only provides what that endpoint lacks: the installed models, their context length, and
+//! whether they accept tools.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::core::network;
+
+use super::info::{DEFAULT_CONTEXT_LENGTH, ModelInfo};
+
+/// Local server: listing and inspecting models is fast unless it is down.
+const OLLAMA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tool support by model name, filled by `list_models` and `supports_tools`.
+static TOOL_SUPPORT: Mutex<Option<HashMap<String, bool>>> = Mutex::new(None);
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
+}
+
+/// Details of a model from `/api/show`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ModelDetails {
+    context_length: Option<u64>,
+    tools: bool,
+}
+
+/// Parse an `/api/show` response. Tool support comes from `capabilities` (Ollama 0.6+),
+/// else from a chat template that renders `.Tools` (how older versions decide).
+fn parse_show(show: &Value) -> ModelDetails {
+    let tools = match show.get("capabilities").and_then(|c| c.as_array()) {
+        Some(caps) => caps.iter().any(|c| c.as_str() == Some("tools")),
+        None => show
+            .get("template")
+            .and_then(|t| t.as_str())
+            .is_some_and(|t| t.contains(".Tools")),
+    };
+    let info = show.get("model_info");
+    let context_length = info
+        .and_then(|i| i.get("general.architecture"))
+        .and_then(|a| a.as_str())
+        .and_then(|arch| info?.get(format!("{}.context_length", arch))?.as_u64());
+    ModelDetails {
+        context_length,
+        tools,
+    }
+}
+
+fn client() -> reqwest::Result<reqwest::Client> {
+    network::client_builder().timeout(OLLAMA_TIMEOUT).build()
+}
+
+async fn show(
+    client: &reqwest::Client,
+    host: &str,
+    model: &str,
+) -> Result<ModelDetails, Box<dyn Error + Send + Sync>> {
+    let show: Value = client
+        .post(format!("{}/api/show", host))
+        .json(&json!({ "model": model }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(parse_show(&show))
+}
+
+fn remember_tool_support(model: &str, tools: bool) {
+    let mut cache = TOOL_SUPPORT.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get_or_insert_with(HashMap::new)
+        .insert(model.to_string(), tools);
+}
+
+/// Installed models, sorted by name. Models that do not accept tools are included: chats
+/// with them run without tools.
+pub async fn list_models(host: &str) -> Result<Vec<ModelInfo>, Box<dyn Error + Send + Sync>> {
+    let client = client()?;
+    let tags: TagsResponse = client
+        .get(format!("{}/api/tags", host))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama is not reachable at {}: {}", host, e))?
+        .error_for_status()?
+        .json()
+        .await?;
+    let mut models = Vec::with_capacity(tags.models.len());
+    for tag in tags.models {
+        let details = show(&client, host, &tag.name).await.unwrap_or_else(|e| {
+            log::warn!("Ollama /api/show failed for {}: {}", tag.name, e);
+            ModelDetails {
+                context_length: None,
+                tools: true,
+            }
+        });
+        remember_tool_support(&tag.name, details.tools);
+        models.push(ModelInfo {
+            id: tag.name.clone(),
+            name: tag.name,
+            context_length: details.context_length.unwrap_or(DEFAULT_CONTEXT_LENGTH),
+            prompt_price: Some(0.0),
+            completion_price: Some(0.0),
+            cache_read_price: None,
+            cache_write_price: None,
+        });
+    }
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Whether `model` accepts tools. Checked once per model and process; assumed true when
+/// Ollama cannot tell (the request then fails with Ollama's own error).
+pub async fn supports_tools(host: &str, model: &str) -> bool {
+    let cached = TOOL_SUPPORT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|cache| cache.get(model).copied());
+    if let Some(tools) = cached {
+        return tools;
+    }
+    let tools = match client() {
+        Ok(client) => show(&client, host, model).await.map(|d| d.tools),
+        Err(e) => Err(e.into()),
+    }
+    .unwrap_or_else(|e| {
+        log::warn!("Could not check tool support of {}: {}", model, e);
+        true
+    });
+    remember_tool_support(model, tools);
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_show_reads_capabilities_and_context() {
+        let details = parse_show(&json!({
+            "capabilities": ["completion", "tools"],
+            "model_info": {
+                "general.architecture": "llama",
+                "llama.context_length": 131072
+            }
+        }));
+        assert_eq!(
+            details,
+            ModelDetails {
+                context_length: Some(131072),
+                tools: true
+            }
+        );
+
+        let old = parse_show(&json!({"template": "{{ .Prompt }}", "model_info": {}}));
+        assert_eq!(
+            old,
+            ModelDetails {
+                context_length: None,
+                tools: false
+            }
+        );
+        assert!(parse_show(&json!({"template": "{{- if .Tools }}"})).tools);
+    }
+}
@@ -0,0 +1,164 @@
+//! Conversation webhooks: POST a JSON payload to configured URLs when a turn completes,
+//! a destructive command awaits confirmation, or the context budget is exceeded.
+//!
+//! Payloads carry a `text` summary so Slack-compatible incoming webhooks work as-is.
+//! Delivery is best effort: failures are logged and never interrupt the conversation.
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::core::llm::TokenUsage;
+use crate::core::network;
+use crate::core::text::truncate_end;
+
+/// Per-request timeout so a slow endpoint does not stall the agent loop.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Max display width of the response excerpt in `turn_completed` payloads.
+const CONTENT_PREVIEW_WIDTH: usize = 500;
+
+/// Events that fire webhooks.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The model answered without further tool calls.
+    TurnCompleted { content: String, usage: TokenUsage },
+    /// A destructive Bash command or a write outside the workspace is waiting for user
+    /// confirmation.
+    ConfirmationPending { command: String },
+    /// Estimated context exceeded the model's budget; the oldest messages were dropped.
+    BudgetExceeded {
+        removed_messages: usize,
+        context_length: u64,
+    },
+}
+
+impl Event {
+    /// Event name used in the payload (`event` field).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::TurnCompleted { .. } => "turn_completed",
+            Event::ConfirmationPending { .. } => "confirmation_pending",
+            Event::BudgetExceeded { .. } => "budget_exceeded",
+        }
+    }
+
+    fn summary(&self) -> String {
+        match self {
+            Event::TurnCompleted { .. } => "Turn completed".to_string(),
+            Event::ConfirmationPending { command } => {
+                format!("Confirmation needed for: {}", command)
+            }
+            Event::BudgetExceeded {
+                removed_messages, ..
+            } => format!(
+                "Context budget exceeded: dropped {} oldest messages",
+                removed_messages
+            ),
+        }
+    }
+}
+
+/// Where the event happened: model and mode of the turn.
+#[derive(Debug, Clone, Copy)]
+pub struct EventContext<'a> {
+    pub model: &'a str,
+    pub mode: &'a str,
+}
+
+/// Build the JSON payload for an event.
+pub fn payload(event: &Event, ctx: EventContext<'_>) -> Value {
+    let mut body = json!({
+        "event": event.name(),
+        "text": format!("my-open-claude ({}, {}): {}", ctx.mode, ctx.model, event.summary()),
+        "model": ctx.model,
+        "mode": ctx.mode,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    let details = match event {
+        Event::TurnCompleted { content, usage } => json!({
+            "content": truncate_end(content.trim(), CONTENT_PREVIEW_WIDTH),
+            "prompt_tokens": usage.prompt_tokens,
+            "completion_tokens": usage.completion_tokens,
+            "total_tokens": usage.total_tokens,
+        }),
+        Event::ConfirmationPending { command } => json!({ "command": command }),
+        Event::BudgetExceeded {
+            removed_messages,
+            context_length,
+        } => json!({
+            "removed_messages": removed_messages,
+            "context_length": context_length,
+        }),
+    };
+    if let (Some(body), Some(details)) = (body.as_object_mut(), details.as_object()) {
+        body.extend(details.clone());
+    }
+    body
+}
+
+/// POST the event to every URL concurrently. No-op when `urls` is empty.
+pub async fn send(urls: &[String], event: &Event, ctx: EventContext<'_>) {
+    if urls.is_empty() {
+        return;
+    }
+    let client = match network::client_builder().timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!("Webhook client unavailable: {}", e);
+            return;
+        }
+    };
+    let body = payload(event, ctx);
+    let requests = urls.iter().map(|url| {
+        let request = client.post(url).json(&body);
+        async move {
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => log::debug!("Webhook {} sent to {}", event.name(), url),
+                Err(e) => log::warn!("Webhook to {} failed: {}", url, e),
+            }
+        }
+    });
+    futures::future::join_all(requests).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTX: EventContext<'static> = EventContext {
+        model: "anthropic/claude-haiku-4.5",
+        mode: "Build",
+    };
+
+    #[test]
+    fn payload_includes_event_fields_and_text() {
+        let event = Event::ConfirmationPending {
+            command: "rm -rf build".to_string(),
+        };
+        let body = payload(&event, CTX);
+        assert_eq!(body["event"], "confirmation_pending");
+        assert_eq!(body["command"], "rm -rf build");
+        assert_eq!(body["mode"], "Build");
+        assert_eq!(
+            body["text"],
+            "my-open-claude (Build, anthropic/claude-haiku-4.5): Confirmation needed for: rm -rf build"
+        );
+    }
+
+    #[test]
+    fn turn_completed_payload_truncates_content() {
+        let event = Event::TurnCompleted {
+            content: "x".repeat(CONTENT_PREVIEW_WIDTH * 2),
+            usage: TokenUsage {
+                prompt_tokens: 120,
+                completion_tokens: 30,
+                total_tokens: 150,
+                ..TokenUsage::default()
+            },
+        };
+        let body = payload(&event, CTX);
+        assert_eq!(body["prompt_tokens"], 120);
+        let content = body["content"].as_str().expect("content");
+        assert!(content.chars().count() <= CONTENT_PREVIEW_WIDTH);
+    }
+}
@@ -35,6 +35,11 @@ pub fn cache_dir() -> Option<PathBuf> {
     project_dirs().map(|d| d.cache_dir().to_path_buf())
 }
 
+/// Log file written in TUI mode (~/.cache/my-open-claude/my-open-claude.log).
+pub fn log_file() -> Option<PathBuf> {
+    cache_dir().map(|d| d.join(format!("{}.log", app::NAME)))
+}
+
 /// Data directory for conversations (~/.local/share/my-open-claude/conversations/).
 /// In tests, set `TEST_DATA_DIR` env var to override.
 pub fn data_dir() -> Option<PathBuf> {
@@ -44,3 +49,27 @@ pub fn data_dir() -> Option<PathBuf> {
     }
     project_dirs().map(|d| d.data_dir().join("conversations"))
 }
+
+/// Directory for conversations exported by `/share` (~/.local/share/my-open-claude/shares/).
+pub fn shares_dir() -> Option<PathBuf> {
+    project_dirs().map(|d| d.data_dir().join("shares"))
+}
+
+/// Audit log of the commands and file changes made by the agent
+/// (~/.local/share/my-open-claude/audit.jsonl).
+pub fn audit_file() -> Option<PathBuf> {
+    // Tests running turns against the mock server must not write to the real log.
+    if cfg!(test) {
+        return None;
+    }
+    project_dirs().map(|d| d.data_dir().join("audit.jsonl"))
+}
+
+/// Usage statistics across sessions (~/.local/share/my-open-claude/stats.json).
+pub fn stats_file() -> Option<PathBuf> {
+    // Tests running turns against the mock server must not count in the real statistics.
+    if cfg!(test) {
+        return None;
+    }
+    project_dirs().map(|d| d.data_dir().join("stats.json"))
+}
@@ -0,0 +1,261 @@
+//! Path permissions for file tools.
+//!
+//! Paths matching a denied pattern (`MY_OPEN_CLAUDE_DENIED_PATHS`, e.g. `~/.ssh` or `.env`)
+//! are never read or written by tools, and are left out of the files a search walks. Writes
+//! outside the workspace root need the user's approval, given for one tool call at a time
+//! (the confirmation popup, or y/N in prompt mode).
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::core::config::Config;
+use crate::core::remote;
+
+/// Outcome of checking a tool's path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathAccess {
+    Allowed,
+    /// The path matches this denied pattern.
+    Denied {
+        pattern: String,
+    },
+    /// A write outside the workspace root: allowed only once the user approves.
+    NeedsApproval {
+        path: PathBuf,
+    },
+}
+
+struct Policy {
+    root: PathBuf,
+    denied: Vec<(String, GlobSet)>,
+}
+
+static POLICY: RwLock<Option<Policy>> = RwLock::new(None);
+
+/// Apply `config.denied_paths` and confine writes to `root` (called at startup and when
+/// switching profiles). Until then every path is allowed.
+pub fn configure(config: &Config, root: &Path) {
+    let home = directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf());
+    let policy = Policy::new(&config.denied_paths, root, home.as_deref());
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(policy);
+}
+
+/// Check a path given to a tool (relative paths are resolved against `working_dir`).
+pub fn check(working_dir: &Path, path: &str, writes: bool) -> PathAccess {
+    match POLICY.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(policy) => policy.check(working_dir, path, writes),
+        None => PathAccess::Allowed,
+    }
+}
+
+/// True when `path`, found by a tool walking a directory or reading an index, matches a
+/// denied pattern: the tool skips it (and everything inside a denied directory).
+pub fn is_denied(working_dir: &Path, path: &Path) -> bool {
+    matches!(
+        check(working_dir, &path.to_string_lossy(), false),
+        PathAccess::Denied { .. }
+    )
+}
+
+impl Policy {
+    fn new(patterns: &[String], root: &Path, home: Option<&Path>) -> Self {
+        let root = resolve(Path::new("/"), root);
+        let denied = patterns
+            .iter()
+            .filter_map(|p| match compile(p, &root, home) {
+                Some(set) => Some((p.clone(), set)),
+                None => {
+                    log::warn!("Ignoring invalid denied path pattern '{}'", p);
+                    None
+                }
+            })
+            .collect();
+        Policy { root, denied }
+    }
+
+    fn check(&self, cwd: &Path, path: &str, writes: bool) -> PathAccess {
+        let lexical = normalize(&cwd.join(path));
+        let resolved = resolve(cwd, Path::new(path));
+        if let Some((pattern, _)) = self
+            .denied
+            .iter()
+            .find(|(_, set)| set.is_match(&lexical) || set.is_match(&resolved))
+        {
+            return PathAccess::Denied {
+                pattern: pattern.clone(),
+            };
+        }
+        if writes && !resolved.starts_with(&self.root) {
+            return PathAccess::NeedsApproval { path: resolved };
+        }
+        PathAccess::Allowed
+    }
+}
+
+/// Globs for a denied pattern: `~/` is the home directory, patterns with a `/` are paths
+/// (relative ones from the workspace root), and bare names (`.env`, `*.pem`) match a file
+/// or directory of that name anywhere. A matching directory denies everything inside it.
+fn compile(pattern: &str, root: &Path, home: Option<&Path>) -> Option<GlobSet> {
+    let pattern = pattern.trim().trim_end_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+    let base = if let Some(rest) = pattern.strip_prefix("~/") {
+        format!("{}/{}", globset::escape(&home?.to_string_lossy()), rest)
+    } else if pattern.starts_with('/') {
+        pattern.to_string()
+    } else if pattern.contains('/') {
+        format!("{}/{}", globset::escape(&root.to_string_lossy()), pattern)
+    } else {
+        format!("**/{}", pattern)
+    };
+    let mut builder = GlobSetBuilder::new();
+    for glob in [base.clone(), format!("{}/**", base)] {
+        builder.add(
+            GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .ok()?,
+        );
+    }
+    builder.build().ok()
+}
+
+/// Remove `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Absolute path with symlinks resolved in its existing part, so a link inside the
+/// workspace cannot hide a target outside it. Missing components are appended as given.
+/// Paths of a remote workspace are only normalized: they are not on the local disk.
+fn resolve(cwd: &Path, path: &Path) -> PathBuf {
+    let path = normalize(&cwd.join(path));
+    if remote::is_active() {
+        return path;
+    }
+    let mut existing = path.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |acc: PathBuf, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(patterns: &[&str], root: &Path, home: &Path) -> Policy {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        Policy::new(&patterns, root, Some(home))
+    }
+
+    #[test]
+    fn writes_outside_the_workspace_need_approval() {
+        let root = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        let policy = policy(&[], root.path(), outside.path());
+        let root_dir = root.path();
+
+        assert_eq!(
+            policy.check(root_dir, "src/new.rs", true),
+            PathAccess::Allowed
+        );
+        assert!(matches!(
+            policy.check(root_dir, "../escape.rs", true),
+            PathAccess::NeedsApproval { .. }
+        ));
+        let elsewhere = outside.path().join("notes.md");
+        assert!(matches!(
+            policy.check(root_dir, elsewhere.to_str().unwrap(), true),
+            PathAccess::NeedsApproval { .. }
+        ));
+        assert_eq!(
+            policy.check(root_dir, elsewhere.to_str().unwrap(), false),
+            PathAccess::Allowed,
+            "reads outside the workspace are allowed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_out_of_the_workspace_needs_approval() {
+        let root = tempfile::TempDir::new().unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+        let policy = policy(&[], root.path(), outside.path());
+        assert!(matches!(
+            policy.check(root.path(), "link/file.txt", true),
+            PathAccess::NeedsApproval { .. }
+        ));
+    }
+
+    #[test]
+    fn denied_patterns_match_names_home_and_root_paths() {
+        let root = tempfile::TempDir::new().unwrap();
+        let home = tempfile::TempDir::new().unwrap();
+        let policy = policy(
+            &["~/.ssh", ".env", "*.pem", "config/secrets"],
+            root.path(),
+            home.path(),
+        );
+        let denied = |path: &str| match policy.check(root.path(), path, false) {
+            PathAccess::Denied { pattern } => Some(pattern),
+            _ => None,
+        };
+
+        let key = home.path().join(".ssh/id_ed25519");
+        assert_eq!(denied(key.to_str().unwrap()).as_deref(), Some("~/.ssh"));
+        assert_eq!(denied(".env").as_deref(), Some(".env"));
+        assert_eq!(denied("app/.env").as_deref(), Some(".env"));
+        assert_eq!(denied("certs/server.pem").as_deref(), Some("*.pem"));
+        assert_eq!(
+            denied("config/secrets/token.txt").as_deref(),
+            Some("config/secrets")
+        );
+        assert_eq!(denied(".env.example"), None);
+        assert_eq!(denied("app/config/secrets/token.txt"), None);
+        assert_eq!(denied("src/main.rs"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_to_a_denied_file_is_denied() {
+        let root = tempfile::TempDir::new().unwrap();
+        let home = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(home.path().join(".ssh")).unwrap();
+        std::fs::write(home.path().join(".ssh/id_rsa"), "key").unwrap();
+        std::os::unix::fs::symlink(home.path().join(".ssh/id_rsa"), root.path().join("notes"))
+            .unwrap();
+        let policy = policy(&["~/.ssh"], root.path(), home.path());
+        let walked = root.path().join("notes");
+        assert!(matches!(
+            policy.check(root.path(), &walked.to_string_lossy(), false),
+            PathAccess::Denied { .. }
+        ));
+    }
+}
@@ -1,23 +1,215 @@
-//! Persistence of user preferences (e.g. last selected model) in ~/.config/my-open-claude/.
+//! Persistence of user preferences (e.g. last selected model, favorite and recent models,
+//! per-model generation parameters) in ~/.config/my-open-claude/,
+//! and per-workspace settings (e.g. pinned model) in `<root>/.my-open-claude/settings.json`.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
+use std::path::Path;
 
+use serde::{Deserialize, Serialize};
+
+use crate::core::config::Provider;
+use crate::core::generation::GenerationParams;
 use crate::core::paths;
 
-/// Load the last used model ID from disk, if the file exists.
-pub fn load_last_model() -> Option<String> {
-    let path = paths::config_dir()?.join("last_model");
+/// File of the last used model: one per provider, since model IDs differ between them.
+fn last_model_file(provider: Provider) -> &'static str {
+    match provider {
+        Provider::OpenRouter => "last_model",
+        Provider::Ollama => "last_model_ollama",
+        Provider::Anthropic => "last_model_anthropic",
+    }
+}
+
+/// Load the last used model ID of `provider` from disk, if the file exists.
+pub fn load_last_model(provider: Provider) -> Option<String> {
+    let path = paths::config_dir()?.join(last_model_file(provider));
     fs::read_to_string(path)
         .ok()
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
 }
 
-/// Save the last used model ID to disk. Creates the config directory if needed.
-pub fn save_last_model(model_id: &str) -> io::Result<()> {
+/// Save the last used model ID of `provider` to disk. Creates the config directory if needed.
+pub fn save_last_model(provider: Provider, model_id: &str) -> io::Result<()> {
+    let dir = paths::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(last_model_file(provider)), model_id)
+}
+
+/// Recently used models kept for the model selector.
+pub const MAX_RECENT_MODELS: usize = 5;
+
+/// Starred and recently used models (`model_prefs.json`), shown first in the model selector.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPrefs {
+    /// Starred model IDs, in the order they were starred.
+    #[serde(default)]
+    pub favorites: Vec<String>,
+    /// Recently selected model IDs, most recent first.
+    #[serde(default)]
+    pub recent: Vec<String>,
+}
+
+impl ModelPrefs {
+    pub fn is_favorite(&self, model_id: &str) -> bool {
+        self.favorites.iter().any(|id| id == model_id)
+    }
+
+    /// Star or unstar a model. Returns true when it is now a favorite.
+    pub fn toggle_favorite(&mut self, model_id: &str) -> bool {
+        if self.is_favorite(model_id) {
+            self.favorites.retain(|id| id != model_id);
+            false
+        } else {
+            self.favorites.push(model_id.to_string());
+            true
+        }
+    }
+
+    /// Move a model to the front of the recent list, keeping at most `MAX_RECENT_MODELS`.
+    pub fn record_recent(&mut self, model_id: &str) {
+        self.recent.retain(|id| id != model_id);
+        self.recent.insert(0, model_id.to_string());
+        self.recent.truncate(MAX_RECENT_MODELS);
+    }
+}
+
+/// Load favorite and recent models. Missing or invalid files yield empty preferences.
+pub fn load_model_prefs() -> ModelPrefs {
+    paths::config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join("model_prefs.json")).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Save favorite and recent models. Creates the config directory if needed.
+pub fn save_model_prefs(prefs: &ModelPrefs) -> io::Result<()> {
+    let dir = paths::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(prefs).map_err(io::Error::other)?;
+    fs::write(dir.join("model_prefs.json"), json)
+}
+
+/// Generation parameters saved per model ID (`model_params.json`, see `core::generation`).
+pub type ModelParams = BTreeMap<String, GenerationParams>;
+
+/// Load the saved generation parameters of each model. Missing or invalid files yield none.
+pub fn load_model_params() -> ModelParams {
+    paths::config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join("model_params.json")).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Save the generation parameters of `model_id`; empty parameters remove its entry.
+/// Creates the config directory if needed.
+pub fn save_model_params(model_id: &str, params: &GenerationParams) -> io::Result<()> {
     let dir = paths::config_dir()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    let mut all = load_model_params();
+    if params.is_empty() {
+        all.remove(model_id);
+    } else {
+        all.insert(model_id.to_string(), params.clone());
+    }
     fs::create_dir_all(&dir)?;
-    fs::write(dir.join("last_model"), model_id)
+    let json = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+    fs::write(dir.join("model_params.json"), json)
+}
+
+/// Workspace settings directory, relative to the workspace root.
+pub const WORKSPACE_SETTINGS_DIR: &str = ".my-open-claude";
+
+/// Per-workspace settings (`<root>/.my-open-claude/settings.json`).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct WorkspaceSettings {
+    /// Model ID pinned for this workspace; overrides the last used model.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Tool time limits in seconds by tool name (e.g. `{"Bash": 300}`); 0 removes the limit.
+    #[serde(default)]
+    pub tool_timeouts: BTreeMap<String, u64>,
+}
+
+/// Load workspace settings from `root`. Returns None when the file is absent or invalid
+/// (invalid files are logged and ignored).
+pub fn load_workspace_settings(root: &Path) -> Option<WorkspaceSettings> {
+    let path = root.join(WORKSPACE_SETTINGS_DIR).join("settings.json");
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<WorkspaceSettings>(&content) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            log::warn!("Ignoring invalid {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Model pinned by the workspace settings, if any (blank values are ignored).
+pub fn load_workspace_model(root: &Path) -> Option<String> {
+    load_workspace_settings(root)?
+        .model
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_prefs_favorites_and_recents() {
+        let mut prefs = ModelPrefs::default();
+        assert!(prefs.toggle_favorite("a/one"));
+        assert!(prefs.is_favorite("a/one"));
+        assert!(!prefs.toggle_favorite("a/one"));
+        assert!(prefs.favorites.is_empty());
+
+        for i in 0..7 {
+            prefs.record_recent(&format!("m/{}", i));
+        }
+        prefs.record_recent("m/3");
+        assert_eq!(prefs.recent, vec!["m/3", "m/6", "m/5", "m/4", "m/2"]);
+    }
+
+    #[test]
+    fn load_workspace_model_reads_settings_file() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        assert_eq!(load_workspace_model(dir.path()), None);
+
+        let settings_dir = dir.path().join(WORKSPACE_SETTINGS_DIR);
+        fs::create_dir(&settings_dir).expect("mkdir");
+        fs::write(settings_dir.join("settings.json"), "not json").expect("write");
+        assert_eq!(load_workspace_model(dir.path()), None);
+
+        fs::write(
+            settings_dir.join("settings.json"),
+            r#"{"model": " openai/gpt-4o "}"#,
+        )
+        .expect("write");
+        assert_eq!(
+            load_workspace_model(dir.path()),
+            Some("openai/gpt-4o".to_string())
+        );
+    }
+
+    #[test]
+    fn load_workspace_settings_reads_tool_timeouts() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let settings_dir = dir.path().join(WORKSPACE_SETTINGS_DIR);
+        fs::create_dir(&settings_dir).expect("mkdir");
+        fs::write(
+            settings_dir.join("settings.json"),
+            r#"{"tool_timeouts": {"Bash": 300, "Grep": 0}}"#,
+        )
+        .expect("write");
+        let settings = load_workspace_settings(dir.path()).expect("settings");
+        assert_eq!(settings.model, None);
+        assert_eq!(settings.tool_timeouts.get("Bash"), Some(&300));
+        assert_eq!(settings.tool_timeouts.get("Grep"), Some(&0));
+    }
 }
@@ -0,0 +1,265 @@
+//! Pinned files and messages: content sent with every request until unpinned.
+//!
+//! Pins are added to the system prompt of each request only: files are re-read before each
+//! API call, so the model always sees the latest content (including edits made by tools),
+//! and pinned messages stay in view after compaction or truncation drops them from the
+//! history. The saved conversation does not accumulate copies.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::core::llm::PromptSection;
+use crate::core::tools::ignore;
+
+/// Largest pinned content sent per file; longer files are cut with a note.
+pub const MAX_PINNED_FILE_BYTES: usize = 100 * 1024;
+/// Most files listed for the @mention picker.
+pub const MAX_WORKSPACE_FILES: usize = 5_000;
+/// Largest pinned content sent per message; longer messages are cut with a note.
+pub const MAX_PINNED_MESSAGE_BYTES: usize = 16 * 1024;
+/// Title of the system prompt section holding the pinned messages.
+pub const PINNED_MESSAGES_TITLE: &str = "Pinned messages";
+
+/// Error pinning a file.
+#[derive(Debug, thiserror::Error)]
+pub enum PinError {
+    #[error("No such file: {0}")]
+    NotFound(String),
+    #[error("Not a file: {0}")]
+    NotAFile(String),
+}
+
+/// A pinned file as shown in the TUI: absolute path, label, and estimated tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedFile {
+    pub path: PathBuf,
+    /// Path relative to the workspace root when inside it, else absolute.
+    pub label: String,
+    /// Estimated tokens of the current content (bytes / 4), refreshed by `refresh`.
+    pub tokens: usize,
+}
+
+impl PinnedFile {
+    /// Resolve `input` (relative to `root`, or absolute) to an existing file.
+    pub fn resolve(root: &Path, input: &str) -> Result<Self, PinError> {
+        let input = input.trim();
+        let candidate = root.join(input);
+        let path = fs::canonicalize(&candidate).map_err(|_| PinError::NotFound(input.into()))?;
+        if !path.is_file() {
+            return Err(PinError::NotAFile(input.into()));
+        }
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let label = path
+            .strip_prefix(&root)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| path.display().to_string());
+        let mut pin = Self {
+            path,
+            label,
+            tokens: 0,
+        };
+        pin.refresh();
+        Ok(pin)
+    }
+
+    /// Update the token estimate from the file's current size.
+    pub fn refresh(&mut self) {
+        let bytes = fs::metadata(&self.path).map_or(0, |m| m.len() as usize);
+        self.tokens = bytes.min(MAX_PINNED_FILE_BYTES) / 4;
+    }
+}
+
+/// A conversation message pinned by the user (Alt+P, `/pins`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedMessage {
+    /// Written by the user; otherwise an assistant reply.
+    pub from_user: bool,
+    pub content: String,
+}
+
+/// Current content of a pinned file, cut at `MAX_PINNED_FILE_BYTES`.
+fn read_pinned(path: &Path) -> String {
+    match fs::read(path) {
+        Ok(bytes) => {
+            let cut = bytes.len() > MAX_PINNED_FILE_BYTES;
+            let text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_PINNED_FILE_BYTES)]);
+            if cut {
+                format!(
+                    "{}\n... (truncated: file is {} bytes, first {} shown)",
+                    text,
+                    bytes.len(),
+                    MAX_PINNED_FILE_BYTES
+                )
+            } else {
+                text.into_owned()
+            }
+        }
+        Err(e) => format!("(could not read file: {})", e),
+    }
+}
+
+/// System prompt sections with the current content of each pinned file.
+pub fn sections(pins: &[PathBuf]) -> Vec<PromptSection> {
+    pins.iter()
+        .map(|path| PromptSection {
+            id: None,
+            title: format!("Pinned file: {}", path.display()),
+            content: read_pinned(path),
+        })
+        .collect()
+}
+
+/// System prompt section quoting the pinned messages, in conversation order; None when
+/// nothing is pinned.
+pub fn messages_section(messages: &[PinnedMessage]) -> Option<PromptSection> {
+    if messages.is_empty() {
+        return None;
+    }
+    let mut content = String::from(
+        "The user pinned these messages from the conversation. Keep them in mind even when \
+         they are no longer in the message history.",
+    );
+    for message in messages {
+        let mut text = message.content.as_str();
+        let cut = text.len() > MAX_PINNED_MESSAGE_BYTES;
+        if cut {
+            text = &text[..text.floor_char_boundary(MAX_PINNED_MESSAGE_BYTES)];
+        }
+        content.push_str(&format!(
+            "\n\n[{}]\n{}{}",
+            if message.from_user {
+                "User"
+            } else {
+                "Assistant"
+            },
+            text,
+            if cut { "\n... (truncated)" } else { "" }
+        ));
+    }
+    Some(PromptSection {
+        id: None,
+        title: PINNED_MESSAGES_TITLE.to_string(),
+        content,
+    })
+}
+
+/// Files under `root` (relative paths, sorted), skipping ignored directories.
+/// Capped at `MAX_WORKSPACE_FILES`.
+pub fn workspace_files(root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| !ignore::is_ignored(e))
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            e.path()
+                .strip_prefix(root)
+                .ok()
+                .map(|p| p.display().to_string())
+        })
+        .take(MAX_WORKSPACE_FILES)
+        .collect();
+    files.sort();
+    files
+}
+
+/// Files matching `query` (case-insensitive substring), best first: file-name matches,
+/// then shorter paths. At most `limit` results.
+pub fn filter_files<'a>(files: &'a [String], query: &str, limit: usize) -> Vec<&'a str> {
+    let q = query.to_lowercase();
+    let mut matches: Vec<(bool, &str)> = files
+        .iter()
+        .filter(|f| f.to_lowercase().contains(&q))
+        .map(|f| {
+            let name = f.rsplit(['/', '\\']).next().unwrap_or(f);
+            (!name.to_lowercase().contains(&q), f.as_str())
+        })
+        .collect();
+    matches.sort_by_key(|(not_in_name, f)| (*not_in_name, f.len()));
+    matches.into_iter().take(limit).map(|(_, f)| f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_relative_file_and_refresh_tokens() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "x".repeat(400)).unwrap();
+        let pin = PinnedFile::resolve(dir.path(), "src/main.rs").unwrap();
+        assert_eq!(pin.label, "src/main.rs");
+        assert_eq!(pin.tokens, 100);
+    }
+
+    #[test]
+    fn resolve_rejects_missing_and_directories() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(matches!(
+            PinnedFile::resolve(dir.path(), "nope.rs"),
+            Err(PinError::NotFound(_))
+        ));
+        assert!(matches!(
+            PinnedFile::resolve(dir.path(), "."),
+            Err(PinError::NotAFile(_))
+        ));
+    }
+
+    #[test]
+    fn sections_read_current_content() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("notes.md");
+        fs::write(&path, "v1").unwrap();
+        assert_eq!(sections(std::slice::from_ref(&path))[0].content, "v1");
+        fs::write(&path, "v2").unwrap();
+        assert_eq!(sections(&[path])[0].content, "v2");
+    }
+
+    #[test]
+    fn messages_section_quotes_pins_in_order() {
+        assert_eq!(messages_section(&[]), None);
+        let section = messages_section(&[
+            PinnedMessage {
+                from_user: true,
+                content: "Use PostgreSQL 16.".to_string(),
+            },
+            PinnedMessage {
+                from_user: false,
+                content: "é".repeat(MAX_PINNED_MESSAGE_BYTES),
+            },
+        ])
+        .unwrap();
+        assert_eq!(section.title, PINNED_MESSAGES_TITLE);
+        assert!(
+            section
+                .content
+                .contains("\n\n[User]\nUse PostgreSQL 16.\n\n[Assistant]\né")
+        );
+        assert!(section.content.ends_with("é\n... (truncated)"));
+    }
+
+    #[test]
+    fn filter_files_prefers_file_name_matches() {
+        let files = vec![
+            "main/lib.rs".to_string(),
+            "src/domain.rs".to_string(),
+            "src/main.rs".to_string(),
+        ];
+        assert_eq!(
+            filter_files(&files, "main", 10),
+            vec!["src/main.rs", "src/domain.rs", "main/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn workspace_files_skips_ignored_dirs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join("target/out"), "").unwrap();
+        fs::write(dir.path().join("a.rs"), "").unwrap();
+        assert_eq!(workspace_files(dir.path()), vec!["a.rs".to_string()]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Named profiles: presets bundling a provider, API key reference, default model, code theme,
+//! and tool permissions, for quick switches between contexts ("work", "personal", "local").
+//!
+//! A profile is `profiles/<name>.json` in the config directory. It is selected at startup
+//! with `--profile <name>` (or `MY_OPEN_CLAUDE_PROFILE`) and switched in the TUI with
+//! `/profile`. Fields it sets replace the matching environment variables; fields it omits
+//! keep them. The API key itself is never stored in the profile, only the name of the
+//! environment variable holding it (which may come from `.env`).
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::core::paths;
+
+/// Subdirectory of the config directory holding the profiles.
+const PROFILES_DIR: &str = "profiles";
+
+/// Errors loading a profile.
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("Invalid profile name '{0}' (letters, digits, '-' and '_' only)")]
+    InvalidName(String),
+
+    #[error("Profile '{name}' not found (expected {path})")]
+    NotFound { name: String, path: String },
+
+    #[error("Cannot read profile '{name}': {source}")]
+    Io {
+        name: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Invalid profile '{name}': {source}")]
+    Parse {
+        name: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Contents of a profile file. Every field is optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// File name without `.json`.
+    #[serde(skip)]
+    pub name: String,
+    /// "openrouter" or "ollama" (replaces `MY_OPEN_CLAUDE_PROVIDER`).
+    pub provider: Option<String>,
+    /// API base URL with OpenRouter, server address with Ollama (replaces
+    /// `OPENROUTER_BASE_URL` / `OLLAMA_HOST`).
+    pub base_url: Option<String>,
+    /// Environment variable holding the API key (instead of `OPENROUTER_API_KEY`).
+    pub api_key_env: Option<String>,
+    /// Model used at startup, over the last used one (a workspace pin and `--model` still win).
+    pub model: Option<String>,
+    /// Code block highlighting theme, e.g. "InspiredGitHub" for light terminals.
+    pub theme: Option<String>,
+    /// Tools never offered to the model (replaces `MY_OPEN_CLAUDE_DISABLED_TOOLS`).
+    pub disabled_tools: Option<Vec<String>>,
+    /// Path patterns denied to file tools (replaces `MY_OPEN_CLAUDE_DENIED_PATHS`).
+    pub denied_paths: Option<Vec<String>>,
+    /// Regexes masked in tool results (replaces `MY_OPEN_CLAUDE_REDACT_PATTERNS`).
+    pub redact_patterns: Option<Vec<String>>,
+    /// Scripts run on each reply (replaces `MY_OPEN_CLAUDE_POST_HOOKS`).
+    pub post_hooks: Option<Vec<String>>,
+}
+
+impl Profile {
+    /// One-line description of what the profile sets, for lists.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        parts.extend(self.provider.clone());
+        parts.extend(self.model.clone());
+        if let Some(var) = &self.api_key_env {
+            parts.push(format!("key ${}", var));
+        }
+        if let Some(theme) = &self.theme {
+            parts.push(format!("theme {}", theme));
+        }
+        if let Some(tools) = self.disabled_tools.as_ref().filter(|t| !t.is_empty()) {
+            parts.push(format!("no {}", tools.join(", ")));
+        }
+        if self.denied_paths.is_some() {
+            parts.push("denied paths".to_string());
+        }
+        if self.redact_patterns.as_ref().is_some_and(|p| !p.is_empty()) {
+            parts.push("redaction patterns".to_string());
+        }
+        if self.post_hooks.as_ref().is_some_and(|h| !h.is_empty()) {
+            parts.push("post hooks".to_string());
+        }
+        if parts.is_empty() {
+            "environment settings".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+}
+
+/// Profile selected for this session.
+static ACTIVE: Mutex<Option<Profile>> = Mutex::new(None);
+
+/// Directory of the profiles (~/.config/my-open-claude/profiles/).
+pub fn profiles_dir() -> Option<PathBuf> {
+    paths::config_dir().map(|d| d.join(PROFILES_DIR))
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Load the profile `name` from the profiles directory.
+pub fn load(name: &str) -> Result<Profile, ProfileError> {
+    if !valid_name(name) {
+        return Err(ProfileError::InvalidName(name.to_string()));
+    }
+    let path = profiles_dir()
+        .unwrap_or_else(|| PathBuf::from(PROFILES_DIR))
+        .join(format!("{}.json", name));
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(ProfileError::NotFound {
+                name: name.to_string(),
+                path: path.display().to_string(),
+            });
+        }
+        Err(source) => {
+            return Err(ProfileError::Io {
+                name: name.to_string(),
+                source,
+            });
+        }
+    };
+    parse(name, &content)
+}
+
+fn parse(name: &str, content: &str) -> Result<Profile, ProfileError> {
+    let mut profile: Profile =
+        serde_json::from_str(content).map_err(|source| ProfileError::Parse {
+            name: name.to_string(),
+            source,
+        })?;
+    profile.name = name.to_string();
+    Ok(profile)
+}
+
+/// Profiles in the profiles directory, sorted by name. Invalid files are skipped with a
+/// warning.
+pub fn list() -> Vec<Profile> {
+    let Some(entries) = profiles_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<Profile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            match load(&name) {
+                Ok(profile) => Some(profile),
+                Err(e) => {
+                    log::warn!("Skipping profile: {}", e);
+                    None
+                }
+            }
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// Select the profile `name` for the next `config::load` (None: environment settings only).
+pub fn activate(name: Option<&str>) -> Result<(), ProfileError> {
+    let profile = name.map(load).transpose()?;
+    *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = profile;
+    Ok(())
+}
+
+/// The selected profile, if any.
+pub fn active() -> Option<Profile> {
+    ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_fields_and_rejects_unknown_ones() {
+        let profile = parse(
+            "work",
+            r#"{"provider": "openrouter", "api_key_env": "WORK_KEY", "model": "openai/gpt-4o",
+                "disabled_tools": ["Bash"], "theme": "InspiredGitHub"}"#,
+        )
+        .unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(profile.api_key_env.as_deref(), Some("WORK_KEY"));
+        assert_eq!(profile.denied_paths, None);
+        assert_eq!(
+            profile.summary(),
+            "openrouter · openai/gpt-4o · key $WORK_KEY · theme InspiredGitHub · no Bash"
+        );
+
+        assert!(matches!(
+            parse("typo", r#"{"modle": "x"}"#),
+            Err(ProfileError::Parse { .. })
+        ));
+        assert!(matches!(load("../etc"), Err(ProfileError::InvalidName(_))));
+    }
+}
@@ -0,0 +1,89 @@
+//! Workspace file access for tools: on the remote host in a remote workspace, else on the
+//! local disk. Errors keep the `io::ErrorKind` of the local calls (e.g. `NotFound`).
+
+use std::io;
+use std::path::Path;
+
+use super::{Remote, RemoteError, current, shell_quote};
+
+/// Exit status of the read script for a missing file.
+const MISSING: i32 = 3;
+/// Exit status of the read script for a directory.
+const DIRECTORY: i32 = 4;
+
+/// Like `std::fs::read_to_string`.
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let path = path.as_ref();
+    let Some(remote) = current() else {
+        return std::fs::read_to_string(path);
+    };
+    let p = quoted(path);
+    let output = remote
+        .output(
+            &format!(
+                "[ -e {p} ] || exit {MISSING}; [ -d {p} ] && exit {DIRECTORY}; cat -- {p}",
+                p = p
+            ),
+            None,
+        )
+        .map_err(io::Error::other)?;
+    match output.status.code() {
+        Some(0) => String::from_utf8(output.stdout).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        }),
+        Some(MISSING) => Err(io::ErrorKind::NotFound.into()),
+        Some(DIRECTORY) => Err(io::ErrorKind::IsADirectory.into()),
+        _ => Err(command_error(remote, &output.stderr)),
+    }
+}
+
+/// Like `std::fs::write`.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    let Some(remote) = current() else {
+        return std::fs::write(path, contents);
+    };
+    let output = remote
+        .output(&format!("cat > {}", quoted(path)), Some(contents))
+        .map_err(io::Error::other)?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(command_error(remote, &output.stderr))
+    }
+}
+
+/// Like `std::fs::remove_file`.
+pub fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let Some(remote) = current() else {
+        return std::fs::remove_file(path);
+    };
+    let p = quoted(path);
+    let output = remote
+        .output(
+            &format!("[ -e {p} ] || exit {MISSING}; rm -- {p}", p = p),
+            None,
+        )
+        .map_err(io::Error::other)?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(MISSING) => Err(io::ErrorKind::NotFound.into()),
+        _ => Err(command_error(remote, &output.stderr)),
+    }
+}
+
+fn quoted(path: &Path) -> String {
+    shell_quote(&path.to_string_lossy())
+}
+
+/// The remote command's error message (e.g. "cat: x: Permission denied").
+fn command_error(remote: &Remote, stderr: &[u8]) -> io::Error {
+    io::Error::other(RemoteError::Command {
+        host: remote.host().to_string(),
+        message: String::from_utf8_lossy(stderr).trim().to_string(),
+    })
+}
@@ -0,0 +1,280 @@
+//! Remote workspace over SSH (`--remote HOST[:PATH]`, `MY_OPEN_CLAUDE_REMOTE`).
+//!
+//! Bash commands and the file tools (Read, Write, Edit, Grep) run on the remote host
+//! through the system `ssh` client, so host aliases, keys, and agents from `~/.ssh/config`
+//! apply. Workspace detection runs there too, and tool paths are remote paths. Tools that
+//! walk the local disk (ListDir, Glob, ReplaceAll, PreviewData, SemanticSearch, Symbols) are not
+//! offered. Calls share one SSH connection (OpenSSH connection multiplexing), and
+//! authentication must not prompt (keys or an agent): the TUI owns the terminal.
+
+pub mod fs;
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::core::{app, paths};
+
+/// How often a running remote command checks for cancellation.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Seconds an idle shared SSH connection stays open.
+const CONTROL_PERSIST_SECS: u32 = 120;
+
+/// Exit status of `ssh` itself when the connection fails.
+const SSH_CONNECTION_FAILED: i32 = 255;
+
+/// Errors from parsing a remote target or reaching the host.
+#[derive(Debug, Error)]
+pub enum RemoteError {
+    #[error("invalid remote '{0}' (expected HOST or HOST:PATH, e.g. devbox:~/src/app)")]
+    InvalidTarget(String),
+
+    #[error("cannot run ssh: {0}")]
+    Spawn(#[source] io::Error),
+
+    #[error("cannot connect to {host}: {message}")]
+    Connection { host: String, message: String },
+
+    #[error("{host}: {message}")]
+    Command { host: String, message: String },
+}
+
+/// `HOST[:PATH]` given with `--remote`: an SSH destination (host, alias, or user@host)
+/// and the directory to start in (the login directory when omitted).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTarget {
+    pub host: String,
+    pub path: Option<String>,
+}
+
+impl RemoteTarget {
+    pub fn parse(spec: &str) -> Result<Self, RemoteError> {
+        let spec = spec.trim();
+        let (host, path) = match spec.split_once(':') {
+            Some((host, path)) => (host, Some(path).filter(|p| !p.is_empty())),
+            None => (spec, None),
+        };
+        // A leading '-' would be read as an ssh option.
+        if host.is_empty() || host.starts_with('-') || host.contains(char::is_whitespace) {
+            return Err(RemoteError::InvalidTarget(spec.to_string()));
+        }
+        Ok(RemoteTarget {
+            host: host.to_string(),
+            path: path.map(str::to_string),
+        })
+    }
+}
+
+/// The SSH host the session works on.
+#[derive(Debug)]
+pub struct Remote {
+    host: String,
+    /// Socket of the shared connection; None where multiplexing is unsupported.
+    control_path: Option<PathBuf>,
+}
+
+static REMOTE: OnceLock<Remote> = OnceLock::new();
+
+/// Run tools and workspace detection on `host` from now on (called once at startup).
+pub fn configure(host: &str) -> &'static Remote {
+    let control_path = if cfg!(unix) {
+        paths::cache_dir().and_then(|dir| {
+            std::fs::create_dir_all(&dir).ok()?;
+            // %C: hash of the connection parameters, short enough for a socket path.
+            Some(dir.join("ssh-%C"))
+        })
+    } else {
+        None
+    };
+    REMOTE.get_or_init(|| Remote {
+        host: host.to_string(),
+        control_path,
+    })
+}
+
+/// The remote host when the session works on one.
+pub fn current() -> Option<&'static Remote> {
+    REMOTE.get()
+}
+
+/// True when tools run on a remote host.
+pub fn is_active() -> bool {
+    REMOTE.get().is_some()
+}
+
+/// Quote `s` as one word for a POSIX shell.
+pub fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+=:,@%".contains(c))
+    {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote a path for a POSIX shell, keeping a leading `~` (the remote home directory).
+pub fn shell_path(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some("") => "\"$HOME\"".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("\"$HOME\"{}", shell_quote(rest)),
+        _ => shell_quote(path),
+    }
+}
+
+impl Remote {
+    /// SSH destination, as given.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// `ssh` running `script` with `sh` on the host; stdin is null and output piped.
+    pub fn command(&self, script: &str) -> Command {
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=15"]);
+        if let Some(ref path) = self.control_path {
+            cmd.args(["-o", "ControlMaster=auto", "-o"])
+                .arg(format!("ControlPath={}", path.display()))
+                .arg("-o")
+                .arg(format!("ControlPersist={}", CONTROL_PERSIST_SECS));
+        }
+        // The remote login shell joins the arguments; `sh -c` runs the script the same
+        // way whatever that shell is.
+        cmd.arg("--")
+            .arg(&self.host)
+            .arg(format!("sh -c {}", shell_quote(script)));
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        cmd
+    }
+
+    /// Run `script` on the host, feeding it `stdin`. Fails when the connection does.
+    pub fn output(&self, script: &str, stdin: Option<&[u8]>) -> Result<Output, RemoteError> {
+        let mut cmd = self.command(script);
+        if stdin.is_some() {
+            cmd.stdin(Stdio::piped());
+        }
+        let mut child = cmd.spawn().map_err(RemoteError::Spawn)?;
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            // Written on a thread: a large input would otherwise block on a full pipe
+            // while the output is not read.
+            let input = input.to_vec();
+            thread::spawn(move || {
+                let _ = pipe.write_all(&input);
+            });
+        }
+        let output = child.wait_with_output().map_err(RemoteError::Spawn)?;
+        self.check_connection(&output.status, &output.stderr)?;
+        Ok(output)
+    }
+
+    /// Run `script` on the host until it exits, or until `cancel` fires (then the local
+    /// `ssh` is killed, which closes the session, and None is returned).
+    pub fn run_cancellable(
+        &self,
+        script: &str,
+        cancel: &CancellationToken,
+    ) -> Result<Option<Output>, RemoteError> {
+        let mut child = self.command(script).spawn().map_err(RemoteError::Spawn)?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let stdout_reader = thread::spawn(move || read_all(stdout));
+        let stderr_reader = thread::spawn(move || read_all(stderr));
+        let status = loop {
+            if cancel.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+            if let Some(status) = child.try_wait().map_err(RemoteError::Spawn)? {
+                break status;
+            }
+            thread::sleep(CANCEL_POLL_INTERVAL);
+        };
+        let output = Output {
+            status,
+            stdout: stdout_reader.join().unwrap_or_default(),
+            stderr: stderr_reader.join().unwrap_or_default(),
+        };
+        self.check_connection(&output.status, &output.stderr)?;
+        Ok(Some(output))
+    }
+
+    /// A `Connection` error when `ssh` could not reach the host. A remote command exiting
+    /// with 255 looks the same, except that ssh's own errors start with "ssh:" or name
+    /// the host.
+    fn check_connection(&self, status: &ExitStatus, stderr: &[u8]) -> Result<(), RemoteError> {
+        if status.code() != Some(SSH_CONNECTION_FAILED) {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(stderr);
+        let message = stderr.trim();
+        let denied = message.contains("Permission denied (");
+        if !(denied
+            || message.starts_with("ssh:")
+            || message.contains("Host key verification failed"))
+        {
+            return Ok(());
+        }
+        let message = if denied {
+            format!(
+                "{} ({} runs ssh without prompting: use a key or an agent)",
+                message,
+                app::NAME
+            )
+        } else {
+            message.to_string()
+        };
+        Err(RemoteError::Connection {
+            host: self.host.clone(),
+            message,
+        })
+    }
+}
+
+fn read_all<R: io::Read>(pipe: Option<R>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_target_splits_host_and_path() {
+        assert_eq!(
+            RemoteTarget::parse("dev@box:~/src/app").unwrap(),
+            RemoteTarget {
+                host: "dev@box".to_string(),
+                path: Some("~/src/app".to_string()),
+            }
+        );
+        assert_eq!(RemoteTarget::parse("devbox:").unwrap().path, None);
+        assert_eq!(RemoteTarget::parse("devbox").unwrap().host, "devbox");
+        assert!(RemoteTarget::parse("-oProxyCommand=x").is_err());
+        assert!(RemoteTarget::parse(":/srv").is_err());
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("src/main.rs"), "src/main.rs");
+        assert_eq!(shell_quote("my file"), "'my file'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_path("~/src app"), r#""$HOME"'/src app'"#);
+        assert_eq!(shell_path("~"), r#""$HOME""#);
+        assert_eq!(shell_path("~user/x"), "'~user/x'");
+    }
+}
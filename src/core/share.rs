@@ -0,0 +1,216 @@
+//! Conversation sharing (`/share`): the transcript as Markdown with secrets redacted,
+//! written to a local file or uploaded as a secret GitHub gist.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::core::history::{CONTEXT_RESET_ROLE, CONTEXT_SUMMARY_ROLE};
+use crate::core::{app, message, network, paths, redact};
+
+/// GitHub endpoint creating gists.
+const GIST_API_URL: &str = "https://api.github.com/gists";
+/// Upload timeout, so a slow network does not leave the share pending forever.
+const GIST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest file name stem derived from the title.
+const FILE_STEM_MAX_LEN: usize = 60;
+
+/// Errors uploading a gist.
+#[derive(Debug, thiserror::Error)]
+pub enum ShareError {
+    #[error("gist upload failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("GitHub rejected the gist ({status}): {message}")]
+    Api { status: u16, message: String },
+    #[error("GitHub did not return the gist URL")]
+    MissingUrl,
+}
+
+/// A conversation ready to share: redacted Markdown and the file name to give it.
+#[derive(Debug, Clone)]
+pub struct SharedConversation {
+    pub title: String,
+    pub file_name: String,
+    pub markdown: String,
+}
+
+impl SharedConversation {
+    /// Render `messages` (persisted format: user, assistant, tool_log, context markers) under
+    /// `title`, scrubbing `secrets` and common credential shapes.
+    pub fn new(title: &str, messages: &[Value], secrets: &[String]) -> Self {
+        let title = redact::redact(title, secrets);
+        SharedConversation {
+            file_name: file_name(&title),
+            markdown: redact::redact(&to_markdown(&title, messages), secrets),
+            title,
+        }
+    }
+
+    /// Write the Markdown to `path`, creating its directory.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &self.markdown)
+    }
+
+    /// Default location of a local share, in the shares directory.
+    pub fn default_path(&self) -> Option<PathBuf> {
+        paths::shares_dir().map(|d| d.join(&self.file_name))
+    }
+
+    /// Upload as a secret gist with `token`. Returns the gist's URL.
+    pub async fn upload_gist(&self, token: &str) -> Result<String, ShareError> {
+        let body = serde_json::json!({
+            "description": self.title,
+            "public": false,
+            "files": { &self.file_name: { "content": self.markdown } },
+        });
+        let response = network::client_builder()
+            .timeout(GIST_TIMEOUT)
+            .build()?
+            .post(GIST_API_URL)
+            .bearer_auth(token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, app::NAME)
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        let json: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = json
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("no details")
+                .to_string();
+            return Err(ShareError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        json.get("html_url")
+            .and_then(|u| u.as_str())
+            .map(String::from)
+            .ok_or(ShareError::MissingUrl)
+    }
+}
+
+/// Markdown transcript: a heading per message, tool calls as quoted lines.
+fn to_markdown(title: &str, messages: &[Value]) -> String {
+    let mut out = format!(
+        "# {}\n\n*Shared from {} v{} on {}. Secrets were redacted.*\n",
+        title,
+        app::NAME,
+        app::VERSION,
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    let mut in_tool_block = false;
+    for msg in messages {
+        let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("");
+        let content = message::extract_content(msg).unwrap_or_default();
+        if role == "tool_log" {
+            if !in_tool_block {
+                out.push('\n');
+                in_tool_block = true;
+            }
+            let _ = writeln!(out, "> {}  ", content.replace('\n', " "));
+            continue;
+        }
+        in_tool_block = false;
+        match role {
+            "user" => {
+                let _ = write!(out, "\n## You\n\n{}\n", content.trim_end());
+            }
+            "assistant" if !content.trim().is_empty() => {
+                let _ = write!(out, "\n## Assistant\n\n{}\n", content.trim_end());
+            }
+            CONTEXT_RESET_ROLE => {
+                out.push_str(
+                    "\n---\n\n*Context cleared: later replies did not see the messages above.*\n",
+                );
+            }
+            CONTEXT_SUMMARY_ROLE => {
+                let _ = write!(
+                    out,
+                    "\n---\n\n*Context compacted: later replies saw this summary of the messages above.*\n\n{}\n",
+                    content.trim_end()
+                );
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// File name from the title: lowercase words joined by dashes, `conversation.md` if empty.
+fn file_name(title: &str) -> String {
+    let mut stem = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            stem.extend(c.to_lowercase());
+        } else if !stem.is_empty() && !stem.ends_with('-') {
+            stem.push('-');
+        }
+        if stem.chars().count() >= FILE_STEM_MAX_LEN {
+            break;
+        }
+    }
+    let stem = stem.trim_end_matches('-');
+    if stem.is_empty() {
+        "conversation.md".to_string()
+    } else {
+        format!("{}.md", stem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn markdown_has_turns_tool_logs_and_context_reset() {
+        let messages = vec![
+            json!({"role": "user", "content": "List files"}),
+            json!({"role": "tool_log", "content": "→ Bash: ls"}),
+            json!({"role": "tool_log", "content": "→ Read: Cargo.toml"}),
+            json!({"role": "assistant", "content": "There are 3 files."}),
+            json!({"role": CONTEXT_RESET_ROLE}),
+            json!({"role": CONTEXT_SUMMARY_ROLE, "content": "- 3 files listed", "kept_turns": 2}),
+            json!({"role": "user", "content": [{"type": "text", "text": "Thanks"}]}),
+        ];
+        let md = to_markdown("Files", &messages);
+        assert!(md.starts_with("# Files\n"));
+        assert!(md.contains("\n## You\n\nList files\n\n> → Bash: ls  \n> → Read: Cargo.toml  \n"));
+        assert!(md.contains("\n## Assistant\n\nThere are 3 files.\n"));
+        assert!(md.contains("\n---\n"));
+        assert!(md.contains("above.*\n\n- 3 files listed\n"));
+        assert!(md.ends_with("## You\n\nThanks\n"));
+    }
+
+    #[test]
+    fn shared_conversation_redacts_secrets() {
+        let messages = vec![
+            json!({"role": "user", "content": "Show .env"}),
+            json!({"role": "assistant", "content": "```\nOPENROUTER_API_KEY=sk-or-v1-abcdef123456\nGIST=my-gist-token-1234\n```"}),
+        ];
+        let shared =
+            SharedConversation::new("Show .env", &messages, &["my-gist-token-1234".to_string()]);
+        assert!(!shared.markdown.contains("sk-or-v1-abcdef123456"));
+        assert!(!shared.markdown.contains("my-gist-token-1234"));
+        assert!(shared.markdown.contains("OPENROUTER_API_KEY=[REDACTED]"));
+        assert_eq!(shared.file_name, "show-env.md");
+    }
+
+    #[test]
+    fn file_name_slugs_title() {
+        assert_eq!(file_name("Fix the /cd command!"), "fix-the-cd-command.md");
+        assert_eq!(file_name("  ...  "), "conversation.md");
+        assert!(file_name(&"a".repeat(200)).len() <= FILE_STEM_MAX_LEN + 3);
+    }
+}
@@ -0,0 +1,428 @@
+//! Spend across sessions: API cost per day in `spend.json` (config directory), and the cost
+//! of this session in memory, checked against optional session, daily, and weekly caps
+//! before new requests and after each model call of a turn. Prompt cache use of this
+//! session, and what it saved, is counted next to it (`/cost`).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::core::paths;
+
+/// Share of a cap at which a warning is shown.
+pub const WARN_RATIO: f64 = 0.8;
+
+/// Days kept in the store (older entries no longer count toward any cap).
+const KEEP_DAYS: i64 = 14;
+
+/// Cost of the API calls of this process.
+static SESSION_SPEND: Mutex<f64> = Mutex::new(0.0);
+/// The user chose to send requests past the caps for the rest of the session.
+static OVERRIDDEN: AtomicBool = AtomicBool::new(false);
+/// Prompt cache use of the API calls of this process.
+static SESSION_CACHE: Mutex<CacheTotals> = Mutex::new(CacheTotals {
+    prompt_tokens: 0,
+    read_tokens: 0,
+    write_tokens: 0,
+    saved: 0.0,
+});
+
+/// Cost per local day ("YYYY-MM-DD"), in credits (USD).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct SpendStore {
+    /// Keys sort chronologically.
+    #[serde(default)]
+    days: BTreeMap<String, f64>,
+}
+
+/// Optional caps from `MY_OPEN_CLAUDE_SESSION_SPEND_LIMIT`, `MY_OPEN_CLAUDE_DAILY_SPEND_LIMIT`,
+/// and `MY_OPEN_CLAUDE_WEEKLY_SPEND_LIMIT`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpendLimits {
+    pub session: Option<f64>,
+    pub daily: Option<f64>,
+    pub weekly: Option<f64>,
+}
+
+impl fmt::Display for SpendLimits {
+    /// "session none, daily $5.00, weekly none", or "none" when no cap is set.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        let cap = |limit: Option<f64>| limit.map_or("none".to_string(), |l| format!("${:.2}", l));
+        write!(
+            f,
+            "session {}, daily {}, weekly {}",
+            cap(self.session),
+            cap(self.daily),
+            cap(self.weekly)
+        )
+    }
+}
+
+impl SpendLimits {
+    /// True when no cap is set.
+    pub fn is_empty(&self) -> bool {
+        self.session.is_none() && self.daily.is_none() && self.weekly.is_none()
+    }
+
+    /// Compare spend with the caps. An exceeded cap wins over a warning; the session cap is
+    /// reported first, then the daily one.
+    pub fn check(&self, totals: SpendTotals) -> SpendStatus {
+        let hits = [
+            (Period::Session, totals.session, self.session),
+            (Period::Day, totals.today, self.daily),
+            (Period::Week, totals.week, self.weekly),
+        ]
+        .into_iter()
+        .filter_map(|(period, spent, limit)| {
+            limit.map(|limit| LimitHit {
+                period,
+                spent,
+                limit,
+            })
+        });
+        let mut warning = None;
+        for hit in hits {
+            if hit.spent >= hit.limit {
+                return SpendStatus::Exceeded(hit);
+            }
+            if warning.is_none() && hit.spent >= hit.limit * WARN_RATIO {
+                warning = Some(hit);
+            }
+        }
+        warning.map_or(SpendStatus::Ok, SpendStatus::Warning)
+    }
+}
+
+/// Spend of this session, of the current day, and of the current week (from Monday).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SpendTotals {
+    pub session: f64,
+    pub today: f64,
+    pub week: f64,
+}
+
+impl fmt::Display for SpendTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${:.2} today, ${:.2} this week", self.today, self.week)
+    }
+}
+
+/// Period of a spend cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Session,
+    Day,
+    Week,
+}
+
+/// A cap reached or close to being reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitHit {
+    pub period: Period,
+    pub spent: f64,
+    pub limit: f64,
+}
+
+impl fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.period {
+            Period::Session => "Session",
+            Period::Day => "Daily",
+            Period::Week => "Weekly",
+        };
+        write!(
+            f,
+            "{} spend ${:.2} of ${:.2} cap",
+            label, self.spent, self.limit
+        )
+    }
+}
+
+/// Result of checking the caps before a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpendStatus {
+    Ok,
+    /// At least `WARN_RATIO` of a cap is spent.
+    Warning(LimitHit),
+    /// A cap is reached: new requests are blocked unless overridden.
+    Exceeded(LimitHit),
+}
+
+impl SpendStatus {
+    /// The cap reached or close to being reached.
+    pub fn hit(&self) -> Option<&LimitHit> {
+        match self {
+            SpendStatus::Ok => None,
+            SpendStatus::Warning(hit) | SpendStatus::Exceeded(hit) => Some(hit),
+        }
+    }
+}
+
+fn day_key(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+fn store_path() -> Option<PathBuf> {
+    paths::config_dir().map(|d| d.join("spend.json"))
+}
+
+fn load_store(path: &Path) -> SpendStore {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn record_at(path: &Path, day: NaiveDate, cost: f64) -> io::Result<()> {
+    let mut store = load_store(path);
+    *store.days.entry(day_key(day)).or_insert(0.0) += cost;
+    let oldest = day_key(day - Duration::days(KEEP_DAYS - 1));
+    store.days.retain(|d, _| *d >= oldest);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&store).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+fn totals_at(store: &SpendStore, day: NaiveDate) -> SpendTotals {
+    let monday = day - Duration::days(i64::from(day.weekday().num_days_from_monday()));
+    SpendTotals {
+        session: session_spend(),
+        today: store.days.get(&day_key(day)).copied().unwrap_or(0.0),
+        week: store
+            .days
+            .range(day_key(monday)..=day_key(day))
+            .fold(0.0, |sum, (_, cost)| sum + cost),
+    }
+}
+
+/// Prompt tokens of this session, those read from or written to the provider's prompt cache
+/// (both included in `prompt_tokens`), and the credits caching saved.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheTotals {
+    pub prompt_tokens: u64,
+    pub read_tokens: u64,
+    pub write_tokens: u64,
+    /// Credits (USD) saved compared with the same prompts uncached; cache writes, billed
+    /// above the prompt price, count against it.
+    pub saved: f64,
+}
+
+impl CacheTotals {
+    /// Share of the prompt tokens read from the cache, from 0 to 1.
+    pub fn hit_ratio(&self) -> f64 {
+        if self.prompt_tokens == 0 {
+            return 0.0;
+        }
+        self.read_tokens as f64 / self.prompt_tokens as f64
+    }
+
+    /// True when no call read from or wrote to the cache.
+    pub fn is_empty(&self) -> bool {
+        self.read_tokens == 0 && self.write_tokens == 0
+    }
+}
+
+impl fmt::Display for CacheTotals {
+    /// "12000 of 40000 prompt tokens read from cache (30%), 8000 written, $0.03 saved".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} prompt tokens read from cache ({:.0}%), {} written, ${:.2} saved",
+            self.read_tokens,
+            self.prompt_tokens,
+            self.hit_ratio() * 100.0,
+            self.write_tokens,
+            self.saved
+        )
+    }
+}
+
+/// Add the prompt cache use of an API call to the session's totals.
+pub fn record_cache(call: CacheTotals) {
+    if let Ok(mut cache) = SESSION_CACHE.lock() {
+        cache.prompt_tokens += call.prompt_tokens;
+        cache.read_tokens += call.read_tokens;
+        cache.write_tokens += call.write_tokens;
+        cache.saved += call.saved;
+    }
+}
+
+/// Prompt cache use of this session.
+pub fn session_cache() -> CacheTotals {
+    SESSION_CACHE
+        .lock()
+        .map_or(CacheTotals::default(), |cache| *cache)
+}
+
+/// Cost of the API calls of this session.
+pub fn session_spend() -> f64 {
+    SESSION_SPEND.lock().map_or(0.0, |spend| *spend)
+}
+
+/// Add the cost of an API call to the session's and today's spend. Zero costs are not
+/// written.
+pub fn record(cost: f64) -> io::Result<()> {
+    if cost <= 0.0 {
+        return Ok(());
+    }
+    if let Ok(mut spend) = SESSION_SPEND.lock() {
+        *spend += cost;
+    }
+    let path = store_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    record_at(&path, Local::now().date_naive(), cost)
+}
+
+/// Spend of this session, and of today and this week across all sessions.
+pub fn totals() -> SpendTotals {
+    store_path()
+        .map(|path| totals_at(&load_store(&path), Local::now().date_naive()))
+        .unwrap_or(SpendTotals {
+            session: session_spend(),
+            ..SpendTotals::default()
+        })
+}
+
+/// Check current spend against `limits` (no file read when no cap is set).
+pub fn check(limits: &SpendLimits) -> SpendStatus {
+    if limits.is_empty() {
+        return SpendStatus::Ok;
+    }
+    limits.check(totals())
+}
+
+/// Send requests past the caps for the rest of the session (`/spend override`, the
+/// confirmation at a reached cap, `--ignore-spend-limit`).
+pub fn override_caps() {
+    OVERRIDDEN.store(true, Ordering::Relaxed);
+}
+
+/// True once the caps were overridden for this session.
+pub fn caps_overridden() -> bool {
+    OVERRIDDEN.load(Ordering::Relaxed)
+}
+
+/// The cap that stops requests: a reached cap, unless overridden.
+pub fn blocking_limit(limits: &SpendLimits) -> Option<LimitHit> {
+    if caps_overridden() {
+        return None;
+    }
+    match check(limits) {
+        SpendStatus::Exceeded(hit) => Some(hit),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn cache_totals_report_the_share_read_from_cache() {
+        let cache = CacheTotals {
+            prompt_tokens: 40_000,
+            read_tokens: 12_000,
+            write_tokens: 8_000,
+            saved: 0.0324,
+        };
+        assert_eq!(
+            cache.to_string(),
+            "12000 of 40000 prompt tokens read from cache (30%), 8000 written, $0.03 saved"
+        );
+        assert!(!cache.is_empty());
+        assert!(CacheTotals::default().is_empty());
+        assert_eq!(CacheTotals::default().hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn record_accumulates_per_day_and_prunes_old_days() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("spend.json");
+        record_at(&path, day("2026-09-01"), 1.0).unwrap();
+        record_at(&path, day("2026-10-12"), 0.25).unwrap();
+        record_at(&path, day("2026-10-14"), 0.5).unwrap();
+        record_at(&path, day("2026-10-14"), 0.25).unwrap();
+
+        let store = load_store(&path);
+        assert_eq!(store.days.len(), 2, "September entry is pruned");
+        // 2026-10-14 is a Wednesday: the week starts on Monday the 12th.
+        let totals = totals_at(&store, day("2026-10-14"));
+        assert_eq!(totals.today, 0.75);
+        assert_eq!(totals.week, 1.0);
+        assert_eq!(totals_at(&store, day("2026-10-19")).week, 0.0);
+    }
+
+    #[test]
+    fn check_warns_at_80_percent_and_blocks_at_cap() {
+        let limits = SpendLimits {
+            session: None,
+            daily: Some(5.0),
+            weekly: Some(20.0),
+        };
+        let totals = |today, week| SpendTotals {
+            session: 0.0,
+            today,
+            week,
+        };
+        assert_eq!(limits.check(totals(1.0, 10.0)), SpendStatus::Ok);
+        assert!(matches!(
+            limits.check(totals(4.0, 10.0)),
+            SpendStatus::Warning(LimitHit {
+                period: Period::Day,
+                ..
+            })
+        ));
+        // The weekly cap is exceeded even though the daily one only warns.
+        let status = limits.check(totals(4.5, 21.0));
+        assert!(matches!(
+            status,
+            SpendStatus::Exceeded(LimitHit {
+                period: Period::Week,
+                ..
+            })
+        ));
+        if let SpendStatus::Exceeded(hit) = status {
+            assert_eq!(hit.to_string(), "Weekly spend $21.00 of $20.00 cap");
+        }
+        assert_eq!(
+            SpendLimits::default().check(totals(100.0, 100.0)),
+            SpendStatus::Ok
+        );
+    }
+
+    #[test]
+    fn session_cap_is_checked_first() {
+        let limits = SpendLimits {
+            session: Some(1.0),
+            daily: Some(5.0),
+            weekly: None,
+        };
+        let status = limits.check(SpendTotals {
+            session: 1.5,
+            today: 6.0,
+            week: 6.0,
+        });
+        assert_eq!(status.hit().map(|hit| hit.period), Some(Period::Session));
+        assert_eq!(
+            limits.to_string(),
+            "session $1.00, daily $5.00, weekly none"
+        );
+        assert_eq!(SpendStatus::Ok.hit(), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! Usage statistics across sessions: per-session aggregates (requests, tokens, cost, tool
+//! calls, models, slash commands) in `stats.json` (data directory), split by local day.
+//! Shown by `my-open-claude stats` and the F9 screen of the TUI.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::core::llm::TokenUsage;
+use crate::core::paths;
+
+/// Days kept in the store; older days are dropped.
+pub const KEEP_DAYS: usize = 90;
+
+/// Weeks shown in the weekly chart.
+pub const REPORT_WEEKS: usize = 12;
+
+/// Entries listed for top models, commands, and tools.
+const TOP_ENTRIES: usize = 5;
+
+/// Requests, tokens, and cost of one model.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    pub requests: u64,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl ModelUsage {
+    fn add(&mut self, other: &ModelUsage) {
+        self.requests += other.requests;
+        self.tokens += other.tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage of one session on one day.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct DayUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    cost: f64,
+    #[serde(default)]
+    models: BTreeMap<String, ModelUsage>,
+    /// Tool calls by tool name.
+    #[serde(default)]
+    tools: BTreeMap<String, u64>,
+    /// Slash commands by name, without the slash.
+    #[serde(default)]
+    commands: BTreeMap<String, u64>,
+}
+
+/// Sessions by ID (start time and process ID), each with its usage per local day ("YYYY-MM-DD").
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct StatsStore {
+    #[serde(default)]
+    sessions: BTreeMap<String, BTreeMap<String, DayUsage>>,
+}
+
+/// ID of this process's session in the store.
+static SESSION_ID: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "{}-{}",
+        Local::now().format("%Y%m%dT%H%M%S"),
+        std::process::id()
+    )
+});
+
+/// Serializes updates of the store from concurrent tool calls.
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+fn day_key(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+fn load_store(path: &Path) -> StatsStore {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Apply `f` to the usage of `session` on `day`, drop days older than `KEEP_DAYS`, and save.
+fn update_at(
+    path: &Path,
+    session: &str,
+    day: NaiveDate,
+    f: impl FnOnce(&mut DayUsage),
+) -> io::Result<()> {
+    let mut store = load_store(path);
+    f(store
+        .sessions
+        .entry(session.to_string())
+        .or_default()
+        .entry(day_key(day))
+        .or_default());
+    let oldest = day_key(day - Duration::days(KEEP_DAYS as i64 - 1));
+    for days in store.sessions.values_mut() {
+        days.retain(|d, _| *d >= oldest);
+    }
+    store.sessions.retain(|_, days| !days.is_empty());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string(&store).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+/// Update today's usage of this session. Failures are logged: statistics never block a turn.
+fn update(f: impl FnOnce(&mut DayUsage)) {
+    let Some(path) = paths::stats_file() else {
+        return;
+    };
+    let _guard = UPDATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = update_at(&path, &SESSION_ID, Local::now().date_naive(), f) {
+        log::warn!("Failed to record usage statistics: {}", e);
+    }
+}
+
+/// Record an API call to `model`.
+pub fn record_request(model: &str, usage: &TokenUsage) {
+    update(|day| {
+        day.prompt_tokens += usage.prompt_tokens;
+        day.completion_tokens += usage.completion_tokens;
+        day.cost += usage.cost;
+        day.models
+            .entry(model.to_string())
+            .or_default()
+            .add(&ModelUsage {
+                requests: 1,
+                tokens: usage.prompt_tokens + usage.completion_tokens,
+                cost: usage.cost,
+            });
+    });
+}
+
+/// Record a tool call.
+pub fn record_tool(name: &str) {
+    update(|day| *day.tools.entry(name.to_string()).or_default() += 1);
+}
+
+/// Record a slash command (`name` without the slash).
+pub fn record_command(name: &str) {
+    update(|day| *day.commands.entry(name.to_string()).or_default() += 1);
+}
+
+/// Tokens and cost of a day or a week.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PeriodTotals {
+    /// First day of the period.
+    pub start: NaiveDate,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// Usage over the last days, for `stats` and the F9 screen.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StatsReport {
+    /// Each of the last days, oldest first (days without usage included).
+    pub days: Vec<PeriodTotals>,
+    /// Each of the last `REPORT_WEEKS` weeks (from Monday), oldest first.
+    pub weeks: Vec<PeriodTotals>,
+    /// Sessions with usage during the last days.
+    pub sessions: usize,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    /// Most used models by tokens.
+    pub top_models: Vec<(String, ModelUsage)>,
+    /// Most used slash commands.
+    pub top_commands: Vec<(String, u64)>,
+    /// Most called tools.
+    pub top_tools: Vec<(String, u64)>,
+}
+
+/// Entries of `counts` with the highest values first (ties by name), at most `TOP_ENTRIES`.
+fn top<V: Clone>(counts: BTreeMap<String, V>, key: impl Fn(&V) -> u64) -> Vec<(String, V)> {
+    let mut entries: Vec<(String, V)> = counts.into_iter().collect();
+    entries.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(TOP_ENTRIES);
+    entries
+}
+
+fn report_at(store: &StatsStore, today: NaiveDate, days: usize) -> StatsReport {
+    let days = days.clamp(1, KEEP_DAYS);
+    let first_day = today - Duration::days(days as i64 - 1);
+    let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let first_week = monday - Duration::weeks(REPORT_WEEKS as i64 - 1);
+
+    let mut report = StatsReport {
+        days: (0..days)
+            .map(|i| PeriodTotals {
+                start: first_day + Duration::days(i as i64),
+                ..Default::default()
+            })
+            .collect(),
+        weeks: (0..REPORT_WEEKS)
+            .map(|i| PeriodTotals {
+                start: first_week + Duration::weeks(i as i64),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut models: BTreeMap<String, ModelUsage> = BTreeMap::new();
+    let mut commands: BTreeMap<String, u64> = BTreeMap::new();
+    let mut tools: BTreeMap<String, u64> = BTreeMap::new();
+
+    for session_days in store.sessions.values() {
+        let mut in_range = false;
+        for (key, usage) in session_days {
+            let Ok(day) = NaiveDate::parse_from_str(key, "%Y-%m-%d") else {
+                continue;
+            };
+            if day > today {
+                continue;
+            }
+            let tokens = usage.prompt_tokens + usage.completion_tokens;
+            if day >= first_week {
+                let week = &mut report.weeks[((day - first_week).num_days() / 7) as usize];
+                week.tokens += tokens;
+                week.cost += usage.cost;
+            }
+            if day < first_day {
+                continue;
+            }
+            in_range = true;
+            let totals = &mut report.days[(day - first_day).num_days() as usize];
+            totals.tokens += tokens;
+            totals.cost += usage.cost;
+            report.prompt_tokens += usage.prompt_tokens;
+            report.completion_tokens += usage.completion_tokens;
+            report.cost += usage.cost;
+            for (model, model_usage) in &usage.models {
+                report.requests += model_usage.requests;
+                models.entry(model.clone()).or_default().add(model_usage);
+            }
+            for (name, count) in &usage.commands {
+                *commands.entry(name.clone()).or_default() += count;
+            }
+            for (name, count) in &usage.tools {
+                *tools.entry(name.clone()).or_default() += count;
+            }
+        }
+        if in_range {
+            report.sessions += 1;
+        }
+    }
+
+    report.top_models = top(models, |m| m.tokens);
+    report.top_commands = top(commands, |c| *c);
+    report.top_tools = top(tools, |c| *c);
+    report
+}
+
+/// Usage of the last `days` days (at most `KEEP_DAYS`), across all sessions.
+pub fn report(days: usize) -> StatsReport {
+    let store = paths::stats_file()
+        .map(|path| load_store(&path))
+        .unwrap_or_default();
+    report_at(&store, Local::now().date_naive(), days)
+}
+
+/// Values as a line of block characters scaled to the largest one, e.g. "▁▃█ ▂".
+/// Zero values are blank so idle days stand out.
+pub fn sparkline(values: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| {
+            if v == 0 {
+                ' '
+            } else {
+                let level = (v as f64 / max as f64 * (BARS.len() - 1) as f64).round() as usize;
+                BARS[level.min(BARS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn request(model: &str, tokens: u64, cost: f64) -> impl FnOnce(&mut DayUsage) {
+        move |usage| {
+            usage.prompt_tokens += tokens;
+            usage.cost += cost;
+            usage
+                .models
+                .entry(model.to_string())
+                .or_default()
+                .add(&ModelUsage {
+                    requests: 1,
+                    tokens,
+                    cost,
+                });
+        }
+    }
+
+    #[test]
+    fn report_aggregates_days_weeks_and_top_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.json");
+        update_at(&path, "s1", day("2026-10-12"), request("a/big", 1000, 0.5)).unwrap();
+        update_at(&path, "s1", day("2026-10-12"), |u| {
+            *u.commands.entry("review".into()).or_default() += 1;
+            *u.tools.entry("Read".into()).or_default() += 3;
+        })
+        .unwrap();
+        update_at(&path, "s2", day("2026-10-14"), request("b/small", 200, 0.1)).unwrap();
+        update_at(&path, "s2", day("2026-10-14"), request("a/big", 300, 0.2)).unwrap();
+        update_at(&path, "s3", day("2026-09-01"), request("c/old", 50, 0.0)).unwrap();
+
+        let store = load_store(&path);
+        let report = report_at(&store, day("2026-10-14"), 7);
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(report.days[0].start, day("2026-10-08"));
+        assert_eq!(report.days[4].tokens, 1000);
+        assert_eq!(report.days[6].tokens, 500);
+        assert_eq!(report.sessions, 2, "s3 is outside the last 7 days");
+        assert_eq!(report.requests, 3);
+        assert_eq!(report.prompt_tokens, 1500);
+        assert_eq!(report.top_models[0].0, "a/big");
+        assert_eq!(report.top_models[0].1.requests, 2);
+        assert_eq!(report.top_commands, vec![("review".to_string(), 1)]);
+        assert_eq!(report.top_tools, vec![("Read".to_string(), 3)]);
+        // 2026-10-14 is a Wednesday: the last week starts on Monday the 12th.
+        let last_week = report.weeks.last().unwrap();
+        assert_eq!(last_week.start, day("2026-10-12"));
+        assert_eq!(last_week.tokens, 1500);
+        assert_eq!(report.weeks.iter().map(|w| w.tokens).sum::<u64>(), 1550);
+    }
+
+    #[test]
+    fn update_drops_days_older_than_keep_days() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("stats.json");
+        update_at(&path, "old", day("2026-01-01"), request("m", 10, 0.0)).unwrap();
+        update_at(&path, "new", day("2026-10-14"), request("m", 10, 0.0)).unwrap();
+        let store = load_store(&path);
+        assert_eq!(store.sessions.keys().collect::<Vec<_>>(), vec!["new"]);
+    }
+
+    #[test]
+    fn sparkline_scales_to_max_and_blanks_zeros() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), " ▂▅█");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -0,0 +1,442 @@
+//! Edit suggestions (`/suggest`): Edit calls are staged in a change-set instead of applied.
+//! When the turn ends, the TUI shows the staged changes hunk by hunk; the user accepts,
+//! rejects, or rewrites each one, the accepted hunks are written together (or not at all),
+//! and the outcome is reported to the model in the conversation. The mode is set per
+//! conversation (see `llm::ToolModes`).
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::core::diff::{self, Hunk, LineKind};
+use crate::core::dry_run;
+use crate::core::llm::undo::UndoBatch;
+use crate::core::remote::fs;
+use crate::core::tools::{self, Tool, ToolError};
+
+/// First line of the result of a staged call.
+const STAGED_HEADER: &str = "[SUGGESTED] Staged for the user's review: nothing was written yet.";
+
+/// Last paragraph of the result of a staged call, telling the model how to go on.
+const STAGED_NOTE: &str = "Suggest mode is on: later edits of this file in this turn apply on top of the staged change. When you finish, the user reviews every staged hunk and may accept, reject, or rewrite it; the outcome is reported in the conversation. Do not read the file back to check the change.";
+
+/// Change-sets of the turns in flight, by turn ID (see `TurnOrigin`).
+static STAGED: Mutex<Vec<(String, ChangeSet)>> = Mutex::new(Vec::new());
+
+/// Errors applying the accepted hunks. Nothing is written when one occurs.
+#[derive(Debug, Error)]
+pub enum SuggestError {
+    #[error("{0} changed since the edit was suggested")]
+    Conflict(String),
+
+    #[error("Cannot write {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// A file changed by the staged calls of a turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedFile {
+    /// Path as the model gave it, for display.
+    pub path: String,
+    pub full_path: PathBuf,
+    /// Content on disk when the first call was staged.
+    pub original: String,
+    /// Content with every staged call applied.
+    pub proposed: String,
+}
+
+/// Changes staged during one turn.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSet {
+    pub files: Vec<StagedFile>,
+}
+
+/// Stage the call of `tool` with `args` for turn `turn`: its change applies on top of the
+/// changes already staged for the same file. Returns the result given to the model, or
+/// the error the call would fail with.
+pub fn stage(
+    turn: &str,
+    tool: &dyn Tool,
+    args: &Value,
+    working_dir: &Path,
+) -> Result<String, ToolError> {
+    let path = tool
+        .target_path(args)
+        .filter(|p| !p.is_empty())
+        .ok_or("Missing file_path argument")?;
+    let full_path = tools::resolve_path(working_dir, &path);
+    let mut staged = STAGED.lock().unwrap_or_else(|e| e.into_inner());
+    let index = match staged.iter().position(|(t, _)| t == turn) {
+        Some(index) => index,
+        None => {
+            staged.push((turn.to_string(), ChangeSet::default()));
+            staged.len() - 1
+        }
+    };
+    let set = &mut staged[index].1;
+    let file = set.files.iter().position(|f| f.full_path == full_path);
+    let current = match file {
+        Some(i) => set.files[i].proposed.clone(),
+        None => fs::read_to_string(&full_path)
+            .map_err(|e| format!("Cannot read file '{}': {}", path, e))?,
+    };
+    let proposed = tool.suggested_content(args, &current)?;
+    let summary = dry_run::change_summary(&path, &current, &proposed);
+    match file {
+        Some(i) => set.files[i].proposed = proposed,
+        None => set.files.push(StagedFile {
+            path,
+            full_path,
+            original: current,
+            proposed,
+        }),
+    }
+    Ok(format!("{}\n{}\n\n{}", STAGED_HEADER, summary, STAGED_NOTE))
+}
+
+/// Remove and return the changes staged for `turn`; None when it staged nothing (or only
+/// calls that left their files unchanged).
+pub fn take(turn: &str) -> Option<ChangeSet> {
+    let mut staged = STAGED.lock().unwrap_or_else(|e| e.into_inner());
+    let index = staged.iter().position(|(t, _)| t == turn)?;
+    let (_, mut set) = staged.remove(index);
+    set.files.retain(|f| f.original != f.proposed);
+    (!set.files.is_empty()).then_some(set)
+}
+
+/// What the user decided for a hunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Accept,
+    Reject,
+    /// Accepted with its new side replaced by this text.
+    Edit(String),
+}
+
+/// Hunk-by-hunk review of a change-set.
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub set: ChangeSet,
+    /// Every hunk, with the index of its file.
+    pub hunks: Vec<(usize, Hunk)>,
+    pub decisions: Vec<Decision>,
+}
+
+impl Review {
+    pub fn new(set: ChangeSet) -> Self {
+        let hunks = set
+            .files
+            .iter()
+            .enumerate()
+            .flat_map(|(i, file)| {
+                diff::between(&file.path, &file.original, &file.proposed)
+                    .hunks
+                    .into_iter()
+                    .map(move |hunk| (i, hunk))
+            })
+            .collect();
+        Review {
+            set,
+            hunks,
+            decisions: Vec::new(),
+        }
+    }
+
+    /// The hunk waiting for a decision (its index, file, and lines); None once all are decided.
+    pub fn current(&self) -> Option<(usize, &StagedFile, &Hunk)> {
+        let index = self.decisions.len();
+        let (file, hunk) = self.hunks.get(index)?;
+        Some((index, &self.set.files[*file], hunk))
+    }
+
+    pub fn decide(&mut self, decision: Decision) {
+        if self.decisions.len() < self.hunks.len() {
+            self.decisions.push(decision);
+        }
+    }
+
+    /// Give `decision` to every hunk still waiting.
+    pub fn decide_rest(&mut self, decision: Decision) {
+        while self.current().is_some() {
+            self.decide(decision.clone());
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.decisions.len() >= self.hunks.len()
+    }
+
+    /// Write the accepted hunks of every file, after checking that none changed on disk
+    /// since the edits were suggested. A failed write restores the files already written.
+    /// The files are captured in `undo` first, so `/undo` reverts them.
+    pub fn apply(&self, mut undo: Option<&mut UndoBatch>) -> Result<Outcome, SuggestError> {
+        let mut outcome = Outcome::default();
+        let mut writes = Vec::new();
+        for (i, file) in self.set.files.iter().enumerate() {
+            let (hunks, decisions): (Vec<&Hunk>, Vec<&Decision>) = self
+                .hunks
+                .iter()
+                .zip(&self.decisions)
+                .filter(|((f, _), _)| *f == i)
+                .map(|((_, hunk), decision)| (hunk, decision))
+                .unzip();
+            let count = |d: fn(&Decision) -> bool| decisions.iter().filter(|x| d(x)).count();
+            let result = FileOutcome {
+                path: file.path.clone(),
+                accepted: count(|d| *d == Decision::Accept),
+                edited: count(|d| matches!(d, Decision::Edit(_))),
+                rejected: count(|d| *d == Decision::Reject),
+            };
+            if result.accepted + result.edited > 0 {
+                writes.push((file, merged(file, &hunks, &decisions)));
+            }
+            outcome.files.push(result);
+        }
+
+        for (file, _) in &writes {
+            if fs::read_to_string(&file.full_path).ok().as_ref() != Some(&file.original) {
+                return Err(SuggestError::Conflict(file.path.clone()));
+            }
+        }
+        for (done, (file, content)) in writes.iter().enumerate() {
+            if let Some(batch) = undo.as_deref_mut() {
+                batch.capture(&file.full_path.to_string_lossy());
+            }
+            if let Err(source) = fs::write(&file.full_path, content) {
+                for (file, _) in &writes[..done] {
+                    if let Err(e) = fs::write(&file.full_path, &file.original) {
+                        log::warn!("Cannot restore {}: {}", file.path, e);
+                    }
+                }
+                return Err(SuggestError::Write {
+                    path: file.path.clone(),
+                    source,
+                });
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// The new side of `hunk` (context and added lines), as offered for rewriting.
+pub fn hunk_text(hunk: &Hunk) -> String {
+    hunk.lines
+        .iter()
+        .filter(|l| l.kind != LineKind::Removed)
+        .map(|l| format!("{}\n", l.text))
+        .collect()
+}
+
+/// Content of `file` with the hunks of `decisions` applied; rejected hunks keep the
+/// original lines.
+fn merged(file: &StagedFile, hunks: &[&Hunk], decisions: &[&Decision]) -> String {
+    if decisions.iter().all(|d| **d == Decision::Accept) {
+        return file.proposed.clone();
+    }
+    let old: Vec<&str> = file.original.lines().collect();
+    let mut lines: Vec<String> = Vec::new();
+    let mut next = 0;
+    for (hunk, decision) in hunks.iter().zip(decisions) {
+        let start = hunk
+            .lines
+            .iter()
+            .find_map(|l| l.old_line)
+            .map_or(next, |n| n - 1);
+        let consumed = hunk
+            .lines
+            .iter()
+            .filter(|l| l.kind != LineKind::Added)
+            .count();
+        lines.extend(old[next..start].iter().map(|l| l.to_string()));
+        match decision {
+            Decision::Reject => {
+                lines.extend(old[start..start + consumed].iter().map(|l| l.to_string()))
+            }
+            Decision::Accept => lines.extend(
+                hunk.lines
+                    .iter()
+                    .filter(|l| l.kind != LineKind::Removed)
+                    .map(|l| l.text.clone()),
+            ),
+            Decision::Edit(text) => lines.extend(text.lines().map(str::to_string)),
+        }
+        next = start + consumed;
+    }
+    lines.extend(old[next..].iter().map(|l| l.to_string()));
+
+    let newline = if file.original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut content = lines.join(newline);
+    if !content.is_empty() && (file.original.ends_with('\n') || file.original.is_empty()) {
+        content.push_str(newline);
+    }
+    content
+}
+
+/// Decisions taken for the hunks of one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileOutcome {
+    pub path: String,
+    pub accepted: usize,
+    pub edited: usize,
+    pub rejected: usize,
+}
+
+/// Result of a review, once applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub files: Vec<FileOutcome>,
+}
+
+impl Outcome {
+    /// One line for the tool log, e.g. "Suggested edits: 3 of 4 hunks applied (1 rewritten)
+    /// in 2 files."
+    pub fn summary(&self) -> String {
+        let applied: usize = self.files.iter().map(|f| f.accepted + f.edited).sum();
+        let edited: usize = self.files.iter().map(|f| f.edited).sum();
+        let total: usize = applied + self.files.iter().map(|f| f.rejected).sum::<usize>();
+        let changed = self
+            .files
+            .iter()
+            .filter(|f| f.accepted + f.edited > 0)
+            .count();
+        let edited = if edited > 0 {
+            format!(" ({} rewritten)", edited)
+        } else {
+            String::new()
+        };
+        format!(
+            "Suggested edits: {} of {} hunk{} applied{} in {} file{}.",
+            applied,
+            total,
+            if total == 1 { "" } else { "s" },
+            edited,
+            changed,
+            if changed == 1 { "" } else { "s" }
+        )
+    }
+
+    /// Message telling the model what became of its suggestions.
+    pub fn report(&self) -> String {
+        let mut text =
+            "[Edit suggestions reviewed] The user reviewed the edits you suggested:".to_string();
+        for file in &self.files {
+            text.push_str(&format!(
+                "\n- {}: {} applied as suggested, {} applied as rewritten by the user, {} rejected",
+                file.path, file.accepted, file.edited, file.rejected
+            ));
+        }
+        text.push_str(
+            "\nRejected hunks were not written. Read a file again before editing it further.",
+        );
+        text
+    }
+}
+
+/// Message telling the model that none of its suggestions were written, because of `error`.
+pub fn failure_report(error: &SuggestError) -> String {
+    format!(
+        "[Edit suggestions reviewed] None of the edits you suggested were written: {}.",
+        error
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::EditTool;
+    use serde_json::json;
+
+    fn review(original: &str, proposed: &str) -> (tempfile::TempDir, Review) {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let full_path = dir.path().join("a.txt");
+        std::fs::write(&full_path, original).expect("write");
+        let set = ChangeSet {
+            files: vec![StagedFile {
+                path: "a.txt".to_string(),
+                full_path,
+                original: original.to_string(),
+                proposed: proposed.to_string(),
+            }],
+        };
+        (dir, Review::new(set))
+    }
+
+    #[test]
+    fn stage_builds_on_earlier_edits_of_the_same_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").expect("write");
+        let edit = |old: &str, new: &str| json!({ "file_path": "a.rs", "old_string": old, "new_string": new });
+        let turn = "test-stage-builds-on-earlier-edits";
+        let result = stage(turn, &EditTool, &edit("fn a", "fn b"), dir.path()).expect("staged");
+        assert!(result.starts_with(STAGED_HEADER));
+        stage(
+            turn,
+            &EditTool,
+            &edit("fn b() {}", "fn b() { c() }"),
+            dir.path(),
+        )
+        .expect("staged");
+        assert!(stage(turn, &EditTool, &edit("fn a", "x"), dir.path()).is_err());
+
+        // Nothing written until the review applies it.
+        let on_disk = std::fs::read_to_string(dir.path().join("a.rs")).expect("read");
+        assert_eq!(on_disk, "fn a() {}\n");
+        let set = take(turn).expect("change-set");
+        assert_eq!(set.files.len(), 1);
+        assert_eq!(set.files[0].proposed, "fn b() { c() }\n");
+        assert!(take(turn).is_none());
+    }
+
+    #[test]
+    fn apply_writes_accepted_and_rewritten_hunks_only() {
+        let original: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let proposed = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 10\n", "line ten\n")
+            .replace("line 18\n", "line eighteen\n");
+        let (dir, mut review) = review(&original, &proposed);
+        assert_eq!(review.hunks.len(), 3);
+        review.decide(Decision::Accept);
+        review.decide(Decision::Reject);
+        let (_, _, hunk) = review.current().expect("third hunk");
+        let rewritten = hunk_text(hunk).replace("line eighteen", "line 18 (kept)");
+        review.decide(Decision::Edit(rewritten));
+        assert!(review.is_done());
+
+        let mut batch = UndoBatch::default();
+        let outcome = review.apply(Some(&mut batch)).expect("applied");
+        assert!(!batch.is_empty());
+        let written = std::fs::read_to_string(dir.path().join("a.txt")).expect("read");
+        assert_eq!(
+            written,
+            original
+                .replace("line 2\n", "line two\n")
+                .replace("line 18\n", "line 18 (kept)\n")
+        );
+        assert_eq!(
+            outcome.summary(),
+            "Suggested edits: 2 of 3 hunks applied (1 rewritten) in 1 file."
+        );
+        assert!(outcome.report().contains("a.txt: 1 applied as suggested"));
+    }
+
+    #[test]
+    fn apply_refuses_files_changed_since_the_suggestion() {
+        let (dir, mut review) = review("a\nb\n", "a\nc\n");
+        std::fs::write(dir.path().join("a.txt"), "a\nb\nd\n").expect("write");
+        review.decide_rest(Decision::Accept);
+        assert!(matches!(review.apply(None), Err(SuggestError::Conflict(_))));
+        let on_disk = std::fs::read_to_string(dir.path().join("a.txt")).expect("read");
+        assert_eq!(on_disk, "a\nb\nd\n");
+    }
+}
@@ -10,12 +10,17 @@ use serde::{Deserialize, Serialize};
 use crate::core::paths;
 
 /// A user-defined template (custom slash command).
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CustomTemplate {
     pub name: String,
     pub description: String,
     pub prompt_prefix: String,
     pub mode: String,
+    /// Model pinned for this command (e.g. "openai/gpt-4o"); None uses the current model.
+    pub model: Option<String>,
+    /// JSON schema the replies of this command must match (structured output, see
+    /// `core::llm::structured`); None for free text.
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Error loading or saving templates.
@@ -80,6 +85,8 @@ pub fn save_templates(templates: &[CustomTemplate]) -> Result<(), TemplatesError
                 description: t.description.clone(),
                 prompt_prefix: t.prompt_prefix.clone(),
                 mode: t.mode.clone(),
+                model: t.model.clone(),
+                output_schema: t.output_schema.clone(),
             })
             .collect(),
     };
@@ -34,12 +34,16 @@ fn validate_rejects_duplicate_names() {
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             TemplateEntry {
                 name: "a".to_string(),
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
         ],
     };
@@ -55,6 +59,8 @@ fn validate_rejects_builtin_collision() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -69,6 +75,8 @@ fn validate_accepts_valid_custom() {
             description: "Audit".to_string(),
             prompt_prefix: "Check {cwd}".to_string(),
             mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let out = validate_and_convert(file, &builtin_set()).unwrap();
@@ -76,6 +84,22 @@ fn validate_accepts_valid_custom() {
     assert_eq!(out[0].name, "security");
 }
 
+#[test]
+fn validate_accepts_plan_mode() {
+    let file = TemplatesFile {
+        templates: vec![TemplateEntry {
+            name: "design".to_string(),
+            description: "Plan a change".to_string(),
+            prompt_prefix: "Plan".to_string(),
+            mode: "Plan".to_string(),
+            model: None,
+            output_schema: None,
+        }],
+    };
+    let out = validate_and_convert(file, &builtin_set()).unwrap();
+    assert_eq!(out[0].mode, "Plan");
+}
+
 #[test]
 fn validate_rejects_empty_name() {
     let file = TemplatesFile {
@@ -84,6 +108,8 @@ fn validate_rejects_empty_name() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -98,6 +124,8 @@ fn validate_rejects_name_with_spaces() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -116,6 +144,8 @@ fn validate_accepts_name_with_hyphens() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let out = validate_and_convert(file, &builtin_set()).unwrap();
@@ -131,6 +161,8 @@ fn validate_accepts_name_with_underscores() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let out = validate_and_convert(file, &builtin_set()).unwrap();
@@ -146,6 +178,8 @@ fn validate_rejects_name_with_special_chars() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -164,6 +198,8 @@ fn validate_rejects_builtin_collision_case_insensitive() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -179,12 +215,16 @@ fn validate_rejects_duplicate_names_case_insensitive() {
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             TemplateEntry {
                 name: "foo".to_string(),
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
         ],
     };
@@ -200,6 +240,8 @@ fn validate_rejects_invalid_mode() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Random".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -215,6 +257,8 @@ fn validate_rejects_mode_lowercase() {
             description: "x".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -229,6 +273,8 @@ fn validate_rejects_empty_description() {
             description: "".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -243,6 +289,8 @@ fn validate_rejects_whitespace_only_description() {
             description: "   \t  ".to_string(),
             prompt_prefix: "y".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -257,6 +305,8 @@ fn validate_rejects_empty_prompt_prefix() {
             description: "x".to_string(),
             prompt_prefix: "".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -271,6 +321,8 @@ fn validate_rejects_whitespace_only_prompt_prefix() {
             description: "x".to_string(),
             prompt_prefix: "\n\t  ".to_string(),
             mode: "Ask".to_string(),
+            model: None,
+            output_schema: None,
         }],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
@@ -293,12 +345,16 @@ fn validate_accepts_multiple_valid_templates() {
                 description: "First".to_string(),
                 prompt_prefix: "Do A".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             TemplateEntry {
                 name: "beta".to_string(),
                 description: "Second".to_string(),
                 prompt_prefix: "Do B".to_string(),
                 mode: "Build".to_string(),
+                model: None,
+                output_schema: None,
             },
         ],
     };
@@ -337,21 +393,88 @@ fn validate_fails_first_invalid_among_many() {
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             TemplateEntry {
                 name: "".to_string(),
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
             TemplateEntry {
                 name: "also invalid".to_string(),
                 description: "x".to_string(),
                 prompt_prefix: "y".to_string(),
                 mode: "Ask".to_string(),
+                model: None,
+                output_schema: None,
             },
         ],
     };
     let err = validate_and_convert(file, &builtin_set()).unwrap_err();
     assert!(err.to_string().contains("index 1") || err.to_string().contains("cannot be empty"));
 }
+
+#[test]
+fn validate_trims_model_and_treats_blank_as_none() {
+    let file = TemplatesFile {
+        templates: vec![
+            TemplateEntry {
+                name: "fast".to_string(),
+                description: "Fast".to_string(),
+                prompt_prefix: "Do".to_string(),
+                mode: "Ask".to_string(),
+                model: Some(" openai/gpt-4o-mini ".to_string()),
+                output_schema: None,
+            },
+            TemplateEntry {
+                name: "plain".to_string(),
+                description: "Plain".to_string(),
+                prompt_prefix: "Do".to_string(),
+                mode: "Ask".to_string(),
+                model: Some("  ".to_string()),
+                output_schema: None,
+            },
+        ],
+    };
+    let out = validate_and_convert(file, &builtin_set()).unwrap();
+    assert_eq!(out[0].model.as_deref(), Some("openai/gpt-4o-mini"));
+    assert_eq!(out[1].model, None);
+}
+
+#[test]
+fn templates_file_model_is_optional() {
+    let file: TemplatesFile = serde_json::from_str(
+        r#"{"templates": [{"name": "a", "description": "A", "prompt_prefix": "x", "mode": "Ask"}]}"#,
+    )
+    .unwrap();
+    let json = serde_json::to_string(&file).unwrap();
+    assert!(!json.contains("model"));
+}
+
+#[test]
+fn validate_accepts_object_output_schemas_only() {
+    let entry = |schema: serde_json::Value| TemplateEntry {
+        name: "todos".to_string(),
+        description: "List TODOs".to_string(),
+        prompt_prefix: "List the TODOs".to_string(),
+        mode: "Ask".to_string(),
+        model: None,
+        output_schema: Some(schema),
+    };
+    let schema = serde_json::json!({"type": "object", "required": ["todos"]});
+    let file = TemplatesFile {
+        templates: vec![entry(schema.clone())],
+    };
+    let result = validate_and_convert(file, &builtin_set()).unwrap();
+    assert_eq!(result[0].output_schema, Some(schema));
+
+    let file = TemplatesFile {
+        templates: vec![entry(serde_json::json!("object"))],
+    };
+    let err = validate_and_convert(file, &builtin_set()).unwrap_err();
+    assert!(err.to_string().contains("output_schema"));
+}
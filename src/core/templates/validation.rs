@@ -18,6 +18,10 @@ pub(crate) struct TemplateEntry {
     pub description: String,
     pub prompt_prefix: String,
     pub mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Validate file entries and convert to CustomTemplate list.
@@ -65,9 +69,9 @@ pub(crate) fn validate_and_convert(
         }
 
         // mode
-        if entry.mode != "Ask" && entry.mode != "Build" {
+        if !["Ask", "Build", "Plan"].contains(&entry.mode.as_str()) {
             return Err(TemplatesError::Validation(format!(
-                "Template '{}': mode must be 'Ask' or 'Build', got '{}'",
+                "Template '{}': mode must be 'Ask', 'Build', or 'Plan', got '{}'",
                 entry.name, entry.mode
             )));
         }
@@ -86,11 +90,37 @@ pub(crate) fn validate_and_convert(
             )));
         }
 
+        // model: optional; blank means "use the current model"
+        let model = entry
+            .model
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        if let Some(ref m) = model
+            && m.chars().any(char::is_whitespace)
+        {
+            return Err(TemplatesError::Validation(format!(
+                "Template '{}': model must be a model ID without spaces, got '{}'",
+                entry.name, m
+            )));
+        }
+
+        // output_schema: optional JSON schema object
+        if let Some(ref schema) = entry.output_schema
+            && !schema.is_object()
+        {
+            return Err(TemplatesError::Validation(format!(
+                "Template '{}': output_schema must be a JSON schema object",
+                entry.name
+            )));
+        }
+
         result.push(super::CustomTemplate {
             name: entry.name,
             description: entry.description,
             prompt_prefix: entry.prompt_prefix,
             mode: entry.mode,
+            model,
+            output_schema: entry.output_schema,
         });
     }
 
@@ -0,0 +1,385 @@
+//! Mock OpenAI-compatible server for smoke tests of the agent loop, without network access.
+//!
+//! The server answers `POST …/chat/completions` with a script of responses, in order: text
+//! replies, tool calls, or API errors. Streamed requests (`"stream": true`) get the reply as
+//! server-sent events, text in several chunks and tool call arguments split across deltas,
+//! as real providers send them; other requests get a single JSON response. Every request
+//! body is recorded for assertions.
+//!
+//! Built for the unit tests, and with the `testing` feature for the hidden `mock-server`
+//! subcommand, which serves a script file to try the TUI or prompt mode against it.
+
+// The builders and assertion helpers are for tests; the `mock-server` command only loads
+// script files.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// A tool call made by the mock model.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MockToolCall {
+    pub id: String,
+    pub name: String,
+    /// Arguments object, sent as a JSON string like real providers do.
+    pub arguments: Value,
+}
+
+/// What the mock model answers to one request.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MockReply {
+    /// An assistant message without tool calls (`finish_reason` "stop").
+    Text { content: String },
+    /// Tool calls, optionally with some text before them (`finish_reason` "tool_calls").
+    ToolCalls {
+        #[serde(default)]
+        content: String,
+        calls: Vec<MockToolCall>,
+    },
+    /// An API error with this HTTP status.
+    Error { status: u16, message: String },
+}
+
+/// A scripted response: the reply, sent after `delay_ms` (to test cancellation).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MockResponse {
+    #[serde(flatten)]
+    pub reply: MockReply,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+impl MockResponse {
+    pub fn text(content: &str) -> Self {
+        Self {
+            reply: MockReply::Text {
+                content: content.to_string(),
+            },
+            delay_ms: 0,
+        }
+    }
+
+    /// Tool calls given as (id, tool name, arguments).
+    pub fn tool_calls(calls: &[(&str, &str, Value)]) -> Self {
+        Self {
+            reply: MockReply::ToolCalls {
+                content: String::new(),
+                calls: calls
+                    .iter()
+                    .map(|(id, name, arguments)| MockToolCall {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        arguments: arguments.clone(),
+                    })
+                    .collect(),
+            },
+            delay_ms: 0,
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            reply: MockReply::Error {
+                status,
+                message: message.to_string(),
+            },
+            delay_ms: 0,
+        }
+    }
+
+    pub fn delayed(self, delay: Duration) -> Self {
+        Self {
+            delay_ms: delay.as_millis() as u64,
+            ..self
+        }
+    }
+}
+
+/// State shared with the server thread.
+#[derive(Default)]
+struct Shared {
+    script: Mutex<VecDeque<MockResponse>>,
+    requests: Mutex<Vec<Value>>,
+    stopped: AtomicBool,
+}
+
+/// A mock server on a local port; stopped when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl MockServer {
+    /// Serve `script` on a free local port.
+    pub fn start(script: Vec<MockResponse>) -> io::Result<Self> {
+        Self::bind(0, script)
+    }
+
+    /// Serve `script` on `port` of 127.0.0.1 (0: a free port).
+    pub fn bind(port: u16, script: Vec<MockResponse>) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            script: Mutex::new(script.into()),
+            ..Default::default()
+        });
+        let thread_shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_shared.stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let shared = Arc::clone(&thread_shared);
+                // One thread per connection: a delayed response does not hold the others.
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &shared) {
+                        log::debug!("Mock server connection: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Self { addr, shared })
+    }
+
+    /// API base URL to configure the client with (`http://127.0.0.1:PORT/v1`).
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    /// Bodies of the chat requests received so far, in order.
+    pub fn requests(&self) -> Vec<Value> {
+        self.shared
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Scripted responses not served yet.
+    pub fn remaining(&self) -> usize {
+        self.shared
+            .script
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Read one request and answer it; the connection is closed after the response.
+fn handle_connection(stream: TcpStream, shared: &Shared) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let mut stream = stream;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    if !path.ends_with("/chat/completions") {
+        return write_response(&mut stream, 404, "application/json", "{}");
+    }
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let streamed = body["stream"].as_bool().unwrap_or(false);
+    let model = body["model"].as_str().unwrap_or("mock").to_string();
+    shared
+        .requests
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(body);
+
+    let next = shared
+        .script
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pop_front();
+    let Some(response) = next else {
+        return write_error(&mut stream, 500, "Mock server script exhausted");
+    };
+    thread::sleep(Duration::from_millis(response.delay_ms));
+    match response.reply {
+        MockReply::Error { status, message } => write_error(&mut stream, status, &message),
+        reply if streamed => {
+            let events: String = stream_chunks(&model, &reply)
+                .iter()
+                .map(|chunk| format!("data: {}\n\n", chunk))
+                .chain(std::iter::once("data: [DONE]\n\n".to_string()))
+                .collect();
+            write_response(&mut stream, 200, "text/event-stream", &events)
+        }
+        reply => write_response(
+            &mut stream,
+            200,
+            "application/json",
+            &completion(&model, &reply).to_string(),
+        ),
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn write_error(stream: &mut TcpStream, status: u16, message: &str) -> io::Result<()> {
+    let body = json!({ "error": { "message": message, "code": status } });
+    write_response(stream, status, "application/json", &body.to_string())
+}
+
+/// Token usage reported with every reply.
+fn usage() -> Value {
+    json!({ "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 })
+}
+
+fn finish_reason(reply: &MockReply) -> &'static str {
+    match reply {
+        MockReply::ToolCalls { .. } => "tool_calls",
+        _ => "stop",
+    }
+}
+
+/// Non-streaming chat completion for `reply`.
+fn completion(model: &str, reply: &MockReply) -> Value {
+    let message = match reply {
+        MockReply::ToolCalls { content, calls } => json!({
+            "role": "assistant",
+            "content": content,
+            "tool_calls": calls.iter().map(|call| json!({
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments.to_string() },
+            })).collect::<Vec<_>>(),
+        }),
+        MockReply::Text { content } => json!({ "role": "assistant", "content": content }),
+        MockReply::Error { .. } => Value::Null,
+    };
+    json!({
+        "id": "mock",
+        "object": "chat.completion",
+        "model": model,
+        "choices": [{ "index": 0, "message": message, "finish_reason": finish_reason(reply) }],
+        "usage": usage(),
+    })
+}
+
+/// Streaming chunks for `reply`: text a few words at a time, each tool call's arguments in
+/// two deltas (the first with the id and name), then a final chunk with the finish reason
+/// and usage.
+fn stream_chunks(model: &str, reply: &MockReply) -> Vec<Value> {
+    let chunk = |delta: Value, finish: Option<&str>| {
+        json!({
+            "id": "mock",
+            "object": "chat.completion.chunk",
+            "model": model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish }],
+        })
+    };
+    let mut chunks = vec![chunk(json!({ "role": "assistant", "content": "" }), None)];
+    let (content, calls) = match reply {
+        MockReply::Text { content } => (content.as_str(), &[][..]),
+        MockReply::ToolCalls { content, calls } => (content.as_str(), calls.as_slice()),
+        MockReply::Error { .. } => ("", &[][..]),
+    };
+    for piece in content.split_inclusive(' ').collect::<Vec<_>>().chunks(3) {
+        chunks.push(chunk(json!({ "content": piece.concat() }), None));
+    }
+    for (index, call) in calls.iter().enumerate() {
+        let arguments = call.arguments.to_string();
+        let (head, tail) = arguments.split_at(arguments.floor_char_boundary(arguments.len() / 2));
+        chunks.push(chunk(
+            json!({ "tool_calls": [{
+                "index": index,
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": head },
+            }] }),
+            None,
+        ));
+        chunks.push(chunk(
+            json!({ "tool_calls": [{ "index": index, "function": { "arguments": tail } }] }),
+            None,
+        ));
+    }
+    let mut last = chunk(json!({}), Some(finish_reason(reply)));
+    last["usage"] = usage();
+    chunks.push(last);
+    chunks
+}
+
+/// Read a script file: a JSON array of responses, e.g.
+/// `[{"type": "tool_calls", "calls": [{"id": "c1", "name": "ListDir", "arguments": {}}]},
+///   {"type": "text", "content": "Done."}]`.
+pub fn load_script(path: &std::path::Path) -> Result<Vec<MockResponse>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid script {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_script_reads_replies_and_delays() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("script.json");
+        std::fs::write(
+            &path,
+            r#"[{"type": "tool_calls", "calls": [{"id": "c1", "name": "ListDir", "arguments": {"path": "."}}]},
+                {"type": "text", "content": "Done.", "delay_ms": 500},
+                {"type": "error", "status": 429, "message": "Slow down"}]"#,
+        )
+        .unwrap();
+        let script = load_script(&path).unwrap();
+        assert_eq!(
+            script,
+            vec![
+                MockResponse::tool_calls(&[("c1", "ListDir", json!({ "path": "." }))]),
+                MockResponse::text("Done.").delayed(Duration::from_millis(500)),
+                MockResponse::error(429, "Slow down"),
+            ]
+        );
+        assert!(load_script(&dir.path().join("missing.json")).is_err());
+    }
+}
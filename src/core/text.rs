@@ -0,0 +1,122 @@
+//! Display-width text measurement and truncation.
+//!
+//! Works on grapheme clusters and terminal column widths (unicode-width), so emoji,
+//! combining marks, and CJK text are never split mid-character and do not overflow.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Ellipsis appended or prepended when text is truncated (1 column wide).
+const ELLIPSIS: &str = "…";
+
+/// Terminal column width of a string.
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Truncate to at most `max_width` columns, keeping the start and ending with "…".
+pub fn truncate_end(s: &str, max_width: usize) -> String {
+    if display_width(s) <= max_width {
+        return s.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let budget = max_width - 1;
+    let mut out = String::new();
+    let mut width = 0;
+    for g in s.graphemes(true) {
+        let w = display_width(g);
+        if width + w > budget {
+            break;
+        }
+        out.push_str(g);
+        width += w;
+    }
+    out.push_str(ELLIPSIS);
+    out
+}
+
+/// Truncate to at most `max_width` columns, keeping the end and starting with "…".
+/// Suited to paths and model IDs where the tail is the informative part.
+pub fn truncate_start(s: &str, max_width: usize) -> String {
+    if display_width(s) <= max_width {
+        return s.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let budget = max_width - 1;
+    let mut tail: Vec<&str> = Vec::new();
+    let mut width = 0;
+    for g in s.graphemes(true).rev() {
+        let w = display_width(g);
+        if width + w > budget {
+            break;
+        }
+        tail.push(g);
+        width += w;
+    }
+    let mut out = String::from(ELLIPSIS);
+    out.extend(tail.into_iter().rev());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_end_short_string_unchanged() {
+        assert_eq!(truncate_end("hello", 10), "hello");
+        assert_eq!(truncate_end("hello", 5), "hello");
+    }
+
+    #[test]
+    fn truncate_end_adds_ellipsis() {
+        let result = truncate_end("hello world", 8);
+        assert_eq!(result, "hello w…");
+        assert_eq!(display_width(&result), 8);
+    }
+
+    #[test]
+    fn truncate_end_utf8_chars() {
+        assert_eq!(truncate_end("café au lait", 5), "café…");
+    }
+
+    #[test]
+    fn truncate_end_width_one_and_zero() {
+        assert_eq!(truncate_end("ab", 1), "…");
+        assert_eq!(truncate_end("ab", 0), "");
+    }
+
+    #[test]
+    fn truncate_end_cjk_counts_double_width() {
+        // Each CJK char is 2 columns: "你好" (4) + "…" (1) fits in 6, a third char would not.
+        let result = truncate_end("你好世界", 6);
+        assert_eq!(result, "你好…");
+        assert!(display_width(&result) <= 6);
+    }
+
+    #[test]
+    fn truncate_end_keeps_emoji_clusters_whole() {
+        // Family emoji is one grapheme made of several code points joined by ZWJ.
+        let s = "👨‍👩‍👧 family photo";
+        let result = truncate_end(s, 4);
+        assert!(result.starts_with("👨‍👩‍👧"));
+        assert!(result.ends_with('…'));
+    }
+
+    #[test]
+    fn truncate_start_keeps_tail() {
+        assert_eq!(truncate_start("/home/user/project", 8), "…project");
+        assert_eq!(truncate_start("short", 10), "short");
+    }
+
+    #[test]
+    fn truncate_start_cjk() {
+        let result = truncate_start("项目/目录", 5);
+        assert_eq!(result, "…目录");
+        assert!(display_width(&result) <= 5);
+    }
+}
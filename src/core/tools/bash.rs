@@ -1,8 +1,24 @@
 use serde::Deserialize;
 use serde_json::{Value, json};
-use std::process::Command;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 use super::{str_arg, tool_definition};
+use crate::core::command_rules::{self, RuleKind};
+use crate::core::remote;
+
+/// Tool result when the user cancels while the command runs.
+pub const CANCELLED_BY_USER: &str = "Command cancelled by user (process killed).";
+
+/// Default time limit for a command.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often a running command checks for cancellation.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Command prefixes (normalized, lowercase) that are considered destructive and require confirmation.
 const DESTRUCTIVE_PREFIXES: &[&str] = &[
@@ -17,17 +33,9 @@ pub struct BashArgs {
     pub command: String,
 }
 
-fn normalized_command(cmd: &str) -> String {
-    cmd.trim()
-        .to_lowercase()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ")
-}
-
 /// Returns true if the command is considered destructive (e.g. rm, rmdir) and should require user confirmation.
 pub fn is_destructive(command: &str) -> bool {
-    let n = normalized_command(command);
+    let n = command_rules::normalize(command);
     if n.is_empty() {
         return false;
     }
@@ -36,6 +44,16 @@ pub fn is_destructive(command: &str) -> bool {
         .any(|&prefix| n.starts_with(prefix))
 }
 
+/// Returns true if the command needs the user's confirmation: a deny rule matches it, or it is
+/// destructive and no allow rule matches it (see `core::command_rules`).
+pub fn needs_confirmation(command: &str) -> bool {
+    match command_rules::check(command) {
+        Some(RuleKind::Deny) => true,
+        Some(RuleKind::Allow) => false,
+        None => is_destructive(command),
+    }
+}
+
 pub struct BashTool;
 
 impl super::Tool for BashTool {
@@ -64,6 +82,18 @@ impl super::Tool for BashTool {
         str_arg(args, "command")
     }
 
+    fn simulate(&self, args: &Value, _working_dir: &Path) -> Result<String, super::ToolError> {
+        let command = str_arg(args, "command");
+        if command.is_empty() {
+            return Err("missing command argument".into());
+        }
+        Ok(format!("Would run: {}", command))
+    }
+
+    fn default_timeout(&self) -> Option<Duration> {
+        Some(DEFAULT_TIMEOUT)
+    }
+
     fn output_limit(&self) -> Option<usize> {
         Some(super::MAX_OUTPUT_LARGE)
     }
@@ -72,38 +102,144 @@ impl super::Tool for BashTool {
         true
     }
 
+    fn runs_remotely(&self) -> bool {
+        true
+    }
+
     fn may_need_confirmation(&self, args: &Value) -> bool {
         args.get("command")
             .and_then(|v| v.as_str())
-            .is_some_and(is_destructive)
+            .is_some_and(needs_confirmation)
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
         let parsed: BashArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        run_command(&parsed.command, working_dir, None)
+    }
 
-        let output = if cfg!(target_os = "windows") {
-            Command::new("cmd").args(["/C", &parsed.command]).output()
-        } else {
-            Command::new("sh").arg("-c").arg(&parsed.command).output()
-        };
-
-        match output {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-
-                if !stderr.is_empty() && !stdout.is_empty() {
-                    Ok(format!("{}\n{}", stderr, stdout))
-                } else if !stderr.is_empty() {
-                    Ok(stderr.to_string())
-                } else {
-                    Ok(stdout.to_string())
-                }
+    fn execute_cancellable(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, super::ToolError> {
+        let parsed: BashArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        run_command(&parsed.command, working_dir, Some(cancel))
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    // Own process group, so cancellation also stops the command's children.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+/// Kill the command and everything it started.
+fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pgid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill(2) with a negative pid signals the process group created in
+        // `shell_command`; it has no memory-safety preconditions.
+        unsafe { libc::kill(-pgid, libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Read a pipe to the end on a background thread.
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Run a shell command and return stderr followed by stdout. When `cancel` fires before the
+/// command exits, its process group is killed and `CANCELLED_BY_USER` is returned.
+fn run_command(
+    command: &str,
+    working_dir: &Path,
+    cancel: Option<&CancellationToken>,
+) -> Result<String, super::ToolError> {
+    if let Some(remote) = remote::current() {
+        return run_remote_command(remote, command, working_dir, cancel);
+    }
+    let mut child = shell_command(command).current_dir(working_dir).spawn()?;
+    let stdout_reader = spawn_reader(child.stdout.take());
+    let stderr_reader = spawn_reader(child.stderr.take());
+
+    match cancel {
+        Some(token) => loop {
+            if token.is_cancelled() {
+                kill_process_tree(&mut child);
+                // Readers finish once the killed processes close their pipes; not joined
+                // in case a detached grandchild keeps them open.
+                return Ok(CANCELLED_BY_USER.to_string());
+            }
+            if child.try_wait()?.is_some() {
+                break;
             }
-            Err(e) => Err(Box::new(e)),
+            thread::sleep(CANCEL_POLL_INTERVAL);
+        },
+        None => {
+            child.wait()?;
         }
     }
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+    Ok(combined_output(&stdout, &stderr))
+}
+
+/// Run a shell command on the remote host, in `working_dir` there. Cancelling closes the
+/// SSH session, which stops the command.
+fn run_remote_command(
+    remote: &remote::Remote,
+    command: &str,
+    working_dir: &Path,
+    cancel: Option<&CancellationToken>,
+) -> Result<String, super::ToolError> {
+    let script = format!(
+        "cd {} || exit 1\n{}",
+        remote::shell_quote(&working_dir.to_string_lossy()),
+        command
+    );
+    let cancel = cancel.cloned().unwrap_or_default();
+    match remote.run_cancellable(&script, &cancel)? {
+        Some(output) => Ok(combined_output(&output.stdout, &output.stderr)),
+        None => Ok(CANCELLED_BY_USER.to_string()),
+    }
+}
+
+/// Stderr followed by stdout.
+fn combined_output(stdout: &[u8], stderr: &[u8]) -> String {
+    let stdout = String::from_utf8_lossy(stdout);
+    let stderr = String::from_utf8_lossy(stderr);
+    if !stderr.is_empty() && !stdout.is_empty() {
+        format!("{}\n{}", stderr, stdout)
+    } else if !stderr.is_empty() {
+        stderr.to_string()
+    } else {
+        stdout.to_string()
+    }
 }
 
 #[cfg(test)]
@@ -144,6 +280,36 @@ mod tests {
         assert!(!is_destructive("echo hello"));
     }
 
+    #[test]
+    fn run_command_returns_output() {
+        let out = run_command("echo hello", Path::new("."), None).unwrap();
+        assert_eq!(out.trim(), "hello");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_runs_in_working_dir() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        std::fs::write(dir.path().join("marker.txt"), "").expect("write");
+        let out = run_command("ls", dir.path(), None).unwrap();
+        assert!(out.contains("marker.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_cancel_kills_process_group() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+        });
+        let started = std::time::Instant::now();
+        let out = run_command("sleep 30 & sleep 30; wait", Path::new("."), Some(&token)).unwrap();
+        assert_eq!(out, CANCELLED_BY_USER);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
     #[test]
     fn is_destructive_empty_returns_false() {
         assert!(!is_destructive(""));
@@ -5,9 +5,11 @@
 
 use serde::Deserialize;
 use serde_json::{Value, json};
-use std::fs;
+use std::path::Path;
 
-use super::{str_arg, tool_definition};
+use super::{ToolError, resolve_path, str_arg, tool_definition};
+use crate::core::remote::fs;
+use crate::core::{dry_run, redact};
 
 #[derive(Debug, Deserialize)]
 struct EditArgs {
@@ -52,36 +54,58 @@ impl super::Tool for EditTool {
         true
     }
 
+    fn runs_remotely(&self) -> bool {
+        true
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(str_arg(args, "file_path"))
+    }
+
+    fn stages_suggestions(&self) -> bool {
+        true
+    }
+
+    fn suggested_content(&self, args: &Value, content: &str) -> Result<String, ToolError> {
+        let parsed: EditArgs = serde_json::from_value(args.clone())?;
+        Ok(replace_unique(content, &parsed)?)
+    }
+
+    fn proposed_change(&self, args: &Value, working_dir: &Path) -> Option<(String, String)> {
+        let parsed: EditArgs = serde_json::from_value(args.clone()).ok()?;
+        let content = fs::read_to_string(resolve_path(working_dir, &parsed.file_path)).ok()?;
+        let new_content = replace_unique(&content, &parsed).ok()?;
+        Some((content, new_content))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         str_arg(args, "file_path")
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    /// The diff, or the error the edit would fail with (unlike `proposed_change`).
+    fn simulate(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
         let parsed: EditArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
-
-        let content = fs::read_to_string(&parsed.file_path)
+        let content = fs::read_to_string(resolve_path(working_dir, &parsed.file_path))
             .map_err(|e| format!("Cannot read file '{}': {}", parsed.file_path, e))?;
+        let new_content = replace_unique(&content, &parsed)?;
+        Ok(dry_run::change_summary(
+            &parsed.file_path,
+            &content,
+            &new_content,
+        ))
+    }
 
-        // Count occurrences
-        let count = content.matches(&parsed.old_string).count();
-        if count == 0 {
-            return Err(format!(
-                "old_string not found in '{}'. Make sure it matches the file content exactly (including whitespace and indentation).",
-                parsed.file_path
-            )
-            .into());
-        }
-        if count > 1 {
-            return Err(format!(
-                "old_string found {} times in '{}'. It must occur exactly once. Add more surrounding context to make it unique.",
-                count, parsed.file_path
-            )
-            .into());
-        }
-
-        let new_content = content.replacen(&parsed.old_string, &parsed.new_string, 1);
-        fs::write(&parsed.file_path, &new_content)
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        let parsed: EditArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        redact::check_no_new_placeholders(&parsed.old_string, &parsed.new_string)?;
+
+        let path = resolve_path(working_dir, &parsed.file_path);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read file '{}': {}", parsed.file_path, e))?;
+        let new_content = replace_unique(&content, &parsed)?;
+        fs::write(&path, &new_content)
             .map_err(|e| format!("Cannot write file '{}': {}", parsed.file_path, e))?;
 
         Ok(format!(
@@ -93,6 +117,25 @@ impl super::Tool for EditTool {
     }
 }
 
+/// `content` with the single occurrence of `old_string` replaced; an error when it occurs
+/// zero or several times.
+fn replace_unique(content: &str, args: &EditArgs) -> Result<String, String> {
+    let count = content.matches(&args.old_string).count();
+    if count == 0 {
+        return Err(format!(
+            "old_string not found in '{}'. Make sure it matches the file content exactly (including whitespace and indentation).",
+            args.file_path
+        ));
+    }
+    if count > 1 {
+        return Err(format!(
+            "old_string found {} times in '{}'. It must occur exactly once. Add more surrounding context to make it unique.",
+            count, args.file_path
+        ));
+    }
+    Ok(content.replacen(&args.old_string, &args.new_string, 1))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,7 +152,7 @@ mod tests {
             "old_string": "world",
             "new_string": "earth"
         });
-        let result = tool.execute(&args).expect("execute");
+        let result = tool.execute(&args, Path::new(".")).expect("execute");
         assert!(result.contains("OK"));
         assert_eq!(
             std::fs::read_to_string(file.path()).expect("read"),
@@ -117,6 +160,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn edit_refuses_to_write_a_redaction_placeholder() {
+        let file = tempfile::NamedTempFile::new().expect("temp file");
+        std::fs::write(file.path(), "API_KEY=real-secret-value\n").expect("write");
+        let args = json!({
+            "file_path": file.path().to_str().expect("path"),
+            "old_string": "API_KEY=real-secret-value",
+            "new_string": "API_KEY=[REDACTED]"
+        });
+        let err = EditTool.execute(&args, Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("[REDACTED]"));
+        assert_eq!(
+            std::fs::read_to_string(file.path()).expect("read"),
+            "API_KEY=real-secret-value\n"
+        );
+    }
+
     #[test]
     fn edit_fails_when_old_string_not_found() {
         let tool = EditTool;
@@ -127,7 +187,7 @@ mod tests {
             "old_string": "xyz",
             "new_string": "replacement"
         });
-        let err = tool.execute(&args).unwrap_err();
+        let err = tool.execute(&args, Path::new(".")).unwrap_err();
         assert!(err.to_string().contains("not found"));
     }
 
@@ -141,7 +201,7 @@ mod tests {
             "old_string": "foo",
             "new_string": "bar"
         });
-        let err = tool.execute(&args).unwrap_err();
+        let err = tool.execute(&args, Path::new(".")).unwrap_err();
         assert!(err.to_string().contains("found 3 times"));
     }
 }
@@ -5,7 +5,15 @@ use serde::Deserialize;
 use serde_json::{Value, json};
 use walkdir::WalkDir;
 
-use super::{GLOB_DEFAULT_MAX_RESULTS, default_search_path, ignore, str_arg, tool_definition};
+use std::path::Path;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    GLOB_DEFAULT_MAX_RESULTS, SEARCH_CANCELLED, SEARCH_DEFAULT_TIMEOUT, ToolOutput, ToolView,
+    default_search_path, ignore, resolve_path, search_path_arg, str_arg, tool_definition,
+};
+use crate::core::{permissions, remote};
 
 #[derive(Debug, Deserialize)]
 struct GlobArgs {
@@ -60,6 +68,14 @@ impl super::Tool for GlobTool {
         true
     }
 
+    fn available(&self) -> bool {
+        !remote::is_active()
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(search_path_arg(args))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         let pattern = str_arg(args, "pattern");
         let path = str_arg(args, "path");
@@ -70,7 +86,32 @@ impl super::Tool for GlobTool {
         }
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn default_timeout(&self) -> Option<Duration> {
+        Some(SEARCH_DEFAULT_TIMEOUT)
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_cancellable(args, working_dir, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, super::ToolError> {
+        self.execute_with_view(args, working_dir, cancel)
+            .map(|output| output.text)
+    }
+
+    /// Stops walking the tree when `cancel` fires (user interrupt or timeout). The matched
+    /// files are also returned as a tree view.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<ToolOutput, super::ToolError> {
         let parsed: GlobArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
 
@@ -78,19 +119,23 @@ impl super::Tool for GlobTool {
             .map_err(|e| format!("Invalid glob pattern: {}", e))?
             .compile_matcher();
 
-        let root = std::path::Path::new(&parsed.path);
+        let root = resolve_path(working_dir, &parsed.path);
+        let root = root.as_path();
         if !root.exists() {
             return Err(format!("Path does not exist: {}", parsed.path).into());
         }
 
-        let walker = WalkDir::new(root)
-            .into_iter()
-            .filter_entry(|e| !ignore::is_ignored(e));
+        let walker = WalkDir::new(root).into_iter().filter_entry(|e| {
+            !ignore::is_ignored(e) && !permissions::is_denied(working_dir, e.path())
+        });
 
         let mut results: Vec<String> = Vec::new();
         let mut total: usize = 0;
 
         for entry in walker.flatten() {
+            if cancel.is_cancelled() {
+                return Err(SEARCH_CANCELLED.into());
+            }
             if !entry.file_type().is_file() {
                 continue;
             }
@@ -106,17 +151,24 @@ impl super::Tool for GlobTool {
         }
 
         if results.is_empty() {
-            return Ok("No files matched the pattern.".to_string());
+            return Ok(ToolOutput::from(
+                "No files matched the pattern.".to_string(),
+            ));
         }
 
         let mut output = results.join("\n");
-        if total > parsed.max_results {
-            output.push_str(&format!(
-                "\n... ({} more files truncated)",
-                total - parsed.max_results
-            ));
+        let omitted = total.saturating_sub(parsed.max_results);
+        if omitted > 0 {
+            output.push_str(&format!("\n... ({} more files truncated)", omitted));
         }
 
-        Ok(output)
+        Ok(ToolOutput::new(
+            output,
+            ToolView::Tree {
+                root: parsed.path,
+                entries: results,
+                omitted,
+            },
+        ))
     }
 }
@@ -6,7 +6,17 @@ use serde_json::{Value, json};
 use std::fs;
 use walkdir::WalkDir;
 
-use super::{GREP_DEFAULT_MAX_RESULTS, default_search_path, ignore, str_arg, tool_definition};
+use std::path::Path;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::view::group_matches;
+use super::{
+    GREP_DEFAULT_MAX_RESULTS, MatchLine, SEARCH_CANCELLED, SEARCH_DEFAULT_TIMEOUT, ToolOutput,
+    ToolView, default_search_path, display_path, ignore, resolve_path, search_path_arg, str_arg,
+    tool_definition,
+};
+use crate::core::{permissions, remote};
 
 #[derive(Debug, Deserialize)]
 struct GrepArgs {
@@ -72,6 +82,14 @@ impl super::Tool for GrepTool {
         true
     }
 
+    fn runs_remotely(&self) -> bool {
+        true
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(search_path_arg(args))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         let pattern = str_arg(args, "pattern");
         let path = str_arg(args, "path");
@@ -82,31 +100,72 @@ impl super::Tool for GrepTool {
         }
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn default_timeout(&self) -> Option<Duration> {
+        Some(SEARCH_DEFAULT_TIMEOUT)
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_cancellable(args, working_dir, &CancellationToken::new())
+    }
+
+    fn execute_cancellable(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, super::ToolError> {
+        self.execute_with_view(args, working_dir, cancel)
+            .map(|output| output.text)
+    }
+
+    /// Stops walking the tree when `cancel` fires (user interrupt or timeout). The lines
+    /// found are also returned as matches grouped by file.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<ToolOutput, super::ToolError> {
         let parsed: GrepArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        if let Some(remote) = remote::current() {
+            let lines = search_remote(remote, &parsed, working_dir, cancel)?;
+            return Ok(matches_output(
+                lines,
+                parsed.max_results,
+                parsed.context_lines,
+            ));
+        }
 
         let re =
             Regex::new(&parsed.pattern).map_err(|e| format!("Invalid regex pattern: {}", e))?;
 
-        let root = std::path::Path::new(&parsed.path);
+        let root = resolve_path(working_dir, &parsed.path);
+        let root = root.as_path();
         if !root.exists() {
             return Err(format!("Path does not exist: {}", parsed.path).into());
         }
 
-        let mut results: Vec<String> = Vec::new();
+        let mut results: Vec<(String, MatchLine)> = Vec::new();
         let mut total_matches: usize = 0;
 
         // If path is a file, search just that file
         if root.is_file() {
-            search_file(root, &re, &parsed, &mut results, &mut total_matches);
+            let file = SearchedFile {
+                path: root,
+                display: display_path(working_dir, root),
+            };
+            search_file(&file, &re, &parsed, &mut results, &mut total_matches);
         } else {
             // Walk directory
-            let walker = WalkDir::new(root)
-                .into_iter()
-                .filter_entry(|e| !ignore::is_ignored(e));
+            let walker = WalkDir::new(root).into_iter().filter_entry(|e| {
+                !ignore::is_ignored(e) && !permissions::is_denied(working_dir, e.path())
+            });
 
             for entry in walker.flatten() {
+                if cancel.is_cancelled() {
+                    return Err(SEARCH_CANCELLED.into());
+                }
                 if !entry.file_type().is_file() {
                     continue;
                 }
@@ -123,7 +182,11 @@ impl super::Tool for GrepTool {
                     }
                 }
 
-                search_file(entry.path(), &re, &parsed, &mut results, &mut total_matches);
+                let file = SearchedFile {
+                    path: entry.path(),
+                    display: display_path(working_dir, entry.path()),
+                };
+                search_file(&file, &re, &parsed, &mut results, &mut total_matches);
 
                 if results.len() >= parsed.max_results {
                     break;
@@ -131,38 +194,180 @@ impl super::Tool for GrepTool {
             }
         }
 
-        if results.is_empty() {
-            return Ok("No matches found.".to_string());
-        }
+        Ok(grep_output(results, total_matches))
+    }
+}
 
-        let truncated = results.len() < total_matches;
-        let mut output = results.join("\n");
-        if truncated {
-            output.push_str(&format!(
-                "\n... ({} more matches truncated)",
-                total_matches - results.len()
-            ));
+/// Result text (`path:N:text` for matches, `path-N-text` for context lines) and view of
+/// the lines found, out of `total_matches` matches.
+fn grep_output(results: Vec<(String, MatchLine)>, total_matches: usize) -> ToolOutput {
+    if results.is_empty() {
+        return ToolOutput::from("No matches found.".to_string());
+    }
+
+    let omitted = total_matches.saturating_sub(results.len());
+    let mut output = results
+        .iter()
+        .map(|(path, line)| {
+            let sep = if line.is_match { ':' } else { '-' };
+            format!("{}{}{}{}{}", path, sep, line.number, sep, line.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if omitted > 0 {
+        output.push_str(&format!("\n... ({} more matches truncated)", omitted));
+    }
+
+    ToolOutput::new(
+        output,
+        ToolView::Matches {
+            files: group_matches(results),
+            omitted,
+        },
+    )
+}
+
+/// Lines printed by `rg` or `grep` on the remote host, in order. ripgrep is used when
+/// installed (same regex syntax as the local search), else `grep -E`; both skip the
+/// directories the local walk ignores.
+fn search_remote(
+    remote: &remote::Remote,
+    args: &GrepArgs,
+    working_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<Vec<RemoteLine>, super::ToolError> {
+    let quote = remote::shell_quote;
+    let context = match args.context_lines {
+        0 => String::new(),
+        n => format!(" -C {}", n),
+    };
+    let mut rg_filters = String::new();
+    let mut grep_filters = String::new();
+    if let Some(ref ext) = args.include {
+        let glob = quote(&format!("*.{}", ext));
+        rg_filters.push_str(&format!(" -g {}", glob));
+        grep_filters.push_str(&format!(" --include={}", glob));
+    }
+    for dir in ignore::IGNORED_DIRS {
+        rg_filters.push_str(&format!(" -g {}", quote(&format!("!{}/", dir))));
+        grep_filters.push_str(&format!(" --exclude-dir={}", quote(dir)));
+    }
+    // Enough lines for the results and their context; the rest is not transferred.
+    let cap = (args.max_results + 1) * (2 * args.context_lines + 2);
+    let script = format!(
+        "cd {dir} || exit 1\n\
+         if command -v rg >/dev/null 2>&1; then \
+         rg --no-heading --with-filename --line-number --null --color never{context}{rg_filters} -e {pattern} -- {path}; \
+         else grep -rnHI --null{context}{grep_filters} -E -e {pattern} -- {path}; fi | head -n {cap}",
+        dir = quote(&working_dir.to_string_lossy()),
+        pattern = quote(&args.pattern),
+        path = quote(&args.path),
+    );
+    let Some(output) = remote.run_cancellable(&script, cancel)? else {
+        return Err(SEARCH_CANCELLED.into());
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // The remote walk does not know the denied patterns: their lines are dropped here.
+    let lines: Vec<RemoteLine> = stdout
+        .lines()
+        .filter_map(parse_remote_line)
+        .filter(|line| match line {
+            RemoteLine::Result(path, _) => !permissions::is_denied(working_dir, Path::new(path)),
+            RemoteLine::Break => true,
+        })
+        .collect();
+    if lines.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            return Err(stderr.trim().to_string().into());
         }
+    }
+    Ok(lines)
+}
+
+/// A line of `rg`/`grep --null` output: a result, or `--` between non-adjacent groups.
+#[derive(Debug, PartialEq)]
+enum RemoteLine {
+    Result(String, MatchLine),
+    Break,
+}
+
+/// Parse `path\0N:text` (match) or `path\0N-text` (context line).
+fn parse_remote_line(line: &str) -> Option<RemoteLine> {
+    if line == "--" {
+        return Some(RemoteLine::Break);
+    }
+    let (path, rest) = line.split_once('\0')?;
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let number = rest[..digits].parse().ok()?;
+    let is_match = match rest[digits..].chars().next()? {
+        ':' => true,
+        '-' => false,
+        _ => return None,
+    };
+    Some(RemoteLine::Result(
+        path.trim_start_matches("./").to_string(),
+        MatchLine {
+            number,
+            text: rest[digits + 1..].to_string(),
+            is_match,
+        },
+    ))
+}
 
-        Ok(output)
+/// Keep the first `max_results` matches with their context lines, like the local search.
+fn matches_output(lines: Vec<RemoteLine>, max_results: usize, context_lines: usize) -> ToolOutput {
+    let mut results: Vec<(String, MatchLine)> = Vec::new();
+    let mut total_matches = 0;
+    // After the last kept match: its trailing context lines, until a gap or another file.
+    let mut trailing: Option<usize> = None;
+    for line in lines {
+        let RemoteLine::Result(path, line) = line else {
+            trailing = trailing.map(|_| 0);
+            continue;
+        };
+        if line.is_match {
+            total_matches += 1;
+            if total_matches == max_results {
+                trailing = Some(context_lines);
+            } else if total_matches > max_results {
+                trailing = Some(0);
+                continue;
+            }
+        } else if let Some(left) = trailing.as_mut() {
+            let same_file = results.last().is_some_and(|(last, _)| *last == path);
+            if *left == 0 || !same_file {
+                *left = 0;
+                continue;
+            }
+            *left -= 1;
+        }
+        results.push((path, line));
     }
+    grep_output(results, total_matches)
+}
+
+/// File to search and the path printed in its results.
+struct SearchedFile<'a> {
+    path: &'a Path,
+    display: String,
 }
 
 /// Search a single file for regex matches with optional context lines.
 fn search_file(
-    path: &std::path::Path,
+    file: &SearchedFile<'_>,
     re: &Regex,
     args: &GrepArgs,
-    results: &mut Vec<String>,
+    results: &mut Vec<(String, MatchLine)>,
     total_matches: &mut usize,
 ) {
-    let content = match fs::read_to_string(path) {
+    let content = match fs::read_to_string(file.path) {
         Ok(c) => c,
         Err(_) => return, // skip binary / unreadable files
     };
 
     let lines: Vec<&str> = content.lines().collect();
-    let path_str = path.display().to_string();
+    let path_str = &file.display;
 
     // Find all matching line indices
     let matching: Vec<usize> = lines
@@ -183,17 +388,47 @@ fn search_file(
             return;
         }
 
-        if args.context_lines == 0 {
-            results.push(format!("{}:{}:{}", path_str, line_idx + 1, lines[line_idx]));
-        } else {
-            let start = line_idx.saturating_sub(args.context_lines);
-            let end = (line_idx + args.context_lines + 1).min(lines.len());
+        let start = line_idx.saturating_sub(args.context_lines);
+        let end = (line_idx + args.context_lines + 1).min(lines.len());
 
-            for (idx, line) in lines[start..end].iter().enumerate() {
-                let i = start + idx;
-                let prefix = if i == line_idx { ":" } else { "-" };
-                results.push(format!("{}{}{}{}{}", path_str, prefix, i + 1, prefix, line));
-            }
+        for (idx, line) in lines[start..end].iter().enumerate() {
+            let i = start + idx;
+            results.push((
+                path_str.clone(),
+                MatchLine {
+                    number: i + 1,
+                    text: line.to_string(),
+                    is_match: i == line_idx,
+                },
+            ));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_lines_keep_max_results_with_trailing_context() {
+        let out = "./src/a.rs\u{0}3-fn a() {\n./src/a.rs\u{0}4:    hello();\n./src/a.rs\u{0}5-}\n--\nsrc/b.rs\u{0}10:hello\nsrc/b.rs\u{0}11-after\n";
+        let lines: Vec<RemoteLine> = out.lines().filter_map(parse_remote_line).collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[3], RemoteLine::Break);
+
+        let output = matches_output(lines, 1, 1);
+        let ToolView::Matches { files, omitted } = output.view.expect("view") else {
+            panic!("expected matches");
+        };
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "src/a.rs");
+        assert_eq!(files[0].lines.len(), 3);
+        assert!(files[0].lines[1].is_match);
+        assert_eq!(omitted, 0);
+        assert!(
+            output
+                .text
+                .starts_with("src/a.rs-3-fn a() {\nsrc/a.rs:4:    hello();")
+        );
+    }
+}
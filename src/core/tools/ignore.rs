@@ -1,10 +1,16 @@
 //! Smart-ignore helper for directory traversal.
 //!
 //! Filters out common junk directories (node_modules, target, .git, etc.)
-//! used by Grep, ListDir, and Glob tools.
+//! used by Grep, ListDir, and Glob tools, and reads the workspace `.gitignore`
+//! for the file tree popup.
+
+use std::fs;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
 
 /// Directories always skipped during traversal.
-const IGNORED_DIRS: &[&str] = &[
+pub(crate) const IGNORED_DIRS: &[&str] = &[
     "node_modules",
     "target",
     ".git",
@@ -24,3 +30,94 @@ pub fn is_ignored(entry: &walkdir::DirEntry) -> bool {
             .to_str()
             .is_some_and(|n| IGNORED_DIRS.contains(&n))
 }
+
+/// One `.gitignore` line.
+struct GitIgnoreRule {
+    matcher: GlobMatcher,
+    /// `!pattern`: re-include what an earlier rule ignored.
+    negated: bool,
+    /// `pattern/`: directories only.
+    dir_only: bool,
+}
+
+/// Patterns of the `.gitignore` at the workspace root. Nested `.gitignore` files are not
+/// read; a negation cannot re-include a file inside an ignored directory (as with git).
+#[derive(Default)]
+pub struct GitIgnore {
+    rules: Vec<GitIgnoreRule>,
+}
+
+impl GitIgnore {
+    /// Rules of `root/.gitignore`; none when the file is missing or unreadable.
+    pub fn load(root: &Path) -> Self {
+        fs::read_to_string(root.join(".gitignore"))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Rules of a `.gitignore` file. Invalid patterns are skipped.
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_end();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let (negated, pattern) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, line),
+                };
+                let dir_only = pattern.ends_with('/');
+                let pattern = pattern.trim_end_matches('/');
+                // A slash at the start or in the middle anchors the pattern to the root.
+                let glob = match pattern.strip_prefix('/') {
+                    Some(anchored) => anchored.to_string(),
+                    None if pattern.contains('/') => pattern.to_string(),
+                    None => format!("**/{}", pattern),
+                };
+                let matcher = GlobBuilder::new(&glob)
+                    .literal_separator(true)
+                    .build()
+                    .ok()?
+                    .compile_matcher();
+                Some(GitIgnoreRule {
+                    matcher,
+                    negated,
+                    dir_only,
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// True if `relative` (a path from the root) is ignored; the last matching rule wins.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(relative))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitignore_rules_anchor_negate_and_match_directories() {
+        let ignore =
+            GitIgnore::parse("# build output\n*.log\n!keep.log\n/out\ncoverage/\ndocs/*.html\n\n");
+        assert!(ignore.is_ignored(Path::new("app.log"), false));
+        assert!(ignore.is_ignored(Path::new("src/deep/app.log"), false));
+        assert!(!ignore.is_ignored(Path::new("keep.log"), false));
+        assert!(ignore.is_ignored(Path::new("out"), true));
+        assert!(!ignore.is_ignored(Path::new("src/out"), true));
+        assert!(ignore.is_ignored(Path::new("coverage"), true));
+        assert!(!ignore.is_ignored(Path::new("coverage"), false));
+        assert!(ignore.is_ignored(Path::new("docs/index.html"), false));
+        assert!(!ignore.is_ignored(Path::new("docs/api/index.html"), false));
+        assert!(!ignore.is_ignored(Path::new("src/main.rs"), false));
+    }
+}
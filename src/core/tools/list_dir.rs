@@ -4,7 +4,11 @@ use serde::Deserialize;
 use serde_json::{Value, json};
 use walkdir::WalkDir;
 
-use super::{ignore, str_arg, tool_definition};
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+use super::{ToolOutput, ToolView, ignore, resolve_path, str_arg, tool_definition};
+use crate::core::{permissions, remote};
 
 #[derive(Debug, Deserialize)]
 struct ListDirArgs {
@@ -53,15 +57,35 @@ impl super::Tool for ListDirTool {
         true
     }
 
+    fn available(&self) -> bool {
+        !remote::is_active()
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(str_arg(args, "path"))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         str_arg(args, "path")
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_with_view(args, working_dir, &CancellationToken::new())
+            .map(|output| output.text)
+    }
+
+    /// The entries are also returned as a tree view.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        _cancel: &CancellationToken,
+    ) -> Result<ToolOutput, super::ToolError> {
         let parsed: ListDirArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
 
-        let root = std::path::Path::new(&parsed.path);
+        let root = resolve_path(working_dir, &parsed.path);
+        let root = root.as_path();
         if !root.exists() {
             return Err(format!("Path does not exist: {}", parsed.path).into());
         }
@@ -72,7 +96,9 @@ impl super::Tool for ListDirTool {
         let walker = WalkDir::new(root)
             .max_depth(parsed.max_depth)
             .into_iter()
-            .filter_entry(|e| !ignore::is_ignored(e));
+            .filter_entry(|e| {
+                !ignore::is_ignored(e) && !permissions::is_denied(working_dir, e.path())
+            });
 
         let mut dirs: Vec<String> = Vec::new();
         let mut files: Vec<String> = Vec::new();
@@ -102,9 +128,16 @@ impl super::Tool for ListDirTool {
         output.append(&mut files);
 
         if output.is_empty() {
-            return Ok("Directory is empty.".to_string());
+            return Ok(ToolOutput::from("Directory is empty.".to_string()));
         }
 
-        Ok(output.join("\n"))
+        Ok(ToolOutput::new(
+            output.join("\n"),
+            ToolView::Tree {
+                root: parsed.path,
+                entries: output,
+                omitted: 0,
+            },
+        ))
     }
 }
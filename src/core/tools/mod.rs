@@ -2,21 +2,40 @@ mod bash;
 mod edit;
 mod glob_tool;
 mod grep;
-mod ignore;
+pub(crate) mod ignore;
 mod list_dir;
+mod preview_data;
 mod read;
+mod replace_all;
+mod semantic_search;
+mod symbols;
+mod task;
+mod task_plan;
+mod view;
 mod write;
 
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::dry_run;
 
 pub use bash::BashTool;
 pub use edit::EditTool;
 pub use glob_tool::GlobTool;
 pub use grep::GrepTool;
 pub use list_dir::ListDirTool;
+pub use preview_data::PreviewDataTool;
 pub use read::ReadTool;
+pub use replace_all::ReplaceAllTool;
+pub use semantic_search::SemanticSearchTool;
+pub use symbols::SymbolsTool;
+pub use task::{DEFAULT_TASK_TURNS, MAX_TASK_TURNS, TaskArgs, TaskTool};
+pub use task_plan::TaskPlanTool;
+pub use view::{FileMatches, MatchLine, PlanStep, StepStatus, ToolOutput, ToolView};
 pub use write::WriteTool;
 
 /// Default path for search tools (current directory).
@@ -33,6 +52,12 @@ pub const GREP_DEFAULT_MAX_RESULTS: usize = 50;
 /// Default max results for Glob (files).
 pub const GLOB_DEFAULT_MAX_RESULTS: usize = 100;
 
+/// Default time limit for Grep and Glob.
+pub const SEARCH_DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Result of a Grep or Glob walk stopped by cancellation.
+const SEARCH_CANCELLED: &str = "Search cancelled before it finished.";
+
 /// Helper to extract a string argument from tool args JSON.
 pub fn str_arg(args: &Value, key: &str) -> String {
     args.get(key)
@@ -41,6 +66,30 @@ pub fn str_arg(args: &Value, key: &str) -> String {
         .to_string()
 }
 
+/// `path` argument of the search tools, `.` when omitted (their target for the path
+/// permissions).
+pub fn search_path_arg(args: &Value) -> String {
+    let path = str_arg(args, "path");
+    if path.is_empty() {
+        default_search_path()
+    } else {
+        path
+    }
+}
+
+/// Path argument resolved against the working directory (absolute paths are kept).
+pub fn resolve_path(working_dir: &Path, path: &str) -> PathBuf {
+    working_dir.join(path)
+}
+
+/// Path as shown in tool output: relative to the working directory when inside it.
+pub fn display_path(working_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(working_dir)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
 /// Helper to build the standard tool definition structure for the API.
 pub fn tool_definition(name: &str, description: &str, parameters: Value) -> Value {
     json!({
@@ -53,6 +102,45 @@ pub fn tool_definition(name: &str, description: &str, parameters: Value) -> Valu
     })
 }
 
+/// Per-tool timeouts in seconds from the workspace settings, replacing the tools' defaults
+/// (0 removes the limit). Tool names match case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolTimeouts(Vec<(String, u64)>);
+
+impl ToolTimeouts {
+    pub fn new(overrides: impl IntoIterator<Item = (String, u64)>) -> Self {
+        ToolTimeouts(overrides.into_iter().collect())
+    }
+
+    /// Time limit of a call to `tool`. Tools without a default timeout cannot be stopped,
+    /// so overrides for them are ignored.
+    pub fn for_tool(&self, tool: &dyn Tool) -> Option<Duration> {
+        let default = tool.default_timeout()?;
+        match self
+            .0
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tool.name()))
+        {
+            Some((_, 0)) => None,
+            Some((_, secs)) => Some(Duration::from_secs(*secs)),
+            None => Some(default),
+        }
+    }
+
+    /// Overridden tool names that have no timeout (unknown or not stoppable).
+    pub fn unsupported(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| {
+                !all()
+                    .iter()
+                    .any(|t| t.name().eq_ignore_ascii_case(name) && t.default_timeout().is_some())
+            })
+            .collect()
+    }
+}
+
 /// Error type for tool execution (Send + Sync for use across async/thread boundaries).
 pub type ToolError = Box<dyn std::error::Error + Send + Sync>;
 
@@ -69,8 +157,39 @@ pub trait Tool: Send + Sync {
     fn definition(&self) -> Value;
     /// Short preview of arguments for display in progress/log (e.g. path, command).
     fn args_preview(&self, args: &Value) -> String;
-    /// Execute the tool with the given arguments. Returns output string or error.
-    fn execute(&self, args: &Value) -> Result<String, ToolError>;
+    /// Execute the tool with the given arguments. Relative paths (and Bash commands) run in
+    /// `working_dir`. Returns output string or error.
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, ToolError>;
+
+    /// Execute, stopping early when `cancel` fires. Default: ignores the token.
+    /// Long-running tools (Bash) override this so Esc interrupts them.
+    fn execute_cancellable(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, ToolError> {
+        let _ = cancel;
+        self.execute(args, working_dir)
+    }
+
+    /// Execute like `execute_cancellable`, also returning a typed view of the result for
+    /// display (see `ToolView`). The model only gets the text. Default: no view.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<ToolOutput, ToolError> {
+        self.execute_cancellable(args, working_dir, cancel)
+            .map(ToolOutput::from)
+    }
+
+    /// Optional: time limit of a call, after which it is cancelled and the model is told it
+    /// timed out. Only tools that stop when cancelled set one. Default: None.
+    fn default_timeout(&self) -> Option<Duration> {
+        None
+    }
 
     /// Optional: max output size in bytes. Default: None (unlimited).
     fn output_limit(&self) -> Option<usize> {
@@ -82,6 +201,24 @@ pub trait Tool: Send + Sync {
         false
     }
 
+    /// Optional: allowed in Plan mode? Default: everything allowed in Ask mode.
+    fn allowed_in_plan_mode(&self) -> bool {
+        !self.disabled_in_ask_mode()
+    }
+
+    /// Optional: usable with the current configuration? Unavailable tools are never offered
+    /// to the model. Default: true.
+    fn available(&self) -> bool {
+        true
+    }
+
+    /// Optional: runs on the remote host in a remote workspace (see `core::remote`)? Tools
+    /// that read the workspace without this report themselves unavailable there.
+    /// Default: false.
+    fn runs_remotely(&self) -> bool {
+        false
+    }
+
     /// Whether this tool is read-only (no side effects). Read-only tools can run in parallel.
     fn is_read_only(&self) -> bool {
         false
@@ -93,6 +230,43 @@ pub trait Tool: Send + Sync {
         false
     }
 
+    /// Optional: file or directory the tool reads or writes, checked against the path
+    /// permissions (see `core::permissions`). Default: None (no path).
+    fn target_path(&self, args: &Value) -> Option<String> {
+        let _ = args;
+        None
+    }
+
+    /// Optional: content of the target file before and after this call, shown as a diff
+    /// when the call needs approval. Default: None (no preview).
+    fn proposed_change(&self, args: &Value, working_dir: &Path) -> Option<(String, String)> {
+        let _ = (args, working_dir);
+        None
+    }
+
+    /// Optional: what the call would do, without doing it, for dry-run mode (see
+    /// `core::dry_run`). Only called for tools with side effects. Default: the diff of
+    /// `proposed_change` when there is one, else the call itself.
+    fn simulate(&self, args: &Value, working_dir: &Path) -> Result<String, ToolError> {
+        Ok(match self.proposed_change(args, working_dir) {
+            Some((old, new)) => dry_run::change_summary(&self.args_preview(args), &old, &new),
+            None => format!("Would run {}: {}", self.name(), self.args_preview(args)),
+        })
+    }
+
+    /// Optional: are calls staged as suggestions in suggest mode (see `core::suggest`)
+    /// instead of run? Such tools implement `suggested_content`. Default: false.
+    fn stages_suggestions(&self) -> bool {
+        false
+    }
+
+    /// Optional: `content` of the target file with this call applied, without writing it.
+    /// Only called when `stages_suggestions` is true.
+    fn suggested_content(&self, args: &Value, content: &str) -> Result<String, ToolError> {
+        let _ = (args, content);
+        Err(format!("{} cannot be staged as a suggestion", self.name()).into())
+    }
+
     /// Optional: is this path an init file (AGENT.md/AGENTS.md) that should be written only once per session? Default: false.
     fn is_init_file_target(&self, file_path: &str) -> bool {
         let _ = file_path;
@@ -109,9 +283,15 @@ fn init_tools() -> Vec<Box<dyn Tool>> {
         Box::new(ReadTool),
         Box::new(WriteTool),
         Box::new(EditTool),
+        Box::new(ReplaceAllTool),
         Box::new(GrepTool),
+        Box::new(SymbolsTool),
         Box::new(ListDirTool),
         Box::new(GlobTool),
+        Box::new(SemanticSearchTool),
+        Box::new(PreviewDataTool),
+        Box::new(TaskPlanTool),
+        Box::new(TaskTool),
     ]
 }
 
@@ -141,15 +321,37 @@ mod tests {
         assert_eq!(str_arg(&args, "path"), "");
     }
 
+    #[test]
+    fn path_tools_report_their_target_path() {
+        let with_path = serde_json::json!({"pattern": ".", "path": "~/.ssh/id_rsa"});
+        for tool in init_tools() {
+            match tool.name() {
+                "Read" | "Write" | "Edit" | "PreviewData" => {}
+                "Grep" | "Glob" | "ListDir" | "Symbols" | "ReplaceAll" => {
+                    assert_eq!(
+                        tool.target_path(&with_path).as_deref(),
+                        Some("~/.ssh/id_rsa"),
+                        "{}",
+                        tool.name()
+                    );
+                }
+                _ => assert_eq!(tool.target_path(&with_path), None, "{}", tool.name()),
+            }
+        }
+        let without_path = serde_json::json!({"pattern": "."});
+        assert_eq!(GrepTool.target_path(&without_path).as_deref(), Some("."));
+    }
+
     #[test]
     fn read_only_tools() {
         let tools = init_tools();
         for tool in &tools {
             match tool.name() {
-                "Read" | "Grep" | "ListDir" | "Glob" => {
+                "Read" | "Grep" | "Symbols" | "ListDir" | "Glob" | "SemanticSearch"
+                | "PreviewData" | "TaskPlan" | "Task" => {
                     assert!(tool.is_read_only(), "{} should be read-only", tool.name());
                 }
-                "Bash" | "Write" | "Edit" => {
+                "Bash" | "Write" | "Edit" | "ReplaceAll" => {
                     assert!(
                         !tool.is_read_only(),
                         "{} should NOT be read-only",
@@ -161,6 +363,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tool_timeouts_override_defaults() {
+        let timeouts = ToolTimeouts::new([
+            ("bash".to_string(), 300),
+            ("Grep".to_string(), 0),
+            ("Read".to_string(), 5),
+        ]);
+        assert_eq!(timeouts.for_tool(&BashTool), Some(Duration::from_secs(300)));
+        assert_eq!(timeouts.for_tool(&GrepTool), None);
+        assert_eq!(timeouts.for_tool(&GlobTool), Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.for_tool(&ReadTool), None);
+        assert_eq!(timeouts.unsupported(), vec!["Read"]);
+    }
+
     #[test]
     fn tool_definition_structure() {
         let def = tool_definition(
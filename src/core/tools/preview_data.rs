@@ -0,0 +1,623 @@
+//! PreviewData tool — schema, row count, and first rows of a CSV/TSV/JSON/Parquet data file.
+//!
+//! Cheaper than Read for data files: the model sees column names and types plus a
+//! compact table of the first rows instead of the raw bytes. Parquet types come from the
+//! file schema and the row count from its footer, so only the rows shown are decoded.
+
+use parquet::basic::{ConvertedType, LogicalType, Repetition, Type as PhysicalType};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::schema::types::Type as ParquetType;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+use crate::core::remote;
+use crate::core::text::truncate_end;
+
+use super::{ToolOutput, ToolView, resolve_path, str_arg, tool_definition};
+
+/// Default number of rows shown.
+const DEFAULT_ROWS: usize = 10;
+/// Upper bound on rows shown, whatever the model asks for.
+const MAX_ROWS: usize = 100;
+/// Max display width of a table cell.
+const MAX_CELL_WIDTH: usize = 40;
+
+#[derive(Debug, Deserialize)]
+struct PreviewDataArgs {
+    file_path: String,
+    #[serde(default = "default_rows")]
+    rows: usize,
+}
+
+fn default_rows() -> usize {
+    DEFAULT_ROWS
+}
+
+/// Inferred column type, widened as more values are seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    /// No non-empty value seen yet.
+    Empty,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Object,
+    Array,
+    Mixed,
+}
+
+impl ColumnType {
+    fn label(self) -> &'static str {
+        match self {
+            ColumnType::Empty => "empty",
+            ColumnType::Bool => "bool",
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::String => "string",
+            ColumnType::Object => "object",
+            ColumnType::Array => "array",
+            ColumnType::Mixed => "mixed",
+        }
+    }
+
+    /// Combine with the type of another value in the same column.
+    fn widen(self, other: ColumnType) -> ColumnType {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Empty, b) => b,
+            (a, Empty) => a,
+            (Integer, Float) | (Float, Integer) => Float,
+            _ => Mixed,
+        }
+    }
+
+    /// Type of a CSV cell; numbers and booleans are recognized, everything else is a string.
+    fn of_text(cell: &str) -> ColumnType {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            ColumnType::Empty
+        } else if cell.parse::<i64>().is_ok() {
+            ColumnType::Integer
+        } else if cell.parse::<f64>().is_ok() {
+            ColumnType::Float
+        } else if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+            ColumnType::Bool
+        } else {
+            ColumnType::String
+        }
+    }
+
+    fn of_json(value: &Value) -> ColumnType {
+        match value {
+            Value::Null => ColumnType::Empty,
+            Value::Bool(_) => ColumnType::Bool,
+            Value::Number(n) if n.is_i64() || n.is_u64() => ColumnType::Integer,
+            Value::Number(_) => ColumnType::Float,
+            Value::String(_) => ColumnType::String,
+            Value::Object(_) => ColumnType::Object,
+            Value::Array(_) => ColumnType::Array,
+        }
+    }
+
+    /// Type of a top-level Parquet field, from its physical and logical types. Dates,
+    /// times, and binary values are shown as text.
+    fn of_parquet(field: &ParquetType) -> ColumnType {
+        let info = field.get_basic_info();
+        if info.has_repetition() && info.repetition() == Repetition::REPEATED {
+            return ColumnType::Array;
+        }
+        if field.is_group() {
+            return match (info.logical_type(), info.converted_type()) {
+                (Some(LogicalType::List), _) | (_, ConvertedType::LIST) => ColumnType::Array,
+                _ => ColumnType::Object,
+            };
+        }
+        match field.get_physical_type() {
+            PhysicalType::BOOLEAN => ColumnType::Bool,
+            PhysicalType::FLOAT | PhysicalType::DOUBLE => ColumnType::Float,
+            PhysicalType::INT32 | PhysicalType::INT64 => match info.logical_type() {
+                Some(LogicalType::Integer { .. }) => ColumnType::Integer,
+                Some(LogicalType::Decimal { .. }) => ColumnType::Float,
+                Some(_) => ColumnType::String,
+                None => match info.converted_type() {
+                    ConvertedType::DECIMAL => ColumnType::Float,
+                    ConvertedType::DATE
+                    | ConvertedType::TIME_MILLIS
+                    | ConvertedType::TIME_MICROS
+                    | ConvertedType::TIMESTAMP_MILLIS
+                    | ConvertedType::TIMESTAMP_MICROS => ColumnType::String,
+                    _ => ColumnType::Integer,
+                },
+            },
+            PhysicalType::INT96 | PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY => {
+                match info.logical_type() {
+                    Some(LogicalType::Decimal { .. }) => ColumnType::Float,
+                    _ if info.converted_type() == ConvertedType::DECIMAL => ColumnType::Float,
+                    _ => ColumnType::String,
+                }
+            }
+        }
+    }
+}
+
+/// Tabular view of a data file: columns with types, total rows, and the first rows.
+struct DataPreview {
+    format: String,
+    columns: Vec<(String, ColumnType)>,
+    row_count: usize,
+    rows: Vec<Vec<String>>,
+}
+
+pub struct PreviewDataTool;
+
+impl super::Tool for PreviewDataTool {
+    fn name(&self) -> &'static str {
+        "PreviewData"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "Preview a data file (CSV, TSV, JSON array of objects, JSON Lines, Parquet): column names and inferred types, total row count, and the first rows as a compact table. Prefer this over Read for data files.",
+            json!({
+                "type": "object",
+                "required": ["file_path"],
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the data file (.csv, .tsv, .json, .jsonl, .ndjson, .parquet)"
+                    },
+                    "rows": {
+                        "type": "integer",
+                        "description": "Number of rows to show (default: 10, max: 100)"
+                    }
+                }
+            }),
+        )
+    }
+
+    fn output_limit(&self) -> Option<usize> {
+        Some(super::MAX_OUTPUT_SMALL)
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn available(&self) -> bool {
+        !remote::is_active()
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(str_arg(args, "file_path"))
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        str_arg(args, "file_path")
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_with_view(args, working_dir, &CancellationToken::new())
+            .map(|output| output.text)
+    }
+
+    /// The first rows are also returned as a table view.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        _cancel: &CancellationToken,
+    ) -> Result<ToolOutput, super::ToolError> {
+        let parsed: PreviewDataArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        let limit = parsed.rows.min(MAX_ROWS);
+        let path = resolve_path(working_dir, &parsed.file_path);
+        let path = path.as_path();
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let preview = match ext.as_str() {
+            "csv" => preview_delimited(path, b',', "CSV", limit)?,
+            "tsv" | "tab" => preview_delimited(path, b'\t', "TSV", limit)?,
+            "json" => preview_json(&read(path)?, limit)?,
+            "jsonl" | "ndjson" => preview_json_lines(&read(path)?, limit)?,
+            "parquet" | "pq" => preview_parquet(path, limit)?,
+            _ => {
+                return Err(format!(
+                    "Unsupported data file '{}'. Supported: .csv, .tsv, .json, .jsonl, .ndjson, .parquet",
+                    parsed.file_path
+                )
+                .into());
+            }
+        };
+        let text = format_preview(&preview);
+        Ok(ToolOutput::new(
+            text,
+            ToolView::Table {
+                columns: preview.columns.into_iter().map(|(name, _)| name).collect(),
+                rows: preview.rows,
+                total_rows: preview.row_count,
+            },
+        ))
+    }
+}
+
+fn read(path: &Path) -> Result<String, super::ToolError> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read file '{}': {}", path.display(), e).into())
+}
+
+/// Stream a CSV/TSV file: every row is counted and typed, only the first `limit` are kept.
+fn preview_delimited(
+    path: &Path,
+    delimiter: u8,
+    format: &'static str,
+    limit: usize,
+) -> Result<DataPreview, super::ToolError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Cannot read file '{}': {}", path.display(), e))?;
+
+    let mut columns: Vec<(String, ColumnType)> = reader
+        .headers()
+        .map_err(|e| format!("Invalid {} header: {}", format, e))?
+        .iter()
+        .map(|h| (h.to_string(), ColumnType::Empty))
+        .collect();
+    let mut rows = Vec::new();
+    let mut row_count = 0;
+
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid {} row: {}", format, e))?;
+        for (i, cell) in record.iter().enumerate() {
+            if i >= columns.len() {
+                columns.push((format!("column_{}", i + 1), ColumnType::Empty));
+            }
+            columns[i].1 = columns[i].1.widen(ColumnType::of_text(cell));
+        }
+        if rows.len() < limit {
+            rows.push(record.iter().map(str::to_string).collect());
+        }
+        row_count += 1;
+    }
+
+    // Every CSV cell is text: a column mixing numbers and words is a string column.
+    for (_, ty) in &mut columns {
+        if *ty == ColumnType::Mixed {
+            *ty = ColumnType::String;
+        }
+    }
+
+    Ok(DataPreview {
+        format: format.to_string(),
+        columns,
+        row_count,
+        rows,
+    })
+}
+
+/// A JSON array of objects becomes a table; any other document is summarized by shape.
+fn preview_json(content: &str, limit: usize) -> Result<DataPreview, super::ToolError> {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+    match value {
+        Value::Array(items) => Ok(table_from_objects(&items, "JSON array", limit)),
+        Value::Object(map) => {
+            // A single top-level array field (e.g. {"data": [...]}) is the usual wrapper.
+            let arrays: Vec<_> = map.iter().filter(|(_, v)| v.is_array()).collect();
+            if let [(key, Value::Array(items))] = arrays.as_slice()
+                && items.iter().all(Value::is_object)
+                && !items.is_empty()
+            {
+                let mut preview = table_from_objects(items, "JSON object", limit);
+                preview.format = format!("JSON object (rows from \"{}\")", key);
+                return Ok(preview);
+            }
+            Ok(DataPreview {
+                format: "JSON object".to_string(),
+                columns: map
+                    .iter()
+                    .map(|(k, v)| (k.clone(), ColumnType::of_json(v)))
+                    .collect(),
+                row_count: 1,
+                rows: vec![map.values().map(cell_text).collect()],
+            })
+        }
+        other => Err(format!(
+            "JSON document is a single {} value, not tabular data",
+            ColumnType::of_json(&other).label()
+        )
+        .into()),
+    }
+}
+
+fn preview_json_lines(content: &str, limit: usize) -> Result<DataPreview, super::ToolError> {
+    let items = content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            serde_json::from_str::<Value>(l)
+                .map_err(|e| format!("Invalid JSON on line {}: {}", i + 1, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(table_from_objects(&items, "JSON Lines", limit))
+}
+
+/// Schema and row count come from the footer; only the first `limit` rows are decoded.
+fn preview_parquet(path: &Path, limit: usize) -> Result<DataPreview, super::ToolError> {
+    let file = fs::File::open(path)
+        .map_err(|e| format!("Cannot read file '{}': {}", path.display(), e))?;
+    let reader = SerializedFileReader::new(file)
+        .map_err(|e| format!("Invalid Parquet file '{}': {}", path.display(), e))?;
+    let metadata = reader.metadata().file_metadata();
+    let columns: Vec<(String, ColumnType)> = metadata
+        .schema()
+        .get_fields()
+        .iter()
+        .map(|field| (field.name().to_string(), ColumnType::of_parquet(field)))
+        .collect();
+    let row_count = usize::try_from(metadata.num_rows()).unwrap_or(0);
+
+    let mut rows = Vec::new();
+    let iter = reader
+        .get_row_iter(None)
+        .map_err(|e| format!("Invalid Parquet file '{}': {}", path.display(), e))?;
+    for row in iter.take(limit) {
+        let row = row.map_err(|e| format!("Invalid Parquet row: {}", e))?;
+        let values = row.to_json_value();
+        rows.push(
+            columns
+                .iter()
+                .map(|(name, _)| values.get(name).map(cell_text).unwrap_or_default())
+                .collect(),
+        );
+    }
+
+    Ok(DataPreview {
+        format: "Parquet".to_string(),
+        columns,
+        row_count,
+        rows,
+    })
+}
+
+/// Columns are the union of object keys in first-seen order; non-object items land in a
+/// `value` column.
+fn table_from_objects(items: &[Value], format: &'static str, limit: usize) -> DataPreview {
+    let mut columns: Vec<(String, ColumnType)> = Vec::new();
+    let column_index = |columns: &mut Vec<(String, ColumnType)>, key: &str| {
+        columns
+            .iter()
+            .position(|(k, _)| k == key)
+            .unwrap_or_else(|| {
+                columns.push((key.to_string(), ColumnType::Empty));
+                columns.len() - 1
+            })
+    };
+
+    for item in items {
+        match item {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let i = column_index(&mut columns, key);
+                    columns[i].1 = columns[i].1.widen(ColumnType::of_json(value));
+                }
+            }
+            other => {
+                let i = column_index(&mut columns, "value");
+                columns[i].1 = columns[i].1.widen(ColumnType::of_json(other));
+            }
+        }
+    }
+
+    let rows = items
+        .iter()
+        .take(limit)
+        .map(|item| {
+            columns
+                .iter()
+                .map(|(key, _)| match item {
+                    Value::Object(map) => map.get(key).map(cell_text).unwrap_or_default(),
+                    other if key == "value" => cell_text(other),
+                    _ => String::new(),
+                })
+                .collect()
+        })
+        .collect();
+
+    DataPreview {
+        format: format.to_string(),
+        columns,
+        row_count: items.len(),
+        rows,
+    }
+}
+
+/// Cell text for a JSON value: strings unquoted, null empty, everything else compact JSON.
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Schema list followed by a Markdown-style table of the first rows.
+fn format_preview(preview: &DataPreview) -> String {
+    let mut out = format!(
+        "Format: {}\nRows: {}\nColumns ({}):",
+        preview.format,
+        preview.row_count,
+        preview.columns.len()
+    );
+    for (name, ty) in &preview.columns {
+        out.push_str(&format!("\n  {}: {}", name, ty.label()));
+    }
+    if preview.rows.is_empty() || preview.columns.is_empty() {
+        return out;
+    }
+
+    let cell = |s: &str| {
+        truncate_end(
+            &s.replace(['\n', '\r'], " ").replace('|', "\\|"),
+            MAX_CELL_WIDTH,
+        )
+    };
+    out.push_str(&format!("\n\nFirst {} rows:\n|", preview.rows.len()));
+    for (name, _) in &preview.columns {
+        out.push_str(&format!(" {} |", cell(name)));
+    }
+    out.push_str("\n|");
+    out.push_str(&"---|".repeat(preview.columns.len()));
+    for row in &preview.rows {
+        out.push_str("\n|");
+        for i in 0..preview.columns.len() {
+            out.push_str(&format!(
+                " {} |",
+                cell(row.get(i).map_or("", String::as_str))
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::Tool;
+
+    fn preview_file(
+        name: &str,
+        content: &str,
+        rows: usize,
+    ) -> Result<String, super::super::ToolError> {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join(name);
+        fs::write(&path, content).expect("write");
+        // Relative to the working directory, as the model usually passes it.
+        PreviewDataTool.execute(&json!({"file_path": name, "rows": rows}), dir.path())
+    }
+
+    #[test]
+    fn csv_reports_schema_count_and_first_rows() {
+        let out = preview_file(
+            "data.csv",
+            "id,name,score\n1,Ada,9.5\n2,\"Lin, Bo\",7\n3,Cy,\n",
+            2,
+        )
+        .expect("preview");
+        assert!(out.contains("Format: CSV\nRows: 3\nColumns (3):"));
+        assert!(out.contains("  id: integer\n  name: string\n  score: float"));
+        assert!(out.contains("First 2 rows:"));
+        assert!(out.contains("| 2 | Lin, Bo | 7 |"));
+        assert!(!out.contains("| 3 |"));
+    }
+
+    #[test]
+    fn tsv_uses_tab_delimiter() {
+        let out = preview_file("data.tsv", "a\tb\ntrue\tx\n", 10).expect("preview");
+        assert!(out.contains("  a: bool\n  b: string"));
+    }
+
+    #[test]
+    fn json_array_of_objects_unions_keys() {
+        let out = preview_file(
+            "data.json",
+            r#"[{"id": 1, "tags": ["a"]}, {"id": 2.5, "extra": null}]"#,
+            10,
+        )
+        .expect("preview");
+        assert!(out.contains("Rows: 2"));
+        assert!(out.contains("  id: float\n  tags: array\n  extra: empty"));
+        assert!(out.contains("| 1 | [\"a\"] |  |"));
+    }
+
+    #[test]
+    fn json_lines_reports_bad_line() {
+        let err = preview_file("data.jsonl", "{\"a\": 1}\n{oops\n", 10).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn unsupported_extension_errors() {
+        assert!(preview_file("notes.txt", "x", 10).is_err());
+    }
+
+    #[test]
+    fn parquet_reports_schema_count_and_first_rows() {
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().expect("temp dir");
+        let schema = parse_message_type(
+            "message m { required int64 id; optional binary name (UTF8); required double score; }",
+        )
+        .expect("schema");
+        let file = fs::File::create(dir.path().join("data.parquet")).expect("create");
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), props).expect("writer");
+        let mut group = writer.next_row_group().expect("row group");
+        let mut column = group.next_column().expect("column").expect("id");
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2, 3], None, None)
+            .expect("write id");
+        column.close().expect("close id");
+        let mut column = group.next_column().expect("column").expect("name");
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(
+                &[ByteArray::from("Ada"), ByteArray::from("Cy")],
+                Some(&[1, 0, 1]),
+                None,
+            )
+            .expect("write name");
+        column.close().expect("close name");
+        let mut column = group.next_column().expect("column").expect("score");
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[9.5, 7.0, 8.25], None, None)
+            .expect("write score");
+        column.close().expect("close score");
+        group.close().expect("close row group");
+        writer.close().expect("close file");
+
+        let out = PreviewDataTool
+            .execute(&json!({"file_path": "data.parquet", "rows": 2}), dir.path())
+            .expect("preview");
+        assert!(out.contains("Format: Parquet\nRows: 3\nColumns (3):"));
+        assert!(out.contains("  id: integer\n  name: string\n  score: float"));
+        assert!(out.contains("| 1 | Ada | 9.5 |"));
+        assert!(out.contains("| 2 |  | 7.0 |"));
+        assert!(!out.contains("| 3 |"));
+
+        let err = preview_file("bad.parquet", "PAR1", 10).unwrap_err();
+        assert!(err.to_string().contains("Invalid Parquet file"));
+    }
+
+    #[test]
+    fn widen_integer_float_and_mixed() {
+        assert_eq!(
+            ColumnType::Integer.widen(ColumnType::Float),
+            ColumnType::Float
+        );
+        assert_eq!(
+            ColumnType::Integer.widen(ColumnType::String),
+            ColumnType::Mixed
+        );
+        assert_eq!(ColumnType::Empty.widen(ColumnType::Bool), ColumnType::Bool);
+    }
+}
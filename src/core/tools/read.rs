@@ -6,7 +6,10 @@
 use serde::Deserialize;
 use serde_json::{Value, json};
 
-use super::{str_arg, tool_definition};
+use std::path::Path;
+
+use super::{resolve_path, str_arg, tool_definition};
+use crate::core::remote;
 
 #[derive(Debug, Deserialize)]
 pub struct ReadArgs {
@@ -57,6 +60,14 @@ impl super::Tool for ReadTool {
         true
     }
 
+    fn runs_remotely(&self) -> bool {
+        true
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(str_arg(args, "file_path"))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         let path = str_arg(args, "file_path");
         let start = args.get("start_line").and_then(|v| v.as_u64());
@@ -69,11 +80,11 @@ impl super::Tool for ReadTool {
         }
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
         let parsed: ReadArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
 
-        let content = std::fs::read_to_string(&parsed.file_path)?;
+        let content = remote::fs::read_to_string(resolve_path(working_dir, &parsed.file_path))?;
         if parsed.start_line.is_none() && parsed.end_line.is_none() {
             return Ok(content);
         }
@@ -112,7 +123,7 @@ mod tests {
         let file = tempfile::NamedTempFile::new().expect("temp file");
         std::fs::write(file.path(), "line1\nline2\nline3").expect("write");
         let args = json!({"file_path": file.path().to_str().expect("path")});
-        let result = tool.execute(&args).expect("execute");
+        let result = tool.execute(&args, Path::new(".")).expect("execute");
         assert_eq!(result, "line1\nline2\nline3");
     }
 
@@ -126,7 +137,7 @@ mod tests {
             "start_line": 2,
             "end_line": 4
         });
-        let result = tool.execute(&args).expect("execute");
+        let result = tool.execute(&args, Path::new(".")).expect("execute");
         assert_eq!(result, "b\nc\nd");
     }
 
@@ -139,7 +150,7 @@ mod tests {
             "file_path": file.path().to_str().expect("path"),
             "start_line": 2
         });
-        let result = tool.execute(&args).expect("execute");
+        let result = tool.execute(&args, Path::new(".")).expect("execute");
         assert_eq!(result, "b\nc");
     }
 
@@ -152,7 +163,7 @@ mod tests {
             "file_path": file.path().to_str().expect("path"),
             "start_line": 10
         });
-        let err = tool.execute(&args).unwrap_err();
+        let err = tool.execute(&args, Path::new(".")).unwrap_err();
         assert!(err.to_string().contains("beyond file"));
     }
 
@@ -161,7 +172,7 @@ mod tests {
         let tool = ReadTool;
         let file = tempfile::NamedTempFile::new().expect("temp file");
         let args = json!({"file_path": file.path().to_str().expect("path")});
-        let result = tool.execute(&args).expect("execute");
+        let result = tool.execute(&args, Path::new(".")).expect("execute");
         assert_eq!(result, "");
     }
 }
@@ -0,0 +1,389 @@
+//! ReplaceAll tool — literal or regex replacement across many files.
+//!
+//! Matches are line-based (a pattern never spans lines). The change is reported as a
+//! per-line diff; `dry_run` reports without writing, and `max_replacements` aborts
+//! oversized renames before any file is touched.
+
+use regex::{NoExpand, Regex};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::{
+    default_search_path, display_path, ignore, resolve_path, search_path_arg, str_arg,
+    tool_definition,
+};
+use crate::core::{permissions, remote};
+
+/// Default cap on the total number of replacements.
+const DEFAULT_MAX_REPLACEMENTS: usize = 500;
+/// Max diff lines included in the output.
+const MAX_DIFF_LINES: usize = 200;
+
+#[derive(Debug, Deserialize)]
+struct ReplaceAllArgs {
+    pattern: String,
+    replacement: String,
+    #[serde(default = "default_search_path")]
+    path: String,
+    include: Option<String>,
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default = "default_max_replacements")]
+    max_replacements: usize,
+}
+
+fn default_max_replacements() -> usize {
+    DEFAULT_MAX_REPLACEMENTS
+}
+
+/// Pending change to one file.
+struct FileChange {
+    path: PathBuf,
+    new_content: String,
+    replacements: usize,
+    /// (line number, old line, new line) for each changed line.
+    lines: Vec<(usize, String, String)>,
+}
+
+pub struct ReplaceAllTool;
+
+impl super::Tool for ReplaceAllTool {
+    fn name(&self) -> &'static str {
+        "ReplaceAll"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "Replace every occurrence of a literal string (or regex) across files under a path, respecting ignored directories. Use for mechanical renames instead of repeated Edit calls. Run with dry_run first to review the diff.",
+            json!({
+                "type": "object",
+                "required": ["pattern", "replacement"],
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Text to find (literal unless regex is true). Matches never span lines."
+                    },
+                    "replacement": {
+                        "type": "string",
+                        "description": "Replacement text. With regex, $1 / ${name} refer to capture groups."
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to process (default: current directory)"
+                    },
+                    "include": {
+                        "type": "string",
+                        "description": "File extension filter, e.g. \"rs\", \"ts\" (without dot)"
+                    },
+                    "regex": {
+                        "type": "boolean",
+                        "description": "Treat pattern as a regex (default: false)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Report the diff without writing files (default: false)"
+                    },
+                    "max_replacements": {
+                        "type": "integer",
+                        "description": "Abort without writing if more replacements would be made (default: 500)"
+                    }
+                }
+            }),
+        )
+    }
+
+    fn output_limit(&self) -> Option<usize> {
+        Some(super::MAX_OUTPUT_LARGE)
+    }
+
+    fn disabled_in_ask_mode(&self) -> bool {
+        true
+    }
+
+    fn available(&self) -> bool {
+        !remote::is_active()
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(search_path_arg(args))
+    }
+
+    /// Its own `dry_run` report: the replacements it would make, file by file.
+    fn simulate(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        let mut args = args.clone();
+        args["dry_run"] = json!(true);
+        self.execute(&args, working_dir)
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        let pattern = str_arg(args, "pattern");
+        let replacement = str_arg(args, "replacement");
+        let path = str_arg(args, "path");
+        if path.is_empty() || path == "." {
+            format!("{} → {}", pattern, replacement)
+        } else {
+            format!("{} → {} in {}", pattern, replacement, path)
+        }
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        let parsed: ReplaceAllArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        if parsed.pattern.is_empty() {
+            return Err("pattern cannot be empty".into());
+        }
+
+        let re = if parsed.regex {
+            Regex::new(&parsed.pattern).map_err(|e| format!("Invalid regex pattern: {}", e))?
+        } else {
+            Regex::new(&regex::escape(&parsed.pattern)).map_err(|e| e.to_string())?
+        };
+
+        let root = resolve_path(working_dir, &parsed.path);
+        let root = root.as_path();
+        if !root.exists() {
+            return Err(format!("Path does not exist: {}", parsed.path).into());
+        }
+
+        let changes: Vec<FileChange> =
+            candidate_files(root, working_dir, parsed.include.as_deref())
+                .into_iter()
+                .filter_map(|path| replace_in_file(path, &re, &parsed))
+                .collect();
+        if changes.is_empty() {
+            return Ok("No matches found.".to_string());
+        }
+
+        let total: usize = changes.iter().map(|c| c.replacements).sum();
+        if total > parsed.max_replacements {
+            return Err(format!(
+                "{} replacements in {} files exceed max_replacements ({}). No files were changed; narrow the path or pattern, or raise the limit.",
+                total,
+                changes.len(),
+                parsed.max_replacements
+            )
+            .into());
+        }
+
+        if !parsed.dry_run {
+            for change in &changes {
+                fs::write(&change.path, &change.new_content)
+                    .map_err(|e| format!("Cannot write file '{}': {}", change.path.display(), e))?;
+            }
+        }
+
+        Ok(format_report(&changes, total, parsed.dry_run, working_dir))
+    }
+}
+
+/// Files to process: `root` itself when it is a file, otherwise a walk that skips ignored
+/// dirs and denied paths.
+fn candidate_files(root: &Path, working_dir: &Path, include: Option<&str>) -> Vec<PathBuf> {
+    if root.is_file() {
+        return vec![root.to_path_buf()];
+    }
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| !ignore::is_ignored(e) && !permissions::is_denied(working_dir, e.path()))
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            include.is_none_or(|ext| {
+                e.path()
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .is_some_and(|x| x.eq_ignore_ascii_case(ext))
+            })
+        })
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Compute the replacement for one file. None when unreadable (binary) or unchanged.
+fn replace_in_file(path: PathBuf, re: &Regex, args: &ReplaceAllArgs) -> Option<FileChange> {
+    let content = fs::read_to_string(&path).ok()?;
+    let mut new_content = String::with_capacity(content.len());
+    let mut replacements = 0;
+    let mut lines = Vec::new();
+
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        // Match against the line without its terminator so patterns cannot join lines.
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+        let count = re.find_iter(body).count();
+        if count == 0 {
+            new_content.push_str(line);
+            continue;
+        }
+        let replaced = if args.regex {
+            re.replace_all(body, args.replacement.as_str())
+        } else {
+            re.replace_all(body, NoExpand(&args.replacement))
+        };
+        replacements += count;
+        lines.push((i + 1, body.to_string(), replaced.to_string()));
+        new_content.push_str(&replaced);
+        new_content.push_str(ending);
+    }
+
+    (replacements > 0 && new_content != content).then_some(FileChange {
+        path,
+        new_content,
+        replacements,
+        lines,
+    })
+}
+
+/// Summary line plus a per-line diff, capped at `MAX_DIFF_LINES`.
+fn format_report(
+    changes: &[FileChange],
+    total: usize,
+    dry_run: bool,
+    working_dir: &Path,
+) -> String {
+    let mut out = format!(
+        "{} {} replacements in {} files",
+        if dry_run {
+            "Dry run: would make"
+        } else {
+            "OK — made"
+        },
+        total,
+        changes.len()
+    );
+    let mut diff_lines = 0;
+    for change in changes {
+        if diff_lines >= MAX_DIFF_LINES {
+            break;
+        }
+        out.push_str(&format!(
+            "\n\n--- {} ({})",
+            display_path(working_dir, &change.path),
+            change.replacements
+        ));
+        for (line_no, old, new) in &change.lines {
+            if diff_lines >= MAX_DIFF_LINES {
+                break;
+            }
+            out.push_str(&format!("\n{}:-{}\n{}:+{}", line_no, old, line_no, new));
+            diff_lines += 1;
+        }
+    }
+    let total_lines = changes.iter().map(|c| c.lines.len()).sum::<usize>();
+    if total_lines > diff_lines {
+        out.push_str(&format!(
+            "\n... ({} more changed lines not shown)",
+            total_lines - diff_lines
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::Tool;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().expect("temp dir");
+        fs::write(dir.path().join("a.rs"), "let old_name = 1;\nold_name + 1\n").expect("write");
+        fs::write(dir.path().join("b.txt"), "old_name\n").expect("write");
+        fs::create_dir(dir.path().join("target")).expect("mkdir");
+        fs::write(dir.path().join("target/c.rs"), "old_name\n").expect("write");
+        dir
+    }
+
+    #[test]
+    fn replaces_across_files_and_skips_ignored_dirs() {
+        let dir = setup();
+        let args = json!({
+            "pattern": "old_name",
+            "replacement": "new_name",
+            "path": dir.path().to_str().expect("path")
+        });
+        let out = ReplaceAllTool
+            .execute(&args, Path::new("."))
+            .expect("execute");
+        assert!(out.starts_with("OK — made 3 replacements in 2 files"));
+        assert!(out.contains("1:-let old_name = 1;\n1:+let new_name = 1;"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.rs")).expect("read"),
+            "let new_name = 1;\nnew_name + 1\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("target/c.rs")).expect("read"),
+            "old_name\n"
+        );
+    }
+
+    #[test]
+    fn dry_run_does_not_write() {
+        let dir = setup();
+        let args = json!({
+            "pattern": "old_name",
+            "replacement": "new_name",
+            "path": dir.path().to_str().expect("path"),
+            "include": "rs",
+            "dry_run": true
+        });
+        let out = ReplaceAllTool
+            .execute(&args, Path::new("."))
+            .expect("execute");
+        assert!(out.starts_with("Dry run: would make 2 replacements in 1 files"));
+        assert!(
+            fs::read_to_string(dir.path().join("a.rs"))
+                .expect("read")
+                .contains("old_name")
+        );
+    }
+
+    #[test]
+    fn cap_exceeded_writes_nothing() {
+        let dir = setup();
+        let args = json!({
+            "pattern": "old_name",
+            "replacement": "new_name",
+            "path": dir.path().to_str().expect("path"),
+            "max_replacements": 2
+        });
+        let err = ReplaceAllTool.execute(&args, Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("exceed max_replacements"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("b.txt")).expect("read"),
+            "old_name\n"
+        );
+    }
+
+    #[test]
+    fn regex_expands_groups_literal_does_not() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let file = dir.path().join("f.txt");
+        fs::write(&file, "get_user get_item\n").expect("write");
+        let path = file.to_str().expect("path");
+        let args = json!({
+            "pattern": r"get_(\w+)",
+            "replacement": "fetch_$1",
+            "path": path,
+            "regex": true
+        });
+        ReplaceAllTool
+            .execute(&args, Path::new("."))
+            .expect("execute");
+        assert_eq!(
+            fs::read_to_string(&file).expect("read"),
+            "fetch_user fetch_item\n"
+        );
+
+        let args = json!({"pattern": "fetch_user", "replacement": "$1", "path": path});
+        ReplaceAllTool
+            .execute(&args, Path::new("."))
+            .expect("execute");
+        assert_eq!(fs::read_to_string(&file).expect("read"), "$1 fetch_item\n");
+    }
+}
@@ -0,0 +1,106 @@
+//! SemanticSearch tool — find code by meaning using the workspace embedding index.
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+use super::{str_arg, tool_definition};
+use crate::core::{index, permissions, remote};
+
+/// Default number of chunks returned.
+const DEFAULT_MAX_RESULTS: usize = 8;
+/// Lines of each matching chunk shown in the output.
+const SNIPPET_LINES: usize = 8;
+
+#[derive(Debug, Deserialize)]
+struct SemanticSearchArgs {
+    query: String,
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+fn default_max_results() -> usize {
+    DEFAULT_MAX_RESULTS
+}
+
+pub struct SemanticSearchTool;
+
+impl super::Tool for SemanticSearchTool {
+    fn name(&self) -> &'static str {
+        "SemanticSearch"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "Search the workspace by meaning: returns the code chunks most related to a natural-language query, with file paths and line ranges. Use it to find where a concept is implemented when the exact identifiers are unknown; use Grep for exact text.",
+            json!({
+                "type": "object",
+                "required": ["query"],
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "What to look for, e.g. \"where API retries are scheduled\""
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum number of chunks to return (default: 8)"
+                    }
+                }
+            }),
+        )
+    }
+
+    fn output_limit(&self) -> Option<usize> {
+        Some(super::MAX_OUTPUT_SMALL)
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn available(&self) -> bool {
+        index::is_configured() && !remote::is_active()
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        str_arg(args, "query")
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        let parsed: SemanticSearchArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        if parsed.query.trim().is_empty() {
+            return Err("Query is empty".into());
+        }
+        let root = working_dir;
+        // An index built before a path was denied may still hold its chunks.
+        let mut hits = index::search(root, &parsed.query, parsed.max_results.max(1))?;
+        hits.retain(|hit| !permissions::is_denied(root, Path::new(&hit.path)));
+        if hits.is_empty() {
+            return Ok("No indexed files.".to_string());
+        }
+        let blocks: Vec<String> = hits
+            .iter()
+            .map(|hit| {
+                let mut block = format!(
+                    "{}:{}-{} (score {:.2})",
+                    hit.path, hit.start_line, hit.end_line, hit.score
+                );
+                if let Ok(content) = fs::read_to_string(root.join(&hit.path)) {
+                    for line in content
+                        .lines()
+                        .skip(hit.start_line.saturating_sub(1))
+                        .take(SNIPPET_LINES.min(hit.end_line + 1 - hit.start_line))
+                    {
+                        block.push_str("\n  ");
+                        block.push_str(line);
+                    }
+                }
+                block
+            })
+            .collect();
+        Ok(blocks.join("\n\n"))
+    }
+}
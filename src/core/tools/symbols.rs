@@ -0,0 +1,667 @@
+//! Symbols tool — definitions and references of functions, types, and other items in Rust,
+//! TypeScript/JavaScript, Python, and Go sources.
+//!
+//! Files are parsed with tree-sitter grammars: definitions come from a per-language query
+//! on the syntax tree (a multi-line signature is listed at its first line), and references
+//! are identifiers with the searched name, so mentions in comments and string literals are
+//! not counted.
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+use streaming_iterator::StreamingIterator;
+use tokio_util::sync::CancellationToken;
+use tree_sitter::{Node, Parser, Query, QueryCursor, Tree};
+use walkdir::WalkDir;
+
+use super::{
+    SEARCH_CANCELLED, SEARCH_DEFAULT_TIMEOUT, default_search_path, display_path, ignore,
+    resolve_path, search_path_arg, str_arg, tool_definition,
+};
+use crate::core::text::truncate_end;
+use crate::core::{permissions, remote};
+
+/// Default max definitions (outline) or references (symbol search) returned.
+const DEFAULT_MAX_RESULTS: usize = 100;
+/// Widest source line quoted in the output, in display columns.
+const MAX_LINE_WIDTH: usize = 160;
+
+#[derive(Debug, Deserialize)]
+struct SymbolsArgs {
+    #[serde(default = "default_search_path")]
+    path: String,
+    name: Option<String>,
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+fn default_max_results() -> usize {
+    DEFAULT_MAX_RESULTS
+}
+
+/// Definition queries: `@name` is the defined identifier, the other capture is the item,
+/// named after the kind shown. Inline modules only: `mod name;` has no items of its own.
+const RUST_DEFINITIONS: &str = r#"
+(function_item name: (identifier) @name) @fn
+(function_signature_item name: (identifier) @name) @fn
+(struct_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(union_item name: (type_identifier) @name) @union
+(trait_item name: (type_identifier) @name) @trait
+(type_item name: (type_identifier) @name) @type
+(mod_item name: (identifier) @name body: (declaration_list)) @mod
+(const_item name: (identifier) @name) @const
+(static_item name: (identifier) @name) @static
+(macro_definition name: (identifier) @name) @macro
+(impl_item
+  type: [
+    (type_identifier) @name
+    (generic_type type: (type_identifier) @name)
+    (scoped_type_identifier name: (type_identifier) @name)
+  ]) @impl
+"#;
+
+/// Also used for JavaScript, which the TSX grammar parses.
+const TYPESCRIPT_DEFINITIONS: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (type_identifier) @name) @class
+(abstract_class_declaration name: (type_identifier) @name) @class
+(interface_declaration name: (type_identifier) @name) @interface
+(enum_declaration name: (identifier) @name) @enum
+(type_alias_declaration name: (type_identifier) @name) @type
+(internal_module name: (identifier) @name) @namespace
+(method_definition name: (property_identifier) @name) @method
+(abstract_method_signature name: (property_identifier) @name) @method
+(lexical_declaration
+  (variable_declarator
+    name: (identifier) @name
+    value: [(arrow_function) (function_expression)])) @function
+(variable_declaration
+  (variable_declarator
+    name: (identifier) @name
+    value: [(arrow_function) (function_expression)])) @function
+"#;
+
+const PYTHON_DEFINITIONS: &str = r#"
+(function_definition name: (identifier) @name) @def
+(class_definition name: (identifier) @name) @class
+"#;
+
+/// A type spec matches the first pattern that fits: struct, interface, then any type.
+const GO_DEFINITIONS: &str = r#"
+(function_declaration name: (identifier) @name) @func
+(method_declaration name: (field_identifier) @name) @method
+(type_spec name: (type_identifier) @name type: (struct_type)) @struct
+(type_spec name: (type_identifier) @name type: (interface_type)) @interface
+(type_spec name: (type_identifier) @name) @type
+(type_alias name: (type_identifier) @name) @type
+"#;
+
+/// Languages with a grammar, by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    TypeScript,
+    /// TSX, also for JavaScript (with or without JSX).
+    Tsx,
+    Python,
+    Go,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "ts" | "mts" | "cts" => Some(Language::TypeScript),
+            "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some(Language::Tsx),
+            "py" | "pyi" => Some(Language::Python),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// Definition query of the language, compiled on first use.
+    fn query(self) -> &'static Query {
+        static RUST: OnceLock<Query> = OnceLock::new();
+        static TYPESCRIPT: OnceLock<Query> = OnceLock::new();
+        static TSX: OnceLock<Query> = OnceLock::new();
+        static PYTHON: OnceLock<Query> = OnceLock::new();
+        static GO: OnceLock<Query> = OnceLock::new();
+        let (cell, source) = match self {
+            Language::Rust => (&RUST, RUST_DEFINITIONS),
+            Language::TypeScript => (&TYPESCRIPT, TYPESCRIPT_DEFINITIONS),
+            Language::Tsx => (&TSX, TYPESCRIPT_DEFINITIONS),
+            Language::Python => (&PYTHON, PYTHON_DEFINITIONS),
+            Language::Go => (&GO, GO_DEFINITIONS),
+        };
+        cell.get_or_init(|| Query::new(&self.grammar(), source).expect("valid definition query"))
+    }
+
+    /// Syntax tree of `content`. Syntax errors still give a tree, with error nodes.
+    fn parse(self, content: &str) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(&self.grammar()).ok()?;
+        parser.parse(content, None)
+    }
+}
+
+/// An item defined in a source file.
+struct Definition {
+    /// Line of the item's first token (0-based).
+    row: usize,
+    kind: &'static str,
+    /// Byte range of the defined name.
+    name: Range<usize>,
+}
+
+/// Definitions in `tree`, in source order.
+fn definitions(language: Language, tree: &Tree, content: &str) -> Vec<Definition> {
+    let query = language.query();
+    let captures = query.capture_names();
+    // (pattern index, item start byte, definition)
+    let mut found: Vec<(usize, usize, Definition)> = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), content.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut item = None;
+        let mut name = None;
+        for capture in m.captures {
+            match captures[capture.index as usize] {
+                "name" => name = Some(capture.node.byte_range()),
+                kind => item = Some((kind, capture.node)),
+            }
+        }
+        let (Some((kind, node)), Some(name)) = (item, name) else {
+            continue;
+        };
+        let definition = Definition {
+            row: node.start_position().row,
+            kind,
+            name,
+        };
+        // An item matched by several patterns keeps the first one's kind.
+        match found
+            .iter_mut()
+            .find(|(_, start, _)| *start == node.start_byte())
+        {
+            Some(existing) if m.pattern_index < existing.0 => {
+                *existing = (m.pattern_index, node.start_byte(), definition);
+            }
+            Some(_) => {}
+            None => found.push((m.pattern_index, node.start_byte(), definition)),
+        }
+    }
+    found.sort_by_key(|(_, start, _)| *start);
+    found.into_iter().map(|(_, _, d)| d).collect()
+}
+
+/// Lines (0-based) where `name` is an identifier, outside comments and string literals,
+/// except at the byte ranges in `skip` (the definitions).
+fn reference_rows(tree: &Tree, content: &str, name: &str, skip: &[Range<usize>]) -> Vec<usize> {
+    let root = tree.root_node();
+    let mut rows: Vec<usize> = Vec::new();
+    for (start, _) in content.match_indices(name) {
+        let range = start..start + name.len();
+        if skip.contains(&range) {
+            continue;
+        }
+        let Some(node) = root.descendant_for_byte_range(range.start, range.end) else {
+            continue;
+        };
+        if node.byte_range() != range || !node.is_named() || node.child_count() > 0 {
+            continue;
+        }
+        let row = node.start_position().row;
+        if is_code(node) && rows.last() != Some(&row) {
+            rows.push(row);
+        }
+    }
+    rows
+}
+
+/// False inside a comment or a string literal; interpolations in strings are code.
+fn is_code(node: Node) -> bool {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        let kind = node.kind();
+        if kind == "template_substitution" || kind == "interpolation" {
+            return true;
+        }
+        if kind.contains("comment") || kind.contains("string") {
+            return false;
+        }
+        current = node.parent();
+    }
+    true
+}
+
+/// A line found in a source file.
+struct Found {
+    path: String,
+    number: usize,
+    kind: Option<&'static str>,
+    text: String,
+}
+
+impl Found {
+    fn line(&self, with_path: bool) -> String {
+        let text = truncate_end(self.text.trim(), MAX_LINE_WIDTH);
+        let place = if with_path {
+            format!("{}:{}", self.path, self.number)
+        } else {
+            format!("  {}", self.number)
+        };
+        match self.kind {
+            Some(kind) => format!("{}: [{}] {}", place, kind, text),
+            None => format!("{}: {}", place, text),
+        }
+    }
+}
+
+pub struct SymbolsTool;
+
+impl super::Tool for SymbolsTool {
+    fn name(&self) -> &'static str {
+        "Symbols"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "List the definitions (functions, methods, structs, classes, traits, interfaces, types) of Rust, TypeScript/JavaScript, Python, and Go files. Without name: an outline of the file or directory, with line numbers. With name: where that symbol is defined, then the lines that reference it. Cheaper and more precise than Grep for navigating code.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to look in (default: current directory)"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Identifier to find, e.g. \"parse_config\" or \"UserService\" (for Foo::bar, pass bar)"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum number of outline entries or references to return (default: 100)"
+                    }
+                }
+            }),
+        )
+    }
+
+    fn output_limit(&self) -> Option<usize> {
+        Some(super::MAX_OUTPUT_SMALL)
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn available(&self) -> bool {
+        !remote::is_active()
+    }
+
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(search_path_arg(args))
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        let name = str_arg(args, "name");
+        let path = str_arg(args, "path");
+        match (name.is_empty(), path.is_empty() || path == ".") {
+            (true, true) => "outline".to_string(),
+            (true, false) => format!("outline of {}", path),
+            (false, true) => name,
+            (false, false) => format!("{} in {}", name, path),
+        }
+    }
+
+    fn default_timeout(&self) -> Option<Duration> {
+        Some(SEARCH_DEFAULT_TIMEOUT)
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_cancellable(args, working_dir, &CancellationToken::new())
+    }
+
+    /// Stops walking the tree when `cancel` fires (user interrupt or timeout).
+    fn execute_cancellable(
+        &self,
+        args: &Value,
+        working_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<String, super::ToolError> {
+        let parsed: SymbolsArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        let name = parsed.name.as_deref().map(str::trim).unwrap_or_default();
+        if !name.is_empty()
+            && !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            return Err(format!(
+                "Invalid name '{}': pass a single identifier (for Foo::bar or foo.bar, pass bar)",
+                name
+            )
+            .into());
+        }
+
+        let root = resolve_path(working_dir, &parsed.path);
+        if !root.exists() {
+            return Err(format!("Path does not exist: {}", parsed.path).into());
+        }
+        let files = if root.is_file() {
+            if Language::from_path(&root).is_none() {
+                return Err(format!(
+                    "Unsupported file type: {} (Symbols reads .rs, .ts/.tsx/.js/.jsx, .py, and .go files)",
+                    parsed.path
+                )
+                .into());
+            }
+            vec![root.clone()]
+        } else {
+            let mut files = Vec::new();
+            let walker = WalkDir::new(&root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|e| {
+                    !ignore::is_ignored(e) && !permissions::is_denied(working_dir, e.path())
+                });
+            for entry in walker.flatten() {
+                if cancel.is_cancelled() {
+                    return Err(SEARCH_CANCELLED.into());
+                }
+                if entry.file_type().is_file() && Language::from_path(entry.path()).is_some() {
+                    files.push(entry.into_path());
+                }
+            }
+            files
+        };
+
+        let mut sources = Vec::new();
+        for path in &files {
+            if cancel.is_cancelled() {
+                return Err(SEARCH_CANCELLED.into());
+            }
+            // Unreadable or non-UTF-8 files are skipped, like Grep.
+            if let Ok(content) = fs::read_to_string(path) {
+                sources.push((display_path(working_dir, path), path.as_path(), content));
+            }
+        }
+        let sources = sources.iter().map(|(display, path, content)| Source {
+            display,
+            language: Language::from_path(path).expect("filtered by language"),
+            content,
+        });
+
+        let max_results = parsed.max_results.max(1);
+        Ok(if name.is_empty() {
+            outline(sources, max_results)
+        } else {
+            find_symbol(sources, name, max_results)
+        })
+    }
+}
+
+/// A source file read for the search.
+struct Source<'a> {
+    display: &'a str,
+    language: Language,
+    content: &'a str,
+}
+
+/// Definitions of each file, grouped under the file path.
+fn outline<'a>(sources: impl Iterator<Item = Source<'a>>, max_results: usize) -> String {
+    let mut out = Vec::new();
+    let mut shown = 0;
+    let mut omitted = 0;
+    for source in sources {
+        let Some(tree) = source.language.parse(source.content) else {
+            continue;
+        };
+        let lines: Vec<&str> = source.content.lines().collect();
+        let defs: Vec<Found> = definitions(source.language, &tree, source.content)
+            .into_iter()
+            .map(|def| Found {
+                path: source.display.to_string(),
+                number: def.row + 1,
+                kind: Some(def.kind),
+                text: lines.get(def.row).copied().unwrap_or_default().to_string(),
+            })
+            .collect();
+        if defs.is_empty() {
+            continue;
+        }
+        let room = max_results - shown;
+        if room == 0 {
+            omitted += defs.len();
+            continue;
+        }
+        out.push(format!("{}:", source.display));
+        for def in defs.iter().take(room) {
+            out.push(def.line(false));
+        }
+        shown += defs.len().min(room);
+        omitted += defs.len().saturating_sub(room);
+    }
+    if out.is_empty() {
+        return "No definitions found.".to_string();
+    }
+    if omitted > 0 {
+        out.push(format!(
+            "... ({} more definitions truncated; narrow the path)",
+            omitted
+        ));
+    }
+    out.join("\n")
+}
+
+/// Definitions of `name`, then the other lines where it is used as an identifier.
+fn find_symbol<'a>(
+    sources: impl Iterator<Item = Source<'a>>,
+    name: &str,
+    max_results: usize,
+) -> String {
+    let mut definitions_found = Vec::new();
+    let mut references = Vec::new();
+    for source in sources {
+        if !source.content.contains(name) {
+            continue;
+        }
+        let Some(tree) = source.language.parse(source.content) else {
+            continue;
+        };
+        let lines: Vec<&str> = source.content.lines().collect();
+        let found = |row: usize, kind| Found {
+            path: source.display.to_string(),
+            number: row + 1,
+            kind,
+            text: lines.get(row).copied().unwrap_or_default().to_string(),
+        };
+        let defined: Vec<Definition> = definitions(source.language, &tree, source.content)
+            .into_iter()
+            .filter(|def| &source.content[def.name.clone()] == name)
+            .collect();
+        let skip: Vec<Range<usize>> = defined.iter().map(|def| def.name.clone()).collect();
+        definitions_found.extend(defined.iter().map(|def| found(def.row, Some(def.kind))));
+        references.extend(
+            reference_rows(&tree, source.content, name, &skip)
+                .into_iter()
+                .map(|row| found(row, None)),
+        );
+    }
+    if definitions_found.is_empty() && references.is_empty() {
+        return format!("No definitions or references of '{}' found.", name);
+    }
+
+    let mut out = Vec::new();
+    if definitions_found.is_empty() {
+        out.push(format!("No definition of '{}' found.", name));
+    } else {
+        out.push(format!("Definitions of '{}':", name));
+        out.extend(
+            definitions_found
+                .iter()
+                .take(max_results)
+                .map(|d| d.line(true)),
+        );
+    }
+    if references.is_empty() {
+        out.push(format!("No references to '{}'.", name));
+    } else {
+        out.push(format!("References to '{}' ({}):", name, references.len()));
+        out.extend(references.iter().take(max_results).map(|r| r.line(true)));
+        if references.len() > max_results {
+            out.push(format!(
+                "... ({} more references truncated)",
+                references.len() - max_results
+            ));
+        }
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Tool;
+    use super::*;
+
+    fn defs(language: Language, source: &str) -> Vec<(&'static str, String)> {
+        let tree = language.parse(source).expect("tree");
+        definitions(language, &tree, source)
+            .into_iter()
+            .map(|def| (def.kind, source[def.name].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn definitions_per_language() {
+        let rust = "mod tools;\nmod tests {\n    pub(crate) async fn load() {}\n}\nstruct Foo;\npub enum Kind { A }\nimpl Tool for GrepTool {}\nconst MAX: usize = 1;\n// fn commented() {}\nfn main() { let f = fn_ptr; }\n";
+        assert_eq!(
+            defs(Language::Rust, rust),
+            [
+                ("mod", "tests".to_string()),
+                ("fn", "load".to_string()),
+                ("struct", "Foo".to_string()),
+                ("enum", "Kind".to_string()),
+                ("impl", "GrepTool".to_string()),
+                ("const", "MAX".to_string()),
+                ("fn", "main".to_string()),
+            ]
+        );
+
+        let ts = "export default class App {\n  async render(): Promise<void> {\n    if (ready) {}\n  }\n}\nexport const useThing = (a: number) => a;\ninterface Props {}\n";
+        assert_eq!(
+            defs(Language::TypeScript, ts),
+            [
+                ("class", "App".to_string()),
+                ("method", "render".to_string()),
+                ("function", "useThing".to_string()),
+                ("interface", "Props".to_string()),
+            ]
+        );
+        let jsx = "function Button() {\n  return <button>ok</button>;\n}\n";
+        assert_eq!(
+            defs(Language::Tsx, jsx),
+            [("function", "Button".to_string())]
+        );
+
+        let py = "class Service:\n    async def fetch(self):\n        pass\n# def old():\n";
+        assert_eq!(
+            defs(Language::Python, py),
+            [
+                ("class", "Service".to_string()),
+                ("def", "fetch".to_string())
+            ]
+        );
+
+        let go = "package main\nfunc (s *Server) Start() error { return nil }\nfunc main() {}\ntype Config struct{}\ntype ID string\n";
+        assert_eq!(
+            defs(Language::Go, go),
+            [
+                ("method", "Start".to_string()),
+                ("func", "main".to_string()),
+                ("struct", "Config".to_string()),
+                ("type", "ID".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_line_signatures_are_found_at_their_first_line() {
+        let rust = "#[inline]\npub fn build(\n    name: &str,\n    size: usize,\n) -> String {\n    name.repeat(size)\n}\n";
+        let tree = Language::Rust.parse(rust).expect("tree");
+        let found = definitions(Language::Rust, &tree, rust);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].row, 1);
+        assert_eq!(&rust[found[0].name.clone()], "build");
+    }
+
+    #[test]
+    fn references_skip_comments_and_strings() {
+        let source = "fn run() {\n    // run is called twice\n    let label = \"run\";\n    run();\n    let msg = format!(\"{}\", run_count);\n}\n";
+        let tree = Language::Rust.parse(source).expect("tree");
+        let defined = definitions(Language::Rust, &tree, source);
+        let skip: Vec<Range<usize>> = defined.iter().map(|def| def.name.clone()).collect();
+        assert_eq!(reference_rows(&tree, source, "run", &skip), [3]);
+
+        let py = "name = 'x'\nprint(f\"{name} and name\")\n";
+        let tree = Language::Python.parse(py).expect("tree");
+        assert_eq!(reference_rows(&tree, py, "name", &[]), [0, 1]);
+    }
+
+    #[test]
+    fn finds_definitions_and_references_of_a_name() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "pub fn parse(s: &str) -> u32 {\n    s.len() as u32\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("main.rs"),
+            "// parse is defined in lib\nfn main() {\n    let n = parse(\"x\");\n    let parsed = n;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "parse me").unwrap();
+
+        let out = SymbolsTool
+            .execute(&json!({"name": "parse"}), dir.path())
+            .unwrap();
+        assert_eq!(
+            out,
+            "Definitions of 'parse':\nlib.rs:1: [fn] pub fn parse(s: &str) -> u32 {\nReferences to 'parse' (1):\nmain.rs:3: let n = parse(\"x\");"
+        );
+
+        let outline = SymbolsTool
+            .execute(&json!({"path": "main.rs"}), dir.path())
+            .unwrap();
+        assert_eq!(outline, "main.rs:\n  2: [fn] fn main() {");
+
+        assert!(
+            SymbolsTool
+                .execute(&json!({"name": "Foo::bar"}), dir.path())
+                .is_err()
+        );
+        assert!(
+            SymbolsTool
+                .execute(&json!({"path": "notes.txt"}), dir.path())
+                .is_err()
+        );
+    }
+}
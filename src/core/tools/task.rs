@@ -0,0 +1,100 @@
+//! Task tool — delegate a focused subtask to a child agent with read-only tools and a budget.
+//!
+//! The agent loop runs the child itself (see `core::llm::subtask`): it needs the model and
+//! the API client of the turn, which tools do not get. Only the summary the child ends with
+//! is returned to the parent conversation.
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::Path;
+
+use super::{str_arg, tool_definition};
+
+/// Model calls of a subtask when the call sets no `max_turns`.
+pub const DEFAULT_TASK_TURNS: u32 = 8;
+/// Highest `max_turns` a call may ask for.
+pub const MAX_TASK_TURNS: u32 = 20;
+
+/// Arguments of a Task call.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskArgs {
+    /// Short label of the subtask, shown in the progress log.
+    #[serde(default)]
+    pub description: String,
+    /// Instructions for the child agent.
+    pub prompt: String,
+    /// Model calls the child may make; the last one gets no tools so it answers.
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+    /// Tools the child may use (read-only ones only); all read-only tools when unset.
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+}
+
+impl TaskArgs {
+    /// `max_turns`, defaulted and capped to `1..=MAX_TASK_TURNS`.
+    pub fn turns(&self) -> u32 {
+        self.max_turns
+            .unwrap_or(DEFAULT_TASK_TURNS)
+            .clamp(1, MAX_TASK_TURNS)
+    }
+}
+
+pub struct TaskTool;
+
+impl super::Tool for TaskTool {
+    fn name(&self) -> &'static str {
+        "Task"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "Delegate a focused, read-only subtask (e.g. \"find all usages of X and summarize how it is used\") to a child agent with its own context. It explores with the read-only tools and returns only its final summary, keeping this conversation small. Give it complete instructions: it does not see this conversation.",
+            json!({
+                "type": "object",
+                "required": ["prompt"],
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "Short label of the subtask (3-6 words)"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Instructions for the child agent, including what its summary should contain"
+                    },
+                    "max_turns": {
+                        "type": "integer",
+                        "description": format!("Model calls the child may make (default: {}, max: {})", DEFAULT_TASK_TURNS, MAX_TASK_TURNS)
+                    },
+                    "tools": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Read-only tools the child may use (default: all of them), e.g. [\"Grep\", \"Read\"]"
+                    }
+                }
+            }),
+        )
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        match str_arg(args, "description") {
+            d if d.is_empty() => str_arg(args, "prompt"),
+            d => d,
+        }
+    }
+
+    /// Never called: the agent loop runs Task calls (see the module docs).
+    fn execute(&self, _args: &Value, _working_dir: &Path) -> Result<String, super::ToolError> {
+        Err("the Task tool only runs within the agent loop".into())
+    }
+
+    fn output_limit(&self) -> Option<usize> {
+        Some(super::MAX_OUTPUT_LARGE)
+    }
+
+    /// The child only gets read-only tools.
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
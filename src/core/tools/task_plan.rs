@@ -0,0 +1,140 @@
+//! TaskPlan tool — record an ordered list of steps with their status.
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+use super::{PlanStep, ToolOutput, ToolView, tool_definition};
+
+#[derive(Debug, Deserialize)]
+struct TaskPlanArgs {
+    steps: Vec<PlanStep>,
+}
+
+pub struct TaskPlanTool;
+
+impl super::Tool for TaskPlanTool {
+    fn name(&self) -> &'static str {
+        "TaskPlan"
+    }
+
+    fn definition(&self) -> Value {
+        tool_definition(
+            self.name(),
+            "Record or update the step-by-step plan for the current task. Send the full list of steps each time, in order, with their status.",
+            json!({
+                "type": "object",
+                "required": ["steps"],
+                "properties": {
+                    "steps": {
+                        "type": "array",
+                        "description": "Ordered plan steps",
+                        "items": {
+                            "type": "object",
+                            "required": ["step"],
+                            "properties": {
+                                "step": {
+                                    "type": "string",
+                                    "description": "Short description of the step"
+                                },
+                                "status": {
+                                    "type": "string",
+                                    "enum": ["pending", "in_progress", "completed"],
+                                    "description": "Step status (default: pending)"
+                                }
+                            }
+                        }
+                    }
+                }
+            }),
+        )
+    }
+
+    fn disabled_in_ask_mode(&self) -> bool {
+        true
+    }
+
+    fn allowed_in_plan_mode(&self) -> bool {
+        true
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn args_preview(&self, args: &Value) -> String {
+        let count = args
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .map_or(0, Vec::len);
+        format!("{} steps", count)
+    }
+
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
+        self.execute_with_view(args, working_dir, &CancellationToken::new())
+            .map(|output| output.text)
+    }
+
+    /// The steps are also returned as a plan view.
+    fn execute_with_view(
+        &self,
+        args: &Value,
+        _working_dir: &Path,
+        _cancel: &CancellationToken,
+    ) -> Result<ToolOutput, super::ToolError> {
+        let parsed: TaskPlanArgs = serde_json::from_value(args.clone())
+            .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
+        if parsed.steps.is_empty() {
+            return Err("Plan must contain at least one step".into());
+        }
+
+        let mut out = String::from("Plan updated:");
+        for (i, step) in parsed.steps.iter().enumerate() {
+            out.push_str(&format!(
+                "\n{} {}. {}",
+                step.status.marker(),
+                i + 1,
+                step.step.trim()
+            ));
+        }
+        Ok(ToolOutput::new(
+            out,
+            ToolView::Plan {
+                steps: parsed.steps,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Tool;
+    use super::*;
+
+    #[test]
+    fn execute_formats_checklist() {
+        let args = json!({"steps": [
+            {"step": "Read config", "status": "completed"},
+            {"step": "Add flag", "status": "in_progress"},
+            {"step": "Write tests"}
+        ]});
+        let output = TaskPlanTool
+            .execute_with_view(&args, Path::new("."), &CancellationToken::new())
+            .unwrap();
+        assert_eq!(
+            output.text,
+            "Plan updated:\n[x] 1. Read config\n[~] 2. Add flag\n[ ] 3. Write tests"
+        );
+        assert_eq!(output.view.unwrap().summary(), "1/3 steps done");
+    }
+
+    #[test]
+    fn execute_rejects_empty_plan() {
+        assert!(
+            TaskPlanTool
+                .execute(&json!({"steps": []}), Path::new("."))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Typed tool results for display: directory trees, grep matches, tables, and task plans. The
+//! model still gets the plain text of the result; the TUI draws these as collapsible blocks.
+
+use serde::{Deserialize, Serialize};
+
+/// Output of a tool call: the text sent to the model, and optionally a typed view of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolOutput {
+    pub text: String,
+    pub view: Option<ToolView>,
+}
+
+impl ToolOutput {
+    pub fn new(text: String, view: ToolView) -> Self {
+        ToolOutput {
+            text,
+            view: Some(view),
+        }
+    }
+}
+
+impl From<String> for ToolOutput {
+    fn from(text: String) -> Self {
+        ToolOutput { text, view: None }
+    }
+}
+
+/// Structured result of a tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ToolView {
+    /// Paths under `root`, relative to it; directories end with `/` (ListDir, Glob).
+    Tree {
+        root: String,
+        entries: Vec<String>,
+        /// Entries left out of `entries` (over the result limit).
+        #[serde(default)]
+        omitted: usize,
+    },
+    /// Matching lines grouped by file, with their context lines (Grep).
+    Matches {
+        files: Vec<FileMatches>,
+        /// Matches left out (over the result limit).
+        #[serde(default)]
+        omitted: usize,
+    },
+    /// First rows of a data file (PreviewData).
+    Table {
+        columns: Vec<String>,
+        rows: Vec<Vec<String>>,
+        /// Rows in the whole file.
+        total_rows: usize,
+    },
+    /// Steps of the current task, in order (TaskPlan).
+    Plan { steps: Vec<PlanStep> },
+}
+
+/// One step of a task plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub step: String,
+    #[serde(default)]
+    pub status: StepStatus,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+}
+
+impl StepStatus {
+    /// Checklist marker: "[ ]", "[~]" or "[x]".
+    pub fn marker(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "[ ]",
+            StepStatus::InProgress => "[~]",
+            StepStatus::Completed => "[x]",
+        }
+    }
+}
+
+/// Grep lines of one file, in file order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileMatches {
+    pub path: String,
+    pub lines: Vec<MatchLine>,
+}
+
+/// A matching line, or a context line around one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchLine {
+    /// Line number, from 1.
+    pub number: usize,
+    pub text: String,
+    pub is_match: bool,
+}
+
+impl ToolView {
+    /// One-line description, e.g. "3 directories, 12 files" or "8 matches in 3 files".
+    pub fn summary(&self) -> String {
+        match self {
+            ToolView::Tree {
+                entries, omitted, ..
+            } => {
+                let dirs = entries.iter().filter(|e| e.ends_with('/')).count();
+                let files = count(entries.len() - dirs + omitted, "file", "files");
+                if dirs == 0 {
+                    files
+                } else {
+                    format!("{}, {}", count(dirs, "directory", "directories"), files)
+                }
+            }
+            ToolView::Matches { files, omitted } => {
+                let matches = files
+                    .iter()
+                    .flat_map(|f| &f.lines)
+                    .filter(|l| l.is_match)
+                    .count()
+                    + omitted;
+                format!(
+                    "{} in {}",
+                    count(matches, "match", "matches"),
+                    count(files.len(), "file", "files")
+                )
+            }
+            ToolView::Table {
+                columns,
+                rows,
+                total_rows,
+            } => format!(
+                "{} × {}, first {} shown",
+                count(*total_rows, "row", "rows"),
+                count(columns.len(), "column", "columns"),
+                rows.len()
+            ),
+            ToolView::Plan { steps } => format!(
+                "{}/{} steps done",
+                steps
+                    .iter()
+                    .filter(|s| s.status == StepStatus::Completed)
+                    .count(),
+                steps.len()
+            ),
+        }
+    }
+}
+
+/// "1 file", "3 files".
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// Group grep result lines by file, keeping their order.
+pub(crate) fn group_matches(lines: Vec<(String, MatchLine)>) -> Vec<FileMatches> {
+    let mut files: Vec<FileMatches> = Vec::new();
+    for (path, line) in lines {
+        match files.last_mut() {
+            Some(file) if file.path == path => file.lines.push(line),
+            _ => files.push(FileMatches {
+                path,
+                lines: vec![line],
+            }),
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(number: usize, is_match: bool) -> MatchLine {
+        MatchLine {
+            number,
+            text: String::new(),
+            is_match,
+        }
+    }
+
+    #[test]
+    fn summaries_count_entries_matches_and_rows() {
+        let tree = ToolView::Tree {
+            root: "src".to_string(),
+            entries: vec!["core/".to_string(), "main.rs".to_string()],
+            omitted: 0,
+        };
+        assert_eq!(tree.summary(), "1 directory, 1 file");
+
+        let files = group_matches(vec![
+            ("a.rs".to_string(), line(1, false)),
+            ("a.rs".to_string(), line(2, true)),
+            ("b.rs".to_string(), line(7, true)),
+        ]);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].lines.len(), 2);
+        let matches = ToolView::Matches { files, omitted: 3 };
+        assert_eq!(matches.summary(), "5 matches in 2 files");
+
+        let table = ToolView::Table {
+            columns: vec!["id".to_string()],
+            rows: vec![vec!["1".to_string()]],
+            total_rows: 40,
+        };
+        assert_eq!(table.summary(), "40 rows × 1 column, first 1 shown");
+
+        let plan = ToolView::Plan {
+            steps: vec![
+                PlanStep {
+                    step: "Read".to_string(),
+                    status: StepStatus::Completed,
+                },
+                PlanStep {
+                    step: "Edit".to_string(),
+                    status: StepStatus::InProgress,
+                },
+            ],
+        };
+        assert_eq!(plan.summary(), "1/2 steps done");
+    }
+}
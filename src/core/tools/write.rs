@@ -1,7 +1,10 @@
 use serde::Deserialize;
 use serde_json::{Value, json};
 
-use super::{str_arg, tool_definition};
+use std::path::Path;
+
+use super::{resolve_path, str_arg, tool_definition};
+use crate::core::{redact, remote};
 
 #[derive(Debug, Deserialize)]
 pub struct WriteArgs {
@@ -41,8 +44,12 @@ impl super::Tool for WriteTool {
         true
     }
 
+    fn runs_remotely(&self) -> bool {
+        true
+    }
+
     fn is_init_file_target(&self, file_path: &str) -> bool {
-        std::path::Path::new(file_path)
+        Path::new(file_path)
             .file_name()
             .and_then(|s| s.to_str())
             .map(|n| {
@@ -53,14 +60,28 @@ impl super::Tool for WriteTool {
             .unwrap_or(false)
     }
 
+    fn target_path(&self, args: &Value) -> Option<String> {
+        Some(str_arg(args, "file_path"))
+    }
+
+    fn proposed_change(&self, args: &Value, working_dir: &Path) -> Option<(String, String)> {
+        let parsed: WriteArgs = serde_json::from_value(args.clone()).ok()?;
+        let current = remote::fs::read_to_string(resolve_path(working_dir, &parsed.file_path))
+            .unwrap_or_default();
+        Some((current, parsed.content))
+    }
+
     fn args_preview(&self, args: &Value) -> String {
         str_arg(args, "file_path")
     }
 
-    fn execute(&self, args: &Value) -> Result<String, super::ToolError> {
+    fn execute(&self, args: &Value, working_dir: &Path) -> Result<String, super::ToolError> {
         let parsed: WriteArgs = serde_json::from_value(args.clone())
             .map_err(|e| std::io::Error::other(format!("Invalid arguments: {}", e)))?;
-        std::fs::write(&parsed.file_path, &parsed.content)?;
+        let path = resolve_path(working_dir, &parsed.file_path);
+        let current = remote::fs::read_to_string(&path).unwrap_or_default();
+        redact::check_no_new_placeholders(&current, &parsed.content)?;
+        remote::fs::write(path, &parsed.content)?;
         Ok("OK".to_string())
     }
 }
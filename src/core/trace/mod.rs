@@ -0,0 +1,408 @@
+//! Structured session trace: chat turns, API requests and responses, retries, context
+//! truncation, and tool durations.
+//!
+//! Every event goes to an in-memory ring buffer (read by the TUI debug overlay, F12).
+//! After `init`, events are also appended to a JSONL file per session in `<cache>/traces/`,
+//! unless `MY_OPEN_CLAUDE_TRACE` is 0 or false. With the `otel` feature, turns, API calls,
+//! and tool calls are also exported as OpenTelemetry spans when an OTLP endpoint is set
+//! (see `otel`).
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::core::{paths, redact};
+
+#[cfg(feature = "otel")]
+mod otel;
+
+/// Events kept in memory for the debug overlay.
+const RECENT_CAPACITY: usize = 200;
+/// Trace files kept in the traces directory; older ones are removed on `init`.
+const MAX_TRACE_FILES: usize = 20;
+
+/// A traced event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// A chat turn started: a prompt, or the rest of a turn after a confirmation.
+    TurnStarted { model: String, mode: String },
+    /// A chat turn ended. `outcome` is `complete`, `needs_confirmation`, `cancelled`, or
+    /// `error`; tokens and cost cover every API call of the turn.
+    Turn {
+        model: String,
+        mode: String,
+        duration_ms: u64,
+        outcome: String,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        cost: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// An API call is about to be made.
+    ApiRequest {
+        model: String,
+        messages: usize,
+        request_bytes: usize,
+        streaming: bool,
+    },
+    /// An API call returned.
+    ApiResponse {
+        model: String,
+        duration_ms: u64,
+        content_bytes: usize,
+        tool_calls: usize,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        /// Cost in credits, as reported by the API (0 when not reported).
+        cost: f64,
+    },
+    /// An API call failed.
+    ApiError {
+        model: String,
+        duration_ms: u64,
+        error: String,
+    },
+    /// A transient API error will be retried after `delay_ms`.
+    Retry {
+        attempt: u32,
+        delay_ms: u64,
+        error: String,
+    },
+    /// Streaming failed before any content; the turn continues without streaming.
+    StreamFallback { error: String },
+    /// The oldest messages were dropped to fit the context window.
+    ContextTruncated {
+        removed_messages: usize,
+        context_length: u64,
+    },
+    /// A tool call finished.
+    Tool {
+        name: String,
+        duration_ms: u64,
+        success: bool,
+        output_bytes: usize,
+    },
+    /// Secrets were masked in a tool result before it was sent to the model.
+    Redaction {
+        tool: String,
+        /// Kind of each masked secret (e.g. "GitHub token"); never the secret itself.
+        kinds: Vec<String>,
+    },
+}
+
+impl TraceEvent {
+    /// Event name (the `event` field in the trace file).
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceEvent::TurnStarted { .. } => "turn_started",
+            TraceEvent::Turn { .. } => "turn",
+            TraceEvent::ApiRequest { .. } => "api_request",
+            TraceEvent::ApiResponse { .. } => "api_response",
+            TraceEvent::ApiError { .. } => "api_error",
+            TraceEvent::Retry { .. } => "retry",
+            TraceEvent::StreamFallback { .. } => "stream_fallback",
+            TraceEvent::ContextTruncated { .. } => "context_truncated",
+            TraceEvent::Tool { .. } => "tool",
+            TraceEvent::Redaction { .. } => "redaction",
+        }
+    }
+
+    /// One-line description for the debug overlay.
+    pub fn summary(&self) -> String {
+        match self {
+            TraceEvent::TurnStarted { model, mode } => format!("{} · {}", mode, model),
+            TraceEvent::Turn {
+                duration_ms,
+                outcome,
+                prompt_tokens,
+                completion_tokens,
+                error,
+                ..
+            } => format!(
+                "{} · {} · {}→{} tok{}",
+                outcome,
+                format_ms(*duration_ms),
+                prompt_tokens,
+                completion_tokens,
+                error
+                    .as_ref()
+                    .map(|e| format!(" · {}", e))
+                    .unwrap_or_default()
+            ),
+            TraceEvent::ApiRequest {
+                model,
+                messages,
+                request_bytes,
+                streaming,
+            } => format!(
+                "{} · {} msgs · {}{}",
+                model,
+                messages,
+                format_bytes(*request_bytes),
+                if *streaming { " · stream" } else { "" }
+            ),
+            TraceEvent::ApiResponse {
+                duration_ms,
+                content_bytes,
+                tool_calls,
+                prompt_tokens,
+                completion_tokens,
+                ..
+            } => format!(
+                "{} · {} · {} tool calls · {}→{} tok",
+                format_ms(*duration_ms),
+                format_bytes(*content_bytes),
+                tool_calls,
+                prompt_tokens,
+                completion_tokens
+            ),
+            TraceEvent::ApiError {
+                duration_ms, error, ..
+            } => format!("{} · {}", format_ms(*duration_ms), error),
+            TraceEvent::Retry {
+                attempt,
+                delay_ms,
+                error,
+            } => format!("#{} in {} · {}", attempt, format_ms(*delay_ms), error),
+            TraceEvent::StreamFallback { error } => error.clone(),
+            TraceEvent::ContextTruncated {
+                removed_messages,
+                context_length,
+            } => format!(
+                "dropped {} msgs (context {})",
+                removed_messages, context_length
+            ),
+            TraceEvent::Tool {
+                name,
+                duration_ms,
+                success,
+                output_bytes,
+            } => format!(
+                "{} · {} · {}{}",
+                name,
+                format_ms(*duration_ms),
+                format_bytes(*output_bytes),
+                if *success { "" } else { " · error" }
+            ),
+            TraceEvent::Redaction { tool, kinds } => {
+                format!("{} · masked {}", tool, redact::summary(kinds))
+            }
+        }
+    }
+}
+
+/// A recorded event with its timing.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceRecord {
+    /// Unix time in milliseconds.
+    pub ts_ms: u64,
+    /// Milliseconds since the session started.
+    pub elapsed_ms: u64,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+struct Tracer {
+    started: Instant,
+    recent: Mutex<VecDeque<TraceRecord>>,
+    file: Mutex<Option<(PathBuf, File)>>,
+}
+
+static TRACER: LazyLock<Tracer> = LazyLock::new(|| Tracer {
+    started: Instant::now(),
+    recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+    file: Mutex::new(None),
+});
+
+/// Duration in whole milliseconds (saturating).
+pub fn millis(d: Duration) -> u64 {
+    u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1}KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+/// True unless `MY_OPEN_CLAUDE_TRACE` is 0 or false.
+fn file_trace_enabled() -> bool {
+    env::var("MY_OPEN_CLAUDE_TRACE")
+        .map(|s| s != "0" && !s.eq_ignore_ascii_case("false"))
+        .unwrap_or(true)
+}
+
+/// Remove the oldest trace files, keeping `MAX_TRACE_FILES - 1` (room for the new one).
+fn prune_trace_files(dir: &std::path::Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "jsonl"))
+        .collect();
+    // Names start with a sortable timestamp.
+    files.sort();
+    let excess = files.len().saturating_sub(MAX_TRACE_FILES - 1);
+    for path in files.into_iter().take(excess) {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Failed to remove trace file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Open the session trace file in `<cache>/traces/`, and start the OTLP export when it is
+/// configured (`otel` feature). Returns the file path, or None when file tracing is disabled
+/// or the file cannot be created (events are still kept in memory).
+pub fn init() -> Option<PathBuf> {
+    #[cfg(feature = "otel")]
+    otel::init();
+    if !file_trace_enabled() {
+        return None;
+    }
+    let dir = paths::cache_dir()?.join("traces");
+    if let Err(e) = fs::create_dir_all(&dir) {
+        log::warn!("Trace disabled: cannot create {}: {}", dir.display(), e);
+        return None;
+    }
+    prune_trace_files(&dir);
+    let name = format!(
+        "{}-{}.jsonl",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    );
+    let path = dir.join(name);
+    match File::create(&path) {
+        Ok(file) => {
+            let mut slot = TRACER.file.lock().unwrap_or_else(|e| e.into_inner());
+            *slot = Some((path.clone(), file));
+            Some(path)
+        }
+        Err(e) => {
+            log::warn!("Trace disabled: cannot create {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Send the spans not exported yet, waiting for the export to finish. Call before exiting;
+/// a no-op without the `otel` feature or an OTLP endpoint.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
+
+/// Path of the session trace file, if one is open.
+pub fn file_path() -> Option<PathBuf> {
+    let slot = TRACER.file.lock().unwrap_or_else(|e| e.into_inner());
+    slot.as_ref().map(|(path, _)| path.clone())
+}
+
+/// Record an event: keep it for the overlay and append it to the trace file.
+pub fn record(event: TraceEvent) {
+    let ts_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(millis)
+        .unwrap_or(0);
+    let record = TraceRecord {
+        ts_ms,
+        elapsed_ms: millis(TRACER.started.elapsed()),
+        event,
+    };
+    log::debug!("trace {}: {}", record.event.name(), record.event.summary());
+    #[cfg(feature = "otel")]
+    otel::export(&record);
+
+    {
+        let mut slot = TRACER.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, file)) = slot.as_mut()
+            && let Ok(line) = serde_json::to_string(&record)
+            && let Err(e) = writeln!(file, "{}", line)
+        {
+            log::warn!("Trace write failed, disabling file trace: {}", e);
+            *slot = None;
+        }
+    }
+
+    let mut recent = TRACER.recent.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(record);
+}
+
+/// The last `n` recorded events, oldest first.
+pub fn recent(n: usize) -> Vec<TraceRecord> {
+    let recent = TRACER.recent.lock().unwrap_or_else(|e| e.into_inner());
+    recent
+        .iter()
+        .skip(recent.len().saturating_sub(n))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_serializes_flat_with_event_tag() {
+        let record = TraceRecord {
+            ts_ms: 1,
+            elapsed_ms: 2,
+            event: TraceEvent::Tool {
+                name: "Grep".to_string(),
+                duration_ms: 1500,
+                success: false,
+                output_bytes: 2048,
+            },
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["event"], "tool");
+        assert_eq!(json["name"], "Grep");
+        assert_eq!(json["elapsed_ms"], 2);
+        assert_eq!(record.event.summary(), "Grep · 1.5s · 2.0KB · error");
+    }
+
+    #[test]
+    fn recent_returns_latest_events_in_order() {
+        for i in 0..3 {
+            record(TraceEvent::Retry {
+                attempt: i,
+                delay_ms: 1000,
+                error: "trace-test".to_string(),
+            });
+        }
+        let attempts: Vec<u32> = recent(RECENT_CAPACITY)
+            .into_iter()
+            .filter_map(|r| match r.event {
+                TraceEvent::Retry {
+                    attempt, ref error, ..
+                } if error == "trace-test" => Some(attempt),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(attempts, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,538 @@
+//! OpenTelemetry export (`otel` feature): chat turns, API calls, and tool calls of the trace
+//! as OTLP spans, sent as JSON over HTTP to the collector set by the standard variables:
+//!
+//! - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` (full URL), or `OTEL_EXPORTER_OTLP_ENDPOINT` (base
+//!   URL, `/v1/traces` appended): enables the export
+//! - `OTEL_EXPORTER_OTLP_TRACES_HEADERS` or `OTEL_EXPORTER_OTLP_HEADERS`: `key=value` pairs,
+//!   comma-separated (e.g. an API key of the backend)
+//! - `OTEL_SERVICE_NAME`, `OTEL_RESOURCE_ATTRIBUTES`: resource of the spans
+//! - `OTEL_EXPORTER_OTLP_TIMEOUT`: request timeout in milliseconds (10 s by default)
+//! - `OTEL_SDK_DISABLED=true` or `OTEL_TRACES_EXPORTER=none`: no export
+//!
+//! Only `http/json` is sent: a `grpc` protocol disables the export with a warning. API and
+//! tool spans are children of the turn they happen in; spans are batched and sent from a
+//! background thread, so a slow collector never delays the conversation.
+
+use std::env;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use super::{TraceEvent, TraceRecord};
+use crate::core::network;
+
+/// Spans are sent at most this long after they end.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Spans per export request.
+const MAX_BATCH: usize = 64;
+/// Request timeout without `OTEL_EXPORTER_OTLP_TIMEOUT`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// `service.name` without `OTEL_SERVICE_NAME`.
+const SERVICE_NAME: &str = "my-open-claude";
+
+/// OTLP span kinds.
+const KIND_INTERNAL: u8 = 1;
+const KIND_CLIENT: u8 = 3;
+/// OTLP status code of a failed span.
+const STATUS_ERROR: u8 = 2;
+
+/// Where and how spans are exported.
+#[derive(Debug, Clone, PartialEq)]
+struct OtlpConfig {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    resource: Vec<(String, String)>,
+    timeout: Duration,
+}
+
+impl OtlpConfig {
+    fn from_env() -> Option<Self> {
+        Self::from_vars(|name| env::var(name).ok().filter(|v| !v.trim().is_empty()))
+    }
+
+    /// Configuration from the OTEL variables read by `var`; None when no endpoint is set or
+    /// the export is disabled.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let is = |name: &str, value: &str| {
+            var(name).is_some_and(|v| v.trim().eq_ignore_ascii_case(value))
+        };
+        if is("OTEL_SDK_DISABLED", "true") || is("OTEL_TRACES_EXPORTER", "none") {
+            return None;
+        }
+        let endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").or_else(|| {
+            var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .map(|base| format!("{}/v1/traces", base.trim().trim_end_matches('/')))
+        })?;
+        let protocol = var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_PROTOCOL"))
+            .unwrap_or_default();
+        if protocol.trim().eq_ignore_ascii_case("grpc") {
+            log::warn!("OTLP export disabled: only http/json is supported, not grpc");
+            return None;
+        }
+
+        let mut resource = parse_pairs(&var("OTEL_RESOURCE_ATTRIBUTES").unwrap_or_default());
+        let service = var("OTEL_SERVICE_NAME")
+            .or_else(|| {
+                resource
+                    .iter()
+                    .find(|(key, _)| key == "service.name")
+                    .map(|(_, value)| value.clone())
+            })
+            .unwrap_or_else(|| SERVICE_NAME.to_string());
+        resource.retain(|(key, _)| key != "service.name" && key != "service.version");
+        resource.insert(0, ("service.name".to_string(), service));
+        resource.insert(
+            1,
+            (
+                "service.version".to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+        );
+
+        Some(Self {
+            endpoint: endpoint.trim().to_string(),
+            headers: parse_pairs(
+                &var("OTEL_EXPORTER_OTLP_TRACES_HEADERS")
+                    .or_else(|| var("OTEL_EXPORTER_OTLP_HEADERS"))
+                    .unwrap_or_default(),
+            ),
+            resource,
+            timeout: var("OTEL_EXPORTER_OTLP_TIMEOUT")
+                .and_then(|ms| ms.trim().parse().ok())
+                .map_or(DEFAULT_TIMEOUT, Duration::from_millis),
+        })
+    }
+}
+
+/// `key=value` pairs separated by commas, values percent-decoded (W3C baggage format used by
+/// `OTEL_EXPORTER_OTLP_HEADERS` and `OTEL_RESOURCE_ATTRIBUTES`). Malformed pairs are skipped.
+fn parse_pairs(s: &str) -> Vec<(String, String)> {
+    s.split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_string(), percent_decode(value.trim())))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) if bytes[i] == b'%' => {
+                out.push(byte);
+                i += 3;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Ids of a span: its trace, itself, and its parent (None for a root span).
+struct SpanIds {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+}
+
+/// Trace and span id of the turn in progress, opened by `TurnStarted`.
+#[derive(Clone)]
+struct TurnContext {
+    trace_id: String,
+    span_id: String,
+}
+
+impl TurnContext {
+    fn new() -> Self {
+        Self {
+            trace_id: new_trace_id(),
+            span_id: new_span_id(),
+        }
+    }
+}
+
+fn new_trace_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+fn new_span_id() -> String {
+    Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+fn str_attr(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// 64-bit integers are strings in OTLP JSON.
+fn int_attr(key: &str, value: u64) -> Value {
+    json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn double_attr(key: &str, value: f64) -> Value {
+    json!({ "key": key, "value": { "doubleValue": value } })
+}
+
+/// OTLP span of a turn, API call, or tool call event; None for the other events.
+fn span(record: &TraceRecord, ids: SpanIds) -> Option<Value> {
+    let (name, kind, duration_ms, attributes, error) = match &record.event {
+        TraceEvent::Turn {
+            model,
+            mode,
+            duration_ms,
+            outcome,
+            prompt_tokens,
+            completion_tokens,
+            cost,
+            error,
+        } => (
+            "turn".to_string(),
+            KIND_INTERNAL,
+            *duration_ms,
+            vec![
+                str_attr("gen_ai.request.model", model),
+                str_attr("my_open_claude.mode", mode),
+                str_attr("my_open_claude.outcome", outcome),
+                int_attr("gen_ai.usage.input_tokens", *prompt_tokens),
+                int_attr("gen_ai.usage.output_tokens", *completion_tokens),
+                double_attr("my_open_claude.cost", *cost),
+            ],
+            error.clone(),
+        ),
+        TraceEvent::ApiResponse {
+            model,
+            duration_ms,
+            tool_calls,
+            prompt_tokens,
+            completion_tokens,
+            cost,
+            ..
+        } => (
+            format!("chat {}", model),
+            KIND_CLIENT,
+            *duration_ms,
+            vec![
+                str_attr("gen_ai.operation.name", "chat"),
+                str_attr("gen_ai.request.model", model),
+                int_attr("gen_ai.usage.input_tokens", *prompt_tokens),
+                int_attr("gen_ai.usage.output_tokens", *completion_tokens),
+                double_attr("my_open_claude.cost", *cost),
+                int_attr("my_open_claude.tool_calls", *tool_calls as u64),
+            ],
+            None,
+        ),
+        TraceEvent::ApiError {
+            model,
+            duration_ms,
+            error,
+        } => (
+            format!("chat {}", model),
+            KIND_CLIENT,
+            *duration_ms,
+            vec![
+                str_attr("gen_ai.operation.name", "chat"),
+                str_attr("gen_ai.request.model", model),
+            ],
+            Some(error.clone()),
+        ),
+        TraceEvent::Tool {
+            name,
+            duration_ms,
+            success,
+            output_bytes,
+        } => (
+            format!("execute_tool {}", name),
+            KIND_INTERNAL,
+            *duration_ms,
+            vec![
+                str_attr("gen_ai.operation.name", "execute_tool"),
+                str_attr("gen_ai.tool.name", name),
+                int_attr("my_open_claude.output_bytes", *output_bytes as u64),
+            ],
+            (!success).then(|| "the tool returned an error".to_string()),
+        ),
+        _ => return None,
+    };
+    let end_ns = record.ts_ms.saturating_mul(1_000_000);
+    let start_ns = end_ns.saturating_sub(duration_ms.saturating_mul(1_000_000));
+    let mut span = json!({
+        "traceId": ids.trace_id,
+        "spanId": ids.span_id,
+        "name": name,
+        "kind": kind,
+        "startTimeUnixNano": start_ns.to_string(),
+        "endTimeUnixNano": end_ns.to_string(),
+        "attributes": attributes,
+    });
+    if let Some(parent) = ids.parent_span_id {
+        span["parentSpanId"] = json!(parent);
+    }
+    if let Some(message) = error {
+        span["status"] = json!({ "code": STATUS_ERROR, "message": message });
+    }
+    Some(span)
+}
+
+/// Body of an export request (`ExportTraceServiceRequest` in JSON).
+fn request_body(spans: &[Value], resource: &[(String, String)]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": resource
+                    .iter()
+                    .map(|(key, value)| str_attr(key, value))
+                    .collect::<Vec<_>>(),
+            },
+            "scopeSpans": [{
+                "scope": { "name": SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+struct Exporter {
+    spans: mpsc::Sender<Value>,
+    worker: thread::JoinHandle<()>,
+}
+
+static EXPORTER: Mutex<Option<Exporter>> = Mutex::new(None);
+static TURN: Mutex<Option<TurnContext>> = Mutex::new(None);
+
+/// Start the export when an OTLP endpoint is configured (once per process).
+pub(super) fn init() {
+    let mut slot = EXPORTER.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return;
+    }
+    let Some(config) = OtlpConfig::from_env() else {
+        return;
+    };
+    log::info!("Exporting OpenTelemetry spans to {}", config.endpoint);
+    let (spans, receiver) = mpsc::channel();
+    match thread::Builder::new()
+        .name("otel-export".to_string())
+        .spawn(move || run_worker(&config, &receiver))
+    {
+        Ok(worker) => *slot = Some(Exporter { spans, worker }),
+        Err(e) => log::warn!("OTLP export disabled: cannot start its thread: {}", e),
+    }
+}
+
+/// Queue the span of `record`, if it is one; `turn_started` opens the parent of the spans
+/// that follow, until its `turn` event.
+pub(super) fn export(record: &TraceRecord) {
+    let slot = EXPORTER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(exporter) = slot.as_ref() else {
+        return;
+    };
+    let mut turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let ids = match &record.event {
+        TraceEvent::TurnStarted { .. } => {
+            *turn = Some(TurnContext::new());
+            return;
+        }
+        TraceEvent::Turn { .. } => {
+            let context = turn.take().unwrap_or_else(TurnContext::new);
+            SpanIds {
+                trace_id: context.trace_id,
+                span_id: context.span_id,
+                parent_span_id: None,
+            }
+        }
+        _ => match turn.as_ref() {
+            Some(context) => SpanIds {
+                trace_id: context.trace_id.clone(),
+                span_id: new_span_id(),
+                parent_span_id: Some(context.span_id.clone()),
+            },
+            None => SpanIds {
+                trace_id: new_trace_id(),
+                span_id: new_span_id(),
+                parent_span_id: None,
+            },
+        },
+    };
+    if let Some(span) = span(record, ids) {
+        let _ = exporter.spans.send(span);
+    }
+}
+
+/// Send the queued spans and stop the export thread.
+pub(super) fn shutdown() {
+    let exporter = EXPORTER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(Exporter { spans, worker }) = exporter {
+        drop(spans);
+        let _ = worker.join();
+    }
+}
+
+/// Batch the spans received and send them every `FLUSH_INTERVAL` (or every `MAX_BATCH`
+/// spans), until the sender is dropped.
+fn run_worker(config: &OtlpConfig, spans: &mpsc::Receiver<Value>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::warn!("OTLP export disabled: {}", e);
+            return;
+        }
+    };
+    let client = match network::client_builder().timeout(config.timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("OTLP export disabled: {}", e);
+            return;
+        }
+    };
+    let mut batch = Vec::new();
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let received = match deadline {
+            Some(at) => spans.recv_timeout(at.saturating_duration_since(Instant::now())),
+            None => spans.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let done = match received {
+            Ok(span) => {
+                batch.push(span);
+                deadline.get_or_insert_with(|| Instant::now() + FLUSH_INTERVAL);
+                if batch.len() < MAX_BATCH {
+                    continue;
+                }
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+        if !batch.is_empty() {
+            let body = request_body(&batch, &config.resource);
+            let mut request = client.post(&config.endpoint).json(&body);
+            for (key, value) in &config.headers {
+                request = request.header(key, value);
+            }
+            match runtime.block_on(async { request.send().await?.error_for_status() }) {
+                Ok(_) => log::debug!("Exported {} spans", batch.len()),
+                Err(e) => log::warn!("OTLP export of {} spans failed: {}", batch.len(), e),
+            }
+            batch.clear();
+        }
+        deadline = None;
+        if done {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn config_reads_the_standard_variables() {
+        assert_eq!(OtlpConfig::from_vars(vars(&[])), None);
+        let config = OtlpConfig::from_vars(vars(&[
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318/"),
+            ("OTEL_EXPORTER_OTLP_HEADERS", "x-api-key=a%20b, bad"),
+            ("OTEL_RESOURCE_ATTRIBUTES", "service.name=agents,team=infra"),
+            ("OTEL_EXPORTER_OTLP_TIMEOUT", "2500"),
+        ]))
+        .unwrap();
+        assert_eq!(config.endpoint, "http://collector:4318/v1/traces");
+        assert_eq!(
+            config.headers,
+            vec![("x-api-key".to_string(), "a b".to_string())]
+        );
+        assert_eq!(config.resource[0], ("service.name".into(), "agents".into()));
+        assert_eq!(config.resource[2], ("team".into(), "infra".into()));
+        assert_eq!(config.timeout, Duration::from_millis(2500));
+
+        let disabled = [
+            (
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                "http://collector:4318/v1/traces",
+            ),
+            ("OTEL_SDK_DISABLED", "true"),
+        ];
+        assert_eq!(OtlpConfig::from_vars(vars(&disabled)), None);
+        let grpc = [
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4317"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "grpc"),
+        ];
+        assert_eq!(OtlpConfig::from_vars(vars(&grpc)), None);
+    }
+
+    #[test]
+    fn api_call_span_is_a_child_with_token_and_cost_attributes() {
+        let record = TraceRecord {
+            ts_ms: 2_000,
+            elapsed_ms: 0,
+            event: TraceEvent::ApiResponse {
+                model: "mock/model".to_string(),
+                duration_ms: 500,
+                content_bytes: 10,
+                tool_calls: 1,
+                prompt_tokens: 120,
+                completion_tokens: 30,
+                cost: 0.25,
+            },
+        };
+        let span = span(
+            &record,
+            SpanIds {
+                trace_id: "t".repeat(32),
+                span_id: "s".repeat(16),
+                parent_span_id: Some("p".repeat(16)),
+            },
+        )
+        .unwrap();
+        assert_eq!(span["name"], "chat mock/model");
+        assert_eq!(span["parentSpanId"], "p".repeat(16));
+        assert_eq!(span["startTimeUnixNano"], "1500000000");
+        assert_eq!(span["endTimeUnixNano"], "2000000000");
+        let attributes = span["attributes"].as_array().unwrap();
+        assert!(attributes.contains(&int_attr("gen_ai.usage.input_tokens", 120)));
+        assert!(attributes.contains(&double_attr("my_open_claude.cost", 0.25)));
+        assert!(span.get("status").is_none());
+
+        let started = TraceRecord {
+            ts_ms: 0,
+            elapsed_ms: 0,
+            event: TraceEvent::TurnStarted {
+                model: "mock/model".to_string(),
+                mode: "Build".to_string(),
+            },
+        };
+        let ids = SpanIds {
+            trace_id: new_trace_id(),
+            span_id: new_span_id(),
+            parent_span_id: None,
+        };
+        assert!(super::span(&started, ids).is_none());
+    }
+}
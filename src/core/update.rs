@@ -1,11 +1,32 @@
 //! Self-update from GitHub releases.
 //!
-//! Uses the `self_update` crate to download the latest release and replace
-//! the current binary in place.
+//! Release metadata goes through the `self_update` crate. The asset is picked for the target
+//! triple of this build (`.zip` on Windows, `.tar.gz` elsewhere, as published by the release
+//! workflow) and downloaded to the cache directory: an interrupted download is resumed with
+//! an HTTP range request by the next update, and the archive is checked against the
+//! release's `SHA256SUMS` before it is extracted. The running binary is swapped by renames
+//! in its own directory, which Windows allows for a running executable: the previous binary
+//! is kept as a backup, restored when the swap fails or the new binary does not report the
+//! new version, and by `update --rollback`.
 
-use self_update::Status;
+use std::env;
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::future::Future;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-use crate::core::app;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, RANGE};
+use self_update::update::{Release, ReleaseAsset, ReleaseUpdate};
+use self_update::{Extract, TempDir};
+use sha2::{Digest, Sha256};
+
+use crate::core::{app, network, paths};
+
+/// Asset listing the SHA-256 of the other assets of a release (`sha256sum` output).
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
 
 fn format_update_error(err: &(dyn std::error::Error + 'static)) -> String {
     let msg = err.to_string().to_lowercase();
@@ -17,68 +38,622 @@ fn format_update_error(err: &(dyn std::error::Error + 'static)) -> String {
         "Could not fetch releases. Check your network connection.".to_string()
     } else if msg.contains("not found") || msg.contains("404") {
         "No release found. The project may not have published releases yet.".to_string()
-    } else if msg.contains("no asset") || msg.contains("target") {
-        format!(
-            "No pre-built binary for your platform. Build from source: \
-             https://github.com/{}/{}/releases",
-            app::VENDOR,
-            app::NAME
-        )
     } else {
         format!("Update failed: {}", err)
     }
 }
 
-/// Check if an update is available without downloading.
-///
-/// Fetches release metadata from GitHub and compares with the current version.
-///
-/// # Errors
-/// Returns an error if the release check fails (network, API, etc.).
-pub fn run_update_check() -> Result<(), Box<dyn std::error::Error>> {
-    let updater = self_update::backends::github::Update::configure()
+fn releases_url() -> String {
+    format!("https://github.com/{}/{}/releases", app::VENDOR, app::NAME)
+}
+
+fn no_asset_message(target: &str) -> String {
+    format!(
+        "No pre-built binary for your platform ({}). Build from source: {}",
+        target,
+        releases_url()
+    )
+}
+
+fn updater() -> Result<Box<dyn ReleaseUpdate>, Box<dyn std::error::Error>> {
+    Ok(self_update::backends::github::Update::configure()
         .repo_owner(app::VENDOR)
         .repo_name(app::NAME)
         .bin_name(app::NAME)
         .current_version(app::VERSION)
         .no_confirm(true)
         .show_download_progress(true)
-        .build()?;
-    let current = updater.current_version();
-    let release = updater.get_latest_release().map_err(|e| {
+        .build()?)
+}
+
+/// Release tag of a `--version` pin: "0.4.2" and "v0.4.2" both give "v0.4.2".
+fn release_tag(version: &str) -> Result<String, String> {
+    let version = version.trim();
+    let number = version.strip_prefix('v').unwrap_or(version);
+    semver::Version::parse(number)
+        .map(|_| format!("v{}", number))
+        .map_err(|e| format!("Invalid version '{}': {}", version, e))
+}
+
+/// The release pinned by `version`, or the latest one.
+fn fetch_release(
+    updater: &dyn ReleaseUpdate,
+    version: Option<&str>,
+) -> Result<Release, Box<dyn std::error::Error>> {
+    let Some(version) = version else {
+        return updater
+            .get_latest_release()
+            .map_err(|e| io::Error::other(format_update_error(&e)).into());
+    };
+    let tag = release_tag(version).map_err(io::Error::other)?;
+    updater.get_release_version(&tag).map_err(|e| {
         let msg = format_update_error(&e);
-        std::io::Error::other(msg)
-    })?;
-    let latest = release.version;
-    if semver::Version::parse(&latest)? > semver::Version::parse(&current)? {
-        println!("Update available: v{} (current: v{})", latest, current);
+        if msg.starts_with("No release found") {
+            io::Error::other(format!("No release {} found: see {}", tag, releases_url())).into()
+        } else {
+            io::Error::other(msg).into()
+        }
+    })
+}
+
+/// Archive format of the release assets for this platform.
+fn archive_extension() -> &'static str {
+    if cfg!(windows) { ".zip" } else { ".tar.gz" }
+}
+
+/// File name of the binary, in the release archive and once installed.
+fn bin_file_name() -> String {
+    format!("{}{}", app::NAME, EXE_SUFFIX)
+}
+
+/// Release asset built for `target` (`<name>-<tag>-<target><extension>`).
+fn select_asset<'a>(
+    assets: &'a [ReleaseAsset],
+    target: &str,
+    extension: &str,
+) -> Option<&'a ReleaseAsset> {
+    assets
+        .iter()
+        .find(|a| a.name.contains(target) && a.name.ends_with(extension))
+}
+
+fn is_newer(latest: &str, current: &str) -> Result<bool, semver::Error> {
+    Ok(semver::Version::parse(latest)? > semver::Version::parse(current)?)
+}
+
+/// SHA-256 (lowercase hex) listed for `file` in `sums`, in `sha256sum` format: one
+/// `<hex>  <name>` line per file, with `*` before the name in binary mode.
+fn expected_checksum(sums: &str, file: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+        let name = name.trim_start();
+        let name = name.strip_prefix('*').unwrap_or(name);
+        (name == file && hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hash.to_ascii_lowercase())
+    })
+}
+
+/// SHA-256 of the file at `path`, in lowercase hex.
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where release archives are downloaded (~/.cache/my-open-claude/updates/).
+fn download_dir() -> PathBuf {
+    paths::cache_dir()
+        .unwrap_or_else(|| env::temp_dir().join(app::NAME))
+        .join("updates")
+}
+
+/// How the body of a download response is written, given `on_disk` bytes from an earlier
+/// attempt.
+#[derive(Debug, PartialEq)]
+enum Resume {
+    /// The server sent the rest of the file.
+    Append,
+    /// The server sent the whole file (it ignored the range, or there was nothing to resume).
+    Restart,
+    /// The file on disk is already complete (the range starts at its end).
+    Complete,
+}
+
+fn resume_mode(status: StatusCode, on_disk: u64) -> Result<Resume, String> {
+    match status {
+        StatusCode::PARTIAL_CONTENT if on_disk > 0 => Ok(Resume::Append),
+        StatusCode::RANGE_NOT_SATISFIABLE if on_disk > 0 => Ok(Resume::Complete),
+        s if s.is_success() => Ok(Resume::Restart),
+        s => Err(format!("Download failed: HTTP {}", s)),
+    }
+}
+
+/// Run `future` on a thread of its own: the update commands are called from the main
+/// runtime, which cannot block on another future.
+fn block_on<T: Send + 'static>(
+    future: impl Future<Output = io::Result<T>> + Send + 'static,
+) -> io::Result<T> {
+    std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(future)
+    })
+    .join()
+    .map_err(|_| io::Error::other("The download thread panicked"))?
+}
+
+/// GET a release asset (its API URL, which redirects to the file), from `offset` when > 0.
+async fn get_asset(url: &str, offset: u64) -> io::Result<reqwest::Response> {
+    let client = network::client_builder()
+        .user_agent(format!("{}/{}", app::NAME, app::VERSION))
+        .build()
+        .map_err(io::Error::other)?;
+    let mut request = client.get(url).header(ACCEPT, "application/octet-stream");
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    request
+        .send()
+        .await
+        .map_err(|e| io::Error::other(format_update_error(&e)))
+}
+
+async fn fetch_text(url: String) -> io::Result<String> {
+    let response = get_asset(&url, 0).await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(io::Error::other(format!(
+            "Download failed: HTTP {}",
+            status
+        )));
+    }
+    response.text().await.map_err(io::Error::other)
+}
+
+fn megabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Download `url` to `path`, continuing from the bytes already there. When the transfer
+/// breaks, they are kept for the next attempt.
+async fn download(url: String, path: PathBuf) -> io::Result<()> {
+    let on_disk = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let mut response = get_asset(&url, on_disk).await?;
+    let (mut file, mut done) =
+        match resume_mode(response.status(), on_disk).map_err(io::Error::other)? {
+            Resume::Complete => return Ok(()),
+            Resume::Append => {
+                println!("Resuming at {:.1} MB", megabytes(on_disk));
+                let file = fs::OpenOptions::new().append(true).open(&path)?;
+                (file, on_disk)
+            }
+            Resume::Restart => (fs::File::create(&path)?, 0),
+        };
+    let total = response.content_length().map(|len| len + done);
+    let progress = io::stderr().is_terminal();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                if progress {
+                    eprintln!();
+                }
+                return Err(io::Error::other(format!(
+                    "Download interrupted at {:.1} MB ({}). Run the update again to resume.",
+                    megabytes(done),
+                    e
+                )));
+            }
+        };
+        file.write_all(&chunk)?;
+        done += chunk.len() as u64;
+        if progress {
+            match total {
+                Some(total) => eprint!(
+                    "\r{:.1} / {:.1} MB ({}%)",
+                    megabytes(done),
+                    megabytes(total),
+                    done * 100 / total.max(1)
+                ),
+                None => eprint!("\r{:.1} MB", megabytes(done)),
+            }
+        }
+    }
+    if progress {
+        eprintln!();
+    }
+    file.flush()?;
+    match total {
+        Some(total) if done < total => Err(io::Error::other(format!(
+            "Download interrupted at {:.1} of {:.1} MB. Run the update again to resume.",
+            megabytes(done),
+            megabytes(total)
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Download `asset` of `release` to the update cache, resuming an earlier partial download,
+/// and check it against the release's checksums. Returns the path of the archive.
+fn download_asset(
+    release: &Release,
+    asset: &ReleaseAsset,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = download_dir();
+    fs::create_dir_all(&dir)
+        .map_err(|e| io::Error::other(format!("Cannot write to {}: {}", dir.display(), e)))?;
+    let part = dir.join(format!("{}.part", asset.name));
+    // Downloads of other releases (or assets) cannot be resumed by this one.
+    for entry in fs::read_dir(&dir)?.flatten() {
+        if entry.path() != part {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+
+    let expected = match release.assets.iter().find(|a| a.name == CHECKSUMS_ASSET) {
+        Some(sums) => {
+            let sums = block_on(fetch_text(sums.download_url.clone()))?;
+            Some(expected_checksum(&sums, &asset.name).ok_or_else(|| {
+                io::Error::other(format!(
+                    "{} of v{} has no checksum for {}",
+                    CHECKSUMS_ASSET, release.version, asset.name
+                ))
+            })?)
+        }
+        None => {
+            eprintln!(
+                "Warning: v{} publishes no {}; the download cannot be verified.",
+                release.version, CHECKSUMS_ASSET
+            );
+            None
+        }
+    };
+
+    println!("Downloading {}...", asset.name);
+    block_on(download(asset.download_url.clone(), part.clone()))?;
+    if let Some(expected) = expected {
+        let actual = sha256_file(&part)?;
+        if actual != expected {
+            let _ = fs::remove_file(&part);
+            return Err(io::Error::other(format!(
+                "Checksum mismatch for {} (expected {}, got {}). The download was discarded; \
+                 run the update again.",
+                asset.name, expected, actual
+            ))
+            .into());
+        }
+        println!("Checksum verified (SHA-256).");
+    }
+    // The archive format is read from the file extension.
+    let archive = dir.join(&asset.name);
+    fs::rename(&part, &archive)?;
+    Ok(archive)
+}
+
+/// Where the previous binary is kept after an update, next to `exe`.
+fn backup_path(exe: &Path) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(".old");
+    exe.with_file_name(name)
+}
+
+/// Move `new_exe` to `exe`, keeping the previous binary at `backup`. All three paths must be
+/// on the same filesystem. If the new binary cannot be moved in, the previous one is put back.
+fn replace_binary(exe: &Path, new_exe: &Path, backup: &Path) -> io::Result<()> {
+    if backup.exists() {
+        fs::remove_file(backup)?;
+    }
+    fs::rename(exe, backup)?;
+    if let Err(e) = fs::rename(new_exe, exe) {
+        fs::rename(backup, exe)?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Exchange `exe` and `backup`, so that a rollback can itself be undone by another one.
+fn swap_binaries(exe: &Path, backup: &Path) -> io::Result<()> {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(".swap");
+    let swap = exe.with_file_name(name);
+    fs::rename(exe, &swap)?;
+    if let Err(e) = fs::rename(backup, exe) {
+        fs::rename(&swap, exe)?;
+        return Err(e);
+    }
+    fs::rename(&swap, backup)
+}
+
+/// Version printed by `exe --version` ("my-open-claude 0.4.2" gives "0.4.2"), if it runs.
+fn binary_version(exe: &Path) -> Option<String> {
+    let out = Command::new(exe).arg("--version").output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&out.stdout)
+        .split_whitespace()
+        .last()
+        .map(str::to_string)
+}
+
+/// Check if an update is available without downloading.
+///
+/// Fetches release metadata from GitHub and compares with the current version; with
+/// `version`, checks that this release exists instead.
+///
+/// # Errors
+/// Returns an error if the release check fails (network, API, etc.).
+pub fn run_update_check(version: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let updater = updater()?;
+    let current = updater.current_version();
+    let release = fetch_release(&*updater, version)?;
+    if release.version == current {
+        println!("Already up to date (v{})", current);
+        return Ok(());
+    }
+    if version.is_some() {
+        println!("v{} is available (current: v{})", release.version, current);
+    } else if is_newer(&release.version, &current)? {
+        println!(
+            "Update available: v{} (current: v{})",
+            release.version, current
+        );
     } else {
         println!("Already up to date (v{})", current);
+        return Ok(());
+    }
+    let target = self_update::get_target();
+    if select_asset(&release.assets, target, archive_extension()).is_none() {
+        println!("{}", no_asset_message(target));
     }
     Ok(())
 }
 
-/// Run the self-update: fetch latest release from GitHub and replace the binary.
+/// Run the self-update: fetch the latest release from GitHub (or the one pinned by
+/// `version`, older ones included) and replace the binary, keeping the previous one as
+/// `<binary>.old` for `run_rollback`.
 ///
 /// # Errors
-/// Returns an error if the update check, download, or replacement fails.
-pub fn run_update() -> Result<(), Box<dyn std::error::Error>> {
-    let updater = self_update::backends::github::Update::configure()
-        .repo_owner(app::VENDOR)
-        .repo_name(app::NAME)
-        .bin_name(app::NAME)
-        .current_version(app::VERSION)
-        .no_confirm(true)
-        .show_download_progress(true)
-        .build()?;
-    let status = updater.update().map_err(|e| {
-        let msg = format_update_error(&e);
-        std::io::Error::other(msg)
-    })?;
+/// Returns an error if the release check, download, checksum, or replacement fails. When
+/// the new binary cannot be installed or does not start, the previous one is restored.
+pub fn run_update(version: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let updater = updater()?;
+    let current = updater.current_version();
+    let release = fetch_release(&*updater, version)?;
+    if release.version == current || (version.is_none() && !is_newer(&release.version, &current)?) {
+        println!("Already up to date (v{})", current);
+        return Ok(());
+    }
+    let target = self_update::get_target();
+    let asset = select_asset(&release.assets, target, archive_extension())
+        .ok_or_else(|| io::Error::other(no_asset_message(target)))?;
 
-    match status {
-        Status::UpToDate(v) => println!("Already up to date (v{})", v),
-        Status::Updated(v) => println!("Updated to v{}!", v),
+    // Replace the file itself, not a symlink to it.
+    let exe = fs::canonicalize(env::current_exe()?)?;
+    let dir = exe
+        .parent()
+        .ok_or_else(|| io::Error::other("Could not determine the binary's directory"))?;
+    let backup = backup_path(&exe);
+
+    let archive = download_asset(&release, asset)?;
+    // Extract next to the binary so the final move is a rename on the same filesystem.
+    let tmp = TempDir::with_prefix_in(format!("{}_download", app::NAME), dir)
+        .map_err(|e| io::Error::other(format!("Cannot write to {}: {}", dir.display(), e)))?;
+    Extract::from_source(&archive).extract_file(tmp.path(), bin_file_name())?;
+    let _ = fs::remove_file(&archive);
+    let new_exe = tmp.path().join(bin_file_name());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))?;
     }
+
+    replace_binary(&exe, &new_exe, &backup)
+        .map_err(|e| io::Error::other(format!("Could not replace {}: {}", exe.display(), e)))?;
+    if binary_version(&exe).as_deref() != Some(release.version.as_str()) {
+        fs::rename(&backup, &exe)?;
+        return Err(io::Error::other(format!(
+            "The new binary (v{}) did not start. Restored v{}.",
+            release.version, current
+        ))
+        .into());
+    }
+    println!("Updated to v{}!", release.version);
+    println!(
+        "v{} is kept as {}: `{} update --rollback` restores it.",
+        current,
+        backup.display(),
+        app::NAME
+    );
     Ok(())
 }
+
+/// Put back the binary replaced by the last update (`<binary>.old`); the current one takes
+/// its place, so a second rollback undoes the first.
+///
+/// # Errors
+/// Returns an error if there is no backup, it does not start, or the swap fails.
+pub fn run_rollback() -> Result<(), Box<dyn std::error::Error>> {
+    let exe = fs::canonicalize(env::current_exe()?)?;
+    let backup = backup_path(&exe);
+    if !backup.exists() {
+        return Err(io::Error::other(format!(
+            "No previous binary to roll back to ({} not found).",
+            backup.display()
+        ))
+        .into());
+    }
+    let previous = binary_version(&backup).ok_or_else(|| {
+        io::Error::other(format!(
+            "The previous binary ({}) does not start; not rolling back.",
+            backup.display()
+        ))
+    })?;
+    swap_binaries(&exe, &backup)
+        .map_err(|e| io::Error::other(format!("Could not restore {}: {}", exe.display(), e)))?;
+    println!(
+        "Rolled back to v{} (v{} is kept as {}).",
+        previous,
+        app::VERSION,
+        backup.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str) -> ReleaseAsset {
+        ReleaseAsset {
+            download_url: format!("https://example.com/{}", name),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn select_asset_matches_target_and_extension() {
+        let assets = [
+            asset("my-open-claude-v1.0.0-x86_64-unknown-linux-gnu.tar.gz"),
+            asset("my-open-claude-v1.0.0-aarch64-apple-darwin.tar.gz"),
+            asset("my-open-claude-v1.0.0-x86_64-pc-windows-msvc.zip"),
+            asset(CHECKSUMS_ASSET),
+        ];
+        let windows = select_asset(&assets, "x86_64-pc-windows-msvc", ".zip").unwrap();
+        assert!(windows.name.ends_with("x86_64-pc-windows-msvc.zip"));
+        let linux = select_asset(&assets, "x86_64-unknown-linux-gnu", ".tar.gz").unwrap();
+        assert!(linux.name.contains("linux"));
+        assert!(select_asset(&assets, "x86_64-pc-windows-msvc", ".tar.gz").is_none());
+        assert!(select_asset(&assets, "x86_64-apple-darwin", ".tar.gz").is_none());
+    }
+
+    #[test]
+    fn release_tag_accepts_optional_v_prefix() {
+        assert_eq!(release_tag("0.4.2").unwrap(), "v0.4.2");
+        assert_eq!(release_tag(" v0.4.2 ").unwrap(), "v0.4.2");
+        assert!(release_tag("latest").is_err());
+        assert!(release_tag("0.4").is_err());
+    }
+
+    #[test]
+    fn expected_checksum_reads_sha256sum_output() {
+        let hash = "a".repeat(64);
+        let upper = "B".repeat(64);
+        let sums = format!(
+            "{}  app-v1.0.0-x86_64-unknown-linux-gnu.tar.gz\n{} *app-v1.0.0-x86_64-pc-windows-msvc.zip\nshort  other.tar.gz\n",
+            hash, upper
+        );
+        assert_eq!(
+            expected_checksum(&sums, "app-v1.0.0-x86_64-unknown-linux-gnu.tar.gz"),
+            Some(hash)
+        );
+        assert_eq!(
+            expected_checksum(&sums, "app-v1.0.0-x86_64-pc-windows-msvc.zip"),
+            Some("b".repeat(64))
+        );
+        assert_eq!(expected_checksum(&sums, "other.tar.gz"), None);
+        assert_eq!(expected_checksum(&sums, "missing.zip"), None);
+    }
+
+    #[test]
+    fn sha256_file_matches_known_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn resume_mode_follows_response_status() {
+        assert_eq!(
+            resume_mode(StatusCode::PARTIAL_CONTENT, 100),
+            Ok(Resume::Append)
+        );
+        // The server ignored the range: start over.
+        assert_eq!(resume_mode(StatusCode::OK, 100), Ok(Resume::Restart));
+        assert_eq!(resume_mode(StatusCode::OK, 0), Ok(Resume::Restart));
+        assert_eq!(
+            resume_mode(StatusCode::RANGE_NOT_SATISFIABLE, 100),
+            Ok(Resume::Complete)
+        );
+        assert!(resume_mode(StatusCode::RANGE_NOT_SATISFIABLE, 0).is_err());
+        assert!(resume_mode(StatusCode::NOT_FOUND, 100).is_err());
+    }
+
+    #[test]
+    fn backup_path_keeps_exe_suffix() {
+        assert_eq!(
+            backup_path(Path::new("/bin/my-open-claude.exe")),
+            PathBuf::from("/bin/my-open-claude.exe.old")
+        );
+    }
+
+    #[test]
+    fn replace_binary_keeps_previous_as_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app");
+        let new_exe = dir.path().join("app.new");
+        let backup = backup_path(&exe);
+        fs::write(&exe, "old").unwrap();
+        fs::write(&new_exe, "new").unwrap();
+        fs::write(&backup, "stale").unwrap();
+
+        replace_binary(&exe, &new_exe, &backup).unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+        assert!(!new_exe.exists());
+    }
+
+    #[test]
+    fn replace_binary_restores_previous_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app");
+        let backup = backup_path(&exe);
+        fs::write(&exe, "old").unwrap();
+
+        let missing = dir.path().join("missing");
+        assert!(replace_binary(&exe, &missing, &backup).is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old");
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn swap_binaries_exchanges_current_and_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app");
+        let backup = backup_path(&exe);
+        fs::write(&exe, "new").unwrap();
+        fs::write(&backup, "old").unwrap();
+
+        swap_binaries(&exe, &backup).unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "new");
+        swap_binaries(&exe, &backup).unwrap();
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "old");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn swap_binaries_without_backup_leaves_current() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("app");
+        fs::write(&exe, "new").unwrap();
+
+        assert!(swap_binaries(&exe, &backup_path(&exe)).is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "new");
+    }
+}
@@ -1,7 +1,10 @@
 //! Generic utilities used across core modules.
 
-/// Filter items by case-insensitive query matching on two string fields.
-/// Returns all items when query is empty.
+use crate::core::fuzzy;
+
+/// Filter items by fuzzy matching the query on two string fields, best matches first.
+/// A match on the first field ranks above an equally good match on the second; ties keep
+/// the input order. Returns all items, in order, when query is empty.
 pub fn filter_by_query<'a, T, F>(items: &'a [T], query: &str, get_fields: F) -> Vec<&'a T>
 where
     F: Fn(&'a T) -> (&str, &str),
@@ -9,14 +12,17 @@ where
     if query.is_empty() {
         return items.iter().collect();
     }
-    let q = query.to_lowercase();
-    items
+    let mut scored: Vec<((i32, bool), &T)> = items
         .iter()
-        .filter(|item| {
+        .filter_map(|item| {
             let (a, b) = get_fields(item);
-            a.to_lowercase().contains(&q) || b.to_lowercase().contains(&q)
+            let first = fuzzy::fuzzy_match(query, a).map(|m| (m.score, true));
+            let second = fuzzy::fuzzy_match(query, b).map(|m| (m.score, false));
+            first.max(second).map(|rank| (rank, item))
         })
-        .collect()
+        .collect();
+    scored.sort_by_key(|(rank, _)| std::cmp::Reverse(*rank));
+    scored.into_iter().map(|(_, item)| item).collect()
 }
 
 #[cfg(test)]
@@ -51,6 +57,20 @@ mod tests {
         assert_eq!(out, vec![&"World"]);
     }
 
+    #[test]
+    fn filter_fuzzy_ranks_best_match_first() {
+        let items = vec![
+            ("gpt", "openai/gpt-4o-mini"),
+            ("sonnet", "anthropic/claude-sonnet-4"),
+            ("cs", "description mentioning claude sonnet"),
+        ];
+        let out = filter_by_query(&items, "cson", |t| (t.1, t.0));
+        assert_eq!(out, vec![&items[1], &items[2]]);
+        // A consecutive match on the second field beats a scattered one on the first.
+        let out = filter_by_query(&items, "cs", |t| (t.1, t.0));
+        assert_eq!(out, vec![&items[2], &items[1]]);
+    }
+
     #[test]
     fn filter_no_match_returns_empty() {
         let items = vec!["hello", "world"];
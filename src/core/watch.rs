@@ -0,0 +1,262 @@
+//! Watch mode (`watch` subcommand): run a prompt again each time files matching the given
+//! glob patterns change, in one rolling conversation.
+//!
+//! Changes are debounced: a run starts once no matching file changed for the debounce
+//! delay, so a save that touches several files (or a formatter run) triggers a single run.
+//! Files under the always-ignored directories (`target`, `node_modules`, `.git`, ...) never
+//! trigger a run, and neither do the files the agent itself wrote during the previous run.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::core::tools::ignore::IGNORED_DIRS;
+
+/// Quiet time after the last change before a run starts, unless `--debounce` is given.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Most changed files named in the prompt of a run.
+const MAX_LISTED_CHANGES: usize = 20;
+
+/// Error starting a watch.
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("invalid glob '{pattern}': {source}")]
+    Glob {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+    #[error("cannot watch {path}: {source}")]
+    Notify {
+        path: String,
+        #[source]
+        source: notify::Error,
+    },
+}
+
+/// Watches the workspace for changes to files matching the glob patterns.
+pub struct FileWatcher {
+    /// Kept alive for as long as events are wanted; dropping it stops the watch.
+    _watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<notify::Result<Event>>,
+    root: PathBuf,
+    globs: GlobSet,
+    debounce: Duration,
+    /// Matching files changed since the last reported batch (relative to the root).
+    changed: BTreeSet<String>,
+    /// Time of the last matching change not yet reported.
+    last_change: Option<Instant>,
+}
+
+impl FileWatcher {
+    /// Watch `root` recursively for changes to files matching `patterns` (relative to
+    /// `root`, e.g. `src/**/*.rs`).
+    pub fn new(root: &Path, patterns: &[String], debounce: Duration) -> Result<Self, WatchError> {
+        let globs = glob_set(patterns)?;
+        // Events carry canonical paths (e.g. /private/var on macOS).
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let (tx, rx) = mpsc::channel();
+        let notify_error = |source| WatchError::Notify {
+            path: root.display().to_string(),
+            source,
+        };
+        let mut watcher = notify::recommended_watcher(tx).map_err(notify_error)?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(notify_error)?;
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            root,
+            globs,
+            debounce,
+            changed: BTreeSet::new(),
+            last_change: None,
+        })
+    }
+
+    /// Drain pending events. Returns the changed files (sorted, relative to the root) once
+    /// a matching file changed and no further change came for the debounce delay; they are
+    /// then considered reported.
+    pub fn poll(&mut self) -> Option<Vec<String>> {
+        self.drain();
+        match self.last_change {
+            Some(t) if t.elapsed() >= self.debounce => {
+                self.last_change = None;
+                Some(std::mem::take(&mut self.changed).into_iter().collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget the pending changes to `written` (files the agent wrote or edited), so a run
+    /// is not triggered by its own edits.
+    pub fn forget(&mut self, written: &[PathBuf]) {
+        self.drain();
+        for path in written {
+            let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if let Some(relative) = relative_path(&self.root, &path) {
+                self.changed.remove(&relative);
+            }
+        }
+        if self.changed.is_empty() {
+            self.last_change = None;
+        }
+    }
+
+    fn drain(&mut self) {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                Ok(event) => {
+                    let paths = matching_paths(&event, &self.root, &self.globs);
+                    if !paths.is_empty() {
+                        self.changed.extend(paths);
+                        self.last_change = Some(Instant::now());
+                    }
+                }
+                Err(e) => log::warn!("File watcher error: {}", e),
+            }
+        }
+    }
+}
+
+/// Prompt of a run: `prompt`, followed by the files whose change triggered it.
+pub fn run_prompt(prompt: &str, changed: &[String]) -> String {
+    if changed.is_empty() {
+        return prompt.to_string();
+    }
+    let mut listed = changed
+        .iter()
+        .take(MAX_LISTED_CHANGES)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if changed.len() > MAX_LISTED_CHANGES {
+        listed.push_str(&format!(" and {} more", changed.len() - MAX_LISTED_CHANGES));
+    }
+    format!("{}\n\nFiles changed since the last run: {}", prompt, listed)
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, WatchError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_start_matches("./");
+        let glob = Glob::new(pattern).map_err(|source| WatchError::Glob {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|source| WatchError::Glob {
+        pattern: patterns.join(" "),
+        source,
+    })
+}
+
+/// Files of `event` (relative to `root`) that match `globs`, outside ignored directories.
+/// Reads (access events) are not changes.
+fn matching_paths(event: &Event, root: &Path, globs: &GlobSet) -> Vec<String> {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return Vec::new();
+    }
+    event
+        .paths
+        .iter()
+        .filter_map(|path| relative_path(root, path))
+        .filter(|relative| globs.is_match(relative))
+        .collect()
+}
+
+/// `path` relative to `root`, with `/` separators; None outside the root or inside an
+/// ignored directory.
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        let part = component.as_os_str().to_str()?;
+        parts.push(part);
+    }
+    let (_, dirs) = parts.split_last()?;
+    if dirs.iter().any(|dir| IGNORED_DIRS.contains(dir)) {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, ModifyKind};
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn only_matching_files_outside_ignored_dirs_count() {
+        let globs = glob_set(&["src/**/*.rs".to_string(), "./Cargo.toml".to_string()]).unwrap();
+        let root = Path::new("/repo");
+        let changed = |kind, path| matching_paths(&event(kind, path), root, &globs);
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert_eq!(
+            changed(modify, "/repo/src/core/llm/mod.rs"),
+            ["src/core/llm/mod.rs"]
+        );
+        assert_eq!(changed(modify, "/repo/Cargo.toml"), ["Cargo.toml"]);
+        assert!(changed(modify, "/repo/README.md").is_empty());
+        assert!(changed(modify, "/repo/src/target/gen.rs").is_empty());
+        assert!(changed(modify, "/elsewhere/src/main.rs").is_empty());
+        assert!(changed(EventKind::Access(AccessKind::Any), "/repo/src/main.rs").is_empty());
+    }
+
+    #[test]
+    fn invalid_globs_are_reported() {
+        let err = glob_set(&["src/[".to_string()]).unwrap_err();
+        assert!(
+            err.to_string().starts_with("invalid glob 'src/['"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn run_prompt_lists_the_changed_files() {
+        assert_eq!(run_prompt("Run the tests", &[]), "Run the tests");
+        let changed = vec!["src/a.rs".to_string(), "src/b.rs".to_string()];
+        assert_eq!(
+            run_prompt("Run the tests", &changed),
+            "Run the tests\n\nFiles changed since the last run: src/a.rs, src/b.rs"
+        );
+        let many: Vec<String> = (0..25).map(|i| format!("f{}.rs", i)).collect();
+        assert!(run_prompt("p", &many).ends_with("f19.rs and 5 more"));
+    }
+
+    #[test]
+    fn watcher_reports_a_change_after_the_debounce() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let mut watcher = FileWatcher::new(
+            dir.path(),
+            &["src/*.rs".to_string()],
+            Duration::from_millis(50),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("notes.md"), "x").unwrap();
+        let started = Instant::now();
+        let changed = loop {
+            if let Some(changed) = watcher.poll() {
+                break changed;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "no change seen");
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(changed, ["src/lib.rs"]);
+        assert!(watcher.poll().is_none());
+    }
+}
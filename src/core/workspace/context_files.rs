@@ -0,0 +1,258 @@
+//! Context files: files a project wants in every request (a database schema, an API spec),
+//! listed in `<root>/.my-open-claude/context.yaml`:
+//!
+//! ```yaml
+//! files:
+//!   - db/schema.sql
+//!   - docs/api/*.yaml
+//! max_file_bytes: 32768    # optional, per file
+//! max_total_bytes: 131072  # optional, all files together
+//! ```
+//!
+//! Entries are paths relative to the workspace root, or glob patterns matched against the
+//! workspace files (ignored directories are skipped). Content past the caps is cut, and the
+//! system prompt says so.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::GlobBuilder;
+use serde::Deserialize;
+
+use crate::core::persistence::WORKSPACE_SETTINGS_DIR;
+use crate::core::pins;
+
+/// Name of the context files list in the workspace settings directory.
+pub const CONTEXT_FILES_CONFIG: &str = "context.yaml";
+/// Default largest content sent per file.
+pub const DEFAULT_MAX_FILE_BYTES: usize = 32 * 1024;
+/// Default largest content sent for all the files together.
+pub const DEFAULT_MAX_TOTAL_BYTES: usize = 128 * 1024;
+
+/// Content of `context.yaml`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContextConfig {
+    #[serde(default)]
+    files: Vec<String>,
+    max_file_bytes: Option<usize>,
+    max_total_bytes: Option<usize>,
+}
+
+/// A file of the context files list, as sent in the system prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextFile {
+    /// Path relative to the workspace root.
+    pub path: String,
+    /// Content sent, cut at the size caps.
+    pub content: String,
+    /// Size of the file, in bytes.
+    pub size: usize,
+}
+
+impl ContextFile {
+    /// The content was cut by the size caps.
+    pub fn truncated(&self) -> bool {
+        self.content.len() < self.size
+    }
+
+    /// Estimated tokens of the content sent (bytes / 4).
+    pub fn tokens(&self) -> usize {
+        self.content.len() / 4
+    }
+}
+
+/// Path of the context files list of the workspace at `root`.
+pub fn config_path(root: &Path) -> PathBuf {
+    root.join(WORKSPACE_SETTINGS_DIR).join(CONTEXT_FILES_CONFIG)
+}
+
+/// Read the files listed in `root`'s `context.yaml`, in list order. Empty when the list is
+/// absent or invalid (invalid lists are logged and ignored); entries matching no file are
+/// logged and skipped.
+pub fn load(root: &Path) -> Vec<ContextFile> {
+    let path = config_path(root);
+    let Ok(yaml) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let config = match serde_yaml::from_str::<Option<ContextConfig>>(&yaml) {
+        Ok(Some(config)) => config,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            log::warn!("Ignoring invalid {}: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+    let max_file = config.max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES);
+    let mut budget = config.max_total_bytes.unwrap_or(DEFAULT_MAX_TOTAL_BYTES);
+    let mut files: Vec<ContextFile> = Vec::new();
+    for rel in resolve(root, &config.files) {
+        if files.iter().any(|f| f.path == rel) {
+            continue;
+        }
+        let bytes = match fs::read(root.join(&rel)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("Context file {} not read: {}", rel, e);
+                continue;
+            }
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let cap = max_file.min(budget);
+        let content = text[..text.floor_char_boundary(cap)].to_string();
+        budget -= content.len();
+        files.push(ContextFile {
+            path: rel,
+            size: text.len(),
+            content,
+        });
+    }
+    files
+}
+
+/// Relative paths of the files named by `entries`: existing paths as given, glob patterns
+/// expanded (sorted) against the workspace files.
+fn resolve(root: &Path, entries: &[String]) -> Vec<String> {
+    let mut workspace_files: Option<Vec<String>> = None;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry.trim().trim_start_matches("./");
+        if entry.is_empty() {
+            continue;
+        }
+        if !entry.contains(['*', '?', '[', '{']) {
+            if root.join(entry).is_file() {
+                paths.push(entry.to_string());
+            } else {
+                log::warn!("Context file {} not found", entry);
+            }
+            continue;
+        }
+        let matcher = match GlobBuilder::new(entry).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                log::warn!("Invalid context file pattern {}: {}", entry, e);
+                continue;
+            }
+        };
+        let files = workspace_files.get_or_insert_with(|| pins::workspace_files(root));
+        let before = paths.len();
+        paths.extend(files.iter().filter(|f| matcher.is_match(f)).cloned());
+        if paths.len() == before {
+            log::warn!("Context file pattern {} matches no file", entry);
+        }
+    }
+    paths
+}
+
+/// Descriptions of the changes from `old` to `new` ("context file added: db/schema.sql").
+pub(super) fn changes(old: &[ContextFile], new: &[ContextFile]) -> Vec<String> {
+    let mut changes = Vec::new();
+    for file in new {
+        match old.iter().find(|f| f.path == file.path) {
+            None => changes.push(format!("context file added: {}", file.path)),
+            Some(previous) if previous.content != file.content => {
+                changes.push(format!("context file updated: {}", file.path))
+            }
+            Some(_) => {}
+        }
+    }
+    for file in old {
+        if !new.iter().any(|f| f.path == file.path) {
+            changes.push(format!("context file removed: {}", file.path));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn load_reads_paths_and_globs_in_order() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "db/schema.sql", "CREATE TABLE users;");
+        write(root, "docs/api/users.yaml", "users");
+        write(root, "docs/api/orders.yaml", "orders");
+        write(root, "docs/api/v1/old.yaml", "old");
+        write(
+            root,
+            ".my-open-claude/context.yaml",
+            "files:\n  - db/schema.sql\n  - docs/api/*.yaml\n  - ./db/schema.sql\n  - missing.md\n",
+        );
+        let files = load(root);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "db/schema.sql",
+                "docs/api/orders.yaml",
+                "docs/api/users.yaml"
+            ]
+        );
+        assert_eq!(files[0].content, "CREATE TABLE users;");
+        assert!(!files[0].truncated());
+    }
+
+    #[test]
+    fn load_applies_file_and_total_caps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "a.txt", &"a".repeat(100));
+        write(root, "b.txt", &"b".repeat(100));
+        write(root, "c.txt", "c");
+        write(
+            root,
+            ".my-open-claude/context.yaml",
+            "files: [a.txt, b.txt, c.txt]\nmax_file_bytes: 60\nmax_total_bytes: 100\n",
+        );
+        let files = load(root);
+        assert_eq!(files[0].content.len(), 60);
+        assert_eq!(files[1].content.len(), 40);
+        assert_eq!(files[2].content, "");
+        assert!(files.iter().all(ContextFile::truncated));
+        assert_eq!(files[0].size, 100);
+    }
+
+    #[test]
+    fn load_ignores_missing_and_invalid_lists() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(load(dir.path()).is_empty());
+        write(
+            dir.path(),
+            ".my-open-claude/context.yaml",
+            "file: [a.txt]\n",
+        );
+        assert!(load(dir.path()).is_empty());
+        write(dir.path(), ".my-open-claude/context.yaml", "");
+        assert!(load(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn changes_describe_added_updated_and_removed_files() {
+        let file = |path: &str, content: &str| ContextFile {
+            path: path.to_string(),
+            content: content.to_string(),
+            size: content.len(),
+        };
+        let old = vec![file("a.sql", "v1"), file("b.yaml", "x")];
+        let new = vec![file("a.sql", "v2"), file("c.md", "y")];
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                "context file updated: a.sql",
+                "context file added: c.md",
+                "context file removed: b.yaml"
+            ]
+        );
+        assert!(changes(&new, &new).is_empty());
+    }
+}
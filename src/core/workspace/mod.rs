@@ -1,4 +1,12 @@
-//! Workspace detection: current directory, project type, AGENT.md loading, and Git context.
+//! Workspace detection: root directory, project type, AGENT.md loading, context files, and
+//! Git context.
+
+pub mod context_files;
+mod probe;
+mod watch;
+
+pub use context_files::ContextFile;
+pub use watch::ContextWatcher;
 
 use std::env;
 use std::path::{Path, PathBuf};
@@ -6,6 +14,8 @@ use std::process::Command;
 
 use thiserror::Error;
 
+use crate::core::remote::{self, RemoteError, RemoteTarget};
+
 /// Type of project detected from marker files in the workspace.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProjectType {
@@ -66,15 +76,21 @@ impl GitContext {
     }
 }
 
-/// Workspace: root directory, detected project type, optional AGENTS.md/AGENT.md content, and optional Git context.
+/// Workspace: root directory, detected project type, optional AGENTS.md/AGENT.md content,
+/// context files, and optional Git context.
 #[derive(Debug, Clone)]
 pub struct Workspace {
-    /// Absolute path of the current working directory (at startup).
+    /// Absolute path of the workspace root (Git worktree or project directory, see `detect`).
     pub root: PathBuf,
+    /// Absolute directory that tools run in. Starts at `root`; changed with `/cd`.
+    pub working_dir: PathBuf,
     /// Detected project type (Rust, Node, Python, Go).
     pub project_type: Option<ProjectType>,
     /// Content of AGENTS.md or AGENT.md if present (AGENTS.md takes precedence).
     pub agent_md: Option<String>,
+    /// Files listed in `.my-open-claude/context.yaml`, sent with every request (see
+    /// `context_files`). Not loaded for remote workspaces.
+    pub context_files: Vec<ContextFile>,
     /// Git context (branch, status) when in a Git repo and MY_OPEN_CLAUDE_GIT_CONTEXT is enabled.
     pub git_context: Option<GitContext>,
 }
@@ -124,6 +140,101 @@ impl GitContextConfig {
     }
 }
 
+impl Workspace {
+    /// Change the directory tools run in. `path` is resolved against the current working
+    /// directory; an empty path returns to the workspace root.
+    pub fn set_working_dir(&mut self, path: &str) -> Result<&Path, WorkingDirError> {
+        let path = path.trim();
+        if let Some(remote) = remote::current() {
+            let target = if path.is_empty() {
+                self.root.clone()
+            } else {
+                remote_dir(remote, &self.working_dir, path)?
+            };
+            self.working_dir = target;
+            return Ok(&self.working_dir);
+        }
+        let target = if path.is_empty() {
+            self.root.clone()
+        } else {
+            self.working_dir.join(path)
+        };
+        let target = target
+            .canonicalize()
+            .map_err(|_| WorkingDirError::NotFound(path.to_string()))?;
+        if !target.is_dir() {
+            return Err(WorkingDirError::NotADirectory(path.to_string()));
+        }
+        self.working_dir = target;
+        Ok(&self.working_dir)
+    }
+
+    /// Re-read the context that can change during a session: project type, AGENTS.md/AGENT.md,
+    /// context files, and Git context. The root and working directory are kept. Returns a
+    /// description of each change to the project type, agent instructions, or context files
+    /// (Git context is not compared).
+    pub fn refresh_context(&mut self) -> Vec<String> {
+        let mut changes = Vec::new();
+        let (project_type, agent_md, context_files, git_context) = match remote::current() {
+            Some(remote) => {
+                match probe::run(
+                    remote,
+                    &remote::shell_quote(&self.root.to_string_lossy()),
+                    false,
+                ) {
+                    Ok(probe) => (
+                        probe.project_type,
+                        probe.agent_md,
+                        Vec::new(),
+                        probe.git_context,
+                    ),
+                    Err(e) => {
+                        log::warn!("Workspace context not refreshed: {}", e);
+                        return changes;
+                    }
+                }
+            }
+            None => (
+                detect_project_type(&self.root),
+                load_agent_md(&self.root),
+                context_files::load(&self.root),
+                gather_git_context(&self.root),
+            ),
+        };
+        if project_type != self.project_type {
+            changes.push(match project_type {
+                Some(pt) => format!("project type: {}", pt),
+                None => "project type: none".to_string(),
+            });
+        }
+        match (&self.agent_md, &agent_md) {
+            (None, Some(_)) => changes.push("AGENTS.md added".to_string()),
+            (Some(_), None) => changes.push("AGENTS.md removed".to_string()),
+            (Some(old), Some(new)) if old != new => changes.push("AGENTS.md updated".to_string()),
+            _ => {}
+        }
+        changes.extend(context_files::changes(&self.context_files, &context_files));
+        self.project_type = project_type;
+        self.agent_md = agent_md;
+        self.context_files = context_files;
+        self.git_context = git_context;
+        changes
+    }
+}
+
+/// Errors from `Workspace::set_working_dir`.
+#[derive(Debug, Error)]
+pub enum WorkingDirError {
+    #[error("no such directory: {0}")]
+    NotFound(String),
+
+    #[error("not a directory: {0}")]
+    NotADirectory(String),
+
+    #[error("{0}")]
+    Remote(String),
+}
+
 /// Errors that can occur when gathering Git context.
 #[derive(Debug, Error)]
 pub enum GitContextError {
@@ -143,28 +254,126 @@ const MARKERS: &[(ProjectType, &str)] = &[
     (ProjectType::Go, "go.mod"),
 ];
 
+/// Agent instruction files, by precedence (matched case-insensitively).
+const AGENT_MD_NAMES: [&str; 2] = ["AGENTS.md", "AGENT.md"];
+
 /// Detect workspace from the current working directory.
 ///
-/// - Resolves root to an absolute path (canonicalize when possible)
+/// - Root is the enclosing Git worktree, else the nearest ancestor (below the home directory)
+///   with a project marker or AGENTS.md/AGENT.md, else the current directory
+/// - Tools start in the root, so a session launched from a subdirectory sees the whole project
 /// - Detects project type from marker files (first match wins)
 /// - Loads AGENTS.md or AGENT.md if present (AGENTS.md takes precedence)
+/// - Reads the context files listed in `.my-open-claude/context.yaml`
 pub fn detect() -> Workspace {
-    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    let home = directories::BaseDirs::new().map(|d| d.home_dir().to_path_buf());
 
-    let root = root.canonicalize().unwrap_or_else(|_| root.clone());
+    let root = find_root(&cwd, home.as_deref());
 
     let project_type = detect_project_type(&root);
     let agent_md = load_agent_md(&root);
+    let context_files = context_files::load(&root);
     let git_context = gather_git_context(&root);
 
     Workspace {
+        working_dir: root.clone(),
         root,
         project_type,
         agent_md,
+        context_files,
         git_context,
     }
 }
 
+/// Detect the workspace on a remote host (`--remote HOST[:PATH]`) and run tools there from
+/// now on. Like `detect`, starting from PATH (the login directory when omitted).
+pub fn detect_remote(spec: &str) -> Result<Workspace, RemoteError> {
+    let target = RemoteTarget::parse(spec)?;
+    let remote = remote::configure(&target.host);
+    let dir = remote::shell_path(target.path.as_deref().unwrap_or("."));
+    let probe = probe::run(remote, &dir, true)?;
+    Ok(Workspace {
+        working_dir: probe.root.clone(),
+        root: probe.root,
+        project_type: probe.project_type,
+        agent_md: probe.agent_md,
+        context_files: Vec::new(),
+        git_context: probe.git_context,
+    })
+}
+
+/// Resolve `path` against `working_dir` on the remote host, like `set_working_dir`.
+fn remote_dir(
+    remote: &remote::Remote,
+    working_dir: &Path,
+    path: &str,
+) -> Result<PathBuf, WorkingDirError> {
+    /// Exit statuses of the script.
+    const MISSING: i32 = 3;
+    const NOT_A_DIRECTORY: i32 = 4;
+    let script = format!(
+        "cd {wd} || exit {MISSING}; p={p}; [ -e \"$p\" ] || exit {MISSING}; \
+         [ -d \"$p\" ] || exit {NOT_A_DIRECTORY}; cd \"$p\" && pwd -P",
+        wd = remote::shell_quote(&working_dir.to_string_lossy()),
+        p = remote::shell_path(path),
+    );
+    let output = remote
+        .output(&script, None)
+        .map_err(|e| WorkingDirError::Remote(e.to_string()))?;
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    match output.status.code() {
+        Some(0) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        Some(NOT_A_DIRECTORY) => Err(WorkingDirError::NotADirectory(path.to_string())),
+        _ => Err(WorkingDirError::NotFound(path.to_string())),
+    }
+}
+
+/// Workspace root for a session started in `cwd` (see `detect`).
+fn find_root(cwd: &Path, home: Option<&Path>) -> PathBuf {
+    if let Some(toplevel) = git_toplevel(cwd) {
+        return toplevel;
+    }
+    cwd.ancestors()
+        .take_while(|dir| home.is_none_or(|home| !home.starts_with(dir)))
+        .find(|dir| is_project_dir(dir))
+        .unwrap_or(cwd)
+        .to_path_buf()
+}
+
+/// Top-level directory of the Git worktree containing `dir`.
+fn git_toplevel(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    path.canonicalize().ok()
+}
+
+/// True when `dir` has a project marker file or an AGENTS.md/AGENT.md.
+fn is_project_dir(dir: &Path) -> bool {
+    MARKERS.iter().any(|(_, marker)| dir.join(marker).exists())
+        || AGENT_MD_NAMES.iter().any(|name| dir.join(name).is_file())
+}
+
+/// Branch checked out in the Git repo at `root` (None outside a repo, or when detached).
+pub fn current_branch(root: &Path) -> Option<String> {
+    Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(root)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| {
+            let s = String::from_utf8_lossy(&o.stdout).trim().to_string();
+            if s.is_empty() { None } else { Some(s) }
+        })
+}
+
 fn gather_git_context(root: &Path) -> Option<GitContext> {
     let config = GitContextConfig::from_env();
     if !config.enabled {
@@ -192,16 +401,7 @@ fn gather_git_context(root: &Path) -> Option<GitContext> {
         }
     }
 
-    let branch = Command::new("git")
-        .args(["branch", "--show-current"])
-        .current_dir(root)
-        .output()
-        .ok()
-        .filter(|o| o.status.success())
-        .and_then(|o| {
-            let s = String::from_utf8_lossy(&o.stdout).trim().to_string();
-            if s.is_empty() { None } else { Some(s) }
-        });
+    let branch = current_branch(root);
 
     let status_out = match Command::new("git")
         .args(["status", "--short"])
@@ -277,9 +477,9 @@ fn load_agent_md(root: &Path) -> Option<String> {
         if path.is_file()
             && let Some(name) = path.file_name().and_then(|n| n.to_str())
         {
-            if name.eq_ignore_ascii_case("AGENTS.md") {
+            if name.eq_ignore_ascii_case(AGENT_MD_NAMES[0]) {
                 agents_content = std::fs::read_to_string(&path).ok();
-            } else if name.eq_ignore_ascii_case("AGENT.md") {
+            } else if name.eq_ignore_ascii_case(AGENT_MD_NAMES[1]) {
                 agent_content = std::fs::read_to_string(&path).ok();
             }
         }
@@ -291,6 +491,97 @@ fn load_agent_md(root: &Path) -> Option<String> {
 mod tests {
     use super::*;
 
+    fn workspace_at(root: &Path) -> Workspace {
+        Workspace {
+            root: root.to_path_buf(),
+            working_dir: root.to_path_buf(),
+            project_type: None,
+            agent_md: None,
+            context_files: Vec::new(),
+            git_context: None,
+        }
+    }
+
+    #[test]
+    fn find_root_uses_nearest_project_dir() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let home = home.path().canonicalize().expect("canonical");
+        let project = home.join("project");
+        let nested = project.join("src/deep");
+        std::fs::create_dir_all(&nested).expect("mkdir");
+        std::fs::write(project.join("Cargo.toml"), "").expect("write");
+
+        assert_eq!(find_root(&nested, Some(&home)), project);
+    }
+
+    #[test]
+    fn find_root_stops_at_home() {
+        let home = tempfile::tempdir().expect("temp dir");
+        let home = home.path().canonicalize().expect("canonical");
+        let nested = home.join("notes/drafts");
+        std::fs::create_dir_all(&nested).expect("mkdir");
+        std::fs::write(home.join("package.json"), "{}").expect("write");
+
+        assert_eq!(find_root(&nested, Some(&home)), nested);
+    }
+
+    #[test]
+    fn set_working_dir_resolves_relative_and_resets() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let root = dir.path().canonicalize().expect("canonical");
+        std::fs::create_dir(root.join("sub")).expect("mkdir");
+        std::fs::write(root.join("file.txt"), "").expect("write");
+        let mut workspace = workspace_at(&root);
+
+        assert_eq!(workspace.set_working_dir("sub").unwrap(), root.join("sub"));
+        assert_eq!(workspace.set_working_dir("..").unwrap(), root);
+        assert!(matches!(
+            workspace.set_working_dir("missing"),
+            Err(WorkingDirError::NotFound(_))
+        ));
+        assert!(matches!(
+            workspace.set_working_dir("file.txt"),
+            Err(WorkingDirError::NotADirectory(_))
+        ));
+        workspace.set_working_dir("sub").unwrap();
+        assert_eq!(workspace.set_working_dir("").unwrap(), root);
+    }
+
+    #[test]
+    fn refresh_context_reports_agents_md_and_project_type_changes() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let root = dir.path().canonicalize().expect("canonical");
+        let mut workspace = workspace_at(&root);
+        assert!(workspace.refresh_context().is_empty());
+
+        std::fs::write(root.join("AGENTS.md"), "Use tabs.").expect("write");
+        std::fs::write(root.join("go.mod"), "module x").expect("write");
+        assert_eq!(
+            workspace.refresh_context(),
+            vec!["project type: Go", "AGENTS.md added"]
+        );
+        assert_eq!(workspace.agent_md.as_deref(), Some("Use tabs."));
+
+        std::fs::write(root.join("AGENTS.md"), "Use spaces.").expect("write");
+        assert_eq!(workspace.refresh_context(), vec!["AGENTS.md updated"]);
+        std::fs::remove_file(root.join("AGENTS.md")).expect("remove");
+        assert_eq!(workspace.refresh_context(), vec!["AGENTS.md removed"]);
+        assert_eq!(workspace.root, root);
+
+        std::fs::create_dir(root.join(".my-open-claude")).expect("mkdir");
+        std::fs::write(root.join("schema.sql"), "CREATE TABLE t;").expect("write");
+        std::fs::write(
+            root.join(".my-open-claude/context.yaml"),
+            "files: [schema.sql]",
+        )
+        .expect("write");
+        assert_eq!(
+            workspace.refresh_context(),
+            vec!["context file added: schema.sql"]
+        );
+        assert_eq!(workspace.context_files[0].content, "CREATE TABLE t;");
+    }
+
     #[test]
     fn truncate_status_adds_truncated_marker_when_over_limit() {
         let long_status = (0..100)
@@ -0,0 +1,158 @@
+//! Workspace context of a remote host (see `core::remote`), read with one SSH call: a
+//! script prints the root, project markers, AGENTS.md/AGENT.md, and Git context as
+//! sections.
+
+use std::path::PathBuf;
+
+use crate::core::remote::{Remote, RemoteError};
+
+use super::{AGENT_MD_NAMES, GitContext, GitContextConfig, MARKERS, ProjectType, truncate_status};
+
+/// Start of the lines opening each section of the script's output.
+const SECTION: &str = "@@my-open-claude:";
+
+/// Workspace context found on the host.
+#[derive(Debug)]
+pub(super) struct Probe {
+    pub root: PathBuf,
+    pub project_type: Option<ProjectType>,
+    pub agent_md: Option<String>,
+    pub git_context: Option<GitContext>,
+}
+
+/// Read the context of `dir` (a shell word, see `remote::shell_path`). With `find_root`,
+/// the root is the enclosing Git worktree when there is one.
+pub(super) fn run(remote: &Remote, dir: &str, find_root: bool) -> Result<Probe, RemoteError> {
+    let git = GitContextConfig::from_env();
+    let output = remote.output(&script(dir, find_root, git.enabled), None)?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match parse(&stdout, &git) {
+        Some(probe) if output.status.success() => Ok(probe),
+        _ => Err(RemoteError::Command {
+            host: remote.host().to_string(),
+            message: match String::from_utf8_lossy(&output.stderr).trim() {
+                "" => format!("cannot open {}", dir),
+                stderr => stderr.to_string(),
+            },
+        }),
+    }
+}
+
+fn script(dir: &str, find_root: bool, git: bool) -> String {
+    let mut script = format!("cd {} || exit 1\n", dir);
+    let markers: Vec<&str> = MARKERS.iter().map(|(_, name)| *name).collect();
+    if find_root {
+        // Like `find_root`: the Git worktree, else the nearest project directory below home.
+        script.push_str(&format!(
+            "if root=$(git rev-parse --show-toplevel 2>/dev/null); then cd \"$root\"; \
+             else d=$(pwd -P); while [ \"$d\" != / ]; do \
+             case \"$HOME/\" in \"$d\"/*) break ;; esac; \
+             for m in {} {}; do [ -e \"$d/$m\" ] && cd \"$d\" && break 2; done; \
+             d=$(dirname \"$d\"); done; fi\n",
+            markers.join(" "),
+            AGENT_MD_NAMES.join(" ")
+        ));
+    }
+    script.push_str(&format!("echo '{SECTION}root'; pwd -P\n"));
+    script.push_str(&format!(
+        "echo '{SECTION}markers'; for m in {}; do [ -e \"$m\" ] && echo \"$m\"; done\n",
+        markers.join(" ")
+    ));
+    // By precedence, matched case-insensitively; `echo` ends a file without a final newline.
+    script.push_str(&format!("echo '{SECTION}agents'; f=\n"));
+    for name in AGENT_MD_NAMES {
+        script.push_str(&format!(
+            "[ -n \"$f\" ] || f=$(ls -1A | grep -ix '{}' | head -n 1)\n",
+            name.replace('.', "\\.")
+        ));
+    }
+    script.push_str("[ -f \"$f\" ] && cat -- \"$f\" && echo\n");
+    if git {
+        script.push_str(&format!(
+            "if git rev-parse --is-inside-work-tree >/dev/null 2>&1; then \
+             echo '{SECTION}branch'; git branch --show-current; \
+             echo '{SECTION}status'; git status --short; fi\n"
+        ));
+    }
+    script
+}
+
+/// Context from the script's output; None without a root section.
+fn parse(output: &str, git: &GitContextConfig) -> Option<Probe> {
+    let mut sections: Vec<(&str, String)> = Vec::new();
+    for line in output.split_inclusive('\n') {
+        match line.trim_end().strip_prefix(SECTION) {
+            Some(name) => sections.push((name, String::new())),
+            None => {
+                if let Some((_, content)) = sections.last_mut() {
+                    content.push_str(line);
+                }
+            }
+        }
+    }
+    let section = |name: &str| {
+        sections
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, content)| content.as_str())
+    };
+    let root = section("root")?.trim();
+    if root.is_empty() {
+        return None;
+    }
+    let markers: Vec<&str> = section("markers").unwrap_or_default().lines().collect();
+    let project_type = MARKERS
+        .iter()
+        .find(|(_, name)| markers.contains(name))
+        .map(|(pt, _)| *pt);
+    let agent_md = section("agents")
+        .filter(|content| !content.is_empty())
+        .map(|content| content.strip_suffix('\n').unwrap_or(content).to_string());
+    let git_context = section("branch").map(|branch| {
+        let status = section("status").unwrap_or_default().trim();
+        GitContext {
+            branch: Some(branch.trim().to_string()).filter(|b| !b.is_empty()),
+            status: truncate_status(status, git.max_lines, git.max_bytes),
+        }
+    });
+    Some(Probe {
+        root: PathBuf::from(root),
+        project_type,
+        agent_md,
+        git_context,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_each_section() {
+        let git = GitContextConfig {
+            enabled: true,
+            max_lines: 50,
+            max_bytes: 2048,
+        };
+        let output = format!(
+            "{s}root\n/srv/app\n{s}markers\npackage.json\nCargo.toml\n{s}agents\nUse tabs.\n\n{s}branch\nmain\n{s}status\n M src/lib.rs\n",
+            s = SECTION
+        );
+        let probe = parse(&output, &git).expect("probe");
+        assert_eq!(probe.root, PathBuf::from("/srv/app"));
+        // Marker precedence, not output order.
+        assert_eq!(probe.project_type, Some(ProjectType::Rust));
+        assert_eq!(probe.agent_md.as_deref(), Some("Use tabs.\n"));
+        let git_context = probe.git_context.expect("git");
+        assert_eq!(git_context.branch.as_deref(), Some("main"));
+        assert_eq!(git_context.status, "M src/lib.rs");
+
+        let output = format!("{s}root\n/home/dev\n{s}markers\n{s}agents\n", s = SECTION);
+        let probe = parse(&output, &git).expect("probe");
+        assert_eq!(probe.project_type, None);
+        assert_eq!(probe.agent_md, None);
+        assert!(probe.git_context.is_none());
+
+        assert!(parse("cd: /nope: No such file or directory\n", &git).is_none());
+    }
+}
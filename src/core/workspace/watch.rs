@@ -0,0 +1,170 @@
+//! Watch the workspace root for changes to AGENTS.md/AGENT.md and project markers, and the
+//! context files list with the files it names, so the workspace context can be refreshed
+//! mid-session.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::core::persistence::WORKSPACE_SETTINGS_DIR;
+
+use super::{AGENT_MD_NAMES, MARKERS, Workspace, context_files};
+
+/// Quiet time after the last change before reporting it: editors often write a file in
+/// several steps (truncate, write, rename), which should trigger a single refresh.
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Watches the files of the workspace root that make up its context.
+pub struct ContextWatcher {
+    /// Kept alive for as long as events are wanted; dropping it stops the watch.
+    watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<notify::Result<Event>>,
+    /// Time of the last relevant change not yet reported.
+    last_change: Option<Instant>,
+    root: PathBuf,
+    /// Directories watched besides the root: those of the context files list and of the
+    /// files it names.
+    dirs: Vec<PathBuf>,
+    /// Context files list and the files it names (absolute paths).
+    files: HashSet<PathBuf>,
+}
+
+impl ContextWatcher {
+    /// Start watching the root of `workspace` (not recursively) and its context files.
+    pub fn new(workspace: &Workspace) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(&workspace.root, RecursiveMode::NonRecursive)?;
+        let mut context_watcher = Self {
+            watcher,
+            rx,
+            last_change: None,
+            root: workspace.root.clone(),
+            dirs: Vec::new(),
+            files: HashSet::new(),
+        };
+        context_watcher.follow(workspace);
+        Ok(context_watcher)
+    }
+
+    /// Watch the context files of `workspace` after they were reloaded: the directories of
+    /// files no longer listed are unwatched, those of new ones are watched.
+    pub fn follow(&mut self, workspace: &Workspace) {
+        let mut files: HashSet<PathBuf> = workspace
+            .context_files
+            .iter()
+            .map(|f| workspace.root.join(&f.path))
+            .collect();
+        files.insert(context_files::config_path(&workspace.root));
+        let mut wanted: Vec<&Path> = files
+            .iter()
+            .filter_map(|f| f.parent())
+            .filter(|dir| *dir != self.root && dir.is_dir())
+            .collect();
+        wanted.sort();
+        wanted.dedup();
+        for dir in self.dirs.iter().filter(|d| !wanted.contains(&d.as_path())) {
+            let _ = self.watcher.unwatch(dir);
+        }
+        let mut dirs = Vec::new();
+        for dir in wanted {
+            if self.dirs.iter().any(|d| d == dir) {
+                dirs.push(dir.to_path_buf());
+                continue;
+            }
+            match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                Ok(()) => dirs.push(dir.to_path_buf()),
+                Err(e) => log::warn!("Not watching {}: {}", dir.display(), e),
+            }
+        }
+        self.dirs = dirs;
+        self.files = files;
+    }
+
+    /// Drain pending events. True once a context file changed and no further change came
+    /// for `SETTLE_DELAY`; the change is then considered reported.
+    pub fn poll(&mut self) -> bool {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                Ok(event) if is_context_change(&event, &self.files) => {
+                    self.last_change = Some(Instant::now());
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Workspace watcher error: {}", e),
+            }
+        }
+        match self.last_change {
+            Some(t) if t.elapsed() >= SETTLE_DELAY => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// True when `event` creates, modifies, or removes a context file: one of `files`, or a
+/// file of the root found by name.
+fn is_context_change(event: &Event, files: &HashSet<PathBuf>) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+        && event.paths.iter().any(|path| {
+            files.contains(path)
+                || path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(is_context_file)
+        })
+}
+
+/// True for AGENTS.md/AGENT.md (any case), project marker files, and the settings directory
+/// (created with a context files list in it).
+fn is_context_file(name: &str) -> bool {
+    AGENT_MD_NAMES
+        .iter()
+        .any(|agent_md| name.eq_ignore_ascii_case(agent_md))
+        || MARKERS.iter().any(|(_, marker)| name == *marker)
+        || name == WORKSPACE_SETTINGS_DIR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+    use std::path::PathBuf;
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn only_context_files_trigger_refresh() {
+        let files = HashSet::from([PathBuf::from("/repo/db/schema.sql")]);
+        assert!(is_context_change(
+            &event(EventKind::Modify(ModifyKind::Any), "/repo/agents.md"),
+            &files
+        ));
+        assert!(is_context_change(
+            &event(EventKind::Create(CreateKind::File), "/repo/Cargo.toml"),
+            &files
+        ));
+        assert!(is_context_change(
+            &event(EventKind::Modify(ModifyKind::Any), "/repo/db/schema.sql"),
+            &files
+        ));
+        assert!(!is_context_change(
+            &event(EventKind::Modify(ModifyKind::Any), "/repo/db/seed.sql"),
+            &files
+        ));
+        assert!(!is_context_change(
+            &event(EventKind::Modify(ModifyKind::Any), "/repo/main.rs"),
+            &files
+        ));
+        assert!(!is_context_change(
+            &event(EventKind::Access(AccessKind::Any), "/repo/AGENTS.md"),
+            &files
+        ));
+    }
+}
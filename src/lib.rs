@@ -0,0 +1,12 @@
+//! # My Open Claude - library
+//!
+//! The agent behind the `my-open-claude` CLI and TUI, for embedding in other Rust tools.
+//! [`Agent`] runs a conversation with the model and the tools on a workspace; `core`
+//! holds the building blocks it is made of: the agent loop (`core::llm::chat`), the tools
+//! (`core::tools`), workspace detection (`core::workspace`), and saved conversations
+//! (`core::history`). The CLI and the TUI live in the binary.
+
+mod agent;
+pub mod core;
+
+pub use agent::{Agent, AgentBuilder, AgentEvent, AgentReply, DEFAULT_MODE, configure};
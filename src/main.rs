@@ -4,18 +4,32 @@
 //! either single-prompt mode or the interactive TUI.
 
 mod cli;
-mod core;
+use my_open_claude::core;
 mod run;
 mod tui;
 
 use std::env;
 
-use clap::{CommandFactory, Parser};
-use cli::{Args, Commands, ConfigSubcommand, HistorySubcommand};
+use clap::Parser;
+use cli::{
+    AgentsSubcommand, Args, AuditSubcommand, Commands, ConfigSubcommand, HistorySubcommand,
+    ToolsSubcommand,
+};
 use dotenv::dotenv;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
+    core::crash::install_panic_hook();
+    let result = run_cli().await;
+    core::trace::shutdown();
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        core::crash::report_fatal(&e.to_string());
+        std::process::exit(1);
+    }
+}
+
+async fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
     // Load stored API key if not in env (before dotenv, so cwd .env can override)
     if env::var("OPENROUTER_API_KEY")
         .map(|v| v.trim().is_empty())
@@ -28,6 +42,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
     let args = Args::parse();
 
+    // Profile first: `config show` and every later config load read it.
+    let profile = args
+        .profile
+        .clone()
+        .or_else(|| env::var("MY_OPEN_CLAUDE_PROFILE").ok())
+        .filter(|name| !name.trim().is_empty());
+    if let Err(e) = core::profiles::activate(profile.as_deref().map(str::trim)) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
     // Early subcommands (no config needed)
     if let Some(cmd) = args.command.as_ref()
         && dispatch_early_command(cmd)?.is_some()
@@ -36,17 +61,112 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     run::init_logger(&args);
-    let config = load_config_or_exit();
+    let mut config = load_config_or_exit();
+    core::crash::set_config(&config);
+    core::index::configure(&config);
+    core::models::configure(&config);
+    core::network::configure(&config);
+    config.dry_run = args.dry_run;
 
-    // Models subcommand (needs config)
+    // Models and index subcommands (need config)
     if let Some(Commands::Models { query }) = &args.command {
         core::cli::run_models(&config, query.as_deref()).await;
         return Ok(());
     }
+    if let Some(Commands::Index { rebuild }) = &args.command {
+        core::cli::run_index(*rebuild);
+        return Ok(());
+    }
+
+    let workspace = match args.remote.as_ref().or(config.remote.as_ref()) {
+        Some(spec) => match core::workspace::detect_remote(spec) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => core::workspace::detect(),
+    };
+    core::permissions::configure(&config, &workspace.root);
+    core::redact::configure(&config);
+    core::command_rules::load();
+    if let Some(path) = core::trace::init() {
+        log::info!("Session trace: {}", path.display());
+    }
+
+    // Per-invocation model override (-m/--model): not persisted as the last model.
+    // Otherwise a model pinned in the workspace settings wins over the last used one.
+    if let Some(model) = &args.model {
+        if let Err(e) = core::models::validate_model_id(model) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        config.model_id = model.clone();
+        config.model_source = core::config::ModelSource::Flag;
+    } else {
+        core::config::apply_workspace_model(&mut config, &workspace.root);
+    }
+    core::config::apply_workspace_tool_timeouts(&mut config, &workspace.root);
+
+    if args.ignore_spend_limit {
+        core::spend::override_caps();
+    }
+
+    if let Some(Commands::Agents { subcommand }) = &args.command {
+        let AgentsSubcommand::Generate = subcommand;
+        run::enforce_spend_limits(&args, &config);
+        run::run_agents_generate(&config, &workspace).await?;
+        return Ok(());
+    }
+
+    if let Some(Commands::Tools { subcommand }) = &args.command {
+        match subcommand {
+            ToolsSubcommand::List { json } => core::cli::run_tools_list(&config, *json),
+            ToolsSubcommand::Run {
+                name,
+                args: tool_args,
+                ask,
+                plan,
+            } => core::cli::run_tool(
+                &config,
+                &workspace.working_dir,
+                name,
+                tool_args,
+                run::mode_name(*ask, *plan),
+            ),
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Watch {
+        prompt,
+        globs,
+        debounce,
+        ask,
+        plan,
+    }) = &args.command
+    {
+        if args.remote.is_some() || config.remote.is_some() {
+            eprintln!("Error: watch mode needs a local workspace (not --remote)");
+            std::process::exit(1);
+        }
+        run::enforce_spend_limits(&args, &config);
+        let watch = run::WatchOptions {
+            prompt,
+            globs,
+            debounce: std::time::Duration::from_millis(*debounce),
+            mode: run::mode_name(*ask, *plan),
+        };
+        return run::run_watch(&watch, &args, &config, &workspace).await;
+    }
 
-    let workspace = core::workspace::detect();
+    if args.batch.is_some() {
+        return run::run_batch(&args, &config, &workspace).await;
+    }
 
     if args.prompt.is_some() {
+        run::enforce_spend_limits(&args, &config);
         run::run_single_prompt(&args, &config, &workspace).await?;
         return Ok(());
     }
@@ -54,18 +174,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     run::launch_tui(config, workspace).await
 }
 
-/// Dispatch install, update, config, completions, history. Returns Some(()) if handled.
+/// Dispatch install, update, config, completions, history, replay. Returns Some(()) if handled.
 fn dispatch_early_command(cmd: &Commands) -> Result<Option<()>, Box<dyn std::error::Error>> {
     match cmd {
         Commands::Install => {
             core::install::run_install()?;
             Ok(Some(()))
         }
-        Commands::Update { check } => {
-            if *check {
-                core::update::run_update_check()?;
+        Commands::Update {
+            check,
+            version,
+            rollback,
+        } => {
+            if *rollback {
+                core::update::run_rollback()?;
+            } else if *check {
+                core::update::run_update_check(version.as_deref())?;
             } else {
-                core::update::run_update()?;
+                core::update::run_update(version.as_deref())?;
             }
             Ok(Some(()))
         }
@@ -80,17 +206,50 @@ fn dispatch_early_command(cmd: &Commands) -> Result<Option<()>, Box<dyn std::err
             }
             Ok(Some(()))
         }
+        Commands::Stats { days } => {
+            core::cli::run_stats(*days as usize);
+            Ok(Some(()))
+        }
+        Commands::Audit { subcommand } => {
+            match subcommand {
+                AuditSubcommand::Show { since, json } => {
+                    core::cli::run_audit_show(since.as_deref(), *json)
+                }
+            }
+            Ok(Some(()))
+        }
+        Commands::Report { no_open } => {
+            core::cli::run_report(*no_open);
+            Ok(Some(()))
+        }
         Commands::Completions { shell } => {
-            let mut app = Args::command();
-            cli::generate(*shell, &mut app, core::app::NAME, &mut std::io::stdout());
+            cli::write_completions(*shell, &mut std::io::stdout())?;
             Ok(Some(()))
         }
         Commands::History { subcommand } => {
-            let HistorySubcommand::List { limit } = subcommand;
-            core::cli::run_history_list(*limit);
+            match subcommand {
+                HistorySubcommand::List { limit } => core::cli::run_history_list(*limit),
+                HistorySubcommand::Reactions { only } => core::cli::run_history_reactions(*only),
+                HistorySubcommand::Prune { dry_run } => core::cli::run_history_prune(*dry_run),
+                HistorySubcommand::Archive { id } => core::cli::run_history_archive(id, true),
+                HistorySubcommand::Unarchive { id } => core::cli::run_history_archive(id, false),
+            }
+            Ok(Some(()))
+        }
+        Commands::Replay { id, step, speed } => {
+            run::run_replay(id, *step, *speed)?;
+            Ok(Some(()))
+        }
+        #[cfg(feature = "testing")]
+        Commands::MockServer { script, port } => {
+            core::cli::run_mock_server(script, *port)?;
             Ok(Some(()))
         }
-        Commands::Models { .. } => Ok(None),
+        Commands::Models { .. }
+        | Commands::Index { .. }
+        | Commands::Agents { .. }
+        | Commands::Tools { .. }
+        | Commands::Watch { .. } => Ok(None),
     }
 }
 
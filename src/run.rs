@@ -1,31 +1,285 @@
 //! Application run modes: logger init, single prompt, TUI launch.
 
-use std::io::{self, Write};
-use std::sync::Arc;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+
+use crossterm::style::Stylize;
 
 use crate::cli::Args;
 use crate::core;
 use crate::core::config::Config;
 use crate::core::workspace::Workspace;
 
+/// Instructions file written by `agents generate` (and `/init`).
+const AGENTS_FILE: &str = "AGENTS.md";
+
+/// Longest title of a batch task saved without one, and of a watch conversation (the
+/// TUI's title length).
+const BATCH_TITLE_MAX_LEN: usize = 60;
+
 /// Initialize env_logger. In TUI mode, writes to file to avoid corrupting the display.
 pub fn init_logger(args: &Args) {
     let log_level = args.log_level();
     let mut logger =
         env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level));
 
-    if args.prompt.is_none() {
-        let log_path = core::paths::cache_dir().map(|d| d.join(format!("{}.log", core::app::NAME)));
-        if let Some(path) = log_path
-            && let Ok(file) = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&path)
+    if args.prompt.is_none()
+        && args.batch.is_none()
+        && let Some(path) = core::paths::log_file()
+        && let Ok(file) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+    {
+        logger.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+    let _ = logger.try_init();
+}
+
+/// Line-buffered stdout printer for `--stream`: tool logs and response text share stdout
+/// in arrival order, and a log line never lands in the middle of a response line.
+#[derive(Default)]
+struct LinePrinter {
+    /// Response text received since the last newline.
+    partial: Mutex<String>,
+}
+
+impl LinePrinter {
+    /// Buffer a content chunk and print every completed line.
+    fn content(&self, chunk: &str) {
+        let mut partial = self.partial.lock().unwrap_or_else(|e| e.into_inner());
+        partial.push_str(chunk);
+        if let Some(pos) = partial.rfind('\n') {
+            let complete: String = partial.drain(..=pos).collect();
+            let mut out = io::stdout().lock();
+            let _ = out.write_all(complete.as_bytes());
+            let _ = out.flush();
+        }
+    }
+
+    /// Print a full line (e.g. "→ Bash: ls"), terminating any pending response text first.
+    fn line(&self, line: &str) {
+        let mut partial = self.partial.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = io::stdout().lock();
+        if !partial.is_empty() {
+            let _ = writeln!(out, "{}", partial);
+            partial.clear();
+        }
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+
+    /// Chat options printing tool logs and response text through this printer.
+    fn chat_options(self: &Arc<Self>) -> core::llm::ChatOptions {
+        let progress_printer = Arc::clone(self);
+        let content_printer = Arc::clone(self);
+        core::llm::ChatOptions {
+            on_progress: Some(Box::new(move |e| {
+                // Lines are permanent here: a call streaming in shows when it runs.
+                if !matches!(e, core::llm::ProgressEvent::PreparingTool { .. }) {
+                    progress_printer.line(&e.to_string());
+                }
+            })),
+            on_content_chunk: Some(Box::new(move |s| content_printer.content(s))),
+            ..Default::default()
+        }
+    }
+
+    /// Print any remaining response text.
+    fn finish(&self) {
+        let mut partial = self.partial.lock().unwrap_or_else(|e| e.into_inner());
+        if !partial.is_empty() {
+            let mut out = io::stdout().lock();
+            let _ = writeln!(out, "{}", partial);
+            let _ = out.flush();
+            partial.clear();
+        }
+    }
+}
+
+/// Default prompt-mode progress on stderr. On a terminal, tool lines are colored and cut to
+/// the terminal width, and "Calling API..." and tool calls streaming in are a status
+/// rewritten in place instead of piling up. Otherwise (or with `NO_COLOR`), events are
+/// printed as plain lines, without the streaming ones.
+struct StderrProgress {
+    styled: bool,
+    /// stdout is the same terminal: progress must not land in the middle of response text.
+    shared_screen: bool,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    /// A transient status is shown on the current stderr line.
+    status_shown: bool,
+    /// The last response chunk did not end with a newline.
+    content_mid_line: bool,
+}
+
+impl StderrProgress {
+    fn new() -> Self {
+        let styled = io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        Self {
+            styled,
+            shared_screen: styled && io::stdout().is_terminal(),
+            state: Mutex::new(ProgressState::default()),
+        }
+    }
+
+    fn clear_status(&self, state: &mut ProgressState, err: &mut impl Write) {
+        if state.status_shown {
+            let _ = write!(err, "\r\x1b[2K");
+            state.status_shown = false;
+        }
+    }
+
+    /// Print a progress event (tool call, API status, retry notice).
+    fn line(&self, event: &core::llm::ProgressEvent) {
+        let preparing = matches!(event, core::llm::ProgressEvent::PreparingTool { .. });
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut err = io::stderr().lock();
+        if !self.styled {
+            // A call streaming in is reported again and again: plain output shows it run.
+            if !preparing {
+                let _ = writeln!(err, "{}", event);
+                let _ = err.flush();
+            }
+            return;
+        }
+        self.clear_status(&mut state, &mut err);
+        let width = crossterm::terminal::size()
+            .map(|(w, _)| w as usize)
+            .unwrap_or(80);
+        if *event == core::llm::ProgressEvent::CallingModel || preparing {
+            // Transient: replaced by the next line or response text.
+            if !state.content_mid_line {
+                let status = core::text::truncate_end(&event.to_string(), width.saturating_sub(1));
+                let _ = write!(err, "{}", status.dark_grey());
+                state.status_shown = true;
+            }
+            let _ = err.flush();
+            return;
+        }
+        if self.shared_screen && state.content_mid_line {
+            let _ = writeln!(err);
+            state.content_mid_line = false;
+        }
+        match event {
+            core::llm::ProgressEvent::Tool {
+                name: tool,
+                preview: args,
+                remote,
+                dry_run,
+                ..
+            } => {
+                let tag = match (*remote, *dry_run) {
+                    (true, true) => " (remote) (dry run)",
+                    (true, false) => " (remote)",
+                    (false, true) => " (dry run)",
+                    (false, false) => "",
+                };
+                let args_width = width.saturating_sub(tool.chars().count() + tag.len() + 4);
+                let _ = writeln!(
+                    err,
+                    "{} {}{}{} {}",
+                    "→".dark_grey(),
+                    tool.as_str().cyan().bold(),
+                    tag.dark_grey(),
+                    ":".dark_grey(),
+                    core::text::truncate_end(args, args_width)
+                );
+            }
+            _ => {
+                let msg = event.to_string();
+                let _ = writeln!(err, "{}", core::text::truncate_end(&msg, width).yellow());
+            }
+        }
+        let _ = err.flush();
+    }
+
+    /// Write a response chunk to stdout, clearing any status line first.
+    fn content(&self, chunk: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if self.shared_screen {
+            self.clear_status(&mut state, &mut io::stderr().lock());
+        }
+        if let Some(last) = chunk.chars().last() {
+            state.content_mid_line = last != '\n';
+        }
+        let mut out = io::stdout().lock();
+        let _ = out.write_all(chunk.as_bytes());
+        let _ = out.flush();
+    }
+
+    /// Clear the status line and, on a terminal, print a one-line summary of the run.
+    fn finish(&self, summary: Option<String>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut err = io::stderr().lock();
+        self.clear_status(&mut state, &mut err);
+        if !self.styled {
+            return;
+        }
+        if self.shared_screen && state.content_mid_line {
+            let _ = writeln!(err);
+            state.content_mid_line = false;
+        }
+        if let Some(summary) = summary {
+            let _ = writeln!(err, "{}", summary.dark_grey());
+        }
+        let _ = err.flush();
+    }
+}
+
+/// "Done in 12.4s · 5 tool calls · 3210 tokens" for the end of a prompt-mode run.
+fn run_summary(elapsed: std::time::Duration, tool_calls: usize, total_tokens: u64) -> String {
+    let mut summary = format!("Done in {:.1}s", elapsed.as_secs_f64());
+    if tool_calls > 0 {
+        summary.push_str(&format!(
+            " · {} tool call{}",
+            tool_calls,
+            if tool_calls == 1 { "" } else { "s" }
+        ));
+    }
+    if total_tokens > 0 {
+        summary.push_str(&format!(" · {} tokens", total_tokens));
+    }
+    summary
+}
+
+/// Error of a failed chat, followed by its hint when there is one.
+fn with_hint(e: core::llm::ChatError) -> Box<dyn std::error::Error> {
+    match e.hint() {
+        Some(hint) => format!("{}\nHint: {}", e, hint).into(),
+        None => e.into(),
+    }
+}
+
+/// Interaction mode of the `--ask` and `--plan` flags (Build without either).
+pub fn mode_name(ask: bool, plan: bool) -> &'static str {
+    if ask {
+        "Ask"
+    } else if plan {
+        "Plan"
+    } else {
+        "Build"
+    }
+}
+
+/// Exit before a prompt-mode request when a spend cap is reached, unless
+/// `--ignore-spend-limit` is passed. Warns on stderr from 80% of a cap.
+pub fn enforce_spend_limits(args: &Args, config: &Config) {
+    match core::spend::check(&config.spend_limits) {
+        core::spend::SpendStatus::Exceeded(hit) if !args.ignore_spend_limit => {
+            eprintln!("Error: {}. Pass --ignore-spend-limit to send anyway.", hit);
+            std::process::exit(1);
+        }
+        core::spend::SpendStatus::Exceeded(hit) | core::spend::SpendStatus::Warning(hit)
+            if !args.quiet =>
         {
-            logger.target(env_logger::Target::Pipe(Box::new(file)));
+            eprintln!("Warning: {}.", hit);
         }
+        _ => {}
     }
-    let _ = logger.try_init();
 }
 
 /// Run single prompt mode: chat with model, print response to stdout.
@@ -49,30 +303,52 @@ pub async fn run_single_prompt(
         std::process::exit(1);
     }
 
-    let model = args.model.as_deref().unwrap_or(&config.model_id);
-    let mode = if args.ask { "Ask" } else { "Build" };
+    let model = config.model_id.as_str();
+    let mode = mode_name(args.ask, args.plan);
     let context_length = core::models::resolve_context_length(model);
+    let schema = match args.json_schema.as_deref().map(load_json_schema) {
+        Some(Ok(schema)) => Some(schema),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
 
+    let printer = Arc::new(LinePrinter::default());
+    let progress = Arc::new(StderrProgress::new());
     let options = if args.no_stream {
         core::llm::ChatOptions::default()
+    } else if args.stream {
+        printer.chat_options()
     } else {
+        let progress_out = Arc::clone(&progress);
+        let content_out = Arc::clone(&progress);
         core::llm::ChatOptions {
-            on_progress: Some(Box::new(|s| {
-                let _ = writeln!(io::stderr(), "{}", s);
-                let _ = io::stderr().flush();
-            })),
-            on_content_chunk: Some(Box::new(|s| {
-                let _ = io::stdout().write_all(s.as_bytes());
-                let _ = io::stdout().flush();
-            })),
+            // -q: response text only.
+            on_progress: if args.quiet {
+                None
+            } else {
+                Some(Box::new(move |s| progress_out.line(s)))
+            },
+            // Structured output is printed once validated.
+            on_content_chunk: if schema.is_some() {
+                None
+            } else {
+                Some(Box::new(move |s| content_out.content(s)))
+            },
             ..Default::default()
         }
     };
 
+    let mut generation = core::generation::effective(&config.generation, model, None);
+    generation.response_schema = schema;
+    let started = std::time::Instant::now();
     let result = core::llm::chat(core::llm::ChatRequest {
         config,
         model,
         prompt,
+        images: &[],
         mode,
         context_length,
         confirm_destructive: Some(core::confirm::default_confirm()),
@@ -82,18 +358,503 @@ pub async fn run_single_prompt(
         tools_list: core::tools::all(),
         tools_defs: core::tools::definitions(),
         undo_stack: None,
+        pinned_files: &[],
+        pinned_messages: &[],
+        generation: &generation,
     })
-    .await?;
+    .await;
+    printer.finish();
+    let summary = match &result {
+        Ok(core::llm::ChatResult::Complete {
+            tool_log, usage, ..
+        }) if !args.quiet && !args.stream && !args.no_stream => Some(run_summary(
+            started.elapsed(),
+            tool_log.len(),
+            usage.total_tokens,
+        )),
+        _ => None,
+    };
+    progress.finish(summary);
+    let result = result.map_err(with_hint)?;
 
-    if let core::llm::ChatResult::Complete { content, .. } = result {
+    if let core::llm::ChatResult::Complete {
+        content,
+        truncated,
+        annotations,
+        structured,
+        ..
+    } = result
+    {
+        if args.json_schema.is_some() {
+            for note in annotations {
+                eprintln!("{}", note);
+            }
+            match structured {
+                Some(value) => println!("{}", value),
+                None => {
+                    eprintln!("Error: the response does not match the JSON schema");
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
         // In streaming mode, content was already printed via on_content_chunk
         if args.no_stream {
             println!("{}", content);
         }
+        // Streamed text shares the terminal line with stderr.
+        if (truncated || !annotations.is_empty())
+            && !args.no_stream
+            && !content.ends_with('\n')
+            && io::stdout().is_terminal()
+        {
+            eprintln!();
+        }
+        for note in annotations {
+            eprintln!("{}", note);
+        }
+        if truncated {
+            eprintln!(
+                "Warning: response truncated by the output token limit \
+                 (raise MY_OPEN_CLAUDE_MAX_TOKENS or set MY_OPEN_CLAUDE_AUTO_CONTINUE)."
+            );
+        }
     }
     Ok(())
 }
 
+/// Read the JSON schema of `--json-schema`: a JSON object.
+fn load_json_schema(path: &str) -> Result<serde_json::Value, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let schema: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| format!("invalid JSON in {}: {}", path, e))?;
+    if !schema.is_object() {
+        return Err(format!("{} is not a JSON schema object", path));
+    }
+    Ok(schema)
+}
+
+/// Run `--batch FILE`: each task like a `--stream` prompt, saved as its own conversation,
+/// then the report on stdout (and as JSON with `--batch-report`). Once a spend cap is
+/// reached, the remaining tasks are skipped. Exits with status 1 unless every task is done.
+pub async fn run_batch(
+    args: &Args,
+    config: &Config,
+    workspace: &Workspace,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args
+        .batch
+        .as_deref()
+        .ok_or_else(|| io::Error::other("a task file is required for batch mode"))?;
+    let tasks = match core::batch::load(std::path::Path::new(path)) {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let started = std::time::Instant::now();
+    let mut reports = Vec::with_capacity(tasks.len());
+    let mut skip_reason: Option<String> = None;
+    for (i, task) in tasks.iter().enumerate() {
+        let model = task.model.as_deref().unwrap_or(&config.model_id);
+        let title = task.title.clone().unwrap_or_else(|| {
+            let prompt = serde_json::json!({"role": "user", "content": task.prompt});
+            core::history::first_message_preview(&[prompt], BATCH_TITLE_MAX_LEN)
+        });
+        if skip_reason.is_none()
+            && !args.ignore_spend_limit
+            && let core::spend::SpendStatus::Exceeded(hit) =
+                core::spend::check(&config.spend_limits)
+        {
+            skip_reason = Some(format!("{} (pass --ignore-spend-limit to run anyway)", hit));
+        }
+        if let Some(ref reason) = skip_reason {
+            reports.push(core::batch::TaskReport {
+                title,
+                mode: task.mode.clone(),
+                model: model.to_string(),
+                status: core::batch::TaskStatus::Skipped,
+                conversation_id: None,
+                cost: 0.0,
+                tokens: 0,
+                tool_calls: 0,
+                duration_secs: 0.0,
+                changed_files: Vec::new(),
+                error: Some(reason.clone()),
+            });
+            continue;
+        }
+        if !args.quiet {
+            eprintln!("[{}/{}] {}", i + 1, tasks.len(), title);
+        }
+        reports.push(run_batch_task(task, title, model, args, config, workspace).await);
+    }
+
+    let report = core::batch::BatchReport::new(reports, started.elapsed().as_secs_f64());
+    if let Some(ref report_path) = args.batch_report {
+        std::fs::write(report_path, serde_json::to_string_pretty(&report)?)?;
+    }
+    if !args.quiet {
+        println!();
+    }
+    print!("{}", report);
+    if !report.succeeded() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Run one batch task and save its conversation: the prompt, tool logs, and answer.
+async fn run_batch_task(
+    task: &core::batch::Task,
+    title: String,
+    model: &str,
+    args: &Args,
+    config: &Config,
+    workspace: &Workspace,
+) -> core::batch::TaskReport {
+    let printer = Arc::new(LinePrinter::default());
+    let generation = core::generation::effective(&config.generation, model, None);
+    let undo_stack = core::llm::undo::new_shared();
+    let started = std::time::Instant::now();
+    let result = core::llm::chat(core::llm::ChatRequest {
+        config,
+        model,
+        prompt: &task.prompt,
+        images: &[],
+        mode: &task.mode,
+        context_length: core::models::resolve_context_length(model),
+        confirm_destructive: Some(core::confirm::default_confirm()),
+        previous_messages: None,
+        options: if args.quiet {
+            core::llm::ChatOptions::default()
+        } else {
+            printer.chat_options()
+        },
+        workspace,
+        tools_list: core::tools::all(),
+        tools_defs: core::tools::definitions(),
+        // Records the files written or edited, for the report.
+        undo_stack: Some(undo_stack.clone()),
+        pinned_files: &[],
+        pinned_messages: &[],
+        generation: &generation,
+    })
+    .await;
+    printer.finish();
+
+    let changed_files = undo_stack
+        .lock()
+        .map(|stack| stack.changed_paths())
+        .unwrap_or_default()
+        .iter()
+        .map(|p| {
+            p.strip_prefix(&workspace.root)
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        })
+        .collect();
+    let mut report = core::batch::TaskReport {
+        title,
+        mode: task.mode.clone(),
+        model: model.to_string(),
+        status: core::batch::TaskStatus::Failed,
+        conversation_id: None,
+        cost: 0.0,
+        tokens: 0,
+        tool_calls: 0,
+        duration_secs: started.elapsed().as_secs_f64(),
+        changed_files,
+        error: None,
+    };
+    match result {
+        Ok(core::llm::ChatResult::Complete {
+            content,
+            tool_log,
+            message_usage,
+            annotations,
+            save_blocked,
+            ..
+        }) => {
+            for note in &annotations {
+                eprintln!("{}", note);
+            }
+            report.status = core::batch::TaskStatus::Done;
+            report.cost = message_usage.cost;
+            report.tokens = message_usage.prompt_tokens + message_usage.completion_tokens;
+            report.tool_calls = tool_log.len();
+            let mut messages = vec![serde_json::json!({"role": "user", "content": task.prompt})];
+            messages.extend(
+                tool_log
+                    .iter()
+                    .map(|line| serde_json::json!({"role": "tool_log", "content": line})),
+            );
+            messages.push(serde_json::json!({"role": "assistant", "content": content}));
+            messages.extend(
+                annotations
+                    .iter()
+                    .map(|note| serde_json::json!({"role": "tool_log", "content": note})),
+            );
+            if let Some(reason) = save_blocked {
+                report.error = Some(format!("conversation not saved: {}", reason));
+                return report;
+            }
+            let settings = core::history::ConversationSettings {
+                model_id: Some(model.to_string()),
+                mode: Some(task.mode.clone()),
+                origin: Some(core::history::ConversationOrigin::capture(workspace)),
+                ..Default::default()
+            };
+            match core::history::save_conversation(
+                None,
+                &report.title,
+                &messages,
+                &settings,
+                config,
+            ) {
+                Ok(id) => report.conversation_id = Some(id),
+                Err(e) => report.error = Some(format!("conversation not saved: {}", e)),
+            }
+        }
+        Ok(core::llm::ChatResult::NeedsConfirmation { action, .. }) => {
+            let (label, value) = action.detail();
+            report.error = Some(format!("needs confirmation: {} {}", label, value));
+        }
+        Err(e) => report.error = Some(with_hint(e).to_string()),
+    }
+    report
+}
+
+/// What `watch` runs, and on which changes.
+pub struct WatchOptions<'a> {
+    pub prompt: &'a str,
+    pub globs: &'a [String],
+    pub debounce: std::time::Duration,
+    /// Ask, Build, or Plan.
+    pub mode: &'a str,
+}
+
+/// How often `watch` checks for file changes.
+const WATCH_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Run `watch`: the prompt once, then again each time files matching the globs change.
+/// Runs print like `--stream` prompts and continue one conversation, saved to the history
+/// after each run. Files the agent edits do not trigger a run. Stops with Ctrl+C, or once
+/// a spend cap is reached.
+pub async fn run_watch(
+    watch: &WatchOptions<'_>,
+    args: &Args,
+    config: &Config,
+    workspace: &Workspace,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut watcher =
+        match core::watch::FileWatcher::new(&workspace.root, watch.globs, watch.debounce) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+    let model = config.model_id.as_str();
+    let context_length = core::models::resolve_context_length(model);
+    let generation = core::generation::effective(&config.generation, model, None);
+    let mut api_messages: Option<Vec<serde_json::Value>> = None;
+    // Persisted form of the conversation (prompts, tool logs, replies).
+    let mut transcript: Vec<serde_json::Value> = Vec::new();
+    let mut conversation_id: Option<String> = None;
+    let mut changed: Vec<String> = Vec::new();
+    for run in 1.. {
+        if run > 1 {
+            changed = loop {
+                tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+                if let Some(changed) = watcher.poll() {
+                    break changed;
+                }
+            };
+            if let Some(hit) = core::spend::blocking_limit(&config.spend_limits) {
+                eprintln!("{}: watch stopped.", hit);
+                return Ok(());
+            }
+        }
+        let trigger = match changed.len() {
+            0 => "start".to_string(),
+            1 => changed[0].clone(),
+            n => format!("{} files changed", n),
+        };
+        println!("── Run {} ({}) ──", run, trigger);
+        let prompt = core::watch::run_prompt(watch.prompt, &changed);
+        let printer = Arc::new(LinePrinter::default());
+        let undo_stack = core::llm::undo::new_shared();
+        let result = core::llm::chat(core::llm::ChatRequest {
+            config,
+            model,
+            prompt: &prompt,
+            images: &[],
+            mode: watch.mode,
+            context_length,
+            confirm_destructive: Some(core::confirm::default_confirm()),
+            previous_messages: api_messages.clone(),
+            options: core::llm::ChatOptions {
+                conversation_id: conversation_id.clone(),
+                ..if args.quiet {
+                    core::llm::ChatOptions::default()
+                } else {
+                    printer.chat_options()
+                }
+            },
+            workspace,
+            tools_list: core::tools::all(),
+            tools_defs: core::tools::definitions(),
+            // Records the files the run writes, which must not trigger the next one.
+            undo_stack: Some(undo_stack.clone()),
+            pinned_files: &[],
+            pinned_messages: &[],
+            generation: &generation,
+        })
+        .await;
+        printer.finish();
+        let written = undo_stack
+            .lock()
+            .map(|stack| stack.changed_paths())
+            .unwrap_or_default();
+        watcher.forget(&written);
+        match result {
+            Ok(core::llm::ChatResult::Complete {
+                content,
+                tool_log,
+                messages,
+                annotations,
+                save_blocked,
+                ..
+            }) => {
+                if args.quiet {
+                    println!("{}", content);
+                }
+                for note in &annotations {
+                    eprintln!("{}", note);
+                }
+                api_messages = Some(messages);
+                transcript.push(serde_json::json!({"role": "user", "content": prompt}));
+                transcript.extend(
+                    tool_log
+                        .iter()
+                        .map(|line| serde_json::json!({"role": "tool_log", "content": line})),
+                );
+                transcript.push(serde_json::json!({"role": "assistant", "content": content}));
+                if let Some(reason) = save_blocked {
+                    eprintln!("Conversation not saved: {}", reason);
+                    continue;
+                }
+                let settings = core::history::ConversationSettings {
+                    model_id: Some(model.to_string()),
+                    mode: Some(watch.mode.to_string()),
+                    origin: Some(core::history::ConversationOrigin::capture(workspace)),
+                    ..Default::default()
+                };
+                let title = format!("watch: {}", watch.prompt);
+                let title = core::text::truncate_end(&title, BATCH_TITLE_MAX_LEN);
+                match core::history::save_conversation(
+                    conversation_id.as_deref(),
+                    &title,
+                    &transcript,
+                    &settings,
+                    config,
+                ) {
+                    Ok(id) => conversation_id = Some(id),
+                    Err(e) => eprintln!("Conversation not saved: {}", e),
+                }
+            }
+            Ok(core::llm::ChatResult::NeedsConfirmation { action, .. }) => {
+                let (label, value) = action.detail();
+                eprintln!("Run stopped: needs confirmation: {} {}", label, value);
+            }
+            // A failed run does not stop the watch: the next change runs the prompt again.
+            Err(e) => eprintln!("Error: {}", with_hint(e)),
+        }
+    }
+    Ok(())
+}
+
+/// Run `agents generate`: the `/init` flow without the TUI. Tool logs and the response are
+/// printed line by line to stdout. Fails when AGENTS.md does not exist afterwards.
+pub async fn run_agents_generate(
+    config: &Config,
+    workspace: &Workspace,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let init = core::commands::builtin_command("init")
+        .ok_or_else(|| io::Error::other("built-in /init command is missing"))?;
+    let prompt = core::templates::expand_cwd(&init.prompt_prefix, &workspace.working_dir);
+    // Tools resolve relative paths against the working directory, like /init in the TUI.
+    let agents_path = workspace.working_dir.join(AGENTS_FILE);
+    let before = std::fs::read_to_string(&agents_path).ok();
+
+    let model = config.model_id.as_str();
+    let printer = Arc::new(LinePrinter::default());
+    let generation = core::generation::effective(&config.generation, model, None);
+    let result = core::llm::chat(core::llm::ChatRequest {
+        config,
+        model,
+        prompt: &prompt,
+        images: &[],
+        mode: &init.mode,
+        context_length: core::models::resolve_context_length(model),
+        confirm_destructive: Some(core::confirm::default_confirm()),
+        previous_messages: None,
+        options: printer.chat_options(),
+        workspace,
+        tools_list: core::tools::all(),
+        tools_defs: core::tools::definitions(),
+        undo_stack: None,
+        pinned_files: &[],
+        pinned_messages: &[],
+        generation: &generation,
+    })
+    .await;
+    printer.finish();
+    result.map_err(with_hint)?;
+
+    match (before, std::fs::read_to_string(&agents_path).ok()) {
+        (_, None) => {
+            return Err(io::Error::other(format!("{} was not written", AGENTS_FILE)).into());
+        }
+        (None, Some(_)) => println!("Created {}", AGENTS_FILE),
+        (Some(before), Some(after)) if before == after => {
+            println!("{} is unchanged", AGENTS_FILE)
+        }
+        (Some(_), Some(_)) => println!("Updated {}", AGENTS_FILE),
+    }
+    Ok(())
+}
+
+/// Run `replay <id>`: play a saved conversation back in the TUI. Needs no API key; the
+/// header shows the model of the first recorded reply, else the conversation's model, else
+/// the model the TUI would use.
+pub fn run_replay(id: &str, step: bool, speed: f64) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(persisted) = core::history::load_conversation(id) else {
+        eprintln!(
+            "Error: conversation '{}' not found (see `{} history list`)",
+            id,
+            core::app::NAME
+        );
+        std::process::exit(1);
+    };
+    let provider = core::config::Provider::from_env()?;
+    let fallback_model = core::history::load_conversation_settings(id)
+        .model_id
+        .unwrap_or_else(|| core::config::resolve_model(provider).0);
+    let workspace = core::workspace::detect();
+    crate::tui::replay::run(
+        persisted,
+        id.to_string(),
+        fallback_model,
+        workspace,
+        crate::tui::replay::ReplayOptions { step, speed },
+    )?;
+    Ok(())
+}
+
 /// Launch the TUI in a blocking thread. Returns on panic or IO error.
 pub async fn launch_tui(
     config: Config,
@@ -0,0 +1,105 @@
+//! Code block display (Alt+W): soft-wrapped to the history width, or one row per line,
+//! truncated, with Left/Right scrolling the focused block. The choice is per conversation
+//! and saved with its view state.
+
+use super::App;
+
+/// A fenced code block of the history: message index, and index of the block among the
+/// message's code blocks.
+pub type CodeBlockId = (usize, usize);
+
+/// Rows of a code block drawn in the history, recorded by the draw pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeBlockRegion {
+    pub id: CodeBlockId,
+    /// First and past-the-last history line of the block (header and footer included).
+    pub start: usize,
+    pub end: usize,
+    /// Columns of the longest line beyond the block width; 0 when every line fits.
+    pub max_scroll: usize,
+}
+
+/// First and past-the-last column of a `len`-column line shown `width` columns wide from
+/// column `offset`.
+pub(crate) fn visible_columns(len: usize, offset: usize, width: usize) -> (usize, usize) {
+    let start = offset.min(len);
+    (start, (start + width).min(len))
+}
+
+impl App {
+    /// Switch code blocks between soft-wrapped and truncated, dropping the focus.
+    pub(crate) fn toggle_code_wrap(&mut self) {
+        self.set_truncate_code(!self.truncate_code);
+    }
+
+    pub(crate) fn set_truncate_code(&mut self, truncate: bool) {
+        self.truncate_code = truncate;
+        self.focused_code = None;
+        self.code_scroll = 0;
+    }
+
+    /// Focus the code block drawn at history line `line` (click), when code is truncated.
+    pub(crate) fn focus_code_at_line(&mut self, line: usize) {
+        if !self.truncate_code {
+            return;
+        }
+        let id = self
+            .code_blocks
+            .iter()
+            .find(|r| r.start <= line && line < r.end)
+            .map(|r| r.id);
+        if id != self.focused_code {
+            self.focused_code = id;
+            self.code_scroll = 0;
+        }
+    }
+
+    /// Scroll the focused code block `delta` columns (Left/Right on an empty input). Without
+    /// a focused block on screen, the first visible one that overflows is focused. Returns
+    /// false when code is soft-wrapped or no block can scroll, so the key does its usual job.
+    pub(crate) fn scroll_code(&mut self, delta: isize) -> bool {
+        if !self.truncate_code {
+            return false;
+        }
+        let top = self.scroll_line();
+        let bottom = top + self.history_area_rect.map_or(0, |r| r.height as usize);
+        let on_screen = |r: &&CodeBlockRegion| r.start < bottom && r.end > top;
+        let focused = self
+            .code_blocks
+            .iter()
+            .filter(on_screen)
+            .find(|r| Some(r.id) == self.focused_code)
+            .or_else(|| {
+                self.code_blocks
+                    .iter()
+                    .filter(on_screen)
+                    .find(|r| r.max_scroll > 0)
+            })
+            .copied();
+        let Some(region) = focused else {
+            return false;
+        };
+        if self.focused_code != Some(region.id) {
+            self.focused_code = Some(region.id);
+            self.code_scroll = 0;
+        }
+        self.code_scroll = self
+            .code_scroll
+            .saturating_add_signed(delta)
+            .min(region.max_scroll);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_columns_stay_within_the_line() {
+        assert_eq!(visible_columns(100, 0, 40), (0, 40));
+        assert_eq!(visible_columns(100, 70, 40), (70, 100));
+        assert_eq!(visible_columns(10, 20, 40), (10, 10));
+        assert_eq!(visible_columns(0, 0, 40), (0, 0));
+    }
+}
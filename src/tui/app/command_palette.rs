@@ -0,0 +1,140 @@
+//! Command palette (Ctrl+K): every shortcut action and slash command in one fuzzy-searchable
+//! list, with its key binding, so features can be found without knowing their keys.
+
+use crate::core::commands::ResolvedCommand;
+use crate::core::util::filter_by_query;
+use crate::tui::shortcuts::Shortcut;
+
+use super::App;
+
+/// What running a palette entry does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteAction {
+    /// Same as pressing the shortcut.
+    Shortcut(Shortcut),
+    /// Start a conversation search (Ctrl+F).
+    Search,
+    /// Run the slash command with this name.
+    Command(String),
+}
+
+/// A row of the palette.
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub label: String,
+    /// Key binding ("Alt+H") or slash command ("/review").
+    pub hint: String,
+    pub action: PaletteAction,
+}
+
+/// State of the command palette popup.
+pub struct CommandPaletteState {
+    pub entries: Vec<PaletteEntry>,
+    pub filter: String,
+    /// Index of the selection among the matching entries.
+    pub selected: usize,
+}
+
+impl CommandPaletteState {
+    /// The shortcut actions, search, then the slash commands.
+    pub fn new(commands: &[ResolvedCommand]) -> Self {
+        let shortcuts = Shortcut::PALETTE.iter().map(|&shortcut| PaletteEntry {
+            label: shortcut.label().to_string(),
+            hint: shortcut.keys().to_string(),
+            action: PaletteAction::Shortcut(shortcut),
+        });
+        let search = PaletteEntry {
+            label: "Search conversation".to_string(),
+            hint: "Ctrl+F".to_string(),
+            action: PaletteAction::Search,
+        };
+        let commands = commands.iter().map(|cmd| PaletteEntry {
+            label: cmd.description.clone(),
+            hint: cmd.full_name(),
+            action: PaletteAction::Command(cmd.name.clone()),
+        });
+        Self {
+            entries: shortcuts
+                .chain(std::iter::once(search))
+                .chain(commands)
+                .collect(),
+            filter: String::new(),
+            selected: 0,
+        }
+    }
+
+    /// Entries matching the filter (on the label or the hint), best first.
+    pub fn matches(&self) -> Vec<&PaletteEntry> {
+        filter_by_query(&self.entries, &self.filter, |e| {
+            (e.label.as_str(), e.hint.as_str())
+        })
+    }
+
+    pub fn selected_entry(&self) -> Option<&PaletteEntry> {
+        self.matches().get(self.selected).copied()
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.matches().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        self.filter.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop_filter(&mut self) {
+        self.filter.pop();
+        self.selected = 0;
+    }
+}
+
+impl App {
+    pub(crate) fn open_command_palette(&mut self) {
+        self.command_palette = Some(CommandPaletteState::new(&self.resolved_commands));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str, description: &str) -> ResolvedCommand {
+        ResolvedCommand {
+            name: name.to_string(),
+            description: description.to_string(),
+            prompt_prefix: String::new(),
+            mode: "Build".to_string(),
+            model: None,
+            output_schema: None,
+            is_custom: false,
+        }
+    }
+
+    #[test]
+    fn palette_matches_labels_and_hints() {
+        let mut state = CommandPaletteState::new(&[command("review", "Review Git changes")]);
+        assert!(state.entries.len() > Shortcut::PALETTE.len());
+
+        state.filter = "/review".to_string();
+        assert_eq!(
+            state.selected_entry().map(|e| &e.action),
+            Some(&PaletteAction::Command("review".to_string()))
+        );
+
+        state.filter = "history".to_string();
+        assert_eq!(
+            state.selected_entry().map(|e| &e.action),
+            Some(&PaletteAction::Shortcut(Shortcut::History))
+        );
+
+        state.filter = "zzzz".to_string();
+        assert!(state.matches().is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Conversation compaction (`/compact`): the request sent to the model and how its summary
+//! replaces the API messages.
+
+use serde_json::Value;
+
+use crate::core::llm::{self, Compaction};
+use crate::tui::draw::format_tokens_compact;
+
+use super::{App, ChatMessage};
+
+/// API messages waiting to be summarized by `model`.
+pub struct CompactRequest {
+    pub model: String,
+    /// The API messages when /compact ran.
+    pub messages: Vec<Value>,
+    /// Index of the first message kept as is (see `llm::split_point`).
+    pub split: usize,
+}
+
+/// True when `current` still starts with `snapshot`. The system prompt may have been
+/// rebuilt meanwhile (workspace context refresh), so it is not compared.
+fn continues(current: &[Value], snapshot: &[Value]) -> bool {
+    current.len() >= snapshot.len()
+        && current
+            .iter()
+            .zip(snapshot)
+            .enumerate()
+            .all(|(i, (a, b))| a == b || (i == 0 && a["role"] == "system" && b["role"] == "system"))
+}
+
+impl App {
+    /// Start compacting `api_messages` (`/compact`): the last `llm::KEEP_TURNS` user turns
+    /// are kept, everything before is summarized in the background.
+    pub(crate) fn request_compaction(&mut self, api_messages: &Option<Vec<Value>>) {
+        if self.compacting {
+            self.push_tool_log("The conversation is already being compacted.".to_string());
+            return;
+        }
+        let Some(messages) = api_messages.as_ref() else {
+            self.push_tool_log("Nothing to compact yet.".to_string());
+            return;
+        };
+        let Some(split) = llm::split_point(messages, llm::KEEP_TURNS) else {
+            self.push_tool_log(format!(
+                "Nothing to compact: the conversation has {} turns or fewer.",
+                llm::KEEP_TURNS
+            ));
+            return;
+        };
+        self.compacting = true;
+        self.compact_request = Some(CompactRequest {
+            model: self.current_model_id.clone(),
+            messages: messages.clone(),
+            split,
+        });
+        self.push_tool_log("Compacting the conversation...".to_string());
+    }
+
+    /// Replace the API messages with a compaction of `request`, keeping messages added
+    /// since. Dropped when the conversation changed otherwise (e.g. another one was opened).
+    pub(crate) fn apply_compaction(
+        &mut self,
+        api_messages: &mut Option<Vec<Value>>,
+        request: &CompactRequest,
+        result: Result<Compaction, llm::ChatError>,
+    ) {
+        self.compacting = false;
+        let compaction = match result {
+            Ok(compaction) => compaction,
+            Err(e) => {
+                self.push_tool_log(format!("Compaction failed: {}", e));
+                return;
+            }
+        };
+        let Some(current) = api_messages
+            .as_mut()
+            .filter(|m| continues(m, &request.messages))
+        else {
+            self.push_tool_log(
+                "Compaction discarded: the conversation changed meanwhile.".to_string(),
+            );
+            return;
+        };
+        let mut messages = compaction.messages;
+        if let (Some(first), Some(system)) = (messages.first_mut(), current.first())
+            && first["role"] == "system"
+            && !llm::is_summary(first)
+        {
+            *first = system.clone();
+        }
+        messages.extend_from_slice(&current[request.messages.len()..]);
+        let kept_turns = messages.iter().filter(|m| m["role"] == "user").count();
+        *current = messages;
+
+        self.messages.push(ChatMessage::ContextCompacted {
+            summary: compaction.summary,
+            kept_turns,
+        });
+        self.message_timestamps.push(None);
+        self.token_usage = None;
+        self.mark_dirty();
+        let k = |tokens: usize| format_tokens_compact(tokens as u64);
+        self.push_tool_log(format!(
+            "Compacted {} messages into a summary: ~{} → ~{} tokens (~{} saved). The last {} turns are kept.",
+            compaction.summarized,
+            k(compaction.tokens_before),
+            k(compaction.tokens_after),
+            k(compaction
+                .tokens_before
+                .saturating_sub(compaction.tokens_after)),
+            kept_turns
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn continues_ignores_rebuilt_system_prompt() {
+        let snapshot = vec![
+            json!({"role": "system", "content": "old prompt"}),
+            json!({"role": "user", "content": "hi"}),
+        ];
+        let mut current = snapshot.clone();
+        current[0]["content"] = json!("new prompt");
+        current.push(json!({"role": "assistant", "content": "hello"}));
+        assert!(continues(&current, &snapshot));
+
+        current[1]["content"] = json!("other conversation");
+        assert!(!continues(&current, &snapshot));
+        assert!(!continues(&snapshot[..1], &snapshot));
+    }
+}
@@ -0,0 +1,57 @@
+//! Editor requests: a file of the tree (Alt+F, `e`), the input itself (Ctrl+G), or a
+//! suggested hunk (`e` in the review), opened in `$EDITOR` by the main loop with the TUI
+//! suspended.
+
+use std::path::PathBuf;
+
+use super::super::paste;
+use super::App;
+
+/// What the main loop opens in the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditorRequest {
+    /// A workspace file; the file tree previews its new content afterwards.
+    File(PathBuf),
+    /// The input, written to a temporary file and read back into the input box.
+    Input,
+    /// The new side of a suggested hunk, rewritten by the user (see `core::suggest`).
+    Hunk(String),
+}
+
+/// Prompt text of an edited input file: the trailing newlines editors add are dropped.
+fn edited_input(text: &str) -> &str {
+    text.trim_end_matches(['\n', '\r'])
+}
+
+impl App {
+    /// Ask the main loop to open the input in `$EDITOR` (Ctrl+G).
+    pub(crate) fn compose_in_editor(&mut self) {
+        self.editor_request = Some(EditorRequest::Input);
+    }
+
+    /// Replace the input with the text written in the editor, cursor at its end. Chips
+    /// removed in the editor drop their attachments.
+    pub(crate) fn set_input_from_editor(&mut self, text: &str) {
+        self.input = edited_input(text).to_string();
+        self.input_cursor = self.input.len();
+        let kept: Vec<usize> = paste::placeholder_ranges(&self.input, &self.attachments)
+            .into_iter()
+            .map(|(_, a)| a.id)
+            .collect();
+        self.attachments.retain(|a| kept.contains(&a.id));
+        self.selected_command_index = 0;
+        self.update_mention_files();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edited_input_drops_the_trailing_newlines_only() {
+        assert_eq!(edited_input("Fix it\n"), "Fix it");
+        assert_eq!(edited_input("  a\n\nb\r\n\n"), "  a\n\nb");
+        assert_eq!(edited_input(""), "");
+    }
+}
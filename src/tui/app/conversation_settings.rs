@@ -0,0 +1,75 @@
+//! Model, mode, and `/params` values saved with each conversation and restored when it is
+//! loaded from history, with the workspace it was held in.
+
+use crate::core::config::ModelSource;
+use crate::core::history::{ConversationOrigin, ConversationSettings};
+use crate::core::models;
+
+use super::super::constants::SUGGESTIONS;
+use super::App;
+
+impl App {
+    /// Settings to save with the conversation: the current model and mode, the model's
+    /// `/params` values of this session, and the workspace, branch, and environment.
+    pub(crate) fn conversation_settings(&self) -> ConversationSettings {
+        ConversationSettings {
+            origin: Some(ConversationOrigin::capture(&self.workspace)),
+            model_id: Some(self.current_model_id.clone()),
+            mode: Some(SUGGESTIONS[self.selected_suggestion].to_string()),
+            params: self
+                .session_params
+                .get(&self.current_model_id)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Switch to the model, mode, and `/params` values a loaded conversation was held with.
+    /// A model given with `--model`, or no longer listed by the provider, is kept instead,
+    /// with a note; the conversation's `/params` values then do not apply. A conversation
+    /// held in another workspace gets a warning.
+    pub(crate) fn restore_conversation_settings(&mut self, settings: &ConversationSettings) {
+        if let Some(warning) = settings
+            .origin
+            .as_ref()
+            .and_then(|origin| origin.mismatch_warning(&self.workspace.root))
+        {
+            self.push_tool_log(format!("Warning: {}", warning));
+        }
+        if let Some(mode) = settings.mode.as_deref()
+            && let Some(index) = SUGGESTIONS.iter().position(|m| *m == mode)
+        {
+            self.selected_suggestion = index;
+        }
+        let model_id = match settings.model_id.as_deref() {
+            None => return,
+            Some(id) if id == self.current_model_id => id.to_string(),
+            Some(id) if self.model_source == ModelSource::Flag => {
+                self.push_tool_log(format!(
+                    "This conversation used {}; keeping {} (--model).",
+                    id, self.current_model_id
+                ));
+                return;
+            }
+            Some(id) if models::validate_model_id(id).is_err() => {
+                self.push_tool_log(format!(
+                    "This conversation used {}, which is no longer available; continuing with {} (Alt+M to switch).",
+                    id, self.current_model_id
+                ));
+                return;
+            }
+            Some(id) => {
+                self.current_model_id = id.to_string();
+                self.model_name = models::resolve_model_display_name(id);
+                self.model_source = ModelSource::Conversation;
+                self.context_length = models::resolve_context_length(id);
+                self.push_tool_log(format!("Model restored from this conversation: {}", id));
+                id.to_string()
+            }
+        };
+        if !settings.params.is_empty() {
+            self.session_params
+                .insert(model_id, settings.params.clone());
+        }
+    }
+}
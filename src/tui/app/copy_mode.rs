@@ -0,0 +1,199 @@
+//! Copy mode (`/copy`): every code block of the conversation in a list, to copy several at
+//! once — concatenated, or one after the other so a clipboard history keeps each of them.
+
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use crate::tui::text::{MessageSegment, parse_message_segments};
+
+use super::{App, ChatMessage};
+
+/// Pause between the snippets copied separately, so clipboard managers record each one.
+const SEPARATE_COPY_INTERVAL: Duration = Duration::from_millis(400);
+
+/// A fenced code block of the conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSnippet {
+    /// Fence language ("" when none).
+    pub lang: String,
+    pub code: String,
+}
+
+/// State of the `/copy` popup.
+pub struct CopyModeState {
+    pub snippets: Vec<CodeSnippet>,
+    pub selected: usize,
+    /// Indices of the snippets marked with Space.
+    pub marked: BTreeSet<usize>,
+}
+
+impl CopyModeState {
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.snippets.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if !self.marked.remove(&self.selected) {
+            self.marked.insert(self.selected);
+        }
+    }
+
+    /// Mark every snippet, or clear the marks when all are marked.
+    pub fn toggle_all(&mut self) {
+        if self.marked.len() == self.snippets.len() {
+            self.marked.clear();
+        } else {
+            self.marked = (0..self.snippets.len()).collect();
+        }
+    }
+
+    /// Code of the marked snippets in conversation order, or of the selected one when none
+    /// is marked.
+    pub fn chosen(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            self.snippets
+                .get(self.selected)
+                .map(|s| vec![s.code.clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked
+                .iter()
+                .filter_map(|&i| self.snippets.get(i))
+                .map(|s| s.code.clone())
+                .collect()
+        }
+    }
+}
+
+/// Code blocks (including Mermaid and math blocks) of the User and Assistant messages.
+pub(crate) fn code_snippets(messages: &[ChatMessage]) -> Vec<CodeSnippet> {
+    let mut snippets = Vec::new();
+    for message in messages {
+        let (ChatMessage::User(text) | ChatMessage::Assistant(text)) = message else {
+            continue;
+        };
+        for segment in parse_message_segments(text) {
+            let (lang, code) = match segment {
+                MessageSegment::CodeBlock { lang, code } => (lang, code),
+                MessageSegment::Preview { kind, source } => (kind.label(), source),
+                MessageSegment::Text(_) => continue,
+            };
+            if code.trim().is_empty() {
+                continue;
+            }
+            snippets.push(CodeSnippet {
+                lang: lang.to_string(),
+                code: code.to_string(),
+            });
+        }
+    }
+    snippets
+}
+
+/// Put `text` on the system clipboard.
+fn set_clipboard(text: String) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new().and_then(|mut c| c.set_text(text))
+}
+
+impl App {
+    pub(crate) fn open_copy_mode(&mut self) {
+        let snippets = code_snippets(&self.messages);
+        if snippets.is_empty() {
+            self.push_tool_log("No code blocks in this conversation.".to_string());
+            return;
+        }
+        // Start on the most recent block.
+        let selected = snippets.len() - 1;
+        self.copy_mode = Some(CopyModeState {
+            snippets,
+            selected,
+            marked: BTreeSet::new(),
+        });
+    }
+
+    /// Copy the chosen snippets as one text, separated by blank lines, and close the popup.
+    pub(crate) fn copy_snippets_concatenated(&mut self) {
+        let Some(state) = self.copy_mode.take() else {
+            return;
+        };
+        self.copy_text(state.chosen().join("\n\n"));
+    }
+
+    fn copy_text(&mut self, text: String) {
+        match set_clipboard(text) {
+            Ok(()) => self.copy_toast_until = Some(Instant::now() + Duration::from_secs(2)),
+            Err(e) => self.push_tool_log(format!("Copy failed: {}", e)),
+        }
+    }
+
+    /// Copy the chosen snippets one after the other, in the background, so a clipboard
+    /// history (the OS one or a clipboard manager) keeps each as its own entry. The last one
+    /// stays on the clipboard.
+    pub(crate) fn copy_snippets_separately(&mut self) {
+        let Some(state) = self.copy_mode.take() else {
+            return;
+        };
+        let chosen = state.chosen();
+        let count = chosen.len();
+        if count <= 1 {
+            self.copy_text(chosen.concat());
+            return;
+        }
+        std::thread::spawn(move || {
+            for (i, code) in chosen.into_iter().enumerate() {
+                if i > 0 {
+                    std::thread::sleep(SEPARATE_COPY_INTERVAL);
+                }
+                if let Err(e) = set_clipboard(code) {
+                    log::warn!("Copying snippet {} of {} failed: {}", i + 1, count, e);
+                    return;
+                }
+            }
+        });
+        self.push_tool_log(format!(
+            "Copying {} code blocks one by one into the clipboard history (the last stays on the clipboard).",
+            count
+        ));
+        self.copy_toast_until = Some(Instant::now() + Duration::from_secs(2));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_snippets_lists_blocks_in_order_and_chosen_follows_marks() {
+        let messages = vec![
+            ChatMessage::User("Fix this:\n```rust\nfn a() {}\n```".to_string()),
+            ChatMessage::Assistant(
+                "```\nls\n```\ntext\n```mermaid\ngraph TD\n```\n```\n\n```".to_string(),
+            ),
+        ];
+        let snippets = code_snippets(&messages);
+        let langs: Vec<&str> = snippets.iter().map(|s| s.lang.as_str()).collect();
+        assert_eq!(langs, vec!["rust", "", "mermaid"]);
+        assert_eq!(snippets[1].code, "ls");
+
+        let mut state = CopyModeState {
+            snippets,
+            selected: 2,
+            marked: BTreeSet::new(),
+        };
+        assert_eq!(state.chosen(), vec!["graph TD"]);
+        state.toggle_selected();
+        state.selected = 0;
+        state.toggle_selected();
+        assert_eq!(state.chosen(), vec!["fn a() {}", "graph TD"]);
+        state.toggle_all();
+        assert_eq!(state.marked.len(), 3);
+        state.toggle_all();
+        assert!(state.marked.is_empty());
+    }
+}
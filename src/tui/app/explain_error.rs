@@ -0,0 +1,243 @@
+//! "Explain this error" (Alt+X, or a click on the action under an error block): fills the
+//! input with a prompt asking the model to diagnose the error, with the tool calls and the
+//! request that led to it.
+
+use serde_json::Value;
+
+use crate::core::message::{self, ToolFailure};
+use crate::core::text::truncate_end;
+
+use super::{App, ChatMessage};
+
+/// Tool calls quoted before the error, most recent last.
+const MAX_TOOL_CALLS: usize = 10;
+/// Widest error text quoted in the prompt, in columns.
+const MAX_ERROR_WIDTH: usize = 4000;
+/// Widest request quoted in the prompt, in columns.
+const MAX_REQUEST_WIDTH: usize = 500;
+
+impl App {
+    /// Fill the input with a prompt explaining the error block at `target`, or else the
+    /// last error of the turn: an error block after the last prompt, or the last failed tool
+    /// call in `api_messages`. A log line says why when there is nothing to explain.
+    pub(crate) fn explain_error(&mut self, target: Option<usize>, api_messages: &[Value]) {
+        if self.turn_status.is_some() {
+            self.push_tool_log("Wait for the turn to end before explaining an error.".into());
+            return;
+        }
+        if !self.input.trim().is_empty() {
+            self.push_tool_log(
+                "Clear the input (Ctrl+U) to explain the error: the prompt is written there."
+                    .into(),
+            );
+            return;
+        }
+        let prompt = match self.error_block_idx(target) {
+            Some(idx) => {
+                let error = error_text(&self.messages[idx]).unwrap_or_default();
+                explain_prompt(
+                    &error,
+                    None,
+                    &self.tool_calls_before(idx),
+                    self.request_before(idx),
+                )
+            }
+            None => match message::last_tool_failure(api_messages) {
+                Some(failure) => {
+                    let end = self.messages.len();
+                    explain_prompt(
+                        &failure.error,
+                        Some(&failure),
+                        &self.tool_calls_before(end),
+                        self.request_before(end),
+                    )
+                }
+                None => {
+                    self.push_tool_log(
+                        "No error to explain: hover an error block, or run a turn where a tool fails."
+                            .into(),
+                    );
+                    return;
+                }
+            },
+        };
+        self.input_cursor = prompt.len();
+        self.input = prompt;
+    }
+
+    /// `target` when it is an error block, else the last error block after the last prompt.
+    fn error_block_idx(&self, target: Option<usize>) -> Option<usize> {
+        if let Some(idx) = target.filter(|&i| self.messages.get(i).is_some_and(is_error)) {
+            return Some(idx);
+        }
+        self.messages
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, m)| !matches!(m, ChatMessage::User(_)))
+            .find(|(_, m)| is_error(m))
+            .map(|(i, _)| i)
+    }
+
+    /// Tool call log lines ("→ Bash: ls") between the prompt before `end` and `end`.
+    fn tool_calls_before(&self, end: usize) -> Vec<&str> {
+        let mut calls: Vec<&str> = self.messages[..end]
+            .iter()
+            .rev()
+            .take_while(|m| !matches!(m, ChatMessage::User(_)))
+            .filter_map(|m| match m {
+                ChatMessage::ToolLog(line) if line.starts_with("→ ") => Some(line.as_str()),
+                _ => None,
+            })
+            .take(MAX_TOOL_CALLS)
+            .collect();
+        calls.reverse();
+        calls
+    }
+
+    /// The prompt the message at `end` answers.
+    fn request_before(&self, end: usize) -> Option<&str> {
+        self.messages[..end].iter().rev().find_map(|m| match m {
+            ChatMessage::User(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Failed request, or a reply saved as "Error: ..." (failed requests of loaded conversations).
+fn is_error(message: &ChatMessage) -> bool {
+    error_text(message).is_some()
+}
+
+fn error_text(message: &ChatMessage) -> Option<String> {
+    match message {
+        ChatMessage::Error(notice) => Some(match &notice.hint {
+            Some(hint) => format!("{}\n{}", notice.message, hint),
+            None => notice.message.clone(),
+        }),
+        ChatMessage::Assistant(text) if text.starts_with("Error:") => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// Prompt asking the model to diagnose `error`.
+fn explain_prompt(
+    error: &str,
+    failed_call: Option<&ToolFailure>,
+    tool_calls: &[&str],
+    request: Option<&str>,
+) -> String {
+    let mut prompt = String::from("Explain this error and how to fix it.\n\nError:\n```text\n");
+    prompt.push_str(&truncate_end(error.trim(), MAX_ERROR_WIDTH));
+    prompt.push_str("\n```\n");
+    if let Some(call) = failed_call {
+        prompt.push_str(&format!(
+            "\nIt was returned by the {} tool called with {}\n",
+            call.tool, call.arguments
+        ));
+    }
+    if !tool_calls.is_empty() {
+        prompt.push_str("\nTool calls before it:\n");
+        for call in tool_calls {
+            prompt.push_str(call);
+            prompt.push('\n');
+        }
+    }
+    if let Some(request) = request.map(str::trim).filter(|r| !r.is_empty()) {
+        prompt.push_str(&format!(
+            "\nMy request was: {}\n",
+            truncate_end(request, MAX_REQUEST_WIDTH)
+        ));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::config::ModelSource;
+    use crate::core::llm::ChatError;
+    use crate::core::workspace::Workspace;
+    use crate::tui::app::ErrorNotice;
+
+    fn app() -> App {
+        let root = PathBuf::from("/work/project");
+        App::new(
+            "mock/model".to_string(),
+            "Mock Model".to_string(),
+            ModelSource::Default,
+            Workspace {
+                root: root.clone(),
+                working_dir: root,
+                project_type: None,
+                agent_md: None,
+                context_files: Vec::new(),
+                git_context: None,
+            },
+            false,
+            false,
+        )
+    }
+
+    #[test]
+    fn explain_error_quotes_the_error_block_and_its_turn() {
+        let mut app = app();
+        app.messages = vec![
+            ChatMessage::User("Fix the build".to_string()),
+            ChatMessage::ToolLog("→ Bash: cargo build".to_string()),
+            ChatMessage::ToolLog("Saved".to_string()),
+            ChatMessage::Error(ErrorNotice::new(&ChatError::Other(
+                "connection reset".into(),
+            ))),
+        ];
+        app.explain_error(None, &[]);
+        assert!(app.input.starts_with("Explain this error"), "{}", app.input);
+        assert!(app.input.contains("connection reset"));
+        assert!(
+            app.input
+                .contains("Tool calls before it:\n→ Bash: cargo build\n")
+        );
+        assert!(!app.input.contains("Saved"));
+        assert!(app.input.ends_with("My request was: Fix the build\n"));
+        assert_eq!(app.input_cursor, app.input.len());
+    }
+
+    #[test]
+    fn explain_error_falls_back_to_the_failed_tool_call() {
+        let mut app = app();
+        app.messages = vec![
+            ChatMessage::User("Run the tests".to_string()),
+            ChatMessage::Assistant("They fail.".to_string()),
+        ];
+        let api = vec![
+            serde_json::json!({"role": "user", "content": "Run the tests"}),
+            serde_json::json!({"role": "assistant", "content": "", "tool_calls": [
+                {"id": "1", "type": "function", "function": {"name": "Bash", "arguments": "{\"command\":\"cargo test\"}"}}
+            ]}),
+            serde_json::json!({"role": "tool", "tool_call_id": "1", "content": "Error: exit status 101"}),
+            serde_json::json!({"role": "assistant", "content": "They fail."}),
+        ];
+        app.explain_error(None, &api);
+        assert!(
+            app.input.contains("Error: exit status 101"),
+            "{}",
+            app.input
+        );
+        assert!(
+            app.input
+                .contains("the Bash tool called with {\"command\":\"cargo test\"}")
+        );
+    }
+
+    #[test]
+    fn explain_error_keeps_a_typed_input() {
+        let mut app = app();
+        app.messages = vec![ChatMessage::Assistant("Error: boom".to_string())];
+        app.input = "draft".to_string();
+        app.explain_error(Some(0), &[]);
+        assert_eq!(app.input, "draft");
+        assert!(matches!(app.messages.last(), Some(ChatMessage::ToolLog(_))));
+    }
+}
@@ -0,0 +1,221 @@
+//! Workspace file tree popup (Alt+F): browse the files, preview the selected one, and
+//! insert its path, attach its content to the prompt, or open it in `$EDITOR`.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::core::file_tree::{self, TreeEntry};
+use crate::core::remote;
+use crate::tui::paste::AttachmentKind;
+
+use super::{App, EditorRequest};
+
+/// State of the file tree popup.
+pub struct FileTreeState {
+    /// Every entry, depth first; collapsed directories hide theirs when drawn.
+    pub entries: Vec<TreeEntry>,
+    /// Paths of the expanded directories.
+    pub expanded: HashSet<String>,
+    /// Index of the selection among the visible entries.
+    pub selected: usize,
+    /// Path of the previewed file and the start of its content.
+    pub preview: Option<(String, String)>,
+}
+
+impl FileTreeState {
+    pub fn new(entries: Vec<TreeEntry>) -> Self {
+        Self {
+            entries,
+            expanded: HashSet::new(),
+            selected: 0,
+            preview: None,
+        }
+    }
+
+    /// Entries whose parent directories are all expanded, in display order.
+    pub fn visible(&self) -> Vec<&TreeEntry> {
+        let mut visible = Vec::new();
+        // Depth of the collapsed directory whose content is being skipped.
+        let mut collapsed_at: Option<usize> = None;
+        for entry in &self.entries {
+            if let Some(depth) = collapsed_at {
+                if entry.depth > depth {
+                    continue;
+                }
+                collapsed_at = None;
+            }
+            visible.push(entry);
+            if entry.is_dir && !self.expanded.contains(&entry.path) {
+                collapsed_at = Some(entry.depth);
+            }
+        }
+        visible
+    }
+
+    pub fn selected_entry(&self) -> Option<&TreeEntry> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.visible().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Expand the selected directory.
+    pub fn expand_selected(&mut self) {
+        if let Some(entry) = self.selected_entry().filter(|e| e.is_dir) {
+            let path = entry.path.clone();
+            self.expanded.insert(path);
+        }
+    }
+
+    /// Expand the selected directory, or collapse it if expanded.
+    pub fn toggle_selected(&mut self) {
+        let Some(entry) = self.selected_entry().filter(|e| e.is_dir) else {
+            return;
+        };
+        let path = entry.path.clone();
+        if !self.expanded.remove(&path) {
+            self.expanded.insert(path);
+        }
+    }
+
+    /// Collapse the selected directory; on a file or collapsed directory, select the
+    /// parent directory.
+    pub fn collapse_or_select_parent(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if entry.is_dir && self.expanded.contains(&entry.path) {
+            let path = entry.path.clone();
+            self.expanded.remove(&path);
+            return;
+        }
+        let Some((parent, _)) = entry.path.rsplit_once('/') else {
+            return;
+        };
+        if let Some(index) = self.visible().iter().position(|e| e.path == parent) {
+            self.selected = index;
+        }
+    }
+
+    /// Read the selected file for the preview, unless it is already shown.
+    pub fn update_preview(&mut self, root: &Path) {
+        let Some(entry) = self.selected_entry().filter(|e| !e.is_dir) else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().is_some_and(|(p, _)| p == &entry.path) {
+            return;
+        }
+        let path = entry.path.clone();
+        let text = file_tree::preview(&root.join(&path));
+        self.preview = Some((path, text));
+    }
+}
+
+impl App {
+    /// Open the file tree popup on the workspace root.
+    pub(crate) fn open_file_tree(&mut self) {
+        if remote::is_active() {
+            self.push_tool_log("The file tree is not available in a remote workspace.".to_string());
+            return;
+        }
+        let entries = file_tree::workspace_tree(&self.workspace.root);
+        if entries.is_empty() {
+            self.push_tool_log("No files in the workspace.".to_string());
+            return;
+        }
+        let mut state = FileTreeState::new(entries);
+        state.update_preview(&self.workspace.root);
+        self.file_tree = Some(state);
+    }
+
+    /// Selected file of the file tree: relative path and absolute path.
+    fn file_tree_selection(&self) -> Option<(String, PathBuf)> {
+        let entry = self.file_tree.as_ref()?.selected_entry()?;
+        Some((entry.path.clone(), self.workspace.root.join(&entry.path)))
+    }
+
+    /// Close the file tree and insert the selected path at the cursor.
+    pub(crate) fn file_tree_insert_path(&mut self) {
+        let Some((path, _)) = self.file_tree_selection() else {
+            return;
+        };
+        self.file_tree = None;
+        self.insert_at_cursor(&path);
+    }
+
+    /// Close the file tree and attach the selected file's content as a chip.
+    pub(crate) fn file_tree_attach(&mut self) {
+        let Some((path, absolute)) = self.file_tree_selection() else {
+            return;
+        };
+        if absolute.is_dir() {
+            return;
+        }
+        match file_tree::read_for_attachment(&absolute) {
+            Ok(content) => {
+                self.file_tree = None;
+                self.insert_attachment(AttachmentKind::File { path, content });
+            }
+            Err(e) => self.push_tool_log(e),
+        }
+    }
+
+    /// Ask the main loop to open the selected file in `$EDITOR`; the popup stays open.
+    pub(crate) fn file_tree_edit(&mut self) {
+        if let Some((_, absolute)) = self.file_tree_selection()
+            && absolute.is_file()
+        {
+            self.editor_request = Some(EditorRequest::File(absolute));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, is_dir: bool) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            depth: path.split('/').count(),
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn collapsed_directories_hide_their_content() {
+        let mut state = FileTreeState::new(vec![
+            entry("src", true),
+            entry("src/bin", true),
+            entry("src/bin/tool.rs", false),
+            entry("src/main.rs", false),
+            entry("README.md", false),
+        ]);
+        let paths = |s: &FileTreeState| -> Vec<String> {
+            s.visible().iter().map(|e| e.path.clone()).collect()
+        };
+        assert_eq!(paths(&state), ["src", "README.md"]);
+
+        state.toggle_selected();
+        assert_eq!(
+            paths(&state),
+            ["src", "src/bin", "src/main.rs", "README.md"]
+        );
+
+        state.select_next();
+        state.select_next();
+        assert_eq!(state.selected_entry().unwrap().path, "src/main.rs");
+        state.collapse_or_select_parent();
+        assert_eq!(state.selected_entry().unwrap().path, "src");
+        state.collapse_or_select_parent();
+        assert_eq!(paths(&state), ["src", "README.md"]);
+    }
+}
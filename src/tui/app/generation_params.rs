@@ -0,0 +1,198 @@
+//! Generation parameters of the session (`/params`): popup state and per-model overrides.
+
+use crate::core::config::Config;
+use crate::core::generation::{self, GenerationParams, ParamError};
+use crate::core::persistence;
+
+use super::App;
+
+/// Field of the `/params` popup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamsField {
+    Temperature,
+    MaxTokens,
+    TopP,
+    Stop,
+}
+
+impl ParamsField {
+    pub const ALL: [ParamsField; 4] = [
+        ParamsField::Temperature,
+        ParamsField::MaxTokens,
+        ParamsField::TopP,
+        ParamsField::Stop,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ParamsField::Temperature => "Temperature",
+            ParamsField::MaxTokens => "Max tokens",
+            ParamsField::TopP => "Top P",
+            ParamsField::Stop => "Stop",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// State of the `/params` popup: the current model's parameters as editable text.
+pub struct ParamsPopupState {
+    pub model_id: String,
+    /// Field values, indexed like `ParamsField::ALL`. Empty means the global default.
+    values: [String; 4],
+    pub focused_field: ParamsField,
+    /// Global defaults, shown in empty fields.
+    pub defaults: GenerationParams,
+    pub error: Option<String>,
+}
+
+impl ParamsPopupState {
+    fn new(model_id: String, params: &GenerationParams, defaults: GenerationParams) -> Self {
+        let values = [
+            params.temperature.map(|t| t.to_string()),
+            params.max_tokens.map(|m| m.to_string()),
+            params.top_p.map(|p| p.to_string()),
+            params.stop.as_deref().map(generation::format_stop),
+        ]
+        .map(Option::unwrap_or_default);
+        ParamsPopupState {
+            model_id,
+            values,
+            focused_field: ParamsField::Temperature,
+            defaults,
+            error: None,
+        }
+    }
+
+    pub fn value(&self, field: ParamsField) -> &str {
+        &self.values[field.index()]
+    }
+
+    pub fn value_mut(&mut self, field: ParamsField) -> &mut String {
+        &mut self.values[field.index()]
+    }
+
+    /// What an empty `field` stands for: the global default, else the provider's.
+    pub fn placeholder(&self, field: ParamsField) -> String {
+        let default = match field {
+            ParamsField::Temperature => self.defaults.temperature.map(|t| t.to_string()),
+            ParamsField::MaxTokens => self.defaults.max_tokens.map(|m| m.to_string()),
+            ParamsField::TopP => self.defaults.top_p.map(|p| p.to_string()),
+            ParamsField::Stop => self.defaults.stop.as_deref().map(generation::format_stop),
+        };
+        match default {
+            Some(value) => format!("{} (default)", value),
+            None => "provider default".to_string(),
+        }
+    }
+
+    /// The parameters typed in the fields.
+    pub fn parse(&self) -> Result<GenerationParams, ParamError> {
+        Ok(GenerationParams {
+            temperature: generation::parse_temperature(self.value(ParamsField::Temperature))?,
+            max_tokens: generation::parse_max_tokens(self.value(ParamsField::MaxTokens))?,
+            top_p: generation::parse_top_p(self.value(ParamsField::TopP))?,
+            stop: generation::parse_stop(self.value(ParamsField::Stop))?,
+            response_schema: None,
+        })
+    }
+}
+
+impl App {
+    /// Generation parameters of a request to `model_id`: the global defaults, then the
+    /// session values of the model (`/params`), else its saved values.
+    pub(crate) fn generation_params(&self, config: &Config, model_id: &str) -> GenerationParams {
+        generation::effective(
+            &config.generation,
+            model_id,
+            self.session_params.get(model_id),
+        )
+    }
+
+    /// Open `/params` for the current model, filled with its session or saved values.
+    pub(crate) fn open_params_popup(&mut self, config: &Config) {
+        let model_id = self.current_model_id.clone();
+        let params = match self.session_params.get(&model_id) {
+            Some(params) => params.clone(),
+            None => persistence::load_model_params()
+                .remove(&model_id)
+                .unwrap_or_default(),
+        };
+        self.params_popup = Some(ParamsPopupState::new(
+            model_id,
+            &params,
+            config.generation.clone(),
+        ));
+    }
+
+    /// Use `params` for `model_id` until the app exits, or save them as the model's
+    /// parameters when `save` is set.
+    pub(crate) fn set_model_params(
+        &mut self,
+        config: &Config,
+        model_id: &str,
+        params: GenerationParams,
+        save: bool,
+    ) {
+        let line = if save {
+            match persistence::save_model_params(model_id, &params) {
+                Ok(()) => {
+                    self.session_params.remove(model_id);
+                    format!(
+                        "Saved generation parameters for {}: {}",
+                        model_id,
+                        self.generation_params(config, model_id).summary()
+                    )
+                }
+                Err(e) => format!("Failed to save generation parameters: {}", e),
+            }
+        } else {
+            self.session_params.insert(model_id.to_string(), params);
+            format!(
+                "Generation parameters for {} (this session): {}",
+                model_id,
+                self.generation_params(config, model_id).summary()
+            )
+        };
+        self.push_tool_log(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popup_fields_round_trip_parameters() {
+        let params = GenerationParams {
+            temperature: Some(0.2),
+            max_tokens: None,
+            top_p: Some(0.9),
+            stop: Some(vec!["###".to_string(), "\n\n".to_string()]),
+            response_schema: None,
+        };
+        let mut state =
+            ParamsPopupState::new("m".to_string(), &params, GenerationParams::default());
+        assert_eq!(state.value(ParamsField::Stop), "###, \\n\\n");
+        assert_eq!(state.parse().unwrap(), params);
+        assert_eq!(
+            state.placeholder(ParamsField::MaxTokens),
+            "provider default"
+        );
+
+        state.value_mut(ParamsField::MaxTokens).push_str("-1");
+        assert!(matches!(state.parse(), Err(ParamError::MaxTokens(_))));
+        assert_eq!(ParamsField::Stop.next(), ParamsField::Temperature);
+        assert_eq!(ParamsField::Temperature.prev(), ParamsField::Stop);
+    }
+}
@@ -0,0 +1,95 @@
+//! Pinned messages (Alt+P, `/pins`): User and Assistant messages quoted in the system prompt
+//! of every request, so they stay in view after compaction or context truncation.
+
+use crate::core::pins::PinnedMessage;
+use crate::core::text::truncate_end;
+
+use super::{App, ChatMessage};
+
+/// Width of the message preview in pin and unpin log lines.
+const PIN_PREVIEW_WIDTH: usize = 50;
+
+/// State of the `/pins` popup. The pins themselves are read from the app when drawn.
+pub struct PinsPopupState {
+    pub selected: usize,
+}
+
+impl PinsPopupState {
+    pub fn select_next(&mut self, count: usize) {
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+/// First line of `text`, shortened for a log line or the `/pins` list.
+pub(crate) fn pin_preview(text: &str, width: usize) -> String {
+    truncate_end(text.trim().lines().next().unwrap_or_default(), width)
+}
+
+impl App {
+    /// Pinned messages in conversation order, for the next request.
+    pub(crate) fn pinned_message_list(&self) -> Vec<PinnedMessage> {
+        self.pinned_messages
+            .iter()
+            .filter_map(|&idx| match self.messages.get(idx) {
+                Some(ChatMessage::User(text)) => Some(PinnedMessage {
+                    from_user: true,
+                    content: text.clone(),
+                }),
+                Some(ChatMessage::Assistant(text)) => Some(PinnedMessage {
+                    from_user: false,
+                    content: text.clone(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Pin the User or Assistant message at `msg_idx`, or unpin it when already pinned. The
+    /// outcome is shown as a tool log line.
+    pub(crate) fn toggle_message_pin(&mut self, msg_idx: usize) {
+        let Some(ChatMessage::User(text) | ChatMessage::Assistant(text)) =
+            self.messages.get(msg_idx)
+        else {
+            return;
+        };
+        let preview = pin_preview(text, PIN_PREVIEW_WIDTH);
+        // The reply in progress still changes (and moves when the turn ends).
+        let last_user = self
+            .messages
+            .iter()
+            .rposition(|m| matches!(m, ChatMessage::User(_)));
+        if self.is_streaming && last_user.is_some_and(|user| msg_idx > user) {
+            self.push_tool_log("Wait for the reply to finish before pinning it.".to_string());
+            return;
+        }
+        let line = if self.pinned_messages.remove(&msg_idx) {
+            format!("Unpinned message \"{}\"", preview)
+        } else {
+            self.pinned_messages.insert(msg_idx);
+            format!(
+                "Pinned message \"{}\" (sent with every request, /pins to manage)",
+                preview
+            )
+        };
+        self.push_tool_log(line);
+        self.mark_dirty();
+    }
+
+    /// Open the `/pins` popup, or say how to pin a message when none is pinned.
+    pub(crate) fn open_pins_popup(&mut self) {
+        if self.pinned_messages.is_empty() {
+            self.push_tool_log(
+                "No pinned messages. Hover a message (or scroll to it) and press Alt+P to pin it."
+                    .to_string(),
+            );
+            return;
+        }
+        self.pins_popup = Some(PinsPopupState { selected: 0 });
+    }
+}
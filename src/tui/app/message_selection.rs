@@ -0,0 +1,260 @@
+//! Keyboard message selection (Alt+V, or `v` while scrolled back): whole User and Assistant
+//! messages picked with ↑↓, Shift+↑↓ extending the range, then copied, pinned, branched
+//! from, or deleted.
+
+use std::ops::RangeInclusive;
+
+use super::{App, ChatMessage};
+
+/// Selected messages: from `anchor` to `cursor`, in either order (indexes in `App::messages`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageSelection {
+    pub anchor: usize,
+    pub cursor: usize,
+    /// `d` was pressed once: the next `d` deletes the selection.
+    pub confirm_delete: bool,
+}
+
+impl MessageSelection {
+    /// Indexes of the selected messages, first to last. Tool logs and results between two
+    /// selected messages belong to the selection.
+    pub fn range(&self) -> RangeInclusive<usize> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+}
+
+fn is_selectable(message: &ChatMessage) -> bool {
+    matches!(message, ChatMessage::User(_) | ChatMessage::Assistant(_))
+}
+
+impl App {
+    /// Start selecting at `from` when it is a User or Assistant message, else at the last one.
+    pub(crate) fn start_message_selection(&mut self, from: Option<usize>) {
+        let start = from
+            .filter(|&i| self.messages.get(i).is_some_and(is_selectable))
+            .or_else(|| self.messages.iter().rposition(is_selectable));
+        let Some(start) = start else {
+            self.push_tool_log("No messages to select yet.".to_string());
+            return;
+        };
+        self.unzoom();
+        self.message_selection = Some(MessageSelection {
+            anchor: start,
+            cursor: start,
+            confirm_delete: false,
+        });
+        self.scroll_to_message(start);
+    }
+
+    /// Move the cursor to the previous or next User or Assistant message. With `extend`, the
+    /// anchor stays and the range grows or shrinks; without, the selection is that message.
+    pub(crate) fn move_message_selection(&mut self, down: bool, extend: bool) {
+        let Some(selection) = self.message_selection.as_mut() else {
+            return;
+        };
+        let cursor = selection.cursor;
+        let next = if down {
+            self.messages
+                .iter()
+                .enumerate()
+                .skip(cursor + 1)
+                .find(|(_, m)| is_selectable(m))
+                .map(|(i, _)| i)
+        } else {
+            self.messages[..cursor.min(self.messages.len())]
+                .iter()
+                .rposition(is_selectable)
+        };
+        let cursor = next.unwrap_or(cursor);
+        selection.cursor = cursor;
+        if !extend {
+            selection.anchor = cursor;
+        }
+        selection.confirm_delete = false;
+        self.scroll_to_message(cursor);
+    }
+
+    /// Scroll so that the first line of message `msg_idx` is visible (layout of the last frame).
+    fn scroll_to_message(&mut self, msg_idx: usize) {
+        let visible = self
+            .history_area_rect
+            .map_or(1, |r| r.height.max(1) as usize);
+        if let Some(&(_, start, _)) = self
+            .message_line_ranges
+            .iter()
+            .find(|(idx, _, _)| *idx == msg_idx)
+        {
+            self.scroll_to_line(start, visible);
+        }
+    }
+
+    /// Text of the selected User and Assistant messages, separated by blank lines.
+    pub(crate) fn selected_messages_text(&self) -> Option<String> {
+        let range = self.message_selection?.range();
+        let texts: Vec<&str> = self.messages[range]
+            .iter()
+            .filter_map(|m| match m {
+                ChatMessage::User(text) | ChatMessage::Assistant(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        (!texts.is_empty()).then(|| texts.join("\n\n"))
+    }
+
+    /// Pin the selected messages, or unpin them all when they are all pinned already.
+    pub(crate) fn pin_selected_messages(&mut self) {
+        let Some(selection) = self.message_selection else {
+            return;
+        };
+        let selected: Vec<usize> = selection
+            .range()
+            .filter(|&i| self.messages.get(i).is_some_and(is_selectable))
+            .collect();
+        let all_pinned = selected.iter().all(|i| self.pinned_messages.contains(i));
+        for i in selected {
+            if all_pinned || !self.pinned_messages.contains(&i) {
+                self.toggle_message_pin(i);
+            }
+        }
+    }
+
+    /// Remove the selected messages (and the tool lines between them) from the conversation
+    /// and close the selection. Returns how many User and Assistant messages were removed.
+    pub(crate) fn delete_selected_messages(&mut self) -> usize {
+        let Some(selection) = self.message_selection.take() else {
+            return 0;
+        };
+        let range = selection.range();
+        let (first, last) = (*range.start(), *range.end());
+        if last >= self.messages.len() {
+            return 0;
+        }
+        let removed = self
+            .messages
+            .drain(range.clone())
+            .filter(is_selectable)
+            .count();
+        let timestamps_end = (last + 1).min(self.message_timestamps.len());
+        if first < timestamps_end {
+            self.message_timestamps.drain(first..timestamps_end);
+        }
+        let count = last - first + 1;
+        let shift = |i: usize| match i {
+            i if i < first => Some(i),
+            i if i > last => Some(i - count),
+            _ => None,
+        };
+        self.message_usage = std::mem::take(&mut self.message_usage)
+            .into_iter()
+            .filter_map(|(i, usage)| shift(i).map(|i| (i, usage)))
+            .collect();
+        self.pinned_messages = std::mem::take(&mut self.pinned_messages)
+            .into_iter()
+            .filter_map(shift)
+            .collect();
+        self.message_reactions = std::mem::take(&mut self.message_reactions)
+            .into_iter()
+            .filter_map(|(i, reaction)| shift(i).map(|i| (i, reaction)))
+            .collect();
+        self.hovered_message_idx = None;
+        self.focused_code = None;
+        self.selection = None;
+        self.selection_drag_start = None;
+        self.mark_dirty();
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::config::ModelSource;
+    use crate::core::history::Reaction;
+    use crate::core::workspace::Workspace;
+
+    fn app() -> App {
+        let root = PathBuf::from("/work/project");
+        let mut app = App::new(
+            "mock/model".to_string(),
+            "Mock Model".to_string(),
+            ModelSource::Default,
+            Workspace {
+                root: root.clone(),
+                working_dir: root,
+                project_type: None,
+                agent_md: None,
+                context_files: Vec::new(),
+                git_context: None,
+            },
+            false,
+            false,
+        );
+        app.messages = vec![
+            ChatMessage::User("one".to_string()),
+            ChatMessage::Assistant("two".to_string()),
+            ChatMessage::ToolLog("→ Read: a.rs".to_string()),
+            ChatMessage::User("three".to_string()),
+            ChatMessage::Assistant("four".to_string()),
+        ];
+        app.message_timestamps = vec![Some(1), Some(2), None, Some(4), Some(5)];
+        app
+    }
+
+    #[test]
+    fn selection_moves_over_messages_and_extends_with_shift() {
+        let mut app = app();
+        app.start_message_selection(None);
+        assert_eq!(app.selected_messages_text().as_deref(), Some("four"));
+        app.move_message_selection(false, false);
+        assert_eq!(app.selected_messages_text().as_deref(), Some("three"));
+        // The tool log between two messages is skipped by the cursor.
+        app.move_message_selection(false, true);
+        assert_eq!(app.message_selection.map(|s| s.range()), Some(1..=3));
+        assert_eq!(
+            app.selected_messages_text().as_deref(),
+            Some("two\n\nthree")
+        );
+        // Without Shift, the selection collapses to the message under the cursor.
+        app.move_message_selection(true, false);
+        assert_eq!(app.selected_messages_text().as_deref(), Some("three"));
+        app.move_message_selection(true, false);
+        app.move_message_selection(true, false);
+        assert_eq!(app.message_selection.map(|s| s.range()), Some(4..=4));
+    }
+
+    #[test]
+    fn pinning_a_range_pins_all_then_unpins_all() {
+        let mut app = app();
+        app.pinned_messages.insert(1);
+        app.start_message_selection(Some(0));
+        app.move_message_selection(true, true);
+        app.pin_selected_messages();
+        assert_eq!(
+            app.pinned_messages.iter().copied().collect::<Vec<_>>(),
+            [0, 1]
+        );
+        app.pin_selected_messages();
+        assert!(app.pinned_messages.is_empty());
+    }
+
+    #[test]
+    fn deleting_shifts_the_state_of_later_messages() {
+        let mut app = app();
+        app.pinned_messages.insert(4);
+        app.message_reactions.insert(4, Reaction::Good);
+        app.message_reactions.insert(1, Reaction::Bad);
+        app.start_message_selection(Some(1));
+        app.move_message_selection(true, true);
+        assert_eq!(app.delete_selected_messages(), 2);
+        assert!(app.message_selection.is_none());
+        assert_eq!(app.messages.len(), 2);
+        assert!(matches!(&app.messages[1], ChatMessage::Assistant(s) if s == "four"));
+        assert_eq!(app.message_timestamps, [Some(1), Some(5)]);
+        assert!(app.pinned_messages.contains(&1));
+        assert_eq!(app.message_reactions.len(), 1);
+        assert_eq!(app.message_reactions.get(&1), Some(&Reaction::Good));
+        assert!(app.is_dirty());
+    }
+}
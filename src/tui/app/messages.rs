@@ -1,11 +1,15 @@
 //! Message handling for the chat history.
 
 use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::core::history::{CONTEXT_RESET_ROLE, CONTEXT_SUMMARY_ROLE, Reaction};
+use crate::core::llm::{ChatError, MessageUsage};
 use crate::core::message;
+use crate::core::tools::ToolView;
 
-use super::{App, ChatMessage};
+use super::{App, ChatMessage, ErrorNotice};
 
 fn unix_timestamp_secs() -> u64 {
     SystemTime::now()
@@ -15,7 +19,8 @@ fn unix_timestamp_secs() -> u64 {
 }
 
 impl App {
-    /// Populate messages from persisted format (user, assistant, tool_log).
+    /// Populate messages from persisted format (user, assistant, tool_log, context_reset,
+    /// context_summary).
     /// Malformed messages (e.g. unsupported content types) are surfaced as
     /// "[Unsupported message format]" with a log warning instead of silently omitted.
     /// If `fallback_timestamp` is set, it is used for messages that lack a timestamp (old format).
@@ -26,6 +31,10 @@ impl App {
     ) {
         self.messages.clear();
         self.message_timestamps.clear();
+        self.message_usage.clear();
+        self.pinned_messages.clear();
+        self.message_reactions.clear();
+        self.message_selection = None;
         for msg in api_messages {
             let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("");
             match role {
@@ -59,10 +68,22 @@ impl App {
                         .get("timestamp")
                         .and_then(|t| t.as_u64())
                         .or(fallback_timestamp);
+                    if msg.get("pinned").and_then(|p| p.as_bool()) == Some(true) {
+                        self.pinned_messages.insert(self.messages.len());
+                    }
                     if role == "user" {
                         self.messages.push(ChatMessage::User(content));
                         self.message_timestamps.push(timestamp);
                     } else {
+                        if let Some(usage) = msg
+                            .get("usage")
+                            .and_then(|u| serde_json::from_value(u.clone()).ok())
+                        {
+                            self.message_usage.insert(self.messages.len(), usage);
+                        }
+                        if let Some(reaction) = Reaction::of_message(msg) {
+                            self.message_reactions.insert(self.messages.len(), reaction);
+                        }
                         self.messages.push(ChatMessage::Assistant(content));
                         self.message_timestamps.push(timestamp);
                     }
@@ -76,16 +97,51 @@ impl App {
                     self.messages.push(ChatMessage::ToolLog(content));
                     self.message_timestamps.push(None);
                 }
+                "tool_result" => {
+                    // Views saved by a newer version may not parse: skip them.
+                    if let Some(view) = msg
+                        .get("view")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    {
+                        let tool = msg.get("tool").and_then(|t| t.as_str()).unwrap_or("");
+                        self.messages.push(ChatMessage::ToolResult {
+                            tool: tool.to_string(),
+                            view,
+                            expanded: false,
+                        });
+                        self.message_timestamps.push(None);
+                    }
+                }
+                CONTEXT_RESET_ROLE => {
+                    self.messages.push(ChatMessage::ContextCleared);
+                    self.message_timestamps.push(None);
+                }
+                CONTEXT_SUMMARY_ROLE => {
+                    self.messages.push(ChatMessage::ContextCompacted {
+                        summary: msg
+                            .get("content")
+                            .and_then(|c| c.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        kept_turns: msg.get("kept_turns").and_then(|k| k.as_u64()).unwrap_or(0)
+                            as usize,
+                    });
+                    self.message_timestamps.push(None);
+                }
                 _ => {}
             }
         }
     }
 
     /// Serialize app messages to persistence format (user, assistant, tool_log).
-    /// Used when saving; preserves ToolLog and timestamps for display when re-opening.
+    /// Used when saving; preserves ToolLog, timestamps, reply usage, pins, and reactions for
+    /// display when re-opening.
     pub(crate) fn messages_to_persist_format(
         msgs: &[ChatMessage],
         timestamps: &[Option<u64>],
+        usage: &HashMap<usize, MessageUsage>,
+        pinned: &BTreeSet<usize>,
+        reactions: &HashMap<usize, Reaction>,
     ) -> Vec<Value> {
         msgs.iter()
             .enumerate()
@@ -97,6 +153,9 @@ impl App {
                         if let Some(t) = ts {
                             v["timestamp"] = serde_json::json!(t);
                         }
+                        if pinned.contains(&i) {
+                            v["pinned"] = serde_json::json!(true);
+                        }
                         Some(v)
                     }
                     ChatMessage::Assistant(s) => {
@@ -104,11 +163,47 @@ impl App {
                         if let Some(t) = ts {
                             v["timestamp"] = serde_json::json!(t);
                         }
+                        if let Some(u) = usage.get(&i) {
+                            v["usage"] = serde_json::json!(u);
+                        }
+                        if pinned.contains(&i) {
+                            v["pinned"] = serde_json::json!(true);
+                        }
+                        if let Some(reaction) = reactions.get(&i) {
+                            v["reaction"] = serde_json::json!(reaction);
+                        }
                         Some(v)
                     }
                     ChatMessage::ToolLog(s) => {
                         Some(serde_json::json!({"role": "tool_log", "content": s}))
                     }
+                    ChatMessage::ToolResult { tool, view, .. } => Some(serde_json::json!({
+                        "role": "tool_result",
+                        "tool": tool,
+                        "view": view,
+                    })),
+                    ChatMessage::ContextCleared => {
+                        Some(serde_json::json!({"role": CONTEXT_RESET_ROLE}))
+                    }
+                    ChatMessage::ContextCompacted {
+                        summary,
+                        kept_turns,
+                    } => Some(serde_json::json!({
+                        "role": CONTEXT_SUMMARY_ROLE,
+                        "content": summary,
+                        "kept_turns": kept_turns,
+                    })),
+                    // Saved as an error reply, drawn in red when reopened.
+                    ChatMessage::Error(notice) => {
+                        let mut v = serde_json::json!({
+                            "role": "assistant",
+                            "content": format!("Error: {}", notice.message)
+                        });
+                        if let Some(t) = ts {
+                            v["timestamp"] = serde_json::json!(t);
+                        }
+                        Some(v)
+                    }
                     ChatMessage::Thinking => None,
                 }
             })
@@ -159,6 +254,80 @@ impl App {
         }
     }
 
+    /// Replace the last Assistant message (placeholder or partial reply) with the error block
+    /// of a failed request.
+    pub(crate) fn push_error(&mut self, error: &ChatError) {
+        if matches!(self.messages.last(), Some(ChatMessage::Assistant(_))) {
+            self.messages.pop();
+            self.message_timestamps.pop();
+        }
+        self.messages
+            .push(ChatMessage::Error(ErrorNotice::new(error)));
+        self.message_timestamps.push(Some(unix_timestamp_secs()));
+    }
+
+    /// Record the usage of the reply in the last Assistant message.
+    pub(crate) fn set_last_assistant_usage(&mut self, usage: MessageUsage) {
+        if let Some(idx) = self
+            .messages
+            .iter()
+            .rposition(|m| matches!(m, ChatMessage::Assistant(_)))
+        {
+            self.message_usage.insert(idx, usage);
+        }
+    }
+
+    /// Mark the point where the model context was cleared (no-op when there is nothing
+    /// before it). The caller drops the API messages.
+    pub(crate) fn clear_context(&mut self) {
+        if matches!(
+            self.messages.last(),
+            None | Some(ChatMessage::ContextCleared)
+        ) {
+            return;
+        }
+        self.messages.push(ChatMessage::ContextCleared);
+        self.message_timestamps.push(None);
+        self.token_usage = None;
+        self.tool_queue.clear();
+        self.mark_dirty();
+    }
+
+    pub(crate) fn push_tool_result(&mut self, tool: String, view: ToolView) {
+        self.remove_last_if_empty_assistant();
+        self.messages.push(ChatMessage::ToolResult {
+            tool,
+            view,
+            expanded: false,
+        });
+        self.message_timestamps.push(None);
+    }
+
+    /// Expand or collapse the tool result at `idx`.
+    pub(crate) fn toggle_tool_result(&mut self, idx: usize) {
+        if let Some(ChatMessage::ToolResult { expanded, .. }) = self.messages.get_mut(idx) {
+            *expanded = !*expanded;
+        }
+    }
+
+    /// Expand every tool result, or collapse them all when they already are (Ctrl+O).
+    pub(crate) fn toggle_all_tool_results(&mut self) {
+        let expand = self.messages.iter().any(|m| {
+            matches!(
+                m,
+                ChatMessage::ToolResult {
+                    expanded: false,
+                    ..
+                }
+            )
+        });
+        for message in &mut self.messages {
+            if let ChatMessage::ToolResult { expanded, .. } = message {
+                *expanded = expand;
+            }
+        }
+    }
+
     pub(crate) fn push_tool_log(&mut self, line: String) {
         self.messages.push(ChatMessage::ToolLog(line));
         self.message_timestamps.push(None);
@@ -181,6 +350,32 @@ impl App {
         }
     }
 
+    /// Remove the last user message and everything after it (its reply and tool logs).
+    /// Returns the removed user text; None when there is no user message since the last
+    /// context reset or compaction.
+    pub(crate) fn truncate_from_last_user(&mut self) -> Option<String> {
+        let idx = self.messages.iter().rposition(|m| {
+            matches!(
+                m,
+                ChatMessage::User(_)
+                    | ChatMessage::ContextCleared
+                    | ChatMessage::ContextCompacted { .. }
+            )
+        })?;
+        let text = match &self.messages[idx] {
+            ChatMessage::User(s) => s.clone(),
+            _ => return None,
+        };
+        self.messages.truncate(idx);
+        self.message_timestamps.truncate(idx);
+        self.message_usage.retain(|&i, _| i < idx);
+        self.pinned_messages.retain(|&i| i < idx);
+        self.message_reactions.retain(|&i, _| i < idx);
+        self.tool_queue.clear();
+        self.mark_dirty();
+        Some(text)
+    }
+
     /// Append "[cancelled]" to the last assistant message (or create one).
     /// Keeps whatever partial content was already streamed.
     pub(crate) fn append_cancelled_notice(&mut self) {
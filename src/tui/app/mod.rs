@@ -1,16 +1,82 @@
 //! TUI application state: messages, input, scroll, suggestions.
 
+mod code_view;
+mod command_palette;
+mod compact;
+mod compose;
+mod conversation_settings;
+mod copy_mode;
+mod explain_error;
+mod file_tree;
+mod generation_params;
+mod message_pins;
+mod message_selection;
 mod messages;
+mod model_selector;
+mod permissions;
+mod profiles;
+mod prompt_queue;
+mod reactions;
+mod save_last;
+mod search;
+mod side_pane;
+mod spend_cap;
+mod suggestions;
+mod tool_queue;
+mod turn_status;
+mod ui_state;
+
+pub(crate) use code_view::visible_columns;
+pub use code_view::{CodeBlockId, CodeBlockRegion};
+pub use command_palette::{CommandPaletteState, PaletteAction};
+pub use compact::CompactRequest;
+pub use compose::EditorRequest;
+pub use copy_mode::{CodeSnippet, CopyModeState};
+pub use file_tree::FileTreeState;
+pub use generation_params::{ParamsField, ParamsPopupState};
+pub use message_pins::PinsPopupState;
+pub(crate) use message_pins::pin_preview;
+pub use message_selection::MessageSelection;
+pub use model_selector::ModelSection;
+pub use permissions::PermissionsPopupState;
+pub use profiles::ProfilePickerState;
+pub use prompt_queue::QueuedPrompt;
+pub use save_last::SaveLastState;
+pub(crate) use search::find_matches;
+pub use search::{SearchState, TextLine};
+pub use side_pane::{PaneFile, SidePane, ToolFileReader, ToolUpdate};
+pub use suggestions::SuggestionReviewState;
+pub use tool_queue::{ToolQueueEntry, ToolStatus};
+pub use turn_status::{Activity, TurnStatus};
 
 use crate::core::commands::ResolvedCommand;
-use crate::core::history::ConversationMeta;
-use crate::core::llm::{ConfirmState, TokenUsage, undo};
+use crate::core::config::{Config, ModelSource};
+use crate::core::confirm::PendingAction;
+use crate::core::diff::{self, FileDiff};
+use crate::core::generation::GenerationParams;
+use crate::core::git_commit::{self, StagedChanges};
+use crate::core::history::{self, ConversationMeta, ConversationOrigin, Reaction};
+use crate::core::llm::{
+    self, ChatError, ConfirmState, ContextPreview, MessageUsage, NextAction, TokenUsage, ToolModes,
+    undo,
+};
 use crate::core::models::ModelInfo;
+use crate::core::persistence::ModelPrefs;
+use crate::core::pins::{self, PinnedFile};
+use crate::core::share::SharedConversation;
+use crate::core::spend::{LimitHit, SpendStatus};
+use crate::core::stats::StatsReport;
 use crate::core::templates::CustomTemplate;
+use crate::core::tools::ToolView;
 use crate::core::workspace::Workspace;
+use crate::tui::hyperlinks::{LinkCache, LinkRect};
+use crate::tui::paste::{self, Attachment, AttachmentKind};
+use crate::tui::preview::Preview;
 use ratatui::layout::Rect;
 use ratatui::widgets::ListState;
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::PathBuf;
 use std::time::Instant;
 
 /// Messages displayed in the history (user or assistant).
@@ -21,12 +87,147 @@ pub enum ChatMessage {
     Thinking,
     /// Tool call log line for verbose output.
     ToolLog(String),
+    /// Typed result of a tool call (tree, matches, table), collapsed to its summary until
+    /// expanded (click, Ctrl+O).
+    ToolResult {
+        tool: String,
+        view: ToolView,
+        expanded: bool,
+    },
+    /// Divider left by /clear-context: earlier messages stay visible but are not sent.
+    ContextCleared,
+    /// Divider left by /compact: earlier messages stay visible; the model gets `summary` of
+    /// them and the last `kept_turns` user turns instead.
+    ContextCompacted {
+        summary: String,
+        kept_turns: usize,
+    },
+    /// Failed request, drawn as an error block with suggested next actions.
+    Error(ErrorNotice),
+}
+
+/// Error of a failed request as shown in the history.
+#[derive(Clone)]
+pub struct ErrorNotice {
+    pub message: String,
+    pub hint: Option<String>,
+    pub actions: &'static [NextAction],
+}
+
+impl ErrorNotice {
+    pub fn new(error: &ChatError) -> Self {
+        ErrorNotice {
+            message: error.to_string(),
+            hint: error.hint(),
+            actions: error.next_actions(),
+        }
+    }
 }
 
-/// Pending confirmation for a destructive command (popup displayed).
+/// Pending confirmation for a destructive command or a write outside the workspace
+/// (popup displayed).
 pub struct ConfirmPopup {
-    pub command: String,
+    pub action: PendingAction,
     pub state: ConfirmState,
+    /// Change a Write or Edit would make, shown in the diff viewer.
+    pub diff: Option<DiffViewerState>,
+}
+
+impl ConfirmPopup {
+    pub fn new(action: PendingAction, state: ConfirmState) -> Self {
+        let diff = match &action {
+            PendingAction::OutsideWorkspace { tool, path, args } => crate::core::tools::all()
+                .iter()
+                .find(|t| t.name() == tool)
+                .and_then(|t| t.proposed_change(args, state.working_dir()))
+                .map(|(old, new)| {
+                    DiffViewerState::new(
+                        format!("{}: {} {}", action.title(), tool, path),
+                        vec![diff::between(path, &old, &new)],
+                    )
+                }),
+            PendingAction::Command(_) => None,
+        };
+        ConfirmPopup {
+            action,
+            state,
+            diff,
+        }
+    }
+}
+
+/// Diff viewer (/review results, Write/Edit approval): one file of the diff at a time.
+pub struct DiffViewerState {
+    pub title: String,
+    pub files: Vec<FileDiff>,
+    pub file_index: usize,
+    /// Vertical scroll offset (lines) of the current file.
+    pub scroll: u16,
+}
+
+impl DiffViewerState {
+    pub fn new(title: String, files: Vec<FileDiff>) -> Self {
+        DiffViewerState {
+            title,
+            files,
+            file_index: 0,
+            scroll: 0,
+        }
+    }
+
+    pub fn next_file(&mut self) {
+        if self.file_index + 1 < self.files.len() {
+            self.file_index += 1;
+            self.scroll = 0;
+        }
+    }
+
+    pub fn prev_file(&mut self) {
+        if self.file_index > 0 {
+            self.file_index -= 1;
+            self.scroll = 0;
+        }
+    }
+}
+
+/// Entry of the tab bar, shown under the header when several tabs are open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TabLabel {
+    pub title: String,
+    pub active: bool,
+    /// A turn is in flight.
+    pub busy: bool,
+    /// The turn waits for a confirmation in the tab.
+    pub needs_confirmation: bool,
+    /// A turn ended while another tab was shown.
+    pub unseen: bool,
+}
+
+/// Popup opened when the turn of a slash command ends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TurnFollowUp {
+    /// /commit: the drafted message opens the commit popup.
+    Commit,
+    /// /review: the reviewed changes open in the diff viewer (`scope` as typed after /review).
+    Review { scope: Option<String> },
+}
+
+/// Playback state of `replay`, shown in the bottom bar instead of the shortcuts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStatus {
+    /// Messages advance on key press instead of with timing.
+    pub step: bool,
+    pub paused: bool,
+    pub finished: bool,
+}
+
+/// First exchange of a newly saved conversation, sent to the title model.
+pub struct TitleRequest {
+    pub conversation_id: String,
+    /// Title it was saved with; a title changed meanwhile (renamed) is not replaced.
+    pub saved_title: String,
+    pub prompt: String,
+    pub answer: String,
 }
 
 /// State for the model selector popup.
@@ -39,6 +240,8 @@ pub struct ModelSelectorState {
     pub filter: String,
     /// When the model fetch started; used for loading spinner animation.
     pub(crate) fetch_started_at: Option<Instant>,
+    /// Favorite and recent models, listed first.
+    pub prefs: ModelPrefs,
 }
 
 /// Which field is focused in the command form.
@@ -48,6 +251,7 @@ pub enum CommandFormField {
     Description,
     Prompt,
     Mode,
+    Model,
 }
 
 /// Phase of the command form popup.
@@ -64,6 +268,8 @@ pub struct CommandFormState {
     pub description: String,
     pub prompt_prefix: String,
     pub llm_mode: String,
+    /// Optional model ID for the command (blank = current model).
+    pub model: String,
     pub focused_field: CommandFormField,
     pub error: Option<String>,
     pub phase: CommandFormPhase,
@@ -85,6 +291,40 @@ pub struct DeleteCommandState {
     pub selected: Vec<bool>,
 }
 
+/// State for the context preview popup (/preview).
+pub struct ContextPreviewState {
+    pub preview: ContextPreview,
+    /// Expanded flag per system prompt section (parallel to preview.sections).
+    pub expanded: Vec<bool>,
+    pub selected_index: usize,
+    /// Vertical scroll offset (lines) of the popup body.
+    pub scroll: u16,
+}
+
+/// State for the duplicate conversation popup: a saved conversation starts like this one.
+pub struct DuplicatePopupState {
+    pub existing: ConversationMeta,
+}
+
+/// Model pinned by a slash command, used instead of the current model for one turn.
+#[derive(Clone, Debug)]
+pub struct CommandModel {
+    pub id: String,
+    /// Display name (from the models cache, else the ID).
+    pub name: String,
+    /// Command that pinned the model (without slash).
+    pub command: String,
+}
+
+/// State for the commit popup (/commit): staged changes and the editable drafted message.
+pub struct CommitPopupState {
+    pub changes: StagedChanges,
+    pub message: String,
+    /// The Bash tool flagged the commit command; the next Enter runs it.
+    pub confirming: bool,
+    pub error: Option<String>,
+}
+
 /// Target for copy-on-click: either the full message or a code block's content.
 #[derive(Clone)]
 pub enum CopyTarget {
@@ -106,6 +346,9 @@ pub struct HistorySelectorState {
     pub error: Option<String>,
     /// Conversation ID -> concatenated message content for full-text search.
     pub content_cache: HashMap<String, String>,
+    /// Conversation ID -> workspace, branch, and environment it was saved from, for the
+    /// detail pane.
+    pub origins: HashMap<String, ConversationOrigin>,
 }
 
 /// Scroll position: either a specific line index, or "at bottom" (follow new content).
@@ -121,33 +364,68 @@ impl Default for ScrollPosition {
     }
 }
 
+/// Zoomed message (Alt+Z): the history shows only this message, with its own scroll.
+#[derive(Clone, Copy)]
+pub struct ZoomState {
+    pub msg_idx: usize,
+    /// History scroll to restore when leaving the zoom.
+    pub saved_scroll: ScrollPosition,
+}
+
 pub struct App {
     pub(crate) messages: Vec<ChatMessage>,
     /// User input in the text field.
     pub(crate) input: String,
     /// Cursor position in the input (byte index; used for Left/Right, insert, Backspace).
     pub(crate) input_cursor: usize,
+    /// Collapsed pastes and images referenced by placeholders in the input.
+    pub(crate) attachments: Vec<Attachment>,
     pub(crate) scroll: ScrollPosition,
     pub(crate) last_max_scroll: usize,
     /// Index of the selected suggestion (Tab to cycle).
     pub selected_suggestion: usize,
     /// Index of the selected slash command in the autocomplete list (when input starts with /).
     pub selected_command_index: usize,
+    /// Files whose current content is sent with every request (/pin, @mention).
+    pub(crate) pinned_files: Vec<PinnedFile>,
+    /// Indices of the User and Assistant messages quoted in every request (Alt+P, /pins).
+    pub(crate) pinned_messages: BTreeSet<usize>,
+    /// Reactions to assistant replies, by message index (Alt+A).
+    pub(crate) message_reactions: HashMap<usize, Reaction>,
+    /// Workspace files for the @mention picker; listed when a mention starts.
+    pub(crate) mention_files: Option<Vec<String>>,
+    /// Index of the selected file in the @mention picker.
+    pub(crate) selected_mention_index: usize,
     /// Mode to use when sending; set when user selects a slash command and inserts its template.
     pub(crate) pending_command_mode: Option<String>,
+    /// Model to use when sending; set when the selected slash command pins a model.
+    pub(crate) pending_command_model: Option<CommandModel>,
+    /// Model pinned by the slash command of the in-flight turn (kept across confirmations).
+    pub(crate) turn_model: Option<CommandModel>,
+    /// Output schema to use when sending; set when the selected slash command has one.
+    pub(crate) pending_output_schema: Option<Value>,
+    /// Output schema of the in-flight turn (see `core::llm::structured`).
+    pub(crate) turn_output_schema: Option<Value>,
     /// When set, show confirmation popup and ignore normal input until y/n.
     pub confirm_popup: Option<ConfirmPopup>,
     /// Model ID displayed in the header and used for chat (e.g. "anthropic/claude-haiku-4.5").
     pub model_name: String,
     /// Same as model_name; used for API calls.
     pub current_model_id: String,
+    /// Where current_model_id came from (shown in the header).
+    pub model_source: ModelSource,
     /// When set, show model selector popup (Alt+M).
     pub model_selector: Option<ModelSelectorState>,
     /// When set, show history selector popup (Alt+H).
     pub history_selector: Option<HistorySelectorState>,
     /// Content width from last draw; used to compute scroll-to-start when adding new messages.
     pub(crate) last_content_width: Option<usize>,
-    /// Credit balance: (total_credits, total_usage). Fetched on startup, refreshed every 30 min.
+    /// Models are served locally (Ollama): no credits to fetch or show.
+    pub(crate) local_models: bool,
+    /// Dry-run and suggest modes of this tab's conversation (`/dryrun`, `/suggest`).
+    pub(crate) tool_modes: ToolModes,
+    /// Credit balance: (total_credits, total_usage). Fetched on startup, refreshed every 30 min
+    /// and when the terminal regains focus after a long time away.
     pub(crate) credit_data: Option<(f64, f64)>,
     /// Rect of credits widget in header; used for click detection and hover.
     pub(crate) credits_header_rect: Option<Rect>,
@@ -157,12 +435,20 @@ pub struct App {
     pub(crate) credits_fetch_error: Option<String>,
     /// Mouse is over credits area; used for cursor style.
     pub(crate) hovering_credits: bool,
+    /// Tabs of the TUI, set before each draw (empty with a single tab).
+    pub(crate) tab_labels: Vec<TabLabel>,
+    /// When the terminal lost focus (None while focused or when focus events are unsupported).
+    pub(crate) unfocused_since: Option<Instant>,
     /// (msg_idx, start_line, end_line) for each User/Assistant; updated each draw.
     pub(crate) message_line_ranges: Vec<(usize, usize, usize)>,
     /// Unix timestamps (seconds) for each message; parallel to messages. None when loading from history.
     pub(crate) message_timestamps: Vec<Option<u64>>,
     /// Whether to show timestamps next to message labels (from MY_OPEN_CLAUDE_SHOW_TIMESTAMPS).
     pub(crate) show_timestamps: bool,
+    /// Token usage and latency of completed assistant replies, by message index.
+    pub(crate) message_usage: HashMap<usize, MessageUsage>,
+    /// Whether to annotate assistant replies with their usage (from MY_OPEN_CLAUDE_SHOW_USAGE).
+    pub(crate) show_usage: bool,
     /// Rect of history text area; for click hit testing.
     pub(crate) history_area_rect: Option<Rect>,
     /// Mouse is over a message block; used for cursor style.
@@ -171,6 +457,16 @@ pub struct App {
     pub(crate) hovered_message_idx: Option<usize>,
     /// Copy regions (start_line, end_line, target) for click-to-copy. Code blocks first, then message fallback.
     pub(crate) copy_regions: Vec<(usize, usize, CopyTarget)>,
+    /// Summary lines of tool results (line, message index); clicking one expands or collapses it.
+    pub(crate) tool_result_headers: Vec<(usize, usize)>,
+    /// Action lines of error blocks (line, message index); clicking one explains the error.
+    pub(crate) error_action_lines: Vec<(usize, usize)>,
+    /// Header lines of Mermaid and math blocks; clicking one opens the block in the browser.
+    pub(crate) preview_headers: Vec<(usize, Preview)>,
+    /// Links of the visible replies (screen rects); clicking one opens it.
+    pub(crate) link_rects: Vec<LinkRect>,
+    /// Links found in each reply, kept until the reply changes.
+    pub(crate) link_cache: LinkCache,
     /// Text selection for copy: (start_line, start_col, end_line, end_col) in buffer coordinates.
     pub(crate) selection: Option<(usize, usize, usize, usize)>,
     /// Mouse drag start position; used to distinguish click vs drag.
@@ -181,6 +477,8 @@ pub struct App {
     pub(crate) copy_toast_until: Option<Instant>,
     /// When set, show "Save failed" toast until this instant.
     pub(crate) save_error_toast_until: Option<Instant>,
+    /// When set, show the "history in use by another instance" toast until this instant.
+    pub(crate) history_busy_toast_until: Option<Instant>,
     /// Current conversation ID; None = new unsaved conversation.
     pub(crate) current_conversation_id: Option<String>,
     /// True if content has changed since last save.
@@ -205,16 +503,105 @@ pub struct App {
     pub command_form_popup: Option<CommandFormState>,
     /// Delete command popup.
     pub delete_command_popup: Option<DeleteCommandState>,
+    /// Context preview popup (/preview).
+    pub context_preview_popup: Option<ContextPreviewState>,
+    /// Duplicate conversation popup, shown instead of the first save of a near-duplicate.
+    pub duplicate_popup: Option<DuplicatePopupState>,
+    /// The first save of this conversation was already checked for near-duplicates.
+    pub(crate) duplicate_checked: bool,
+    /// Zoomed message (Alt+Z), hiding the rest of the history.
+    pub(crate) zoom: Option<ZoomState>,
+    /// Messages selected with the keyboard (Alt+V, `v` while scrolled back).
+    pub(crate) message_selection: Option<MessageSelection>,
+    /// Trace debug overlay (F12): recent API calls, retries, and tool timings.
+    pub debug_overlay: bool,
+    /// Usage statistics screen (F9), loaded when opened.
+    pub stats_screen: Option<StatsReport>,
+    /// Commit popup (/commit), opened when the drafted message arrives.
+    pub commit_popup: Option<CommitPopupState>,
+    /// Diff viewer of the changes reviewed by /review, opened when its turn ends.
+    pub diff_viewer: Option<DiffViewerState>,
+    /// Review of the edits suggested in suggest mode, opened when their turn ends.
+    pub suggestion_review: Option<SuggestionReviewState>,
+    /// /commit or /review was selected; the next message sent starts that turn.
+    pub(crate) pending_follow_up: Option<TurnFollowUp>,
+    /// Popup opened when the in-flight turn ends (commit popup, diff viewer).
+    pub(crate) turn_follow_up: Option<TurnFollowUp>,
     /// Shared undo stack for reverting file modifications made by Write/Edit tools.
     pub undo_stack: undo::SharedUndoStack,
+    /// Tool calls of the current model response (queue widget while streaming).
+    pub(crate) tool_queue: Vec<ToolQueueEntry>,
+    /// Activity of the request in flight (status line above the input).
+    pub(crate) turn_status: Option<TurnStatus>,
+    /// Prompts sent while a request was in flight, started in order as turns finish.
+    pub(crate) prompt_queue: VecDeque<QueuedPrompt>,
+    /// Queued prompts wait until this instant after a rate-limited request.
+    pub(crate) queue_held_until: Option<Instant>,
+    /// The 80% spend warning was shown (reset once spend is back under it).
+    pub(crate) spend_warned: bool,
+    /// Spend against the caps, shown by the header credits; updated before each request
+    /// and after each turn.
+    pub(crate) spend_status: SpendStatus,
+    /// A spend cap was reached: the popup asks whether to continue past it.
+    pub(crate) spend_popup: Option<LimitHit>,
+    /// A conversation saved for the first time, waiting for a generated title.
+    pub(crate) title_request: Option<TitleRequest>,
+    /// Set while replaying a saved conversation (no input, no API calls).
+    pub(crate) replay: Option<ReplayStatus>,
+    /// `/share` with a gist token: the redacted conversation waiting to be uploaded.
+    pub(crate) share_request: Option<SharedConversation>,
+    /// `/compact`: the API messages waiting to be summarized.
+    pub(crate) compact_request: Option<CompactRequest>,
+    /// A compaction is in progress (requested or running).
+    pub(crate) compacting: bool,
+    /// Generation parameters popup (/params).
+    pub params_popup: Option<ParamsPopupState>,
+    /// Generation parameters set with /params for this session, by model ID. They replace
+    /// the model's saved parameters.
+    pub(crate) session_params: HashMap<String, GenerationParams>,
+    /// Bash command rules popup (/permissions).
+    pub permissions_popup: Option<PermissionsPopupState>,
+    /// Pinned messages popup (/pins).
+    pub pins_popup: Option<PinsPopupState>,
+    /// Code block list popup (/copy).
+    pub copy_mode: Option<CopyModeState>,
+    /// Profile switcher popup (/profile).
+    pub profile_picker: Option<ProfilePickerState>,
+    /// Profile to switch to (None inside: environment settings only); applied by the main
+    /// loop between turns.
+    pub(crate) profile_request: Option<Option<String>>,
+    /// Search within the conversation (Ctrl+F, or `/` while scrolled back).
+    pub search: Option<SearchState>,
+    /// Split layout (Alt+S): the history on the left, the side pane on the right.
+    pub split_pane: bool,
+    /// Code blocks show one row per line, truncated, instead of soft-wrapping (Alt+W).
+    pub(crate) truncate_code: bool,
+    /// Code block scrolled by Left/Right when code is truncated.
+    pub(crate) focused_code: Option<CodeBlockId>,
+    /// First column shown of the focused code block.
+    pub(crate) code_scroll: usize,
+    /// Code blocks of the last drawn history.
+    pub(crate) code_blocks: Vec<CodeBlockRegion>,
+    /// Last file touched by a tool and the task plan, shown in the split layout.
+    pub side_pane: SidePane,
+    /// Workspace file tree popup (Alt+F).
+    pub file_tree: Option<FileTreeState>,
+    /// Command palette popup (Ctrl+K).
+    pub command_palette: Option<CommandPaletteState>,
+    /// Confirmation of a `/save-last` write.
+    pub save_last_popup: Option<SaveLastState>,
+    /// File or input to open in `$EDITOR`; the main loop suspends the TUI while it runs.
+    pub(crate) editor_request: Option<EditorRequest>,
 }
 
 impl App {
     pub fn new(
         model_id: String,
         model_name: String,
+        model_source: ModelSource,
         workspace: Workspace,
         show_timestamps: bool,
+        show_usage: bool,
     ) -> Self {
         let context_length = crate::core::models::resolve_context_length(&model_id);
 
@@ -242,34 +629,57 @@ impl App {
             messages: vec![],
             input: String::new(),
             input_cursor: 0,
+            attachments: Vec::new(),
             scroll: ScrollPosition::default(),
             last_max_scroll: 0,
             selected_suggestion: 0,
             selected_command_index: 0,
+            pinned_files: Vec::new(),
+            pinned_messages: BTreeSet::new(),
+            message_reactions: HashMap::new(),
+            mention_files: None,
+            selected_mention_index: 0,
             pending_command_mode: None,
+            pending_command_model: None,
+            turn_model: None,
+            pending_output_schema: None,
+            turn_output_schema: None,
             confirm_popup: None,
             model_name,
             current_model_id: model_id,
+            model_source,
             model_selector: None,
             history_selector: None,
             last_content_width: None,
             credit_data: None,
+            local_models: false,
+            tool_modes: ToolModes::default(),
             credits_header_rect: None,
             credits_last_fetched_at: None,
             credits_fetch_error: None,
             hovering_credits: false,
+            tab_labels: Vec::new(),
+            unfocused_since: None,
             message_line_ranges: vec![],
             message_timestamps: vec![],
             show_timestamps,
+            message_usage: HashMap::new(),
+            show_usage,
             history_area_rect: None,
             hovering_message_block: false,
             hovered_message_idx: None,
             copy_regions: vec![],
+            tool_result_headers: vec![],
+            error_action_lines: vec![],
+            preview_headers: vec![],
+            link_rects: vec![],
+            link_cache: LinkCache::default(),
             selection: None,
             selection_drag_start: None,
             rendered_lines: vec![],
             copy_toast_until: None,
             save_error_toast_until: None,
+            history_busy_toast_until: None,
             current_conversation_id: None,
             dirty: false,
             escape_pending: false,
@@ -282,7 +692,49 @@ impl App {
             templates_load_error,
             command_form_popup: None,
             delete_command_popup: None,
+            context_preview_popup: None,
+            duplicate_popup: None,
+            duplicate_checked: false,
+            zoom: None,
+            message_selection: None,
+            debug_overlay: false,
+            stats_screen: None,
+            commit_popup: None,
+            diff_viewer: None,
+            suggestion_review: None,
+            pending_follow_up: None,
+            turn_follow_up: None,
             undo_stack: undo::new_shared(),
+            tool_queue: vec![],
+            turn_status: None,
+            prompt_queue: VecDeque::new(),
+            queue_held_until: None,
+            spend_warned: false,
+            spend_status: SpendStatus::Ok,
+            spend_popup: None,
+            title_request: None,
+            replay: None,
+            share_request: None,
+            compact_request: None,
+            compacting: false,
+            params_popup: None,
+            session_params: HashMap::new(),
+            permissions_popup: None,
+            pins_popup: None,
+            copy_mode: None,
+            profile_picker: None,
+            profile_request: None,
+            search: None,
+            split_pane: false,
+            truncate_code: false,
+            focused_code: None,
+            code_scroll: 0,
+            code_blocks: Vec::new(),
+            side_pane: SidePane::default(),
+            file_tree: None,
+            command_palette: None,
+            save_last_popup: None,
+            editor_request: None,
         }
     }
 
@@ -293,6 +745,7 @@ impl App {
             description: String::new(),
             prompt_prefix: String::new(),
             llm_mode: "Build".to_string(),
+            model: String::new(),
             focused_field: CommandFormField::Name,
             error: None,
             phase: CommandFormPhase::EditForm,
@@ -312,6 +765,7 @@ impl App {
             description: String::new(),
             prompt_prefix: String::new(),
             llm_mode: "Build".to_string(),
+            model: String::new(),
             focused_field: CommandFormField::Name,
             error: None,
             phase: CommandFormPhase::SelectCommand,
@@ -329,6 +783,133 @@ impl App {
         });
     }
 
+    /// Open the context preview for the next request. `prompt` is the pending user input;
+    /// only tools usable in the current mode and not disabled by `config` are counted, as the
+    /// others are not sent.
+    pub(crate) fn open_context_preview_popup(
+        &mut self,
+        api_messages: Option<&[Value]>,
+        prompt: &str,
+        config: &Config,
+    ) {
+        let mode = self
+            .pending_command_mode
+            .as_deref()
+            .unwrap_or(super::constants::SUGGESTIONS[self.selected_suggestion]);
+        let tools_defs = crate::core::llm::enabled_tool_definitions(
+            crate::core::tools::definitions(),
+            crate::core::tools::all(),
+            mode,
+            &config.disabled_tools,
+        );
+        let preview = crate::core::llm::preview_context(
+            &self.workspace,
+            &config.prompt_layout,
+            api_messages,
+            prompt,
+            &self.pinned_paths(),
+            &self.pinned_message_list(),
+            &tools_defs,
+            self.context_length,
+        );
+        self.context_preview_popup = Some(ContextPreviewState {
+            expanded: vec![false; preview.sections.len()],
+            preview,
+            selected_index: 0,
+            scroll: 0,
+        });
+    }
+
+    /// Model ID for the next API call: the in-flight turn's command model, else the current model.
+    pub(crate) fn turn_model_id(&self) -> String {
+        self.turn_model
+            .as_ref()
+            .map_or_else(|| self.current_model_id.clone(), |m| m.id.clone())
+    }
+
+    /// Model shown in the header with its source: a command-pinned model while its turn is
+    /// pending or running, otherwise the current model.
+    pub(crate) fn effective_model(&self) -> (&str, ModelSource) {
+        match self
+            .turn_model
+            .as_ref()
+            .or(self.pending_command_model.as_ref())
+        {
+            Some(m) => (m.name.as_str(), ModelSource::Command(m.command.clone())),
+            None => (self.model_name.as_str(), self.model_source.clone()),
+        }
+    }
+
+    /// Open the commit popup with the message drafted in `response`. When there is nothing
+    /// to commit (not a repo, nothing staged), the reason is shown as a tool log line instead.
+    pub(crate) fn open_commit_popup(&mut self, response: &str) {
+        match git_commit::staged_changes(&self.workspace.root) {
+            Ok(changes) => {
+                self.commit_popup = Some(CommitPopupState {
+                    changes,
+                    message: git_commit::extract_commit_message(response),
+                    confirming: false,
+                    error: None,
+                });
+            }
+            Err(e) => self.push_tool_log(format!("Commit skipped: {}", e)),
+        }
+    }
+
+    /// Open the diff viewer on the changes /review looked at. When there are none (or no
+    /// repository), the reason is shown as a tool log line instead.
+    pub(crate) fn open_review_diff(&mut self, scope: Option<&str>) {
+        match diff::git_changes(&self.workspace.root, scope) {
+            Ok(files) if files.is_empty() => {
+                self.push_tool_log("Diff viewer: no changes to show.".to_string())
+            }
+            Ok(files) => {
+                self.diff_viewer = Some(DiffViewerState::new("Reviewed changes".to_string(), files))
+            }
+            Err(e) => self.push_tool_log(format!("Diff viewer skipped: {}", e)),
+        }
+    }
+
+    /// Record a focus change. Returns true when focus comes back after at least `away` and
+    /// the credits are at least that old (or failed to load), so they should be refreshed.
+    pub(crate) fn focus_changed(&mut self, focused: bool, away: std::time::Duration) -> bool {
+        if !focused {
+            self.unfocused_since = Some(Instant::now());
+            return false;
+        }
+        let Some(since) = self.unfocused_since.take() else {
+            return false;
+        };
+        since.elapsed() >= away
+            && self
+                .credits_last_fetched_at
+                .is_none_or(|t| t.elapsed() >= away)
+    }
+
+    /// True when any popup is open (input and history do not receive keys or mouse events).
+    pub(crate) fn popup_open(&self) -> bool {
+        self.confirm_popup.is_some()
+            || self.model_selector.is_some()
+            || self.history_selector.is_some()
+            || self.command_form_popup.is_some()
+            || self.delete_command_popup.is_some()
+            || self.context_preview_popup.is_some()
+            || self.commit_popup.is_some()
+            || self.diff_viewer.is_some()
+            || self.suggestion_review.is_some()
+            || self.duplicate_popup.is_some()
+            || self.params_popup.is_some()
+            || self.permissions_popup.is_some()
+            || self.pins_popup.is_some()
+            || self.copy_mode.is_some()
+            || self.profile_picker.is_some()
+            || self.stats_screen.is_some()
+            || self.file_tree.is_some()
+            || self.command_palette.is_some()
+            || self.save_last_popup.is_some()
+            || self.spend_popup.is_some()
+    }
+
     pub(crate) fn is_dirty(&self) -> bool {
         self.dirty
     }
@@ -355,13 +936,343 @@ impl App {
 
     /// Reset to a new empty conversation.
     pub(crate) fn new_conversation(&mut self) {
+        self.save_ui_state();
         self.messages.clear();
         self.message_timestamps.clear();
+        self.message_usage.clear();
+        self.pinned_messages.clear();
+        self.message_reactions.clear();
         self.current_conversation_id = None;
         self.dirty = false;
         self.scroll = ScrollPosition::default();
         self.last_max_scroll = 0;
         self.token_usage = None;
+        self.tool_queue.clear();
+        self.side_pane = SidePane::default();
+        self.clear_prompt_queue();
+        self.pending_follow_up = None;
+        self.turn_follow_up = None;
+        self.pending_command_model = None;
+        self.turn_model = None;
+        self.pending_output_schema = None;
+        self.turn_output_schema = None;
+        self.duplicate_popup = None;
+        self.duplicate_checked = false;
+        self.zoom = None;
+        self.message_selection = None;
+        self.attachments.clear();
+        self.set_truncate_code(false);
+    }
+
+    /// Insert `text` at the cursor (must be a char boundary) and move the cursor after it.
+    pub(crate) fn insert_at_cursor(&mut self, text: &str) {
+        let pos = self
+            .input
+            .floor_char_boundary(self.input_cursor.min(self.input.len()));
+        self.input.insert_str(pos, text);
+        self.input_cursor = pos + text.len();
+    }
+
+    /// Insert pasted text at the cursor; large blocks go in as a `[pasted N lines]` chip.
+    pub(crate) fn insert_paste(&mut self, pasted: &str) {
+        let text = paste::normalize(pasted);
+        if paste::should_collapse(&text) {
+            self.insert_attachment(AttachmentKind::Text(text));
+        } else {
+            self.insert_at_cursor(&text);
+        }
+    }
+
+    /// Add an attachment and insert its placeholder chip at the cursor.
+    pub(crate) fn insert_attachment(&mut self, kind: AttachmentKind) {
+        let id = self.attachments.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+        let attachment = Attachment { id, kind };
+        self.insert_at_cursor(&attachment.placeholder());
+        self.attachments.push(attachment);
+    }
+
+    /// If the cursor sits right after an attachment chip, delete the whole chip.
+    pub(crate) fn delete_chip_before_cursor(&mut self) -> bool {
+        let pos = self.input_cursor.min(self.input.len());
+        let chip = paste::placeholder_ranges(&self.input, &self.attachments)
+            .into_iter()
+            .find(|(range, _)| range.end == pos)
+            .map(|(range, a)| (range, a.id));
+        let Some((range, id)) = chip else {
+            return false;
+        };
+        self.input.drain(range.clone());
+        self.input_cursor = range.start;
+        self.attachments.retain(|a| a.id != id);
+        true
+    }
+
+    /// Take the input as a prompt: chips expanded, attachments cleared.
+    /// Returns the trimmed prompt and the attached image data URLs.
+    pub(crate) fn take_input(&mut self) -> (String, Vec<String>) {
+        let (prompt, images) = paste::expand(&self.input, &self.attachments);
+        self.input.clear();
+        self.input_cursor = 0;
+        self.attachments.clear();
+        self.mention_files = None;
+        (prompt.trim().to_string(), images)
+    }
+
+    /// Slash commands matching the input (empty when the input does not start with "/").
+    pub(crate) fn slash_commands(&self) -> Vec<&ResolvedCommand> {
+        match self.input.strip_prefix('/') {
+            Some(query) => {
+                crate::core::commands::filter_commands_for_input(&self.resolved_commands, query)
+            }
+            None => vec![],
+        }
+    }
+
+    /// The @mention being typed: byte offset of the "@" and the query after it. A mention
+    /// is the word ending at the cursor, starting with "@" (outside slash commands).
+    pub(crate) fn mention_query(&self) -> Option<(usize, &str)> {
+        if self.input.starts_with('/') {
+            return None;
+        }
+        let before = self.input.get(..self.input_cursor.min(self.input.len()))?;
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| {
+            i + before[i..].chars().next().map_or(1, char::len_utf8)
+        });
+        before[start..].strip_prefix('@').map(|q| (start, q))
+    }
+
+    /// List workspace files for the @mention picker if a mention is being typed.
+    pub(crate) fn update_mention_files(&mut self) {
+        if self.mention_query().is_none() {
+            self.selected_mention_index = 0;
+        } else if self.mention_files.is_none() {
+            self.mention_files = Some(pins::workspace_files(&self.workspace.root));
+        }
+    }
+
+    /// Files offered by the @mention picker for the current query.
+    pub(crate) fn mention_candidates(&self) -> Vec<&str> {
+        match (self.mention_query(), &self.mention_files) {
+            (Some((_, query)), Some(files)) => pins::filter_files(files, query, 50),
+            _ => vec![],
+        }
+    }
+
+    /// True when the slash command or @mention autocomplete list is shown.
+    pub(crate) fn autocomplete_open(&self) -> bool {
+        !self.slash_commands().is_empty() || !self.mention_candidates().is_empty()
+    }
+
+    /// Replace the @mention being typed by pinning `path`.
+    pub(crate) fn accept_mention(&mut self, path: &str) {
+        if let Some((start, _)) = self.mention_query() {
+            let end = self.input_cursor.min(self.input.len());
+            self.input.drain(start..end);
+            self.input_cursor = start;
+        }
+        self.mention_files = None;
+        self.selected_mention_index = 0;
+        self.pin_file(path);
+    }
+
+    /// Pin a file (relative to the workspace root, or absolute). The outcome is shown as a
+    /// tool log line.
+    pub(crate) fn pin_file(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            self.push_tool_log("Usage: /pin <path>".to_string());
+            return;
+        }
+        match PinnedFile::resolve(&self.workspace.root, input) {
+            Ok(pin) if self.pinned_files.iter().any(|p| p.path == pin.path) => {
+                self.push_tool_log(format!("Already pinned: {}", pin.label));
+            }
+            Ok(pin) => {
+                self.push_tool_log(format!(
+                    "Pinned {} (~{} tokens, sent with every request)",
+                    pin.label, pin.tokens
+                ));
+                self.pinned_files.push(pin);
+            }
+            Err(e) => self.push_tool_log(format!("Pin failed: {}", e)),
+        }
+    }
+
+    /// Change the directory tools run in (`/cd`), or return to the workspace root when
+    /// `input` is empty. The outcome is shown as a tool log line.
+    pub(crate) fn change_working_dir(&mut self, input: &str) {
+        let line = match self.workspace.set_working_dir(input) {
+            Ok(dir) => format!("Working directory: {}", dir.display()),
+            Err(e) => format!("cd failed: {}", e),
+        };
+        self.push_tool_log(line);
+    }
+
+    /// Turn dry-run mode of this tab on or off (`/dryrun [on|off]`; toggles without argument).
+    /// The new state is shown as a tool log line, and in the header while on.
+    pub(crate) fn dry_run_command(&mut self, input: &str) {
+        let active = match input.trim().to_ascii_lowercase().as_str() {
+            "" => !self.tool_modes.dry_run,
+            "on" => true,
+            "off" => false,
+            other => {
+                self.push_tool_log(format!("Usage: /dryrun [on|off] (got '{}')", other));
+                return;
+            }
+        };
+        self.tool_modes.dry_run = active;
+        self.push_tool_log(if active {
+            "Dry run on: Write, Edit, ReplaceAll, and Bash are simulated — the model gets the diffs and commands they would apply, and nothing changes. /dryrun off to run them again.".to_string()
+        } else {
+            "Dry run off: tools run normally again.".to_string()
+        });
+    }
+
+    /// Re-read AGENTS.md/AGENT.md, the project type, and Git context, after a watched file
+    /// changed or on `/reload-context` (`manual`), and rebuild the system message of the
+    /// conversation so the next request uses them. Changes are shown as a tool log line; a
+    /// manual reload reports even when nothing changed.
+    pub(crate) fn reload_workspace_context(
+        &mut self,
+        api_messages: &mut Option<Vec<Value>>,
+        config: &Config,
+        manual: bool,
+    ) {
+        let changes = self.workspace.refresh_context();
+        if let Some(messages) = api_messages.as_mut() {
+            llm::refresh_system_message(messages, &self.workspace, &config.prompt_layout);
+        }
+        if !changes.is_empty() {
+            self.push_tool_log(format!(
+                "Workspace context refreshed: {}",
+                changes.join(", ")
+            ));
+        } else if manual {
+            self.push_tool_log("Workspace context reloaded (no changes).".to_string());
+        }
+    }
+
+    /// Share the conversation with secrets redacted (`/share`): uploaded as a secret gist
+    /// when a gist token is configured and no file is named, else written as Markdown to
+    /// `input` (relative to the working directory) or the shares directory.
+    pub(crate) fn share_conversation(&mut self, input: &str, config: &Config) {
+        let messages = Self::messages_to_persist_format(
+            &self.messages,
+            &self.message_timestamps,
+            &self.message_usage,
+            &self.pinned_messages,
+            &self.message_reactions,
+        );
+        if !messages
+            .iter()
+            .any(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+        {
+            self.push_tool_log("Nothing to share yet.".to_string());
+            return;
+        }
+        let title = self
+            .conversation_id()
+            .and_then(|id| {
+                history::list_conversations()
+                    .ok()?
+                    .into_iter()
+                    .find(|c| c.id == id)
+            })
+            .map(|c| c.title)
+            .unwrap_or_else(|| {
+                history::first_message_preview(&messages, super::constants::TITLE_PREVIEW_MAX_LEN)
+            });
+        let shared = SharedConversation::new(&title, &messages, &config.secrets());
+        let input = input.trim();
+        if input.is_empty() && config.gist_token.is_some() {
+            self.push_tool_log("Uploading the conversation as a secret gist...".to_string());
+            self.share_request = Some(shared);
+            return;
+        }
+        let path = if input.is_empty() {
+            shared.default_path()
+        } else {
+            Some(crate::core::tools::resolve_path(
+                &self.workspace.working_dir,
+                input,
+            ))
+        };
+        let line = match path {
+            Some(path) => match shared.write_to(&path) {
+                Ok(()) => format!(
+                    "Shared conversation written to {} (secrets redacted)",
+                    path.display()
+                ),
+                Err(e) => format!("Share failed: {}", e),
+            },
+            None => "Share failed: no data directory".to_string(),
+        };
+        self.push_tool_log(line);
+    }
+
+    /// Unpin the file matching `input` (its label or path), or all files when empty.
+    pub(crate) fn unpin(&mut self, input: &str) {
+        let input = input.trim();
+        if input.is_empty() {
+            let count = std::mem::take(&mut self.pinned_files).len();
+            self.push_tool_log(match count {
+                0 => "No pinned files.".to_string(),
+                n => format!("Unpinned {} file(s).", n),
+            });
+            return;
+        }
+        let path = PinnedFile::resolve(&self.workspace.root, input)
+            .ok()
+            .map(|p| p.path);
+        let before = self.pinned_files.len();
+        self.pinned_files
+            .retain(|p| p.label != input && Some(&p.path) != path.as_ref());
+        if self.pinned_files.len() < before {
+            self.push_tool_log(format!("Unpinned {}", input));
+        } else {
+            self.push_tool_log(format!("Not pinned: {}", input));
+        }
+    }
+
+    /// Paths of the pinned files, for the next request.
+    pub(crate) fn pinned_paths(&self) -> Vec<PathBuf> {
+        self.pinned_files.iter().map(|p| p.path.clone()).collect()
+    }
+
+    /// Update the pinned files' token counts (their content may have changed).
+    pub(crate) fn refresh_pins(&mut self) {
+        for pin in &mut self.pinned_files {
+            pin.refresh();
+        }
+    }
+
+    /// Show only the User or Assistant message at `msg_idx`, scrolled to its top.
+    pub(crate) fn zoom_message(&mut self, msg_idx: usize) {
+        if !matches!(
+            self.messages.get(msg_idx),
+            Some(ChatMessage::User(_) | ChatMessage::Assistant(_))
+        ) {
+            return;
+        }
+        let saved_scroll = match self.zoom.take() {
+            Some(z) => z.saved_scroll,
+            None => self.scroll,
+        };
+        self.zoom = Some(ZoomState {
+            msg_idx,
+            saved_scroll,
+        });
+        self.scroll = ScrollPosition::default();
+        self.selection = None;
+        self.selection_drag_start = None;
+    }
+
+    /// Leave the zoomed view and restore the history scroll.
+    pub(crate) fn unzoom(&mut self) {
+        if let Some(z) = self.zoom.take() {
+            self.scroll = z.saved_scroll;
+            self.selection = None;
+            self.selection_drag_start = None;
+        }
     }
 
     /// Must be called before scroll_up/scroll_down when at bottom.
@@ -0,0 +1,72 @@
+//! Model selector entries: favorites and recently used models first, then all others.
+
+use crate::core::models::{ModelInfo, filter_models};
+use crate::core::persistence;
+
+use super::ModelSelectorState;
+
+/// Section of the model selector a model is listed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelSection {
+    Favorites,
+    Recent,
+    All,
+}
+
+impl ModelSection {
+    pub fn title(self) -> &'static str {
+        match self {
+            ModelSection::Favorites => "Favorites",
+            ModelSection::Recent => "Recent",
+            ModelSection::All => "All models",
+        }
+    }
+}
+
+impl ModelSelectorState {
+    /// Models matching the filter, in display order: favorites (as starred), recent (most
+    /// recent first), then the rest (best matches first while filtering, alphabetically
+    /// otherwise). Each model is listed once.
+    pub(crate) fn entries(&self) -> Vec<(ModelSection, &ModelInfo)> {
+        let filtered = filter_models(&self.models, &self.filter);
+        let find = |id: &String| filtered.iter().copied().find(|m| &m.id == id);
+        let favorites = self.prefs.favorites.iter().filter_map(find);
+        let recent = self
+            .prefs
+            .recent
+            .iter()
+            .filter(|id| !self.prefs.is_favorite(id))
+            .filter_map(find);
+        let mut entries: Vec<(ModelSection, &ModelInfo)> = favorites
+            .map(|m| (ModelSection::Favorites, m))
+            .chain(recent.map(|m| (ModelSection::Recent, m)))
+            .collect();
+        let listed: Vec<&str> = entries.iter().map(|(_, m)| m.id.as_str()).collect();
+        let rest: Vec<&ModelInfo> = filtered
+            .iter()
+            .copied()
+            .filter(|m| !listed.contains(&m.id.as_str()))
+            .collect();
+        entries.extend(rest.into_iter().map(|m| (ModelSection::All, m)));
+        entries
+    }
+
+    /// Star or unstar the selected model and save the preference. The selection follows
+    /// the model to its new section.
+    pub(crate) fn toggle_selected_favorite(&mut self) {
+        let Some(id) = self
+            .entries()
+            .get(self.selected_index)
+            .map(|(_, m)| m.id.clone())
+        else {
+            return;
+        };
+        self.prefs.toggle_favorite(&id);
+        if let Err(e) = persistence::save_model_prefs(&self.prefs) {
+            log::warn!("Failed to save model favorites: {}", e);
+        }
+        if let Some(index) = self.entries().iter().position(|(_, m)| m.id == id) {
+            self.selected_index = index;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Bash command rules popup (`/permissions`): review, save, add and remove allow/deny rules.
+
+use crate::core::command_rules::{self, CommandRule, RuleKind};
+
+use super::App;
+
+/// State of the `/permissions` popup.
+pub struct PermissionsPopupState {
+    /// Snapshot of `command_rules::list()`, refreshed after each change.
+    pub rules: Vec<CommandRule>,
+    pub selected: usize,
+    /// A new rule being typed: its kind and pattern.
+    pub input: Option<(RuleKind, String)>,
+    /// Result of the last action (error or confirmation).
+    pub message: Option<String>,
+}
+
+impl PermissionsPopupState {
+    fn new() -> Self {
+        PermissionsPopupState {
+            rules: command_rules::list(),
+            selected: 0,
+            input: None,
+            message: None,
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.rules = command_rules::list();
+        self.selected = self.selected.min(self.rules.len().saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.rules.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Save the selected session rule to `bash_rules.json`.
+    pub fn save_selected(&mut self) {
+        let Some(rule) = self.rules.get(self.selected) else {
+            return;
+        };
+        self.message = Some(if rule.saved {
+            "Already saved.".to_string()
+        } else {
+            match command_rules::save(self.selected) {
+                Ok(()) => format!("Saved {} rule `{}`.", rule.kind.label(), rule.pattern),
+                Err(e) => format!("Failed to save rule: {}", e),
+            }
+        });
+        self.refresh();
+    }
+
+    /// Remove the selected rule (also from `bash_rules.json` when saved).
+    pub fn remove_selected(&mut self) {
+        let Some(rule) = self.rules.get(self.selected) else {
+            return;
+        };
+        self.message = Some(match command_rules::remove(self.selected) {
+            Ok(()) => format!("Removed {} rule `{}`.", rule.kind.label(), rule.pattern),
+            Err(e) => format!("Failed to remove rule: {}", e),
+        });
+        self.refresh();
+    }
+
+    /// Add the typed rule for the session and select it.
+    pub fn submit_input(&mut self) {
+        let Some((kind, pattern)) = self.input.take() else {
+            return;
+        };
+        if command_rules::add(kind, &pattern) {
+            self.message = None;
+            self.rules = command_rules::list();
+            self.selected = self.rules.len().saturating_sub(1);
+        } else if pattern.trim().is_empty() {
+            self.message = None;
+        } else {
+            self.message = Some(format!("A {} rule for this already exists.", kind.label()));
+        }
+    }
+}
+
+impl App {
+    /// Open `/permissions` with the current rules.
+    pub(crate) fn open_permissions_popup(&mut self) {
+        self.permissions_popup = Some(PermissionsPopupState::new());
+    }
+}
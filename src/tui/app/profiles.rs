@@ -0,0 +1,116 @@
+//! Profile switcher (`/profile`): pick one of the named profiles, or the environment
+//! settings alone. The main loop applies the switch between turns.
+
+use crate::core::profiles::{self, Profile};
+
+use super::App;
+
+/// An entry of the switcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// None: no profile, the environment settings only.
+    pub name: Option<String>,
+    pub summary: String,
+}
+
+/// State of the `/profile` popup.
+pub struct ProfilePickerState {
+    pub entries: Vec<ProfileEntry>,
+    pub selected: usize,
+    /// Index of the active entry.
+    pub active: usize,
+}
+
+impl ProfilePickerState {
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+/// Switcher entries, environment settings first, with the active one selected.
+fn picker_state(profiles: Vec<Profile>, active: Option<&str>) -> ProfilePickerState {
+    let mut entries = vec![ProfileEntry {
+        name: None,
+        summary: "environment settings only".to_string(),
+    }];
+    entries.extend(profiles.into_iter().map(|p| ProfileEntry {
+        summary: p.summary(),
+        name: Some(p.name),
+    }));
+    let active = entries
+        .iter()
+        .position(|e| e.name.as_deref() == active)
+        .unwrap_or(0);
+    ProfilePickerState {
+        entries,
+        selected: active,
+        active,
+    }
+}
+
+impl App {
+    /// `/profile [NAME]`: switch to NAME, or open the switcher without argument.
+    pub(crate) fn profile_command(&mut self, rest: &str) {
+        if !rest.is_empty() {
+            self.profile_request = Some(Some(rest.to_string()));
+            return;
+        }
+        let list = profiles::list();
+        if list.is_empty() {
+            let dir = profiles::profiles_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_else(|| "the config directory".to_string());
+            self.push_tool_log(format!(
+                "No profiles yet: create NAME.json in {} (see the README).",
+                dir
+            ));
+            return;
+        }
+        let active = profiles::active().map(|p| p.name);
+        self.profile_picker = Some(picker_state(list, active.as_deref()));
+    }
+
+    /// Request a switch to the selected entry and close the switcher.
+    pub(crate) fn choose_profile(&mut self) {
+        let Some(state) = self.profile_picker.take() else {
+            return;
+        };
+        if state.selected != state.active
+            && let Some(entry) = state.entries.get(state.selected)
+        {
+            self.profile_request = Some(entry.name.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picker_lists_environment_first_and_selects_the_active_profile() {
+        let profile = |name: &str, model: &str| Profile {
+            name: name.to_string(),
+            model: Some(model.to_string()),
+            ..Default::default()
+        };
+        let list = vec![profile("local", "llama3"), profile("work", "openai/gpt-4o")];
+
+        let state = picker_state(list.clone(), Some("work"));
+        let names: Vec<Option<&str>> = state.entries.iter().map(|e| e.name.as_deref()).collect();
+        assert_eq!(names, vec![None, Some("local"), Some("work")]);
+        assert_eq!((state.selected, state.active), (2, 2));
+        assert_eq!(state.entries[1].summary, "llama3");
+
+        let state = picker_state(list.clone(), None);
+        assert_eq!(state.selected, 0);
+        // A profile that was removed since: the environment entry is marked instead.
+        assert_eq!(picker_state(list, Some("gone")).active, 0);
+    }
+}
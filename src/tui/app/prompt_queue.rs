@@ -0,0 +1,63 @@
+//! Prompts sent while a request is in flight: shown as "(queued)" in the history and
+//! started in order as each turn finishes, one request at a time.
+
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::{App, CommandModel, TurnFollowUp};
+
+/// A prompt waiting for the in-flight request to finish.
+#[derive(Clone, Debug)]
+pub struct QueuedPrompt {
+    /// Prompt text, attachment chips already expanded.
+    pub prompt: String,
+    /// Attached images, as data URLs.
+    pub images: Vec<String>,
+    /// Ask, Build, or Plan.
+    pub mode: String,
+    /// Model pinned by the slash command the prompt was sent with.
+    pub turn_model: Option<CommandModel>,
+    /// Output schema of the slash command the prompt was sent with.
+    pub output_schema: Option<Value>,
+    /// The prompt was sent with /commit or /review.
+    pub follow_up: Option<TurnFollowUp>,
+}
+
+impl App {
+    /// Add a prompt to the end of the queue.
+    pub(crate) fn queue_prompt(&mut self, queued: QueuedPrompt) {
+        self.prompt_queue.push_back(queued);
+    }
+
+    /// Take the next prompt to send, unless the queue is held after a rate limit.
+    pub(crate) fn next_queued_prompt(&mut self) -> Option<QueuedPrompt> {
+        if self.queue_hold_remaining().is_some() {
+            return None;
+        }
+        self.queue_held_until = None;
+        self.prompt_queue.pop_front()
+    }
+
+    /// Delay the next queued prompt (the API answered with a rate limit).
+    pub(crate) fn hold_queue(&mut self, delay: Duration) {
+        if !self.prompt_queue.is_empty() {
+            self.queue_held_until = Some(Instant::now() + delay);
+        }
+    }
+
+    /// Time left before queued prompts start again, while held.
+    pub(crate) fn queue_hold_remaining(&self) -> Option<Duration> {
+        self.queue_held_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Drop all queued prompts. Returns how many were dropped.
+    pub(crate) fn clear_prompt_queue(&mut self) -> usize {
+        self.queue_held_until = None;
+        let dropped = self.prompt_queue.len();
+        self.prompt_queue.clear();
+        dropped
+    }
+}
@@ -0,0 +1,45 @@
+//! Reactions to assistant replies (Alt+A): good, bad, or flag, saved with the conversation
+//! and exported with `history reactions` to build an evaluation set.
+
+use crate::core::history::Reaction;
+
+use super::message_pins::pin_preview;
+use super::{App, ChatMessage};
+
+/// Width of the reply preview in reaction log lines.
+const REACTION_PREVIEW_WIDTH: usize = 50;
+
+impl App {
+    /// Cycle the reaction to the assistant reply at `msg_idx`: none → good → bad → flag →
+    /// none. The outcome is shown as a tool log line.
+    pub(crate) fn cycle_message_reaction(&mut self, msg_idx: usize) {
+        let Some(ChatMessage::Assistant(text)) = self.messages.get(msg_idx) else {
+            self.push_tool_log(
+                "Reactions are for assistant replies: hover one and press Alt+A.".to_string(),
+            );
+            return;
+        };
+        let preview = pin_preview(text, REACTION_PREVIEW_WIDTH);
+        if self.is_streaming && msg_idx + 1 == self.messages.len() {
+            self.push_tool_log("Wait for the reply to finish before reacting to it.".to_string());
+            return;
+        }
+        let line = match Reaction::cycle(self.message_reactions.get(&msg_idx).copied()) {
+            Some(reaction) => {
+                self.message_reactions.insert(msg_idx, reaction);
+                format!(
+                    "Marked reply \"{}\" {} {} (Alt+A again to change)",
+                    preview,
+                    reaction.glyph(),
+                    reaction
+                )
+            }
+            None => {
+                self.message_reactions.remove(&msg_idx);
+                format!("Cleared the reaction to \"{}\"", preview)
+            }
+        };
+        self.push_tool_log(line);
+        self.mark_dirty();
+    }
+}
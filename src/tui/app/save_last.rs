@@ -0,0 +1,142 @@
+//! `/save-last [code] <path>`: write the last assistant reply, or its largest code block,
+//! to a file once the user confirms, instead of copying it out of the terminal.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::core::permissions::{self, PathAccess};
+use crate::core::remote;
+use crate::core::tools;
+
+use super::copy_mode::{CodeSnippet, code_snippets};
+use super::{App, ChatMessage};
+
+/// State of the confirmation popup of `/save-last`.
+pub struct SaveLastState {
+    pub path: PathBuf,
+    /// Path as shown to the user (relative to the working directory when inside it).
+    pub display_path: String,
+    pub content: String,
+    /// What is written, e.g. "the last reply" or "its largest code block (rust)".
+    pub what: String,
+    /// The file exists and would be overwritten.
+    pub exists: bool,
+    /// The path is outside the workspace root.
+    pub outside_workspace: bool,
+}
+
+/// Split the `/save-last` argument into (largest code block only, path).
+fn parse_args(input: &str) -> (bool, &str) {
+    let input = input.trim();
+    match input.split_once(char::is_whitespace) {
+        Some(("code", path)) => (true, path.trim()),
+        _ => (false, input),
+    }
+}
+
+/// Longest code block of `text`, the first one on a tie.
+fn largest_code_block(text: &str) -> Option<CodeSnippet> {
+    let message = [ChatMessage::Assistant(text.to_string())];
+    code_snippets(&message)
+        .into_iter()
+        .rev()
+        .max_by_key(|s| s.code.len())
+}
+
+impl App {
+    /// Prepare the write asked by `/save-last` and open its confirmation popup.
+    pub(crate) fn save_last_command(&mut self, input: &str) {
+        let (code_only, path) = parse_args(input);
+        if path.is_empty() {
+            self.push_tool_log("Usage: /save-last [code] <path>".to_string());
+            return;
+        }
+        let Some(reply) = self.messages.iter().rev().find_map(|m| match m {
+            ChatMessage::Assistant(text) if !text.trim().is_empty() => Some(text.clone()),
+            _ => None,
+        }) else {
+            self.push_tool_log("No reply to save yet.".to_string());
+            return;
+        };
+        let (mut content, what) = if code_only {
+            match largest_code_block(&reply) {
+                Some(snippet) if snippet.lang.is_empty() => {
+                    (snippet.code, "its largest code block".to_string())
+                }
+                Some(snippet) => (
+                    snippet.code,
+                    format!("its largest code block ({})", snippet.lang),
+                ),
+                None => {
+                    self.push_tool_log("The last reply has no code block.".to_string());
+                    return;
+                }
+            }
+        } else {
+            (reply, "the last reply".to_string())
+        };
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+
+        let working_dir = &self.workspace.working_dir;
+        let outside_workspace = match permissions::check(working_dir, path, true) {
+            PathAccess::Allowed => false,
+            PathAccess::NeedsApproval { .. } => true,
+            PathAccess::Denied { pattern } => {
+                self.push_tool_log(format!("Cannot save to {}: denied by '{}'.", path, pattern));
+                return;
+            }
+        };
+        let resolved = tools::resolve_path(working_dir, path);
+        let exists = !matches!(
+            remote::fs::read_to_string(&resolved),
+            Err(e) if e.kind() == io::ErrorKind::NotFound
+        );
+        self.save_last_popup = Some(SaveLastState {
+            display_path: tools::display_path(working_dir, &resolved),
+            path: resolved,
+            content,
+            what,
+            exists,
+            outside_workspace,
+        });
+    }
+
+    /// Write the confirmed `/save-last` content and close the popup.
+    pub(crate) fn confirm_save_last(&mut self) {
+        let Some(state) = self.save_last_popup.take() else {
+            return;
+        };
+        let line = match remote::fs::write(&state.path, &state.content) {
+            Ok(()) => format!(
+                "Saved {} to {} ({} lines).",
+                state.what,
+                state.display_path,
+                state.content.lines().count()
+            ),
+            Err(e) => format!("Cannot save to {}: {}", state.display_path, e),
+        };
+        self.push_tool_log(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_last_picks_the_target_and_the_largest_block() {
+        assert_eq!(parse_args(" out.md "), (false, "out.md"));
+        assert_eq!(parse_args("code src/main.rs"), (true, "src/main.rs"));
+        assert_eq!(parse_args("code"), (false, "code"));
+
+        let reply = "Here:\n```sh\nls\n```\nand\n```rust\nfn main() {}\n```\n";
+        let block = largest_code_block(reply).unwrap();
+        assert_eq!(
+            (block.lang.as_str(), block.code.as_str()),
+            ("rust", "fn main() {}")
+        );
+        assert!(largest_code_block("no code").is_none());
+    }
+}
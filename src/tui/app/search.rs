@@ -0,0 +1,286 @@
+//! Search within the current conversation (Ctrl+F, or `/` while scrolled back): query,
+//! matches in the rendered history, and the current match.
+
+use super::{App, ScrollPosition};
+
+/// Rendered history line holding searchable text (message text, code, tool logs, errors).
+/// Borders, labels, and separators are not searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextLine {
+    /// Index of the line in the rendered history.
+    pub line: usize,
+    /// Column (in chars) where the text starts, after borders and indentation.
+    pub col: usize,
+    /// The line is the wrapped continuation of the previous text line.
+    pub continued: bool,
+}
+
+/// One occurrence of the query: `(line, start_col, end_col)` ranges in chars, one per
+/// rendered line it covers (several when the match crosses a wrap).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub ranges: Vec<(usize, usize, usize)>,
+}
+
+impl SearchMatch {
+    /// First rendered line of the match.
+    pub fn line(&self) -> usize {
+        self.ranges.first().map(|r| r.0).unwrap_or(0)
+    }
+}
+
+/// Which match to show on the next draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchJump {
+    /// The query changed: the first match from the top of the view (wrapping).
+    FromView,
+    /// `current` changed (n/N): scroll it into view.
+    Current,
+}
+
+/// State of the conversation search.
+pub struct SearchState {
+    pub query: String,
+    /// The query is being typed; Enter confirms it and n/N then move between matches.
+    pub editing: bool,
+    /// Matches in the rendered history, top to bottom (updated when the history is drawn).
+    pub matches: Vec<SearchMatch>,
+    /// Index of the current match in `matches`.
+    pub current: usize,
+    pub(crate) jump: Option<SearchJump>,
+}
+
+impl SearchState {
+    fn new() -> Self {
+        SearchState {
+            query: String::new(),
+            editing: true,
+            matches: Vec::new(),
+            current: 0,
+            jump: None,
+        }
+    }
+
+    /// Type a character of the query; the view jumps to the first match from its top.
+    pub(crate) fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.jump = Some(SearchJump::FromView);
+    }
+
+    /// Delete the last character of the query.
+    pub(crate) fn pop_char(&mut self) {
+        self.query.pop();
+        self.jump = Some(SearchJump::FromView);
+    }
+
+    /// Move to the next match (or the previous one when `forward` is false), wrapping.
+    pub(crate) fn step(&mut self, forward: bool) {
+        let len = self.matches.len();
+        if len == 0 {
+            return;
+        }
+        self.current = if forward {
+            (self.current + 1) % len
+        } else {
+            (self.current + len - 1) % len
+        };
+        self.jump = Some(SearchJump::Current);
+    }
+
+    /// Replace the matches after a redraw and resolve a pending jump. Returns the rendered
+    /// line to bring into view, if any.
+    pub(crate) fn update_matches(
+        &mut self,
+        matches: Vec<SearchMatch>,
+        view_top: usize,
+    ) -> Option<usize> {
+        self.matches = matches;
+        if self.matches.is_empty() {
+            self.current = 0;
+            self.jump = None;
+            return None;
+        }
+        match self.jump.take() {
+            Some(SearchJump::FromView) => {
+                self.current = self
+                    .matches
+                    .iter()
+                    .position(|m| m.line() >= view_top)
+                    .unwrap_or(0);
+            }
+            Some(SearchJump::Current) => {}
+            None => {
+                self.current = self.current.min(self.matches.len() - 1);
+                return None;
+            }
+        }
+        Some(self.matches[self.current].line())
+    }
+
+    /// "3/12", or "no matches" once something is typed.
+    pub fn counter(&self) -> Option<String> {
+        if self.query.is_empty() {
+            None
+        } else if self.matches.is_empty() {
+            Some("no matches".to_string())
+        } else {
+            Some(format!("{}/{}", self.current + 1, self.matches.len()))
+        }
+    }
+}
+
+/// Lowercase a character without changing the character count, so indices stay aligned.
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Occurrences of `query` (case-insensitive) in the text of `rendered` history lines.
+/// Wrapped lines are searched as one line, joined by a space, so a phrase split by the
+/// wrap still matches.
+pub(crate) fn find_matches(
+    rendered: &[String],
+    text_lines: &[TextLine],
+    query: &str,
+) -> Vec<SearchMatch> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    // Text of the current logical line, with the rendered position of each char (None for
+    // the space joining wrapped lines).
+    let mut chars: Vec<char> = Vec::new();
+    let mut positions: Vec<Option<(usize, usize)>> = Vec::new();
+    let mut flush = |chars: &mut Vec<char>, positions: &mut Vec<Option<(usize, usize)>>| {
+        let mut i = 0;
+        while i + query.len() <= chars.len() {
+            if chars[i..i + query.len()] == query[..] {
+                let mut ranges: Vec<(usize, usize, usize)> = Vec::new();
+                for &(line, col) in positions[i..i + query.len()].iter().flatten() {
+                    match ranges.last_mut() {
+                        Some(r) if r.0 == line => r.2 = col + 1,
+                        _ => ranges.push((line, col, col + 1)),
+                    }
+                }
+                matches.push(SearchMatch { ranges });
+                i += query.len();
+            } else {
+                i += 1;
+            }
+        }
+        chars.clear();
+        positions.clear();
+    };
+    for text_line in text_lines {
+        let Some(rendered_line) = rendered.get(text_line.line) else {
+            continue;
+        };
+        if !text_line.continued {
+            flush(&mut chars, &mut positions);
+        } else if !chars.is_empty() {
+            chars.push(' ');
+            positions.push(None);
+        }
+        let text: Vec<char> = rendered_line.chars().skip(text_line.col).collect();
+        let len = text.len() - text.iter().rev().take_while(|c| c.is_whitespace()).count();
+        for (offset, c) in text[..len].iter().enumerate() {
+            chars.push(fold(*c));
+            positions.push(Some((text_line.line, text_line.col + offset)));
+        }
+    }
+    flush(&mut chars, &mut positions);
+    matches
+}
+
+impl App {
+    /// Start a conversation search, or edit the query of the open one.
+    pub(crate) fn open_search(&mut self) {
+        self.search.get_or_insert_with(SearchState::new).editing = true;
+    }
+
+    /// Close the search and its highlights; the view stays where it is.
+    pub(crate) fn close_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Reading back through the history: `/` starts a search instead of a command.
+    pub(crate) fn in_scrollback(&self) -> bool {
+        self.input.is_empty() && (self.zoom.is_some() || self.scroll_line() < self.last_max_scroll)
+    }
+
+    /// Scroll so that rendered `line` is visible, about a third down the view when it was not.
+    pub(crate) fn scroll_to_line(&mut self, line: usize, visible: usize) {
+        let top = self.scroll_line();
+        if line < top || line >= top + visible {
+            let target = line.saturating_sub(visible / 3).min(self.last_max_scroll);
+            self.scroll = ScrollPosition::Line(target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_line(line: usize, continued: bool) -> TextLine {
+        TextLine {
+            line,
+            col: 4,
+            continued,
+        }
+    }
+
+    #[test]
+    fn finds_matches_across_wrapped_lines() {
+        let rendered: Vec<String> = [
+            "┌─ You ──┐",
+            "│   Where is the",
+            "│   config file?  ",
+            "│ │ let config = load();",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let text_lines = [text_line(1, false), text_line(2, true), text_line(3, false)];
+
+        let matches = find_matches(&rendered, &text_lines, "THE CONFIG");
+        assert_eq!(
+            matches,
+            vec![SearchMatch {
+                ranges: vec![(1, 13, 16), (2, 4, 10)]
+            }]
+        );
+
+        let matches = find_matches(&rendered, &text_lines, "config");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].ranges, vec![(3, 8, 14)]);
+        // Borders and labels are not searched.
+        assert!(find_matches(&rendered, &text_lines, "You").is_empty());
+    }
+
+    #[test]
+    fn jumps_from_view_and_steps_with_wrap() {
+        let at = |line| SearchMatch {
+            ranges: vec![(line, 0, 1)],
+        };
+        let mut state = SearchState::new();
+        state.query = "x".to_string();
+        state.jump = Some(SearchJump::FromView);
+        assert_eq!(
+            state.update_matches(vec![at(2), at(10), at(20)], 5),
+            Some(10)
+        );
+        assert_eq!(state.counter().as_deref(), Some("2/3"));
+
+        state.step(true);
+        state.step(true);
+        assert_eq!(
+            state.update_matches(vec![at(2), at(10), at(20)], 5),
+            Some(2)
+        );
+        assert_eq!(state.update_matches(vec![at(2), at(10), at(20)], 5), None);
+
+        assert_eq!(state.update_matches(Vec::new(), 0), None);
+        assert_eq!(state.counter().as_deref(), Some("no matches"));
+    }
+}
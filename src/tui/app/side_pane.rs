@@ -0,0 +1,237 @@
+//! Split-pane layout (Alt+S): the right pane shows the file most recently touched by a tool,
+//! with the lines the call changed highlighted, and the current task plan.
+//!
+//! Files are read on the agent thread as tool events are emitted: before the queued calls
+//! run, then after each call finishes. The pane diffs the two to find the changed lines.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::core::diff::{self, LineKind};
+use crate::core::llm::ToolEvent;
+use crate::core::remote;
+use crate::core::tools::{self, PlanStep, ToolView};
+
+use super::App;
+
+/// Larger files are not shown in the pane.
+const MAX_FILE_BYTES: usize = 1024 * 1024;
+
+/// Content of each file, None when it does not exist or cannot be read as text.
+pub type FileContents = Vec<(PathBuf, Option<String>)>;
+
+/// A tool event with the contents of the files it concerns: before the calls run for
+/// `Queued`, after the call for a successful `Finished`.
+pub struct ToolUpdate {
+    pub event: ToolEvent,
+    pub files: FileContents,
+}
+
+/// Reads the files of tool calls for `ToolUpdate`s. Runs in the agent thread, so contents
+/// are read before the calls run and right after each finishes.
+#[derive(Default)]
+pub struct ToolFileReader {
+    /// Target path of each queued call, by call id.
+    paths: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl ToolFileReader {
+    pub fn update(&self, event: &ToolEvent) -> ToolUpdate {
+        let mut paths = self.paths.lock().unwrap_or_else(|e| e.into_inner());
+        let files = match event {
+            ToolEvent::Queued(calls) => {
+                paths.clear();
+                let mut files: FileContents = Vec::new();
+                for call in calls {
+                    let Some(path) = &call.path else { continue };
+                    paths.insert(call.id.clone(), path.clone());
+                    if !files.iter().any(|(p, _)| p == path) {
+                        files.push((path.clone(), read_file(path)));
+                    }
+                }
+                files
+            }
+            ToolEvent::Finished {
+                id, success: true, ..
+            } => paths
+                .get(id)
+                .map(|path| vec![(path.clone(), read_file(path))])
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        ToolUpdate {
+            event: event.clone(),
+            files,
+        }
+    }
+}
+
+fn read_file(path: &PathBuf) -> Option<String> {
+    remote::fs::read_to_string(path)
+        .ok()
+        .filter(|content| content.len() <= MAX_FILE_BYTES)
+}
+
+/// File shown in the pane.
+#[derive(Debug, Clone)]
+pub struct PaneFile {
+    /// Path relative to the workspace root when inside it.
+    pub display: String,
+    /// Tool that touched it last.
+    pub tool: String,
+    pub lines: Vec<String>,
+    /// Line numbers (from 1) added or changed by that call.
+    pub changed: BTreeSet<usize>,
+    /// Line numbers right after lines the call removed (one past the end for the last line).
+    pub removed_before: BTreeSet<usize>,
+    /// The call created the file.
+    pub created: bool,
+    /// The call deleted the file.
+    pub deleted: bool,
+}
+
+impl PaneFile {
+    /// First line the call changed or removed lines before.
+    pub fn first_change(&self) -> Option<usize> {
+        let changed = self.changed.first().copied();
+        let removed = self.removed_before.first().copied();
+        match (changed, removed) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Content of the right pane.
+#[derive(Debug, Default)]
+pub struct SidePane {
+    pub file: Option<PaneFile>,
+    /// Steps of the last TaskPlan call.
+    pub plan: Vec<PlanStep>,
+    /// Content of the queued calls' files before they ran, by path.
+    before: HashMap<PathBuf, Option<String>>,
+}
+
+/// Lines of `after` added or changed since `before`, and the lines after removed ones.
+fn changed_lines(before: &str, after: &str) -> (BTreeSet<usize>, BTreeSet<usize>) {
+    let mut changed = BTreeSet::new();
+    let mut removed_before = BTreeSet::new();
+    let end = after.lines().count() + 1;
+    for hunk in &diff::between("", before, after).hunks {
+        let mut removed = false;
+        for line in &hunk.lines {
+            match (line.kind, line.new_line) {
+                (LineKind::Removed, _) => removed = true,
+                (kind, Some(n)) => {
+                    if removed {
+                        removed_before.insert(n);
+                        removed = false;
+                    }
+                    if kind == LineKind::Added {
+                        changed.insert(n);
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Hunks end with context lines unless the removal is at the end of the file.
+        if removed {
+            removed_before.insert(end);
+        }
+    }
+    // A removed line replaced by an added one shows as changed only.
+    let removed_before = removed_before
+        .into_iter()
+        .filter(|n| !changed.contains(n))
+        .collect();
+    (changed, removed_before)
+}
+
+impl App {
+    /// Apply a tool event with its file contents: update the side pane, then the tool queue.
+    pub(crate) fn apply_tool_update(&mut self, update: ToolUpdate) {
+        self.update_side_pane(&update.event, update.files);
+        self.apply_tool_event(update.event);
+    }
+
+    fn update_side_pane(&mut self, event: &ToolEvent, files: FileContents) {
+        match event {
+            ToolEvent::Queued(_) => self.side_pane.before = files.into_iter().collect(),
+            ToolEvent::Finished { id, view, .. } => {
+                if let Some(ToolView::Plan { steps }) = view {
+                    self.side_pane.plan = steps.clone();
+                }
+                let tool = self
+                    .tool_queue
+                    .iter()
+                    .find(|e| &e.id == id)
+                    .map(|e| e.name.clone())
+                    .unwrap_or_default();
+                for (path, after) in files {
+                    let before = self.side_pane.before.remove(&path);
+                    let display = tools::display_path(&self.workspace.root, &path);
+                    let file = match (before, after) {
+                        (before, Some(after)) => {
+                            let created = matches!(before, Some(None));
+                            let (changed, removed_before) = match &before {
+                                Some(before) => {
+                                    changed_lines(before.as_deref().unwrap_or(""), &after)
+                                }
+                                None => Default::default(),
+                            };
+                            // Later calls of the batch diff against this content.
+                            self.side_pane
+                                .before
+                                .insert(path.clone(), Some(after.clone()));
+                            PaneFile {
+                                display,
+                                tool: tool.clone(),
+                                lines: after.lines().map(str::to_string).collect(),
+                                changed,
+                                removed_before,
+                                created,
+                                deleted: false,
+                            }
+                        }
+                        (Some(Some(_)), None) => PaneFile {
+                            display,
+                            tool: tool.clone(),
+                            lines: Vec::new(),
+                            changed: BTreeSet::new(),
+                            removed_before: BTreeSet::new(),
+                            created: false,
+                            deleted: true,
+                        },
+                        // Not a file (a directory, or too large).
+                        _ => continue,
+                    };
+                    self.side_pane.file = Some(file);
+                }
+            }
+            ToolEvent::Started { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_lines_marks_added_and_removed_lines() {
+        let before = "a\nb\nc\nd\n";
+        let after = "a\nB\nc\n";
+        let (changed, removed_before) = changed_lines(before, after);
+        assert_eq!(changed, BTreeSet::from([2]));
+        // "d" was removed after the last line.
+        assert_eq!(removed_before, BTreeSet::from([4]));
+
+        let (changed, removed_before) = changed_lines("a\nb\nc\n", "a\nc\n");
+        assert!(changed.is_empty());
+        assert_eq!(removed_before, BTreeSet::from([2]));
+
+        let (changed, _) = changed_lines("", "x\ny\n");
+        assert_eq!(changed, BTreeSet::from([1, 2]));
+    }
+}
@@ -0,0 +1,103 @@
+//! Confirmation at a reached spend cap: the request that hit it waits in the input until the
+//! user chooses to continue past the caps for the rest of the session, or not.
+
+use crate::core::spend;
+
+use super::{App, QueuedPrompt};
+
+impl App {
+    /// Continue past the reached cap (y on the spend popup): lift the caps for the session
+    /// and send the prompt left in the input.
+    pub(crate) fn confirm_spend_cap(&mut self) {
+        if self.spend_popup.take().is_none() {
+            return;
+        }
+        spend::override_caps();
+        if self.input.trim().is_empty() || self.input.starts_with('/') {
+            self.push_tool_log(
+                "Spend caps lifted for this session: run the command again to send it.".to_string(),
+            );
+            return;
+        }
+        let mode = self.pending_command_mode.take().unwrap_or_else(|| {
+            crate::tui::constants::SUGGESTIONS[self.selected_suggestion].to_string()
+        });
+        let (prompt, images) = self.take_input();
+        let queued = QueuedPrompt {
+            prompt,
+            images,
+            mode,
+            turn_model: self.pending_command_model.take(),
+            output_schema: self.pending_output_schema.take(),
+            follow_up: self.pending_follow_up.take(),
+        };
+        self.queue_prompt(queued);
+        self.push_tool_log("Spend caps lifted for this session: sending.".to_string());
+    }
+
+    /// Stay under the reached cap (n on the spend popup): the prompt stays in the input.
+    pub(crate) fn decline_spend_cap(&mut self) {
+        if let Some(hit) = self.spend_popup.take() {
+            self.push_tool_log(format!(
+                "{}: new requests are blocked. Run /spend override to send anyway.",
+                hit
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::config::ModelSource;
+    use crate::core::spend::{LimitHit, Period};
+    use crate::core::workspace::Workspace;
+
+    fn app() -> App {
+        let root = PathBuf::from("/work/project");
+        let mut app = App::new(
+            "mock/model".to_string(),
+            "Mock Model".to_string(),
+            ModelSource::Default,
+            Workspace {
+                root: root.clone(),
+                working_dir: root,
+                project_type: None,
+                agent_md: None,
+                context_files: Vec::new(),
+                git_context: None,
+            },
+            false,
+            false,
+        );
+        app.spend_popup = Some(LimitHit {
+            period: Period::Session,
+            spent: 2.5,
+            limit: 2.0,
+        });
+        app
+    }
+
+    #[test]
+    fn decline_keeps_the_prompt_in_the_input() {
+        let mut app = app();
+        app.input = "Refactor the parser".to_string();
+        app.decline_spend_cap();
+        assert!(app.spend_popup.is_none());
+        assert_eq!(app.input, "Refactor the parser");
+        assert!(app.prompt_queue.is_empty());
+    }
+
+    #[test]
+    fn confirm_lifts_the_caps_and_queues_the_prompt() {
+        let mut app = app();
+        app.input = "Refactor the parser".to_string();
+        app.confirm_spend_cap();
+        assert!(spend::caps_overridden());
+        assert!(app.input.is_empty());
+        assert_eq!(app.prompt_queue.len(), 1);
+        assert_eq!(app.prompt_queue[0].prompt, "Refactor the parser");
+    }
+}
@@ -0,0 +1,127 @@
+//! `/suggest`: Edit calls are staged instead of applied, and the review popup opened when
+//! the turn ends goes through their hunks one by one (see `core::suggest`).
+
+use serde_json::{Value, json};
+
+use crate::core::diff::{FileChange, FileDiff};
+use crate::core::llm::undo::UndoBatch;
+use crate::core::suggest::{self, ChangeSet, Decision, Review};
+
+use super::{App, DiffViewerState};
+
+/// Review popup of the edits suggested in a turn.
+pub struct SuggestionReviewState {
+    pub review: Review,
+    /// Diff of the hunk waiting for a decision.
+    pub view: DiffViewerState,
+}
+
+impl SuggestionReviewState {
+    /// None when the change-set has no hunk to review.
+    pub fn new(set: ChangeSet) -> Option<Self> {
+        let review = Review::new(set);
+        let mut state = SuggestionReviewState {
+            review,
+            view: DiffViewerState::new(String::new(), Vec::new()),
+        };
+        state.show_current().then_some(state)
+    }
+
+    /// Show the hunk waiting for a decision. Returns false once every hunk is decided.
+    fn show_current(&mut self) -> bool {
+        let total = self.review.hunks.len();
+        let Some((index, file, hunk)) = self.review.current() else {
+            return false;
+        };
+        let diff = FileDiff {
+            path: file.path.clone(),
+            change: FileChange::Modified,
+            binary: false,
+            hunks: vec![hunk.clone()],
+        };
+        self.view = DiffViewerState::new(
+            format!("Suggested edit {}/{}", index + 1, total),
+            vec![diff],
+        );
+        true
+    }
+
+    /// New side of the current hunk, as offered for rewriting in the editor.
+    pub fn current_text(&self) -> Option<String> {
+        self.review
+            .current()
+            .map(|(_, _, hunk)| suggest::hunk_text(hunk))
+    }
+}
+
+impl App {
+    /// Turn suggest mode of this tab on or off (`/suggest [on|off]`; toggles without
+    /// argument). The new state is shown as a tool log line, and in the header while on.
+    pub(crate) fn suggest_command(&mut self, input: &str) {
+        let active = match input.trim().to_ascii_lowercase().as_str() {
+            "" => !self.tool_modes.suggest,
+            "on" => true,
+            "off" => false,
+            other => {
+                self.push_tool_log(format!("Usage: /suggest [on|off] (got '{}')", other));
+                return;
+            }
+        };
+        self.tool_modes.suggest = active;
+        self.push_tool_log(if active {
+            "Suggest mode on: Edit calls are staged, and you review their hunks when the turn ends — y accept, n reject, e rewrite in $EDITOR. /suggest off to apply edits directly again.".to_string()
+        } else {
+            "Suggest mode off: edits apply directly again.".to_string()
+        });
+    }
+
+    /// Open the review popup on the edits staged during the turn that just ended.
+    pub(crate) fn open_suggestion_review(&mut self, set: ChangeSet) {
+        self.suggestion_review = SuggestionReviewState::new(set);
+    }
+
+    /// Record `decision` for the current hunk, or for every hunk left when `rest` is set.
+    /// Once all are decided, the accepted hunks are written (as one undo step), the popup
+    /// closes, and the outcome is added to the tool log and reported to the model.
+    pub(crate) fn decide_suggestion(
+        &mut self,
+        decision: Decision,
+        rest: bool,
+        api_messages: &mut Option<Vec<Value>>,
+    ) {
+        let Some(state) = self.suggestion_review.as_mut() else {
+            return;
+        };
+        if rest {
+            state.review.decide_rest(decision);
+        } else {
+            state.review.decide(decision);
+        }
+        if state.show_current() {
+            return;
+        }
+        let Some(state) = self.suggestion_review.take() else {
+            return;
+        };
+        let mut batch = UndoBatch::default();
+        let report = match state.review.apply(Some(&mut batch)) {
+            Ok(outcome) => {
+                self.push_tool_log(outcome.summary());
+                outcome.report()
+            }
+            Err(e) => {
+                self.push_tool_log(format!("Suggested edits not applied: {}", e));
+                suggest::failure_report(&e)
+            }
+        };
+        if !batch.is_empty()
+            && let Ok(mut stack) = self.undo_stack.lock()
+        {
+            stack.push_batch(batch);
+        }
+        if let Some(messages) = api_messages.as_mut() {
+            messages.push(json!({ "role": "user", "content": report }));
+        }
+        self.mark_dirty();
+    }
+}
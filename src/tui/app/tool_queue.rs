@@ -0,0 +1,93 @@
+//! Tool call queue for the current model response: per-call status and timing.
+
+use std::time::{Duration, Instant};
+
+use crate::core::llm::ToolEvent;
+
+use super::App;
+
+/// Execution state of a queued tool call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+/// One tool call in the queue widget.
+#[derive(Clone, Debug)]
+pub struct ToolQueueEntry {
+    pub id: String,
+    pub name: String,
+    pub preview: String,
+    pub status: ToolStatus,
+    pub started_at: Option<Instant>,
+    /// Set when finished (measured by the agent loop).
+    pub duration: Option<Duration>,
+}
+
+impl ToolQueueEntry {
+    /// Elapsed time: final duration when finished, live time while running.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.duration
+            .or_else(|| self.started_at.map(|t| t.elapsed()))
+    }
+}
+
+impl App {
+    /// Update the tool queue from an agent loop event. `Queued` replaces the previous batch.
+    pub(crate) fn apply_tool_event(&mut self, event: ToolEvent) {
+        match event {
+            ToolEvent::Queued(calls) => {
+                self.tool_queue = calls
+                    .into_iter()
+                    .map(|c| ToolQueueEntry {
+                        id: c.id,
+                        name: c.name,
+                        preview: c.preview,
+                        status: ToolStatus::Pending,
+                        started_at: None,
+                        duration: None,
+                    })
+                    .collect();
+            }
+            ToolEvent::Started { id } => {
+                if let Some(entry) = self.tool_queue.iter_mut().find(|e| e.id == id) {
+                    entry.status = ToolStatus::Running;
+                    entry.started_at = Some(Instant::now());
+                }
+            }
+            ToolEvent::Finished {
+                id,
+                success,
+                duration,
+                view,
+            } => {
+                let entry = self.tool_queue.iter_mut().find(|e| e.id == id);
+                let tool = entry.as_ref().map(|e| e.name.clone()).unwrap_or_default();
+                if let Some(entry) = entry {
+                    entry.status = if success {
+                        ToolStatus::Done
+                    } else {
+                        ToolStatus::Failed
+                    };
+                    entry.duration = Some(duration);
+                }
+                if let Some(view) = view {
+                    self.push_tool_result(tool, view);
+                }
+            }
+        }
+    }
+
+    /// Queue to display: only when the current batch has several calls.
+    /// The queue is cleared when the chat result arrives.
+    pub(crate) fn visible_tool_queue(&self) -> &[ToolQueueEntry] {
+        if self.tool_queue.len() > 1 {
+            &self.tool_queue
+        } else {
+            &[]
+        }
+    }
+}
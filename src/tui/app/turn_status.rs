@@ -0,0 +1,138 @@
+//! Live status of the turn in flight: current activity and elapsed time (status line).
+
+use std::time::{Duration, Instant};
+
+use crate::core::llm::ProgressEvent;
+
+use super::App;
+
+/// What the turn is doing right now.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Activity {
+    /// Waiting for the model's reply.
+    Thinking,
+    /// The reply is streaming in.
+    Responding,
+    /// A tool call is streaming in; `summary` is its progress line (e.g. "Preparing Write:
+    /// src/foo.rs (2.1 KB so far)").
+    Preparing { name: String, summary: String },
+    /// A tool call is running: the `index`-th of the `total` calls in the response.
+    Tool {
+        name: String,
+        preview: String,
+        index: usize,
+        total: usize,
+    },
+    /// A failed request is sent again at `until`.
+    Retrying {
+        until: Instant,
+        attempt: u32,
+        max_attempts: u32,
+    },
+}
+
+/// Status line state, present while a request is in flight.
+#[derive(Clone, Debug)]
+pub struct TurnStatus {
+    pub started: Instant,
+    pub activity: Activity,
+    pub activity_started: Instant,
+}
+
+impl TurnStatus {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        TurnStatus {
+            started: now,
+            activity: Activity::Thinking,
+            activity_started: now,
+        }
+    }
+
+    /// Switch to `activity`; its timer restarts unless it is already the current one.
+    fn set(&mut self, activity: Activity) {
+        if self.activity != activity {
+            self.activity = activity;
+            self.activity_started = Instant::now();
+        }
+    }
+
+    /// Time spent on the current activity.
+    pub fn activity_elapsed(&self) -> Duration {
+        self.activity_started.elapsed()
+    }
+}
+
+impl Default for TurnStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    /// Apply a progress event from the agent loop: update the status line, and log tool
+    /// calls, retries, and notices. Model calls, continuations, and tool calls streaming in
+    /// only show in the status line.
+    pub(crate) fn apply_progress(&mut self, event: ProgressEvent) {
+        let status = self.turn_status.get_or_insert_with(TurnStatus::new);
+        match &event {
+            ProgressEvent::CallingModel
+            | ProgressEvent::StreamingFallback
+            | ProgressEvent::Continuing { .. }
+            | ProgressEvent::InvalidStructuredOutput { .. } => status.set(Activity::Thinking),
+            ProgressEvent::Tool {
+                name,
+                preview,
+                index,
+                total,
+                ..
+            } => status.set(Activity::Tool {
+                name: name.clone(),
+                preview: preview.clone(),
+                index: *index,
+                total: *total,
+            }),
+            ProgressEvent::Retrying {
+                delay,
+                attempt,
+                max_attempts,
+            } => status.set(Activity::Retrying {
+                until: Instant::now() + *delay,
+                attempt: *attempt,
+                max_attempts: *max_attempts,
+            }),
+            ProgressEvent::PreparingTool { name, .. } => {
+                let activity = Activity::Preparing {
+                    name: name.clone(),
+                    summary: event.to_string(),
+                };
+                // The same call growing keeps its timer.
+                if matches!(&status.activity, Activity::Preparing { name: current, .. } if current == name)
+                {
+                    status.activity = activity;
+                } else {
+                    status.set(activity);
+                }
+            }
+            ProgressEvent::ToolsUnsupported { .. } => {}
+        }
+        // A continuation streams into the reply in progress: a log line would split it. A
+        // call streaming in is logged when it runs.
+        if !matches!(
+            event,
+            ProgressEvent::CallingModel
+                | ProgressEvent::Continuing { .. }
+                | ProgressEvent::PreparingTool { .. }
+        ) {
+            self.remove_last_if_empty_assistant();
+            self.push_tool_log(event.to_string());
+        }
+    }
+
+    /// Note that reply text is streaming in.
+    pub(crate) fn mark_responding(&mut self) {
+        if let Some(status) = self.turn_status.as_mut() {
+            status.set(Activity::Responding);
+        }
+    }
+}
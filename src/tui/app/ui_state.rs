@@ -0,0 +1,55 @@
+//! View state saved per conversation (scroll position, expanded tool results, split pane,
+//! code block wrapping) when leaving it, and restored when it is reopened from history.
+
+use crate::core::history::{self, UiState};
+
+use super::{App, ChatMessage, ScrollPosition};
+
+impl App {
+    /// How the current conversation is displayed. A scroll position at the bottom is saved
+    /// as following the latest message.
+    fn ui_state(&self) -> UiState {
+        UiState {
+            scroll_line: match self.scroll {
+                ScrollPosition::Line(line) if line < self.last_max_scroll => Some(line),
+                _ => None,
+            },
+            expanded_tool_results: self
+                .messages
+                .iter()
+                .enumerate()
+                .filter_map(|(i, m)| {
+                    matches!(m, ChatMessage::ToolResult { expanded: true, .. }).then_some(i)
+                })
+                .collect(),
+            split_pane: self.split_pane,
+            truncate_code: self.truncate_code,
+        }
+    }
+
+    /// Save the view state of the current conversation, if it was saved to history.
+    pub(crate) fn save_ui_state(&self) {
+        let Some(id) = self.conversation_id() else {
+            return;
+        };
+        if let Err(e) = history::save_ui_state(id, &self.ui_state()) {
+            log::warn!("Cannot save the view state of conversation {}: {}", id, e);
+        }
+    }
+
+    /// Apply the view state saved for a conversation just loaded; indices no longer
+    /// pointing at a tool result are skipped.
+    pub(crate) fn restore_ui_state(&mut self, state: &UiState) {
+        self.scroll = match state.scroll_line {
+            Some(line) => ScrollPosition::Line(line),
+            None => ScrollPosition::Bottom,
+        };
+        for &idx in &state.expanded_tool_results {
+            if let Some(ChatMessage::ToolResult { expanded, .. }) = self.messages.get_mut(idx) {
+                *expanded = true;
+            }
+        }
+        self.split_pane = state.split_pane;
+        self.set_truncate_code(state.truncate_code);
+    }
+}
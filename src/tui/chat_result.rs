@@ -1,17 +1,33 @@
 //! Handling of chat completion results and conversation save.
 
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use serde_json::Value;
+use tokio::runtime::Runtime;
 
 use crate::core::config::Config;
+use crate::core::desktop::{self, TurnOutcome};
 use crate::core::history::{self, first_message_preview};
 use crate::core::llm;
+use crate::core::spend;
 
 use super::app;
 use super::constants;
 
-const SAVE_ERROR_TOAST_DURATION: Duration = Duration::from_secs(4);
+pub(super) const SAVE_ERROR_TOAST_DURATION: Duration = Duration::from_secs(4);
+/// How long the toast shows after waiting for another instance's history lock.
+pub(super) const HISTORY_BUSY_TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// Notice when another instance saved the conversation since it was loaded here.
+const SAVED_AS_COPY_NOTICE: &str = "Another instance saved this conversation meanwhile: this version was saved as a new conversation (\"(copy)\" in /history), the other one is unchanged.";
+/// Notice when the history lock of another instance made a save give up.
+const HISTORY_LOCKED_NOTICE: &str = "Not saved: another instance is holding the conversation history. The conversation is saved again with the next change.";
+
+/// Notice under a reply cut off by the output token limit.
+const TRUNCATED_NOTICE: &str = "Response truncated: the model reached its output token limit. /continue picks up where it stopped.";
 
 /// When the assistant returns empty content after tool execution, use the last tool result.
 fn last_tool_result(messages: &[Value]) -> Option<String> {
@@ -24,6 +40,132 @@ fn last_tool_result(messages: &[Value]) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
+/// Ask for a generated title once a new conversation has its first exchange saved.
+fn request_title(app: &mut app::App, id: &str, title: &str, persisted: &[Value]) {
+    if let Some((prompt, answer)) = history::first_exchange(persisted) {
+        app.title_request = Some(app::TitleRequest {
+            conversation_id: id.to_string(),
+            saved_title: title.to_string(),
+            prompt,
+            answer,
+        });
+    }
+}
+
+/// Generate the title of `request` in the background. Returns None when titles are
+/// disabled or a spend cap is reached. The receiver gets the new title, if any.
+pub(super) fn spawn_title_generation(
+    request: app::TitleRequest,
+    config: Arc<Config>,
+    rt: &Arc<Runtime>,
+) -> Option<mpsc::Receiver<Option<(app::TitleRequest, String)>>> {
+    config.title_model.as_ref()?;
+    if spend::blocking_limit(&config.spend_limits).is_some() {
+        return None;
+    }
+    let (tx, rx) = mpsc::channel();
+    let rt_clone = Arc::clone(rt);
+    thread::spawn(move || {
+        let result = rt_clone.block_on(llm::generate_title(
+            config.as_ref(),
+            &request.prompt,
+            &request.answer,
+        ));
+        let title = match result {
+            Ok(title) => title,
+            Err(e) => {
+                log::warn!("Title generation failed: {}", e);
+                None
+            }
+        };
+        let _ = tx.send(title.map(|t| (request, t)));
+    });
+    Some(rx)
+}
+
+/// Save a generated title unless the conversation was renamed or deleted meanwhile, and
+/// show it in the history selector when open.
+pub(super) fn apply_generated_title(app: &mut app::App, request: &app::TitleRequest, title: &str) {
+    let unchanged = history::list_conversations().is_ok_and(|list| {
+        list.iter()
+            .any(|c| c.id == request.conversation_id && c.title == request.saved_title)
+    });
+    if !unchanged {
+        return;
+    }
+    if let Err(e) = history::rename_conversation(&request.conversation_id, title) {
+        log::warn!("Failed to save generated title: {}", e);
+        return;
+    }
+    if let Some(selector) = app.history_selector.as_mut()
+        && let Some(meta) = selector
+            .conversations
+            .iter_mut()
+            .find(|c| c.id == request.conversation_id)
+    {
+        meta.title = title.to_string();
+    }
+}
+
+/// Continue `existing` instead of saving the current conversation as a new entry: append
+/// the current messages to it, save, and load the merged conversation.
+pub(super) fn continue_existing_conversation(
+    app: &mut app::App,
+    api_messages: &mut Option<Vec<Value>>,
+    existing: &history::ConversationMeta,
+    config: &Config,
+) {
+    let Some(mut merged) = history::load_conversation(&existing.id) else {
+        // Deleted or unreadable meanwhile: keep the conversation as a new entry.
+        save_conversation_if_dirty(app, api_messages, config);
+        return;
+    };
+    merged.extend(app::App::messages_to_persist_format(
+        &app.messages,
+        &app.message_timestamps,
+        &app.message_usage,
+        &app.pinned_messages,
+        &app.message_reactions,
+    ));
+    match history::save_conversation(
+        Some(&existing.id),
+        &existing.title,
+        &merged,
+        &app.conversation_settings(),
+        config,
+    ) {
+        Ok(id) => {
+            app.set_messages_from_api(&merged, Some(existing.updated_at));
+            app.set_conversation_id(Some(id));
+            app.clear_dirty();
+            app.scroll = app::ScrollPosition::Bottom;
+            let api_only = history::api_messages_from_persisted(&merged);
+            app.token_usage = Some(llm::TokenUsage::estimated_from_messages(&api_only));
+            *api_messages = Some(api_only);
+        }
+        Err(e) => report_save_error(app, &e),
+    }
+}
+
+/// Adopt `id` as the conversation ID after a save, and tell when the save went to a copy
+/// because another instance changed the conversation (the ID then differs).
+pub(super) fn conversation_saved(app: &mut app::App, id: String) {
+    if app.conversation_id().is_some_and(|previous| previous != id) {
+        app.push_tool_log(SAVED_AS_COPY_NOTICE.to_string());
+    }
+    app.set_conversation_id(Some(id));
+}
+
+/// Log a failed save and surface it with a toast, and a notice when another instance held
+/// the history lock.
+pub(super) fn report_save_error(app: &mut app::App, e: &std::io::Error) {
+    log::warn!("Failed to save conversation: {}", e);
+    if history::is_lock_timeout(e) {
+        app.push_tool_log(HISTORY_LOCKED_NOTICE.to_string());
+    }
+    app.set_save_error_toast(Instant::now() + SAVE_ERROR_TOAST_DURATION);
+}
+
 /// Save the current conversation if it has unsaved changes.
 /// Logs and surfaces save errors via a toast.
 /// Persists app.messages (including ToolLog) so tool logs are visible when re-opening.
@@ -35,21 +177,59 @@ pub(super) fn save_conversation_if_dirty(
     if !app.is_dirty() {
         return;
     }
-    let msgs = app::App::messages_to_persist_format(&app.messages, &app.message_timestamps);
+    let msgs = app::App::messages_to_persist_format(
+        &app.messages,
+        &app.message_timestamps,
+        &app.message_usage,
+        &app.pinned_messages,
+        &app.message_reactions,
+    );
     if msgs.is_empty() {
         return;
     }
     let title = first_message_preview(&msgs, constants::TITLE_PREVIEW_MAX_LEN);
-    match history::save_conversation(app.conversation_id(), &title, &msgs, config) {
+    let is_new = app.conversation_id().is_none();
+    match history::save_conversation(
+        app.conversation_id(),
+        &title,
+        &msgs,
+        &app.conversation_settings(),
+        config,
+    ) {
         Ok(id) => {
-            app.set_conversation_id(Some(id));
+            if is_new {
+                request_title(app, &id, &title, &msgs);
+            }
+            conversation_saved(app, id);
             app.clear_dirty();
         }
-        Err(e) => {
-            log::warn!("Failed to save conversation: {}", e);
-            app.set_save_error_toast(Instant::now() + SAVE_ERROR_TOAST_DURATION);
-        }
+        Err(e) => report_save_error(app, &e),
+    }
+}
+
+/// Send a desktop notification for a turn that ended while the terminal was unfocused, when
+/// it ran longer than `config.desktop_notify_after` (cancelled turns are not reported).
+pub(super) fn notify_desktop(
+    app: &app::App,
+    config: &Config,
+    result: &Result<llm::ChatResult, llm::ChatError>,
+) {
+    let (Some(threshold), Some(status)) = (config.desktop_notify_after, &app.turn_status) else {
+        return;
+    };
+    let elapsed = status.started.elapsed();
+    if app.unfocused_since.is_none() || elapsed < threshold {
+        return;
     }
+    let outcome = match result {
+        Ok(llm::ChatResult::Complete { content, .. }) => TurnOutcome::Finished(content.clone()),
+        Ok(llm::ChatResult::NeedsConfirmation { action, .. }) => {
+            TurnOutcome::NeedsConfirmation(action.to_string())
+        }
+        Err(llm::ChatError::Cancelled) => return,
+        Err(e) => TurnOutcome::Failed(e.to_string()),
+    };
+    desktop::send(&desktop::message(&outcome, elapsed));
 }
 
 /// Process a chat result: update app state, show confirmation popup, or display error.
@@ -66,6 +246,12 @@ pub(super) fn handle_chat_result(
             tool_log,
             messages,
             usage,
+            message_usage,
+            truncated,
+            annotations,
+            save_blocked,
+            structured,
+            suggestions,
         }) => {
             app.token_usage = Some(usage);
             if !tool_log_already_streamed {
@@ -73,36 +259,79 @@ pub(super) fn handle_chat_result(
                     app.push_tool_log(line);
                 }
             }
-            let display_content = if content.trim().is_empty() {
+            let display_content = if let Some(value) = structured {
+                // Structured output shows as a highlighted JSON block.
+                let json = serde_json::to_string_pretty(&value).unwrap_or(content);
+                format!("```json\n{}\n```", json)
+            } else if content.trim().is_empty() {
                 last_tool_result(&messages).unwrap_or(content)
             } else {
                 content
             };
             app.replace_or_push_assistant(display_content);
+            app.set_last_assistant_usage(message_usage);
+            if truncated {
+                app.push_tool_log(TRUNCATED_NOTICE.to_string());
+            }
+            for note in annotations {
+                app.push_tool_log(note);
+            }
+            if let Some(set) = suggestions {
+                app.open_suggestion_review(set);
+            }
+            // Tools may have edited pinned files during the turn.
+            app.refresh_pins();
             app.scroll = app::ScrollPosition::Bottom;
-            let to_save =
-                app::App::messages_to_persist_format(&app.messages, &app.message_timestamps);
+            if let Some(reason) = save_blocked {
+                app.push_tool_log(format!("Conversation not saved: {}", reason));
+                app.clear_dirty();
+                *api_messages = Some(messages);
+                return;
+            }
+            let to_save = app::App::messages_to_persist_format(
+                &app.messages,
+                &app.message_timestamps,
+                &app.message_usage,
+                &app.pinned_messages,
+                &app.message_reactions,
+            );
+            // First save of a new conversation: offer to continue a near-duplicate instead.
+            if app.conversation_id().is_none() && !app.duplicate_checked {
+                app.duplicate_checked = true;
+                if let Some(existing) = history::find_similar_conversation(&to_save) {
+                    app.duplicate_popup = Some(app::DuplicatePopupState { existing });
+                    *api_messages = Some(messages);
+                    return;
+                }
+            }
             let title = first_message_preview(&to_save, constants::TITLE_PREVIEW_MAX_LEN);
-            match history::save_conversation(app.conversation_id(), &title, &to_save, config) {
+            let is_new = app.conversation_id().is_none();
+            match history::save_conversation(
+                app.conversation_id(),
+                &title,
+                &to_save,
+                &app.conversation_settings(),
+                config,
+            ) {
                 Ok(id) => {
-                    app.set_conversation_id(Some(id));
+                    if is_new {
+                        request_title(app, &id, &title, &to_save);
+                    }
+                    conversation_saved(app, id);
                     app.clear_dirty();
                 }
-                Err(e) => {
-                    log::warn!("Failed to save conversation: {}", e);
-                    app.set_save_error_toast(std::time::Instant::now() + SAVE_ERROR_TOAST_DURATION);
-                }
+                Err(e) => report_save_error(app, &e),
             }
             *api_messages = Some(messages);
         }
-        Ok(llm::ChatResult::NeedsConfirmation { command, state }) => {
-            app.confirm_popup = Some(app::ConfirmPopup { command, state });
+        Ok(llm::ChatResult::NeedsConfirmation { action, state }) => {
+            app.confirm_popup = Some(app::ConfirmPopup::new(action, *state));
         }
         Err(llm::ChatError::Cancelled) => {
             app.append_cancelled_notice();
         }
         Err(ref e) => {
-            app.replace_or_push_assistant(format!("Error: {}", e));
+            app.push_error(e);
             app.scroll = app::ScrollPosition::Bottom;
         }
     }
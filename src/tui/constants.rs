@@ -12,7 +12,7 @@ pub(super) const ACCENT_SECONDARY: Color = Color::Rgb(126, 200, 227);
 pub(super) const CUSTOM_COMMAND_COLOR: Color = Color::Rgb(255, 179, 71);
 
 /// Actions below input: Ask (explanation), Build (writing / files, bash, etc.).
-pub(super) const SUGGESTIONS: &[&str] = &["Ask", "Build"];
+pub(super) const SUGGESTIONS: &[&str] = &["Ask", "Build", "Plan"];
 
 /// Event poll timeout in milliseconds (main loop).
 pub(crate) const EVENT_POLL_TIMEOUT_MS: u64 = 100;
@@ -30,6 +30,9 @@ pub(crate) const SCROLL_LINES_SMALL: usize = 3;
 /// Scroll amount for PageUp/PageDown.
 pub(crate) const SCROLL_LINES_PAGE: usize = 10;
 
+/// Horizontal scroll amount of a truncated code block (Left/Right).
+pub(crate) const SCROLL_COLUMNS: isize = 8;
+
 /// Input textarea height (number of visible lines; -2 for block borders = inner lines).
 pub(crate) const INPUT_LINES: u16 = 7;
 
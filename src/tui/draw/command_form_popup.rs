@@ -25,8 +25,11 @@ fn field_label(f: CommandFormField, value: &str, focused: bool) -> (String, bool
         CommandFormField::Description => "Description",
         CommandFormField::Prompt => "Prompt",
         CommandFormField::Mode => "Mode",
+        CommandFormField::Model => "Model",
     };
-    let display = if value.is_empty() && f != CommandFormField::Mode {
+    let display = if value.is_empty() && f == CommandFormField::Model {
+        "current model (optional)".to_string()
+    } else if value.is_empty() && f != CommandFormField::Mode {
         format!("{}...", label)
     } else {
         value.to_string()
@@ -93,7 +96,7 @@ pub(crate) fn draw_command_form_popup(
                 crate::tui::app::CommandFormMode::Create => " Create command ",
                 crate::tui::app::CommandFormMode::Update { .. } => " Update command ",
             };
-            let rect = popup_area(area, 70, 55);
+            let rect = popup_area(area, 70, 60);
             (title, rect)
         }
     };
@@ -112,6 +115,7 @@ pub(crate) fn draw_command_form_popup(
             Constraint::Length(2),
             Constraint::Length(2),
             Constraint::Length(2),
+            Constraint::Length(2),
             Constraint::Min(4),
             Constraint::Length(2),
             Constraint::Length(1),
@@ -142,6 +146,12 @@ pub(crate) fn draw_command_form_popup(
         state.focused_field == CommandFormField::Mode,
     );
 
+    let (model_str, model_focused) = field_label(
+        CommandFormField::Model,
+        &state.model,
+        state.focused_field == CommandFormField::Model,
+    );
+
     let focus_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
     let normal_style = Style::default();
 
@@ -178,6 +188,17 @@ pub(crate) fn draw_command_form_popup(
         ))),
         chunks[2],
     );
+    f.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            model_str,
+            if model_focused {
+                focus_style
+            } else {
+                normal_style
+            },
+        ))),
+        chunks[3],
+    );
 
     let prompt_para = Paragraph::new(Line::from(Span::styled(
         prompt_display,
@@ -188,7 +209,7 @@ pub(crate) fn draw_command_form_popup(
         },
     )))
     .wrap(ratatui::widgets::Wrap { trim: true });
-    f.render_widget(prompt_para, chunks[3]);
+    f.render_widget(prompt_para, chunks[4]);
 
     if let Some(ref err) = state.error {
         f.render_widget(
@@ -196,7 +217,7 @@ pub(crate) fn draw_command_form_popup(
                 err.as_str(),
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ))),
-            chunks[4],
+            chunks[5],
         );
     }
 
@@ -210,5 +231,5 @@ pub(crate) fn draw_command_form_popup(
         Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
         Span::raw("cancel"),
     ]));
-    f.render_widget(hint, chunks[5]);
+    f.render_widget(hint, chunks[6]);
 }
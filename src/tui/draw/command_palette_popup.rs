@@ -0,0 +1,109 @@
+//! Command palette popup (Ctrl+K): a filter line, then the matching actions with their key
+//! binding or slash command on the right.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::CommandPaletteState;
+use super::super::constants::ACCENT;
+use super::fuzzy_matches;
+use crate::core::text::{display_width, truncate_end};
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+pub(crate) fn draw_command_palette_popup(f: &mut Frame, area: Rect, state: &CommandPaletteState) {
+    let popup_rect = popup_area(area, 60, 60);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Commands (Ctrl+K) ");
+    let inner = block.inner(popup_rect);
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(block, popup_rect);
+
+    let [filter_area, list_area, footer] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let filter_line = if state.filter.is_empty() {
+        Line::from(vec![
+            Span::styled(" > ", Style::default().fg(ACCENT)),
+            Span::styled("Type to search actions and commands", dim),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled(" > ", Style::default().fg(ACCENT)),
+            Span::raw(state.filter.clone()),
+            Span::styled("_", dim),
+        ])
+    };
+    f.render_widget(Paragraph::new(filter_line), filter_area);
+
+    let matches = state.matches();
+    let rows = list_area.height as usize;
+    let width = list_area.width as usize;
+    let lines: Vec<Line> = if matches.is_empty() {
+        vec![Line::from(Span::styled(" No matching action", dim))]
+    } else {
+        let first = (state.selected + 1).saturating_sub(rows);
+        matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(i, entry)| {
+                let selected = i == state.selected;
+                let style = if selected {
+                    Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                // Marker, label, at least two spaces, hint, one space of margin.
+                let hint_width = display_width(&entry.hint);
+                let label = truncate_end(&entry.label, width.saturating_sub(hint_width + 5));
+                let gap = width.saturating_sub(display_width(&label) + hint_width + 3);
+                let mut spans = vec![Span::raw(if selected { " ▸ " } else { "   " })];
+                spans.extend(fuzzy_matches::highlight(
+                    &label,
+                    &fuzzy_matches::positions(&state.filter, &label),
+                    style,
+                ));
+                spans.push(Span::raw(" ".repeat(gap)));
+                spans.extend(fuzzy_matches::highlight(
+                    &entry.hint,
+                    &fuzzy_matches::positions(&state.filter, &entry.hint),
+                    dim,
+                ));
+                Line::from(spans)
+            })
+            .collect()
+    };
+    f.render_widget(Paragraph::new(lines), list_area);
+
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" ↑↓ ", dim),
+            Span::raw("select  "),
+            Span::styled("Enter ", dim),
+            Span::raw("run  "),
+            Span::styled("Tab ", dim),
+            Span::raw("add arguments  "),
+            Span::styled("Esc ", dim),
+            Span::raw("close"),
+        ])),
+        footer,
+    );
+}
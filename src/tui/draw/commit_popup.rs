@@ -0,0 +1,108 @@
+//! Draw commit popup (/commit): branch, staged diff summary, editable message.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use super::super::app::CommitPopupState;
+use super::super::constants::ACCENT;
+use crate::core::git_commit;
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+fn section_title(title: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        title.to_string(),
+        Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+    ))
+}
+
+pub(crate) fn draw_commit_popup(f: &mut Frame, area: Rect, state: &CommitPopupState) {
+    let popup_rect = popup_area(area, 70, 70);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Commit ");
+    let inner = block.inner(popup_rect);
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(block, popup_rect);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+
+    let changes = &state.changes;
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Branch   ", Style::default().fg(Color::DarkGray)),
+        Span::raw(
+            changes
+                .branch
+                .clone()
+                .unwrap_or_else(|| "(detached HEAD)".to_string()),
+        ),
+        Span::styled("  in ", Style::default().fg(Color::DarkGray)),
+        Span::raw(changes.worktree.display().to_string()),
+    ])];
+
+    lines.push(Line::from(""));
+    lines.push(section_title("Staged changes"));
+    for stat_line in changes.stat.lines() {
+        lines.push(Line::from(Span::styled(
+            stat_line.to_string(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(section_title("Message"));
+    let message_lines: Vec<&str> = state.message.split('\n').collect();
+    let last = message_lines.len() - 1;
+    for (i, message_line) in message_lines.iter().enumerate() {
+        let mut spans = vec![Span::raw(message_line.to_string())];
+        if i == last {
+            spans.push(Span::styled("▏", Style::default().fg(ACCENT)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    if let Some(ref error) = state.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let para = Paragraph::new(lines).wrap(Wrap { trim: false });
+    f.render_widget(para, chunks[0]);
+
+    let hint = if state.confirming {
+        Line::from(vec![
+            Span::styled("Run ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}?  ", git_commit::shell_command(changes))),
+            Span::styled("Enter ", Style::default().fg(Color::DarkGray)),
+            Span::raw("confirm  "),
+            Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+            Span::raw("back"),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("Enter ", Style::default().fg(Color::DarkGray)),
+            Span::raw("commit  "),
+            Span::styled("Shift/Alt+Enter ", Style::default().fg(Color::DarkGray)),
+            Span::raw("newline  "),
+            Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+            Span::raw("cancel"),
+        ])
+    };
+    f.render_widget(Paragraph::new(hint), chunks[1]);
+}
@@ -0,0 +1,164 @@
+//! Draw context preview popup (/preview): system prompt sections, message count, token totals.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+
+use super::super::app::ContextPreviewState;
+use super::super::constants::ACCENT;
+use super::header::format_tokens_compact;
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+fn stat_line(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("{:<20}", label),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::raw(value),
+    ])
+}
+
+pub(crate) fn draw_context_preview_popup(
+    f: &mut Frame,
+    area: Rect,
+    state: &mut ContextPreviewState,
+) {
+    let popup_rect = popup_area(area, 70, 70);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Next request preview ");
+    let inner = block.inner(popup_rect);
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(block, popup_rect);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(inner);
+
+    let preview = &state.preview;
+    let total = preview.total_tokens() as u64;
+    let total_text = if preview.context_length > 0 {
+        format!(
+            "~{} / {} ({:.0}%)",
+            format_tokens_compact(total),
+            format_tokens_compact(preview.context_length),
+            total as f64 / preview.context_length as f64 * 100.0
+        )
+    } else {
+        format!("~{}", format_tokens_compact(total))
+    };
+
+    let mut lines = vec![
+        stat_line("Messages", preview.message_count.to_string()),
+        stat_line(
+            "System prompt",
+            format!("~{}", format_tokens_compact(preview.system_tokens as u64)),
+        ),
+        stat_line(
+            "History + prompt",
+            format!("~{}", format_tokens_compact(preview.message_tokens as u64)),
+        ),
+        stat_line(
+            &format!("Tools ({})", preview.tool_count),
+            format!("~{}", format_tokens_compact(preview.tool_tokens as u64)),
+        ),
+        Line::from(vec![
+            Span::styled(format!("{:<20}", "Total"), Style::default().fg(ACCENT)),
+            Span::styled(total_text, Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "System prompt sections",
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    for (i, (section, tokens)) in preview.sections.iter().enumerate() {
+        let expanded = state.expanded.get(i).copied().unwrap_or(false);
+        let marker = if expanded { "▾" } else { "▸" };
+        let style = if i == state.selected_index {
+            Style::default().fg(Color::Black).bg(ACCENT)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} {}{} (~{})",
+                marker,
+                section.title,
+                section
+                    .id
+                    .map(|id| format!(" [{}]", id.key()))
+                    .unwrap_or_default(),
+                format_tokens_compact(*tokens as u64)
+            ),
+            style,
+        )));
+        if expanded {
+            for content_line in section.content.lines() {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", content_line),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+    }
+
+    // Registered sections not sent: nothing to include, or left out by configuration.
+    for (ids, reason) in [
+        (&preview.empty_sections, "empty"),
+        (
+            &preview.disabled_sections,
+            "off (MY_OPEN_CLAUDE_PROMPT_SECTIONS)",
+        ),
+    ] {
+        for id in ids.iter() {
+            lines.push(Line::from(Span::styled(
+                format!("  {} [{}] — {}", id.title(), id.key(), reason),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+    }
+
+    // Keep the last page in view: PgDn past the end would scroll into a blank popup.
+    let width = usize::from(chunks[0].width.max(1));
+    let height: usize = lines
+        .iter()
+        .map(|line| {
+            let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+            textwrap::wrap(&text, width).len().max(1)
+        })
+        .sum();
+    let max_scroll = height.saturating_sub(usize::from(chunks[0].height));
+    state.scroll = state
+        .scroll
+        .min(u16::try_from(max_scroll).unwrap_or(u16::MAX));
+    let para = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((state.scroll, 0));
+    f.render_widget(para, chunks[0]);
+
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled("↑↓ ", Style::default().fg(Color::DarkGray)),
+        Span::raw("select  "),
+        Span::styled("Enter ", Style::default().fg(Color::DarkGray)),
+        Span::raw("expand/collapse  "),
+        Span::styled("PgUp/PgDn ", Style::default().fg(Color::DarkGray)),
+        Span::raw("scroll  "),
+        Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+        Span::raw("close"),
+    ]));
+    f.render_widget(hint, chunks[1]);
+}
@@ -0,0 +1,122 @@
+//! Code block copy popup (/copy).
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::{CodeSnippet, CopyModeState};
+use super::super::constants::ACCENT;
+use crate::core::text::truncate_end;
+
+/// Snippets shown at once; the list scrolls to keep the selection visible.
+const VISIBLE_SNIPPETS: usize = 12;
+/// Width of the language column.
+const LANG_WIDTH: usize = 10;
+
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+/// First non-blank line of the snippet, and its line count.
+fn preview(snippet: &CodeSnippet, width: usize) -> (String, String) {
+    let first = snippet
+        .code
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+    let count = snippet.code.lines().count();
+    (
+        truncate_end(first, width),
+        format!(" {} line{}", count, if count == 1 { "" } else { "s" }),
+    )
+}
+
+pub(crate) fn draw_copy_mode_popup(f: &mut Frame, area: Rect, state: &CopyModeState) {
+    let rows = state.snippets.len().min(VISIBLE_SNIPPETS);
+    let popup_rect = popup_area(area, 70, rows as u16 + 6);
+    let title = if state.marked.is_empty() {
+        format!(" Code blocks ({}) ", state.snippets.len())
+    } else {
+        format!(
+            " Code blocks ({}, {} marked) ",
+            state.snippets.len(),
+            state.marked.len()
+        )
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(title);
+    // Room for the markers, the language, the line count, and the borders.
+    let preview_width = (popup_rect.width as usize).saturating_sub(LANG_WIDTH + 22);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let mut lines = vec![Line::from("")];
+    let first = (state.selected + 1).saturating_sub(VISIBLE_SNIPPETS);
+    for (i, snippet) in state
+        .snippets
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(VISIBLE_SNIPPETS)
+    {
+        let selected = i == state.selected;
+        let preview_style = if selected {
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let lang = if snippet.lang.is_empty() {
+            "text"
+        } else {
+            snippet.lang.as_str()
+        };
+        let (text, count) = preview(snippet, preview_width);
+        lines.push(Line::from(vec![
+            Span::raw(if selected { "▸ " } else { "  " }),
+            Span::styled(
+                if state.marked.contains(&i) {
+                    "[x] "
+                } else {
+                    "[ ] "
+                },
+                Style::default().fg(ACCENT),
+            ),
+            Span::styled(
+                format!("{:<w$} ", truncate_end(lang, LANG_WIDTH), w = LANG_WIDTH),
+                dim,
+            ),
+            Span::styled(text, preview_style),
+            Span::styled(count, dim),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  ↑↓ ", dim),
+        Span::raw("select  "),
+        Span::styled("Space ", dim),
+        Span::raw("mark  "),
+        Span::styled("a ", dim),
+        Span::raw("all  "),
+        Span::styled("Enter ", dim),
+        Span::raw("copy together  "),
+        Span::styled("s ", dim),
+        Span::raw("copy one by one  "),
+        Span::styled("Esc ", dim),
+        Span::raw("close"),
+    ]));
+    lines.push(Line::from(Span::styled(
+        "  Without marks, the selected block is copied.",
+        dim,
+    )));
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(Paragraph::new(lines).block(block), popup_rect);
+}
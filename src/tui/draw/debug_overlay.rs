@@ -0,0 +1,95 @@
+//! Draw the trace debug overlay (F12): latest session trace events with timings.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::constants::ACCENT;
+use crate::core::text::truncate_end;
+use crate::core::trace::{self, TraceEvent};
+
+/// Header height kept visible above the overlay.
+const HEADER_HEIGHT: u16 = 2;
+/// Width of the event name column.
+const NAME_WIDTH: usize = 18;
+
+/// Overlay area: right half of the screen, below the header, half the height.
+fn overlay_area(area: Rect) -> Rect {
+    let width = (area.width / 2).max(40).min(area.width);
+    let height = (area.height / 2)
+        .max(8)
+        .min(area.height.saturating_sub(HEADER_HEIGHT));
+    Rect {
+        x: area.x + area.width - width,
+        y: area.y + HEADER_HEIGHT,
+        width,
+        height,
+    }
+}
+
+fn event_color(event: &TraceEvent) -> Color {
+    match event {
+        TraceEvent::ApiError { .. } => Color::Red,
+        TraceEvent::Tool { success: false, .. } => Color::Red,
+        TraceEvent::Turn { error: Some(_), .. } => Color::Red,
+        TraceEvent::Retry { .. }
+        | TraceEvent::StreamFallback { .. }
+        | TraceEvent::ContextTruncated { .. }
+        | TraceEvent::Redaction { .. } => Color::Yellow,
+        _ => Color::Reset,
+    }
+}
+
+pub(crate) fn draw_debug_overlay(f: &mut Frame, area: Rect) {
+    let rect = overlay_area(area);
+    let title = match trace::file_path().and_then(|p| p.file_name().map(|n| n.to_owned())) {
+        Some(name) => format!(" Trace · {} ", name.to_string_lossy()),
+        None => " Trace (not saved) ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(title)
+        .title_bottom(Line::from(vec![
+            Span::styled(" F12 ", Style::default().fg(Color::DarkGray)),
+            Span::raw("close "),
+        ]))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(rect);
+    f.render_widget(Clear, rect);
+    f.render_widget(block, rect);
+
+    let records = trace::recent(inner.height as usize);
+    if records.is_empty() {
+        let empty = Paragraph::new(Line::from(Span::styled(
+            "No events yet.",
+            Style::default().fg(Color::DarkGray),
+        )));
+        f.render_widget(empty, inner);
+        return;
+    }
+
+    let summary_width = (inner.width as usize).saturating_sub(NAME_WIDTH + 10);
+    let lines: Vec<Line> = records
+        .iter()
+        .map(|r| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>8.1}s ", r.elapsed_ms as f64 / 1000.0),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:<width$}", r.event.name(), width = NAME_WIDTH),
+                    Style::default().fg(ACCENT),
+                ),
+                Span::styled(
+                    truncate_end(&r.event.summary(), summary_width),
+                    Style::default().fg(event_color(&r.event)),
+                ),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,187 @@
+//! Draw the diff viewer (/review results, Write/Edit approval, suggested edits): one file at a time, hunks
+//! with old and new line numbers, removed lines red and added lines green.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::core::diff::{FileChange, FileDiff, LineKind};
+
+use super::super::app::DiffViewerState;
+use super::super::constants::{ACCENT, ACCENT_SECONDARY};
+
+pub(super) const REMOVED: Color = Color::Rgb(255, 120, 120);
+pub(super) const ADDED: Color = Color::Rgb(130, 220, 130);
+const REMOVED_BG: Color = Color::Rgb(60, 20, 20);
+pub(super) const ADDED_BG: Color = Color::Rgb(20, 50, 20);
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+/// File name line: position in the diff, path, kind of change, and line counts.
+fn file_line(state: &DiffViewerState, file: &FileDiff) -> Line<'static> {
+    let (added, removed) = file.stats();
+    let change = match &file.change {
+        FileChange::Modified => String::new(),
+        FileChange::Added => " (new)".to_string(),
+        FileChange::Deleted => " (deleted)".to_string(),
+        FileChange::Renamed { from } => format!(" (renamed from {})", from),
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("{}/{}  ", state.file_index + 1, state.files.len()),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(
+            file.path.clone(),
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(change, Style::default().fg(Color::DarkGray)),
+        Span::styled(format!("  +{}", added), Style::default().fg(ADDED)),
+        Span::styled(format!(" −{}", removed), Style::default().fg(REMOVED)),
+    ])
+}
+
+fn line_number(n: Option<usize>) -> String {
+    n.map(|n| format!("{:>5}", n))
+        .unwrap_or_else(|| " ".repeat(5))
+}
+
+/// Hunk headers and lines of `file`, with an old | new line-number gutter.
+fn body_lines(file: &FileDiff) -> Vec<Line<'static>> {
+    if file.binary {
+        return vec![Line::from(Span::styled(
+            "Binary file (not shown)",
+            Style::default().fg(Color::DarkGray),
+        ))];
+    }
+    if file.hunks.is_empty() {
+        return vec![Line::from(Span::styled(
+            "No content changes",
+            Style::default().fg(Color::DarkGray),
+        ))];
+    }
+    let gutter = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    for (i, hunk) in file.hunks.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            hunk.header.clone(),
+            Style::default().fg(ACCENT_SECONDARY),
+        )));
+        for line in &hunk.lines {
+            let (marker, style) = match line.kind {
+                LineKind::Context => (' ', Style::default()),
+                LineKind::Added => ('+', Style::default().fg(ADDED).bg(ADDED_BG)),
+                LineKind::Removed => ('-', Style::default().fg(REMOVED).bg(REMOVED_BG)),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(line_number(line.old_line), gutter),
+                Span::styled(" ", gutter),
+                Span::styled(line_number(line.new_line), gutter),
+                Span::styled(" │", gutter),
+                Span::styled(
+                    format!("{}{}", marker, line.text.replace('\t', "    ")),
+                    style,
+                ),
+            ]));
+        }
+    }
+    lines
+}
+
+/// Draw the viewer with `footer` (key hints) on its last line.
+pub(crate) fn draw_diff_viewer(
+    f: &mut Frame,
+    area: Rect,
+    state: &mut DiffViewerState,
+    footer: Line<'static>,
+) {
+    let popup_rect = popup_area(area, 90, 85);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(format!(" {} ", state.title));
+    let inner = block.inner(popup_rect);
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(block, popup_rect);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    let Some(file) = state.files.get(state.file_index) else {
+        return;
+    };
+    f.render_widget(Paragraph::new(file_line(state, file)), chunks[0]);
+
+    let lines = body_lines(file);
+    let max_scroll = lines.len().saturating_sub(chunks[2].height as usize);
+    state.scroll = state.scroll.min(max_scroll as u16);
+    f.render_widget(Paragraph::new(lines).scroll((state.scroll, 0)), chunks[2]);
+
+    f.render_widget(Paragraph::new(footer), chunks[3]);
+}
+
+/// Key hints of the /review viewer.
+pub(crate) fn review_footer(files: usize) -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut spans = Vec::new();
+    if files > 1 {
+        spans.extend([Span::styled("n/p ", dim), Span::raw("next/prev file  ")]);
+    }
+    spans.extend([
+        Span::styled("↑↓ PgUp/PgDn ", dim),
+        Span::raw("scroll  "),
+        Span::styled("Esc ", dim),
+        Span::raw("close"),
+    ]);
+    Line::from(spans)
+}
+
+/// Key hints of a Write/Edit approval.
+pub(crate) fn approval_footer() -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    Line::from(vec![
+        Span::styled("y ", Style::default().fg(ACCENT)),
+        Span::raw("approve  "),
+        Span::styled("n ", dim),
+        Span::raw("cancel  "),
+        Span::styled("↑↓ PgUp/PgDn ", dim),
+        Span::raw("scroll"),
+    ])
+}
+
+/// Key hints of the review of suggested edits.
+pub(crate) fn suggestion_footer() -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    Line::from(vec![
+        Span::styled("y ", Style::default().fg(ACCENT)),
+        Span::raw("accept  "),
+        Span::styled("n ", dim),
+        Span::raw("reject  "),
+        Span::styled("e ", dim),
+        Span::raw("rewrite  "),
+        Span::styled("a ", dim),
+        Span::raw("accept all left  "),
+        Span::styled("Esc ", dim),
+        Span::raw("reject all left  "),
+        Span::styled("↑↓ ", dim),
+        Span::raw("scroll"),
+    ])
+}
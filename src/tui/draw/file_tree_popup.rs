@@ -0,0 +1,121 @@
+//! Workspace file tree popup (Alt+F): the tree on the left, the selected file on the right.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::FileTreeState;
+use super::super::constants::ACCENT;
+use crate::core::text::truncate_end;
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+pub(crate) fn draw_file_tree_popup(f: &mut Frame, area: Rect, state: &FileTreeState) {
+    let popup_rect = popup_area(area, 85, 80);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(format!(" Files ({}) ", state.entries.len()));
+    let inner = block.inner(popup_rect);
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(block, popup_rect);
+
+    let [body, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+    let [tree_area, preview_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let rows = tree_area.height as usize;
+    let width = tree_area.width as usize;
+    let visible = state.visible();
+    let first = (state.selected + 1).saturating_sub(rows);
+    let tree_lines: Vec<Line> = visible
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .map(|(i, entry)| {
+            let selected = i == state.selected;
+            let icon = match (entry.is_dir, state.expanded.contains(&entry.path)) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                (false, _) => "  ",
+            };
+            let indent = "  ".repeat(entry.depth.saturating_sub(1));
+            let name = if entry.is_dir {
+                format!("{}/", entry.name())
+            } else {
+                entry.name().to_string()
+            };
+            let style = match (selected, entry.is_dir) {
+                (true, _) => Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+                (false, true) => Style::default().fg(Color::Blue),
+                (false, false) => Style::default(),
+            };
+            let used = indent.len() + 3;
+            Line::from(vec![
+                Span::raw(if selected { "›" } else { " " }),
+                Span::raw(indent),
+                Span::styled(icon, dim),
+                Span::styled(truncate_end(&name, width.saturating_sub(used)), style),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(tree_lines), tree_area);
+
+    let preview_block = Block::default()
+        .borders(Borders::LEFT)
+        .border_style(dim)
+        .title(match &state.preview {
+            Some((path, _)) => format!(" {} ", path),
+            None => String::new(),
+        });
+    let text_area = preview_block.inner(preview_area);
+    let preview_width = (text_area.width as usize).saturating_sub(1);
+    let preview_lines: Vec<Line> = match &state.preview {
+        Some((_, text)) => text
+            .lines()
+            .take(text_area.height as usize)
+            .map(|l| {
+                Line::from(format!(
+                    " {}",
+                    truncate_end(&l.replace('\t', "    "), preview_width)
+                ))
+            })
+            .collect(),
+        None => vec![Line::from(Span::styled(
+            " Select a file to preview it.",
+            dim,
+        ))],
+    };
+    f.render_widget(
+        Paragraph::new(preview_lines).block(preview_block),
+        preview_area,
+    );
+
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" ↑↓ ", dim),
+            Span::raw("select  "),
+            Span::styled("→← ", dim),
+            Span::raw("open/close  "),
+            Span::styled("Enter ", dim),
+            Span::raw("insert path  "),
+            Span::styled("a ", dim),
+            Span::raw("attach  "),
+            Span::styled("e ", dim),
+            Span::raw("edit  "),
+            Span::styled("Esc ", dim),
+            Span::raw("close"),
+        ])),
+        footer,
+    );
+}
@@ -0,0 +1,38 @@
+//! Highlighting of the characters a fuzzy filter matched in list rows.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Span;
+
+use crate::core::fuzzy;
+
+/// Char indices of `text` matched by `query` (empty when it does not match).
+pub(super) fn positions(query: &str, text: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    fuzzy::fuzzy_match(query, text)
+        .map(|m| m.positions)
+        .unwrap_or_default()
+}
+
+/// Spans of `text` in `style`, with the characters at `positions` (char indices) bold and
+/// underlined. Consecutive characters of the same kind share a span.
+pub(super) fn highlight(text: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let matched = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_matched = positions.contains(&i);
+        if is_matched != run_matched && !run.is_empty() {
+            let s = if run_matched { matched } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), s));
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_matched { matched } else { style }));
+    }
+    spans
+}
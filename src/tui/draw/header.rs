@@ -1,4 +1,5 @@
-//! Header: logo, conversation count, title, model name, token usage, credits.
+//! Header: logo, conversation count, title, model name, token usage, credits, and the tab
+//! bar when several tabs are open.
 
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
@@ -8,27 +9,51 @@ use ratatui::widgets::Paragraph;
 use std::time::Instant;
 
 use crate::core::app;
+use crate::core::config::ModelSource;
 use crate::core::history;
+use crate::core::remote;
+use crate::core::spend::{self, SpendStatus};
+use crate::core::text;
 
-use super::super::app::App;
+use super::super::app::{App, TabLabel};
 
 /// Start time for header animation phase (thinking spinner).
 pub(crate) static HEADER_START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
 
-/// Max width for model name in header; longer names are truncated with "…".
-const MODEL_HEADER_WIDTH: u16 = 28;
+/// Max width for model name and source in header; longer names are truncated with "…".
+const MODEL_HEADER_WIDTH: u16 = 36;
 /// Width for token usage display (e.g. "12k/128k").
 const TOKENS_HEADER_WIDTH: u16 = 14;
 /// Width for credits display in header (e.g. "$12.50" or "—" when loading).
 const CREDITS_HEADER_WIDTH: u16 = 12;
 
-/// Title text for header (used for centering). Append " *" when dirty.
+/// Title text for header (used for centering). Append " *" when dirty, the host of a
+/// remote workspace, "dry run" while tools are simulated, and "suggest" while edits are
+/// staged for review.
 pub(crate) fn title_text(app: &App) -> String {
-    if app.is_dirty() {
-        format!("{} v{} * ", app::NAME, app::VERSION)
+    let remote = remote::current()
+        .map(|r| format!("· {} (remote) ", r.host()))
+        .unwrap_or_default();
+    let dirty = if app.is_dirty() { "* " } else { "" };
+    let dry_run = if app.tool_modes.dry_run {
+        "· dry run "
     } else {
-        format!("{} v{} ", app::NAME, app::VERSION)
-    }
+        ""
+    };
+    let suggest = if app.tool_modes.suggest {
+        "· suggest "
+    } else {
+        ""
+    };
+    format!(
+        "{} v{} {}{}{}{}",
+        app::NAME,
+        app::VERSION,
+        dirty,
+        remote,
+        dry_run,
+        suggest
+    )
 }
 
 pub(crate) fn is_thinking(app: &App) -> bool {
@@ -39,7 +64,7 @@ pub(crate) fn is_thinking(app: &App) -> bool {
 }
 
 /// Format a token count in compact form: 1234 -> "1k", 128000 -> "128k", 1500000 -> "1.5M".
-fn format_tokens_compact(tokens: u64) -> String {
+pub(crate) fn format_tokens_compact(tokens: u64) -> String {
     if tokens >= 1_000_000 {
         let m = tokens as f64 / 1_000_000.0;
         if m == m.floor() {
@@ -74,13 +99,42 @@ fn token_usage_color(used: u64, total: u64) -> Color {
     }
 }
 
+/// Tabs with their number (Alt+N), title, and state: ● turn running, ? confirmation
+/// awaited, • reply not seen yet.
+fn tab_bar_line(labels: &[TabLabel], accent: Color) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (i, label) in labels.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+        }
+        let style = if label.active {
+            Style::default().fg(accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(format!(" {} {}", i + 1, label.title), style));
+        let (marker, color) = if label.needs_confirmation {
+            (" ?", Color::Red)
+        } else if label.busy {
+            (" ●", Color::Yellow)
+        } else if label.unseen {
+            (" •", accent)
+        } else {
+            ("", accent)
+        };
+        spans.push(Span::styled(marker, Style::default().fg(color)));
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
+
 pub(crate) fn draw_header(f: &mut Frame, app: &mut App, area: Rect, accent: Color) {
     let header_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Length(8),
             Constraint::Min(0),
-            Constraint::Length(MODEL_HEADER_WIDTH),
+            Constraint::Max(MODEL_HEADER_WIDTH),
             Constraint::Length(TOKENS_HEADER_WIDTH),
             Constraint::Length(CREDITS_HEADER_WIDTH),
         ])
@@ -112,37 +166,66 @@ pub(crate) fn draw_header(f: &mut Frame, app: &mut App, area: Rect, accent: Colo
     ]);
     f.render_widget(Paragraph::new(logo_line), logo_area);
 
-    let title_str = title_text(app);
-    let title_len = title_str.len() as u16;
-    let title_area = Rect {
-        x: area.x + area.width.saturating_sub(title_len) / 2,
-        y: area.y,
-        width: title_len.min(area.width),
-        height: area.height,
-    };
-    let title = Line::from(vec![Span::styled(
-        title_str,
-        Style::default().fg(accent).add_modifier(Modifier::BOLD),
-    )]);
-    f.render_widget(Paragraph::new(title), title_area);
-
-    let max_len = MODEL_HEADER_WIDTH as usize;
-    let model_display = if app.model_name.chars().count() > max_len {
-        let chars: Vec<char> = app.model_name.chars().collect();
-        let start = chars.len().saturating_sub(max_len.saturating_sub(1));
-        format!("…{}", chars[start..].iter().collect::<String>())
+    // Model name followed by where it came from (e.g. "Claude Haiku 4.5 · workspace").
+    let (model_name, model_source) = app.effective_model();
+    let model_width = model_area.width as usize;
+    let mut source_label = format!(" · {}", model_source.label());
+    if text::display_width(&source_label) >= model_width {
+        source_label.clear();
+    }
+    let max_len = model_width.saturating_sub(text::display_width(&source_label));
+    let model_display = text::truncate_start(model_name, max_len);
+    let model_start = model_area.right().saturating_sub(
+        (text::display_width(&model_display) + text::display_width(&source_label)) as u16,
+    );
+    let source_style = if matches!(model_source, ModelSource::Command(_)) {
+        Style::default().fg(accent)
     } else {
-        app.model_name.clone()
+        Style::default().fg(Color::DarkGray)
     };
-    let model_line = Line::from(Span::styled(
-        model_display,
-        Style::default().fg(Color::DarkGray),
-    ));
+    let model_line = Line::from(vec![
+        Span::styled(model_display, Style::default().fg(Color::DarkGray)),
+        Span::styled(source_label, source_style),
+    ]);
     f.render_widget(
         Paragraph::new(model_line).alignment(ratatui::layout::HorizontalAlignment::Right),
         model_area,
     );
 
+    // Title centered on the header, moved left when it would run into the model name, and
+    // left out when the terminal is too narrow for both.
+    let title_str = title_text(app);
+    let title_len = text::display_width(&title_str) as u16;
+    let centered = area.x + area.width.saturating_sub(title_len) / 2;
+    let title_x = centered.min(model_start.saturating_sub(title_len + 1));
+    if title_x >= logo_area.right() {
+        let title = Line::from(vec![Span::styled(
+            title_str,
+            Style::default().fg(accent).add_modifier(Modifier::BOLD),
+        )]);
+        f.render_widget(
+            Paragraph::new(title),
+            Rect {
+                x: title_x,
+                y: area.y,
+                width: title_len,
+                height: area.height,
+            },
+        );
+    }
+
+    if !app.tab_labels.is_empty() && area.height > 1 {
+        let tab_bar = Rect {
+            y: area.y + 1,
+            height: 1,
+            ..area
+        };
+        f.render_widget(
+            Paragraph::new(tab_bar_line(&app.tab_labels, accent)),
+            tab_bar,
+        );
+    }
+
     // Token usage display: "used/context" with color coding.
     let tokens_display = match &app.token_usage {
         Some(usage) => {
@@ -167,6 +250,23 @@ pub(crate) fn draw_header(f: &mut Frame, app: &mut App, area: Rect, accent: Colo
         tokens_area,
     );
 
+    if app.local_models || app.replay.is_some() {
+        let label = if app.replay.is_some() {
+            "replay"
+        } else {
+            "local"
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(Span::styled(
+                label,
+                Style::default().fg(Color::DarkGray),
+            )))
+            .alignment(ratatui::layout::HorizontalAlignment::Right),
+            credits_area,
+        );
+        app.credits_header_rect = None;
+        return;
+    }
     let credits_display = match &app.credit_data {
         Some((total, used)) => {
             let balance = (*total - *used).max(0.0);
@@ -174,10 +274,20 @@ pub(crate) fn draw_header(f: &mut Frame, app: &mut App, area: Rect, accent: Colo
         }
         None => "—".to_string(),
     };
+    // Spend caps: yellow from 80% of a cap, red once one is reached (unless overridden).
+    let (credits_display, credits_color) = match app.spend_status {
+        SpendStatus::Exceeded(_) if !spend::caps_overridden() => {
+            (format!("⚠ {}", credits_display), Color::Red)
+        }
+        SpendStatus::Warning(_) | SpendStatus::Exceeded(_) => {
+            (format!("⚠ {}", credits_display), Color::Yellow)
+        }
+        SpendStatus::Ok => (credits_display, accent),
+    };
     let credits_line = Line::from(Span::styled(
         credits_display,
         Style::default()
-            .fg(accent)
+            .fg(credits_color)
             .add_modifier(Modifier::UNDERLINED),
     ));
     f.render_widget(
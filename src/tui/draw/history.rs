@@ -7,29 +7,61 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState};
 
-use super::super::app::{App, ChatMessage, CopyTarget};
+use super::super::app::{
+    self, App, ChatMessage, CodeBlockRegion, CopyTarget, ErrorNotice, SearchState, TextLine,
+};
 use super::super::constants::{ACCENT, ACCENT_SECONDARY};
-use super::super::syntax::{highlight_code_line, slice_spans_by_range};
+use super::super::hyperlinks::{self, LinkRect};
+use super::super::preview::Preview;
+use super::super::shortcuts::labels;
 use super::super::text::{
-    MessageSegment, normalize_code_operators, parse_markdown_inline, parse_message_segments,
-    wrap_message,
+    MessageSegment, highlight_code_block, normalize_code_operators, parse_markdown_inline,
+    parse_message_segments, slice_spans_by_range, wrap_message, wrap_message_continued,
 };
+use super::header::format_tokens_compact;
+use super::tool_result;
+use crate::core::history::Reaction;
+use crate::core::llm::MessageUsage;
+
+/// Background of the messages selected with the keyboard (Alt+V), and of the one under the cursor.
+const SELECTED_BG: Color = Color::Rgb(35, 40, 60);
+const SELECTION_CURSOR_BG: Color = Color::Rgb(50, 58, 90);
 
 /// Repeat a character to fill width (approximate; chars may have different display widths).
 fn repeat_char(c: char, n: usize) -> String {
     std::iter::repeat_n(c, n).collect()
 }
 
+/// Annotation under an assistant reply: "1.2k↑ 430↓ · 6.4s · claude-haiku-4.5".
+/// Token counts are omitted when the API reported none.
+fn usage_annotation(usage: &MessageUsage) -> String {
+    let mut parts = Vec::with_capacity(3);
+    if usage.prompt_tokens > 0 || usage.completion_tokens > 0 {
+        parts.push(format!(
+            "{}↑ {}↓",
+            format_tokens_compact(usage.prompt_tokens),
+            format_tokens_compact(usage.completion_tokens)
+        ));
+    }
+    parts.push(format!("{:.1}s", usage.latency_ms as f64 / 1000.0));
+    let model = usage.model.rsplit('/').next().unwrap_or(&usage.model);
+    if !model.is_empty() {
+        parts.push(model.to_string());
+    }
+    parts.join(" · ")
+}
+
 /// Concatenate all span contents in a line to a plain string.
 fn line_to_string(line: &Line<'_>) -> String {
     line.spans.iter().map(|s| s.content.as_ref()).collect()
 }
 
-/// Apply selection highlight (REVERSED) to a line for columns [col_start, col_end).
-fn apply_selection_to_line(
+/// Patch the style of a line's columns [col_start, col_end) (selection, search matches).
+fn style_columns(
     line: &Line<'static>,
     col_start: usize,
     col_end: usize,
+    patch: Style,
 ) -> Line<'static> {
     if col_start >= col_end {
         return line.clone();
@@ -66,10 +98,7 @@ fn apply_selection_to_line(
                     .skip(seg_start)
                     .take(seg_end - seg_start)
                     .collect();
-                result.push(Span::styled(
-                    sel,
-                    span.style.add_modifier(Modifier::REVERSED),
-                ));
+                result.push(Span::styled(sel, span.style.patch(patch)));
             }
             if seg_end < len {
                 let after: String = s.chars().skip(seg_end).collect();
@@ -81,8 +110,40 @@ fn apply_selection_to_line(
     Line::from(result)
 }
 
+/// Highlight the search matches on rendered line `line_idx`; the current match stands out.
+fn highlight_search_matches(
+    line: Line<'static>,
+    line_idx: usize,
+    search: &SearchState,
+) -> Line<'static> {
+    let mut line = line;
+    for (i, m) in search.matches.iter().enumerate() {
+        let style = if i == search.current {
+            Style::default()
+                .fg(Color::Black)
+                .bg(ACCENT)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        };
+        for &(l, start, end) in &m.ranges {
+            if l == line_idx {
+                line = style_columns(&line, start, end, style);
+            }
+        }
+    }
+    line
+}
+
 const TOOL_LOG_PREFIX: &str = "→ ";
 
+/// Column where message text and code start, after the border and indentation ("│   ",
+/// "│ │ ").
+const MESSAGE_TEXT_COL: usize = 4;
+
+/// Clickable action in the header of Mermaid and math blocks.
+const PREVIEW_ACTION: &str = "open in browser ↗";
+
 /// Parse tool log format "→ ToolName: args" into (tool_name, args) if it matches.
 fn parse_tool_log(s: &str) -> Option<(&str, &str)> {
     let s = s.trim_start();
@@ -101,7 +162,12 @@ fn parse_tool_log(s: &str) -> Option<(&str, &str)> {
 }
 
 /// Render tool log lines with structured styling: tool name highlighted, args wrapped.
-fn add_tool_log_lines(lines: &mut Vec<Line<'static>>, s: &str, content_width: usize) {
+fn add_tool_log_lines(
+    lines: &mut Vec<Line<'static>>,
+    text_lines: &mut Vec<TextLine>,
+    s: &str,
+    content_width: usize,
+) {
     let marker_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
     let tool_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
     let args_style = Style::default().fg(ACCENT_SECONDARY);
@@ -116,30 +182,48 @@ fn add_tool_log_lines(lines: &mut Vec<Line<'static>>, s: &str, content_width: us
         let args_width = available.saturating_sub(header_char_len);
 
         let mut first_line = true;
-        for chunk in wrap_message(args, args_width.max(1)) {
+        for (chunk, continued) in wrap_message_continued(args, args_width.max(1)) {
             let mut spans = vec![Span::styled(prefix.to_string(), marker_style)];
             if first_line {
+                text_lines.push(TextLine {
+                    line: lines.len(),
+                    col: prefix_len,
+                    continued: false,
+                });
                 spans.push(Span::styled(header.to_string(), tool_style));
                 first_line = false;
             } else {
-                spans.push(Span::styled(
-                    " ".repeat(header_char_len.min(available)),
-                    Style::default(),
-                ));
+                let indent = header_char_len.min(available);
+                text_lines.push(TextLine {
+                    line: lines.len(),
+                    col: prefix_len + indent,
+                    continued,
+                });
+                spans.push(Span::styled(" ".repeat(indent), Style::default()));
             }
             spans.push(Span::styled(chunk, args_style));
             lines.push(Line::from(spans));
         }
         if first_line {
+            text_lines.push(TextLine {
+                line: lines.len(),
+                col: prefix_len,
+                continued: false,
+            });
             lines.push(Line::from(vec![
                 Span::styled(prefix.to_string(), marker_style),
                 Span::styled(format!("{} ", header), tool_style),
             ]));
         }
     } else {
-        for chunk in
-            super::super::text::wrap_message(s, content_width.saturating_sub(prefix_len).max(1))
+        for (chunk, continued) in
+            wrap_message_continued(s, content_width.saturating_sub(prefix_len).max(1))
         {
+            text_lines.push(TextLine {
+                line: lines.len(),
+                col: prefix_len,
+                continued,
+            });
             lines.push(Line::from(vec![
                 Span::styled(prefix.to_string(), marker_style),
                 Span::styled(format!("{} ", chunk), args_style),
@@ -157,16 +241,31 @@ struct MessageBlockParams<'a> {
     is_error: bool,
     is_user: bool,
     stream_cursor: bool,
+    /// Pinned message (Alt+P, /pins): marked in the top border.
+    pinned: bool,
+    /// Reaction to an assistant reply (Alt+A): its glyph in the top border.
+    reaction: Option<Reaction>,
     /// Unix timestamp (seconds) when message was created; None for loaded history.
     timestamp: Option<u64>,
+    /// Usage annotation shown in the bottom border (assistant replies).
+    annotation: Option<String>,
+    /// Code lines are truncated to the block width instead of wrapped (Alt+W).
+    truncate_code: bool,
+    /// Focused code block of this message and its first shown column (truncated code).
+    code_scroll: Option<(usize, usize)>,
 }
 
 /// Add a User or Assistant message block with borders, code blocks, and separator.
 /// Returns (start_line, end_line) for this block in the lines array.
 /// Pushes copy regions to copy_regions: code blocks first (for priority), then message fallback.
+/// Header lines of Mermaid and math blocks go to previews, code blocks to code_blocks.
+/// Text and code lines are recorded in text_lines for search.
 fn add_message_block(
     lines: &mut Vec<Line<'static>>,
     copy_regions: &mut Vec<(usize, usize, CopyTarget)>,
+    text_lines: &mut Vec<TextLine>,
+    previews: &mut Vec<(usize, Preview)>,
+    code_blocks: &mut Vec<CodeBlockRegion>,
     msg_idx: usize,
     p: MessageBlockParams<'_>,
 ) -> (usize, usize) {
@@ -190,26 +289,33 @@ fn add_message_block(
                 .map(|dt| format!(" {:02}:{:02}", dt.hour(), dt.minute()))
         })
         .unwrap_or_default();
+    let mut marker = if p.pinned { " 📌" } else { "" }.to_string();
+    if let Some(reaction) = p.reaction {
+        marker.push(' ');
+        marker.push_str(reaction.glyph());
+    }
     let top_label = if time_suffix.is_empty() {
-        format!("┌─ {} ", p.label)
+        format!("┌─ {}{} ", p.label, marker)
     } else {
-        format!("┌─ {} {} ", p.label, time_suffix.trim())
+        format!("┌─ {}{} {} ", p.label, marker, time_suffix.trim())
     };
-    let top_trail_len = p.wrap_width.saturating_sub(top_label.chars().count() + 1);
-    let top_line = format!("{}{}┐", top_label, repeat_char('─', top_trail_len.max(0)));
+    let top_trail_len = p
+        .wrap_width
+        .saturating_sub(crate::core::text::display_width(&top_label) + 1);
+    let top_line = format!("{}{}┐", top_label, repeat_char('─', top_trail_len));
     lines.push(Line::from(Span::styled(top_line, border_style)));
 
     let segments = parse_message_segments(p.content);
 
-    for segment in &segments {
+    let last_segment = segments.len().saturating_sub(1);
+    for (segment_idx, segment) in segments.iter().enumerate() {
         match segment {
             MessageSegment::Text(text) => {
                 let trimmed = text.trim();
                 if trimmed.is_empty() {
                     continue;
                 }
-                let chunks: Vec<String> = wrap_message(trimmed, p.content_width);
-                for chunk in chunks {
+                for (chunk, continued) in wrap_message_continued(trimmed, p.content_width) {
                     let (prefix, chunk_style) = if chunk.is_empty() {
                         ("  ", Style::default())
                     } else if p.is_error {
@@ -221,6 +327,11 @@ fn add_message_block(
                         Span::styled("│ ", border_style),
                         Span::styled(prefix, Style::default()),
                     ];
+                    text_lines.push(TextLine {
+                        line: lines.len(),
+                        col: MESSAGE_TEXT_COL,
+                        continued,
+                    });
                     if p.is_error {
                         spans.push(Span::styled(chunk.clone(), chunk_style));
                     } else {
@@ -231,29 +342,75 @@ fn add_message_block(
             }
             MessageSegment::CodeBlock { lang, code } => {
                 let code_block_start = lines.len();
+                let block_idx = code_blocks.iter().filter(|b| b.id.0 == msg_idx).count();
+                // Only the last block of the streaming reply can still grow.
+                let normalized = normalize_code_operators(code);
+                let streaming = p.stream_cursor && segment_idx == last_segment;
+                let highlighted = highlight_code_block(lang, &normalized, streaming);
+                let longest = normalized
+                    .split('\n')
+                    .map(|l| l.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let max_scroll = if p.truncate_code {
+                    longest.saturating_sub(code_inner_width)
+                } else {
+                    0
+                };
+                let scroll = p
+                    .code_scroll
+                    .filter(|(idx, _)| *idx == block_idx)
+                    .map(|(_, col)| col.min(max_scroll));
+
                 let lang_label = if lang.is_empty() { "code" } else { lang };
-                let code_header = format!("┌─ {} ", lang_label);
+                let code_header = match scroll {
+                    Some(col) if max_scroll > 0 => format!(
+                        "┌─ {} · ←→ {}-{}/{} ",
+                        lang_label,
+                        col + 1,
+                        (col + code_inner_width).min(longest),
+                        longest
+                    ),
+                    _ if max_scroll > 0 => format!("┌─ {} · {} cols ", lang_label, longest),
+                    _ => format!("┌─ {} ", lang_label),
+                };
                 let code_trail_len =
                     code_inner_width.saturating_sub(code_header.chars().count() + 1);
-                let code_header_line = format!(
-                    "{}{}┐",
-                    code_header,
-                    repeat_char('─', code_trail_len.max(0))
-                );
+                let code_header_line =
+                    format!("{}{}┐", code_header, repeat_char('─', code_trail_len));
                 lines.push(Line::from(vec![
                     Span::styled("│ ", border_style),
                     Span::styled(code_header_line, Style::default().fg(ACCENT_SECONDARY)),
                 ]));
-                for code_line in code.split('\n') {
-                    let normalized = normalize_code_operators(code_line);
-                    let line_spans = highlight_code_line(lang, &normalized);
-                    let mut offset = 0;
-                    let code_chunks: Vec<String> = wrap_message(&normalized, code_inner_width);
-                    for chunk in code_chunks {
+                for (code_line, line_spans) in normalized.split('\n').zip(highlighted.iter()) {
+                    // (chunk, first column) per row: one truncated row, or the wrapped chunks.
+                    let rows: Vec<(String, usize)> = if p.truncate_code {
+                        let (from, to) = app::visible_columns(
+                            code_line.chars().count(),
+                            scroll.unwrap_or(0),
+                            code_inner_width,
+                        );
+                        let chunk = code_line.chars().skip(from).take(to - from).collect();
+                        vec![(chunk, from)]
+                    } else {
+                        let mut offset = 0;
+                        wrap_message(code_line, code_inner_width)
+                            .into_iter()
+                            .map(|chunk| {
+                                let from = offset;
+                                offset += chunk.chars().count();
+                                (chunk, from)
+                            })
+                            .collect()
+                    };
+                    for (chunk_idx, (chunk, from)) in rows.into_iter().enumerate() {
+                        text_lines.push(TextLine {
+                            line: lines.len(),
+                            col: MESSAGE_TEXT_COL,
+                            continued: chunk_idx > 0,
+                        });
                         let chunk_len = chunk.chars().count();
-                        let spans_slice =
-                            slice_spans_by_range(&line_spans, offset, offset + chunk_len);
-                        offset += chunk_len;
+                        let spans_slice = slice_spans_by_range(line_spans, from, from + chunk_len);
                         let mut line_content = vec![
                             Span::styled("│ ", border_style),
                             Span::styled("│ ", Style::default().fg(ACCENT_SECONDARY)),
@@ -279,6 +436,60 @@ fn add_message_block(
                     code_block_end,
                     CopyTarget::Code(code.to_string()),
                 ));
+                code_blocks.push(CodeBlockRegion {
+                    id: (msg_idx, block_idx),
+                    start: code_block_start,
+                    end: code_block_end,
+                    max_scroll,
+                });
+            }
+            MessageSegment::Preview { kind, source } => {
+                let block_start = lines.len();
+                let block_style = Style::default().fg(ACCENT_SECONDARY);
+                let header = format!("┌─ {} · ", kind.label());
+                let trail_len = code_inner_width
+                    .saturating_sub(header.chars().count() + PREVIEW_ACTION.chars().count() + 2);
+                previews.push((
+                    lines.len(),
+                    Preview {
+                        kind: *kind,
+                        source: source.to_string(),
+                    },
+                ));
+                lines.push(Line::from(vec![
+                    Span::styled("│ ", border_style),
+                    Span::styled(header, block_style),
+                    Span::styled(
+                        PREVIEW_ACTION,
+                        Style::default()
+                            .fg(ACCENT)
+                            .add_modifier(Modifier::UNDERLINED),
+                    ),
+                    Span::styled(format!(" {}┐", repeat_char('─', trail_len)), block_style),
+                ]));
+                // The source as written: the terminal cannot draw the diagram or formula.
+                for (chunk, continued) in wrap_message_continued(source, code_inner_width) {
+                    text_lines.push(TextLine {
+                        line: lines.len(),
+                        col: MESSAGE_TEXT_COL,
+                        continued,
+                    });
+                    lines.push(Line::from(vec![
+                        Span::styled("│ ", border_style),
+                        Span::styled("│ ", block_style),
+                        Span::raw(chunk),
+                    ]));
+                }
+                let footer = format!("└{}┘", repeat_char('─', code_inner_width.saturating_sub(2)));
+                lines.push(Line::from(vec![
+                    Span::styled("│ ", border_style),
+                    Span::styled(footer, block_style),
+                ]));
+                copy_regions.push((
+                    block_start,
+                    lines.len(),
+                    CopyTarget::Code(source.to_string()),
+                ));
             }
         }
     }
@@ -294,9 +505,25 @@ fn add_message_block(
         ]));
     }
 
-    // Bottom border
-    let bottom_line = format!("└{}┘", repeat_char('─', p.wrap_width.saturating_sub(2)));
-    lines.push(Line::from(Span::styled(bottom_line, border_style)));
+    // Bottom border: "└───...── 1.2k↑ 430↓ · 6.4s ─┘" when annotated, dimmed.
+    let annotation = p
+        .annotation
+        .map(|a| format!(" {} ", a))
+        .filter(|a| a.chars().count() + 4 <= p.wrap_width);
+    match annotation {
+        Some(a) => {
+            let lead = p.wrap_width.saturating_sub(a.chars().count() + 3);
+            lines.push(Line::from(vec![
+                Span::styled(format!("└{}", repeat_char('─', lead)), border_style),
+                Span::styled(a, Style::default().fg(Color::DarkGray)),
+                Span::styled("─┘", border_style),
+            ]));
+        }
+        None => {
+            let bottom_line = format!("└{}┘", repeat_char('─', p.wrap_width.saturating_sub(2)));
+            lines.push(Line::from(Span::styled(bottom_line, border_style)));
+        }
+    }
 
     // Separator between messages
     let sep_line = repeat_char('─', p.wrap_width);
@@ -311,7 +538,175 @@ fn add_message_block(
     (start, end)
 }
 
+/// Block of a failed request: red border, the error, its hint, and the suggested actions
+/// with their keys.
+fn add_error_block(
+    lines: &mut Vec<Line<'static>>,
+    text_lines: &mut Vec<TextLine>,
+    notice: &ErrorNotice,
+    content_width: usize,
+    wrap_width: usize,
+) -> usize {
+    let border_style = Style::default().fg(Color::Red);
+    let top_label = "┌─ Error ";
+    let top_trail_len = wrap_width.saturating_sub(top_label.chars().count() + 1);
+    lines.push(Line::from(Span::styled(
+        format!("{}{}┐", top_label, repeat_char('─', top_trail_len)),
+        border_style,
+    )));
+    let body = |text: &str,
+                style: Style,
+                lines: &mut Vec<Line<'static>>,
+                text_lines: &mut Vec<TextLine>| {
+        for (chunk, continued) in wrap_message_continued(text.trim(), content_width) {
+            text_lines.push(TextLine {
+                line: lines.len(),
+                col: MESSAGE_TEXT_COL,
+                continued,
+            });
+            lines.push(Line::from(vec![
+                Span::styled("│ ", border_style),
+                Span::raw("  "),
+                Span::styled(chunk, style),
+            ]));
+        }
+    };
+    body(
+        &notice.message,
+        Style::default().fg(Color::Red),
+        lines,
+        text_lines,
+    );
+    if let Some(hint) = &notice.hint {
+        body(hint, Style::default(), lines, text_lines);
+    }
+    let action_line = lines.len();
+    let mut spans = vec![Span::styled("│ ", border_style), Span::raw(" ")];
+    let actions = notice
+        .actions
+        .iter()
+        .map(|&action| labels::next_action(action));
+    for (key, label) in actions.chain([labels::EXPLAIN_ERROR]) {
+        spans.push(Span::styled(
+            format!(" {} ", key),
+            Style::default().fg(ACCENT),
+        ));
+        spans.push(Span::raw(format!("{} ", label)));
+    }
+    lines.push(Line::from(spans));
+    lines.push(Line::from(Span::styled(
+        format!("└{}┘", repeat_char('─', wrap_width.saturating_sub(2))),
+        border_style,
+    )));
+    lines.push(Line::from(Span::styled(
+        repeat_char('─', wrap_width),
+        Style::default().fg(Color::DarkGray),
+    )));
+    action_line
+}
+
+/// Divider left by /clear-context, spanning the history width.
+fn context_cleared_line(width: usize) -> Line<'static> {
+    divider_line(" context cleared · messages above are not sent ", width)
+}
+
+/// Divider left by /compact, spanning the history width.
+fn context_compacted_line(width: usize) -> Line<'static> {
+    divider_line(" context compacted · messages above are summarized ", width)
+}
+
+/// Gray rule across the history with `label` in the middle.
+fn divider_line(label: &str, width: usize) -> Line<'static> {
+    let side = width.saturating_sub(crate::core::text::display_width(label)) / 2;
+    Line::from(Span::styled(
+        format!("{}{}{}", "─".repeat(side), label, "─".repeat(side)),
+        Style::default().fg(Color::DarkGray),
+    ))
+}
+
+/// Hint line above a zoomed message.
+fn zoom_hint_line(label: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!(" Zoomed: {} message ", label),
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" Esc/Alt+Z ", Style::default().fg(Color::DarkGray)),
+        Span::raw("back  "),
+        Span::styled(" Alt+B ", Style::default().fg(Color::DarkGray)),
+        Span::raw("branch here  "),
+        Span::styled(" ↑↓ PgUp PgDn ", Style::default().fg(Color::DarkGray)),
+        Span::raw("scroll"),
+    ])
+}
+
+/// Screen rects of the links in the replies shown in `visible` (rendered line range).
+fn visible_links(
+    app: &mut App,
+    text_lines: &[TextLine],
+    text_area: Rect,
+    visible: std::ops::Range<usize>,
+) -> Vec<LinkRect> {
+    let mut rects = Vec::new();
+    for &(msg_idx, start, end) in &app.message_line_ranges {
+        if end <= visible.start || start >= visible.end {
+            continue;
+        }
+        let Some(ChatMessage::Assistant(content)) = app.messages.get(msg_idx) else {
+            continue;
+        };
+        let links = app.link_cache.links(msg_idx, content, &app.workspace.root);
+        if links.is_empty() {
+            continue;
+        }
+        let lines: Vec<&TextLine> = text_lines
+            .iter()
+            .filter(|t| t.line >= start && t.line < end)
+            .collect();
+        for (line, from, to, link) in hyperlinks::locate(&app.rendered_lines, &lines, links) {
+            if !visible.contains(&line) {
+                continue;
+            }
+            let (rect, text) = hyperlinks::link_rect(
+                &app.rendered_lines[line],
+                from,
+                to,
+                text_area.x,
+                text_area.y + (line - visible.start) as u16,
+            );
+            rects.push(LinkRect {
+                rect: rect.intersection(text_area),
+                text,
+                target: link.target.clone(),
+            });
+        }
+    }
+    rects
+}
+
 pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
+    // Drop a zoom whose message no longer exists (e.g. after Ctrl+E).
+    let zoom_label = app.zoom.and_then(|z| match app.messages.get(z.msg_idx) {
+        Some(ChatMessage::User(_)) => Some("You"),
+        Some(ChatMessage::Assistant(_)) => Some("Assistant"),
+        _ => None,
+    });
+    if app.zoom.is_some() && zoom_label.is_none() {
+        app.unzoom();
+    }
+    let history_area = match zoom_label {
+        Some(label) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(history_area);
+            f.render_widget(Paragraph::new(zoom_hint_line(label)), chunks[0]);
+            chunks[1]
+        }
+        None => history_area,
+    };
+    let zoomed_idx = app.zoom.map(|z| z.msg_idx);
+
     let history_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Min(0), Constraint::Length(1)])
@@ -326,9 +721,22 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
     let mut lines: Vec<Line<'static>> = Vec::new();
     let mut message_line_ranges: Vec<(usize, usize, usize)> = Vec::new();
     let mut copy_regions: Vec<(usize, usize, CopyTarget)> = Vec::new();
+    let mut text_lines: Vec<TextLine> = Vec::new();
+    let mut tool_result_headers: Vec<(usize, usize)> = Vec::new();
+    let mut error_action_lines: Vec<(usize, usize)> = Vec::new();
+    let mut previews: Vec<(usize, Preview)> = Vec::new();
+    let mut code_blocks: Vec<CodeBlockRegion> = Vec::new();
+    let code_scroll = |msg_idx: usize| {
+        app.focused_code
+            .filter(|(idx, _)| *idx == msg_idx)
+            .map(|(_, block)| (block, app.code_scroll))
+    };
 
     let msg_count = app.messages.len();
     for (msg_idx, msg) in app.messages.iter().enumerate() {
+        if zoomed_idx.is_some_and(|z| z != msg_idx) {
+            continue;
+        }
         let timestamp = if app.show_timestamps {
             app.message_timestamps.get(msg_idx).copied().flatten()
         } else {
@@ -339,6 +747,9 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
                 let (start, end) = add_message_block(
                     &mut lines,
                     &mut copy_regions,
+                    &mut text_lines,
+                    &mut previews,
+                    &mut code_blocks,
                     msg_idx,
                     MessageBlockParams {
                         label: "You",
@@ -348,7 +759,12 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
                         is_error: false,
                         is_user: true,
                         stream_cursor: false,
+                        pinned: app.pinned_messages.contains(&msg_idx),
+                        reaction: None,
                         timestamp,
+                        annotation: None,
+                        truncate_code: app.truncate_code,
+                        code_scroll: code_scroll(msg_idx),
                     },
                 );
                 message_line_ranges.push((msg_idx, start, end));
@@ -360,6 +776,9 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
                 let (start, end) = add_message_block(
                     &mut lines,
                     &mut copy_regions,
+                    &mut text_lines,
+                    &mut previews,
+                    &mut code_blocks,
                     msg_idx,
                     MessageBlockParams {
                         label: "Assistant",
@@ -369,13 +788,53 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
                         is_error,
                         is_user: false,
                         stream_cursor: is_last_and_streaming,
+                        pinned: app.pinned_messages.contains(&msg_idx),
+                        reaction: app.message_reactions.get(&msg_idx).copied(),
                         timestamp,
+                        annotation: app
+                            .message_usage
+                            .get(&msg_idx)
+                            .filter(|_| app.show_usage && !is_last_and_streaming)
+                            .map(usage_annotation),
+                        truncate_code: app.truncate_code,
+                        code_scroll: code_scroll(msg_idx),
                     },
                 );
                 message_line_ranges.push((msg_idx, start, end));
             }
             ChatMessage::ToolLog(s) => {
-                add_tool_log_lines(&mut lines, s, content_width);
+                add_tool_log_lines(&mut lines, &mut text_lines, s, content_width);
+            }
+            ChatMessage::ToolResult {
+                tool,
+                view,
+                expanded,
+            } => {
+                let header = tool_result::add_tool_result_lines(
+                    &mut lines,
+                    &mut text_lines,
+                    tool,
+                    view,
+                    *expanded,
+                    content_width,
+                );
+                tool_result_headers.push((header, msg_idx));
+            }
+            ChatMessage::ContextCleared => {
+                lines.push(context_cleared_line(wrap_width));
+            }
+            ChatMessage::ContextCompacted { .. } => {
+                lines.push(context_compacted_line(wrap_width));
+            }
+            ChatMessage::Error(notice) => {
+                let action_line = add_error_block(
+                    &mut lines,
+                    &mut text_lines,
+                    notice,
+                    content_width,
+                    wrap_width,
+                );
+                error_action_lines.push((action_line, msg_idx));
             }
             ChatMessage::Thinking => {
                 lines.push(Line::from(vec![Span::styled(
@@ -388,8 +847,52 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
         }
     }
 
+    // Queued prompts are not messages yet: drawn after the history, without copy targets.
+    if zoomed_idx.is_none() {
+        let (copy_len, previews_len, code_blocks_len) =
+            (copy_regions.len(), previews.len(), code_blocks.len());
+        for (i, queued) in app.prompt_queue.iter().enumerate() {
+            let label = match app.queue_hold_remaining() {
+                Some(left) if i == 0 => {
+                    format!("You (queued · rate limited, {}s)", left.as_secs() + 1)
+                }
+                _ => "You (queued)".to_string(),
+            };
+            add_message_block(
+                &mut lines,
+                &mut copy_regions,
+                &mut text_lines,
+                &mut previews,
+                &mut code_blocks,
+                msg_count,
+                MessageBlockParams {
+                    label: &label,
+                    content: &queued.prompt,
+                    content_width,
+                    wrap_width,
+                    is_error: false,
+                    is_user: true,
+                    stream_cursor: false,
+                    pinned: false,
+                    reaction: None,
+                    timestamp: None,
+                    annotation: None,
+                    truncate_code: app.truncate_code,
+                    code_scroll: None,
+                },
+            );
+        }
+        copy_regions.truncate(copy_len);
+        previews.truncate(previews_len);
+        code_blocks.truncate(code_blocks_len);
+    }
+
     app.message_line_ranges = message_line_ranges;
     app.copy_regions = copy_regions;
+    app.tool_result_headers = tool_result_headers;
+    app.error_action_lines = error_action_lines;
+    app.preview_headers = previews;
+    app.code_blocks = code_blocks;
 
     // Store rendered line strings for selection extract.
     app.rendered_lines = lines.iter().map(line_to_string).collect();
@@ -398,19 +901,46 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
     let visible = text_area.height as usize;
     let max_scroll = total_lines.saturating_sub(visible.max(1));
     app.last_max_scroll = max_scroll;
+
+    // Conversation search: matches in the rendered text, scrolled into view on a jump.
+    let view_top = app.scroll_line();
+    if let Some(search) = app.search.as_mut() {
+        let matches = app::find_matches(&app.rendered_lines, &text_lines, &search.query);
+        if let Some(line) = search.update_matches(matches, view_top) {
+            app.scroll_to_line(line, visible.max(1));
+        }
+    }
     let scroll_pos = app.scroll_line().min(max_scroll);
     let start = scroll_pos;
     let end = (start + visible).min(total_lines);
-
-    // Apply selection highlight to visible lines.
-    let visible_lines: Vec<Line> = if let Some((sl, sc, el, ec)) = app.selection {
-        lines
-            .into_iter()
-            .enumerate()
-            .skip(start)
-            .take(end - start)
-            .map(|(global_idx, line)| {
-                if global_idx >= sl && global_idx <= el {
+    app.link_rects = visible_links(app, &text_lines, text_area, start..end);
+
+    // Lines of the keyboard selection (first to last selected message) and of its cursor.
+    let message_lines = |msg_idx: usize| {
+        app.message_line_ranges
+            .iter()
+            .find(|(idx, _, _)| *idx == msg_idx)
+            .map(|&(_, start, end)| start..end)
+    };
+    let selected_lines = app.message_selection.and_then(|selection| {
+        let range = selection.range();
+        let lines = message_lines(*range.start())?.start..message_lines(*range.end())?.end;
+        Some((lines, message_lines(selection.cursor).unwrap_or_default()))
+    });
+
+    // Apply search and selection highlights to visible lines.
+    let visible_lines: Vec<Line> = lines
+        .into_iter()
+        .enumerate()
+        .skip(start)
+        .take(end - start)
+        .map(|(global_idx, line)| {
+            let line = match app.search.as_ref() {
+                Some(search) => highlight_search_matches(line, global_idx, search),
+                None => line,
+            };
+            match app.selection {
+                Some((sl, sc, el, ec)) if global_idx >= sl && global_idx <= el => {
                     let line_len = app
                         .rendered_lines
                         .get(global_idx)
@@ -425,15 +955,29 @@ pub(crate) fn draw_history(f: &mut Frame, app: &mut App, history_area: Rect) {
                     } else {
                         (0, line_len)
                     };
-                    apply_selection_to_line(&line, line_col_start, line_col_end)
-                } else {
-                    line
+                    style_columns(
+                        &line,
+                        line_col_start,
+                        line_col_end,
+                        Style::default().add_modifier(Modifier::REVERSED),
+                    )
                 }
-            })
-            .collect()
-    } else {
-        lines.into_iter().skip(start).take(end - start).collect()
-    };
+                _ => line,
+            }
+        })
+        .enumerate()
+        .map(|(offset, line)| match &selected_lines {
+            Some((lines, cursor)) if lines.contains(&(start + offset)) => {
+                let bg = if cursor.contains(&(start + offset)) {
+                    SELECTION_CURSOR_BG
+                } else {
+                    SELECTED_BG
+                };
+                line.patch_style(Style::default().bg(bg))
+            }
+            _ => line,
+        })
+        .collect();
 
     f.render_widget(Paragraph::new(visible_lines), text_area);
 
@@ -6,19 +6,52 @@ use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
 
-use crate::core::history::{ConversationMeta, filter_conversations_with_content};
+use crate::core::history::{
+    ConversationMeta, ConversationOrigin, filter_conversations_with_content,
+};
 
 use super::super::app::HistorySelectorState;
 use super::super::constants::ACCENT;
+use super::fuzzy_matches;
 
-fn format_conversation(meta: &ConversationMeta) -> String {
+/// Title and last update time, e.g. "Fix tests — 2025-01-31 14:02", marked when archived.
+pub(super) fn format_conversation(meta: &ConversationMeta) -> String {
     use chrono::TimeZone;
     let dt = chrono::Utc.timestamp_opt(meta.updated_at as i64, 0);
     let date_str = dt
         .single()
         .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_else(|| meta.updated_at.to_string());
-    format!("{} — {}", meta.title, date_str)
+    let archived = if meta.archived { " [archived]" } else { "" };
+    format!("{} — {}{}", meta.title, date_str, archived)
+}
+
+/// Height of the detail pane: a separator and the workspace, branch, and environment.
+const DETAIL_HEIGHT: u16 = 4;
+
+/// Where the selected conversation was last saved from.
+fn draw_details(f: &mut Frame, area: Rect, origin: Option<&ConversationOrigin>) {
+    let label = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = match origin {
+        Some(origin) => origin
+            .details()
+            .into_iter()
+            .map(|(name, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{}: ", name), label),
+                    Span::raw(value),
+                ])
+            })
+            .collect(),
+        None => vec![Line::from(Span::styled(
+            "Workspace not recorded (saved by an earlier version)",
+            label,
+        ))],
+    };
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(Color::DarkGray));
+    f.render_widget(Paragraph::new(lines).block(block), area);
 }
 
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
@@ -50,12 +83,14 @@ pub(crate) fn draw_history_selector_popup(
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Min(3),
+            Constraint::Length(DETAIL_HEIGHT),
             Constraint::Length(1),
         ]
     } else {
         &[
             Constraint::Length(3),
             Constraint::Min(3),
+            Constraint::Length(DETAIL_HEIGHT),
             Constraint::Length(1),
         ]
     };
@@ -64,7 +99,7 @@ pub(crate) fn draw_history_selector_popup(
         .constraints(constraints)
         .split(inner);
     let filter_area = chunks[0];
-    let (list_area, hint_area) = if is_renaming {
+    let (list_area, detail_area, hint_area) = if is_renaming {
         let rename_area = chunks[1];
         let rename_content = selector
             .renaming
@@ -84,9 +119,9 @@ pub(crate) fn draw_history_selector_popup(
             .block(rename_block)
             .style(Style::default().fg(Color::White));
         f.render_widget(rename_para, rename_area);
-        (chunks[2], chunks[3])
+        (chunks[2], chunks[3], chunks[4])
     } else {
-        (chunks[1], chunks[2])
+        (chunks[1], chunks[2], chunks[3])
     };
 
     let filter_content = if selector.filter.is_empty() {
@@ -139,7 +174,16 @@ pub(crate) fn draw_history_selector_popup(
                 } else {
                     Style::default()
                 };
-                ListItem::new(format!(" {} ", format_conversation(meta))).style(style)
+                // The row starts with the title, so title match positions apply as is.
+                let title_matches = fuzzy_matches::positions(&selector.filter, &meta.title);
+                let mut spans = vec![Span::styled(" ", style)];
+                spans.extend(fuzzy_matches::highlight(
+                    &format_conversation(meta),
+                    &title_matches,
+                    style,
+                ));
+                spans.push(Span::styled(" ", style));
+                ListItem::new(Line::from(spans)).style(style)
             })
             .collect();
 
@@ -147,6 +191,9 @@ pub(crate) fn draw_history_selector_popup(
 
         let list = List::new(items).highlight_style(Style::default().fg(Color::Black).bg(ACCENT));
         f.render_stateful_widget(list, list_area, &mut selector.list_state);
+
+        let origin = selector.origins.get(&filtered[selector.selected_index].id);
+        draw_details(f, detail_area, origin);
     }
 
     let hint = if is_renaming {
@@ -166,6 +213,8 @@ pub(crate) fn draw_history_selector_popup(
             Span::raw("rename  "),
             Span::styled("Ctrl+D ", Style::default().fg(Color::DarkGray)),
             Span::raw("delete  "),
+            Span::styled("Ctrl+A ", Style::default().fg(Color::DarkGray)),
+            Span::raw("archive  "),
             Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
             Span::raw("cancel  "),
             Span::styled("Ctrl+N ", Style::default().fg(Color::DarkGray)),
@@ -1,15 +1,71 @@
-//! Bottom bar: path on left, shortcuts on right.
+//! Bottom bar: working directory on left, shortcuts on right.
 
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Paragraph;
-use std::env;
 
-use super::super::super::app::App;
+use crate::core::remote;
+use crate::core::text;
 
-/// Draw the bottom bar with current path and keyboard shortcuts.
+use super::super::super::app::{App, ChatMessage, MessageSelection, SearchState};
+use super::super::super::constants::ACCENT;
+
+/// Search query and match counter, in place of the working directory while searching.
+fn search_line(search: &SearchState) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled("Search: ", Style::default().fg(ACCENT)),
+        Span::raw(search.query.clone()),
+    ];
+    if search.editing {
+        spans.push(Span::styled("▌", Style::default().fg(ACCENT)));
+    }
+    if let Some(counter) = search.counter() {
+        let color = if search.matches.is_empty() {
+            Color::Yellow
+        } else {
+            Color::DarkGray
+        };
+        spans.push(Span::styled(
+            format!("  {}", counter),
+            Style::default().fg(color),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Number of messages selected with the keyboard, in place of the working directory.
+fn selection_line(app: &App, selection: &MessageSelection) -> Line<'static> {
+    let count = app.messages.get(selection.range()).map_or(0, |messages| {
+        messages
+            .iter()
+            .filter(|m| matches!(m, ChatMessage::User(_) | ChatMessage::Assistant(_)))
+            .count()
+    });
+    let label = if selection.confirm_delete {
+        Span::styled(
+            format!(
+                "Delete {} message{}?",
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+            Style::default().fg(Color::Yellow),
+        )
+    } else {
+        Span::raw(format!(
+            "{} message{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        ))
+    };
+    Line::from(vec![
+        Span::styled("Selected: ", Style::default().fg(ACCENT)),
+        label,
+    ])
+}
+
+/// Draw the bottom bar with the working directory and keyboard shortcuts.
 pub(crate) fn draw(f: &mut Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -18,10 +74,36 @@ pub(crate) fn draw(f: &mut Frame, app: &App, area: Rect) {
     let path_area = chunks[0];
     let shortcuts_area = chunks[1];
 
-    let path_display = env::current_dir()
-        .ok()
-        .and_then(|p| p.to_str().map(String::from))
-        .unwrap_or_else(|| "?".to_string());
+    if let Some(search) = &app.search {
+        f.render_widget(Paragraph::new(search_line(search)), path_area);
+        f.render_widget(
+            Paragraph::new(super::super::super::shortcuts::labels::search_bar(
+                search.editing,
+            ))
+            .alignment(ratatui::layout::HorizontalAlignment::Right),
+            shortcuts_area,
+        );
+        return;
+    }
+
+    if let Some(selection) = &app.message_selection {
+        f.render_widget(Paragraph::new(selection_line(app, selection)), path_area);
+        f.render_widget(
+            Paragraph::new(
+                super::super::super::shortcuts::labels::message_selection_bar(
+                    selection.confirm_delete,
+                ),
+            )
+            .alignment(ratatui::layout::HorizontalAlignment::Right),
+            shortcuts_area,
+        );
+        return;
+    }
+
+    let path_display = match remote::current() {
+        Some(remote) => format!("{}:{}", remote.host(), app.workspace.working_dir.display()),
+        None => app.workspace.working_dir.display().to_string(),
+    };
     let project_prefix = app
         .workspace
         .project_type
@@ -29,9 +111,8 @@ pub(crate) fn draw(f: &mut Frame, app: &App, area: Rect) {
         .unwrap_or_default();
     let full_display = format!("{}{}", project_prefix, path_display);
     let max_path_len = path_area.width as usize;
-    let path_display = if full_display.chars().count() > max_path_len && max_path_len > 2 {
-        let tail: String = full_display.chars().rev().take(max_path_len - 1).collect();
-        format!("…{}", tail.chars().rev().collect::<String>())
+    let path_display = if max_path_len > 2 {
+        text::truncate_start(&full_display, max_path_len)
     } else {
         full_display
     };
@@ -44,7 +125,10 @@ pub(crate) fn draw(f: &mut Frame, app: &App, area: Rect) {
         path_area,
     );
 
-    let shortcuts = super::super::super::shortcuts::labels::bottom_bar(app.is_streaming);
+    let shortcuts = match app.replay {
+        Some(r) => super::super::super::shortcuts::labels::replay_bar(r.step, r.paused, r.finished),
+        None => super::super::super::shortcuts::labels::bottom_bar(app.is_streaming),
+    };
     f.render_widget(
         Paragraph::new(shortcuts).alignment(ratatui::layout::HorizontalAlignment::Right),
         shortcuts_area,
@@ -0,0 +1,54 @@
+//! @mention file picker: workspace files matching the word typed after "@".
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use super::super::super::app::App;
+use super::super::super::constants::ACCENT;
+
+/// Draw the file picker in the given area (scrollable like the slash command list).
+pub(super) fn draw(f: &mut Frame, app: &App, area: Rect) {
+    let files = app.mention_candidates();
+    if files.is_empty() {
+        return;
+    }
+    let total = files.len();
+    let visible = (area.height.saturating_sub(2) as usize).max(1).min(total);
+    let selected = app.selected_mention_index.min(total - 1);
+    let scroll_start = selected
+        .saturating_sub(visible.saturating_sub(1))
+        .min(total.saturating_sub(visible));
+    let scroll_end = (scroll_start + visible).min(total);
+
+    let lines: Vec<Line> = files[scroll_start..scroll_end]
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let style = if scroll_start + i == selected {
+                Style::default().fg(Color::Black).bg(ACCENT)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            Line::from(Span::styled(format!("@{}", path), style))
+        })
+        .collect();
+
+    const PANEL_BG: Color = Color::Rgb(28, 32, 36);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(Span::styled(
+            " Pin file (Enter) ",
+            Style::default().fg(Color::DarkGray),
+        ))
+        .style(Style::default().bg(PANEL_BG));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(PANEL_BG)),
+        inner,
+    );
+}
@@ -1,23 +1,25 @@
-//! Input section, welcome center, bottom bar, slash command autocomplete.
+//! Input section, welcome center, bottom bar, slash command and @mention autocomplete.
 
 mod bar;
+mod mention;
 mod slash;
 
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use super::super::constants::INPUT_LINES;
 
-use crate::core::commands;
+use crate::core::text;
 
 use super::super::app::App;
 use super::super::constants::{ACCENT, SUGGESTIONS};
+use super::super::paste;
 use super::welcome_mascot;
 
-/// Fixed viewport height for the slash command autocomplete list (scrollable when more commands).
+/// Fixed viewport height for the slash command / @mention autocomplete list (scrollable).
 pub(crate) const AUTOCOMPLETE_VISIBLE_LINES: u16 = 6;
 
 /// Width of the centered input when in welcome (no conversation) mode.
@@ -27,24 +29,51 @@ pub(crate) use bar::draw as draw_bottom_bar;
 
 const ERROR_LINES: u16 = 2;
 
-fn truncate_with_ellipsis(s: &str, max_width: usize) -> String {
-    if s.chars().count() <= max_width {
-        s.to_string()
+/// Draw the open autocomplete list: slash commands, else @mention files.
+fn draw_autocomplete(f: &mut Frame, app: &App, area: Rect) {
+    if app.input.starts_with('/') {
+        slash::draw(f, app, area);
     } else {
-        format!(
-            "{}…",
-            s.chars()
-                .take(max_width.saturating_sub(1))
-                .collect::<String>()
+        mention::draw(f, app, area);
+    }
+}
+
+/// True when the chips line above the input is shown: pinned files or context files.
+pub(crate) fn has_pins_line(app: &App) -> bool {
+    !app.pinned_files.is_empty() || !app.workspace.context_files.is_empty()
+}
+
+/// Pinned files and context files as chips ("📌 src/main.rs 1.2k", "📎 db/schema.sql 3k"),
+/// one line.
+fn draw_pins(f: &mut Frame, app: &App, area: Rect) {
+    let pins = app
+        .pinned_files
+        .iter()
+        .map(|pin| (format!("📌 {}", pin.label), pin.tokens, ACCENT));
+    let context_files = app.workspace.context_files.iter().map(|file| {
+        let mark = if file.truncated() { " (cut)" } else { "" };
+        (
+            format!("📎 {}{}", file.path, mark),
+            file.tokens(),
+            Color::Gray,
         )
+    });
+    let mut spans = Vec::new();
+    for (label, tokens, color) in pins.chain(context_files) {
+        if !spans.is_empty() {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(label, Style::default().fg(color)));
+        spans.push(Span::styled(
+            format!(" {}", super::header::format_tokens_compact(tokens as u64)),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 pub(crate) fn draw_welcome_center(f: &mut Frame, app: &mut App, area: Rect) {
-    let in_slash = app.input.starts_with('/');
-    let filter = app.input.get(1..).unwrap_or("");
-    let filtered = commands::filter_commands_resolved(&app.resolved_commands, filter);
-    let ac_height = if in_slash && !filtered.is_empty() {
+    let ac_height = if app.autocomplete_open() {
         AUTOCOMPLETE_VISIBLE_LINES
     } else {
         0
@@ -131,7 +160,7 @@ pub(crate) fn draw_welcome_center(f: &mut Frame, app: &mut App, area: Rect) {
         .or(app.templates_load_error.as_ref());
     if let (Some(area), Some(err)) = (error_area, err_msg) {
         let err_line = Line::from(Span::styled(
-            truncate_with_ellipsis(err, area.width as usize),
+            text::truncate_end(err, area.width as usize),
             Style::default().fg(Color::Red),
         ));
         f.render_widget(
@@ -161,7 +190,20 @@ pub(crate) fn draw_welcome_center(f: &mut Frame, app: &mut App, area: Rect) {
             width: input_width,
             height: ac_area.height,
         };
-        slash::draw(f, app, ac_rect);
+        draw_autocomplete(f, app, ac_rect);
+    }
+    if has_pins_line(app) {
+        // Pins take the spacer line above the input (or above the autocomplete list).
+        let spacer = inner_chunks[if has_error { 2 } else { 1 }];
+        draw_pins(
+            f,
+            app,
+            Rect {
+                x: input_area.x,
+                width: input_width,
+                ..spacer
+            },
+        );
     }
 
     draw_input_block(f, app, input_area);
@@ -208,6 +250,27 @@ fn input_has_focus(app: &App) -> bool {
     app.confirm_popup.is_none() && app.model_selector.is_none() && app.history_selector.is_none()
 }
 
+/// Input text with attachment chips (`[pasted N lines #k]`, `[image #k]`) highlighted.
+fn input_spans(app: &App) -> Vec<Span<'_>> {
+    let chip_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (range, _) in paste::placeholder_ranges(&app.input, &app.attachments) {
+        if range.start < last {
+            continue;
+        }
+        if range.start > last {
+            spans.push(Span::raw(&app.input[last..range.start]));
+        }
+        spans.push(Span::styled(&app.input[range.clone()], chip_style));
+        last = range.end;
+    }
+    if last < app.input.len() {
+        spans.push(Span::raw(&app.input[last..]));
+    }
+    spans
+}
+
 fn draw_input_block(f: &mut Frame, app: &mut App, input_area: Rect) {
     let border_style = if input_has_focus(app) {
         Style::default().fg(ACCENT)
@@ -221,9 +284,12 @@ fn draw_input_block(f: &mut Frame, app: &mut App, input_area: Rect) {
     let inner_height = inner.height as usize;
 
     let input_content = if app.input.is_empty() {
-        Span::styled("Ask anything... ", Style::default().fg(Color::DarkGray))
+        vec![Span::styled(
+            "Ask anything... ",
+            Style::default().fg(Color::DarkGray),
+        )]
     } else {
-        Span::raw(app.input.as_str())
+        input_spans(app)
     };
 
     let para = Paragraph::new(Line::from(input_content))
@@ -279,90 +345,35 @@ fn draw_suggestions(f: &mut Frame, app: &mut App, area: Rect) {
 }
 
 pub(crate) fn draw_input_section(f: &mut Frame, app: &mut App, input_section: Rect) {
-    let in_slash = app.input.starts_with('/');
-    let filter = app.input.get(1..).unwrap_or("");
-    let filtered = commands::filter_commands_resolved(&app.resolved_commands, filter);
-    let ac_height = if in_slash && !filtered.is_empty() {
+    let ac_height = if app.autocomplete_open() {
         AUTOCOMPLETE_VISIBLE_LINES
     } else {
         0
     };
+    let pins_height = u16::from(has_pins_line(app));
 
-    let constraints: &[Constraint] = if ac_height > 0 {
-        &[
+    let input_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
             Constraint::Length(ac_height),
+            Constraint::Length(pins_height),
             Constraint::Length(INPUT_LINES),
             Constraint::Length(1),
             Constraint::Length(2),
-        ]
-    } else {
-        &[
-            Constraint::Length(INPUT_LINES),
-            Constraint::Length(1),
-            Constraint::Length(2),
-        ]
-    };
-
-    let input_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(constraints)
+        ])
         .split(input_section);
 
-    let input_area = if ac_height > 0 {
-        input_chunks[1]
-    } else {
-        input_chunks[0]
-    };
-    let suggestions_area = if ac_height > 0 {
-        input_chunks[2]
-    } else {
-        input_chunks[1]
-    };
-    let shortcuts_area = if ac_height > 0 {
-        input_chunks[3]
-    } else {
-        input_chunks[2]
-    };
+    let input_area = input_chunks[2];
+    let suggestions_area = input_chunks[3];
+    let shortcuts_area = input_chunks[4];
 
     if ac_height > 0 {
-        slash::draw(f, app, input_chunks[0]);
+        draw_autocomplete(f, app, input_chunks[0]);
+    }
+    if pins_height > 0 {
+        draw_pins(f, app, input_chunks[1]);
     }
     draw_input_block(f, app, input_area);
     draw_suggestions(f, app, suggestions_area);
     bar::draw(f, app, shortcuts_area);
 }
-
-#[cfg(test)]
-mod tests {
-    use super::truncate_with_ellipsis;
-
-    #[test]
-    fn truncate_short_string_unchanged() {
-        assert_eq!(truncate_with_ellipsis("hello", 10), "hello");
-    }
-
-    #[test]
-    fn truncate_exact_width_unchanged() {
-        assert_eq!(truncate_with_ellipsis("hello", 5), "hello");
-    }
-
-    #[test]
-    fn truncate_long_string_adds_ellipsis() {
-        let result = truncate_with_ellipsis("hello world", 8);
-        assert_eq!(result.chars().count(), 8); // 7 chars + ellipsis
-        assert!(result.ends_with('…'));
-    }
-
-    #[test]
-    fn truncate_utf8_chars() {
-        let result = truncate_with_ellipsis("café", 3);
-        assert!(result.ends_with('…'));
-        assert!(result.len() <= 5); // é is 2 bytes
-    }
-
-    #[test]
-    fn truncate_max_width_one() {
-        let result = truncate_with_ellipsis("ab", 1);
-        assert_eq!(result, "…");
-    }
-}
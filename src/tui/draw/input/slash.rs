@@ -3,22 +3,17 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
-use ratatui::text::{Line, Span};
+use ratatui::text::Line;
 use ratatui::widgets::{Block, Borders, Paragraph};
 
-use crate::core::commands;
-
 use super::super::super::app::App;
 use super::super::super::constants::{ACCENT, ACCENT_SECONDARY, CUSTOM_COMMAND_COLOR};
+use super::super::fuzzy_matches;
 
 /// Draw the slash command autocomplete list above the given area.
 /// List is scrollable when there are more commands than the visible viewport.
 pub(super) fn draw(f: &mut Frame, app: &App, area: Rect) {
-    if !app.input.starts_with('/') {
-        return;
-    }
-    let filter = app.input.get(1..).unwrap_or("");
-    let filtered = commands::filter_commands_resolved(&app.resolved_commands, filter);
+    let filtered = app.slash_commands();
     if filtered.is_empty() {
         return;
     }
@@ -28,9 +23,16 @@ pub(super) fn draw(f: &mut Frame, app: &App, area: Rect) {
     let scroll_start = app
         .selected_command_index
         .saturating_sub(visible.saturating_sub(1))
-        .min(total.saturating_sub(visible).max(0));
+        .min(total.saturating_sub(visible));
     let scroll_end = (scroll_start + visible).min(total);
 
+    // Highlight matches while the command name is typed, not once an argument follows.
+    let query = app
+        .input
+        .strip_prefix('/')
+        .filter(|q| !q.contains(char::is_whitespace))
+        .unwrap_or("");
+
     let lines: Vec<Line> = filtered[scroll_start..scroll_end]
         .iter()
         .enumerate()
@@ -49,17 +51,27 @@ pub(super) fn draw(f: &mut Frame, app: &App, area: Rect) {
             } else {
                 ACCENT
             };
-            if selected {
-                Line::from(vec![
-                    Span::styled(name, Style::default().fg(Color::Black).bg(sel_bg)),
-                    Span::styled(desc, Style::default().fg(Color::Black).bg(sel_bg)),
-                ])
+            let (name_style, desc_style) = if selected {
+                let style = Style::default().fg(Color::Black).bg(sel_bg);
+                (style, style)
             } else {
-                Line::from(vec![
-                    Span::styled(name, Style::default().fg(name_color)),
-                    Span::styled(desc, Style::default().fg(Color::DarkGray)),
-                ])
-            }
+                (
+                    Style::default().fg(name_color),
+                    Style::default().fg(Color::DarkGray),
+                )
+            };
+            // Offsets skip the "/" before the name and the indent before the description.
+            let name_matches = fuzzy_matches::positions(query, &cmd.name);
+            let (name_matches, desc_matches): (Vec<usize>, Vec<usize>) = if name_matches.is_empty()
+            {
+                let desc_matches = fuzzy_matches::positions(query, &cmd.description);
+                (vec![], desc_matches.into_iter().map(|p| p + 2).collect())
+            } else {
+                (name_matches.into_iter().map(|p| p + 1).collect(), vec![])
+            };
+            let mut spans = fuzzy_matches::highlight(&name, &name_matches, name_style);
+            spans.extend(fuzzy_matches::highlight(&desc, &desc_matches, desc_style));
+            Line::from(spans)
         })
         .collect();
 
@@ -1,14 +1,36 @@
 //! TUI rendering: layout and widgets for the chat interface.
 
 mod command_form_popup;
+mod command_palette_popup;
+mod commit_popup;
+mod context_preview_popup;
+mod copy_mode_popup;
+mod debug_overlay;
 mod delete_command_popup;
+mod diff_viewer;
+mod file_tree_popup;
+mod fuzzy_matches;
 mod header;
 mod history;
 mod history_selector_popup;
 mod input;
+mod params_popup;
+mod permissions_popup;
+mod pins_popup;
 mod popups;
+mod profile_popup;
+mod side_pane;
+mod stats_screen;
+mod status_line;
+mod tool_queue;
+mod tool_result;
 mod welcome_mascot;
 
+#[cfg(test)]
+mod tests;
+
+pub(crate) use header::format_tokens_compact;
+
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
 use ratatui::style::{Color, Style};
@@ -16,11 +38,14 @@ use ratatui::text::Line;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use std::time::Instant;
 
-use crate::core::commands;
-
 use super::app::App;
 use super::constants::ACCENT;
 
+/// Narrower terminals keep the single-column layout when the split pane is on.
+const SPLIT_MIN_WIDTH: u16 = 80;
+/// Share of the width given to the history in the split layout, in percent.
+const SPLIT_HISTORY_PERCENT: u16 = 55;
+
 pub(super) fn draw(f: &mut Frame, app: &mut App, area: Rect) {
     let is_welcome = app.messages.is_empty();
     if is_welcome {
@@ -45,32 +70,55 @@ pub(super) fn draw(f: &mut Frame, app: &mut App, area: Rect) {
         input::draw_welcome_center(f, app, chunks[2]);
         input::draw_bottom_bar(f, app, chunks[4]);
     } else {
-        let input_section_height = if app.input.starts_with('/')
-            && !commands::filter_commands_resolved(
-                &app.resolved_commands,
-                app.input.get(1..).unwrap_or(""),
-            )
-            .is_empty()
-        {
-            input::AUTOCOMPLETE_VISIBLE_LINES + super::constants::INPUT_LINES + 3
-        } else {
-            super::constants::INPUT_LINES + 3
-        };
+        let mut input_section_height = super::constants::INPUT_LINES + 3;
+        if app.autocomplete_open() {
+            input_section_height += input::AUTOCOMPLETE_VISIBLE_LINES;
+        }
+        if input::has_pins_line(app) {
+            input_section_height += 1;
+        }
+        let queue_height = tool_queue::height(app.visible_tool_queue());
+        let status_height = status_line::height(app.turn_status.as_ref());
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(2),
                 Constraint::Min(3),
+                Constraint::Length(queue_height),
+                Constraint::Length(status_height),
                 Constraint::Length(input_section_height),
             ])
             .split(area);
         header::draw_header(f, app, chunks[0], ACCENT);
-        history::draw_history(f, app, chunks[1]);
-        input::draw_input_section(f, app, chunks[2]);
+        if app.split_pane && chunks[1].width >= SPLIT_MIN_WIDTH {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(SPLIT_HISTORY_PERCENT),
+                    Constraint::Min(0),
+                ])
+                .split(chunks[1]);
+            history::draw_history(f, app, columns[0]);
+            side_pane::draw_side_pane(f, &app.side_pane, columns[1]);
+        } else {
+            history::draw_history(f, app, chunks[1]);
+        }
+        if queue_height > 0 {
+            tool_queue::draw_tool_queue(f, app.visible_tool_queue(), chunks[2]);
+        }
+        if let Some(ref status) = app.turn_status {
+            status_line::draw_status_line(f, status, &app.tool_queue, chunks[3]);
+        }
+        input::draw_input_section(f, app, chunks[4]);
     }
 
-    if let Some(ref popup) = app.confirm_popup {
-        popups::draw_confirm_popup(f, area, &popup.command);
+    if let Some(ref mut popup) = app.confirm_popup {
+        match popup.diff {
+            Some(ref mut diff) => {
+                diff_viewer::draw_diff_viewer(f, area, diff, diff_viewer::approval_footer())
+            }
+            None => popups::draw_confirm_popup(f, area, &popup.action),
+        }
     }
     if let Some(ref mut selector) = app.model_selector {
         popups::draw_model_selector_popup(f, area, selector);
@@ -84,10 +132,65 @@ pub(super) fn draw(f: &mut Frame, app: &mut App, area: Rect) {
     if let Some(ref mut state) = app.delete_command_popup {
         delete_command_popup::draw_delete_command_popup(f, area, state, &app.custom_templates);
     }
+    if let Some(ref mut state) = app.context_preview_popup {
+        context_preview_popup::draw_context_preview_popup(f, area, state);
+    }
+    if let Some(ref state) = app.params_popup {
+        params_popup::draw_params_popup(f, area, state);
+    }
+    if let Some(ref state) = app.permissions_popup {
+        permissions_popup::draw_permissions_popup(f, area, state);
+    }
+    if let Some(ref state) = app.pins_popup {
+        pins_popup::draw_pins_popup(f, area, app, state);
+    }
+    if let Some(ref state) = app.copy_mode {
+        copy_mode_popup::draw_copy_mode_popup(f, area, state);
+    }
+    if let Some(ref state) = app.file_tree {
+        file_tree_popup::draw_file_tree_popup(f, area, state);
+    }
+    if let Some(ref state) = app.command_palette {
+        command_palette_popup::draw_command_palette_popup(f, area, state);
+    }
+    if let Some(ref state) = app.profile_picker {
+        profile_popup::draw_profile_popup(f, area, state);
+    }
+    if let Some(ref state) = app.commit_popup {
+        commit_popup::draw_commit_popup(f, area, state);
+    }
+    if let Some(ref mut state) = app.diff_viewer {
+        let footer = diff_viewer::review_footer(state.files.len());
+        diff_viewer::draw_diff_viewer(f, area, state, footer);
+    }
+    if let Some(ref mut state) = app.suggestion_review {
+        diff_viewer::draw_diff_viewer(f, area, &mut state.view, diff_viewer::suggestion_footer());
+    }
+    if let Some(ref state) = app.save_last_popup {
+        popups::draw_save_last_popup(f, area, state);
+    }
+    if let Some(ref hit) = app.spend_popup {
+        popups::draw_spend_cap_popup(f, area, hit);
+    }
+    if let Some(ref state) = app.duplicate_popup {
+        popups::draw_duplicate_popup(f, area, state);
+    }
+    if let Some(ref report) = app.stats_screen {
+        stats_screen::draw_stats_screen(f, area, report);
+    }
+    if app.debug_overlay {
+        debug_overlay::draw_debug_overlay(f, area);
+    }
 
     // Toast: top right, below header (y=2). Opaque background so it's visible over history.
     draw_toast(f, area, " Copied ", &mut app.copy_toast_until);
     draw_toast(f, area, " Save failed ", &mut app.save_error_toast_until);
+    draw_toast(
+        f,
+        area,
+        " History in use by another instance ",
+        &mut app.history_busy_toast_until,
+    );
 }
 
 fn draw_toast(f: &mut Frame, area: Rect, text: &str, deadline: &mut Option<Instant>) {
@@ -0,0 +1,86 @@
+//! Generation parameters popup (/params).
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::{ParamsField, ParamsPopupState};
+use super::super::constants::ACCENT;
+
+/// Width of the field labels column.
+const LABEL_WIDTH: usize = 13;
+
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+fn field_line(state: &ParamsPopupState, field: ParamsField) -> Line<'static> {
+    let focused = field == state.focused_field;
+    let marker = if focused { "▸ " } else { "  " };
+    let label = format!("{}{:<width$}", marker, field.label(), width = LABEL_WIDTH);
+    let label_style = if focused {
+        Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    let value = state.value(field);
+    let mut spans = vec![Span::styled(label, label_style)];
+    if value.is_empty() {
+        spans.push(Span::styled(
+            state.placeholder(field),
+            Style::default().fg(Color::DarkGray),
+        ));
+    } else {
+        spans.push(Span::raw(value.to_string()));
+    }
+    if focused {
+        spans.push(Span::styled("_", Style::default().fg(Color::DarkGray)));
+    }
+    Line::from(spans)
+}
+
+pub(crate) fn draw_params_popup(f: &mut Frame, area: Rect, state: &ParamsPopupState) {
+    let popup_rect = popup_area(area, 60, 12);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(format!(" Generation parameters · {} ", state.model_id));
+
+    let mut lines = vec![Line::from("")];
+    lines.extend(
+        ParamsField::ALL
+            .iter()
+            .map(|&field| field_line(state, field)),
+    );
+    lines.push(Line::from(""));
+    lines.push(match state.error {
+        Some(ref err) => Line::from(Span::styled(
+            format!("  {}", err),
+            Style::default().fg(Color::Red),
+        )),
+        None => Line::from(Span::styled(
+            "  Empty fields use the default. Stop: comma-separated, \\n for a newline.",
+            Style::default().fg(Color::DarkGray),
+        )),
+    });
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  ↑↓ ", Style::default().fg(Color::DarkGray)),
+        Span::raw("field  "),
+        Span::styled("Enter ", Style::default().fg(Color::DarkGray)),
+        Span::raw("use for this session  "),
+        Span::styled("Ctrl+S ", Style::default().fg(Color::DarkGray)),
+        Span::raw("save for this model  "),
+        Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+        Span::raw("cancel"),
+    ]));
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(Paragraph::new(lines).block(block), popup_rect);
+}
@@ -0,0 +1,116 @@
+//! Bash command rules popup (/permissions).
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::PermissionsPopupState;
+use super::super::constants::ACCENT;
+use crate::core::command_rules::{CommandRule, RuleKind};
+
+/// Rules shown at once; the list scrolls to keep the selection visible.
+const VISIBLE_RULES: usize = 10;
+
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+fn kind_style(kind: RuleKind) -> Style {
+    match kind {
+        RuleKind::Allow => Style::default().fg(Color::Green),
+        RuleKind::Deny => Style::default().fg(Color::Red),
+    }
+}
+
+fn rule_line(rule: &CommandRule, selected: bool) -> Line<'static> {
+    let marker = if selected { "▸ " } else { "  " };
+    let pattern_style = if selected {
+        Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Line::from(vec![
+        Span::raw(marker),
+        Span::styled(format!("{:<6}", rule.kind.label()), kind_style(rule.kind)),
+        Span::styled(rule.pattern.clone(), pattern_style),
+        Span::styled(
+            if rule.saved { "" } else { "  (session)" },
+            Style::default().fg(Color::DarkGray),
+        ),
+    ])
+}
+
+pub(crate) fn draw_permissions_popup(f: &mut Frame, area: Rect, state: &PermissionsPopupState) {
+    let popup_rect = popup_area(area, 70, VISIBLE_RULES as u16 + 8);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Bash command rules ");
+
+    let mut lines = vec![Line::from("")];
+    if state.rules.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No rules: destructive commands (rm, mv...) ask for confirmation.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let first = (state.selected + 1).saturating_sub(VISIBLE_RULES);
+    lines.extend(
+        state
+            .rules
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_RULES)
+            .map(|(i, rule)| rule_line(rule, i == state.selected)),
+    );
+    lines.push(Line::from(""));
+    lines.push(match (&state.input, &state.message) {
+        (Some((kind, pattern)), _) => Line::from(vec![
+            Span::styled(format!("  New {} rule: ", kind.label()), kind_style(*kind)),
+            Span::raw(pattern.clone()),
+            Span::styled("_", Style::default().fg(Color::DarkGray)),
+        ]),
+        (None, Some(message)) => Line::from(Span::styled(
+            format!("  {}", message),
+            Style::default().fg(ACCENT),
+        )),
+        (None, None) => Line::from(Span::styled(
+            "  Exact command, or a prefix ending with * (e.g. rm -rf target/*).",
+            Style::default().fg(Color::DarkGray),
+        )),
+    });
+    lines.push(Line::from(""));
+    lines.push(Line::from(if state.input.is_some() {
+        vec![
+            Span::styled("  Enter ", Style::default().fg(Color::DarkGray)),
+            Span::raw("add for this session  "),
+            Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+            Span::raw("cancel"),
+        ]
+    } else {
+        vec![
+            Span::styled("  ↑↓ ", Style::default().fg(Color::DarkGray)),
+            Span::raw("select  "),
+            Span::styled("s ", Style::default().fg(Color::DarkGray)),
+            Span::raw("save  "),
+            Span::styled("d ", Style::default().fg(Color::DarkGray)),
+            Span::raw("delete  "),
+            Span::styled("a ", Style::default().fg(Color::DarkGray)),
+            Span::raw("allow  "),
+            Span::styled("x ", Style::default().fg(Color::DarkGray)),
+            Span::raw("deny  "),
+            Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+            Span::raw("close"),
+        ]
+    }));
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(Paragraph::new(lines).block(block), popup_rect);
+}
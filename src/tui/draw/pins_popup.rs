@@ -0,0 +1,79 @@
+//! Pinned messages popup (/pins).
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::{App, ChatMessage, PinsPopupState, pin_preview};
+use super::super::constants::ACCENT;
+
+/// Pins shown at once; the list scrolls to keep the selection visible.
+const VISIBLE_PINS: usize = 10;
+
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+pub(crate) fn draw_pins_popup(f: &mut Frame, area: Rect, app: &App, state: &PinsPopupState) {
+    let popup_rect = popup_area(area, 70, VISIBLE_PINS as u16 + 6);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Pinned messages ");
+    // Room for the marker, the role, and the borders.
+    let preview_width = (popup_rect.width as usize).saturating_sub(16);
+
+    let mut lines = vec![Line::from("")];
+    let first = (state.selected + 1).saturating_sub(VISIBLE_PINS);
+    for (i, &msg_idx) in app
+        .pinned_messages
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(VISIBLE_PINS)
+    {
+        let (role, text) = match app.messages.get(msg_idx) {
+            Some(ChatMessage::User(text)) => ("You", text.as_str()),
+            Some(ChatMessage::Assistant(text)) => ("Assistant", text.as_str()),
+            _ => continue,
+        };
+        let selected = i == state.selected;
+        let preview_style = if selected {
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::raw(if selected { "▸ " } else { "  " }),
+            Span::styled(
+                format!("{:<10}", role),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(pin_preview(text, preview_width), preview_style),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Quoted in the system prompt of every request, even after /compact.",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from(vec![
+        Span::styled("  ↑↓ ", Style::default().fg(Color::DarkGray)),
+        Span::raw("select  "),
+        Span::styled("Enter ", Style::default().fg(Color::DarkGray)),
+        Span::raw("show  "),
+        Span::styled("d ", Style::default().fg(Color::DarkGray)),
+        Span::raw("unpin  "),
+        Span::styled("Esc ", Style::default().fg(Color::DarkGray)),
+        Span::raw("close"),
+    ]));
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(Paragraph::new(lines).block(block), popup_rect);
+}
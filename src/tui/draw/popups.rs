@@ -1,4 +1,5 @@
-//! Popups: confirm destructive command, model selector.
+//! Popups: confirm destructive command, duplicate conversation, `/save-last` write, model
+//! selector.
 
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
@@ -6,10 +7,90 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
 
-use crate::core::models::filter_models;
+use crate::core::confirm::PendingAction;
+use crate::core::models::format_context_length;
+use crate::core::spend::LimitHit;
+use crate::core::text::{display_width, truncate_end};
 
-use super::super::app::ModelSelectorState;
+use super::super::app::{DuplicatePopupState, ModelSection, ModelSelectorState, SaveLastState};
 use super::super::constants::{self, ACCENT};
+use super::fuzzy_matches;
+use super::history_selector_popup::format_conversation;
+
+/// Column widths of the model selector rows: star, name, context, and price.
+struct ModelColumns {
+    name: usize,
+}
+
+impl ModelColumns {
+    const STAR: usize = 2;
+    const CONTEXT: usize = 7;
+    const PRICE: usize = 14;
+
+    fn new(width: u16) -> Self {
+        // Leading space and the three separators of `row`.
+        let fixed = Self::STAR + Self::CONTEXT + Self::PRICE + 4;
+        Self {
+            name: (width as usize).saturating_sub(fixed).max(8),
+        }
+    }
+
+    /// One row: name left-aligned (truncated to fit), context and price right-aligned.
+    fn row(&self, star: &str, name: &str, context: &str, price: &str) -> String {
+        format!(
+            " {} {} {} {}",
+            pad_to_width(star, Self::STAR, false),
+            pad_to_width(&truncate_end(name, self.name), self.name, false),
+            pad_to_width(context, Self::CONTEXT, true),
+            pad_to_width(price, Self::PRICE, true),
+        )
+    }
+
+    /// One model row in `style`, with the characters of the name at `matches` highlighted.
+    fn row_line(
+        &self,
+        star: &str,
+        name: &str,
+        matches: &[usize],
+        context: &str,
+        price: &str,
+        style: Style,
+    ) -> Line<'static> {
+        let shown = truncate_end(name, self.name);
+        // Matches past a truncation fall on or after the ellipsis: not highlighted.
+        let kept = if shown == name {
+            shown.chars().count()
+        } else {
+            shown.chars().count().saturating_sub(1)
+        };
+        let matches: Vec<usize> = matches.iter().copied().filter(|&p| p < kept).collect();
+        let mut spans = vec![Span::styled(
+            format!(" {} ", pad_to_width(star, Self::STAR, false)),
+            style,
+        )];
+        spans.extend(fuzzy_matches::highlight(&shown, &matches, style));
+        spans.push(Span::styled(
+            format!(
+                "{} {} {}",
+                " ".repeat(self.name.saturating_sub(display_width(&shown))),
+                pad_to_width(context, Self::CONTEXT, true),
+                pad_to_width(price, Self::PRICE, true),
+            ),
+            style,
+        ));
+        Line::from(spans)
+    }
+}
+
+/// Pad `s` with spaces to `width` columns, on the left when `right_align`.
+fn pad_to_width(s: &str, width: usize, right_align: bool) -> String {
+    let padding = " ".repeat(width.saturating_sub(display_width(s)));
+    if right_align {
+        format!("{}{}", padding, s)
+    } else {
+        format!("{}{}", s, padding)
+    }
+}
 
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -19,28 +100,143 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     horizontal_areas[0]
 }
 
-pub(crate) fn draw_confirm_popup(f: &mut Frame, area: Rect, command: &str) {
+pub(crate) fn draw_confirm_popup(f: &mut Frame, area: Rect, action: &PendingAction) {
     let popup_rect = popup_area(area, 70, 25);
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(ACCENT))
-        .title(" ⚠ Destructive command ");
+        .title(format!(" ⚠ {} ", action.title()));
 
-    let text = vec![
+    let (label, value) = action.detail();
+    let mut text = vec![
         Line::from(""),
         Line::from(vec![
-            Span::raw("Command: "),
+            Span::raw(format!("{}: ", label)),
             Span::styled(
-                command,
+                value,
                 Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
             ),
         ]),
         Line::from(""),
+    ];
+    let mut keys = vec![
+        Span::styled("y ", Style::default().fg(ACCENT)),
+        Span::raw("confirm  "),
+    ];
+    if action.allowable_command().is_some() {
+        keys.push(Span::styled("a ", Style::default().fg(ACCENT)));
+        keys.push(Span::raw("always allow  "));
+    }
+    keys.push(Span::styled("n ", Style::default().fg(Color::DarkGray)));
+    keys.push(Span::raw("cancel"));
+    text.push(Line::from(keys));
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .alignment(ratatui::layout::HorizontalAlignment::Center);
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(paragraph, popup_rect);
+}
+
+pub(crate) fn draw_duplicate_popup(f: &mut Frame, area: Rect, state: &DuplicatePopupState) {
+    let popup_rect = popup_area(area, 70, 25);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Similar conversation ");
+
+    let text = vec![
+        Line::from(""),
+        Line::from("A saved conversation starts with the same prompt:"),
+        Line::from(Span::styled(
+            format_conversation(&state.existing),
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
         Line::from(vec![
             Span::styled("y ", Style::default().fg(ACCENT)),
-            Span::raw("confirm  "),
+            Span::raw("continue it  "),
             Span::styled("n ", Style::default().fg(Color::DarkGray)),
-            Span::raw("cancel"),
+            Span::raw("save as new"),
+        ]),
+    ];
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .alignment(ratatui::layout::HorizontalAlignment::Center);
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(paragraph, popup_rect);
+}
+
+pub(crate) fn draw_save_last_popup(f: &mut Frame, area: Rect, state: &SaveLastState) {
+    let popup_rect = popup_area(area, 70, 25);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Save to file ");
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(format!(
+            "Write {} ({} lines) to:",
+            state.what,
+            state.content.lines().count()
+        )),
+        Line::from(Span::styled(
+            state.display_path.as_str(),
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        )),
+    ];
+    let warnings = [
+        (state.exists, "The file exists and will be overwritten."),
+        (
+            state.outside_workspace,
+            "The file is outside the workspace.",
+        ),
+    ];
+    for (_, warning) in warnings.iter().filter(|(shown, _)| *shown) {
+        text.push(Line::from(Span::styled(
+            *warning,
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(vec![
+        Span::styled("y ", Style::default().fg(ACCENT)),
+        Span::raw("write  "),
+        Span::styled("n ", Style::default().fg(Color::DarkGray)),
+        Span::raw("cancel"),
+    ]));
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .alignment(ratatui::layout::HorizontalAlignment::Center);
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(paragraph, popup_rect);
+}
+
+/// Confirmation at a reached spend cap. Only y continues: Enter keeps the prompt, so a
+/// reflexive keypress does not spend past the cap.
+pub(crate) fn draw_spend_cap_popup(f: &mut Frame, area: Rect, hit: &LimitHit) {
+    let popup_rect = popup_area(area, 60, 25);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red))
+        .title(" Spend cap reached ");
+
+    let text = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            hit.to_string(),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        Line::from("Continue past the caps for the rest of this session?"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("y ", Style::default().fg(ACCENT)),
+            Span::raw("send anyway  "),
+            Span::styled("n ", Style::default().fg(Color::DarkGray)),
+            Span::raw("keep the prompt"),
         ]),
     ];
     let paragraph = Paragraph::new(text)
@@ -56,7 +252,7 @@ pub(crate) fn draw_model_selector_popup(
     area: Rect,
     selector: &mut ModelSelectorState,
 ) {
-    let popup_rect = popup_area(area, 60, 50);
+    let popup_rect = popup_area(area, 70, 60);
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(ACCENT))
@@ -126,13 +322,11 @@ pub(crate) fn draw_model_selector_popup(
         ]);
         f.render_widget(Paragraph::new(loading_line), list_area);
     } else {
-        let filtered = filter_models(&selector.models, &selector.filter);
-        let clamped_index = selector
-            .selected_index
-            .min(filtered.len().saturating_sub(1));
-        selector.selected_index = clamped_index;
+        let entries = selector.entries();
+        let selected = selector.selected_index.min(entries.len().saturating_sub(1));
 
-        if filtered.is_empty() {
+        if entries.is_empty() {
+            selector.selected_index = 0;
             let msg = if selector.filter.is_empty() {
                 "No models"
             } else {
@@ -146,24 +340,62 @@ pub(crate) fn draw_model_selector_popup(
             )));
             f.render_widget(para, list_area);
         } else {
-            let items: Vec<ListItem> = filtered
-                .iter()
-                .enumerate()
-                .map(|(i, m)| {
-                    let style = if i == selector.selected_index {
-                        Style::default().fg(Color::Black).bg(ACCENT)
-                    } else {
+            let columns = ModelColumns::new(list_area.width);
+            let [header_area, rows_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(list_area);
+            f.render_widget(
+                Paragraph::new(Line::from(Span::styled(
+                    columns.row("", "Model", "Context", "$/M in/out"),
+                    Style::default().fg(Color::DarkGray),
+                ))),
+                header_area,
+            );
+
+            // Section titles are shown once more than one section is listed.
+            let sectioned = entries
+                .first()
+                .is_some_and(|(s, _)| *s != ModelSection::All);
+            let mut items: Vec<ListItem> = Vec::new();
+            let mut selected_item = 0;
+            let mut section = None;
+            for (i, (entry_section, m)) in entries.iter().enumerate() {
+                if sectioned && section != Some(*entry_section) {
+                    section = Some(*entry_section);
+                    items.push(ListItem::new(Span::styled(
+                        format!(" {}", entry_section.title()),
                         Style::default()
-                    };
-                    ListItem::new(format!(" {} ", m.name)).style(style)
-                })
-                .collect();
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    )));
+                }
+                let star = if selector.prefs.is_favorite(&m.id) {
+                    "★"
+                } else {
+                    ""
+                };
+                let style = if i == selected {
+                    selected_item = items.len();
+                    Style::default().fg(Color::Black).bg(ACCENT)
+                } else {
+                    Style::default()
+                };
+                let line = columns.row_line(
+                    star,
+                    &m.name,
+                    &fuzzy_matches::positions(&selector.filter, &m.name),
+                    &format_context_length(m.context_length),
+                    &m.price_label(),
+                    style,
+                );
+                items.push(ListItem::new(line).style(style));
+            }
 
-            selector.list_state.select(Some(selector.selected_index));
+            selector.selected_index = selected;
+            selector.list_state.select(Some(selected_item));
 
             let list =
                 List::new(items).highlight_style(Style::default().fg(Color::Black).bg(ACCENT));
-            f.render_stateful_widget(list, list_area, &mut selector.list_state);
+            f.render_stateful_widget(list, rows_area, &mut selector.list_state);
         }
     }
 
@@ -176,8 +408,8 @@ pub(crate) fn draw_model_selector_popup(
         Span::raw("cancel  "),
         Span::styled("type ", Style::default().fg(Color::DarkGray)),
         Span::raw("filter  "),
-        Span::styled("Alt+M ", Style::default().fg(Color::DarkGray)),
-        Span::raw("reopen"),
+        Span::styled("Ctrl+F ", Style::default().fg(Color::DarkGray)),
+        Span::raw("star"),
     ]));
     f.render_widget(hint, hint_area);
 }
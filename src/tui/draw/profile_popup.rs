@@ -0,0 +1,72 @@
+//! Profile switcher popup (/profile).
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use super::super::app::ProfilePickerState;
+use super::super::constants::ACCENT;
+use crate::core::text::truncate_end;
+
+/// Width of the name column.
+const NAME_WIDTH: usize = 16;
+
+fn popup_area(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let vertical_areas = vertical.split(area);
+    let horizontal_areas = horizontal.split(vertical_areas[0]);
+    horizontal_areas[0]
+}
+
+pub(crate) fn draw_profile_popup(f: &mut Frame, area: Rect, state: &ProfilePickerState) {
+    let popup_rect = popup_area(area, 60, state.entries.len() as u16 + 6);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Profiles ");
+    // Room for the markers, the name column, and the borders.
+    let summary_width = (popup_rect.width as usize).saturating_sub(NAME_WIDTH + 10);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let mut lines = vec![Line::from("")];
+    for (i, entry) in state.entries.iter().enumerate() {
+        let selected = i == state.selected;
+        let name_style = if selected {
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let name = entry.name.as_deref().unwrap_or("(none)");
+        lines.push(Line::from(vec![
+            Span::raw(if selected { "▸ " } else { "  " }),
+            Span::styled(
+                if i == state.active { "● " } else { "  " },
+                Style::default().fg(ACCENT),
+            ),
+            Span::styled(
+                format!("{:<w$} ", truncate_end(name, NAME_WIDTH), w = NAME_WIDTH),
+                name_style,
+            ),
+            Span::styled(truncate_end(&entry.summary, summary_width), dim),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  ↑↓ ", dim),
+        Span::raw("select  "),
+        Span::styled("Enter ", dim),
+        Span::raw("switch  "),
+        Span::styled("Esc ", dim),
+        Span::raw("close"),
+    ]));
+    lines.push(Line::from(Span::styled(
+        "  ● active. Switching reloads the provider, model, theme, and tool permissions.",
+        dim,
+    )));
+
+    f.render_widget(Clear, popup_rect);
+    f.render_widget(Paragraph::new(lines).block(block), popup_rect);
+}
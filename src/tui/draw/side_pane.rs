@@ -0,0 +1,141 @@
+//! Draw the side pane of the split layout (Alt+S): the task plan on top, then the file most
+//! recently touched by a tool, scrolled to the lines the call changed.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use super::super::app::{PaneFile, SidePane};
+use super::diff_viewer::{ADDED, ADDED_BG, REMOVED};
+use super::tool_result::{fit_spans, plan_rows};
+use crate::core::tools::ToolView;
+
+/// Lines kept above the first change when scrolling to it.
+const CONTEXT_LINES: usize = 3;
+/// Tallest plan section, as a fraction of the pane height.
+const PLAN_MAX_FRACTION: u16 = 3;
+
+fn block(title: String) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(title)
+}
+
+pub(super) fn draw_side_pane(f: &mut Frame, pane: &SidePane, area: Rect) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let plan_height = if pane.plan.is_empty() {
+        0
+    } else {
+        (pane.plan.len() as u16 + 2).min((area.height / PLAN_MAX_FRACTION).max(3))
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(plan_height), Constraint::Min(0)])
+        .split(area);
+
+    if plan_height > 0 {
+        let summary = ToolView::Plan {
+            steps: pane.plan.clone(),
+        }
+        .summary();
+        let width = chunks[0].width.saturating_sub(2) as usize;
+        let lines: Vec<Line> = plan_rows(&pane.plan)
+            .into_iter()
+            .map(|spans| Line::from(fit_spans(spans, width)))
+            .collect();
+        f.render_widget(
+            Paragraph::new(lines).block(block(format!(" Plan · {} ", summary))),
+            chunks[0],
+        );
+    }
+
+    let Some(file) = &pane.file else {
+        let hint = Paragraph::new(vec![
+            Line::from(Span::styled(
+                "The last file a tool reads or edits shows here,",
+                dim,
+            )),
+            Line::from(Span::styled("with the lines it changed highlighted.", dim)),
+            Line::from(""),
+            Line::from(Span::styled("Alt+S hides this pane.", dim)),
+        ])
+        .block(block(" Files ".to_string()));
+        f.render_widget(hint, chunks[1]);
+        return;
+    };
+    let state = if file.created {
+        " (new)"
+    } else if file.deleted {
+        " (deleted)"
+    } else {
+        ""
+    };
+    let mut title = format!(" {}{} · {}", file.display, state, file.tool);
+    if !file.changed.is_empty() {
+        title.push_str(&format!(" · +{}", file.changed.len()));
+    }
+    title.push(' ');
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    let lines: Vec<Line> = if file.deleted {
+        vec![Line::from(Span::styled("The file was deleted.", dim))]
+    } else {
+        file_lines(file, height, width)
+    };
+    f.render_widget(Paragraph::new(lines).block(block(title)), chunks[1]);
+}
+
+/// The `height` lines of `file` from the first change (with some context above), with a
+/// line-number gutter: "+" on changed lines, "−" where lines were removed.
+fn file_lines(file: &PaneFile, height: usize, width: usize) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    // One row past the last line when lines were removed at the end.
+    let rows =
+        file.lines.len() + usize::from(file.removed_before.contains(&(file.lines.len() + 1)));
+    let top = file
+        .first_change()
+        .map_or(0, |n| n.saturating_sub(CONTEXT_LINES + 1))
+        .min(rows.saturating_sub(height));
+    let number_width = rows.to_string().len();
+    (top..rows.min(top + height))
+        .map(|i| {
+            let number = i + 1;
+            let text = file.lines.get(i).map(String::as_str).unwrap_or("");
+            let (marker, text_style) = if file.changed.contains(&number) {
+                (
+                    Span::styled("+", Style::default().fg(ADDED)),
+                    Style::default().bg(ADDED_BG),
+                )
+            } else if file.removed_before.contains(&number) {
+                (
+                    Span::styled("−", Style::default().fg(REMOVED)),
+                    Style::default(),
+                )
+            } else {
+                (Span::raw(" "), Style::default())
+            };
+            let number = if i < file.lines.len() {
+                format!("{:>w$} ", number, w = number_width)
+            } else {
+                " ".repeat(number_width + 1)
+            };
+            let number_style = if marker.content == " " {
+                dim
+            } else {
+                dim.add_modifier(Modifier::BOLD)
+            };
+            Line::from(fit_spans(
+                vec![
+                    Span::styled(number, number_style),
+                    marker,
+                    Span::raw(" "),
+                    Span::styled(text.replace('\t', "    "), text_style),
+                ],
+                width,
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+//! Usage statistics screen (F9): daily and weekly token charts, top models, slash commands,
+//! and tools across sessions.
+
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Sparkline};
+
+use super::super::constants::ACCENT;
+use super::header::format_tokens_compact;
+use crate::core::stats::{PeriodTotals, StatsReport};
+use crate::core::text::truncate_end;
+
+/// Header height kept visible above the screen.
+const HEADER_HEIGHT: u16 = 2;
+/// Height of each chart, borders included.
+const CHART_HEIGHT: u16 = 7;
+
+fn chart(f: &mut Frame, area: Rect, title: String, periods: &[PeriodTotals]) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(title);
+    // One column per period: keep the most recent ones when the chart is too narrow.
+    let width = block.inner(area).width as usize;
+    let tokens: Vec<u64> = periods.iter().map(|p| p.tokens).collect();
+    let shown = &tokens[tokens.len().saturating_sub(width)..];
+    let sparkline = Sparkline::default()
+        .block(block)
+        .data(shown)
+        .style(Style::default().fg(ACCENT));
+    f.render_widget(sparkline, area);
+}
+
+fn top_list(title: &str, rows: Vec<(String, String)>, width: u16) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(Span::styled(
+        title.to_string(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    if rows.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "none yet",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (name, value) in rows {
+        let name_width = (width as usize).saturating_sub(value.len() + 2);
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "{:<w$} ",
+                truncate_end(&name, name_width),
+                w = name_width
+            )),
+            Span::styled(value, Style::default().fg(Color::DarkGray)),
+        ]));
+    }
+    lines
+}
+
+pub(crate) fn draw_stats_screen(f: &mut Frame, area: Rect, report: &StatsReport) {
+    let rect = Rect {
+        y: area.y + HEADER_HEIGHT.min(area.height),
+        height: area.height.saturating_sub(HEADER_HEIGHT),
+        ..area
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(ACCENT))
+        .title(" Usage statistics ")
+        .title_bottom(Line::from(vec![
+            Span::styled(" F9/Esc ", Style::default().fg(Color::DarkGray)),
+            Span::raw("close "),
+        ]))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(rect);
+    f.render_widget(Clear, rect);
+    f.render_widget(block, rect);
+
+    let [summary, daily, weekly, tops] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(CHART_HEIGHT),
+        Constraint::Length(CHART_HEIGHT),
+        Constraint::Min(0),
+    ])
+    .areas(inner);
+
+    let summary_line = Line::from(vec![
+        Span::styled(
+            format!("Last {} days  ", report.days.len()),
+            Style::default().fg(ACCENT).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!(
+            "{} sessions · {} requests · {} tokens ({} in, {} out) · ${:.2}",
+            report.sessions,
+            report.requests,
+            format_tokens_compact(report.prompt_tokens + report.completion_tokens),
+            format_tokens_compact(report.prompt_tokens),
+            format_tokens_compact(report.completion_tokens),
+            report.cost
+        )),
+    ]);
+    f.render_widget(Paragraph::new(summary_line), summary);
+
+    chart(
+        f,
+        daily,
+        format!(" Daily tokens · last {} days ", report.days.len()),
+        &report.days,
+    );
+    chart(
+        f,
+        weekly,
+        format!(" Weekly tokens · last {} weeks ", report.weeks.len()),
+        &report.weeks,
+    );
+
+    let columns = Layout::horizontal([
+        Constraint::Percentage(50),
+        Constraint::Percentage(25),
+        Constraint::Percentage(25),
+    ])
+    .spacing(2)
+    .split(tops.inner(ratatui::layout::Margin::new(1, 1)));
+    let models = report
+        .top_models
+        .iter()
+        .map(|(model, usage)| {
+            (
+                model.clone(),
+                format!(
+                    "{} tok · {} req · ${:.2}",
+                    format_tokens_compact(usage.tokens),
+                    usage.requests,
+                    usage.cost
+                ),
+            )
+        })
+        .collect();
+    let commands = report
+        .top_commands
+        .iter()
+        .map(|(name, count)| (format!("/{}", name), count.to_string()))
+        .collect();
+    let tools = report
+        .top_tools
+        .iter()
+        .map(|(name, count)| (name.clone(), count.to_string()))
+        .collect();
+    for (area, lines) in [
+        (columns[0], top_list("Top models", models, columns[0].width)),
+        (
+            columns[1],
+            top_list("Top slash commands", commands, columns[1].width),
+        ),
+        (columns[2], top_list("Top tools", tools, columns[2].width)),
+    ] {
+        f.render_widget(Paragraph::new(lines), area);
+    }
+}
@@ -0,0 +1,101 @@
+//! Status line above the input while a request is in flight: spinner, current activity,
+//! and elapsed time of the activity and of the whole turn.
+
+use std::time::Instant;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Paragraph;
+
+use crate::core::text;
+
+use super::super::app::{Activity, ToolQueueEntry, ToolStatus, TurnStatus};
+use super::super::constants::{ACCENT, LOGO_THINKING};
+use super::tool_queue::format_duration;
+
+/// Height of the status line (0 when no request is in flight).
+pub(crate) fn height(status: Option<&TurnStatus>) -> u16 {
+    u16::from(status.is_some())
+}
+
+/// Description of the current activity. Parallel tool calls are summarized from the queue.
+fn activity_text(status: &TurnStatus, queue: &[ToolQueueEntry]) -> String {
+    let running = queue
+        .iter()
+        .filter(|e| e.status == ToolStatus::Running)
+        .count();
+    match &status.activity {
+        Activity::Thinking => "Thinking".to_string(),
+        Activity::Responding => "Writing the reply".to_string(),
+        Activity::Preparing { summary, .. } => summary.clone(),
+        Activity::Tool { .. } if running > 1 => {
+            let finished = queue
+                .iter()
+                .filter(|e| matches!(e.status, ToolStatus::Done | ToolStatus::Failed))
+                .count();
+            format!(
+                "Running {} tools in parallel ({}/{} done)",
+                running,
+                finished,
+                queue.len()
+            )
+        }
+        Activity::Tool {
+            name,
+            preview,
+            index,
+            total,
+        } => {
+            let position = if *total > 1 {
+                format!(" ({}/{})", index, total)
+            } else {
+                String::new()
+            };
+            format!("Running {}: {}{}", name, preview, position)
+        }
+        Activity::Retrying {
+            until,
+            attempt,
+            max_attempts,
+        } => format!(
+            "Retrying in {}s (attempt {}/{})",
+            until
+                .saturating_duration_since(Instant::now())
+                .as_secs_f64()
+                .ceil(),
+            attempt,
+            max_attempts
+        ),
+    }
+}
+
+pub(crate) fn draw_status_line(
+    f: &mut Frame,
+    status: &TurnStatus,
+    queue: &[ToolQueueEntry],
+    area: Rect,
+) {
+    let turn_elapsed = status.started.elapsed();
+    let frame = (turn_elapsed.as_millis() as usize / 80) % LOGO_THINKING.len();
+    let times = format!(
+        "{} · turn {} ",
+        format_duration(status.activity_elapsed()),
+        format_duration(turn_elapsed)
+    );
+    let text_width = (area.width as usize).saturating_sub(times.chars().count() + 4);
+    let text = text::truncate_end(&activity_text(status, queue), text_width);
+    let padding = text_width.saturating_sub(text::display_width(&text));
+
+    let line = Line::from(vec![
+        Span::styled(
+            format!(" {} ", LOGO_THINKING[frame]),
+            Style::default().fg(ACCENT),
+        ),
+        Span::raw(text),
+        Span::raw(" ".repeat(padding + 1)),
+        Span::styled(times, Style::default().fg(Color::DarkGray)),
+    ]);
+    f.render_widget(Paragraph::new(line), area);
+}
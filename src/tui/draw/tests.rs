@@ -0,0 +1,266 @@
+//! Snapshot tests of the drawn screen: key states are rendered on a `TestBackend` and the
+//! text of the buffer is compared with `snapshots/<name>.txt`, so that overlapping or
+//! misplaced widgets show up as a diff. After an intended layout change, run the tests with
+//! `UPDATE_SNAPSHOTS=1` to rewrite the snapshots, and review them.
+
+use std::path::PathBuf;
+
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::widgets::ListState;
+use ratatui::{Frame, Terminal};
+
+use crate::core::config::ModelSource;
+use crate::core::confirm::PendingAction;
+use crate::core::llm::{ChatError, ContextPreview, PromptSection};
+use crate::core::models::ModelInfo;
+use crate::core::persistence::ModelPrefs;
+use crate::core::workspace::Workspace;
+use crate::core::{app as core_app, text};
+
+use super::super::app::{
+    Activity, App, ChatMessage, ContextPreviewState, ErrorNotice, ModelSelectorState, TabLabel,
+    TurnStatus,
+};
+use super::{context_preview_popup, draw, popups};
+
+/// Width of the header logo cell. It shows the number of saved conversations, which
+/// depends on the machine, so it is blanked in the snapshots.
+const LOGO_WIDTH: u16 = 8;
+
+fn app() -> App {
+    let root = PathBuf::from("/work/project");
+    let mut app = App::new(
+        "mock/model".to_string(),
+        "Mock Model".to_string(),
+        ModelSource::Default,
+        Workspace {
+            root: root.clone(),
+            working_dir: root,
+            project_type: None,
+            agent_md: None,
+            context_files: Vec::new(),
+            git_context: None,
+        },
+        false,
+        false,
+    );
+    // Custom commands of the machine's config would show as a load error.
+    app.templates_load_error = None;
+    app
+}
+
+fn conversation(app: &mut App) {
+    app.messages = vec![
+        ChatMessage::User("How is the project built?".to_string()),
+        ChatMessage::ToolLog("→ Read: Cargo.toml".to_string()),
+        ChatMessage::Assistant(
+            "It is a single Cargo package:\n\n```sh\ncargo build --release\n```\n\nThe binary is `target/release/my-open-claude`.".to_string(),
+        ),
+    ];
+}
+
+/// Text of the screen drawn by `draw_screen`, one line per row, trailing spaces trimmed.
+fn screen(width: u16, height: u16, draw_screen: impl FnOnce(&mut Frame)) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let frame = terminal.draw(draw_screen).unwrap();
+    buffer_text(frame.buffer)
+}
+
+fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut out = String::new();
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        // Cells covered by the previous wide character.
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let symbol = if y == area.top() && x < area.left() + LOGO_WIDTH {
+                " "
+            } else {
+                buffer[(x, y)].symbol()
+            };
+            skip = text::display_width(symbol).saturating_sub(1);
+            line.push_str(symbol);
+        }
+        // The version in the title is masked so that releases leave the snapshots alone.
+        let line = line.replace(core_app::VERSION, &"#".repeat(core_app::VERSION.len()));
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/tui/draw/snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {} (run with UPDATE_SNAPSHOTS=1)",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "screen differs from {} (run with UPDATE_SNAPSHOTS=1 to accept it):\n{}",
+        path.display(),
+        actual
+    );
+}
+
+#[test]
+fn welcome_screen() {
+    let mut app = app();
+    assert_snapshot("welcome", &screen(100, 30, |f| draw(f, &mut app, f.area())));
+}
+
+#[test]
+fn streaming_reply() {
+    let mut app = app();
+    app.messages = vec![
+        ChatMessage::User("Summarize the README".to_string()),
+        ChatMessage::Assistant("The project is a terminal chat agent. It".to_string()),
+    ];
+    app.is_streaming = true;
+    let mut status = TurnStatus::new();
+    status.activity = Activity::Responding;
+    app.turn_status = Some(status);
+    assert_snapshot(
+        "streaming",
+        &screen(100, 30, |f| draw(f, &mut app, f.area())),
+    );
+}
+
+#[test]
+fn confirm_popup() {
+    let mut app = app();
+    conversation(&mut app);
+    // `App::confirm_popup` holds the agent loop state, which only a turn creates: the
+    // popup is drawn over the screen the way `draw` does it.
+    let action = PendingAction::Command("rm -rf target".to_string());
+    let text = screen(100, 30, |f| {
+        draw(f, &mut app, f.area());
+        popups::draw_confirm_popup(f, f.area(), &action);
+    });
+    assert_snapshot("confirm_popup", &text);
+}
+
+#[test]
+fn model_selector() {
+    let mut app = app();
+    conversation(&mut app);
+    let model = |id: &str, name: &str, price: Option<f64>| ModelInfo {
+        id: id.to_string(),
+        name: name.to_string(),
+        context_length: 200_000,
+        prompt_price: price,
+        completion_price: price.map(|p| p * 5.0),
+        cache_read_price: None,
+        cache_write_price: None,
+    };
+    app.model_selector = Some(ModelSelectorState {
+        models: vec![
+            model("mock/model", "Mock Model", Some(0.000_003)),
+            model("mock/small", "Mock Small", Some(0.000_000_25)),
+            model("mock/free", "Mock Free", Some(0.0)),
+        ],
+        selected_index: 1,
+        list_state: ListState::default(),
+        fetch_error: None,
+        filter: String::new(),
+        fetch_started_at: None,
+        prefs: ModelPrefs {
+            favorites: vec!["mock/free".to_string()],
+            recent: Vec::new(),
+        },
+    });
+    assert_snapshot(
+        "model_selector",
+        &screen(100, 30, |f| draw(f, &mut app, f.area())),
+    );
+}
+
+#[test]
+fn error_block() {
+    let mut app = app();
+    app.messages = vec![
+        ChatMessage::User("Refactor the parser".to_string()),
+        ChatMessage::Error(ErrorNotice::new(&ChatError::RateLimited {
+            message: "Rate limit exceeded".to_string(),
+            retry_after: None,
+        })),
+    ];
+    assert_snapshot(
+        "error_block",
+        &screen(100, 30, |f| draw(f, &mut app, f.area())),
+    );
+}
+
+#[test]
+fn tab_bar() {
+    let mut app = app();
+    conversation(&mut app);
+    let label = |title: &str, active, busy, unseen| TabLabel {
+        title: title.to_string(),
+        active,
+        busy,
+        needs_confirmation: false,
+        unseen,
+    };
+    app.tab_labels = vec![
+        label("How is the project built?", true, false, false),
+        label("Fix the flaky test", false, true, false),
+        label("New conversation", false, false, true),
+    ];
+    assert_snapshot("tab_bar", &screen(100, 16, |f| draw(f, &mut app, f.area())));
+}
+
+#[test]
+fn narrow_terminal() {
+    let mut app = app();
+    conversation(&mut app);
+    assert_snapshot("narrow", &screen(40, 24, |f| draw(f, &mut app, f.area())));
+}
+
+#[test]
+fn context_preview_scroll_stops_at_the_last_page() {
+    let content = (1..=100).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+    let mut state = ContextPreviewState {
+        preview: ContextPreview {
+            sections: vec![(
+                PromptSection {
+                    id: None,
+                    title: "Instructions".to_string(),
+                    content: content.join("\n"),
+                },
+                400,
+            )],
+            empty_sections: Vec::new(),
+            disabled_sections: Vec::new(),
+            message_count: 1,
+            system_tokens: 400,
+            message_tokens: 0,
+            tool_count: 0,
+            tool_tokens: 0,
+            context_length: 0,
+        },
+        expanded: vec![true],
+        selected_index: 0,
+        scroll: u16::MAX,
+    };
+    let text = screen(80, 30, |f| {
+        context_preview_popup::draw_context_preview_popup(f, f.area(), &mut state)
+    });
+    assert!(text.contains("line 100"));
+    assert!(state.scroll > 0 && state.scroll < 110);
+}
@@ -0,0 +1,118 @@
+//! Tool queue widget: status and duration of each tool call in the current batch.
+
+use std::time::Duration;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use super::super::app::{ToolQueueEntry, ToolStatus};
+use super::super::constants::{ACCENT, LOGO_THINKING};
+
+/// Max visible rows (excluding borders); extra calls are summarized in the title.
+pub(crate) const TOOL_QUEUE_MAX_LINES: u16 = 6;
+
+/// Height of the widget for the given queue (0 when hidden).
+pub(crate) fn height(queue: &[ToolQueueEntry]) -> u16 {
+    if queue.is_empty() {
+        0
+    } else {
+        (queue.len() as u16).min(TOOL_QUEUE_MAX_LINES) + 2
+    }
+}
+
+pub(super) fn format_duration(d: Duration) -> String {
+    if d.as_secs() >= 60 {
+        format!("{}m{:02}s", d.as_secs() / 60, d.as_secs() % 60)
+    } else {
+        format!("{:.1}s", d.as_secs_f64())
+    }
+}
+
+fn status_span(entry: &ToolQueueEntry) -> Span<'static> {
+    match entry.status {
+        ToolStatus::Pending => Span::styled("○ ", Style::default().fg(Color::DarkGray)),
+        ToolStatus::Running => {
+            let phase = entry.elapsed().map(|d| d.as_millis() as usize).unwrap_or(0);
+            let frame = (phase / 80) % LOGO_THINKING.len();
+            Span::styled(
+                format!("{} ", LOGO_THINKING[frame]),
+                Style::default().fg(ACCENT),
+            )
+        }
+        ToolStatus::Done => Span::styled("✓ ", Style::default().fg(ACCENT)),
+        ToolStatus::Failed => Span::styled("✗ ", Style::default().fg(Color::Red)),
+    }
+}
+
+pub(crate) fn draw_tool_queue(f: &mut Frame, queue: &[ToolQueueEntry], area: Rect) {
+    let finished = queue
+        .iter()
+        .filter(|e| matches!(e.status, ToolStatus::Done | ToolStatus::Failed))
+        .count();
+    let running = queue
+        .iter()
+        .filter(|e| e.status == ToolStatus::Running)
+        .count();
+    let title = format!(
+        " Tools {}/{} done{} ",
+        finished,
+        queue.len(),
+        if running > 1 {
+            format!(", {} running", running)
+        } else {
+            String::new()
+        }
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(title);
+
+    // Keep running/pending calls visible when the batch exceeds the widget height.
+    let visible = TOOL_QUEUE_MAX_LINES as usize;
+    let first_active = queue
+        .iter()
+        .position(|e| matches!(e.status, ToolStatus::Pending | ToolStatus::Running))
+        .unwrap_or(queue.len());
+    let start = first_active.min(queue.len().saturating_sub(visible));
+
+    let lines: Vec<Line> = queue
+        .iter()
+        .skip(start)
+        .take(visible)
+        .map(|entry| {
+            let duration = entry
+                .elapsed()
+                .map(format_duration)
+                .unwrap_or_else(|| "-".to_string());
+            Line::from(vec![
+                status_span(entry),
+                Span::styled(
+                    format!("{:<8}", entry.name),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:>7}  ", duration),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(entry.preview.clone()),
+            ])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration_seconds_and_minutes() {
+        assert_eq!(format_duration(Duration::from_millis(1250)), "1.2s");
+        assert_eq!(format_duration(Duration::from_secs(75)), "1m15s");
+    }
+}
@@ -0,0 +1,330 @@
+//! Typed tool results in the history: a summary line, and when expanded the directory tree,
+//! grep matches with their context lines, table, or task plan checklist.
+
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use super::super::app::TextLine;
+use super::super::constants::{ACCENT, ACCENT_SECONDARY};
+use crate::core::text::{display_width, truncate_end};
+use crate::core::tools::{FileMatches, PlanStep, StepStatus, ToolView};
+
+/// Left margin of tool result lines, matching tool log lines.
+const PREFIX: &str = "  ┃ ";
+/// Indentation of the expanded content under the summary.
+const BODY_INDENT: &str = "  ";
+/// Most lines of an expanded result; the rest is counted in a last line.
+const MAX_BODY_LINES: usize = 200;
+/// Widest table column, in display columns.
+const MAX_COLUMN_WIDTH: usize = 24;
+
+/// Render a tool result: its summary line, then its content when `expanded`. Returns the
+/// index of the summary line (clicking it expands or collapses the result).
+pub(super) fn add_tool_result_lines(
+    lines: &mut Vec<Line<'static>>,
+    text_lines: &mut Vec<TextLine>,
+    tool: &str,
+    view: &ToolView,
+    expanded: bool,
+    content_width: usize,
+) -> usize {
+    let marker_style = Style::default().fg(ACCENT).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+    let header_line = lines.len();
+    let label = if tool.is_empty() {
+        view.summary()
+    } else {
+        format!("{} · {}", tool, view.summary())
+    };
+    lines.push(Line::from(vec![
+        Span::styled(PREFIX, marker_style),
+        Span::styled(if expanded { "▾ " } else { "▸ " }, marker_style),
+        Span::styled(label, dim),
+    ]));
+    if !expanded {
+        return header_line;
+    }
+
+    let width = content_width.saturating_sub(display_width(PREFIX) + BODY_INDENT.len());
+    let mut body = match view {
+        ToolView::Tree {
+            root,
+            entries,
+            omitted,
+        } => {
+            let mut body = vec![vec![Span::styled(
+                format!("{}/", root.trim_end_matches('/')),
+                Style::default().fg(ACCENT_SECONDARY),
+            )]];
+            body.extend(
+                tree_rows(entries)
+                    .into_iter()
+                    .map(|(branch, name, is_dir)| {
+                        let style = if is_dir {
+                            Style::default().fg(ACCENT_SECONDARY)
+                        } else {
+                            Style::default()
+                        };
+                        vec![Span::styled(branch, dim), Span::styled(name, style)]
+                    }),
+            );
+            if *omitted > 0 {
+                body.push(vec![Span::styled(format!("… {} more", omitted), dim)]);
+            }
+            body
+        }
+        ToolView::Matches { files, omitted } => {
+            let mut body = match_rows(files);
+            if *omitted > 0 {
+                body.push(vec![Span::styled(
+                    format!("… {} more matches", omitted),
+                    dim,
+                )]);
+            }
+            body
+        }
+        ToolView::Table {
+            columns,
+            rows,
+            total_rows,
+        } => {
+            let mut body = table_rows(columns, rows);
+            if *total_rows > rows.len() {
+                body.push(vec![Span::styled(
+                    format!("… {} more rows", total_rows - rows.len()),
+                    dim,
+                )]);
+            }
+            body
+        }
+        ToolView::Plan { steps } => plan_rows(steps),
+    };
+    if body.len() > MAX_BODY_LINES {
+        let hidden = body.len() - MAX_BODY_LINES;
+        body.truncate(MAX_BODY_LINES);
+        body.push(vec![Span::styled(
+            format!("… {} more lines (the model got the full result)", hidden),
+            dim,
+        )]);
+    }
+    for spans in body {
+        text_lines.push(TextLine {
+            line: lines.len(),
+            col: PREFIX.chars().count() + BODY_INDENT.len(),
+            continued: false,
+        });
+        let mut line = vec![Span::styled(PREFIX, marker_style), Span::raw(BODY_INDENT)];
+        line.extend(fit_spans(spans, width));
+        lines.push(Line::from(line));
+    }
+    header_line
+}
+
+/// Cut spans to `width` columns, ending with "…" when cut. Result lines are not wrapped.
+pub(super) fn fit_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Span<'static>> {
+    let mut left = width;
+    let mut out = Vec::with_capacity(spans.len());
+    for span in spans {
+        let w = display_width(&span.content);
+        if w <= left {
+            left -= w;
+            out.push(span);
+        } else {
+            out.push(Span::styled(truncate_end(&span.content, left), span.style));
+            break;
+        }
+    }
+    out
+}
+
+/// Node of the directory tree built from result paths.
+#[derive(Default)]
+struct TreeNode {
+    is_dir: bool,
+    children: BTreeMap<String, TreeNode>,
+}
+
+/// Tree rows for `entries` (relative paths, directories ending with `/`): the branch
+/// drawing, the name, and whether it is a directory. Directories come first, then files,
+/// each sorted by name.
+fn tree_rows(entries: &[String]) -> Vec<(String, String, bool)> {
+    let mut root = TreeNode::default();
+    for entry in entries {
+        let is_dir = entry.ends_with('/');
+        let parts: Vec<&str> = entry
+            .trim_end_matches('/')
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .collect();
+        let mut node = &mut root;
+        for (i, part) in parts.iter().enumerate() {
+            node = node.children.entry(part.to_string()).or_default();
+            // Components above the last are directories.
+            node.is_dir |= is_dir || i + 1 < parts.len();
+        }
+    }
+    let mut rows = Vec::new();
+    push_tree_rows(&root, "", &mut rows);
+    rows
+}
+
+fn push_tree_rows(node: &TreeNode, indent: &str, rows: &mut Vec<(String, String, bool)>) {
+    let mut children: Vec<(&String, &TreeNode)> = node.children.iter().collect();
+    children.sort_by_key(|(_, child)| !child.is_dir);
+    let count = children.len();
+    for (i, (name, child)) in children.into_iter().enumerate() {
+        let last = i + 1 == count;
+        let branch = format!("{}{}", indent, if last { "└── " } else { "├── " });
+        let name = if child.is_dir {
+            format!("{}/", name)
+        } else {
+            name.clone()
+        };
+        rows.push((branch, name, child.is_dir));
+        let indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+        push_tree_rows(child, &indent, rows);
+    }
+}
+
+/// Grep rows: each file's path, then its lines with their numbers. Matching lines stand
+/// out from context lines; "⋮" marks skipped lines.
+fn match_rows(files: &[FileMatches]) -> Vec<Vec<Span<'static>>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let number_width = files
+        .iter()
+        .flat_map(|f| &f.lines)
+        .map(|l| l.number.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let mut rows = Vec::new();
+    for file in files {
+        rows.push(vec![Span::styled(
+            file.path.clone(),
+            Style::default().fg(ACCENT_SECONDARY),
+        )]);
+        let mut previous: Option<usize> = None;
+        for line in &file.lines {
+            if previous.is_some_and(|p| line.number > p + 1) {
+                rows.push(vec![Span::styled(
+                    format!("{:>w$} ⋮", "", w = number_width),
+                    dim,
+                )]);
+            }
+            previous = Some(line.number);
+            let (number_style, text_style) = if line.is_match {
+                (Style::default().fg(Color::Yellow), Style::default())
+            } else {
+                (dim, dim)
+            };
+            rows.push(vec![
+                Span::styled(
+                    format!("{:>w$} │ ", line.number, w = number_width),
+                    number_style,
+                ),
+                Span::styled(line.text.replace('\t', "    "), text_style),
+            ]);
+        }
+    }
+    rows
+}
+
+/// Checklist rows of a task plan: completed steps dimmed, the step in progress highlighted.
+pub(super) fn plan_rows(steps: &[PlanStep]) -> Vec<Vec<Span<'static>>> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let style = match step.status {
+                StepStatus::Pending => Style::default(),
+                StepStatus::InProgress => Style::default().fg(Color::Yellow),
+                StepStatus::Completed => Style::default().fg(Color::DarkGray),
+            };
+            vec![
+                Span::styled(format!("{} ", step.status.marker()), style),
+                Span::styled(format!("{}. {}", i + 1, step.step.trim()), style),
+            ]
+        })
+        .collect()
+}
+
+/// Table rows: the column names, a rule, then the cells padded to their column width.
+fn table_rows(columns: &[String], rows: &[Vec<String>]) -> Vec<Vec<Span<'static>>> {
+    let cell = |s: &str| truncate_end(&s.replace(['\n', '\r', '\t'], " "), MAX_COLUMN_WIDTH);
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .chain(std::iter::once(name))
+                .map(|s| display_width(&cell(s)))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let row_spans = |cells: Vec<String>, style: Style| -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        for (i, (text, width)) in cells.iter().zip(&widths).enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+            }
+            let pad = width.saturating_sub(display_width(text));
+            spans.push(Span::styled(format!("{}{}", text, " ".repeat(pad)), style));
+        }
+        spans
+    };
+    let mut out = vec![row_spans(
+        columns.iter().map(|c| cell(c)).collect(),
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    out.push(vec![Span::styled(
+        widths
+            .iter()
+            .map(|w| "─".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("─┼─"),
+        Style::default().fg(Color::DarkGray),
+    )]);
+    for row in rows {
+        let cells = (0..columns.len())
+            .map(|i| cell(row.get(i).map_or("", String::as_str)))
+            .collect();
+        out.push(row_spans(cells, Style::default()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_rows_nest_paths_with_directories_first() {
+        let entries: Vec<String> = [
+            "src/",
+            "src/tui/",
+            "src/main.rs",
+            "src/tui/mod.rs",
+            "Cargo.toml",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let rows: Vec<String> = tree_rows(&entries)
+            .into_iter()
+            .map(|(branch, name, _)| format!("{}{}", branch, name))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                "├── src/",
+                "│   ├── tui/",
+                "│   │   └── mod.rs",
+                "│   └── main.rs",
+                "└── Cargo.toml",
+            ]
+        );
+    }
+}
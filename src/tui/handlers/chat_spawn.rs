@@ -1,5 +1,6 @@
-//! Spawns chat requests in a background thread with progress/stream/result channels.
+//! Spawns chat requests in a background thread with progress/stream/tool event/result channels.
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::mpsc;
 use tokio::runtime::Runtime;
@@ -8,15 +9,23 @@ use tokio_util::sync::CancellationToken;
 use serde_json::Value;
 
 use crate::core::config::Config;
+use crate::core::generation::GenerationParams;
 use crate::core::llm;
+use crate::core::pins::PinnedMessage;
 use crate::core::workspace::Workspace;
 
+use super::super::app::ToolFileReader;
 use super::PendingChat;
 
 /// Spawn an LLM task with progress/stream/result channels. The closure receives the runtime,
 /// options, and result sender, and must run block_on and send the result itself (so borrowed
 /// data stays valid for the duration).
-fn spawn_with_callbacks<F>(rt: &Arc<Runtime>, run_task: F) -> PendingChat
+fn spawn_with_callbacks<F>(
+    rt: &Arc<Runtime>,
+    conversation_id: Option<String>,
+    modes: llm::ToolModes,
+    run_task: F,
+) -> PendingChat
 where
     F: FnOnce(
             Arc<Runtime>,
@@ -27,20 +36,27 @@ where
 {
     let (progress_tx, progress_rx) = mpsc::channel();
     let (stream_tx, stream_rx) = mpsc::channel();
+    let (tool_event_tx, tool_event_rx) = mpsc::channel();
     let (result_tx, result_rx) = mpsc::channel();
     let cancel_token = CancellationToken::new();
     let cancel_token_clone = cancel_token.clone();
 
     let rt_clone = Arc::clone(rt);
+    let file_reader = ToolFileReader::default();
 
     let options = llm::ChatOptions {
-        on_progress: Some(Box::new(move |s| {
-            let _ = progress_tx.send(s.to_string());
+        on_progress: Some(Box::new(move |e| {
+            let _ = progress_tx.send(e.clone());
         })),
         on_content_chunk: Some(Box::new(move |s| {
             let _ = stream_tx.send(s.to_string());
         })),
+        on_tool_event: Some(Box::new(move |e| {
+            let _ = tool_event_tx.send(file_reader.update(e));
+        })),
         cancel_token: Some(cancel_token_clone),
+        conversation_id,
+        modes: Some(modes),
     };
 
     std::thread::spawn(move || {
@@ -50,6 +66,7 @@ where
     PendingChat {
         progress_rx,
         stream_rx,
+        tool_event_rx,
         result_rx,
         cancel_token,
     }
@@ -63,29 +80,44 @@ pub fn spawn_chat(
     workspace: Workspace,
     model_id: String,
     prompt: String,
+    images: Vec<String>,
     mode: String,
     prev_messages: Option<Vec<Value>>,
     undo_stack: Option<llm::undo::SharedUndoStack>,
+    pinned_files: Vec<PathBuf>,
+    pinned_messages: Vec<PinnedMessage>,
+    generation: GenerationParams,
+    conversation_id: Option<String>,
+    modes: llm::ToolModes,
 ) -> PendingChat {
     let context_length = crate::core::models::resolve_context_length(&model_id);
 
-    spawn_with_callbacks(rt, move |rt_clone, options, result_tx| {
-        let result = rt_clone.block_on(llm::chat(llm::ChatRequest {
-            config: config.as_ref(),
-            model: &model_id,
-            prompt: &prompt,
-            mode: &mode,
-            context_length,
-            confirm_destructive: None,
-            previous_messages: prev_messages,
-            options,
-            workspace: &workspace,
-            tools_list: crate::core::tools::all(),
-            tools_defs: crate::core::tools::definitions(),
-            undo_stack,
-        }));
-        let _ = result_tx.send(result);
-    })
+    spawn_with_callbacks(
+        rt,
+        conversation_id,
+        modes,
+        move |rt_clone, options, result_tx| {
+            let result = rt_clone.block_on(llm::chat(llm::ChatRequest {
+                config: config.as_ref(),
+                model: &model_id,
+                prompt: &prompt,
+                images: &images,
+                mode: &mode,
+                context_length,
+                confirm_destructive: None,
+                previous_messages: prev_messages,
+                options,
+                workspace: &workspace,
+                tools_list: crate::core::tools::all(),
+                tools_defs: crate::core::tools::definitions(),
+                undo_stack,
+                pinned_files: &pinned_files,
+                pinned_messages: &pinned_messages,
+                generation: &generation,
+            }));
+            let _ = result_tx.send(result);
+        },
+    )
 }
 
 /// Spawn chat_resume after user confirmed or cancelled a destructive command.
@@ -95,19 +127,26 @@ pub fn spawn_chat_resume(
     model_id: String,
     state: llm::ConfirmState,
     confirmed: bool,
+    conversation_id: Option<String>,
+    modes: llm::ToolModes,
 ) -> PendingChat {
     let context_length = crate::core::models::resolve_context_length(&model_id);
 
-    spawn_with_callbacks(rt, move |rt_clone, options, result_tx| {
-        let result = rt_clone.block_on(llm::chat_resume(
-            config.as_ref(),
-            &model_id,
-            context_length,
-            state,
-            confirmed,
-            crate::core::tools::all(),
-            options,
-        ));
-        let _ = result_tx.send(result);
-    })
+    spawn_with_callbacks(
+        rt,
+        conversation_id,
+        modes,
+        move |rt_clone, options, result_tx| {
+            let result = rt_clone.block_on(llm::chat_resume(
+                config.as_ref(),
+                &model_id,
+                context_length,
+                state,
+                confirmed,
+                crate::core::tools::all(),
+                options,
+            ));
+            let _ = result_tx.send(result);
+        },
+    )
 }
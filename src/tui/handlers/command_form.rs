@@ -5,6 +5,7 @@ use crossterm::event::{KeyCode, KeyModifiers};
 use crate::core::commands::is_builtin_name;
 use crate::core::templates::{self, CustomTemplate};
 
+use super::super::constants::SUGGESTIONS;
 use super::HandleResult;
 use crate::tui::app::{CommandFormField, CommandFormMode, CommandFormPhase, CommandFormState};
 
@@ -53,18 +54,30 @@ fn validate_form(state: &CommandFormState, custom_templates: &[CustomTemplate])
     if state.prompt_prefix.trim().is_empty() {
         return Some("Prompt cannot be empty".to_string());
     }
-    if state.llm_mode != "Ask" && state.llm_mode != "Build" {
-        return Some("Mode must be Ask or Build".to_string());
+    if !SUGGESTIONS.contains(&state.llm_mode.as_str()) {
+        return Some("Mode must be Ask, Build, or Plan".to_string());
+    }
+    if state.model.trim().chars().any(char::is_whitespace) {
+        return Some("Model must be a model ID without spaces".to_string());
     }
     None
 }
 
+/// Cycle to the next mode (Ask → Build → Plan → Ask).
+fn next_mode(mode: &str) -> String {
+    let idx = SUGGESTIONS.iter().position(|m| *m == mode).unwrap_or(0);
+    SUGGESTIONS[(idx + 1) % SUGGESTIONS.len()].to_string()
+}
+
 fn save_command(app: &mut crate::tui::app::App, state: CommandFormState) {
-    let template = templates::CustomTemplate {
+    let mut template = templates::CustomTemplate {
         name: state.name.trim().to_string(),
         description: state.description.trim().to_string(),
         prompt_prefix: state.prompt_prefix.trim().to_string(),
         mode: state.llm_mode.clone(),
+        model: Some(state.model.trim().to_string()).filter(|m| !m.is_empty()),
+        // Not editable in the form: set in templates.json.
+        output_schema: None,
     };
 
     match &state.form_mode {
@@ -79,6 +92,7 @@ fn save_command(app: &mut crate::tui::app::App, state: CommandFormState) {
                 .iter()
                 .position(|t| t.name.to_lowercase() == orig.to_lowercase())
             {
+                template.output_schema = app.custom_templates[idx].output_schema.take();
                 app.custom_templates[idx] = template;
             }
         }
@@ -133,6 +147,7 @@ pub(super) fn handle_command_form_popup(
                     state.description = template.description;
                     state.prompt_prefix = template.prompt_prefix;
                     state.llm_mode = template.mode;
+                    state.model = template.model.unwrap_or_default();
                     state.focused_field = CommandFormField::Name;
                     state.error = None;
                     if let CommandFormMode::Update { original_name } = &mut state.form_mode {
@@ -149,6 +164,7 @@ pub(super) fn handle_command_form_popup(
                     state.name.clear();
                     state.description.clear();
                     state.prompt_prefix.clear();
+                    state.model.clear();
                 } else {
                     app.command_form_popup = None;
                 }
@@ -158,16 +174,18 @@ pub(super) fn handle_command_form_popup(
                     CommandFormField::Name => CommandFormField::Description,
                     CommandFormField::Description => CommandFormField::Prompt,
                     CommandFormField::Prompt => CommandFormField::Mode,
-                    CommandFormField::Mode => CommandFormField::Name,
+                    CommandFormField::Mode => CommandFormField::Model,
+                    CommandFormField::Model => CommandFormField::Name,
                 };
                 state.error = None;
             }
             KeyCode::BackTab => {
                 state.focused_field = match state.focused_field {
-                    CommandFormField::Name => CommandFormField::Mode,
+                    CommandFormField::Name => CommandFormField::Model,
                     CommandFormField::Description => CommandFormField::Name,
                     CommandFormField::Prompt => CommandFormField::Description,
                     CommandFormField::Mode => CommandFormField::Prompt,
+                    CommandFormField::Model => CommandFormField::Mode,
                 };
                 state.error = None;
             }
@@ -180,11 +198,7 @@ pub(super) fn handle_command_form_popup(
             }
             KeyCode::Enter => {
                 if state.focused_field == CommandFormField::Mode {
-                    state.llm_mode = if state.llm_mode == "Ask" {
-                        "Build".to_string()
-                    } else {
-                        "Ask".to_string()
-                    };
+                    state.llm_mode = next_mode(&state.llm_mode);
                     return HandleResult::Continue;
                 }
                 let custom = app.custom_templates.clone();
@@ -233,17 +247,14 @@ pub(super) fn handle_command_form_popup(
                             state.prompt_prefix.truncate(idx);
                         }
                     }
+                    CommandFormField::Model => {
+                        state.model.pop();
+                    }
                     CommandFormField::Mode => {}
                 }
             }
-            KeyCode::Up | KeyCode::Down => {
-                if state.focused_field == CommandFormField::Mode {
-                    state.llm_mode = if state.llm_mode == "Ask" {
-                        "Build".to_string()
-                    } else {
-                        "Ask".to_string()
-                    };
-                }
+            KeyCode::Up | KeyCode::Down if state.focused_field == CommandFormField::Mode => {
+                state.llm_mode = next_mode(&state.llm_mode);
             }
             KeyCode::Char(c) => {
                 if key_modifiers.contains(KeyModifiers::ALT) {
@@ -254,13 +265,10 @@ pub(super) fn handle_command_form_popup(
                     CommandFormField::Name => state.name.push(c),
                     CommandFormField::Description => state.description.push(c),
                     CommandFormField::Prompt => state.prompt_prefix.push(c),
+                    CommandFormField::Model => state.model.push(c),
                     CommandFormField::Mode => {
                         if c == ' ' || c == '\t' {
-                            state.llm_mode = if state.llm_mode == "Ask" {
-                                "Build".to_string()
-                            } else {
-                                "Ask".to_string()
-                            };
+                            state.llm_mode = next_mode(&state.llm_mode);
                         }
                     }
                 }
@@ -0,0 +1,79 @@
+//! Handler for the command palette popup (Ctrl+K).
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use super::HandleResult;
+use super::input;
+use super::shortcuts::{ShortcutContext, handle_shortcut};
+use crate::tui::app::PaletteAction;
+
+pub(super) fn handle_command_palette(
+    key_code: KeyCode,
+    key_modifiers: KeyModifiers,
+    ctx: ShortcutContext<'_>,
+) -> HandleResult {
+    let Some(state) = ctx.app.command_palette.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc => ctx.app.command_palette = None,
+        KeyCode::Down => state.select_next(),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Backspace => state.pop_filter(),
+        KeyCode::Char(c) if !key_modifiers.contains(KeyModifiers::CONTROL) => state.push_filter(c),
+        // Slash commands: put them in the input to type their arguments.
+        KeyCode::Tab => {
+            if let Some(PaletteAction::Command(name)) =
+                state.selected_entry().map(|e| e.action.clone())
+            {
+                ctx.app.command_palette = None;
+                ctx.app.input = format!("/{} ", name);
+                ctx.app.input_cursor = ctx.app.input.len();
+                ctx.app.selected_command_index = 0;
+            }
+        }
+        KeyCode::Enter => {
+            let Some(action) = state.selected_entry().map(|e| e.action.clone()) else {
+                return HandleResult::Continue;
+            };
+            ctx.app.command_palette = None;
+            return run_action(action, ctx);
+        }
+        _ => {}
+    }
+    HandleResult::Continue
+}
+
+/// Run the chosen entry the way its key or slash command would.
+fn run_action(action: PaletteAction, ctx: ShortcutContext<'_>) -> HandleResult {
+    match action {
+        PaletteAction::Shortcut(shortcut) => return handle_shortcut(shortcut, ctx),
+        PaletteAction::Search => ctx.app.open_search(),
+        PaletteAction::Command(_) if ctx.pending_chat.is_some() => {
+            ctx.app
+                .push_tool_log("Slash commands run once the reply is done.".to_string());
+        }
+        PaletteAction::Command(name) => {
+            let Some(cmd) = ctx
+                .app
+                .resolved_commands
+                .iter()
+                .find(|cmd| cmd.name == name)
+                .cloned()
+            else {
+                return HandleResult::Continue;
+            };
+            input::run_slash_command(
+                &cmd,
+                String::new(),
+                ctx.app,
+                ctx.config,
+                ctx.pending_chat,
+                ctx.api_messages,
+                ctx.rt,
+            );
+        }
+    }
+    HandleResult::Continue
+}
@@ -0,0 +1,80 @@
+//! Handler for the commit popup (/commit): edit the drafted message and run `git commit`.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::core::git_commit;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_commit_popup(
+    key_code: KeyCode,
+    modifiers: KeyModifiers,
+    app: &mut App,
+) -> HandleResult {
+    let Some(state) = app.commit_popup.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc => {
+            if state.confirming {
+                state.confirming = false;
+            } else {
+                app.commit_popup = None;
+            }
+        }
+        KeyCode::Enter
+            if modifiers.contains(KeyModifiers::SHIFT) || modifiers.contains(KeyModifiers::ALT) =>
+        {
+            state.message.push('\n');
+            state.confirming = false;
+        }
+        KeyCode::Enter => {
+            if state.message.trim().is_empty() {
+                state.error = Some("Commit message cannot be empty".to_string());
+                return HandleResult::Continue;
+            }
+            let command = git_commit::shell_command(&state.changes);
+            if !state.confirming && git_commit::needs_confirmation(&command) {
+                state.confirming = true;
+                return HandleResult::Continue;
+            }
+            match git_commit::commit(&state.changes, &state.message) {
+                Ok(hash) => {
+                    let branch = state
+                        .changes
+                        .branch
+                        .clone()
+                        .unwrap_or_else(|| "detached HEAD".to_string());
+                    let subject = state
+                        .message
+                        .trim()
+                        .lines()
+                        .next()
+                        .unwrap_or("")
+                        .to_string();
+                    app.commit_popup = None;
+                    app.push_tool_log(format!("Committed {} on {}: {}", hash, branch, subject));
+                }
+                Err(e) => {
+                    state.error = Some(e.to_string());
+                    state.confirming = false;
+                }
+            }
+        }
+        KeyCode::Backspace => {
+            state.message.pop();
+            state.error = None;
+            state.confirming = false;
+        }
+        KeyCode::Char(c) if !modifiers.contains(KeyModifiers::ALT) => {
+            state.message.push(c);
+            state.error = None;
+            state.confirming = false;
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
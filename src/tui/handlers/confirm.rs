@@ -1,4 +1,5 @@
-//! Handler for confirm popup (y/n for destructive command).
+//! Handler for confirm popup (y/n for a destructive command or a write outside the workspace,
+//! a to always allow the command).
 
 use crossterm::event::KeyCode;
 use std::sync::Arc;
@@ -13,8 +14,8 @@ use super::chat_spawn;
 
 /// Result of handling a key in the confirm popup.
 pub(crate) enum ConfirmPopupResult {
-    /// Put the popup back (user pressed something other than y/n/enter, or pending_chat already set).
-    PutBack(ConfirmPopup),
+    /// Put the popup back (user pressed something other than y/n/a/enter, or pending_chat already set).
+    PutBack(Box<ConfirmPopup>),
     /// Spawned chat resume; caller should set pending_chat.
     Spawned(PendingChat),
 }
@@ -22,36 +23,54 @@ pub(crate) enum ConfirmPopupResult {
 /// Handle key when confirm popup is showing.
 pub(crate) fn handle_confirm_popup(
     key_code: KeyCode,
-    popup: ConfirmPopup,
+    mut popup: ConfirmPopup,
     app: &mut App,
     config: &Arc<Config>,
     pending_chat_is_none: bool,
     rt: &Arc<Runtime>,
 ) -> ConfirmPopupResult {
-    let confirmed = matches!(key_code, KeyCode::Char('y') | KeyCode::Char('Y'));
+    let always = matches!(key_code, KeyCode::Char('a') | KeyCode::Char('A'))
+        && popup.action.allowable_command().is_some();
+    let confirmed = always || matches!(key_code, KeyCode::Char('y') | KeyCode::Char('Y'));
     let cancelled = matches!(
         key_code,
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Enter
     );
 
+    // Write/Edit approvals show the change: arrows and page keys scroll it.
+    if let Some(diff) = popup.diff.as_mut()
+        && super::diff_viewer::scroll(diff, key_code)
+    {
+        return ConfirmPopupResult::PutBack(Box::new(popup));
+    }
+
     if confirmed || cancelled {
         if pending_chat_is_none {
+            if always {
+                popup.action.allow_always();
+                app.push_tool_log(format!(
+                    "Always allowing `{}` for this session (/permissions to save it).",
+                    popup.action
+                ));
+            }
             app.push_assistant(String::new());
             app.scroll = ScrollPosition::Bottom;
-            let model_id = app.current_model_id.clone();
+            let model_id = app.turn_model_id();
             let pc = chat_spawn::spawn_chat_resume(
                 rt,
                 Arc::clone(config),
                 model_id,
                 popup.state,
                 confirmed,
+                app.conversation_id().map(str::to_string),
+                app.tool_modes,
             );
             ConfirmPopupResult::Spawned(pc)
         } else {
             // Can't process yet; put popup back
-            ConfirmPopupResult::PutBack(popup)
+            ConfirmPopupResult::PutBack(Box::new(popup))
         }
     } else {
-        ConfirmPopupResult::PutBack(popup)
+        ConfirmPopupResult::PutBack(Box::new(popup))
     }
 }
@@ -0,0 +1,37 @@
+//! Handler for the context preview popup (/preview).
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_context_preview_popup(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let Some(state) = app.context_preview_popup.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    let len = state.expanded.len();
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.context_preview_popup = None;
+        }
+        KeyCode::Up => {
+            state.selected_index = state.selected_index.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            state.selected_index = (state.selected_index + 1).min(len.saturating_sub(1));
+        }
+        KeyCode::Enter | KeyCode::Char(' ') if state.selected_index < len => {
+            state.expanded[state.selected_index] = !state.expanded[state.selected_index];
+        }
+        KeyCode::PageUp => {
+            state.scroll = state.scroll.saturating_sub(10);
+        }
+        KeyCode::PageDown => {
+            state.scroll = state.scroll.saturating_add(10);
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
@@ -0,0 +1,27 @@
+//! Handler for the code block copy popup (/copy).
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_copy_mode(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let Some(state) = app.copy_mode.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.copy_mode = None;
+        }
+        KeyCode::Down => state.select_next(),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Char(' ') => state.toggle_selected(),
+        KeyCode::Char('a') => state.toggle_all(),
+        KeyCode::Enter => app.copy_snippets_concatenated(),
+        KeyCode::Char('s') => app.copy_snippets_separately(),
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
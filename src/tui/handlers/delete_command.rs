@@ -35,10 +35,8 @@ pub(super) fn handle_delete_command_popup(
         KeyCode::Down => {
             state.selected_index = (state.selected_index + 1).min(len.saturating_sub(1));
         }
-        KeyCode::Char(' ') => {
-            if state.selected_index < state.selected.len() {
-                state.selected[state.selected_index] = !state.selected[state.selected_index];
-            }
+        KeyCode::Char(' ') if state.selected_index < state.selected.len() => {
+            state.selected[state.selected_index] = !state.selected[state.selected_index];
         }
         KeyCode::Enter => {
             let any_selected = state.selected.iter().any(|&b| b);
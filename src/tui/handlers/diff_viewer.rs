@@ -0,0 +1,38 @@
+//! Handler for the diff viewer (/review results): file navigation and scrolling.
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::{App, DiffViewerState};
+use crate::tui::constants::SCROLL_LINES_PAGE;
+
+pub(super) fn handle_diff_viewer(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let Some(state) = app.diff_viewer.as_mut() else {
+        return HandleResult::Continue;
+    };
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => app.diff_viewer = None,
+        KeyCode::Char('n') | KeyCode::Right | KeyCode::Tab => state.next_file(),
+        KeyCode::Char('p') | KeyCode::Left | KeyCode::BackTab => state.prev_file(),
+        _ => {
+            scroll(state, key_code);
+        }
+    }
+    HandleResult::Continue
+}
+
+/// Scroll the current file for Up/Down/PageUp/PageDown/Home/End. Returns false for other keys.
+/// Drawing clamps the offset to the end of the file.
+pub(super) fn scroll(state: &mut DiffViewerState, key_code: KeyCode) -> bool {
+    let page = SCROLL_LINES_PAGE as u16;
+    state.scroll = match key_code {
+        KeyCode::Up => state.scroll.saturating_sub(1),
+        KeyCode::Down => state.scroll.saturating_add(1),
+        KeyCode::PageUp => state.scroll.saturating_sub(page),
+        KeyCode::PageDown => state.scroll.saturating_add(page),
+        KeyCode::Home => 0,
+        KeyCode::End => u16::MAX,
+        _ => return false,
+    };
+    true
+}
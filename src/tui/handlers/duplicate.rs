@@ -0,0 +1,36 @@
+//! Handler for the duplicate conversation popup: continue the existing conversation or keep a new one.
+
+use crossterm::event::KeyCode;
+use serde_json::Value;
+
+use crate::core::config::Config;
+
+use super::super::chat_result;
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_duplicate_popup(
+    key_code: KeyCode,
+    app: &mut App,
+    api_messages: &mut Option<Vec<Value>>,
+    config: &Config,
+) -> HandleResult {
+    let Some(state) = app.duplicate_popup.as_ref() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+            let existing = state.existing.clone();
+            app.duplicate_popup = None;
+            chat_result::continue_existing_conversation(app, api_messages, &existing, config);
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.duplicate_popup = None;
+            chat_result::save_conversation_if_dirty(app, api_messages, config);
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
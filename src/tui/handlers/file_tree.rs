@@ -0,0 +1,44 @@
+//! Handler for the workspace file tree popup (Alt+F).
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_file_tree(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let root = app.workspace.root.clone();
+    let Some(state) = app.file_tree.as_mut() else {
+        return HandleResult::Continue;
+    };
+    let on_dir = state.selected_entry().is_some_and(|e| e.is_dir);
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.file_tree = None;
+            return HandleResult::Continue;
+        }
+        KeyCode::Down => state.select_next(),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Right => state.expand_selected(),
+        KeyCode::Left => state.collapse_or_select_parent(),
+        KeyCode::Enter if on_dir => state.toggle_selected(),
+        KeyCode::Enter => {
+            app.file_tree_insert_path();
+            return HandleResult::Continue;
+        }
+        KeyCode::Char('a') => {
+            app.file_tree_attach();
+            return HandleResult::Continue;
+        }
+        KeyCode::Char('e') => {
+            app.file_tree_edit();
+            return HandleResult::Continue;
+        }
+        _ => {}
+    }
+
+    if let Some(state) = app.file_tree.as_mut() {
+        state.update_preview(&root);
+    }
+    HandleResult::Continue
+}
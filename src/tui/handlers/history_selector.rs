@@ -21,6 +21,11 @@ pub(crate) enum HistorySelectorAction {
         id: String,
         new_title: String,
     },
+    /// Archive the conversation (kept when the history is pruned), or unarchive it.
+    Archive {
+        id: String,
+        archived: bool,
+    },
     /// No action; keep the selector open.
     Keep,
 }
@@ -95,6 +100,15 @@ pub(crate) fn handle_history_selector_key(
                 HistorySelectorAction::Keep
             }
         }
+        KeyCode::Char('a') if key_modifiers.contains(KeyModifiers::CONTROL) => {
+            match filtered.get(selector.selected_index) {
+                Some(meta) => HistorySelectorAction::Archive {
+                    id: meta.id.clone(),
+                    archived: !meta.archived,
+                },
+                None => HistorySelectorAction::Keep,
+            }
+        }
         KeyCode::Up => {
             selector.selected_index = selector.selected_index.saturating_sub(1);
             HistorySelectorAction::Keep
@@ -127,7 +141,7 @@ pub(crate) fn handle_history_selector_key(
 }
 
 /// Open the history selector. Caller must save current conversation first if dirty.
-/// Preloads conversation content for full-text search.
+/// Preloads conversation content for full-text search, and origins for the detail pane.
 pub(crate) fn open_history_selector() -> HistorySelectorState {
     let (conversations, error) = match crate::core::history::list_conversations() {
         Ok(c) => (c, None),
@@ -137,6 +151,14 @@ pub(crate) fn open_history_selector() -> HistorySelectorState {
         .iter()
         .filter_map(|c| load_conversation_searchable_content(&c.id).map(|s| (c.id.clone(), s)))
         .collect();
+    let origins = conversations
+        .iter()
+        .filter_map(|c| {
+            crate::core::history::load_conversation_settings(&c.id)
+                .origin
+                .map(|o| (c.id.clone(), o))
+        })
+        .collect();
     HistorySelectorState {
         conversations,
         selected_index: 0,
@@ -145,5 +167,6 @@ pub(crate) fn open_history_selector() -> HistorySelectorState {
         renaming: None,
         error,
         content_cache,
+        origins,
     }
 }
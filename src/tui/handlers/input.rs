@@ -6,30 +6,311 @@ use std::sync::Arc;
 use serde_json::Value;
 use tokio::runtime::Runtime;
 
-use crate::core::commands::{self, ResolvedCommand};
+use crate::core::commands::ResolvedCommand;
 use crate::core::config::Config;
+use crate::core::llm;
+use crate::core::models;
+use crate::core::spend::{self, SpendStatus};
+use crate::core::stats;
 use crate::core::templates;
 
-use super::super::app::{App, ScrollPosition};
+use super::super::app::{App, CommandModel, QueuedPrompt, ScrollPosition, TurnFollowUp};
 use super::super::constants::{self, SUGGESTIONS};
+use super::super::paste::{self, AttachmentKind};
 use super::PendingChat;
 use super::chat_spawn;
 
-/// Filter query from input: everything after the leading "/".
-fn slash_filter(app: &App) -> &str {
-    if app.input.starts_with('/') {
-        app.input.get(1..).unwrap_or("")
-    } else {
-        ""
+/// Show `prompt` as a new user message and start a chat turn with the current model
+/// (or the model pinned by the turn's slash command). `images` are data URLs sent with it.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn send_prompt(
+    app: &mut App,
+    config: &Arc<Config>,
+    pending_chat: &mut Option<PendingChat>,
+    api_messages: &Option<Vec<Value>>,
+    rt: &Arc<Runtime>,
+    prompt: String,
+    images: Vec<String>,
+    mode: String,
+) {
+    app.unzoom();
+    app.mark_dirty();
+    app.push_user(&prompt);
+    app.push_assistant(String::new());
+    app.scroll = ScrollPosition::Bottom;
+
+    let model_id = app.turn_model_id();
+    let mut generation = app.generation_params(config, &model_id);
+    generation.response_schema = app.turn_output_schema.clone();
+    let pc = chat_spawn::spawn_chat(
+        rt,
+        Arc::clone(config),
+        app.workspace.clone(),
+        model_id,
+        prompt,
+        images,
+        mode,
+        api_messages.clone(),
+        Some(app.undo_stack.clone()),
+        app.pinned_paths(),
+        app.pinned_message_list(),
+        generation,
+        app.conversation_id().map(str::to_string),
+        app.tool_modes,
+    );
+    app.is_streaming = true;
+    *pending_chat = Some(pc);
+}
+
+/// Check the spend caps before a new request. Returns false when a cap is reached and not
+/// overridden, and opens the popup asking whether to continue past it. The 80% warning is
+/// shown once, until spend is back under it.
+pub(super) fn spend_allows_request(app: &mut App, config: &Config) -> bool {
+    let status = spend::check(&config.spend_limits);
+    app.spend_status = status;
+    match status {
+        SpendStatus::Exceeded(hit) if !spend::caps_overridden() => {
+            app.spend_popup = Some(hit);
+            false
+        }
+        SpendStatus::Warning(hit) if !app.spend_warned => {
+            app.spend_warned = true;
+            app.push_tool_log(format!("Warning: {}.", hit));
+            true
+        }
+        SpendStatus::Ok => {
+            app.spend_warned = false;
+            true
+        }
+        SpendStatus::Warning(_) | SpendStatus::Exceeded(_) => true,
     }
 }
 
-/// Get filtered commands for current input. Returns empty when not in slash mode.
-fn filtered_commands(app: &App) -> Vec<&ResolvedCommand> {
-    if app.input.starts_with('/') {
-        commands::filter_commands_resolved(&app.resolved_commands, slash_filter(app))
-    } else {
-        vec![]
+/// Start the next queued prompt once no request is in flight. Popups (confirmation,
+/// commit, selectors) hold the queue until they close.
+pub(crate) fn start_queued_prompt(
+    app: &mut App,
+    config: &Arc<Config>,
+    pending_chat: &mut Option<PendingChat>,
+    api_messages: &Option<Vec<Value>>,
+    rt: &Arc<Runtime>,
+) {
+    if pending_chat.is_some() || app.popup_open() || app.prompt_queue.is_empty() {
+        return;
+    }
+    if app.queue_hold_remaining().is_none() && !spend_allows_request(app, config) {
+        // Blocked prompts go back to the input (when free) instead of being lost.
+        let prompts: Vec<String> = app.prompt_queue.drain(..).map(|q| q.prompt).collect();
+        if app.input.is_empty() {
+            app.input = prompts.join("\n\n");
+            app.input_cursor = app.input.len();
+        }
+        return;
+    }
+    let Some(queued) = app.next_queued_prompt() else {
+        return;
+    };
+    app.turn_follow_up = queued.follow_up;
+    app.turn_model = queued.turn_model;
+    app.turn_output_schema = queued.output_schema;
+    send_prompt(
+        app,
+        config,
+        pending_chat,
+        api_messages,
+        rt,
+        queued.prompt,
+        queued.images,
+        queued.mode,
+    );
+}
+
+/// Read the system clipboard into the input: an image becomes an attachment chip,
+/// text goes through the same path as a bracketed paste.
+fn paste_from_clipboard(app: &mut App) {
+    let mut clipboard = match arboard::Clipboard::new() {
+        Ok(c) => c,
+        Err(e) => {
+            log::warn!("Clipboard unavailable: {}", e);
+            return;
+        }
+    };
+    if let Ok(image) = clipboard.get_image() {
+        match paste::png_data_url(image.width, image.height, &image.bytes) {
+            Ok(data_url) => app.insert_attachment(AttachmentKind::Image {
+                data_url,
+                width: image.width,
+                height: image.height,
+            }),
+            Err(e) => app.push_tool_log(e.to_string()),
+        }
+    } else if let Ok(text) = clipboard.get_text() {
+        app.insert_paste(&text);
+    }
+}
+
+/// Run the slash command `cmd` with the text typed after it (`rest`), from the input or
+/// the command palette. Prompt commands fill the input with their prefix.
+pub(super) fn run_slash_command(
+    cmd: &ResolvedCommand,
+    rest: String,
+    app: &mut App,
+    config: &Arc<Config>,
+    pending_chat: &mut Option<PendingChat>,
+    api_messages: &mut Option<Vec<Value>>,
+    rt: &Arc<Runtime>,
+) {
+    stats::record_command(&cmd.name);
+
+    match cmd.name.as_str() {
+        "create-command" => {
+            app.open_create_command_popup();
+        }
+        "update-command" => {
+            app.open_update_command_popup();
+        }
+        "delete-command" => {
+            app.open_delete_command_popup();
+        }
+        "preview" => {
+            app.open_context_preview_popup(api_messages.as_deref(), &rest, config);
+        }
+        "context" => {
+            app.open_context_preview_popup(api_messages.as_deref(), "", config);
+        }
+        "clear-context" => {
+            app.clear_context();
+            *api_messages = None;
+        }
+        "compact" => {
+            if spend_allows_request(app, config) {
+                app.request_compaction(api_messages);
+            }
+        }
+        "continue" => {
+            if api_messages.is_none() {
+                app.push_tool_log("Nothing to continue yet.".to_string());
+            } else if spend_allows_request(app, config) {
+                // Same mode as the reply being continued (the selected one).
+                let mode = SUGGESTIONS[app.selected_suggestion].to_string();
+                send_prompt(
+                    app,
+                    config,
+                    pending_chat,
+                    api_messages,
+                    rt,
+                    llm::CONTINUE_PROMPT.to_string(),
+                    Vec::new(),
+                    mode,
+                );
+            }
+        }
+        "pin" => {
+            app.pin_file(&rest);
+        }
+        "cd" => {
+            app.change_working_dir(&rest);
+        }
+        "share" => {
+            app.share_conversation(&rest, config);
+        }
+        "save-last" => {
+            app.save_last_command(&rest);
+        }
+        "params" => {
+            app.open_params_popup(config);
+        }
+        "permissions" => {
+            app.open_permissions_popup();
+        }
+        "pins" => {
+            app.open_pins_popup();
+        }
+        "copy" => {
+            app.open_copy_mode();
+        }
+        "profile" => {
+            app.profile_command(&rest);
+        }
+        "dryrun" => {
+            app.dry_run_command(&rest);
+        }
+        "suggest" => {
+            app.suggest_command(&rest);
+        }
+        "reload-context" => {
+            app.reload_workspace_context(api_messages, config, true);
+        }
+        "spend" => {
+            if rest.eq_ignore_ascii_case("override") {
+                spend::override_caps();
+                app.push_tool_log(
+                    "Spend caps lifted for this session: requests are sent past the cap."
+                        .to_string(),
+                );
+            } else {
+                let totals = spend::totals();
+                app.spend_status = spend::check(&config.spend_limits);
+                app.push_tool_log(format!(
+                    "Spend: ${:.2} this session, {} (caps: {}{}).",
+                    totals.session,
+                    totals,
+                    config.spend_limits,
+                    if spend::caps_overridden() {
+                        ", overridden this session"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+        }
+        "cost" => {
+            let cache = spend::session_cache();
+            app.push_tool_log(format!("Cost: {}.", spend::totals()));
+            app.push_tool_log(if cache.is_empty() {
+                "Prompt cache: no reads or writes this session.".to_string()
+            } else {
+                format!("Prompt cache: {}.", cache)
+            });
+        }
+        "unpin" => {
+            app.unpin(&rest);
+        }
+        "undo" => {
+            let result = app.undo_stack.lock().ok().and_then(|mut s| s.undo_last());
+            let msg = match result {
+                Some(r) => r.to_string(),
+                None => "Nothing to undo.".to_string(),
+            };
+            app.push_tool_log(msg);
+        }
+        _ => {
+            let prefix = templates::expand_cwd(&cmd.prompt_prefix, &app.workspace.working_dir);
+            app.input = if rest.is_empty() {
+                format!("{} ", prefix)
+            } else {
+                format!("{} {}", prefix, rest)
+            };
+            app.input_cursor = app.input.len();
+            app.pending_command_mode = Some(cmd.mode.clone());
+            app.pending_command_model = cmd.model.as_ref().map(|id| CommandModel {
+                id: id.clone(),
+                name: models::resolve_model_display_name(id),
+                command: cmd.name.clone(),
+            });
+            app.pending_output_schema = cmd.output_schema.clone();
+            app.pending_follow_up = match cmd.name.as_str() {
+                "commit" => Some(TurnFollowUp::Commit),
+                "review" => Some(TurnFollowUp::Review {
+                    scope: (!rest.is_empty()).then(|| rest.clone()),
+                }),
+                _ => None,
+            };
+            app.selected_suggestion = SUGGESTIONS
+                .iter()
+                .position(|s| *s == cmd.mode)
+                .unwrap_or(app.selected_suggestion);
+        }
     }
 }
 
@@ -44,11 +325,34 @@ pub(crate) fn handle_main_input(
     rt: &Arc<Runtime>,
 ) -> super::HandleResult {
     let in_slash_mode = app.input.starts_with('/');
-    let commands = filtered_commands(app);
+    let commands: Vec<ResolvedCommand> = app.slash_commands().into_iter().cloned().collect();
+    let mentions: Vec<String> = app
+        .mention_candidates()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
 
     match (key_code, key_modifiers) {
         (KeyCode::Char('c'), KeyModifiers::CONTROL) => super::HandleResult::Break,
 
+        // @mention picker: Up/Down navigate files, Tab/Enter pins the selected one
+        (KeyCode::Up, _) if !mentions.is_empty() => {
+            app.selected_mention_index = app
+                .selected_mention_index
+                .checked_sub(1)
+                .unwrap_or(mentions.len() - 1);
+            super::HandleResult::Continue
+        }
+        (KeyCode::Down, _) if !mentions.is_empty() => {
+            app.selected_mention_index = (app.selected_mention_index + 1) % mentions.len();
+            super::HandleResult::Continue
+        }
+        (KeyCode::Tab | KeyCode::Enter, KeyModifiers::NONE) if !mentions.is_empty() => {
+            let path = &mentions[app.selected_mention_index.min(mentions.len() - 1)];
+            app.accept_mention(path);
+            super::HandleResult::Continue
+        }
+
         // Slash autocomplete: Up/Down/Tab navigate commands (wrap around at edges)
         (KeyCode::Tab, KeyModifiers::SHIFT) if in_slash_mode && !commands.is_empty() => {
             let len = commands.len();
@@ -89,40 +393,7 @@ pub(crate) fn handle_main_input(
             app.input.clear();
             app.input_cursor = 0;
             app.selected_command_index = 0;
-
-            match cmd.name.as_str() {
-                "create-command" => {
-                    app.open_create_command_popup();
-                }
-                "update-command" => {
-                    app.open_update_command_popup();
-                }
-                "delete-command" => {
-                    app.open_delete_command_popup();
-                }
-                "undo" => {
-                    let result = app.undo_stack.lock().ok().and_then(|mut s| s.undo_last());
-                    let msg = match result {
-                        Some(r) => r.to_string(),
-                        None => "Nothing to undo.".to_string(),
-                    };
-                    app.push_tool_log(msg);
-                }
-                _ => {
-                    let prefix = templates::expand_cwd(&cmd.prompt_prefix, &app.workspace.root);
-                    app.input = if rest.is_empty() {
-                        format!("{} ", prefix)
-                    } else {
-                        format!("{} {}", prefix, rest)
-                    };
-                    app.input_cursor = app.input.len();
-                    app.pending_command_mode = Some(cmd.mode.clone());
-                    app.selected_suggestion = SUGGESTIONS
-                        .iter()
-                        .position(|s| *s == cmd.mode)
-                        .unwrap_or(app.selected_suggestion);
-                }
-            }
+            run_slash_command(&cmd, rest, app, config, pending_chat, api_messages, rt);
             super::HandleResult::Continue
         }
 
@@ -156,36 +427,44 @@ pub(crate) fn handle_main_input(
             super::HandleResult::Continue
         }
 
-        // Enter: send message
+        // Enter: send message, or queue it while a request is in flight
         (KeyCode::Enter, _) => {
-            let input = app.input.trim().to_string();
-            if !input.is_empty() && pending_chat.is_none() {
+            let queue = pending_chat.is_some() || !app.prompt_queue.is_empty();
+            if !app.input.trim().is_empty() && (queue || spend_allows_request(app, config)) {
                 let mode = app
                     .pending_command_mode
                     .take()
                     .unwrap_or_else(|| SUGGESTIONS[app.selected_suggestion].to_string());
 
-                app.mark_dirty();
-                app.input.clear();
-                app.input_cursor = 0;
-                app.push_user(&input);
-                app.push_assistant(String::new());
-                app.scroll = ScrollPosition::Bottom;
-
-                let model_id = app.current_model_id.clone();
-                let prev_messages = api_messages.clone();
-                let pc = chat_spawn::spawn_chat(
-                    rt,
-                    Arc::clone(config),
-                    app.workspace.clone(),
-                    model_id,
-                    input,
-                    mode,
-                    prev_messages,
-                    Some(app.undo_stack.clone()),
-                );
-                app.is_streaming = true;
-                *pending_chat = Some(pc);
+                let (input, images) = app.take_input();
+                let follow_up = app.pending_follow_up.take();
+                let turn_model = app.pending_command_model.take();
+                let output_schema = app.pending_output_schema.take();
+                if queue {
+                    app.queue_prompt(QueuedPrompt {
+                        prompt: input,
+                        images,
+                        mode,
+                        turn_model,
+                        output_schema,
+                        follow_up,
+                    });
+                    app.scroll = ScrollPosition::Bottom;
+                } else {
+                    app.turn_follow_up = follow_up;
+                    app.turn_model = turn_model;
+                    app.turn_output_schema = output_schema;
+                    send_prompt(
+                        app,
+                        config,
+                        pending_chat,
+                        api_messages,
+                        rt,
+                        input,
+                        images,
+                        mode,
+                    );
+                }
             }
             super::HandleResult::Continue
         }
@@ -194,14 +473,26 @@ pub(crate) fn handle_main_input(
         (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
             app.input.clear();
             app.input_cursor = 0;
+            app.attachments.clear();
             app.selected_command_index = 0;
             app.pending_command_mode = None;
+            app.pending_command_model = None;
+            app.pending_output_schema = None;
+            app.pending_follow_up = None;
+            super::HandleResult::Continue
+        }
+
+        // Ctrl+V: paste from the system clipboard (image as attachment, else text)
+        (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+            paste_from_clipboard(app);
             super::HandleResult::Continue
         }
 
         (KeyCode::Backspace, _) => {
             let pos = app.input_cursor.min(app.input.len());
-            if pos > 0 {
+            if app.delete_chip_before_cursor() {
+                // Whole chip removed along with its attachment.
+            } else if pos > 0 {
                 let mut start = pos - 1;
                 while start > 0 && !app.input.is_char_boundary(start) {
                     start -= 1;
@@ -214,11 +505,19 @@ pub(crate) fn handle_main_input(
             }
             if app.input.is_empty() {
                 app.pending_command_mode = None;
+                app.pending_command_model = None;
+                app.pending_output_schema = None;
+                app.pending_follow_up = None;
             }
+            app.update_mention_files();
             super::HandleResult::Continue
         }
 
         (KeyCode::Left, _) if !in_slash_mode => {
+            // On an empty input, Left/Right scroll the focused truncated code block.
+            if app.input.is_empty() && app.scroll_code(-constants::SCROLL_COLUMNS) {
+                return super::HandleResult::Continue;
+            }
             let pos = app.input_cursor.min(app.input.len());
             if pos > 0 {
                 let mut p = pos - 1;
@@ -230,6 +529,9 @@ pub(crate) fn handle_main_input(
             super::HandleResult::Continue
         }
         (KeyCode::Right, _) if !in_slash_mode => {
+            if app.input.is_empty() && app.scroll_code(constants::SCROLL_COLUMNS) {
+                return super::HandleResult::Continue;
+            }
             let pos = app.input_cursor.min(app.input.len());
             if pos < app.input.len() {
                 let mut next = pos + 1;
@@ -290,12 +592,14 @@ pub(crate) fn handle_main_input(
                 app.input_cursor = pos + c.len_utf8();
             }
             // Clamp selected_command_index when filter shrinks (user typed more chars)
-            if app.input.starts_with('/') {
-                let new_commands =
-                    commands::filter_commands_resolved(&app.resolved_commands, slash_filter(app));
-                if !new_commands.is_empty() && app.selected_command_index >= new_commands.len() {
-                    app.selected_command_index = new_commands.len().saturating_sub(1);
-                }
+            let command_count = app.slash_commands().len();
+            if command_count > 0 && app.selected_command_index >= command_count {
+                app.selected_command_index = command_count - 1;
+            }
+            app.update_mention_files();
+            let mention_count = app.mention_candidates().len();
+            if app.selected_mention_index >= mention_count {
+                app.selected_mention_index = mention_count.saturating_sub(1);
             }
             super::HandleResult::Continue
         }
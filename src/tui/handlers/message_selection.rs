@@ -0,0 +1,97 @@
+//! Handler for the keyboard message selection (Alt+V, or `v` while scrolled back): move and
+//! extend the selection, then copy, pin, branch from, or delete it.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::core::history;
+use crate::core::llm::TokenUsage;
+
+use super::super::app::{App, MessageSelection};
+use super::super::chat_result;
+use super::HandleResult;
+use super::selection;
+use super::shortcuts::{ShortcutContext, branch_at};
+
+pub(super) fn handle_message_selection(
+    key_code: KeyCode,
+    key_modifiers: KeyModifiers,
+    mut ctx: ShortcutContext<'_>,
+) -> HandleResult {
+    let Some(selection) = ctx.app.message_selection else {
+        return HandleResult::Continue;
+    };
+    // The second `d` deletes; any other key keeps the messages.
+    if selection.confirm_delete {
+        ctx.app.message_selection = Some(MessageSelection {
+            confirm_delete: false,
+            ..selection
+        });
+        if key_code == KeyCode::Char('d') {
+            delete_selection(&mut ctx);
+        }
+        return HandleResult::Continue;
+    }
+    let extend = key_modifiers.contains(KeyModifiers::SHIFT);
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('q') => ctx.app.message_selection = None,
+        KeyCode::Up | KeyCode::Char('k') => ctx.app.move_message_selection(false, extend),
+        KeyCode::Down | KeyCode::Char('j') => ctx.app.move_message_selection(true, extend),
+        KeyCode::Char('K') => ctx.app.move_message_selection(false, true),
+        KeyCode::Char('J') => ctx.app.move_message_selection(true, true),
+        KeyCode::Char('c') | KeyCode::Char('y') | KeyCode::Enter
+            if selection::try_copy_selected_messages(ctx.app) =>
+        {
+            ctx.app.message_selection = None
+        }
+        KeyCode::Char('p') => ctx.app.pin_selected_messages(),
+        KeyCode::Char('b') => {
+            if ctx.pending_chat.is_some() {
+                ctx.app
+                    .push_tool_log("Wait for the reply to finish before branching.".to_string());
+            } else {
+                let end = *selection.range().end();
+                ctx.app.message_selection = None;
+                branch_at(&mut ctx, end);
+            }
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if ctx.pending_chat.is_some() {
+                ctx.app.push_tool_log(
+                    "Wait for the reply to finish before deleting messages.".to_string(),
+                );
+            } else {
+                ctx.app.message_selection = Some(MessageSelection {
+                    confirm_delete: true,
+                    ..selection
+                });
+            }
+        }
+        _ => {}
+    }
+    HandleResult::Continue
+}
+
+/// Delete the selected messages from the conversation and the context of the next request,
+/// then save it.
+fn delete_selection(ctx: &mut ShortcutContext<'_>) {
+    let removed = ctx.app.delete_selected_messages();
+    if removed == 0 {
+        return;
+    }
+    let persisted = App::messages_to_persist_format(
+        &ctx.app.messages,
+        &ctx.app.message_timestamps,
+        &ctx.app.message_usage,
+        &ctx.app.pinned_messages,
+        &ctx.app.message_reactions,
+    );
+    let api_only = history::api_messages_from_persisted(&persisted);
+    ctx.app.token_usage = Some(TokenUsage::estimated_from_messages(&api_only));
+    *ctx.api_messages = (!api_only.is_empty()).then_some(api_only);
+    ctx.app.push_tool_log(format!(
+        "Deleted {} message{} from the conversation.",
+        removed,
+        if removed == 1 { "" } else { "s" }
+    ));
+    chat_result::save_conversation_if_dirty(ctx.app, ctx.api_messages, ctx.config.as_ref());
+}
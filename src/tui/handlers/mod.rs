@@ -2,16 +2,32 @@
 
 mod chat_spawn;
 mod command_form;
+mod command_palette;
+mod commit;
 mod confirm;
+mod context_preview;
+mod copy_mode;
 mod delete_command;
+mod diff_viewer;
+mod duplicate;
+mod file_tree;
 mod history_selector;
 mod input;
+mod message_selection;
 mod model_selector;
+mod params;
+mod permissions;
+mod pins;
 mod popups;
+mod profile;
+mod save_last;
+mod search;
 mod selection;
 mod shortcuts;
+mod spend_cap;
+mod suggestions;
 
-use crossterm::event::{KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::layout::Position;
 use std::sync::Arc;
 use std::sync::mpsc;
@@ -25,12 +41,16 @@ use crate::core::config::Config;
 use crate::core::llm;
 use crate::core::models::ModelInfo;
 
-use super::app::{App, CopyTarget};
+use super::app::{App, CopyTarget, ToolUpdate};
 use super::constants;
 use super::shortcuts::Shortcut;
+use super::tabs::TabAction;
 
 use self::shortcuts::{ShortcutContext, handle_shortcut};
 
+pub(crate) use self::input::start_queued_prompt;
+pub(crate) use self::suggestions::decide as decide_suggestion;
+
 const CREDITS_URL: &str = "https://openrouter.ai/settings/credits";
 
 /// True if key is the platform-appropriate copy shortcut (⌘C on macOS, Ctrl+Shift+C elsewhere).
@@ -44,20 +64,23 @@ fn is_copy_shortcut(code: crossterm::event::KeyCode, modifiers: KeyModifiers) ->
     return modifiers.contains(KeyModifiers::CONTROL) && modifiers.contains(KeyModifiers::SHIFT);
 }
 
-/// Holds receivers for a chat request in progress (progress logs, streamed content, final result).
+/// Holds receivers for a chat request in progress (progress logs, streamed content,
+/// tool call events, final result).
 pub struct PendingChat {
-    pub progress_rx: mpsc::Receiver<String>,
+    pub progress_rx: mpsc::Receiver<llm::ProgressEvent>,
     pub stream_rx: mpsc::Receiver<String>,
+    pub tool_event_rx: mpsc::Receiver<ToolUpdate>,
     pub result_rx: mpsc::Receiver<Result<llm::ChatResult, llm::ChatError>>,
     /// Token to cancel the in-flight request.
     pub cancel_token: CancellationToken,
 }
 
-/// Result of handling an event: continue the loop or exit.
+/// Result of handling an event: continue the loop, exit, or change tabs.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum HandleResult {
     Continue,
     Break,
+    Tab(TabAction),
 }
 
 /// Set cursor to pointer (hand) or default. Uses OSC 22 (Kitty, iTerm2, Ghostty, Foot).
@@ -72,19 +95,17 @@ pub fn set_cursor_shape(pointer: bool) {
     let _ = std::io::stdout().flush();
 }
 
-/// True when Esc would start Option+key (escape_pending) rather than cancel/slash-clear.
+/// True when Esc would start Option+key (escape_pending) rather than cancel/slash-clear/unzoom.
 pub(crate) fn would_esc_start_meta_sequence(
     key: &crossterm::event::KeyEvent,
     app: &App,
     pending_chat: &Option<PendingChat>,
 ) -> bool {
     Shortcut::is_escape(key)
-        && app.confirm_popup.is_none()
-        && app.model_selector.is_none()
-        && app.history_selector.is_none()
-        && app.command_form_popup.is_none()
-        && app.delete_command_popup.is_none()
+        && !app.popup_open()
         && !app.input.starts_with('/')
+        && app.zoom.is_none()
+        && app.search.is_none()
         && pending_chat.is_none()
 }
 
@@ -97,12 +118,7 @@ pub fn handle_mouse(mouse: crossterm::event::MouseEvent, app: &mut App) -> Handl
     let over_message = selection::hit_test_message(app, pos);
     let buffer_coords = selection::pos_to_buffer_coords(app, pos);
 
-    if app.confirm_popup.is_none()
-        && app.model_selector.is_none()
-        && app.history_selector.is_none()
-        && app.command_form_popup.is_none()
-        && app.delete_command_popup.is_none()
-    {
+    if !app.popup_open() {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
                 if over_credits {
@@ -156,7 +172,23 @@ pub fn handle_mouse(mouse: crossterm::event::MouseEvent, app: &mut App) -> Handl
                         .map(|(sl, sc, el, ec)| (sl, sc) == (el, ec) || drag_start == (sl, sc))
                         .unwrap_or(true);
                     if is_click {
-                        if let Some(target) = selection::hit_test_copy_region(app, pos) {
+                        if let Some((line, _)) = buffer_coords {
+                            app.focus_code_at_line(line);
+                        }
+                        if let Some(target) = selection::hit_test_link(app, pos) {
+                            if let Err(e) = target.open() {
+                                log::warn!("Cannot open link: {}", e);
+                            }
+                        } else if let Some(preview) = selection::hit_test_preview(app, pos) {
+                            if let Err(e) = preview.open() {
+                                log::warn!("Cannot open {} preview: {}", preview.kind.label(), e);
+                            }
+                        } else if let Some(idx) = selection::hit_test_tool_result(app, pos) {
+                            app.toggle_tool_result(idx);
+                        } else if let Some(idx) = selection::hit_test_error_actions(app, pos) {
+                            // Error blocks hold the whole error: no API messages needed.
+                            app.explain_error(Some(idx), &[]);
+                        } else if let Some(target) = selection::hit_test_copy_region(app, pos) {
                             let content = match &target {
                                 CopyTarget::Message(idx) => {
                                     app.messages.get(*idx).and_then(|m| match m {
@@ -262,13 +294,7 @@ pub fn handle_key(key: crossterm::event::KeyEvent, ctx: HandleKeyContext<'_>) ->
     }
 
     // Copy: ⌘C on macOS, Ctrl+Shift+C on Linux/Windows.
-    if is_copy_shortcut(key.code, key.modifiers)
-        && app.confirm_popup.is_none()
-        && app.model_selector.is_none()
-        && app.history_selector.is_none()
-        && app.command_form_popup.is_none()
-        && app.delete_command_popup.is_none()
-    {
+    if is_copy_shortcut(key.code, key.modifiers) && !app.popup_open() {
         if selection::try_copy_selection(app) {
             // Selection copied
         } else if let Some(msg_idx) = app
@@ -280,22 +306,49 @@ pub fn handle_key(key: crossterm::event::KeyEvent, ctx: HandleKeyContext<'_>) ->
         return HandleResult::Continue;
     }
 
+    // Conversation search: typing the query, or moving between matches.
+    if app.search.is_some() && !app.popup_open() {
+        return search::handle_search(key.code, key.modifiers, app);
+    }
+
+    // Keyboard message selection: moving, extending, and acting on the selection.
+    if app.message_selection.is_some() && !app.popup_open() {
+        return message_selection::handle_message_selection(
+            key.code,
+            key.modifiers,
+            ShortcutContext {
+                app,
+                config,
+                api_messages,
+                pending_chat,
+                pending_model_fetch,
+                rt,
+            },
+        );
+    }
+
     // Esc: in slash mode, clear input; else cancel in-flight or start Option+key sequence.
-    if Shortcut::is_escape(&key)
-        && app.confirm_popup.is_none()
-        && app.model_selector.is_none()
-        && app.history_selector.is_none()
-        && app.command_form_popup.is_none()
-        && app.delete_command_popup.is_none()
-    {
+    if Shortcut::is_escape(&key) && !app.popup_open() {
         if app.input.starts_with('/') {
             app.input.clear();
             app.input_cursor = 0;
             app.selected_command_index = 0;
             return HandleResult::Continue;
         }
+        if app.zoom.is_some() {
+            app.unzoom();
+            return HandleResult::Continue;
+        }
         if let Some(pc) = pending_chat.as_ref() {
             pc.cancel_token.cancel();
+            let dropped = app.clear_prompt_queue();
+            if dropped > 0 {
+                app.push_tool_log(format!(
+                    "Dropped {} queued prompt{}.",
+                    dropped,
+                    if dropped == 1 { "" } else { "s" }
+                ));
+            }
             return HandleResult::Continue;
         }
         app.escape_pending = true;
@@ -312,21 +365,49 @@ pub fn handle_key(key: crossterm::event::KeyEvent, ctx: HandleKeyContext<'_>) ->
             pending_chat.is_none(),
             rt,
         ) {
-            confirm::ConfirmPopupResult::PutBack(p) => app.confirm_popup = Some(p),
+            confirm::ConfirmPopupResult::PutBack(p) => app.confirm_popup = Some(*p),
             confirm::ConfirmPopupResult::Spawned(pc) => *pending_chat = Some(pc),
         }
         return HandleResult::Continue;
     }
 
+    // Command palette popup
+    if app.command_palette.is_some() {
+        return command_palette::handle_command_palette(
+            key.code,
+            key.modifiers,
+            ShortcutContext {
+                app,
+                config,
+                api_messages,
+                pending_chat,
+                pending_model_fetch,
+                rt,
+            },
+        );
+    }
+
     // Popups (confirm, history, model) - handled before general shortcuts
     // History selector popup
     if app.history_selector.is_some() {
-        return popups::handle_history_selector(key.code, key.modifiers, app, api_messages);
+        return popups::handle_history_selector(
+            key.code,
+            key.modifiers,
+            app,
+            api_messages,
+            pending_chat,
+        );
     }
 
     // Model selector popup
     if app.model_selector.is_some() {
-        return popups::handle_model_selector(key.code, key.modifiers, app, pending_model_fetch);
+        return popups::handle_model_selector(
+            key.code,
+            key.modifiers,
+            app,
+            config,
+            pending_model_fetch,
+        );
     }
 
     // Command form popup (create/update)
@@ -339,6 +420,97 @@ pub fn handle_key(key: crossterm::event::KeyEvent, ctx: HandleKeyContext<'_>) ->
         return delete_command::handle_delete_command_popup(key.code, key.modifiers, app);
     }
 
+    // Context preview popup
+    if app.context_preview_popup.is_some() {
+        return context_preview::handle_context_preview_popup(key.code, app);
+    }
+
+    // Generation parameters popup
+    if app.params_popup.is_some() {
+        return params::handle_params_popup(key.code, key.modifiers, app, config);
+    }
+
+    // Usage statistics screen (F9 also closes it, as a shortcut)
+    if app.stats_screen.is_some() {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+            app.stats_screen = None;
+        }
+        return HandleResult::Continue;
+    }
+
+    // Bash command rules popup
+    if app.permissions_popup.is_some() {
+        return permissions::handle_permissions_popup(key.code, key.modifiers, app);
+    }
+
+    // Pinned messages popup
+    if app.pins_popup.is_some() {
+        return pins::handle_pins_popup(key.code, app);
+    }
+
+    // Code block copy popup
+    if app.copy_mode.is_some() {
+        return copy_mode::handle_copy_mode(key.code, app);
+    }
+
+    // Workspace file tree popup
+    if app.file_tree.is_some() {
+        return file_tree::handle_file_tree(key.code, app);
+    }
+
+    // Profile switcher popup
+    if app.profile_picker.is_some() {
+        return profile::handle_profile_picker(key.code, app);
+    }
+
+    // Spend cap confirmation popup
+    if app.spend_popup.is_some() {
+        return spend_cap::handle_spend_cap_popup(key.code, app);
+    }
+
+    // `/save-last` confirmation popup
+    if app.save_last_popup.is_some() {
+        return save_last::handle_save_last_popup(key.code, app);
+    }
+
+    // Duplicate conversation popup
+    if app.duplicate_popup.is_some() {
+        return duplicate::handle_duplicate_popup(key.code, app, api_messages, config);
+    }
+
+    // Review of suggested edits (/suggest)
+    if app.suggestion_review.is_some() {
+        return suggestions::handle_suggestion_review(key.code, app, api_messages, config);
+    }
+
+    // Diff viewer (/review)
+    if app.diff_viewer.is_some() {
+        return diff_viewer::handle_diff_viewer(key.code, app);
+    }
+
+    // Commit popup
+    if app.commit_popup.is_some() {
+        return commit::handle_commit_popup(key.code, key.modifiers, app);
+    }
+
+    // Start a conversation search: Ctrl+F, or `/` while reading back through the history.
+    let ctrl_f = key.code == KeyCode::Char('f') && key.modifiers.contains(KeyModifiers::CONTROL);
+    if ctrl_f || key.code == KeyCode::Char('/') && app.in_scrollback() {
+        app.open_search();
+        return HandleResult::Continue;
+    }
+
+    // Select messages with the keyboard: `v` while reading back through the history.
+    if key.code == KeyCode::Char('v') && key.modifiers.is_empty() && app.in_scrollback() {
+        let from = app
+            .zoom
+            .map(|z| z.msg_idx)
+            .or(app.hovered_message_idx)
+            .or_else(|| selection::message_idx_at_scroll_line(app));
+        app.start_message_selection(from);
+        return HandleResult::Continue;
+    }
+
     // Main input handling
     input::handle_main_input(
         key.code,
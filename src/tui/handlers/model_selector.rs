@@ -9,7 +9,8 @@ use ratatui::widgets::ListState;
 use tokio::runtime::Runtime;
 
 use crate::core::config::Config;
-use crate::core::models::{self, ModelInfo, filter_models};
+use crate::core::models::{self, ModelInfo};
+use crate::core::persistence;
 
 use super::super::app::{App, ModelSelectorState};
 
@@ -38,7 +39,13 @@ pub(crate) fn handle_model_selector_key(
         _ => {}
     }
 
-    let filtered = filter_models(&selector.models, &selector.filter);
+    if key_code == KeyCode::Char('f') && key_modifiers.contains(KeyModifiers::CONTROL) {
+        selector.toggle_selected_favorite();
+        return ModelSelectorAction::Keep;
+    }
+
+    let entries = selector.entries();
+    let count = entries.len();
     match key_code {
         KeyCode::Esc => ModelSelectorAction::Close,
         KeyCode::Up => {
@@ -46,23 +53,20 @@ pub(crate) fn handle_model_selector_key(
             ModelSelectorAction::Keep
         }
         KeyCode::Down => {
-            if !filtered.is_empty() {
-                selector.selected_index =
-                    (selector.selected_index + 1).min(filtered.len().saturating_sub(1));
+            if count > 0 {
+                selector.selected_index = (selector.selected_index + 1).min(count - 1);
             }
             ModelSelectorAction::Keep
         }
         KeyCode::Enter => {
-            if selector.fetch_error.is_none() && selector.selected_index < filtered.len() {
-                ModelSelectorAction::Select(filtered[selector.selected_index].clone())
+            if selector.fetch_error.is_none() && selector.selected_index < count {
+                ModelSelectorAction::Select(entries[selector.selected_index].1.clone())
             } else {
                 ModelSelectorAction::Keep
             }
         }
         KeyCode::Backspace | KeyCode::Char(_) => {
-            selector.selected_index = selector
-                .selected_index
-                .min(filtered.len().saturating_sub(1));
+            selector.selected_index = selector.selected_index.min(count.saturating_sub(1));
             ModelSelectorAction::Keep
         }
         _ => ModelSelectorAction::Keep,
@@ -86,6 +90,7 @@ pub(crate) fn open_model_selector(
         fetch_error: None,
         filter: String::new(),
         fetch_started_at: Some(Instant::now()),
+        prefs: persistence::load_model_prefs(),
     });
     *pending_model_fetch = Some(rx);
     std::thread::spawn(move || {
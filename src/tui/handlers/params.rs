@@ -0,0 +1,64 @@
+//! Handler for the generation parameters popup (/params).
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::core::config::Config;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+/// Use the typed parameters for the session, or save them for the model when `save` is set.
+/// Invalid values keep the popup open with the error.
+fn apply_params(app: &mut App, config: &Config, save: bool) {
+    let Some(state) = app.params_popup.as_mut() else {
+        return;
+    };
+    match state.parse() {
+        Ok(params) => {
+            let model_id = state.model_id.clone();
+            app.params_popup = None;
+            app.set_model_params(config, &model_id, params, save);
+        }
+        Err(e) => state.error = Some(e.to_string()),
+    }
+}
+
+pub(super) fn handle_params_popup(
+    key_code: KeyCode,
+    key_modifiers: KeyModifiers,
+    app: &mut App,
+    config: &Config,
+) -> HandleResult {
+    let Some(state) = app.params_popup.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc => {
+            app.params_popup = None;
+        }
+        KeyCode::Tab | KeyCode::Down => {
+            state.focused_field = state.focused_field.next();
+        }
+        KeyCode::BackTab | KeyCode::Up => {
+            state.focused_field = state.focused_field.prev();
+        }
+        KeyCode::Enter => apply_params(app, config, false),
+        KeyCode::Char('s') if key_modifiers.contains(KeyModifiers::CONTROL) => {
+            apply_params(app, config, true)
+        }
+        KeyCode::Backspace => {
+            state.error = None;
+            state.value_mut(state.focused_field).pop();
+        }
+        KeyCode::Char(c)
+            if !key_modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            state.error = None;
+            state.value_mut(state.focused_field).push(c);
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
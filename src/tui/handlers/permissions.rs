@@ -0,0 +1,57 @@
+//! Handler for the Bash command rules popup (/permissions).
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::core::command_rules::RuleKind;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_permissions_popup(
+    key_code: KeyCode,
+    key_modifiers: KeyModifiers,
+    app: &mut App,
+) -> HandleResult {
+    let Some(state) = app.permissions_popup.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    // Typing a new rule: Enter adds it, Esc drops it.
+    if let Some((_, pattern)) = state.input.as_mut() {
+        match key_code {
+            KeyCode::Esc => state.input = None,
+            KeyCode::Enter => state.submit_input(),
+            KeyCode::Backspace => {
+                pattern.pop();
+            }
+            KeyCode::Char(c)
+                if !key_modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                pattern.push(c);
+            }
+            _ => {}
+        }
+        return HandleResult::Continue;
+    }
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.permissions_popup = None;
+        }
+        KeyCode::Down => state.select_next(),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Char('s') => state.save_selected(),
+        KeyCode::Char('d') | KeyCode::Delete => state.remove_selected(),
+        KeyCode::Char('a') => {
+            state.message = None;
+            state.input = Some((RuleKind::Allow, String::new()));
+        }
+        KeyCode::Char('x') => {
+            state.message = None;
+            state.input = Some((RuleKind::Deny, String::new()));
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
@@ -0,0 +1,41 @@
+//! Handler for the pinned messages popup (/pins).
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_pins_popup(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let Some(state) = app.pins_popup.as_mut() else {
+        return HandleResult::Continue;
+    };
+    let selected = app.pinned_messages.iter().nth(state.selected).copied();
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.pins_popup = None;
+        }
+        KeyCode::Down => state.select_next(app.pinned_messages.len()),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Enter => {
+            app.pins_popup = None;
+            if let Some(msg_idx) = selected {
+                app.zoom_message(msg_idx);
+            }
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(msg_idx) = selected {
+                app.toggle_message_pin(msg_idx);
+            }
+            let count = app.pinned_messages.len();
+            match app.pins_popup.as_mut() {
+                Some(_) if count == 0 => app.pins_popup = None,
+                Some(state) => state.selected = state.selected.min(count - 1),
+                None => {}
+            }
+        }
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
@@ -5,19 +5,21 @@ use std::sync::mpsc;
 
 use serde_json::Value;
 
+use crate::core::config::Config;
 use crate::core::history::{self};
 use crate::core::llm;
 use crate::core::models::ModelInfo;
 
 use crate::tui::app::App;
 
-use super::{HandleResult, history_selector, model_selector};
+use super::{HandleResult, PendingChat, history_selector, model_selector};
 
 /// Handle key when model selector popup is open.
 pub(super) fn handle_model_selector(
     key_code: KeyCode,
     modifiers: KeyModifiers,
     app: &mut App,
+    config: &Config,
     pending_model_fetch: &mut Option<mpsc::Receiver<Result<Vec<ModelInfo>, String>>>,
 ) -> HandleResult {
     let Some(selector) = app.model_selector.as_mut() else {
@@ -32,9 +34,14 @@ pub(super) fn handle_model_selector(
         model_selector::ModelSelectorAction::Select(model) => {
             app.current_model_id = model.id.clone();
             app.model_name = model.name.clone();
+            app.model_source = crate::core::config::ModelSource::LastModel;
             app.context_length = model.context_length;
             app.token_usage = None;
-            let _ = crate::core::persistence::save_last_model(&model.id);
+            let _ = crate::core::persistence::save_last_model(config.provider, &model.id);
+            selector.prefs.record_recent(&model.id);
+            if let Err(e) = crate::core::persistence::save_model_prefs(&selector.prefs) {
+                log::warn!("Failed to save recent models: {}", e);
+            }
             app.model_selector = None;
             *pending_model_fetch = None;
         }
@@ -49,6 +56,7 @@ pub(super) fn handle_history_selector(
     modifiers: KeyModifiers,
     app: &mut App,
     api_messages: &mut Option<Vec<Value>>,
+    pending_chat: &mut Option<PendingChat>,
 ) -> HandleResult {
     let Some(selector) = app.history_selector.as_mut() else {
         return HandleResult::Continue;
@@ -65,9 +73,27 @@ pub(super) fn handle_history_selector(
                     .iter()
                     .find(|c| c.id == id)
                     .map(|m| m.updated_at);
+                // The in-flight request and queued prompts belong to the previous conversation.
+                if let Some(pc) = pending_chat.take() {
+                    pc.cancel_token.cancel();
+                    app.is_streaming = false;
+                    app.tool_queue.clear();
+                    app.turn_follow_up = None;
+                    app.turn_model = None;
+                    app.turn_output_schema = None;
+                }
+                app.clear_prompt_queue();
+                app.save_ui_state();
                 app.set_messages_from_api(&persisted, fallback_ts);
                 app.set_conversation_id(Some(id.clone()));
-                app.scroll = crate::tui::app::ScrollPosition::Bottom;
+                app.restore_conversation_settings(&history::load_conversation_settings(&id));
+                match history::load_ui_state(&id) {
+                    Some(state) => app.restore_ui_state(&state),
+                    None => {
+                        app.scroll = crate::tui::app::ScrollPosition::Bottom;
+                        app.set_truncate_code(false);
+                    }
+                }
                 let api_only = history::api_messages_from_persisted(&persisted);
                 app.token_usage = Some(llm::TokenUsage::estimated_from_messages(&api_only));
                 *api_messages = Some(api_only);
@@ -80,6 +106,7 @@ pub(super) fn handle_history_selector(
                 Ok(()) => {
                     selector.conversations.retain(|c| c.id != id);
                     selector.content_cache.remove(&id);
+                    selector.origins.remove(&id);
                     let filtered = history::filter_conversations_with_content(
                         &selector.conversations,
                         &selector.filter,
@@ -103,6 +130,17 @@ pub(super) fn handle_history_selector(
                 Err(e) => selector.error = Some(format!("Rename failed: {}", e)),
             }
         }
+        history_selector::HistorySelectorAction::Archive { id, archived } => {
+            selector.error = None;
+            match history::set_archived(&id, archived) {
+                Ok(()) => {
+                    if let Some(meta) = selector.conversations.iter_mut().find(|c| c.id == id) {
+                        meta.archived = archived;
+                    }
+                }
+                Err(e) => selector.error = Some(format!("Archive failed: {}", e)),
+            }
+        }
         history_selector::HistorySelectorAction::Keep => {}
     }
     HandleResult::Continue
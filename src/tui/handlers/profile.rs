@@ -0,0 +1,24 @@
+//! Handler for the profile switcher popup (/profile).
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_profile_picker(key_code: KeyCode, app: &mut App) -> HandleResult {
+    let Some(state) = app.profile_picker.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.profile_picker = None;
+        }
+        KeyCode::Down => state.select_next(),
+        KeyCode::Up => state.select_prev(),
+        KeyCode::Enter => app.choose_profile(),
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
@@ -0,0 +1,18 @@
+//! Handler for the `/save-last` confirmation popup: write the reply to the file or cancel.
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_save_last_popup(key_code: KeyCode, app: &mut App) -> HandleResult {
+    match key_code {
+        KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => app.confirm_save_last(),
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.save_last_popup = None;
+            app.push_tool_log("Save cancelled.".to_string());
+        }
+        _ => {}
+    }
+    HandleResult::Continue
+}
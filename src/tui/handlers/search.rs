@@ -0,0 +1,46 @@
+//! Handler for the conversation search (Ctrl+F, or `/` while scrolled back).
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use super::HandleResult;
+use crate::tui::app::App;
+use crate::tui::constants;
+
+pub(super) fn handle_search(
+    key_code: KeyCode,
+    key_modifiers: KeyModifiers,
+    app: &mut App,
+) -> HandleResult {
+    let Some(search) = app.search.as_mut() else {
+        return HandleResult::Continue;
+    };
+
+    match key_code {
+        KeyCode::Esc => app.close_search(),
+        KeyCode::Up => app.scroll_up(constants::SCROLL_LINES_SMALL),
+        KeyCode::Down => app.scroll_down(constants::SCROLL_LINES_SMALL),
+        KeyCode::PageUp => app.scroll_up(constants::SCROLL_LINES_PAGE),
+        KeyCode::PageDown => app.scroll_down(constants::SCROLL_LINES_PAGE),
+        KeyCode::Enter if search.editing => {
+            if search.query.is_empty() {
+                app.close_search();
+            } else {
+                search.editing = false;
+            }
+        }
+        KeyCode::Enter => app.close_search(),
+        KeyCode::Backspace if search.editing => search.pop_char(),
+        KeyCode::Char('f') if key_modifiers.contains(KeyModifiers::CONTROL) => {
+            search.editing = true;
+        }
+        KeyCode::Char(c) if search.editing && !key_modifiers.contains(KeyModifiers::CONTROL) => {
+            search.push_char(c);
+        }
+        KeyCode::Char('n') => search.step(true),
+        KeyCode::Char('N') => search.step(false),
+        KeyCode::Char('/') => search.editing = true,
+        _ => {}
+    }
+
+    HandleResult::Continue
+}
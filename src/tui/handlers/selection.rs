@@ -5,6 +5,8 @@ use std::time::{Duration, Instant};
 use ratatui::layout::Position;
 
 use super::super::app::{App, CopyTarget};
+use super::super::hyperlinks::LinkTarget;
+use super::super::preview::Preview;
 
 /// Message index at the current scroll position (for Cmd+C when no hover).
 pub(crate) fn message_idx_at_scroll_line(app: &App) -> Option<usize> {
@@ -69,6 +71,19 @@ pub(crate) fn try_copy_message(app: &mut App, msg_idx: usize) -> bool {
         | Some(super::super::app::ChatMessage::Assistant(s)) => s.clone(),
         _ => return false,
     };
+    copy_text(app, content)
+}
+
+/// Copy the messages selected with the keyboard (Alt+V). Returns true if successful.
+pub(crate) fn try_copy_selected_messages(app: &mut App) -> bool {
+    match app.selected_messages_text() {
+        Some(content) => copy_text(app, content),
+        None => false,
+    }
+}
+
+/// Put `content` on the clipboard and show the copy toast. Returns true if successful.
+fn copy_text(app: &mut App, content: String) -> bool {
     if arboard::Clipboard::new()
         .and_then(|mut c| c.set_text(content))
         .is_ok()
@@ -127,6 +142,41 @@ pub(crate) fn pos_to_buffer_coords(app: &App, pos: Position) -> Option<(usize, u
     Some((buffer_line, rel_col))
 }
 
+/// Message index of the tool result whose summary line is at `pos`.
+pub(crate) fn hit_test_tool_result(app: &App, pos: Position) -> Option<usize> {
+    let (line, _) = pos_to_buffer_coords(app, pos)?;
+    app.tool_result_headers
+        .iter()
+        .find(|(header, _)| *header == line)
+        .map(|(_, idx)| *idx)
+}
+
+/// Message index of the error block whose action line is at `pos`.
+pub(crate) fn hit_test_error_actions(app: &App, pos: Position) -> Option<usize> {
+    let (line, _) = pos_to_buffer_coords(app, pos)?;
+    app.error_action_lines
+        .iter()
+        .find(|(action_line, _)| *action_line == line)
+        .map(|(_, idx)| *idx)
+}
+
+/// Mermaid or math block whose header line is at `pos`.
+pub(crate) fn hit_test_preview(app: &App, pos: Position) -> Option<&Preview> {
+    let (line, _) = pos_to_buffer_coords(app, pos)?;
+    app.preview_headers
+        .iter()
+        .find(|(header, _)| *header == line)
+        .map(|(_, preview)| preview)
+}
+
+/// Link of a reply drawn at `pos`.
+pub(crate) fn hit_test_link(app: &App, pos: Position) -> Option<&LinkTarget> {
+    app.link_rects
+        .iter()
+        .find(|link| link.rect.contains(pos))
+        .map(|link| &link.target)
+}
+
 /// Hit-test for copy-on-click. Returns CopyTarget (code blocks first, then message fallback).
 pub(crate) fn hit_test_copy_region(app: &App, pos: Position) -> Option<CopyTarget> {
     let history_rect = app.history_area_rect?;
@@ -1,4 +1,7 @@
-//! Shortcut key handling (History, NewConversation, ModelSelector, Quit).
+//! Shortcut key handling (History, NewConversation, ModelSelector, EditLastMessage,
+//! ComposeInEditor, Regenerate, ZoomMessage, BranchConversation, PinMessage, SelectMessages,
+//! ReactToMessage, SplitPane, FileTree, CodeWrap, ExplainError, CommandPalette, tabs,
+//! StatsScreen, DebugOverlay, Quit).
 
 use std::sync::Arc;
 use std::sync::mpsc;
@@ -6,14 +9,24 @@ use std::sync::mpsc;
 use tokio::runtime::Runtime;
 
 use crate::core::history::{self, first_message_preview};
+use crate::core::llm::TokenUsage;
+use crate::core::message;
 use crate::core::models::ModelInfo;
+use crate::core::stats;
 use crate::tui::shortcuts::Shortcut;
+use crate::tui::tabs::TabAction;
 
-use super::super::app::App;
-use super::super::constants;
+use super::super::app::{App, ChatMessage, ScrollPosition};
+use super::super::chat_result;
+use super::super::constants::{self, SUGGESTIONS};
 use super::HandleResult;
 use super::history_selector;
+use super::input;
 use super::model_selector;
+use super::selection;
+
+/// Days covered by the usage statistics screen (F9).
+const STATS_SCREEN_DAYS: usize = 30;
 
 /// Context passed to shortcut handlers (reduces parameter count).
 pub(super) struct ShortcutContext<'a> {
@@ -25,21 +38,127 @@ pub(super) struct ShortcutContext<'a> {
     pub rt: &'a Arc<Runtime>,
 }
 
-pub(super) fn handle_shortcut(shortcut: Shortcut, ctx: ShortcutContext<'_>) -> HandleResult {
+/// Drop the last user message and everything after it, in both the display and the API
+/// context. Returns the user text. No-op while a request or popup is active.
+fn rewind_last_turn(ctx: &mut ShortcutContext<'_>) -> Option<String> {
+    if ctx.pending_chat.is_some() || ctx.app.popup_open() {
+        return None;
+    }
+    // A failed prompt never reached the API messages: there is nothing to drop there.
+    let failed = matches!(ctx.app.messages.last(), Some(ChatMessage::Error(_)));
+    let text = ctx.app.truncate_from_last_user()?;
+    if !failed
+        && let Some(msgs) = ctx.api_messages.as_mut()
+        && let Some((idx, _)) = message::last_user_message(msgs)
+    {
+        msgs.truncate(idx);
+    }
+    Some(text)
+}
+
+/// Fork a new conversation from the zoomed, hovered, or scrolled-to message, leaving the
+/// current one saved as is. Branching from an assistant reply keeps it; branching from a
+/// prompt keeps what came before and reloads the prompt into the input to send it differently.
+fn branch_from_message(ctx: &mut ShortcutContext<'_>) {
+    if ctx.pending_chat.is_some() || ctx.app.popup_open() {
+        return;
+    }
+    if let Some(msg_idx) = ctx
+        .app
+        .zoom
+        .map(|z| z.msg_idx)
+        .or(ctx.app.hovered_message_idx)
+        .or_else(|| selection::message_idx_at_scroll_line(ctx.app))
+    {
+        branch_at(ctx, msg_idx);
+    }
+}
+
+/// Fork a new conversation from message `msg_idx` (see `branch_from_message`).
+pub(super) fn branch_at(ctx: &mut ShortcutContext<'_>, msg_idx: usize) {
+    let (end, prompt) = match ctx.app.messages.get(msg_idx) {
+        Some(ChatMessage::User(text)) => (msg_idx, Some(text.clone())),
+        Some(_) => (msg_idx + 1, None),
+        None => return,
+    };
+    chat_result::save_conversation_if_dirty(ctx.app, ctx.api_messages, ctx.config.as_ref());
+
+    let kept = App::messages_to_persist_format(
+        &ctx.app.messages[..end],
+        &ctx.app.message_timestamps,
+        &ctx.app.message_usage,
+        &ctx.app.pinned_messages,
+        &ctx.app.message_reactions,
+    );
+    let to_save = App::messages_to_persist_format(
+        &ctx.app.messages,
+        &ctx.app.message_timestamps,
+        &ctx.app.message_usage,
+        &ctx.app.pinned_messages,
+        &ctx.app.message_reactions,
+    );
+    let title = first_message_preview(&to_save, constants::TITLE_PREVIEW_MAX_LEN);
+    let branch_id = if kept.is_empty() {
+        None
+    } else {
+        match history::branch_conversation(
+            &kept,
+            kept.len(),
+            &title,
+            &ctx.app.conversation_settings(),
+            ctx.config.as_ref(),
+        ) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                chat_result::report_save_error(ctx.app, &e);
+                return;
+            }
+        }
+    };
+
+    ctx.app.new_conversation();
+    *ctx.api_messages = None;
+    if let Some(id) = branch_id {
+        ctx.app.set_messages_from_api(&kept, None);
+        ctx.app.set_conversation_id(Some(id));
+        // Saved on purpose: no near-duplicate prompt for the branch.
+        ctx.app.duplicate_checked = true;
+        let api_only = history::api_messages_from_persisted(&kept);
+        ctx.app.token_usage = Some(TokenUsage::estimated_from_messages(&api_only));
+        *ctx.api_messages = Some(api_only);
+        ctx.app.push_tool_log(format!(
+            "Branched from \"{}\" (the original stays in history, Alt+H)",
+            title
+        ));
+    }
+    if let Some(prompt) = prompt {
+        ctx.app.input_cursor = prompt.len();
+        ctx.app.input = prompt;
+    }
+    ctx.app.scroll = ScrollPosition::Bottom;
+}
+
+pub(super) fn handle_shortcut(shortcut: Shortcut, mut ctx: ShortcutContext<'_>) -> HandleResult {
     match shortcut {
         Shortcut::History => {
             if ctx.app.is_dirty() {
-                let to_save =
-                    App::messages_to_persist_format(&ctx.app.messages, &ctx.app.message_timestamps);
+                let to_save = App::messages_to_persist_format(
+                    &ctx.app.messages,
+                    &ctx.app.message_timestamps,
+                    &ctx.app.message_usage,
+                    &ctx.app.pinned_messages,
+                    &ctx.app.message_reactions,
+                );
                 if !to_save.is_empty() {
                     let title = first_message_preview(&to_save, constants::TITLE_PREVIEW_MAX_LEN);
                     if let Ok(id) = history::save_conversation(
                         ctx.app.conversation_id(),
                         &title,
                         &to_save,
+                        &ctx.app.conversation_settings(),
                         ctx.config.as_ref(),
                     ) {
-                        ctx.app.set_conversation_id(Some(id));
+                        chat_result::conversation_saved(ctx.app, id);
                         ctx.app.clear_dirty();
                     }
                 }
@@ -53,14 +172,20 @@ pub(super) fn handle_shortcut(shortcut: Shortcut, ctx: ShortcutContext<'_>) -> H
             *ctx.pending_chat = None;
             ctx.app.is_streaming = false;
             if ctx.app.is_dirty() {
-                let to_save =
-                    App::messages_to_persist_format(&ctx.app.messages, &ctx.app.message_timestamps);
+                let to_save = App::messages_to_persist_format(
+                    &ctx.app.messages,
+                    &ctx.app.message_timestamps,
+                    &ctx.app.message_usage,
+                    &ctx.app.pinned_messages,
+                    &ctx.app.message_reactions,
+                );
                 if !to_save.is_empty() {
                     let title = first_message_preview(&to_save, constants::TITLE_PREVIEW_MAX_LEN);
                     let _ = history::save_conversation(
                         ctx.app.conversation_id(),
                         &title,
                         &to_save,
+                        &ctx.app.conversation_settings(),
                         ctx.config.as_ref(),
                     );
                 }
@@ -76,6 +201,143 @@ pub(super) fn handle_shortcut(shortcut: Shortcut, ctx: ShortcutContext<'_>) -> H
                 ctx.rt,
             );
         }
+        Shortcut::EditLastMessage => {
+            if let Some(text) = rewind_last_turn(&mut ctx) {
+                ctx.app.input_cursor = text.len();
+                ctx.app.input = text;
+                ctx.app.scroll = ScrollPosition::Bottom;
+            }
+        }
+        Shortcut::Regenerate => {
+            if ctx.pending_chat.is_none()
+                && input::spend_allows_request(ctx.app, ctx.config)
+                && let Some(text) = rewind_last_turn(&mut ctx)
+            {
+                let mode = ctx
+                    .app
+                    .pending_command_mode
+                    .take()
+                    .unwrap_or_else(|| SUGGESTIONS[ctx.app.selected_suggestion].to_string());
+                ctx.app.turn_model = ctx.app.pending_command_model.take();
+                ctx.app.turn_output_schema = ctx.app.pending_output_schema.take();
+                input::send_prompt(
+                    ctx.app,
+                    ctx.config,
+                    ctx.pending_chat,
+                    ctx.api_messages,
+                    ctx.rt,
+                    text,
+                    Vec::new(),
+                    mode,
+                );
+            }
+        }
+        Shortcut::ToggleToolResults => ctx.app.toggle_all_tool_results(),
+        Shortcut::ZoomMessage => {
+            if ctx.app.zoom.is_some() {
+                ctx.app.unzoom();
+            } else if !ctx.app.popup_open()
+                && let Some(msg_idx) = ctx
+                    .app
+                    .hovered_message_idx
+                    .or_else(|| selection::message_idx_at_scroll_line(ctx.app))
+            {
+                ctx.app.zoom_message(msg_idx);
+            }
+        }
+        Shortcut::BranchConversation => branch_from_message(&mut ctx),
+        Shortcut::PinMessage => {
+            if !ctx.app.popup_open()
+                && let Some(msg_idx) = ctx
+                    .app
+                    .zoom
+                    .map(|z| z.msg_idx)
+                    .or(ctx.app.hovered_message_idx)
+                    .or_else(|| selection::message_idx_at_scroll_line(ctx.app))
+            {
+                ctx.app.toggle_message_pin(msg_idx);
+            }
+        }
+        Shortcut::SelectMessages => {
+            if ctx.app.message_selection.is_some() {
+                ctx.app.message_selection = None;
+            } else if !ctx.app.popup_open() {
+                let from = ctx
+                    .app
+                    .zoom
+                    .map(|z| z.msg_idx)
+                    .or(ctx.app.hovered_message_idx)
+                    .or_else(|| selection::message_idx_at_scroll_line(ctx.app));
+                ctx.app.start_message_selection(from);
+            }
+        }
+        Shortcut::ReactToMessage => {
+            if !ctx.app.popup_open()
+                && let Some(msg_idx) = ctx
+                    .app
+                    .zoom
+                    .map(|z| z.msg_idx)
+                    .or(ctx.app.hovered_message_idx)
+                    .or_else(|| selection::message_idx_at_scroll_line(ctx.app))
+            {
+                ctx.app.cycle_message_reaction(msg_idx);
+            }
+        }
+        Shortcut::SplitPane => {
+            if !ctx.app.popup_open() {
+                ctx.app.split_pane = !ctx.app.split_pane;
+            }
+        }
+        Shortcut::ComposeInEditor => {
+            if !ctx.app.popup_open() {
+                ctx.app.compose_in_editor();
+            }
+        }
+        Shortcut::CodeWrap => {
+            if !ctx.app.popup_open() {
+                ctx.app.toggle_code_wrap();
+            }
+        }
+        Shortcut::ExplainError => {
+            if ctx.pending_chat.is_none() && !ctx.app.popup_open() {
+                let target = ctx
+                    .app
+                    .zoom
+                    .map(|z| z.msg_idx)
+                    .or(ctx.app.hovered_message_idx);
+                let api_messages = ctx.api_messages.as_deref().unwrap_or_default();
+                ctx.app.explain_error(target, api_messages);
+            }
+        }
+        Shortcut::FileTree => {
+            if ctx.app.file_tree.is_some() {
+                ctx.app.file_tree = None;
+            } else if !ctx.app.popup_open() {
+                ctx.app.open_file_tree();
+            }
+        }
+        Shortcut::CommandPalette => {
+            if ctx.app.command_palette.is_some() {
+                ctx.app.command_palette = None;
+            } else if !ctx.app.popup_open() {
+                ctx.app.open_command_palette();
+            }
+        }
+        // Tabs are switched by the main loop, which holds them.
+        Shortcut::NewTab => return HandleResult::Tab(TabAction::New),
+        Shortcut::NextTab => return HandleResult::Tab(TabAction::Next),
+        Shortcut::PreviousTab => return HandleResult::Tab(TabAction::Previous),
+        Shortcut::SelectTab(index) => return HandleResult::Tab(TabAction::Select(index)),
+        Shortcut::CloseTab => return HandleResult::Tab(TabAction::Close),
+        Shortcut::StatsScreen => {
+            ctx.app.stats_screen = match ctx.app.stats_screen {
+                Some(_) => None,
+                None => Some(stats::report(STATS_SCREEN_DAYS)),
+            };
+        }
+        Shortcut::DebugOverlay => {
+            ctx.app.debug_overlay = !ctx.app.debug_overlay;
+        }
         Shortcut::Quit => {
             return HandleResult::Break;
         }
@@ -0,0 +1,17 @@
+//! Handler for the spend cap popup: continue past the reached cap or keep the prompt.
+
+use crossterm::event::KeyCode;
+
+use super::HandleResult;
+use crate::tui::app::App;
+
+pub(super) fn handle_spend_cap_popup(key_code: KeyCode, app: &mut App) -> HandleResult {
+    match key_code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => app.confirm_spend_cap(),
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.decline_spend_cap()
+        }
+        _ => {}
+    }
+    HandleResult::Continue
+}
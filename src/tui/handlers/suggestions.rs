@@ -0,0 +1,57 @@
+//! Handler for the review of suggested edits (/suggest): accept, reject, or rewrite each
+//! hunk, then apply the accepted ones.
+
+use crossterm::event::KeyCode;
+use serde_json::Value;
+
+use crate::core::config::Config;
+use crate::core::suggest::Decision;
+
+use super::super::chat_result;
+use super::HandleResult;
+use super::diff_viewer::scroll;
+use crate::tui::app::{App, EditorRequest};
+
+pub(super) fn handle_suggestion_review(
+    key_code: KeyCode,
+    app: &mut App,
+    api_messages: &mut Option<Vec<Value>>,
+    config: &Config,
+) -> HandleResult {
+    let Some(state) = app.suggestion_review.as_mut() else {
+        return HandleResult::Continue;
+    };
+    let (decision, rest) = match key_code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => (Decision::Accept, false),
+        KeyCode::Char('n') | KeyCode::Char('N') => (Decision::Reject, false),
+        KeyCode::Char('a') | KeyCode::Char('A') => (Decision::Accept, true),
+        KeyCode::Esc => (Decision::Reject, true),
+        KeyCode::Char('e') | KeyCode::Char('E') => {
+            if let Some(text) = state.current_text() {
+                app.editor_request = Some(EditorRequest::Hunk(text));
+            }
+            return HandleResult::Continue;
+        }
+        _ => {
+            scroll(&mut state.view, key_code);
+            return HandleResult::Continue;
+        }
+    };
+    decide(app, api_messages, config, decision, rest);
+    HandleResult::Continue
+}
+
+/// Record `decision` (for every hunk left when `rest` is set); once the review is over,
+/// save the conversation with its outcome.
+pub(crate) fn decide(
+    app: &mut App,
+    api_messages: &mut Option<Vec<Value>>,
+    config: &Config,
+    decision: Decision,
+    rest: bool,
+) {
+    app.decide_suggestion(decision, rest, api_messages);
+    if app.suggestion_review.is_none() {
+        chat_result::save_conversation_if_dirty(app, api_messages, config);
+    }
+}
@@ -0,0 +1,408 @@
+//! Links in assistant replies: URLs and workspace file paths are found in the reply text,
+//! located in the rendered history (across wrapped lines), and made clickable. Clicking one
+//! opens it; where the terminal supports OSC 8 hyperlinks, the link cells are also written
+//! again after each frame wrapped in the escape sequence, so the terminal knows the target.
+//!
+//! `MY_OPEN_CLAUDE_HYPERLINKS=0` turns the escape sequences off, `1` forces them.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use ratatui::backend::Backend;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use unicode_width::UnicodeWidthStr;
+
+use super::app::TextLine;
+
+/// Most links looked for in one reply.
+const MAX_LINKS_PER_MESSAGE: usize = 200;
+
+/// Where a link points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LinkTarget {
+    Url(String),
+    /// A file of the workspace (absolute path).
+    File(PathBuf),
+}
+
+impl LinkTarget {
+    /// URI written in the OSC 8 sequence.
+    fn uri(&self) -> String {
+        match self {
+            LinkTarget::Url(url) => url.clone(),
+            LinkTarget::File(path) => format!("file://{}", path.display()),
+        }
+    }
+
+    /// Open the URL in the browser, or the file with its default application.
+    pub(crate) fn open(&self) -> Result<(), opener::OpenError> {
+        match self {
+            LinkTarget::Url(url) => opener::open_browser(url),
+            LinkTarget::File(path) => opener::open(path),
+        }
+    }
+}
+
+/// A link as written in a reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Link {
+    /// Text of the link in the reply (the URL, or the path as written).
+    pub text: String,
+    pub target: LinkTarget,
+}
+
+/// A link on screen: the cells of one rendered line it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LinkRect {
+    pub rect: Rect,
+    /// Text drawn in the rect, to check it is still on screen (not under a popup).
+    pub text: String,
+    pub target: LinkTarget,
+}
+
+/// Links of the replies, found again only when a reply changes.
+#[derive(Debug, Default)]
+pub(crate) struct LinkCache {
+    by_message: HashMap<usize, (u64, Vec<Link>)>,
+}
+
+impl LinkCache {
+    /// Links of message `msg_idx` with `content`; file paths resolve against `root`.
+    pub(crate) fn links(&mut self, msg_idx: usize, content: &str, root: &Path) -> &[Link] {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+        let entry = self
+            .by_message
+            .entry(msg_idx)
+            .or_insert_with(|| (hash, find_links(content, root)));
+        if entry.0 != hash {
+            *entry = (hash, find_links(content, root));
+        }
+        &entry.1
+    }
+}
+
+/// True when the terminal is known to support OSC 8 hyperlinks (see the module docs for
+/// the override).
+pub(crate) fn supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        detect(
+            |name| std::env::var(name).ok(),
+            std::env::var("MY_OPEN_CLAUDE_HYPERLINKS").ok().as_deref(),
+        )
+    })
+}
+
+fn detect(var: impl Fn(&str) -> Option<String>, setting: Option<&str>) -> bool {
+    match setting.map(str::trim) {
+        Some("0") => return false,
+        Some("1") => return true,
+        Some(s) if s.eq_ignore_ascii_case("false") => return false,
+        Some(s) if s.eq_ignore_ascii_case("true") => return true,
+        _ => {}
+    }
+    // Inside tmux or screen, escape sequences only pass through with extra configuration.
+    if var("TMUX").is_some() || var("STY").is_some() {
+        return false;
+    }
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+    matches!(
+        program.as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper" | "Tabby" | "rio"
+    ) || ["KITTY_WINDOW_ID", "WT_SESSION", "KONSOLE_VERSION"]
+        .iter()
+        .any(|name| var(name).is_some())
+        || var("VTE_VERSION")
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|v| v >= 5000)
+        || ["xterm-kitty", "foot", "alacritty", "xterm-ghostty"]
+            .iter()
+            .any(|prefix| term.starts_with(prefix))
+}
+
+/// Characters a URL may contain (besides ASCII alphanumerics).
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c)
+}
+
+/// Characters of a file path written in a reply.
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || "-._/~+@".contains(c)
+}
+
+/// URLs (http, https) and paths of existing workspace files in `content`, once each, in
+/// order of appearance.
+pub(crate) fn find_links(content: &str, root: &Path) -> Vec<Link> {
+    let mut links: Vec<Link> = Vec::new();
+    let push = |links: &mut Vec<Link>, link: Link| {
+        if links.len() < MAX_LINKS_PER_MESSAGE && !links.iter().any(|l| l.text == link.text) {
+            links.push(link);
+        }
+    };
+    let mut rest = content;
+    while let Some(start) = rest.find("http") {
+        let candidate = &rest[start..];
+        let boundary = rest[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_ascii_alphanumeric());
+        if boundary
+            && (candidate.starts_with("https://") || candidate.starts_with("http://"))
+            && let Some(url) = trim_url(candidate)
+        {
+            push(
+                &mut links,
+                Link {
+                    text: url.to_string(),
+                    target: LinkTarget::Url(url.to_string()),
+                },
+            );
+            rest = &candidate[url.len()..];
+        } else {
+            rest = &candidate[4..];
+        }
+    }
+    for token in content.split(|c: char| !is_path_char(c)) {
+        let token = token.trim_end_matches('.');
+        if token.is_empty()
+            || token.starts_with("//")
+            || token.contains("..")
+            || !(token.contains('/') || token.contains('.'))
+            || links.iter().any(|l| l.text.contains(token))
+        {
+            continue;
+        }
+        let path = root.join(token);
+        // Only files inside the workspace (a path like /etc/hosts stays text).
+        if path.starts_with(root) && path.is_file() {
+            push(
+                &mut links,
+                Link {
+                    text: token.to_string(),
+                    target: LinkTarget::File(path),
+                },
+            );
+        }
+    }
+    links
+}
+
+/// The URL at the start of `s`, without the punctuation ending a sentence around it.
+fn trim_url(s: &str) -> Option<&str> {
+    let end = s.find(|c: char| !is_url_char(c)).unwrap_or(s.len());
+    let mut url = &s[..end];
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+        // A closing bracket ends the URL unless the URL opened it (Wikipedia-style links).
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']')) => {
+                let open = if close == ')' { '(' } else { '[' };
+                if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    (url.len() > "https://".len()).then_some(url)
+}
+
+/// Where `links` appear in the rendered lines of one message: (line, start_col, end_col)
+/// ranges in chars, one per rendered line a link covers, with the link. Wrapped lines
+/// (`TextLine::continued`) are joined, so a link split by the wrap is found whole.
+pub(crate) fn locate<'a>(
+    rendered: &[String],
+    text_lines: &[&TextLine],
+    links: &'a [Link],
+) -> Vec<(usize, usize, usize, &'a Link)> {
+    let mut found = Vec::new();
+    let mut chars: Vec<char> = Vec::new();
+    let mut positions: Vec<(usize, usize)> = Vec::new();
+    let mut flush = |chars: &mut Vec<char>, positions: &mut Vec<(usize, usize)>| {
+        for link in links {
+            let needle: Vec<char> = link.text.chars().collect();
+            let mut i = 0;
+            while i + needle.len() <= chars.len() {
+                let end = i + needle.len();
+                let bounded = (i == 0 || !is_path_char(chars[i - 1]))
+                    && (end == chars.len() || !is_path_char(chars[end]) || chars[end] == '.');
+                if bounded && chars[i..end] == needle[..] {
+                    let mut ranges: Vec<(usize, usize, usize)> = Vec::new();
+                    for &(line, col) in &positions[i..end] {
+                        match ranges.last_mut() {
+                            Some(r) if r.0 == line => r.2 = col + 1,
+                            _ => ranges.push((line, col, col + 1)),
+                        }
+                    }
+                    found.extend(ranges.into_iter().map(|(l, s, e)| (l, s, e, link)));
+                    i = end;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        chars.clear();
+        positions.clear();
+    };
+    for text_line in text_lines {
+        let Some(rendered_line) = rendered.get(text_line.line) else {
+            continue;
+        };
+        if !text_line.continued {
+            flush(&mut chars, &mut positions);
+        }
+        let text: Vec<char> = rendered_line.chars().skip(text_line.col).collect();
+        let len = text.len() - text.iter().rev().take_while(|c| c.is_whitespace()).count();
+        for (offset, c) in text[..len].iter().enumerate() {
+            chars.push(*c);
+            positions.push((text_line.line, text_line.col + offset));
+        }
+    }
+    flush(&mut chars, &mut positions);
+    found
+}
+
+/// Screen rect of chars `start..end` of `line`, drawn from column `x` of row `y`.
+pub(crate) fn link_rect(line: &str, start: usize, end: usize, x: u16, y: u16) -> (Rect, String) {
+    let before: String = line.chars().take(start).collect();
+    let text: String = line.chars().skip(start).take(end - start).collect();
+    let rect = Rect::new(
+        x.saturating_add(before.width() as u16),
+        y,
+        text.width() as u16,
+        1,
+    );
+    (rect, text)
+}
+
+/// Write the cells of `links` again wrapped in OSC 8 hyperlink sequences. Links no longer
+/// drawn as they were (e.g. under a popup) are skipped. The cursor is saved and restored.
+pub(crate) fn write_osc8<B: Backend + Write>(
+    backend: &mut B,
+    buffer: &Buffer,
+    links: &[LinkRect],
+) -> io::Result<()> {
+    let area = buffer.area;
+    let mut wrote = false;
+    for link in links {
+        let rect = link.rect.intersection(area);
+        if rect.is_empty() {
+            continue;
+        }
+        let cells: Vec<(u16, u16, &Cell)> = (rect.x..rect.right())
+            .map(|x| (x, rect.y, &buffer[(x, rect.y)]))
+            .collect();
+        let drawn: String = cells.iter().map(|(_, _, cell)| cell.symbol()).collect();
+        if drawn.trim_end() != link.text.trim_end() {
+            continue;
+        }
+        if !wrote {
+            // Save the cursor (DECSC): it stays where the frame left it.
+            backend.write_all(b"\x1b7")?;
+            wrote = true;
+        }
+        write!(backend, "\x1b]8;;{}\x1b\\", link.target.uri())?;
+        backend
+            .draw(cells.into_iter())
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        backend.write_all(b"\x1b]8;;\x1b\\")?;
+    }
+    if wrote {
+        backend.write_all(b"\x1b8")?;
+        Write::flush(backend)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_links_returns_urls_and_existing_workspace_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        let content = "See https://example.com/docs/(v2)). Edit `src/main.rs`, not src/missing.rs \
+                       or /etc/hosts (details: http://x.io/a?b=1, https://example.com/docs/(v2)).";
+        let links = find_links(content, dir.path());
+        let texts: Vec<&str> = links.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "https://example.com/docs/(v2)",
+                "http://x.io/a?b=1",
+                "src/main.rs"
+            ]
+        );
+        assert_eq!(
+            links[2].target,
+            LinkTarget::File(dir.path().join("src/main.rs"))
+        );
+    }
+
+    #[test]
+    fn locate_finds_links_split_by_the_wrap() {
+        let rendered = vec![
+            "│ Docs: https://example.com/a/".to_string(),
+            "│ very/long/path here".to_string(),
+        ];
+        let lines = [
+            TextLine {
+                line: 0,
+                col: 2,
+                continued: false,
+            },
+            TextLine {
+                line: 1,
+                col: 2,
+                continued: true,
+            },
+        ];
+        let links = vec![Link {
+            text: "https://example.com/a/very/long/path".to_string(),
+            target: LinkTarget::Url("https://example.com/a/very/long/path".to_string()),
+        }];
+        let refs: Vec<&TextLine> = lines.iter().collect();
+        let found: Vec<(usize, usize, usize)> = locate(&rendered, &refs, &links)
+            .into_iter()
+            .map(|(line, start, end, _)| (line, start, end))
+            .collect();
+        assert_eq!(found, vec![(0, 8, 30), (1, 2, 16)]);
+    }
+
+    #[test]
+    fn detect_uses_the_setting_then_the_terminal() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert!(detect(env(&[("TERM_PROGRAM", "iTerm.app")]), None));
+        assert!(detect(env(&[("VTE_VERSION", "6800")]), None));
+        assert!(!detect(env(&[("TERM_PROGRAM", "Apple_Terminal")]), None));
+        assert!(!detect(
+            env(&[("TERM_PROGRAM", "WezTerm"), ("TMUX", "/tmp/tmux")]),
+            None
+        ));
+        assert!(!detect(env(&[("TERM_PROGRAM", "WezTerm")]), Some("0")));
+        assert!(detect(env(&[]), Some("true")));
+    }
+}
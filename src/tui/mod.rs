@@ -5,8 +5,12 @@ mod chat_result;
 mod constants;
 mod draw;
 mod handlers;
+mod hyperlinks;
+mod paste;
+mod preview;
+pub mod replay;
 mod shortcuts;
-mod syntax;
+mod tabs;
 mod text;
 
 #[allow(unused_imports)]
@@ -15,22 +19,33 @@ pub use app::{App, ChatMessage, ConfirmPopup, HistorySelectorState, ModelSelecto
 use crossterm::event::{self, Event};
 use crossterm::execute;
 use std::io::{self};
+use std::path::Path;
+use std::process::Command;
 use std::sync::Arc;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use serde_json::Value;
 use tokio::runtime::Runtime;
 
-use crate::core::config::Config;
+use crate::core::config::{self, Config, Provider};
 use crate::core::credits;
+use crate::core::llm;
 use crate::core::models::{self};
-use crate::core::workspace::Workspace;
+use crate::core::profiles;
+use crate::core::remote;
+use crate::core::share::SharedConversation;
+use crate::core::suggest::Decision;
+use crate::core::workspace::{ContextWatcher, Workspace};
+use crate::core::{crash, history, index, network, permissions, redact};
 
-use handlers::{HandleResult, PendingChat, set_cursor_shape};
+use handlers::{HandleResult, set_cursor_shape};
 
 const CREDITS_REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60); // 30 minutes
+/// Time away (terminal unfocused) after which regaining focus refreshes the credits.
+const FOCUS_AWAY_REFRESH: Duration = Duration::from_secs(10 * 60);
+/// Pause before the next queued prompt when a request ends rate-limited (after retries).
+const QUEUE_RATE_LIMIT_HOLD: Duration = Duration::from_secs(30);
 
 use draw::draw;
 
@@ -51,6 +66,227 @@ fn spawn_credits_fetch(
     rx
 }
 
+/// Upload a `/share` gist in background. Returns receiver for the gist URL or error string.
+fn spawn_gist_upload(
+    shared: SharedConversation,
+    token: String,
+    rt: &Arc<Runtime>,
+) -> mpsc::Receiver<Result<String, String>> {
+    let (tx, rx) = mpsc::channel();
+    let rt_clone = Arc::clone(rt);
+    thread::spawn(move || {
+        let result = rt_clone
+            .block_on(shared.upload_gist(&token))
+            .map_err(|e| e.to_string());
+        let _ = tx.send(result);
+    });
+    rx
+}
+
+/// A `/compact` request and the compaction of its messages.
+type CompactionResult = (app::CompactRequest, Result<llm::Compaction, llm::ChatError>);
+
+/// Summarize a `/compact` request in background. Returns receiver for the request and its
+/// compaction.
+fn spawn_compaction(
+    request: app::CompactRequest,
+    config: Arc<Config>,
+    rt: &Arc<Runtime>,
+) -> mpsc::Receiver<CompactionResult> {
+    let (tx, rx) = mpsc::channel();
+    let rt_clone = Arc::clone(rt);
+    thread::spawn(move || {
+        let result = rt_clone.block_on(llm::compact(
+            config.as_ref(),
+            &request.model,
+            &request.messages,
+            request.split,
+        ));
+        let _ = tx.send((request, result));
+    });
+    rx
+}
+
+/// Switch to the profile `name` (None: environment settings only): reload the config and
+/// reconfigure everything that depends on it; `--dry-run` carries over from `current`. On
+/// error the previous profile stays active.
+fn switch_profile(app: &mut App, name: Option<String>, current: &Config) -> Option<Arc<Config>> {
+    let previous = profiles::active().map(|p| p.name);
+    let loaded = profiles::activate(name.as_deref())
+        .map_err(|e| e.to_string())
+        .and_then(|()| config::load().map_err(|e| e.to_string()));
+    let mut config = match loaded {
+        Ok(config) => config,
+        Err(e) => {
+            if let Err(e) = profiles::activate(previous.as_deref()) {
+                log::warn!("Cannot restore the previous profile: {}", e);
+            }
+            app.push_tool_log(format!("Profile switch failed: {}", e));
+            return None;
+        }
+    };
+    config.dry_run = current.dry_run;
+    let root = app.workspace.root.clone();
+    config::apply_workspace_model(&mut config, &root);
+    config::apply_workspace_tool_timeouts(&mut config, &root);
+    crash::set_config(&config);
+    index::configure(&config);
+    models::configure(&config);
+    network::configure(&config);
+    permissions::configure(&config, &root);
+    redact::configure(&config);
+    if let Err(e) = text::set_theme(config.theme.as_deref()) {
+        app.push_tool_log(e);
+    }
+
+    app.current_model_id = config.model_id.clone();
+    app.model_name = models::resolve_model_display_name(&config.model_id);
+    app.model_source = config.model_source.clone();
+    app.context_length = models::resolve_context_length(&config.model_id);
+    app.token_usage = None;
+    app.local_models = config.provider == Provider::Ollama;
+    app.credit_data = None;
+    app.credits_last_fetched_at = None;
+    app.credits_fetch_error = None;
+    app.push_tool_log(match &config.profile {
+        Some(name) => format!(
+            "Switched to profile '{}': {} with {}.",
+            name, config.provider, config.model_id
+        ),
+        None => format!(
+            "Switched to the environment settings: {} with {}.",
+            config.provider, config.model_id
+        ),
+    });
+    Some(Arc::new(config))
+}
+
+/// Editor of `$VISUAL` or `$EDITOR` (arguments allowed, e.g. `code -w`); `vi` when unset.
+fn editor_command() -> (String, Vec<String>) {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_default();
+    let mut parts = editor.split_whitespace().map(String::from);
+    let program = parts.next().unwrap_or_else(|| "vi".to_string());
+    (program, parts.collect())
+}
+
+/// Open `path` in the editor with the TUI suspended, and restore it once the editor exits.
+fn open_in_editor(
+    terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<io::Stdout>>,
+    path: &Path,
+) -> io::Result<()> {
+    use crossterm::terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    };
+    let (program, args) = editor_command();
+
+    let _ = execute!(io::stdout(), crossterm::event::PopKeyboardEnhancementFlags);
+    execute!(
+        io::stdout(),
+        crossterm::event::DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
+    disable_raw_mode()?;
+    let status = Command::new(&program).args(&args).arg(path).status();
+    enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        crossterm::event::EnableMouseCapture
+    )?;
+    let _ = execute!(
+        io::stdout(),
+        crossterm::event::PushKeyboardEnhancementFlags(
+            crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | crossterm::event::KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+        )
+    );
+    terminal.clear()?;
+
+    let status = status.map_err(|e| io::Error::other(format!("{}: {}", program, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
+
+/// Open `input` in the editor as a temporary Markdown file and return the text saved there.
+/// The file is removed afterwards; on an editor error the input is left as it was.
+fn compose_in_editor(
+    terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<io::Stdout>>,
+    input: &str,
+) -> io::Result<String> {
+    let path =
+        std::env::temp_dir().join(format!("my-open-claude-prompt-{}.md", std::process::id()));
+    std::fs::write(&path, input)?;
+    let result = open_in_editor(terminal, &path).and_then(|()| std::fs::read_to_string(&path));
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// App state of a new conversation with `config`'s model.
+fn new_app(config: &Config, workspace: Workspace) -> App {
+    let model_name = models::resolve_model_display_name(&config.model_id);
+    let mut app = App::new(
+        config.model_id.clone(),
+        model_name,
+        config.model_source.clone(),
+        workspace,
+        config.show_timestamps,
+        config.show_usage,
+    );
+    app.local_models = config.provider == Provider::Ollama;
+    app.tool_modes = llm::ToolModes::from_config(config);
+    app
+}
+
+/// Open, close, or switch tabs. Returns the index of the tab shown next.
+fn apply_tab_action(
+    tabs: &mut Vec<tabs::Tab>,
+    active: usize,
+    action: tabs::TabAction,
+    config: &Arc<Config>,
+) -> usize {
+    match action {
+        tabs::TabAction::New => {
+            let current = &tabs[active].app;
+            let mut app = new_app(config, current.workspace.clone());
+            app.credit_data = current.credit_data;
+            app.credits_last_fetched_at = current.credits_last_fetched_at;
+            app.credits_fetch_error = current.credits_fetch_error.clone();
+            app.unfocused_since = current.unfocused_since;
+            tabs.insert(active + 1, tabs::Tab::new(app));
+            active + 1
+        }
+        tabs::TabAction::Close => {
+            let only_tab = tabs.len() == 1;
+            let tab = &mut tabs[active];
+            if only_tab {
+                tab.app
+                    .push_tool_log("This is the only tab: Ctrl+C quits.".to_string());
+                return active;
+            }
+            if tab.pending_chat.is_some() {
+                tab.app.push_tool_log(
+                    "A turn is running in this tab: wait for it or press Esc before closing it."
+                        .to_string(),
+                );
+                return active;
+            }
+            chat_result::save_conversation_if_dirty(&mut tab.app, &tab.api_messages, config);
+            tabs.remove(active);
+            active.min(tabs.len() - 1)
+        }
+        action => tabs::target(action, active, tabs.len()).unwrap_or(active),
+    }
+}
+
 /// Guard that restores terminal state on drop (including on panic).
 struct TerminalGuard;
 
@@ -67,15 +303,22 @@ impl Drop for TerminalGuard {
             std::io::stdout(),
             crossterm::event::PopKeyboardEnhancementFlags
         );
+        let _ = execute!(std::io::stdout(), crossterm::event::DisableFocusChange);
         let _ = disable_raw_mode();
         let _ = execute!(std::io::stdout(), crossterm::event::DisableMouseCapture);
         set_cursor_shape(false); // restore default cursor
         let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+        // The panic hook printed this on the alternate screen; repeat it where it stays visible.
+        if std::thread::panicking()
+            && let Some(path) = crate::core::crash::last_bundle()
+        {
+            eprintln!("{}", crate::core::crash::notice(&path));
+        }
     }
 }
 
 /// Run the TUI loop. Uses a dedicated Tokio runtime for async chat calls.
-pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
+pub fn run(mut config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
     use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, enable_raw_mode};
     use ratatui::Terminal;
     use ratatui::backend::CrosstermBackend;
@@ -93,17 +336,11 @@ pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
         Runtime::new().map_err(|e| io::Error::other(format!("Failed to create runtime: {}", e)))?,
     );
 
-    let model_name = models::resolve_model_display_name(&config.model_id);
-    let mut app = App::new(
-        config.model_id.clone(),
-        model_name,
-        workspace,
-        config.show_timestamps,
-    );
-    let mut api_messages: Option<Vec<Value>> = None;
-    let mut pending_chat: Option<PendingChat> = None;
-    let mut pending_model_fetch: Option<mpsc::Receiver<Result<Vec<models::ModelInfo>, String>>> =
-        None;
+    if let Err(e) = text::set_theme(config.theme.as_deref()) {
+        log::warn!("{}", e);
+    }
+    let mut tabs = vec![tabs::Tab::new(new_app(&config, workspace))];
+    let mut active = 0;
 
     // Enable mouse events for credits click
     execute!(io::stdout(), crossterm::event::EnableMouseCapture)?;
@@ -117,21 +354,42 @@ pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
         )
     );
 
-    // Start credits fetch in background
-    let mut pending_credits_fetch = Some(spawn_credits_fetch(Arc::clone(&config), &rt));
+    // Focus events: refresh stale credits when coming back to the terminal.
+    let _ = execute!(io::stdout(), crossterm::event::EnableFocusChange);
+
+    // Start credits fetch in background (OpenRouter only), shared by every tab.
+    let mut pending_credits_fetch = (config.provider == Provider::OpenRouter)
+        .then(|| spawn_credits_fetch(Arc::clone(&config), &rt));
+    // AGENTS.md, project marker, and context file changes refresh the workspace context
+    // mid-session. A remote workspace is not watched (/reload-context re-reads it).
+    let mut context_watcher = if remote::is_active() {
+        None
+    } else {
+        ContextWatcher::new(&tabs[0].app.workspace)
+            .map_err(|e| log::warn!("Workspace context will not auto-reload: {}", e))
+            .ok()
+    };
 
     loop {
+        if history::take_lock_contention() {
+            tabs[active].app.history_busy_toast_until =
+                Some(Instant::now() + chat_result::HISTORY_BUSY_TOAST_DURATION);
+        }
+
         if let Some(ref credits_rx) = pending_credits_fetch
             && let Ok(result) = credits_rx.try_recv()
         {
-            match result {
-                Ok((total, used)) => {
-                    app.credit_data = Some((total, used));
-                    app.credits_last_fetched_at = Some(Instant::now());
-                    app.credits_fetch_error = None;
-                }
-                Err(e) => {
-                    app.credits_fetch_error = Some(e);
+            for tab in &mut tabs {
+                let app = &mut tab.app;
+                match &result {
+                    Ok((total, used)) => {
+                        app.credit_data = Some((*total, *used));
+                        app.credits_last_fetched_at = Some(Instant::now());
+                        app.credits_fetch_error = None;
+                    }
+                    Err(e) => {
+                        app.credits_fetch_error = Some(e.clone());
+                    }
                 }
             }
             pending_credits_fetch = None;
@@ -139,83 +397,137 @@ pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
 
         // Re-fetch credits every 30 minutes (only after first successful fetch)
         if pending_credits_fetch.is_none()
-            && app
+            && tabs[active]
+                .app
                 .credits_last_fetched_at
                 .is_some_and(|t| t.elapsed() >= CREDITS_REFRESH_INTERVAL)
         {
             pending_credits_fetch = Some(spawn_credits_fetch(Arc::clone(&config), &rt));
         }
 
-        if let Some(ref fetch_rx) = pending_model_fetch
-            && let Ok(result) = fetch_rx.try_recv()
+        // Background tabs keep streaming and running their turns.
+        for (i, tab) in tabs.iter_mut().enumerate() {
+            if tab.poll(&config, &rt) && i != active {
+                tab.unseen = true;
+            }
+        }
+
+        let tab = &mut tabs[active];
+        // Between turns: the turn in flight keeps the config it started with.
+        if tab.pending_chat.is_none()
+            && let Some(name) = tab.app.profile_request.take()
+            && let Some(switched) = switch_profile(&mut tab.app, name, &config)
         {
-            if let Some(ref mut selector) = app.model_selector {
-                match result {
-                    Ok(models) => {
-                        selector.models = models;
-                        selector.selected_index = 0;
-                        selector.fetch_error = None;
-                    }
-                    Err(e) => {
-                        selector.fetch_error = Some(e);
-                    }
-                }
+            config = switched;
+            let local_models = tab.app.local_models;
+            for other in &mut tabs {
+                other.app.local_models = local_models;
+                other.app.credit_data = None;
+                other.app.credits_last_fetched_at = None;
+                other.app.credits_fetch_error = None;
             }
-            pending_model_fetch = None;
+            pending_credits_fetch = (config.provider == Provider::OpenRouter)
+                .then(|| spawn_credits_fetch(Arc::clone(&config), &rt));
         }
 
-        if let Some(ref mut chat) = pending_chat {
-            while let Ok(msg) = chat.progress_rx.try_recv() {
-                app.remove_last_if_empty_assistant();
-                app.push_tool_log(msg);
+        let tab = &mut tabs[active];
+        match tab.app.editor_request.take() {
+            // Alt+F, `e`: the file opens in the editor, then the tree shows its new content.
+            Some(app::EditorRequest::File(path)) => {
+                if let Err(e) = open_in_editor(&mut terminal, &path) {
+                    tab.app
+                        .push_tool_log(format!("Cannot open the editor: {}", e));
+                }
+                if let Some(state) = tab.app.file_tree.as_mut() {
+                    state.preview = None;
+                    state.update_preview(&tab.app.workspace.root);
+                }
             }
-            while let Ok(chunk) = chat.stream_rx.try_recv() {
-                app.append_assistant_chunk(&chunk);
+            // Ctrl+G: the input is composed in the editor and read back.
+            Some(app::EditorRequest::Input) => {
+                match compose_in_editor(&mut terminal, &tab.app.input) {
+                    Ok(text) => tab.app.set_input_from_editor(&text),
+                    Err(e) => tab
+                        .app
+                        .push_tool_log(format!("Cannot open the editor: {}", e)),
+                }
             }
-            if let Ok(result) = chat.result_rx.try_recv() {
-                app.set_thinking(false);
-                app.is_streaming = false;
-                chat_result::handle_chat_result(
-                    &mut app,
-                    &mut api_messages,
-                    result,
-                    true,
+            // `e` in the review of suggested edits: the hunk is rewritten in the editor.
+            Some(app::EditorRequest::Hunk(text)) => match compose_in_editor(&mut terminal, &text) {
+                Ok(text) => handlers::decide_suggestion(
+                    &mut tab.app,
+                    &mut tab.api_messages,
                     config.as_ref(),
-                );
-                pending_chat = None;
+                    Decision::Edit(text),
+                    false,
+                ),
+                Err(e) => tab
+                    .app
+                    .push_tool_log(format!("Cannot open the editor: {}", e)),
+            },
+            None => {}
+        }
+
+        if let Some(watcher) = context_watcher.as_mut()
+            && watcher.poll()
+        {
+            for tab in &mut tabs {
+                tab.app
+                    .reload_workspace_context(&mut tab.api_messages, config.as_ref(), false);
             }
+            watcher.follow(&tabs[0].app.workspace);
         }
 
-        terminal.draw(|f| draw(f, &mut app, f.area()))?;
+        tabs[active].unseen = false;
+        let labels: Vec<app::TabLabel> = if tabs.len() > 1 {
+            tabs.iter()
+                .enumerate()
+                .map(|(i, tab)| tab.label(i == active))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let tab = &mut tabs[active];
+        tab.app.tab_labels = labels;
+        let frame = terminal.draw(|f| draw(f, &mut tab.app, f.area()))?;
+        if hyperlinks::supported() && !tab.app.link_rects.is_empty() {
+            let buffer = frame.buffer.clone();
+            hyperlinks::write_osc8(terminal.backend_mut(), &buffer, &tab.app.link_rects)?;
+        }
 
         if event::poll(std::time::Duration::from_millis(
             constants::EVENT_POLL_TIMEOUT_MS,
         ))? {
-            match event::read()? {
-                Event::Mouse(mouse) => {
-                    let _ = handlers::handle_mouse(mouse, &mut app);
+            let event = event::read()?;
+            // Focus changes concern every tab (desktop notifications of background turns).
+            if let Event::FocusLost | Event::FocusGained = event {
+                let focused = matches!(event, Event::FocusGained);
+                let mut refresh = false;
+                for tab in &mut tabs {
+                    refresh |= tab.app.focus_changed(focused, FOCUS_AWAY_REFRESH);
                 }
-                Event::Paste(pasted) => {
-                    // Insert pasted text at cursor when input has focus (no popup open).
-                    let input_focus = app.confirm_popup.is_none()
-                        && app.model_selector.is_none()
-                        && app.history_selector.is_none()
-                        && app.command_form_popup.is_none()
-                        && app.delete_command_popup.is_none();
-                    if input_focus {
-                        let cursor = app.input_cursor.min(app.input.len());
-                        let cursor_byte = app.input.floor_char_boundary(cursor);
-                        let before = app.input[..cursor_byte].to_string();
-                        let after = app.input[cursor_byte..].to_string();
-                        app.input = before + &pasted + &after;
-                        app.input_cursor = cursor_byte + pasted.len();
-                    }
+                if refresh
+                    && config.provider == Provider::OpenRouter
+                    && pending_credits_fetch.is_none()
+                {
+                    pending_credits_fetch = Some(spawn_credits_fetch(Arc::clone(&config), &rt));
+                }
+                continue;
+            }
+            let tab = &mut tabs[active];
+            let app = &mut tab.app;
+            match event {
+                Event::Mouse(mouse) => {
+                    let _ = handlers::handle_mouse(mouse, app);
                 }
+                // Insert pasted text at cursor when input has focus (no popup open);
+                // large blocks collapse into a chip expanded on send.
+                Event::Paste(pasted) if !app.popup_open() => app.insert_paste(&pasted),
                 Event::Key(key) => {
                     // When Esc would start Option+key (meta), drain: terminals (Ghostty, etc.) send
                     // Esc+key; the second byte may arrive with delay—loop with short polls.
                     let key_to_handle =
-                        if handlers::would_esc_start_meta_sequence(&key, &app, &pending_chat) {
+                        if handlers::would_esc_start_meta_sequence(&key, app, &tab.pending_chat) {
                             let step_ms = 25u64;
                             let mut elapsed = 0u64;
                             let mut next_key = None;
@@ -227,7 +539,7 @@ pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
                                             break;
                                         }
                                         Event::Mouse(m) => {
-                                            let _ = handlers::handle_mouse(m, &mut app);
+                                            let _ = handlers::handle_mouse(m, app);
                                             break;
                                         }
                                         _ => {}
@@ -248,21 +560,30 @@ pub fn run(config: Arc<Config>, workspace: Workspace) -> io::Result<()> {
                     let result = handlers::handle_key(
                         key_to_handle,
                         handlers::HandleKeyContext {
-                            app: &mut app,
+                            app,
                             config: &config,
-                            api_messages: &mut api_messages,
-                            pending_chat: &mut pending_chat,
-                            pending_model_fetch: &mut pending_model_fetch,
+                            api_messages: &mut tab.api_messages,
+                            pending_chat: &mut tab.pending_chat,
+                            pending_model_fetch: &mut tab.pending_model_fetch,
                             rt: &rt,
                         },
                     );
-                    if result == HandleResult::Break {
-                        chat_result::save_conversation_if_dirty(
-                            &mut app,
-                            &api_messages,
-                            config.as_ref(),
-                        );
-                        break;
+                    match result {
+                        HandleResult::Continue => {}
+                        HandleResult::Break => {
+                            for tab in &mut tabs {
+                                chat_result::save_conversation_if_dirty(
+                                    &mut tab.app,
+                                    &tab.api_messages,
+                                    config.as_ref(),
+                                );
+                            }
+                            tabs[active].app.save_ui_state();
+                            break;
+                        }
+                        HandleResult::Tab(action) => {
+                            active = apply_tab_action(&mut tabs, active, action, &config);
+                        }
                     }
                 }
                 _ => {}
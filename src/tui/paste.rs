@@ -0,0 +1,213 @@
+//! Pasted content in the input: large text blocks collapse into a placeholder chip
+//! (expanded on send), clipboard images become multimodal attachments, and files attached
+//! from the file tree (Alt+F) are sent as fenced blocks.
+
+use std::ops::Range;
+
+use base64::Engine;
+
+/// Pastes with at least this many lines are collapsed into a chip.
+pub const COLLAPSE_MIN_LINES: usize = 10;
+/// Pastes of at least this many bytes are collapsed into a chip.
+pub const COLLAPSE_MIN_BYTES: usize = 2_000;
+/// Largest encoded image (PNG bytes) accepted as an attachment.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Content held behind a placeholder in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachmentKind {
+    Text(String),
+    /// A workspace file attached from the file tree: its path and content.
+    File {
+        path: String,
+        content: String,
+    },
+    Image {
+        data_url: String,
+        width: usize,
+        height: usize,
+    },
+}
+
+/// A pasted block or image, shown in the input as its placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub id: usize,
+    pub kind: AttachmentKind,
+}
+
+impl Attachment {
+    /// Chip text inserted in the input, e.g. `[pasted 312 lines #1]`, `[file src/main.rs #2]`,
+    /// or `[image #3 800×600]`.
+    pub fn placeholder(&self) -> String {
+        match &self.kind {
+            AttachmentKind::Text(text) => {
+                format!("[pasted {} lines #{}]", text.lines().count(), self.id)
+            }
+            AttachmentKind::File { path, .. } => format!("[file {} #{}]", path, self.id),
+            AttachmentKind::Image { width, height, .. } => {
+                format!("[image #{} {}×{}]", self.id, width, height)
+            }
+        }
+    }
+}
+
+/// Error turning clipboard pixels into an attachment.
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("Failed to encode image: {0}")]
+    Encode(#[from] png::EncodingError),
+    #[error("Image too large to attach ({} KB, max {} KB)", .0 / 1024, MAX_IMAGE_BYTES / 1024)]
+    TooLarge(usize),
+}
+
+/// Normalize line endings (terminals often paste `\r` or `\r\n`).
+pub fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// True if the pasted text is large enough to show as a chip instead of inline.
+pub fn should_collapse(text: &str) -> bool {
+    text.len() >= COLLAPSE_MIN_BYTES || text.lines().count() >= COLLAPSE_MIN_LINES
+}
+
+/// Byte ranges of the attachment placeholders still present in `input`, in input order.
+pub fn placeholder_ranges<'a>(
+    input: &str,
+    attachments: &'a [Attachment],
+) -> Vec<(Range<usize>, &'a Attachment)> {
+    let mut ranges: Vec<_> = attachments
+        .iter()
+        .filter_map(|a| {
+            let placeholder = a.placeholder();
+            input
+                .find(&placeholder)
+                .map(|start| (start..start + placeholder.len(), a))
+        })
+        .collect();
+    ranges.sort_by_key(|(r, _)| r.start);
+    ranges
+}
+
+/// Prompt to send: text chips replaced by their content, file chips by the file in a
+/// fenced block under its path, image chips kept as references.
+/// Returns the text and the data URLs of the images still referenced in the input.
+pub fn expand(input: &str, attachments: &[Attachment]) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(input.len());
+    let mut images = Vec::new();
+    let mut last = 0;
+    for (range, attachment) in placeholder_ranges(input, attachments) {
+        if range.start < last {
+            continue;
+        }
+        out.push_str(&input[last..range.start]);
+        match &attachment.kind {
+            AttachmentKind::Text(text) => out.push_str(text),
+            AttachmentKind::File { path, content } => {
+                out.push_str(&format!(
+                    "`{}`:\n```\n{}{}```",
+                    path,
+                    content,
+                    if content.ends_with('\n') { "" } else { "\n" }
+                ));
+            }
+            AttachmentKind::Image { data_url, .. } => {
+                out.push_str(&input[range.clone()]);
+                images.push(data_url.clone());
+            }
+        }
+        last = range.end;
+    }
+    out.push_str(&input[last..]);
+    (out, images)
+}
+
+/// Encode RGBA8 pixels as a PNG data URL.
+pub fn png_data_url(width: usize, height: usize, rgba: &[u8]) -> Result<String, ImageError> {
+    let mut png_bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgba)?;
+        writer.finish()?;
+    }
+    if png_bytes.len() > MAX_IMAGE_BYTES {
+        return Err(ImageError::TooLarge(png_bytes.len()));
+    }
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&png_bytes)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(id: usize, s: &str) -> Attachment {
+        Attachment {
+            id,
+            kind: AttachmentKind::Text(s.to_string()),
+        }
+    }
+
+    #[test]
+    fn collapse_threshold() {
+        assert!(!should_collapse("one line"));
+        assert!(should_collapse(&"line\n".repeat(COLLAPSE_MIN_LINES)));
+        assert!(should_collapse(&"x".repeat(COLLAPSE_MIN_BYTES)));
+    }
+
+    #[test]
+    fn normalize_line_endings() {
+        assert_eq!(normalize("a\r\nb\rc"), "a\nb\nc");
+    }
+
+    #[test]
+    fn expand_replaces_text_chips_and_collects_images() {
+        let trace = text(1, "at foo\nat bar");
+        let image = Attachment {
+            id: 2,
+            kind: AttachmentKind::Image {
+                data_url: "data:image/png;base64,AA".to_string(),
+                width: 4,
+                height: 3,
+            },
+        };
+        assert_eq!(trace.placeholder(), "[pasted 2 lines #1]");
+        assert_eq!(image.placeholder(), "[image #2 4×3]");
+        let input = format!("why? {} see {}", trace.placeholder(), image.placeholder());
+        let (out, images) = expand(&input, &[image.clone(), trace]);
+        assert_eq!(out, "why? at foo\nat bar see [image #2 4×3]");
+        assert_eq!(images, vec!["data:image/png;base64,AA".to_string()]);
+    }
+
+    #[test]
+    fn expand_fences_attached_files() {
+        let file = Attachment {
+            id: 3,
+            kind: AttachmentKind::File {
+                path: "src/main.rs".to_string(),
+                content: "fn main() {}".to_string(),
+            },
+        };
+        assert_eq!(file.placeholder(), "[file src/main.rs #3]");
+        let (out, _) = expand("explain [file src/main.rs #3]", &[file]);
+        assert_eq!(out, "explain `src/main.rs`:\n```\nfn main() {}\n```");
+    }
+
+    #[test]
+    fn expand_skips_deleted_chips() {
+        let (out, images) = expand("edited [pasted 2 li", &[text(1, "a\nb")]);
+        assert_eq!(out, "edited [pasted 2 li");
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn png_data_url_encodes_pixels() {
+        let url = png_data_url(2, 2, &[255; 16]).unwrap();
+        assert!(url.starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+}
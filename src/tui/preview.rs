@@ -0,0 +1,91 @@
+//! Browser previews of Mermaid diagrams and display math: the block is written to a
+//! temporary HTML page that loads Mermaid or MathJax from a CDN, then opened.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::PathBuf;
+
+use super::text::PreviewKind;
+
+const MERMAID_SCRIPT: &str = "<script type=\"module\">\
+import mermaid from 'https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs';\
+mermaid.initialize({ startOnLoad: true });</script>";
+
+const MATHJAX_SCRIPT: &str =
+    "<script async src=\"https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-mml-chtml.js\"></script>";
+
+/// A previewable block of a message, clicked from its header line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Preview {
+    pub kind: PreviewKind,
+    pub source: String,
+}
+
+impl Preview {
+    /// Write the page to the temp directory and open it in the default browser.
+    /// The file name comes from the content, so opening a block again reuses its page.
+    pub(crate) fn open(&self) -> io::Result<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        let path = std::env::temp_dir().join(format!(
+            "my-open-claude-{}-{:016x}.html",
+            self.kind.label(),
+            hasher.finish()
+        ));
+        std::fs::write(&path, self.html())?;
+        opener::open(&path).map_err(io::Error::other)?;
+        Ok(path)
+    }
+
+    fn html(&self) -> String {
+        let source = escape_html(self.source.trim());
+        let (script, body) = match self.kind {
+            PreviewKind::Mermaid => (
+                MERMAID_SCRIPT,
+                format!("<pre class=\"mermaid\">\n{}\n</pre>", source),
+            ),
+            PreviewKind::Math => (MATHJAX_SCRIPT, format!("<div>\\[\n{}\n\\]</div>", source)),
+        };
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>my-open-claude · {}</title>\n{}\n</head>\n\
+             <body style=\"font-family: sans-serif; margin: 2em;\">\n{}\n</body>\n</html>\n",
+            self.kind.label(),
+            script,
+            body
+        )
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_escapes_the_source_for_the_renderer() {
+        let mermaid = Preview {
+            kind: PreviewKind::Mermaid,
+            source: "graph TD\n  A-->B<br>C\n".to_string(),
+        }
+        .html();
+        assert!(mermaid.contains("mermaid.esm.min.mjs"));
+        assert!(
+            mermaid.contains("<pre class=\"mermaid\">\ngraph TD\n  A--&gt;B&lt;br&gt;C\n</pre>")
+        );
+
+        let math = Preview {
+            kind: PreviewKind::Math,
+            source: "a < b \\& c".to_string(),
+        }
+        .html();
+        assert!(math.contains("tex-mml-chtml.js"));
+        assert!(math.contains("\\[\na &lt; b \\&amp; c\n\\]"));
+    }
+}
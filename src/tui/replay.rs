@@ -0,0 +1,434 @@
+//! Replay of a saved conversation (`my-open-claude replay <id>`): prompts are typed into the
+//! input, tool logs appear one by one and replies stream in, with realistic timing or one
+//! message per key press. Nothing is sent to the API.
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use serde_json::Value;
+
+use crate::core::config::ModelSource;
+use crate::core::llm::MessageUsage;
+use crate::core::models;
+use crate::core::workspace::Workspace;
+
+use super::app::{App, ChatMessage, ReplayStatus, ScrollPosition};
+use super::constants;
+use super::draw::draw;
+use super::handlers;
+
+/// Prompt typing speed (characters per second at speed 1).
+const TYPING_RATE: f64 = 40.0;
+/// Reply streaming speed (characters per second at speed 1).
+const STREAMING_RATE: f64 = 300.0;
+/// Long prompts and replies are sped up to finish within these durations.
+const MAX_TYPING: Duration = Duration::from_millis(2500);
+const MAX_STREAMING: Duration = Duration::from_secs(8);
+/// Pause before the next prompt is typed.
+const BEFORE_PROMPT: Duration = Duration::from_millis(1200);
+/// "Thinking" time between a sent prompt and its first tool log or reply chunk.
+const THINKING: Duration = Duration::from_millis(900);
+/// Gap between tool logs, and before a reply that follows them.
+const BETWEEN_STEPS: Duration = Duration::from_millis(500);
+
+/// How the conversation is played back.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayOptions {
+    /// Advance one message per key press instead of with timing.
+    pub step: bool,
+    /// Timing multiplier (2.0 plays twice as fast).
+    pub speed: f64,
+}
+
+/// One message of the conversation, revealed as the live session showed it.
+enum Beat {
+    Prompt {
+        text: String,
+        timestamp: Option<u64>,
+    },
+    ToolLog(String),
+    Reply {
+        text: String,
+        timestamp: Option<u64>,
+        usage: Option<MessageUsage>,
+    },
+    /// Shown at once: a divider left by /clear-context or /compact, or a tool result.
+    Static(ChatMessage),
+}
+
+impl Beat {
+    /// Characters per second while typing or streaming (None: shown at once).
+    fn rate(&self) -> Option<f64> {
+        match self {
+            Beat::Prompt { text, .. } => Some(reveal_rate(text, TYPING_RATE, MAX_TYPING)),
+            Beat::Reply { text, .. } => Some(reveal_rate(text, STREAMING_RATE, MAX_STREAMING)),
+            Beat::ToolLog(_) | Beat::Static(_) => None,
+        }
+    }
+}
+
+/// `base` characters per second, faster when `text` would take longer than `max`.
+fn reveal_rate(text: &str, base: f64, max: Duration) -> f64 {
+    let len = text.chars().count() as f64;
+    base.max(len / max.as_secs_f64())
+}
+
+enum Phase {
+    /// Waiting this long (at speed 1) before the next beat.
+    Delay(Duration),
+    /// Step mode: waiting for a key press before the next beat.
+    AwaitKey,
+    /// Typing or streaming a beat; `shown` counts characters, `revealed` those already drawn.
+    Reveal {
+        beat: Beat,
+        shown: f64,
+        revealed: usize,
+    },
+    Finished,
+}
+
+struct Player {
+    beats: VecDeque<Beat>,
+    phase: Phase,
+    options: ReplayOptions,
+    paused: bool,
+}
+
+impl Player {
+    fn new(beats: VecDeque<Beat>, options: ReplayOptions) -> Self {
+        let phase = if options.step {
+            Phase::AwaitKey
+        } else {
+            Phase::Delay(Duration::ZERO)
+        };
+        Player {
+            beats,
+            phase,
+            options,
+            paused: false,
+        }
+    }
+
+    fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            step: self.options.step,
+            paused: self.paused,
+            finished: matches!(self.phase, Phase::Finished),
+        }
+    }
+
+    /// Advance playback by `elapsed` wall-clock time.
+    fn tick(&mut self, app: &mut App, elapsed: Duration) {
+        if self.paused {
+            return;
+        }
+        let elapsed = elapsed.mul_f64(self.options.speed);
+        let done = match &mut self.phase {
+            Phase::Delay(remaining) => {
+                *remaining = remaining.saturating_sub(elapsed);
+                remaining.is_zero()
+            }
+            Phase::Reveal {
+                beat,
+                shown,
+                revealed,
+            } => {
+                *shown += elapsed.as_secs_f64() * beat.rate().unwrap_or(f64::INFINITY);
+                *revealed = reveal(app, beat, *revealed, *shown);
+                beat_text(beat).is_none_or(|t| *revealed >= t.chars().count())
+            }
+            Phase::AwaitKey | Phase::Finished => false,
+        };
+        if done {
+            self.advance(app);
+        }
+    }
+
+    /// Key press: complete the beat being revealed, or start the next one now.
+    fn advance(&mut self, app: &mut App) {
+        match self.phase {
+            Phase::Reveal { .. } => self.finish_beat(app),
+            Phase::Delay(_) | Phase::AwaitKey => self.start_next(app),
+            Phase::Finished => {}
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        if !matches!(self.phase, Phase::Finished) {
+            self.paused = !self.paused;
+        }
+    }
+
+    fn start_next(&mut self, app: &mut App) {
+        let Some(beat) = self.beats.pop_front() else {
+            app.set_thinking(false);
+            self.phase = Phase::Finished;
+            return;
+        };
+        app.scroll = ScrollPosition::Bottom;
+        match &beat {
+            Beat::Prompt { .. } => {
+                app.set_thinking(false);
+                app.input.clear();
+                app.input_cursor = 0;
+            }
+            Beat::Reply { timestamp, .. } => {
+                app.set_thinking(false);
+                app.push_assistant(String::new());
+                set_last_timestamp(app, *timestamp);
+            }
+            Beat::ToolLog(_) | Beat::Static(_) => {}
+        }
+        self.phase = Phase::Reveal {
+            beat,
+            shown: 0.0,
+            revealed: 0,
+        };
+        // Tool logs, dividers and tool results have nothing to type or stream.
+        if matches!(
+            self.phase,
+            Phase::Reveal {
+                beat: Beat::ToolLog(_) | Beat::Static(_),
+                ..
+            }
+        ) {
+            self.finish_beat(app);
+        }
+    }
+
+    /// Show the rest of the current beat and schedule the next one.
+    fn finish_beat(&mut self, app: &mut App) {
+        let Phase::Reveal { beat, revealed, .. } =
+            std::mem::replace(&mut self.phase, Phase::AwaitKey)
+        else {
+            return;
+        };
+        // The first tool log or reply of a turn comes after some "thinking".
+        let after_prompt = matches!(beat, Beat::Prompt { .. });
+        reveal(app, &beat, revealed, f64::INFINITY);
+        match beat {
+            Beat::Prompt { text, timestamp } => {
+                app.input.clear();
+                app.input_cursor = 0;
+                app.push_user(&text);
+                set_last_timestamp(app, timestamp);
+                app.set_thinking(true);
+            }
+            Beat::ToolLog(line) => app.push_tool_log(line),
+            Beat::Reply { usage, .. } => {
+                if let Some(usage) = usage {
+                    app.message_usage
+                        .insert(app.messages.len().saturating_sub(1), usage);
+                }
+            }
+            Beat::Static(message) => {
+                app.messages.push(message);
+                app.message_timestamps.push(None);
+            }
+        }
+        app.scroll = ScrollPosition::Bottom;
+
+        let next_delay = match self.beats.front() {
+            None => {
+                app.set_thinking(false);
+                self.phase = Phase::Finished;
+                return;
+            }
+            Some(_) if after_prompt => THINKING,
+            Some(Beat::Prompt { .. }) => BEFORE_PROMPT,
+            Some(_) => BETWEEN_STEPS,
+        };
+        self.phase = if self.options.step {
+            Phase::AwaitKey
+        } else {
+            Phase::Delay(next_delay)
+        };
+    }
+}
+
+fn beat_text(beat: &Beat) -> Option<&str> {
+    match beat {
+        Beat::Prompt { text, .. } | Beat::Reply { text, .. } => Some(text),
+        Beat::ToolLog(_) | Beat::Static(_) => None,
+    }
+}
+
+/// Draw characters `revealed..shown` of a prompt (into the input) or reply (streamed).
+/// Returns the number of characters now shown.
+fn reveal(app: &mut App, beat: &Beat, revealed: usize, shown: f64) -> usize {
+    let Some(text) = beat_text(beat) else {
+        return revealed;
+    };
+    let total = text.chars().count();
+    let target = if shown.is_finite() {
+        (shown as usize).min(total)
+    } else {
+        total
+    };
+    if target <= revealed {
+        return revealed;
+    }
+    let chunk: String = text
+        .chars()
+        .skip(revealed)
+        .take(target - revealed)
+        .collect();
+    match beat {
+        Beat::Prompt { .. } => {
+            app.input.push_str(&chunk);
+            app.input_cursor = app.input.len();
+        }
+        _ => app.append_assistant_chunk(&chunk),
+    }
+    target
+}
+
+fn set_last_timestamp(app: &mut App, timestamp: Option<u64>) {
+    if let Some(last) = app.message_timestamps.last_mut() {
+        *last = timestamp;
+    }
+}
+
+/// Beats of a persisted conversation, parsed the way the history popup loads it.
+fn beats(app: &mut App, persisted: &[Value]) -> VecDeque<Beat> {
+    app.set_messages_from_api(persisted, None);
+    let messages = std::mem::take(&mut app.messages);
+    let timestamps = std::mem::take(&mut app.message_timestamps);
+    let mut usage = std::mem::take(&mut app.message_usage);
+    // Replays show the conversation as it was written: pins and reactions are not marked.
+    app.pinned_messages.clear();
+    app.message_reactions.clear();
+    messages
+        .into_iter()
+        .zip(timestamps)
+        .enumerate()
+        .filter_map(|(i, (message, timestamp))| match message {
+            ChatMessage::User(text) => Some(Beat::Prompt { text, timestamp }),
+            ChatMessage::Assistant(text) => Some(Beat::Reply {
+                text,
+                timestamp,
+                usage: usage.remove(&i),
+            }),
+            ChatMessage::ToolLog(line) => Some(Beat::ToolLog(line)),
+            shown @ (ChatMessage::ContextCleared
+            | ChatMessage::ContextCompacted { .. }
+            | ChatMessage::ToolResult { .. }) => Some(Beat::Static(shown)),
+            ChatMessage::Error(_) | ChatMessage::Thinking => None,
+        })
+        .collect()
+}
+
+/// Model shown in the header: the one that produced the first reply, when recorded.
+fn recorded_model(persisted: &[Value]) -> Option<String> {
+    persisted.iter().find_map(|m| {
+        m.get("usage")?
+            .get("model")?
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    })
+}
+
+/// Replay `persisted` (messages of conversation `conversation_id`) until the user quits.
+pub fn run(
+    persisted: Vec<Value>,
+    conversation_id: String,
+    fallback_model: String,
+    workspace: Workspace,
+    options: ReplayOptions,
+) -> io::Result<()> {
+    use crossterm::terminal::{Clear, ClearType, EnterAlternateScreen, enable_raw_mode};
+    use ratatui::Terminal;
+    use ratatui::backend::CrosstermBackend;
+
+    let model_id = recorded_model(&persisted).unwrap_or(fallback_model);
+    let mut app = App::new(
+        model_id.clone(),
+        models::resolve_model_display_name(&model_id),
+        ModelSource::LastModel,
+        workspace,
+        true,
+        true,
+    );
+    let beats = beats(&mut app, &persisted);
+    app.set_conversation_id(Some(conversation_id));
+    let mut player = Player::new(beats, options);
+
+    let _guard = super::TerminalGuard::new();
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, Clear(ClearType::All))?;
+    execute!(stdout, crossterm::event::EnableMouseCapture)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut last_tick = Instant::now();
+    loop {
+        let now = Instant::now();
+        player.tick(&mut app, now - last_tick);
+        last_tick = now;
+        app.replay = Some(player.status());
+
+        terminal.draw(|f| draw(f, &mut app, f.area()))?;
+
+        // Poll often enough for smooth typing and streaming.
+        if !event::poll(Duration::from_millis(constants::EVENT_POLL_TIMEOUT_MS / 4))? {
+            continue;
+        }
+        match event::read()? {
+            Event::Mouse(mouse) => {
+                let _ = handlers::handle_mouse(mouse, &mut app);
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Char(' ') if !options.step => player.toggle_pause(),
+                KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Right => {
+                    player.paused = false;
+                    player.advance(&mut app);
+                }
+                KeyCode::Up => app.scroll_up(constants::SCROLL_LINES_SMALL),
+                KeyCode::Down => app.scroll_down(constants::SCROLL_LINES_SMALL),
+                KeyCode::PageUp => app.scroll_up(constants::SCROLL_LINES_PAGE),
+                KeyCode::PageDown => app.scroll_down(constants::SCROLL_LINES_PAGE),
+                KeyCode::Home => {
+                    app.materialize_scroll();
+                    app.scroll = ScrollPosition::Line(0);
+                }
+                KeyCode::End => app.scroll = ScrollPosition::Bottom,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_text_is_sped_up_to_fit_the_max_duration() {
+        assert_eq!(reveal_rate("hello", 40.0, Duration::from_secs(2)), 40.0);
+        let long = "x".repeat(1000);
+        assert_eq!(reveal_rate(&long, 40.0, Duration::from_secs(2)), 500.0);
+    }
+
+    #[test]
+    fn recorded_model_comes_from_the_first_reply_usage() {
+        let persisted = vec![
+            serde_json::json!({"role": "user", "content": "hi"}),
+            serde_json::json!({"role": "assistant", "content": "hello"}),
+            serde_json::json!({"role": "assistant", "content": "again", "usage": {
+                "prompt_tokens": 1, "completion_tokens": 1, "latency_ms": 5,
+                "model": "openai/gpt-4o"
+            }}),
+        ];
+        assert_eq!(recorded_model(&persisted).as_deref(), Some("openai/gpt-4o"));
+        assert_eq!(recorded_model(&persisted[..2]), None);
+    }
+}
@@ -9,13 +9,35 @@
 //! | Scroll        | ↑ ↓ PageUp PageDown                     |
 //! | History       | Alt+H, Esc+h (Option as meta), Mac chars |
 //! | New conv      | Ctrl+N                                      |
+//! | Edit last msg | Ctrl+E                                   |
+//! | Compose in editor | Ctrl+G: the input in $VISUAL/$EDITOR, read back on exit |
+//! | Regenerate    | Ctrl+R                                   |
+//! | Tool results  | Ctrl+O (expand/collapse all; click one to toggle it) |
 //! | Model selector| Alt+M, Esc+m, µ (Option+M Mac)          |
+//! | Paste clipboard | Ctrl+V (images attach as chips)        |
+//! | Pin a file      | @ + path, then Enter/Tab               |
 //! | Copy message  | ⌘C (macOS) / Ctrl+Shift+C (Linux, Windows) |
+//! | Zoom message  | Alt+Z, Esc+z, Ω (Option+Z Mac); Esc exits |
+//! | Search        | Ctrl+F, / while scrolled back; n/N between matches |
+//! | Branch        | Alt+B, Esc+b, ∫ (Option+B Mac)          |
+//! | Pin message   | Alt+P, Esc+p, π (Option+P Mac); /pins lists them |
+//! | Select messages | Alt+V, Esc+v, √ (Option+V Mac), v while scrolled back: ↑↓ (Shift extends), then c copy, p pin, b branch, d delete |
+//! | React to reply| Alt+A, Esc+a, å (Option+A Mac): good, bad, flag, none |
+//! | Split pane    | Alt+S, Esc+s, ß (Option+S Mac): last touched file and plan |
+//! | File tree     | Alt+F, Esc+f, ƒ (Option+F Mac): browse and preview workspace files |
+//! | Code wrap     | Alt+W, Esc+w, ∑ (Option+W Mac): soft-wrap or truncate code blocks; ←→ scroll |
+//! | Explain error | Alt+X, Esc+x, ≈ (Option+X Mac): prompt diagnosing the hovered or last error |
+//! | Command palette | Ctrl+K: every action and slash command, fuzzy-searchable |
+//! | New tab       | Ctrl+T                                   |
+//! | Switch tab    | Ctrl+Tab, Ctrl+Shift+Tab, Alt+1..9       |
+//! | Close tab     | Ctrl+W                                   |
+//! | Usage stats   | F9                                       |
+//! | Debug overlay | F12                                      |
 //! | Quit          | Ctrl+C                                   |
 //!
 //! On macOS, Option+key can send:
 //! - Esc+key if terminal has "Use option as meta key" enabled
-//! - A special character (˙, ˜, µ) if Option is in normal mode
+//! - A special character (˙, ˜, µ, Ω) if Option is in normal mode
 
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
@@ -28,6 +50,49 @@ pub enum Shortcut {
     NewConversation,
     /// Model selector (Alt+M, Esc+m)
     ModelSelector,
+    /// Reload the last user message into the input, dropping it and its reply (Ctrl+E)
+    EditLastMessage,
+    /// Edit the input in `$VISUAL` or `$EDITOR` with the TUI suspended (Ctrl+G)
+    ComposeInEditor,
+    /// Resend the last user message to get a new response (Ctrl+R)
+    Regenerate,
+    /// Expand every tool result block, or collapse them all (Ctrl+O)
+    ToggleToolResults,
+    /// Zoom the hovered message to fill the history, or leave the zoom (Alt+Z, Esc+z)
+    ZoomMessage,
+    /// Fork a new conversation from the zoomed or hovered message (Alt+B, Esc+b)
+    BranchConversation,
+    /// Pin the zoomed or hovered message to every request, or unpin it (Alt+P, Esc+p)
+    PinMessage,
+    /// Select whole messages with the keyboard to copy, pin, branch from, or delete them
+    /// (Alt+V, Esc+v)
+    SelectMessages,
+    /// Cycle the reaction to the zoomed or hovered reply: good, bad, flag, none (Alt+A, Esc+a)
+    ReactToMessage,
+    /// Show or hide the side pane with the last file touched by a tool (Alt+S, Esc+s)
+    SplitPane,
+    /// Browse the workspace files with a preview (Alt+F, Esc+f)
+    FileTree,
+    /// Soft-wrap code blocks, or truncate them with horizontal scrolling (Alt+W, Esc+w)
+    CodeWrap,
+    /// Write a prompt diagnosing the hovered or last error into the input (Alt+X, Esc+x)
+    ExplainError,
+    /// Search and run any action or slash command (Ctrl+K)
+    CommandPalette,
+    /// Open a new conversation in a new tab (Ctrl+T)
+    NewTab,
+    /// Show the next tab (Ctrl+Tab)
+    NextTab,
+    /// Show the previous tab (Ctrl+Shift+Tab)
+    PreviousTab,
+    /// Show the tab at this index (Alt+1..9)
+    SelectTab(usize),
+    /// Close the current tab (Ctrl+W)
+    CloseTab,
+    /// Toggle the usage statistics screen (F9)
+    StatsScreen,
+    /// Toggle the trace debug overlay (F12)
+    DebugOverlay,
     /// Quit (Ctrl+C)
     Quit,
     /// No shortcut
@@ -38,6 +103,15 @@ pub enum Shortcut {
 /// Varies by terminal/keyboard. Option+H = Ì (U+00CC), Option+N = ~ (U+007E), Option+M = µ (U+00B5).
 const MAC_OPTION_H: &[char] = &['\u{00CC}', '\u{02D9}', '\u{0127}', '\u{0302}']; // Ì, ˙, ħ, ̂
 const MAC_OPTION_M: char = '\u{00B5}'; // µ
+const MAC_OPTION_Z: char = '\u{03A9}'; // Ω
+const MAC_OPTION_B: char = '\u{222B}'; // ∫
+const MAC_OPTION_P: char = '\u{03C0}'; // π
+const MAC_OPTION_S: char = '\u{00DF}'; // ß
+const MAC_OPTION_A: char = '\u{00E5}'; // å
+const MAC_OPTION_F: char = '\u{0192}'; // ƒ
+const MAC_OPTION_W: char = '\u{2211}'; // ∑
+const MAC_OPTION_X: char = '\u{2248}'; // ≈
+const MAC_OPTION_V: char = '\u{221A}'; // √
 
 fn is_mac_option_h(c: char) -> bool {
     MAC_OPTION_H.contains(&c)
@@ -55,6 +129,15 @@ impl Shortcut {
             return match key.code {
                 KeyCode::Char('h') => Some(Shortcut::History),
                 KeyCode::Char('m') => Some(Shortcut::ModelSelector),
+                KeyCode::Char('z') => Some(Shortcut::ZoomMessage),
+                KeyCode::Char('b') => Some(Shortcut::BranchConversation),
+                KeyCode::Char('p') => Some(Shortcut::PinMessage),
+                KeyCode::Char('v') => Some(Shortcut::SelectMessages),
+                KeyCode::Char('a') => Some(Shortcut::ReactToMessage),
+                KeyCode::Char('s') => Some(Shortcut::SplitPane),
+                KeyCode::Char('f') => Some(Shortcut::FileTree),
+                KeyCode::Char('w') => Some(Shortcut::CodeWrap),
+                KeyCode::Char('x') => Some(Shortcut::ExplainError),
                 _ => None,
             };
         }
@@ -66,18 +149,181 @@ impl Shortcut {
             KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 Some(Shortcut::NewConversation)
             }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::EditLastMessage)
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::Regenerate)
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::ToggleToolResults)
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::ComposeInEditor)
+            }
+            KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::CommandPalette)
+            }
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::NewTab)
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::CloseTab)
+            }
+            KeyCode::BackTab if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Shortcut::PreviousTab)
+            }
+            KeyCode::Tab if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    Some(Shortcut::PreviousTab)
+                } else {
+                    Some(Shortcut::NextTab)
+                }
+            }
+            KeyCode::Char(c @ '1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::SelectTab(c as usize - '1' as usize))
+            }
             KeyCode::Char('h') if key.modifiers.contains(KeyModifiers::ALT) => {
                 Some(Shortcut::History)
             }
             KeyCode::Char('m') if key.modifiers.contains(KeyModifiers::ALT) => {
                 Some(Shortcut::ModelSelector)
             }
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::ZoomMessage)
+            }
+            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::BranchConversation)
+            }
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::PinMessage)
+            }
+            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::SelectMessages)
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::SplitPane)
+            }
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::ReactToMessage)
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::FileTree)
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::CodeWrap)
+            }
+            KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::ALT) => {
+                Some(Shortcut::ExplainError)
+            }
             KeyCode::Char(c) if is_mac_option_h(c) => Some(Shortcut::History),
             KeyCode::Char(MAC_OPTION_M) => Some(Shortcut::ModelSelector),
+            KeyCode::Char(MAC_OPTION_Z) => Some(Shortcut::ZoomMessage),
+            KeyCode::Char(MAC_OPTION_B) => Some(Shortcut::BranchConversation),
+            KeyCode::Char(MAC_OPTION_P) => Some(Shortcut::PinMessage),
+            KeyCode::Char(MAC_OPTION_V) => Some(Shortcut::SelectMessages),
+            KeyCode::Char(MAC_OPTION_S) => Some(Shortcut::SplitPane),
+            KeyCode::Char(MAC_OPTION_A) => Some(Shortcut::ReactToMessage),
+            KeyCode::Char(MAC_OPTION_F) => Some(Shortcut::FileTree),
+            KeyCode::Char(MAC_OPTION_W) => Some(Shortcut::CodeWrap),
+            KeyCode::Char(MAC_OPTION_X) => Some(Shortcut::ExplainError),
+            KeyCode::F(9) => Some(Shortcut::StatsScreen),
+            KeyCode::F(12) => Some(Shortcut::DebugOverlay),
             _ => None,
         }
     }
 
+    /// Actions listed in the command palette, in order.
+    pub const PALETTE: &[Shortcut] = &[
+        Shortcut::NewConversation,
+        Shortcut::History,
+        Shortcut::ModelSelector,
+        Shortcut::EditLastMessage,
+        Shortcut::ComposeInEditor,
+        Shortcut::Regenerate,
+        Shortcut::ToggleToolResults,
+        Shortcut::ZoomMessage,
+        Shortcut::BranchConversation,
+        Shortcut::PinMessage,
+        Shortcut::SelectMessages,
+        Shortcut::ReactToMessage,
+        Shortcut::SplitPane,
+        Shortcut::FileTree,
+        Shortcut::CodeWrap,
+        Shortcut::ExplainError,
+        Shortcut::NewTab,
+        Shortcut::NextTab,
+        Shortcut::PreviousTab,
+        Shortcut::CloseTab,
+        Shortcut::StatsScreen,
+        Shortcut::DebugOverlay,
+        Shortcut::Quit,
+    ];
+
+    /// Name of the action in the command palette.
+    pub fn label(self) -> &'static str {
+        match self {
+            Shortcut::History => "Open conversation history",
+            Shortcut::NewConversation => "New conversation",
+            Shortcut::ModelSelector => "Switch model",
+            Shortcut::EditLastMessage => "Edit last message",
+            Shortcut::ComposeInEditor => "Compose prompt in editor",
+            Shortcut::Regenerate => "Regenerate last reply",
+            Shortcut::ToggleToolResults => "Expand or collapse tool results",
+            Shortcut::ZoomMessage => "Zoom message",
+            Shortcut::BranchConversation => "Branch conversation from message",
+            Shortcut::PinMessage => "Pin or unpin message",
+            Shortcut::SelectMessages => "Select messages",
+            Shortcut::ReactToMessage => "React to reply",
+            Shortcut::SplitPane => "Toggle split pane",
+            Shortcut::FileTree => "Browse workspace files",
+            Shortcut::CodeWrap => "Toggle code block wrapping",
+            Shortcut::ExplainError => "Explain error",
+            Shortcut::CommandPalette => "Command palette",
+            Shortcut::NewTab => "New tab",
+            Shortcut::NextTab => "Next tab",
+            Shortcut::PreviousTab => "Previous tab",
+            Shortcut::SelectTab(_) => "Go to tab",
+            Shortcut::CloseTab => "Close tab",
+            Shortcut::StatsScreen => "Usage statistics",
+            Shortcut::DebugOverlay => "Toggle debug overlay",
+            Shortcut::Quit => "Quit",
+            Shortcut::None => "",
+        }
+    }
+
+    /// Key binding shown next to the action.
+    pub fn keys(self) -> &'static str {
+        match self {
+            Shortcut::History => "Alt+H",
+            Shortcut::NewConversation => "Ctrl+N",
+            Shortcut::ModelSelector => "Alt+M",
+            Shortcut::EditLastMessage => "Ctrl+E",
+            Shortcut::ComposeInEditor => "Ctrl+G",
+            Shortcut::Regenerate => "Ctrl+R",
+            Shortcut::ToggleToolResults => "Ctrl+O",
+            Shortcut::ZoomMessage => "Alt+Z",
+            Shortcut::BranchConversation => "Alt+B",
+            Shortcut::PinMessage => "Alt+P",
+            Shortcut::SelectMessages => "Alt+V",
+            Shortcut::ReactToMessage => "Alt+A",
+            Shortcut::SplitPane => "Alt+S",
+            Shortcut::FileTree => "Alt+F",
+            Shortcut::CodeWrap => "Alt+W",
+            Shortcut::ExplainError => "Alt+X",
+            Shortcut::CommandPalette => "Ctrl+K",
+            Shortcut::NewTab => "Ctrl+T",
+            Shortcut::NextTab => "Ctrl+Tab",
+            Shortcut::PreviousTab => "Ctrl+Shift+Tab",
+            Shortcut::SelectTab(_) => "Alt+1..9",
+            Shortcut::CloseTab => "Ctrl+W",
+            Shortcut::StatsScreen => "F9",
+            Shortcut::DebugOverlay => "F12",
+            Shortcut::Quit => "Ctrl+C",
+            Shortcut::None => "",
+        }
+    }
+
     /// True if key is Escape (start of Option+key sequence on some terminals).
     pub fn is_escape(key: &KeyEvent) -> bool {
         key.kind == KeyEventKind::Press && key.code == KeyCode::Esc
@@ -142,6 +388,217 @@ mod tests {
         );
     }
 
+    #[test]
+    fn match_edit_and_regenerate() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('e'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::EditLastMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('r'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::Regenerate)
+        );
+    }
+
+    #[test]
+    fn match_toggle_tool_results_ctrl_o() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('o'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::ToggleToolResults)
+        );
+    }
+
+    #[test]
+    fn match_zoom_message() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('z'), KeyModifiers::ALT), false),
+            Some(Shortcut::ZoomMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('z'), KeyModifiers::empty()), true),
+            Some(Shortcut::ZoomMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(
+                &key(KeyCode::Char('\u{03A9}'), KeyModifiers::empty()),
+                false
+            ),
+            Some(Shortcut::ZoomMessage)
+        );
+    }
+
+    #[test]
+    fn match_pin_message() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('p'), KeyModifiers::ALT), false),
+            Some(Shortcut::PinMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('p'), KeyModifiers::empty()), true),
+            Some(Shortcut::PinMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('p'), KeyModifiers::empty()), false),
+            None
+        );
+    }
+
+    #[test]
+    fn match_react_to_message() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('a'), KeyModifiers::ALT), false),
+            Some(Shortcut::ReactToMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('a'), KeyModifiers::empty()), true),
+            Some(Shortcut::ReactToMessage)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('å'), KeyModifiers::empty()), false),
+            Some(Shortcut::ReactToMessage)
+        );
+    }
+
+    #[test]
+    fn match_split_pane() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('s'), KeyModifiers::ALT), false),
+            Some(Shortcut::SplitPane)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('s'), KeyModifiers::empty()), true),
+            Some(Shortcut::SplitPane)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('ß'), KeyModifiers::empty()), false),
+            Some(Shortcut::SplitPane)
+        );
+    }
+
+    #[test]
+    fn match_file_tree() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('f'), KeyModifiers::ALT), false),
+            Some(Shortcut::FileTree)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('f'), KeyModifiers::empty()), true),
+            Some(Shortcut::FileTree)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('ƒ'), KeyModifiers::empty()), false),
+            Some(Shortcut::FileTree)
+        );
+    }
+
+    #[test]
+    fn match_code_wrap() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('w'), KeyModifiers::ALT), false),
+            Some(Shortcut::CodeWrap)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('w'), KeyModifiers::empty()), true),
+            Some(Shortcut::CodeWrap)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('∑'), KeyModifiers::empty()), false),
+            Some(Shortcut::CodeWrap)
+        );
+    }
+
+    #[test]
+    fn match_explain_error() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('x'), KeyModifiers::ALT), false),
+            Some(Shortcut::ExplainError)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('x'), KeyModifiers::empty()), true),
+            Some(Shortcut::ExplainError)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('≈'), KeyModifiers::empty()), false),
+            Some(Shortcut::ExplainError)
+        );
+    }
+
+    #[test]
+    fn match_tabs() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('t'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::NewTab)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Tab, KeyModifiers::CONTROL), false),
+            Some(Shortcut::NextTab)
+        );
+        assert_eq!(
+            Shortcut::match_key(
+                &key(KeyCode::Tab, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+                false
+            ),
+            Some(Shortcut::PreviousTab)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::BackTab, KeyModifiers::CONTROL), false),
+            Some(Shortcut::PreviousTab)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('3'), KeyModifiers::ALT), false),
+            Some(Shortcut::SelectTab(2))
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('w'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::CloseTab)
+        );
+        // Plain Tab cycles the suggestions.
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Tab, KeyModifiers::empty()), false),
+            None
+        );
+    }
+
+    #[test]
+    fn match_compose_in_editor_ctrl_g() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('g'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::ComposeInEditor)
+        );
+    }
+
+    #[test]
+    fn match_command_palette_ctrl_k() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('k'), KeyModifiers::CONTROL), false),
+            Some(Shortcut::CommandPalette)
+        );
+    }
+
+    #[test]
+    fn palette_actions_have_labels_and_keys() {
+        for shortcut in Shortcut::PALETTE {
+            assert!(!shortcut.label().is_empty(), "{:?}", shortcut);
+            assert!(!shortcut.keys().is_empty(), "{:?}", shortcut);
+        }
+    }
+
+    #[test]
+    fn match_stats_screen_f9() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::F(9), KeyModifiers::empty()), false),
+            Some(Shortcut::StatsScreen)
+        );
+    }
+
+    #[test]
+    fn match_debug_overlay_f12() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::F(12), KeyModifiers::empty()), false),
+            Some(Shortcut::DebugOverlay)
+        );
+    }
+
     #[test]
     fn match_escape_pending_h() {
         assert_eq!(
@@ -176,6 +633,41 @@ mod tests {
         };
         assert_eq!(Shortcut::match_key(&key_release, false), None);
     }
+
+    #[test]
+    fn match_select_messages() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('v'), KeyModifiers::ALT), false),
+            Some(Shortcut::SelectMessages)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('v'), KeyModifiers::empty()), true),
+            Some(Shortcut::SelectMessages)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('v'), KeyModifiers::empty()), false),
+            None
+        );
+    }
+
+    #[test]
+    fn match_branch_conversation() {
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('b'), KeyModifiers::ALT), false),
+            Some(Shortcut::BranchConversation)
+        );
+        assert_eq!(
+            Shortcut::match_key(&key(KeyCode::Char('b'), KeyModifiers::empty()), true),
+            Some(Shortcut::BranchConversation)
+        );
+        assert_eq!(
+            Shortcut::match_key(
+                &key(KeyCode::Char('\u{222B}'), KeyModifiers::empty()),
+                false
+            ),
+            Some(Shortcut::BranchConversation)
+        );
+    }
 }
 
 /// Labels for the bottom bar (2 lines for readability on narrow terminals).
@@ -183,6 +675,8 @@ pub mod labels {
     use ratatui::style::Color;
     use ratatui::text::{Line, Span, Text};
 
+    use crate::core::llm::NextAction;
+
     const DIM: Color = Color::DarkGray;
 
     #[cfg(target_os = "macos")]
@@ -195,6 +689,8 @@ pub mod labels {
             Text::from(Line::from(vec![
                 Span::styled("Esc ", Color::Yellow),
                 Span::raw("cancel"),
+                Span::styled("  Enter ", DIM),
+                Span::raw("queue"),
                 Span::styled("  ↑↓ ", DIM),
                 Span::raw("scroll"),
             ]))
@@ -207,11 +703,17 @@ pub mod labels {
                     Span::raw("newline"),
                     Span::styled("  Ctrl+U ", DIM),
                     Span::raw("clear"),
+                    Span::styled("  Ctrl+E ", DIM),
+                    Span::raw("edit last"),
+                    Span::styled("  Ctrl+R ", DIM),
+                    Span::raw("regenerate"),
                     Span::styled("  ↑↓ ", DIM),
                     Span::raw("scroll"),
                 ]),
                 Line::from(vec![
-                    Span::styled("Alt+H ", DIM),
+                    Span::styled("Ctrl+K ", DIM),
+                    Span::raw("commands"),
+                    Span::styled("  Alt+H ", DIM),
                     Span::raw("history"),
                     Span::styled("  Ctrl+N ", DIM),
                     Span::raw("new"),
@@ -219,10 +721,105 @@ pub mod labels {
                     Span::raw("model"),
                     Span::styled(COPY_KEY, DIM),
                     Span::raw("copy"),
+                    Span::styled("  Alt+Z ", DIM),
+                    Span::raw("zoom"),
+                    Span::styled("  Alt+B ", DIM),
+                    Span::raw("branch"),
                     Span::styled("  Ctrl+C ", DIM),
                     Span::raw("quit"),
                 ]),
             ])
         }
     }
+
+    /// Key and label of the action under every failed request (also a click on its line).
+    pub const EXPLAIN_ERROR: (&str, &str) = ("Alt+X", "explain");
+
+    /// Key and label of an action suggested under a failed request.
+    pub fn next_action(action: NextAction) -> (&'static str, &'static str) {
+        match action {
+            NextAction::Retry => ("Ctrl+R", "retry"),
+            NextAction::SwitchModel => ("Alt+M", "switch model"),
+            NextAction::CompactContext => ("/clear-context", "drop earlier messages"),
+        }
+    }
+
+    /// Bottom bar while selecting messages; `confirm_delete` once `d` asked to delete them.
+    pub fn message_selection_bar(confirm_delete: bool) -> Text<'static> {
+        let spans = if confirm_delete {
+            vec![
+                Span::styled("d ", Color::Yellow),
+                Span::raw("delete"),
+                Span::styled("  any other key ", DIM),
+                Span::raw("keep"),
+            ]
+        } else {
+            vec![
+                Span::styled("↑↓ ", DIM),
+                Span::raw("move"),
+                Span::styled("  Shift+↑↓ ", DIM),
+                Span::raw("extend"),
+                Span::styled("  c ", DIM),
+                Span::raw("copy"),
+                Span::styled("  p ", DIM),
+                Span::raw("pin"),
+                Span::styled("  b ", DIM),
+                Span::raw("branch"),
+                Span::styled("  d ", DIM),
+                Span::raw("delete"),
+                Span::styled("  Esc ", DIM),
+                Span::raw("close"),
+            ]
+        };
+        Text::from(Line::from(spans))
+    }
+
+    /// Bottom bar while searching the conversation.
+    pub fn search_bar(editing: bool) -> Text<'static> {
+        let spans = if editing {
+            vec![
+                Span::styled("Enter ", DIM),
+                Span::raw("done"),
+                Span::styled("  ↑↓ ", DIM),
+                Span::raw("scroll"),
+                Span::styled("  Esc ", DIM),
+                Span::raw("close"),
+            ]
+        } else {
+            vec![
+                Span::styled("n/N ", DIM),
+                Span::raw("next/previous"),
+                Span::styled("  / ", DIM),
+                Span::raw("edit"),
+                Span::styled("  ↑↓ ", DIM),
+                Span::raw("scroll"),
+                Span::styled("  Esc ", DIM),
+                Span::raw("close"),
+            ]
+        };
+        Text::from(Line::from(spans))
+    }
+
+    /// Bottom bar while replaying a conversation.
+    pub fn replay_bar(step: bool, paused: bool, finished: bool) -> Text<'static> {
+        let mut spans = if finished {
+            vec![Span::styled("Replay finished", Color::Yellow)]
+        } else if step {
+            vec![Span::styled("Enter/Space ", DIM), Span::raw("next")]
+        } else {
+            vec![
+                Span::styled("Space ", DIM),
+                Span::raw(if paused { "resume" } else { "pause" }),
+                Span::styled("  Enter ", DIM),
+                Span::raw("skip"),
+            ]
+        };
+        spans.extend([
+            Span::styled("  ↑↓ ", DIM),
+            Span::raw("scroll"),
+            Span::styled("  q ", DIM),
+            Span::raw("quit"),
+        ]);
+        Text::from(Line::from(spans))
+    }
 }
@@ -0,0 +1,252 @@
+//! Tabs: conversations open side by side in one TUI (Ctrl+T new tab, Ctrl+Tab and
+//! Ctrl+Shift+Tab or Alt+1..9 to switch, Ctrl+W to close). Each tab has its own app state,
+//! API messages, and requests in flight, which keep running in background tabs; the runtime,
+//! the config, and the credits fetch are shared.
+
+use std::sync::Arc;
+use std::sync::mpsc;
+
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::core::config::Config;
+use crate::core::llm;
+use crate::core::models::ModelInfo;
+use crate::core::spend;
+use crate::core::text;
+
+use super::app::{self, App, ChatMessage, TabLabel};
+use super::chat_result;
+use super::handlers::{self, PendingChat};
+use super::{CompactionResult, QUEUE_RATE_LIMIT_HOLD, spawn_compaction, spawn_gist_upload};
+
+/// Widest tab title in the tab bar, in columns.
+const TAB_TITLE_WIDTH: usize = 24;
+
+/// Tab change asked by a shortcut (see `handlers::HandleResult::Tab`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabAction {
+    New,
+    Next,
+    Previous,
+    Close,
+    /// Go to the tab at this index (Alt+1 is 0).
+    Select(usize),
+}
+
+/// One conversation and the requests in flight for it.
+pub(super) struct Tab {
+    pub app: App,
+    pub api_messages: Option<Vec<Value>>,
+    pub pending_chat: Option<PendingChat>,
+    pub pending_model_fetch: Option<mpsc::Receiver<Result<Vec<ModelInfo>, String>>>,
+    pending_title: Option<mpsc::Receiver<Option<(app::TitleRequest, String)>>>,
+    pending_share: Option<mpsc::Receiver<Result<String, String>>>,
+    pending_compaction: Option<mpsc::Receiver<CompactionResult>>,
+    /// A turn ended while another tab was shown.
+    pub unseen: bool,
+}
+
+impl Tab {
+    pub fn new(app: App) -> Self {
+        Tab {
+            app,
+            api_messages: None,
+            pending_chat: None,
+            pending_model_fetch: None,
+            pending_title: None,
+            pending_share: None,
+            pending_compaction: None,
+            unseen: false,
+        }
+    }
+
+    /// Apply what arrived for this tab since the last frame: model list, progress, reply
+    /// chunks, and result of the turn, generated title, gist upload, compaction. Returns
+    /// true when a turn ended.
+    pub fn poll(&mut self, config: &Arc<Config>, rt: &Arc<Runtime>) -> bool {
+        let app = &mut self.app;
+        if let Some(ref fetch_rx) = self.pending_model_fetch
+            && let Ok(result) = fetch_rx.try_recv()
+        {
+            if let Some(ref mut selector) = app.model_selector {
+                match result {
+                    Ok(models) => {
+                        selector.models = models;
+                        selector.selected_index = 0;
+                        selector.fetch_error = None;
+                    }
+                    Err(e) => {
+                        selector.fetch_error = Some(e);
+                    }
+                }
+            }
+            self.pending_model_fetch = None;
+        }
+
+        let mut turn_ended = false;
+        if let Some(ref mut chat) = self.pending_chat {
+            app.turn_status.get_or_insert_with(app::TurnStatus::new);
+            while let Ok(event) = chat.progress_rx.try_recv() {
+                app.apply_progress(event);
+            }
+            // Tool events before reply chunks: a tool result belongs above the reply text
+            // that follows it.
+            while let Ok(update) = chat.tool_event_rx.try_recv() {
+                app.apply_tool_update(update);
+            }
+            while let Ok(chunk) = chat.stream_rx.try_recv() {
+                app.mark_responding();
+                app.append_assistant_chunk(&chunk);
+            }
+            if let Ok(result) = chat.result_rx.try_recv() {
+                app.set_thinking(false);
+                app.is_streaming = false;
+                app.tool_queue.clear();
+                // /commit and /review turns open their popup once the turn ends.
+                let follow_up = match &result {
+                    Ok(llm::ChatResult::Complete { content, .. }) => app
+                        .turn_follow_up
+                        .clone()
+                        .map(|follow_up| (follow_up, content.clone())),
+                    _ => None,
+                };
+                let awaiting_confirmation =
+                    matches!(result, Ok(llm::ChatResult::NeedsConfirmation { .. }));
+                if let Err(e @ llm::ChatError::RateLimited { .. }) = &result {
+                    app.hold_queue(e.retry_after().unwrap_or(QUEUE_RATE_LIMIT_HOLD));
+                }
+                chat_result::notify_desktop(app, config.as_ref(), &result);
+                app.spend_status = spend::check(&config.spend_limits);
+                chat_result::handle_chat_result(
+                    app,
+                    &mut self.api_messages,
+                    result,
+                    true,
+                    config.as_ref(),
+                );
+                if !awaiting_confirmation {
+                    app.turn_follow_up = None;
+                    app.turn_model = None;
+                    app.turn_output_schema = None;
+                    match follow_up {
+                        Some((app::TurnFollowUp::Commit, draft)) if !draft.trim().is_empty() => {
+                            app.open_commit_popup(&draft);
+                        }
+                        Some((app::TurnFollowUp::Review { scope }, _)) => {
+                            app.open_review_diff(scope.as_deref());
+                        }
+                        _ => {}
+                    }
+                }
+                self.pending_chat = None;
+                turn_ended = true;
+            }
+        }
+        if self.pending_chat.is_none() {
+            app.turn_status = None;
+        }
+
+        // Titles are generated one at a time, after the first exchange of a new conversation.
+        if self.pending_title.is_none()
+            && let Some(request) = app.title_request.take()
+        {
+            self.pending_title =
+                chat_result::spawn_title_generation(request, Arc::clone(config), rt);
+        }
+        if let Some(ref title_rx) = self.pending_title
+            && let Ok(result) = title_rx.try_recv()
+        {
+            if let Some((request, title)) = result {
+                chat_result::apply_generated_title(app, &request, &title);
+            }
+            self.pending_title = None;
+        }
+
+        if self.pending_share.is_none()
+            && let Some(token) = config.gist_token.clone()
+            && let Some(shared) = app.share_request.take()
+        {
+            self.pending_share = Some(spawn_gist_upload(shared, token, rt));
+        }
+        if let Some(ref share_rx) = self.pending_share
+            && let Ok(result) = share_rx.try_recv()
+        {
+            app.push_tool_log(match result {
+                Ok(url) => format!("Shared as a secret gist: {}", url),
+                Err(e) => format!("Share failed: {}", e),
+            });
+            self.pending_share = None;
+        }
+
+        if self.pending_compaction.is_none()
+            && let Some(request) = app.compact_request.take()
+        {
+            self.pending_compaction = Some(spawn_compaction(request, Arc::clone(config), rt));
+        }
+        // A turn in flight replaces the API messages when it ends: apply the compaction after.
+        if self.pending_chat.is_none()
+            && let Some(ref compaction_rx) = self.pending_compaction
+            && let Ok((request, result)) = compaction_rx.try_recv()
+        {
+            app.apply_compaction(&mut self.api_messages, &request, result);
+            chat_result::save_conversation_if_dirty(app, &self.api_messages, config.as_ref());
+            self.pending_compaction = None;
+        }
+
+        handlers::start_queued_prompt(
+            &mut self.app,
+            config,
+            &mut self.pending_chat,
+            &self.api_messages,
+            rt,
+        );
+        turn_ended
+    }
+
+    /// Entry of the tab bar.
+    pub fn label(&self, active: bool) -> TabLabel {
+        let title = self
+            .app
+            .messages
+            .iter()
+            .find_map(|m| match m {
+                ChatMessage::User(text) => text.lines().find(|l| !l.trim().is_empty()),
+                _ => None,
+            })
+            .map(|line| text::truncate_end(line.trim(), TAB_TITLE_WIDTH))
+            .unwrap_or_else(|| "New conversation".to_string());
+        TabLabel {
+            title,
+            active,
+            busy: self.pending_chat.is_some(),
+            needs_confirmation: self.app.confirm_popup.is_some(),
+            unseen: self.unseen,
+        }
+    }
+}
+
+/// Index of the tab `action` switches to among `count` tabs, from `active` (None for New and
+/// Close, which change the list).
+pub(super) fn target(action: TabAction, active: usize, count: usize) -> Option<usize> {
+    match action {
+        TabAction::Next => Some((active + 1) % count),
+        TabAction::Previous => Some((active + count - 1) % count),
+        TabAction::Select(index) => Some(index.min(count - 1)),
+        TabAction::New | TabAction::Close => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_wraps_around_and_clamps() {
+        assert_eq!(target(TabAction::Next, 2, 3), Some(0));
+        assert_eq!(target(TabAction::Previous, 0, 3), Some(2));
+        assert_eq!(target(TabAction::Select(1), 0, 3), Some(1));
+        assert_eq!(target(TabAction::Select(8), 0, 3), Some(2));
+        assert_eq!(target(TabAction::New, 0, 3), None);
+    }
+}
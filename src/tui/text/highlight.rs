@@ -0,0 +1,325 @@
+//! Syntax highlighting for fenced code blocks using syntect.
+//!
+//! A block is highlighted as a whole (multi-line strings and comments keep their style) and
+//! cached by content, so redrawing the history does not highlight finished blocks again.
+//! The block being streamed resumes from the parser state of its last complete line: each
+//! new chunk only highlights the lines it adds.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use syntect::highlighting::{
+    FontStyle, HighlightIterator, HighlightState, Highlighter, Theme, ThemeSet,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+
+use super::super::constants::ACCENT_SECONDARY;
+
+/// Highlighted lines of a code block (one entry per `\n`-separated line).
+pub(crate) type HighlightedBlock = Arc<Vec<Vec<Span<'static>>>>;
+
+/// Finished blocks kept in the cache; it is emptied when full.
+const MAX_CACHED_BLOCKS: usize = 256;
+
+/// Convert syntect Color to ratatui Color. Alpha 0 => None (colourless).
+fn translate_colour(c: syntect::highlighting::Color) -> Option<Color> {
+    let syntect::highlighting::Color { r, g, b, a } = c;
+    if a > 0 {
+        Some(Color::Rgb(r, g, b))
+    } else {
+        None
+    }
+}
+
+/// Convert syntect FontStyle to ratatui Modifier. Supports BOLD, ITALIC, UNDERLINE and combinations.
+fn translate_font_style(f: FontStyle) -> Modifier {
+    if f.is_empty() {
+        return Modifier::empty();
+    }
+    let mut m = Modifier::empty();
+    if f.contains(FontStyle::BOLD) {
+        m.insert(Modifier::BOLD);
+    }
+    if f.contains(FontStyle::ITALIC) {
+        m.insert(Modifier::ITALIC);
+    }
+    if f.contains(FontStyle::UNDERLINE) {
+        m.insert(Modifier::UNDERLINED);
+    }
+    m
+}
+
+/// Convert syntect Style to ratatui Style.
+fn translate_style(s: syntect::highlighting::Style) -> Style {
+    let fg = translate_colour(s.foreground).unwrap_or(ACCENT_SECONDARY);
+    let bg = translate_colour(s.background);
+    let modifier = translate_font_style(s.font_style);
+    let mut style = Style::default().fg(fg).add_modifier(modifier);
+    if let Some(b) = bg {
+        style = style.bg(b);
+    }
+    style
+}
+
+static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+static CACHE: OnceLock<Mutex<HighlightCache>> = OnceLock::new();
+
+fn syntax_set() -> &'static SyntaxSet {
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Theme used when none is selected (or the selected one does not exist).
+const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Theme selected by the active profile.
+static THEME_NAME: Mutex<Option<String>> = Mutex::new(None);
+
+fn theme() -> Option<&'static Theme> {
+    let ts = theme_set();
+    let name = THEME_NAME.lock().unwrap_or_else(|e| e.into_inner());
+    name.as_deref()
+        .and_then(|n| ts.themes.get(n))
+        .or_else(|| ts.themes.get(DEFAULT_THEME))
+        .or_else(|| ts.themes.values().next())
+}
+
+/// Highlight code blocks with the syntect theme `name` (None: the default theme), dropping
+/// the blocks highlighted with the previous one. Errors on an unknown theme, listing the
+/// available ones; the default theme is used then.
+pub(crate) fn set_theme(name: Option<&str>) -> Result<(), String> {
+    let ts = theme_set();
+    let result = match name {
+        Some(n) if !ts.themes.contains_key(n) => {
+            let names: Vec<&str> = ts.themes.keys().map(String::as_str).collect();
+            Err(format!(
+                "Unknown theme '{}' (available: {})",
+                n,
+                names.join(", ")
+            ))
+        }
+        _ => Ok(()),
+    };
+    *THEME_NAME.lock().unwrap_or_else(|e| e.into_inner()) =
+        name.filter(|_| result.is_ok()).map(str::to_string);
+    if let Some(cache) = CACHE.get() {
+        *cache.lock().unwrap_or_else(|e| e.into_inner()) = HighlightCache::default();
+    }
+    result
+}
+
+/// Map language identifier from markdown (e.g. "rust", "python") to syntect extension.
+fn lang_to_extension(lang: &str) -> &'static str {
+    match lang.trim().to_lowercase().as_str() {
+        "rs" | "rust" => "rs",
+        "py" | "python" => "py",
+        "js" | "javascript" => "js",
+        "ts" | "typescript" => "ts",
+        "go" | "golang" => "go",
+        "rb" | "ruby" => "rb",
+        "sh" | "bash" | "zsh" => "sh",
+        "sql" => "sql",
+        "json" => "json",
+        "yaml" | "yml" => "yml",
+        "toml" => "toml",
+        "md" | "markdown" => "md",
+        "html" => "html",
+        "css" => "css",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        _ => "plain",
+    }
+}
+
+/// Grammar for a fence language: the aliases above, else any syntect extension or name
+/// (e.g. "diff", "java", "Makefile"). None for an empty or unknown language.
+fn find_syntax(lang: &str) -> Option<&'static SyntaxReference> {
+    let lang = lang.trim();
+    if lang.is_empty() {
+        return None;
+    }
+    let ps = syntax_set();
+    match lang_to_extension(lang) {
+        "plain" => ps.find_syntax_by_token(lang),
+        ext => ps.find_syntax_by_extension(ext),
+    }
+}
+
+fn plain_line(line: &str) -> Vec<Span<'static>> {
+    vec![Span::styled(
+        line.to_string(),
+        Style::default().fg(ACCENT_SECONDARY),
+    )]
+}
+
+/// Parser and highlighter state after the complete lines of a block.
+#[derive(Clone)]
+struct BlockState {
+    parse: ParseState,
+    highlight: HighlightState,
+}
+
+impl BlockState {
+    fn new(syntax: &SyntaxReference, highlighter: &Highlighter<'_>) -> Self {
+        Self {
+            parse: ParseState::new(syntax),
+            highlight: HighlightState::new(highlighter, ScopeStack::new()),
+        }
+    }
+
+    /// Highlight one line (without its newline) and advance the state past it.
+    /// A parse error leaves the line plain.
+    fn highlight_line(&mut self, line: &str, highlighter: &Highlighter<'_>) -> Vec<Span<'static>> {
+        let with_newline = format!("{}\n", line);
+        let Ok(ops) = self.parse.parse_line(&with_newline, syntax_set()) else {
+            return plain_line(line);
+        };
+        HighlightIterator::new(&mut self.highlight, &ops, &with_newline, highlighter)
+            .map(|(style, text)| (style, text.trim_end_matches('\n')))
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(style, text)| Span::styled(text.to_string(), translate_style(style)))
+            .collect()
+    }
+}
+
+/// The block being streamed: its complete lines, highlighted, and the state after them.
+struct StreamingBlock {
+    lang: String,
+    /// Complete lines, each with its trailing newline.
+    done: String,
+    lines: Vec<Vec<Span<'static>>>,
+    state: BlockState,
+}
+
+#[derive(Default)]
+struct HighlightCache {
+    /// Finished blocks by hash of (lang, code), with the code to rule out collisions.
+    blocks: HashMap<u64, (String, HighlightedBlock)>,
+    streaming: Option<StreamingBlock>,
+}
+
+fn block_key(lang: &str, code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (lang, code).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Highlight a whole block from scratch.
+fn highlight_block(syntax: &SyntaxReference, theme: &Theme, code: &str) -> HighlightedBlock {
+    let highlighter = Highlighter::new(theme);
+    let mut state = BlockState::new(syntax, &highlighter);
+    Arc::new(
+        code.split('\n')
+            .map(|line| state.highlight_line(line, &highlighter))
+            .collect(),
+    )
+}
+
+impl StreamingBlock {
+    /// Highlight `code`, reusing the lines already highlighted when `code` extends them.
+    fn update(
+        slot: &mut Option<StreamingBlock>,
+        lang: &str,
+        syntax: &SyntaxReference,
+        theme: &Theme,
+        code: &str,
+    ) -> HighlightedBlock {
+        let highlighter = Highlighter::new(theme);
+        let resumable = slot
+            .as_ref()
+            .is_some_and(|s| s.lang == lang && code.starts_with(&s.done));
+        if !resumable {
+            *slot = Some(StreamingBlock {
+                lang: lang.to_string(),
+                done: String::new(),
+                lines: Vec::new(),
+                state: BlockState::new(syntax, &highlighter),
+            });
+        }
+        let Some(block) = slot.as_mut() else {
+            return highlight_block(syntax, theme, code);
+        };
+        // Commit the lines completed since the last update.
+        let new = &code[block.done.len()..];
+        let complete_len = new.rfind('\n').map_or(0, |pos| pos + 1);
+        for line in new[..complete_len].split_terminator('\n') {
+            let spans = block.state.highlight_line(line, &highlighter);
+            block.lines.push(spans);
+        }
+        block.done.push_str(&new[..complete_len]);
+        // The partial last line is highlighted on a copy of the state: it will change.
+        let partial = &new[complete_len..];
+        let mut lines = block.lines.clone();
+        lines.push(block.state.clone().highlight_line(partial, &highlighter));
+        Arc::new(lines)
+    }
+}
+
+/// Highlight a fenced code block, one span list per line. `streaming` marks the block still
+/// being received: it is highlighted incrementally and not added to the cache of finished
+/// blocks. Unknown languages are drawn in the accent color.
+pub(crate) fn highlight_code_block(lang: &str, code: &str, streaming: bool) -> HighlightedBlock {
+    let (Some(syntax), Some(theme)) = (find_syntax(lang), theme()) else {
+        return Arc::new(code.split('\n').map(plain_line).collect());
+    };
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if streaming {
+        return StreamingBlock::update(&mut cache.streaming, lang, syntax, theme, code);
+    }
+    let key = block_key(lang, code);
+    if let Some((cached_code, block)) = cache.blocks.get(&key)
+        && cached_code == code
+    {
+        return Arc::clone(block);
+    }
+    let block = highlight_block(syntax, theme, code);
+    if cache.blocks.len() >= MAX_CACHED_BLOCKS {
+        cache.blocks.clear();
+    }
+    cache
+        .blocks
+        .insert(key, (code.to_string(), Arc::clone(&block)));
+    block
+}
+
+/// Slice spans to cover only the character range [range_start, range_end).
+/// Used when wrapping code lines: each wrap chunk gets the spans for its character slice.
+pub(crate) fn slice_spans_by_range(
+    spans: &[Span<'static>],
+    range_start: usize,
+    range_end: usize,
+) -> Vec<Span<'static>> {
+    let mut result = Vec::new();
+    let mut pos = 0;
+    for span in spans {
+        let s = span.content.as_ref();
+        let len = s.chars().count();
+        let span_end = pos + len;
+        if span_end <= range_start || pos >= range_end {
+            pos = span_end;
+            continue;
+        }
+        let take_start = range_start.saturating_sub(pos);
+        let take_end = (range_end - pos).min(len);
+        if take_start < take_end {
+            let sliced: String = s
+                .chars()
+                .skip(take_start)
+                .take(take_end - take_start)
+                .collect();
+            result.push(Span::styled(sliced, span.style));
+        }
+        pos = span_end;
+    }
+    result
+}
@@ -1,12 +1,14 @@
-//! Text utilities: markdown parsing and line wrapping for the chat display.
+//! Text utilities: markdown parsing, code highlighting, and line wrapping for the chat display.
 
+mod highlight;
 mod markdown;
 mod segments;
 mod wrap;
 
+pub(crate) use highlight::{highlight_code_block, set_theme, slice_spans_by_range};
 pub(crate) use markdown::parse_markdown_inline;
-pub(crate) use segments::{MessageSegment, parse_message_segments};
-pub(crate) use wrap::wrap_message;
+pub(crate) use segments::{MessageSegment, PreviewKind, parse_message_segments};
+pub(crate) use wrap::{wrap_message, wrap_message_continued};
 
 /// Normalize Unicode symbols to ASCII equivalents in code blocks.
 /// LLMs sometimes output ≠, ≥, ≤ etc. instead of !=, >=, <= — this restores valid syntax.
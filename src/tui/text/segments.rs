@@ -1,30 +1,57 @@
-//! Message segmentation: split content into text and fenced code blocks.
+//! Message segmentation: split content into text, fenced code blocks, and previewable
+//! blocks (Mermaid diagrams, display math).
 
-/// Segment of a message: either plain text or a fenced code block.
+/// Block that the browser renders better than the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PreviewKind {
+    /// A ```mermaid block.
+    Mermaid,
+    /// `$$ ... $$` display math or a ```math block (LaTeX).
+    Math,
+}
+
+impl PreviewKind {
+    /// Fence language of the kind, case-insensitive.
+    fn from_lang(lang: &str) -> Option<Self> {
+        if lang.eq_ignore_ascii_case("mermaid") {
+            Some(PreviewKind::Mermaid)
+        } else if lang.eq_ignore_ascii_case("math") {
+            Some(PreviewKind::Math)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            PreviewKind::Mermaid => "mermaid",
+            PreviewKind::Math => "math",
+        }
+    }
+}
+
+/// Segment of a message: plain text, a fenced code block, or a previewable block.
 #[derive(Debug, Clone)]
 pub(crate) enum MessageSegment<'a> {
     Text(&'a str),
     CodeBlock { lang: &'a str, code: &'a str },
+    Preview { kind: PreviewKind, source: &'a str },
 }
 
-/// Parse message content into text and code block segments.
-/// Matches ```lang ... ``` or ``` ... ``` patterns.
+/// Parse message content into text, code block, and preview segments.
+/// Matches ```lang ... ``` or ``` ... ``` patterns; ```mermaid and ```math fences, and
+/// `$$ ... $$` opening at the start of a line, become previews.
 pub(crate) fn parse_message_segments(content: &str) -> Vec<MessageSegment<'_>> {
     let mut segments = Vec::new();
     let mut rest = content;
     loop {
         match rest.find("```") {
             None => {
-                if !rest.is_empty() {
-                    segments.push(MessageSegment::Text(rest));
-                }
+                push_text(&mut segments, rest);
                 break;
             }
             Some(idx) => {
-                if idx > 0 {
-                    let text = &rest[..idx];
-                    segments.push(MessageSegment::Text(text));
-                }
+                push_text(&mut segments, &rest[..idx]);
                 rest = &rest[idx + 3..];
                 let lang_end = rest.find('\n').unwrap_or(rest.len());
                 let lang = rest[..lang_end].trim();
@@ -35,7 +62,7 @@ pub(crate) fn parse_message_segments(content: &str) -> Vec<MessageSegment<'_>> {
                 };
                 // Closing ``` can be: "\n```" (on its own line) or "```" (no newline before)
                 let end = rest.find("\n```").or_else(|| rest.find("```"));
-                match end {
+                let code = match end {
                     Some(pos) => {
                         let (code, after) =
                             if rest.get(pos..).is_some_and(|s| s.starts_with("\n```")) {
@@ -43,16 +70,60 @@ pub(crate) fn parse_message_segments(content: &str) -> Vec<MessageSegment<'_>> {
                             } else {
                                 (&rest[..pos], &rest[pos + 3..])
                             };
-                        segments.push(MessageSegment::CodeBlock { lang, code });
                         rest = after;
+                        code
                     }
-                    None => {
-                        segments.push(MessageSegment::CodeBlock { lang, code: rest });
-                        break;
-                    }
+                    None => std::mem::take(&mut rest),
+                };
+                segments.push(match PreviewKind::from_lang(lang) {
+                    Some(kind) => MessageSegment::Preview { kind, source: code },
+                    None => MessageSegment::CodeBlock { lang, code },
+                });
+                if end.is_none() {
+                    break;
                 }
             }
         }
     }
     segments
 }
+
+/// Push `text`, split around its `$$ ... $$` blocks. An unclosed `$$` stays text.
+fn push_text<'a>(segments: &mut Vec<MessageSegment<'a>>, text: &'a str) {
+    let mut rest = text;
+    while let Some(open) = display_math_start(rest) {
+        let body = &rest[open + 2..];
+        let Some(close) = body.find("$$") else {
+            break;
+        };
+        let source = body[..close].trim();
+        if source.is_empty() {
+            break;
+        }
+        if open > 0 {
+            segments.push(MessageSegment::Text(&rest[..open]));
+        }
+        segments.push(MessageSegment::Preview {
+            kind: PreviewKind::Math,
+            source,
+        });
+        rest = &body[close + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(MessageSegment::Text(rest));
+    }
+}
+
+/// Byte offset of the first `$$` that starts a line (after indentation), so prices and
+/// shell `$$` inside sentences stay text.
+fn display_math_start(text: &str) -> Option<usize> {
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if line[indent..].starts_with("$$") {
+            return Some(line_start + indent);
+        }
+        line_start += line.len();
+    }
+    None
+}
@@ -1,4 +1,7 @@
-use super::{MessageSegment, parse_markdown_inline, parse_message_segments, wrap_message};
+use super::{
+    MessageSegment, PreviewKind, highlight_code_block, parse_markdown_inline,
+    parse_message_segments, wrap_message, wrap_message_continued,
+};
 
 #[test]
 fn parse_message_segments_empty() {
@@ -74,6 +77,35 @@ fn parse_message_segments_multiple_code_blocks() {
     );
 }
 
+#[test]
+fn parse_message_segments_mermaid_and_math_fences_are_previews() {
+    let segs = parse_message_segments("```Mermaid\ngraph TD\n  A-->B\n```\n```math\nx^2\n```");
+    assert!(matches!(
+        &segs[0],
+        MessageSegment::Preview { kind: PreviewKind::Mermaid, source } if *source == "graph TD\n  A-->B"
+    ));
+    assert!(matches!(
+        &segs[2],
+        MessageSegment::Preview { kind: PreviewKind::Math, source } if *source == "x^2"
+    ));
+}
+
+#[test]
+fn parse_message_segments_display_math() {
+    let segs =
+        parse_message_segments("Euler:\n$$\ne^{i\\pi} + 1 = 0\n$$\nNeat. It costs $$5 here.");
+    assert_eq!(segs.len(), 3);
+    assert!(matches!(&segs[0], MessageSegment::Text("Euler:\n")));
+    assert!(matches!(
+        &segs[1],
+        MessageSegment::Preview { kind: PreviewKind::Math, source } if *source == "e^{i\\pi} + 1 = 0"
+    ));
+    // `$$` inside a sentence, or without a closing `$$`, stays text.
+    assert!(matches!(&segs[2], MessageSegment::Text(t) if t.ends_with("costs $$5 here.")));
+    let segs = parse_message_segments("$$ x = 1");
+    assert!(matches!(&segs[..], [MessageSegment::Text("$$ x = 1")]));
+}
+
 #[test]
 fn parse_markdown_inline_plain() {
     let spans = parse_markdown_inline("hello");
@@ -138,6 +170,20 @@ fn wrap_message_preserves_newlines() {
     assert_eq!(lines, ["line1", "line2"]);
 }
 
+#[test]
+fn wrap_message_continued_flags_wrapped_chunks() {
+    let lines = wrap_message_continued("hello world test\nnext", 8);
+    assert_eq!(
+        lines,
+        vec![
+            ("hello".to_string(), false),
+            ("world".to_string(), true),
+            ("test".to_string(), true),
+            ("next".to_string(), false),
+        ]
+    );
+}
+
 #[test]
 fn wrap_message_wraps_long_line() {
     let lines = wrap_message("hello world test", 8);
@@ -149,3 +195,34 @@ fn wrap_message_empty_lines() {
     let lines = wrap_message("a\n\nb", 100);
     assert_eq!(lines, ["a", "", "b"]);
 }
+
+#[test]
+fn highlight_code_block_keeps_state_across_lines() {
+    let block = highlight_code_block("rust", "/* open\nstill comment */\nlet x = 1;", false);
+    assert_eq!(block.len(), 3);
+    // The second line is inside the block comment opened on the first one.
+    assert_eq!(block[1][0].style, block[0][0].style);
+    assert_ne!(block[2][0].style, block[0][0].style);
+}
+
+#[test]
+fn highlight_code_block_streaming_matches_full_highlight() {
+    let code = "fn main() {\n    let s = \"multi\n line\";\n}";
+    let mut streamed = None;
+    for end in (1..=code.len()).filter(|i| code.is_char_boundary(*i)) {
+        streamed = Some(highlight_code_block("rust", &code[..end], true));
+    }
+    let full = highlight_code_block("rust", code, false);
+    assert_eq!(streamed.as_deref(), Some(&*full));
+}
+
+#[test]
+fn highlight_code_block_unknown_lang_is_plain() {
+    let block = highlight_code_block("no-such-lang", "a\nb", false);
+    assert_eq!(block.len(), 2);
+    assert_eq!(block[0].len(), 1);
+    assert_eq!(block[0][0].content, "a");
+    // Languages outside the alias list resolve through syntect (here by name).
+    let diff = highlight_code_block("diff", "+added\n-removed", false);
+    assert_ne!(diff[0][0].style, diff[1][0].style);
+}
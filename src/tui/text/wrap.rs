@@ -25,3 +25,16 @@ pub(crate) fn wrap_message(msg: &str, width: usize) -> Vec<String> {
     }
     out
 }
+
+/// `wrap_message` chunks, each flagged when it continues the previous chunk's line (a wrap
+/// rather than a newline of `msg`).
+pub(crate) fn wrap_message_continued(msg: &str, width: usize) -> Vec<(String, bool)> {
+    msg.split('\n')
+        .flat_map(|line| {
+            wrap_message(line, width)
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| (chunk, i > 0))
+        })
+        .collect()
+}
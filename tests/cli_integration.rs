@@ -70,6 +70,28 @@ fn cli_prompt_without_api_key_exits_with_error() {
     );
 }
 
+#[test]
+fn cli_agents_generate_without_api_key_exits_with_error() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .args(["agents", "generate"])
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(
+        !output.status.success(),
+        "expected failure when OPENROUTER_API_KEY is not set"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("OPENROUTER_API_KEY"),
+        "expected API key error message, got: {}",
+        stderr
+    );
+    assert!(!tmp.path().join("AGENTS.md").exists());
+}
+
 #[test]
 fn cli_config_outputs_paths_and_status() {
     let tmp = tempfile::TempDir::new().expect("temp dir");
@@ -90,6 +112,32 @@ fn cli_config_outputs_paths_and_status() {
     assert!(stdout.contains("API key:"), "expected API key: in output");
 }
 
+#[test]
+fn cli_config_show_reports_an_unreadable_ca_bundle() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .args(["config", "show"])
+        .env("OPENROUTER_API_KEY", "test")
+        .env("MY_OPEN_CLAUDE_CA_BUNDLE", tmp.path().join("missing.pem"))
+        .env("HOME", tmp.path())
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Network:      error: Cannot read the CA bundle"),
+        "stdout: {}",
+        stdout
+    );
+}
+
 #[test]
 fn cli_completions_bash_produces_script() {
     let output = bin()
@@ -110,3 +158,326 @@ fn cli_completions_bash_produces_script() {
         APP_NAME
     );
 }
+
+#[test]
+fn cli_completions_complete_replay_conversation_ids() {
+    for shell in ["bash", "zsh", "fish"] {
+        let output = bin()
+            .args(["completions", shell])
+            .output()
+            .expect("binary not found - run cargo build first");
+        assert!(output.status.success(), "{} completions failed", shell);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains(&format!("{} history list", APP_NAME)),
+            "expected {} completions to list conversation IDs",
+            shell
+        );
+    }
+
+    let output = bin().args(["completions", "zsh"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(":_my-open-claude__conversation_ids'"),
+        "expected the replay ID argument to complete conversation IDs"
+    );
+}
+
+#[test]
+fn cli_stream_conflicts_with_no_stream() {
+    let output = bin()
+        .args(["-p", "hello", "--stream", "--no-stream"])
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--no-stream"),
+        "expected argument conflict error, got: {}",
+        stderr
+    );
+}
+
+#[test]
+fn cli_report_without_crash_reports_says_so() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .arg("report")
+        .arg("--no-open")
+        .env("HOME", tmp.path())
+        .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No crash report found"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn cli_stats_without_usage_says_so() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .args(["stats", "--days", "7"])
+        .env("HOME", tmp.path())
+        .env("XDG_DATA_HOME", tmp.path().join("data"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("No usage recorded in the last 7 days"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn cli_history_reactions_without_reactions_says_so() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let run = |args: &[&str]| {
+        bin()
+            .args(args)
+            .env("HOME", tmp.path())
+            .env("XDG_DATA_HOME", tmp.path().join("data"))
+            .current_dir(tmp.path())
+            .output()
+            .expect("binary not found - run cargo build first")
+    };
+
+    let output = run(&["history", "reactions", "--only", "flag"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("No replies with a reaction yet"),
+        "stderr: {}",
+        stderr
+    );
+
+    let output = run(&["history", "reactions", "--only", "meh"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("expected good, bad, or flag"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn cli_replay_unknown_conversation_exits_with_error() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .args(["replay", "does-not-exist"])
+        .env("HOME", tmp.path())
+        .env("XDG_DATA_HOME", tmp.path().join("data"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("conversation 'does-not-exist' not found"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn cli_batch_with_invalid_task_file_exits_with_error() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let tasks = tmp.path().join("tasks.yaml");
+    std::fs::write(&tasks, "tasks:\n  - prompt: ok\n  - mode: Ask\n").expect("write tasks");
+    let output = bin()
+        .arg("--batch")
+        .arg(&tasks)
+        .env("OPENROUTER_API_KEY", "test")
+        .env("HOME", tmp.path())
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .env("XDG_DATA_HOME", tmp.path().join("data"))
+        .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("task 2: empty prompt"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn cli_watch_with_invalid_glob_exits_with_error() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let output = bin()
+        .args(["watch", "-p", "run the tests", "--glob", "src/["])
+        .env("OPENROUTER_API_KEY", "test")
+        .env("HOME", tmp.path())
+        .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+        .env("XDG_DATA_HOME", tmp.path().join("data"))
+        .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+        .current_dir(tmp.path())
+        .output()
+        .expect("binary not found - run cargo build first");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("invalid glob 'src/['"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn cli_tools_list_and_run_a_tool() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    std::fs::write(tmp.path().join("notes.txt"), "remember the milk\n").expect("write file");
+    let tools = |args: &[&str]| {
+        bin()
+            .arg("tools")
+            .args(args)
+            .env("OPENROUTER_API_KEY", "test")
+            .env("HOME", tmp.path())
+            .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+            .env("XDG_DATA_HOME", tmp.path().join("data"))
+            .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+            .current_dir(tmp.path())
+            .output()
+            .expect("binary not found - run cargo build first")
+    };
+
+    let output = tools(&["list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("args: command*"), "stdout: {}", stdout);
+
+    let output = tools(&["run", "Read", "--args", r#"{"file_path": "notes.txt"}"#]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("remember the milk"));
+
+    let output = tools(&["run", "Bash", "--ask", "--args", r#"{"command": "ls"}"#]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Ask mode"), "stderr: {}", stderr);
+}
+
+#[test]
+fn cli_audit_show_lists_the_file_changes_of_tool_runs() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let run = |args: &[&str]| {
+        bin()
+            .args(args)
+            .env("OPENROUTER_API_KEY", "test")
+            .env("HOME", tmp.path())
+            .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+            .env("XDG_DATA_HOME", tmp.path().join("data"))
+            .env("XDG_CACHE_HOME", tmp.path().join("cache"))
+            .current_dir(tmp.path())
+            .output()
+            .expect("binary not found - run cargo build first")
+    };
+
+    let output = run(&["audit", "show"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let args = r#"{"file_path": "notes.txt", "content": "remember the milk\n"}"#;
+    let output = run(&["tools", "run", "Write", "--args", args]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = run(&["audit", "show", "--since", "1h"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\tWrite "), "stdout: {}", stdout);
+    assert!(stdout.contains("notes.txt (none -> "), "stdout: {}", stdout);
+
+    let output = run(&["audit", "show", "--json"]);
+    let line = String::from_utf8_lossy(&output.stdout);
+    let entry: serde_json::Value = serde_json::from_str(line.trim()).expect("JSON line");
+    assert_eq!(entry["event"], "file_change");
+    assert_eq!(entry["after"].as_str().map(str::len), Some(64));
+
+    let output = run(&["audit", "show", "--since", "yesterday"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid --since"), "stderr: {}", stderr);
+}
+
+#[test]
+fn cli_config_show_uses_the_selected_profile() {
+    let tmp = tempfile::TempDir::new().expect("temp dir");
+    let profiles = tmp.path().join("config").join(APP_NAME).join("profiles");
+    std::fs::create_dir_all(&profiles).expect("create profiles dir");
+    std::fs::write(
+        profiles.join("local.json"),
+        r#"{"provider": "ollama", "model": "qwen2.5-coder:7b"}"#,
+    )
+    .expect("write profile");
+    let run = |args: &[&str]| {
+        bin()
+            .args(args)
+            .env_remove("MY_OPEN_CLAUDE_PROFILE")
+            .env("HOME", tmp.path())
+            .env("XDG_CONFIG_HOME", tmp.path().join("config"))
+            .current_dir(tmp.path())
+            .output()
+            .expect("binary not found - run cargo build first")
+    };
+
+    let output = run(&["--profile", "local", "config", "show"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Profile:      local"), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("qwen2.5-coder:7b (from profile 'local')"),
+        "stdout: {}",
+        stdout
+    );
+
+    let output = run(&["--profile", "missing", "config", "show"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Profile 'missing' not found"),
+        "stderr: {}",
+        stderr
+    );
+}